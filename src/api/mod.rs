@@ -0,0 +1,4 @@
+pub mod server;
+pub mod state;
+
+pub use state::BotState;
@@ -0,0 +1,160 @@
+use crate::{
+    api::state::{BotState, HealthStatus},
+    config::BotScoringConfig,
+    dex::HealthStatus as DexHealthStatus,
+    monitor::whales::WhaleMonitor,
+};
+use anyhow::{Context, Result};
+use axum::{
+    extract::{Path, State},
+    http::{header, StatusCode},
+    response::IntoResponse,
+    routing::{get, post},
+    Json, Router,
+};
+use solana_sdk::pubkey::Pubkey;
+use std::{net::SocketAddr, str::FromStr, sync::Arc};
+use tracing::info;
+
+pub fn router(state: Arc<BotState>) -> Router {
+    Router::new()
+        .route("/health", get(health))
+        .route("/health/dex/:name", get(dex_health))
+        .route("/metrics", get(metrics))
+        .route("/config/score-weights", get(score_weights).put(update_score_weights))
+        .route("/whale/watch/:address", post(watch_whale).delete(unwatch_whale))
+        .with_state(state)
+}
+
+/// Serve the health and metrics endpoints on all interfaces until the task is aborted
+pub async fn serve(port: u16, state: Arc<BotState>) -> Result<()> {
+    let addr = SocketAddr::from(([0, 0, 0, 0], port));
+    let server = axum::Server::try_bind(&addr)
+        .with_context(|| format!("Failed to bind API server to {}", addr))?;
+
+    info!("API server listening on {}", addr);
+    server
+        .serve(router(state).into_make_service())
+        .await
+        .context("API server failed")
+}
+
+async fn health(State(state): State<Arc<BotState>>) -> Json<HealthStatus> {
+    Json(state.health())
+}
+
+async fn dex_health(
+    State(state): State<Arc<BotState>>,
+    Path(name): Path<String>,
+) -> Result<Json<DexHealthStatus>, (StatusCode, String)> {
+    let client = state
+        .dex_client(&name)
+        .ok_or_else(|| (StatusCode::NOT_FOUND, format!("Unknown DEX: {}", name)))?;
+    let status = client
+        .health_check()
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    Ok(Json(status))
+}
+
+/// Whale monitor and parsed address for the `/whale/watch` routes
+fn whale_target(state: &BotState, address: &str) -> Result<(Arc<WhaleMonitor>, Pubkey), (StatusCode, String)> {
+    let address = Pubkey::from_str(address)
+        .map_err(|_| (StatusCode::BAD_REQUEST, format!("Invalid address: {}", address)))?;
+    let monitor = state
+        .whale_monitor()
+        .cloned()
+        .ok_or_else(|| (StatusCode::SERVICE_UNAVAILABLE, "Whale monitor not running".to_string()))?;
+    Ok((monitor, address))
+}
+
+async fn watch_whale(
+    State(state): State<Arc<BotState>>,
+    Path(address): Path<String>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let (monitor, address) = whale_target(&state, &address)?;
+    monitor
+        .add_wallet_address(address)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn unwatch_whale(
+    State(state): State<Arc<BotState>>,
+    Path(address): Path<String>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let (monitor, address) = whale_target(&state, &address)?;
+    monitor
+        .remove_wallet_address(address)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+fn score_weights_lock(state: &BotState) -> Result<Arc<std::sync::RwLock<BotScoringConfig>>, (StatusCode, String)> {
+    state
+        .score_weights()
+        .ok_or_else(|| (StatusCode::SERVICE_UNAVAILABLE, "Screener not running".to_string()))
+}
+
+async fn score_weights(
+    State(state): State<Arc<BotState>>,
+) -> Result<Json<BotScoringConfig>, (StatusCode, String)> {
+    let weights = score_weights_lock(&state)?;
+    let weights = weights.read().unwrap().clone();
+    Ok(Json(weights))
+}
+
+/// Replace the score weights the screener ranks opportunities with, once they validate
+async fn update_score_weights(
+    State(state): State<Arc<BotState>>,
+    Json(weights): Json<BotScoringConfig>,
+) -> Result<Json<BotScoringConfig>, (StatusCode, String)> {
+    weights
+        .validate()
+        .map_err(|e| (StatusCode::UNPROCESSABLE_ENTITY, e.to_string()))?;
+    let lock = score_weights_lock(&state)?;
+    *lock.write().unwrap() = weights.clone();
+    info!("Score weights updated: {:?}", weights);
+    Ok(Json(weights))
+}
+
+async fn metrics(State(state): State<Arc<BotState>>) -> impl IntoResponse {
+    (
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        state.render_metrics(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::RwLock;
+
+    #[tokio::test]
+    async fn test_score_weights_handlers() {
+        let state = Arc::new(BotState::new());
+        assert_eq!(score_weights(State(state.clone())).await.unwrap_err().0, StatusCode::SERVICE_UNAVAILABLE);
+
+        let lock = Arc::new(RwLock::new(BotScoringConfig::default()));
+        state.set_score_weights(lock.clone());
+        let Json(current) = score_weights(State(state.clone())).await.unwrap();
+        assert_eq!(current, BotScoringConfig::default());
+
+        let invalid = BotScoringConfig { profit_weight: 0.9, ..BotScoringConfig::default() };
+        let rejected = update_score_weights(State(state.clone()), Json(invalid)).await.unwrap_err();
+        assert_eq!(rejected.0, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(*lock.read().unwrap(), BotScoringConfig::default());
+
+        let profit_first = BotScoringConfig {
+            profit_weight: 0.6,
+            confidence_weight: 0.3,
+            risk_weight: 0.1,
+            ..BotScoringConfig::default()
+        };
+        let Json(applied) = update_score_weights(State(state.clone()), Json(profit_first.clone())).await.unwrap();
+        assert_eq!(applied, profit_first);
+        assert_eq!(*lock.read().unwrap(), profit_first);
+    }
+}
@@ -0,0 +1,243 @@
+use crate::{
+    config::BotScoringConfig,
+    dex::DexClient,
+    metrics::{Histogram, MetricsCollector},
+    monitor::whales::WhaleMonitor,
+    tests::HealthReport,
+};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::{
+    fmt::Write,
+    sync::{
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+        Arc, Mutex, RwLock,
+    },
+    time::Instant,
+};
+
+/// Upper bounds, in milliseconds, of the scan phase histogram buckets
+const SCAN_PHASE_BUCKETS_MS: [f64; 10] = [1.0, 5.0, 10.0, 25.0, 50.0, 100.0, 250.0, 500.0, 1_000.0, 5_000.0];
+
+/// Counters shared between the engine and the HTTP API
+pub struct BotState {
+    started_at: Instant,
+    /// Scan, trade and RPC counters rendered alongside this state's own
+    metrics: &'static MetricsCollector,
+    slippage_revalidation_failed_total: AtomicU64,
+    sandwich_risks_detected_total: AtomicU64,
+    dexes_connected: AtomicUsize,
+    /// Stored as `f64` bits
+    cache_hit_ratio: AtomicU64,
+    last_scan: RwLock<Option<DateTime<Utc>>>,
+    /// Scan phase durations, in the order phases were first reported
+    scan_phases: Mutex<Vec<(&'static str, Histogram)>>,
+    last_scan_opportunities: AtomicUsize,
+    dex_clients: Vec<Arc<dyn DexClient>>,
+    whale_monitor: Option<Arc<WhaleMonitor>>,
+    /// Latest DEX health check, run at startup
+    dex_health: RwLock<Option<HealthReport>>,
+    /// The screener's live score weights, read and replaced by `/config/score-weights`
+    score_weights: RwLock<Option<Arc<RwLock<BotScoringConfig>>>>,
+}
+
+/// Body of `GET /health`
+#[derive(Debug, Clone, Serialize)]
+pub struct HealthStatus {
+    pub status: &'static str,
+    pub uptime_seconds: u64,
+    pub dexes_connected: usize,
+    pub last_scan: Option<DateTime<Utc>>,
+    pub dex_health: Option<HealthReport>,
+}
+
+impl BotState {
+    pub fn new() -> Self {
+        Self {
+            started_at: Instant::now(),
+            metrics: MetricsCollector::global(),
+            slippage_revalidation_failed_total: AtomicU64::new(0),
+            sandwich_risks_detected_total: AtomicU64::new(0),
+            dexes_connected: AtomicUsize::new(0),
+            cache_hit_ratio: AtomicU64::new(0f64.to_bits()),
+            last_scan: RwLock::new(None),
+            scan_phases: Mutex::new(Vec::new()),
+            last_scan_opportunities: AtomicUsize::new(0),
+            dex_clients: Vec::new(),
+            whale_monitor: None,
+            dex_health: RwLock::new(None),
+            score_weights: RwLock::new(None),
+        }
+    }
+
+    /// Render this collector instead of the global one
+    pub fn with_metrics(mut self, metrics: &'static MetricsCollector) -> Self {
+        self.metrics = metrics;
+        self
+    }
+
+    /// DEX clients probed by `GET /health/dex/{name}`
+    pub fn with_dex_clients(mut self, dex_clients: Vec<Arc<dyn DexClient>>) -> Self {
+        self.dex_clients = dex_clients;
+        self
+    }
+
+    /// Whale monitor whose address list `POST`/`DELETE /whale/watch/{address}` edits
+    pub fn with_whale_monitor(mut self, whale_monitor: Arc<WhaleMonitor>) -> Self {
+        self.whale_monitor = Some(whale_monitor);
+        self
+    }
+
+    pub fn whale_monitor(&self) -> Option<&Arc<WhaleMonitor>> {
+        self.whale_monitor.as_ref()
+    }
+
+    /// Client whose `get_dex_name` matches `name`, ignoring case
+    pub fn dex_client(&self, name: &str) -> Option<Arc<dyn DexClient>> {
+        self.dex_clients
+            .iter()
+            .find(|client| client.get_dex_name().eq_ignore_ascii_case(name))
+            .cloned()
+    }
+
+    pub fn record_scan(&self, dexes_connected: usize, cache_hit_ratio: f64) {
+        self.dexes_connected.store(dexes_connected, Ordering::Relaxed);
+        self.cache_hit_ratio.store(cache_hit_ratio.to_bits(), Ordering::Relaxed);
+        *self.last_scan.write().unwrap() = Some(Utc::now());
+    }
+
+    /// Add one scan's per-phase durations, in milliseconds, to the phase histograms
+    pub fn record_scan_phases(&self, phases: &[(&'static str, f64)], opportunities_found: usize) {
+        let mut histograms = self.scan_phases.lock().unwrap();
+        for &(phase, ms) in phases {
+            match histograms.iter_mut().find(|(name, _)| *name == phase) {
+                Some((_, histogram)) => histogram.observe(ms),
+                None => {
+                    let mut histogram = Histogram::new(&SCAN_PHASE_BUCKETS_MS);
+                    histogram.observe(ms);
+                    histograms.push((phase, histogram));
+                }
+            }
+        }
+        self.last_scan_opportunities.store(opportunities_found, Ordering::Relaxed);
+    }
+
+    /// Serve and update `weights` through `/config/score-weights`
+    pub fn set_score_weights(&self, weights: Arc<RwLock<BotScoringConfig>>) {
+        *self.score_weights.write().unwrap() = Some(weights);
+    }
+
+    pub fn score_weights(&self) -> Option<Arc<RwLock<BotScoringConfig>>> {
+        self.score_weights.read().unwrap().clone()
+    }
+
+    pub fn record_dex_health(&self, report: HealthReport) {
+        *self.dex_health.write().unwrap() = Some(report);
+    }
+
+    pub fn record_slippage_revalidation_failed(&self) {
+        self.slippage_revalidation_failed_total.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_sandwich_risk_detected(&self) {
+        self.sandwich_risks_detected_total.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn health(&self) -> HealthStatus {
+        HealthStatus {
+            status: "ok",
+            uptime_seconds: self.started_at.elapsed().as_secs(),
+            dexes_connected: self.dexes_connected.load(Ordering::Relaxed),
+            last_scan: *self.last_scan.read().unwrap(),
+            dex_health: self.dex_health.read().unwrap().clone(),
+        }
+    }
+
+    /// Prometheus text exposition format
+    pub fn render_metrics(&self) -> String {
+        let metrics: [(&str, &str, &str, String); 4] = [
+            ("slippage_revalidation_failed_total", "counter", "Trades abandoned because fresh reserves moved past the slippage limit", self.slippage_revalidation_failed_total.load(Ordering::Relaxed).to_string()),
+            ("sandwich_risks_detected_total", "counter", "Trades that found a likely sandwich setup in the mempool before sending", self.sandwich_risks_detected_total.load(Ordering::Relaxed).to_string()),
+            ("cache_hit_ratio", "gauge", "Fraction of pool lookups served from cache", f64::from_bits(self.cache_hit_ratio.load(Ordering::Relaxed)).to_string()),
+            ("arb_scan_opportunities_found", "gauge", "Opportunities that passed filtering in the last scan", self.last_scan_opportunities.load(Ordering::Relaxed).to_string()),
+        ];
+
+        let mut output = self.metrics.render();
+        for (name, kind, help, value) in metrics {
+            let _ = writeln!(output, "# HELP {} {}", name, help);
+            let _ = writeln!(output, "# TYPE {} {}", name, kind);
+            let _ = writeln!(output, "{} {}", name, value);
+        }
+
+        let histograms = self.scan_phases.lock().unwrap();
+        if !histograms.is_empty() {
+            let name = "arb_scan_phase_duration_ms";
+            let _ = writeln!(output, "# HELP {} Time spent in each phase of an opportunity scan", name);
+            let _ = writeln!(output, "# TYPE {} histogram", name);
+            for (phase, histogram) in histograms.iter() {
+                histogram.render(&mut output, name, &format!("phase=\"{}\",", phase));
+            }
+        }
+        output
+    }
+}
+
+impl Default for BotState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_metrics_and_health() {
+        let metrics: &'static MetricsCollector = Box::leak(Box::new(MetricsCollector::new()));
+        let state = BotState::new().with_metrics(metrics);
+        assert!(state.health().last_scan.is_none());
+
+        state.record_scan(3, 0.75);
+        state.record_scan(4, 0.5);
+        metrics.increment_scans();
+        metrics.increment_trades_executed(10_000);
+        state.record_slippage_revalidation_failed();
+        state.record_sandwich_risk_detected();
+
+        let health = state.health();
+        assert_eq!(health.dexes_connected, 4);
+        assert!(health.last_scan.is_some());
+        assert!(health.dex_health.is_none());
+        state.record_dex_health(HealthReport::new(Vec::new(), 1));
+        assert_eq!(state.health().dex_health.unwrap().recommended_action, "Critical: restart recommended");
+
+        let metrics = state.render_metrics();
+        assert!(metrics.contains("# TYPE arb_scans_total counter\narb_scans_total 1\n"));
+        assert!(metrics.contains("\narb_trades_executed_total 1\n"));
+        assert!(metrics.contains("\narb_profit_lamports_total 10000\n"));
+        assert!(metrics.contains("\nslippage_revalidation_failed_total 1\n"));
+        assert!(metrics.contains("\nsandwich_risks_detected_total 1\n"));
+        assert!(metrics.contains("# TYPE rpc_calls_total counter\n"));
+        assert!(metrics.contains("# TYPE cache_hit_ratio gauge\ncache_hit_ratio 0.5\n"));
+        assert!(!metrics.contains("arb_scan_phase_duration_ms"));
+    }
+
+    #[test]
+    fn test_scan_phase_histograms() {
+        let state = BotState::new();
+        state.record_scan_phases(&[("direct_scan", 3.0), ("total", 40.0)], 2);
+        state.record_scan_phases(&[("direct_scan", 20.0), ("total", 700.0)], 1);
+
+        let metrics = state.render_metrics();
+        assert!(metrics.contains("# TYPE arb_scan_phase_duration_ms histogram\n"));
+        assert!(metrics.contains("arb_scan_phase_duration_ms_bucket{phase=\"direct_scan\",le=\"1\"} 0\n"));
+        assert!(metrics.contains("arb_scan_phase_duration_ms_bucket{phase=\"direct_scan\",le=\"5\"} 1\n"));
+        assert!(metrics.contains("arb_scan_phase_duration_ms_bucket{phase=\"direct_scan\",le=\"25\"} 2\n"));
+        assert!(metrics.contains("arb_scan_phase_duration_ms_bucket{phase=\"total\",le=\"500\"} 1\n"));
+        assert!(metrics.contains("arb_scan_phase_duration_ms_bucket{phase=\"total\",le=\"+Inf\"} 2\n"));
+        assert!(metrics.contains("arb_scan_phase_duration_ms_sum{phase=\"direct_scan\"} 23\n"));
+        assert!(metrics.contains("arb_scan_phase_duration_ms_count{phase=\"total\"} 2\n"));
+        assert!(metrics.contains("\narb_scan_opportunities_found 1\n"));
+    }
+}
@@ -1,9 +1,12 @@
+use crate::{storage::trade_history::DEFAULT_DB_PATH, types::ArbitrageType};
 use anyhow::{Context, Result};
 use dotenvy::dotenv;
 use serde::{Deserialize, Serialize};
-use solana_sdk::signature::Keypair;
-use std::{env, fs};
-use tracing::{error, warn};
+use notify::{EventKind, RecursiveMode, Watcher};
+use solana_sdk::{signature::Keypair, signer::Signer};
+use std::{env, fs, path::{Path, PathBuf}};
+use tokio::sync::{mpsc, watch};
+use tracing::{debug, error, info, warn};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
@@ -12,8 +15,25 @@ pub struct Config {
     pub dexs: DexConfig,
     pub monitoring: MonitoringConfig,
     pub risk_management: RiskManagementConfig,
+    #[serde(default)]
+    pub scoring: BotScoringConfig,
+    /// Trade history database file; defaults to `trade_history.db` in the working directory
+    #[serde(default)]
+    pub storage_path: Option<String>,
+    /// Port for the `/health` and `/metrics` HTTP endpoints
+    #[serde(default = "default_api_port")]
+    pub api_port: u16,
 }
 
+fn default_api_port() -> u16 {
+    8080
+}
+
+pub const CONFIG_PATH: &str = "config.toml";
+
+/// `Config::from_env` is only used when all of these are set
+pub const REQUIRED_ENV_VARS: [&str; 2] = ["ARB_BOT_RPC_URL", "ARB_BOT_ENABLED_DEXES"];
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BotConfig {
     pub profit_threshold_percent: f64,
@@ -25,9 +45,249 @@ pub struct BotConfig {
     pub simulation_mode: bool,
     #[serde(skip_serializing)] // Never serialize private key
     pub private_key: Option<String>,
+    /// Further wallets trades are spread across; `private_key` is the first when set
+    #[serde(default, skip_serializing)]
+    pub private_keys: Vec<String>,
+    /// Path to a Solana CLI style keypair file, used when `private_key` is not set
+    #[serde(default)]
+    pub keypair_file_path: Option<PathBuf>,
+    /// Consecutive fetch failures before a DEX client is re-initialized
+    #[serde(default = "default_dex_reinit_threshold")]
+    pub dex_reinit_threshold: u32,
+    /// Slippage tolerance per route type; multi-hop routes compound slippage at each step
+    #[serde(default = "default_direct_arb_max_slippage_percent")]
+    pub direct_arb_max_slippage_percent: f64,
+    #[serde(default = "default_triangular_arb_max_slippage_percent")]
+    pub triangular_arb_max_slippage_percent: f64,
+    #[serde(default = "default_cross_dex_max_slippage_percent")]
+    pub cross_dex_max_slippage_percent: f64,
+    /// Swap tokens stranded by a half-executed route back to the starting token
+    #[serde(default = "default_auto_recover_partial_execution")]
+    pub auto_recover_partial_execution: bool,
+    /// Submit trades as Jito bundles instead of through the public RPC
+    #[serde(default)]
+    pub use_jito: bool,
+    /// Tip paid to the Jito validator with each bundle
+    #[serde(default = "default_jito_tip_lamports")]
+    pub jito_tip_lamports: u64,
+    /// Fund routes with a MarginFi flash loan instead of the wallet balance
+    #[serde(default)]
+    pub enable_flash_loans: bool,
+    /// MarginFi group whose banks lend for flash loans
+    #[serde(default = "default_marginfi_group")]
+    pub marginfi_group: String,
+    /// Upper bound on the compute unit price, whatever the network's recent fees are
+    #[serde(default = "default_max_priority_fee_microlamports")]
+    pub max_priority_fee_microlamports: u64,
+    /// Percentile of recent slots' priority fees to pay, 1 to 100
+    #[serde(default = "default_priority_fee_percentile")]
+    pub priority_fee_percentile: u8,
+    /// Pool lists kept in the cache, one per DEX; least recently used lists are evicted first
+    #[serde(default = "default_cache_pool_capacity")]
+    pub cache_pool_capacity: usize,
+    /// Pool reserve entries kept in the cache
+    #[serde(default = "default_cache_reserve_capacity")]
+    pub cache_reserve_capacity: usize,
+    /// Pool lists are saved here after each refresh and loaded at startup; unset disables it
+    #[serde(default)]
+    pub cache_file_path: Option<String>,
+    /// Pools whose tokens are both one of these mints are scanned as stable pairs
+    #[serde(default = "default_stable_mints")]
+    pub stable_mints: Vec<String>,
+    /// Profit threshold for stable pair routes, which trade tiny but consistent deviations
+    #[serde(default = "default_stable_profit_threshold_percent")]
+    pub stable_profit_threshold_percent: f64,
+    /// Send and simulate trades as v0 transactions that reference `address_lookup_tables`
+    #[serde(default)]
+    pub use_versioned_transactions: bool,
+    /// Address lookup tables holding DEX programs and common token accounts, loaded at startup
+    #[serde(default)]
+    pub address_lookup_tables: Vec<String>,
+    /// Search the pool graph for cycles of four or more swaps
+    #[serde(default)]
+    pub enable_multi_hop: bool,
+    /// Longest cycle the multi-hop scan follows; clamped to 4..=6
+    #[serde(default = "default_max_multi_hop")]
+    pub max_multi_hop: u8,
+    /// Open missing associated token accounts for a route's tokens before trading it
+    #[serde(default)]
+    pub auto_create_token_accounts: bool,
+    /// Pools whose reserves are older than this many seconds are left out of routes
+    #[serde(default = "default_max_pool_age_secs")]
+    pub max_pool_age_secs: u64,
+    /// Send low-risk, high-confidence trades without RPC preflight once our own simulation passed
+    #[serde(default)]
+    pub allow_skip_preflight: bool,
+    /// Direct routes are reused for this many milliseconds unless one of their pools changes
+    #[serde(default = "default_route_cache_ttl_ms")]
+    pub route_cache_ttl_ms: u64,
+    /// Only this many pools, the most liquid first, are scanned each cycle
+    #[serde(default = "default_max_pools_per_scan")]
+    pub max_pools_per_scan: usize,
+    /// Opportunities claiming more profit than this are assumed to come from bad pool data
+    #[serde(default = "default_max_plausible_profit_percent")]
+    pub max_plausible_profit_percent: f64,
+    /// Routes above 2% profit must only use pools with at least this much liquidity
+    #[serde(default = "default_min_liquidity_for_high_profit_usd")]
+    pub min_liquidity_for_high_profit_usd: f64,
+    /// Pump.fun curves filled beyond this share of the graduation threshold are skipped
+    #[serde(default = "default_pumpfun_max_graduation_progress")]
+    pub pumpfun_max_graduation_progress: f64,
+    /// Routes whose steps add up to more price impact than this, in basis points, are not executed
+    #[serde(default = "default_max_price_impact_bps")]
+    pub max_price_impact_bps: u32,
+    /// The console shows the wallet balance in red below this many SOL
+    #[serde(default = "default_min_wallet_sol")]
+    pub min_wallet_sol: f64,
+    /// A route offered once is not offered again for this many seconds
+    #[serde(default = "default_opportunity_dedup_cooldown_secs")]
+    pub opportunity_dedup_cooldown_secs: u64,
+    /// Pools whose raw `reserve_a * reserve_b` is below this are too thin to trust their
+    /// reported liquidity and are treated as wash trading
+    #[serde(default = "default_min_product_threshold")]
+    pub min_product_threshold: f64,
+    /// Pyth prices published more than this many slots ago are ignored
+    #[serde(default = "default_max_oracle_age_slots")]
+    pub max_oracle_age_slots: u64,
+    /// When non-empty, only pools with at least one of these mints are traded
+    #[serde(default)]
+    pub token_whitelist: Vec<String>,
+    /// Pools with either of these mints are never traded
+    #[serde(default)]
+    pub token_blacklist: Vec<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+fn default_dex_reinit_threshold() -> u32 {
+    3
+}
+
+fn default_direct_arb_max_slippage_percent() -> f64 {
+    2.0
+}
+
+fn default_triangular_arb_max_slippage_percent() -> f64 {
+    1.0
+}
+
+fn default_cross_dex_max_slippage_percent() -> f64 {
+    1.5
+}
+
+fn default_auto_recover_partial_execution() -> bool {
+    true
+}
+
+fn default_jito_tip_lamports() -> u64 {
+    10_000
+}
+
+fn default_marginfi_group() -> String {
+    "4qp6Fx6tnZkY5Wropq9wUYgtFxXKwE6viZxFHg3rdAG8".to_string()
+}
+
+fn default_max_priority_fee_microlamports() -> u64 {
+    100_000
+}
+
+fn default_priority_fee_percentile() -> u8 {
+    75
+}
+
+fn default_cache_pool_capacity() -> usize {
+    32
+}
+
+fn default_cache_reserve_capacity() -> usize {
+    10_000
+}
+
+fn default_stable_mints() -> Vec<String> {
+    [
+        "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v", // USDC
+        "Es9vMFrzaCERmJfrF4H2FYD4KCoNkY11McCe8BenwNYB", // USDT
+        "USDH1SM1ojwWUga67PGrgFWUHibbjqMvuMaDkRJTgkX",  // USDH
+        "7kbnvuGBxxj8AG9qp8Scn56muWGaRaFqxg1FsRp3PaFT", // UXD
+        "2b1kV6DkPAnxd5ixfnxCpjxmKwqjjaYmCZfHsFu24GXo", // PYUSD
+    ]
+    .iter()
+    .map(|mint| mint.to_string())
+    .collect()
+}
+
+fn default_stable_profit_threshold_percent() -> f64 {
+    0.02
+}
+
+fn default_max_multi_hop() -> u8 {
+    4
+}
+
+fn default_max_pool_age_secs() -> u64 {
+    60
+}
+
+fn default_route_cache_ttl_ms() -> u64 {
+    500
+}
+
+fn default_max_pools_per_scan() -> usize {
+    500
+}
+
+fn default_max_plausible_profit_percent() -> f64 {
+    10.0
+}
+
+fn default_min_liquidity_for_high_profit_usd() -> f64 {
+    50_000.0
+}
+
+fn default_pumpfun_max_graduation_progress() -> f64 {
+    0.90
+}
+
+fn default_max_price_impact_bps() -> u32 {
+    300
+}
+
+fn default_min_wallet_sol() -> f64 {
+    0.1
+}
+
+fn default_opportunity_dedup_cooldown_secs() -> u64 {
+    30
+}
+
+fn default_min_product_threshold() -> f64 {
+    // One whole token of six decimals on each side
+    1e12
+}
+
+fn default_max_oracle_age_slots() -> u64 {
+    // About ten seconds
+    25
+}
+
+impl BotConfig {
+    /// Maximum slippage tolerated for the given route type
+    pub fn max_slippage_for(&self, route_type: &ArbitrageType) -> f64 {
+        match route_type {
+            ArbitrageType::Direct | ArbitrageType::Stable => self.direct_arb_max_slippage_percent,
+            ArbitrageType::Triangular | ArbitrageType::MultiHop(_) => self.triangular_arb_max_slippage_percent,
+            ArbitrageType::CrossDex => self.cross_dex_max_slippage_percent,
+        }
+    }
+
+    /// Minimum expected profit for the given route type
+    pub fn profit_threshold_for(&self, route_type: &ArbitrageType) -> f64 {
+        match route_type {
+            ArbitrageType::Stable => self.stable_profit_threshold_percent,
+            _ => self.profit_threshold_percent,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct RpcConfig {
     pub solana_rpc_url: String,
     pub solana_ws_url: String,
@@ -35,11 +295,35 @@ pub struct RpcConfig {
     pub quicknode_ws_url: Option<String>,
     pub max_requests_per_second: u32,
     pub burst_size: u32,
+    /// Tried in order when the primary endpoint fails
+    #[serde(default)]
+    pub fallback_rpc_urls: Vec<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DexConfig {
     pub enabled: Vec<String>,
+    /// Pump.fun pools kept after sorting by market cap
+    #[serde(default = "default_pumpfun_max_pools")]
+    pub pumpfun_max_pools: usize,
+    /// Pump.fun pools below this market cap (USD) are ignored
+    #[serde(default = "default_pumpfun_min_market_cap")]
+    pub pumpfun_min_market_cap: f64,
+    /// Startup is aborted when fewer DEXes than this pass their connection test
+    #[serde(default = "default_min_required_dexes")]
+    pub min_required_dexes: usize,
+}
+
+fn default_pumpfun_max_pools() -> usize {
+    20
+}
+
+fn default_pumpfun_min_market_cap() -> f64 {
+    10000.0
+}
+
+fn default_min_required_dexes() -> usize {
+    1
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -48,6 +332,19 @@ pub struct MonitoringConfig {
     pub mempool_enabled: bool,
     pub whale_tracking_enabled: bool,
     pub whale_wallet_addresses: Vec<String>,
+    /// Upper bound on signatures replayed after a WebSocket reconnect
+    #[serde(default = "default_max_replay_signatures")]
+    pub max_replay_signatures: usize,
+    /// Discord webhook that receives executed trades and critical errors
+    #[serde(default)]
+    pub discord_webhook_url: Option<String>,
+    /// Wallets the mempool monitor follows with `accountSubscribe`, for copy-trading signals
+    #[serde(default)]
+    pub watched_wallets: Vec<String>,
+}
+
+fn default_max_replay_signatures() -> usize {
+    100
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -57,6 +354,42 @@ pub struct RiskManagementConfig {
     pub position_sizing_enabled: bool,
 }
 
+/// Weights used by `ArbitrageOpportunity::score` to rank opportunities
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BotScoringConfig {
+    pub profit_weight: f64,
+    pub confidence_weight: f64,
+    pub risk_weight: f64,
+    /// Added on top of the weighted sum when a route was also seen in the previous scan
+    pub persistence_bonus: f64,
+}
+
+impl Default for BotScoringConfig {
+    fn default() -> Self {
+        Self {
+            profit_weight: 0.5,
+            confidence_weight: 0.3,
+            risk_weight: 0.2,
+            persistence_bonus: 0.05,
+        }
+    }
+}
+
+impl BotScoringConfig {
+    /// Weights must be non-negative and sum to 1.0 (±0.01)
+    pub fn validate(&self) -> Result<()> {
+        let values = [self.profit_weight, self.confidence_weight, self.risk_weight, self.persistence_bonus];
+        if values.iter().any(|value| !value.is_finite() || *value < 0.0) {
+            anyhow::bail!("Score weights must be non-negative");
+        }
+        let sum = self.profit_weight + self.confidence_weight + self.risk_weight;
+        if (sum - 1.0).abs() > 0.01 {
+            anyhow::bail!("Score weights must sum to 1.0, got {:.3}", sum);
+        }
+        Ok(())
+    }
+}
+
 impl Default for Config {
     fn default() -> Self {
         Config {
@@ -69,6 +402,43 @@ impl Default for Config {
                 execute_trades: false,
                 simulation_mode: true,
                 private_key: None,
+                private_keys: vec![],
+                keypair_file_path: None,
+                dex_reinit_threshold: default_dex_reinit_threshold(),
+                direct_arb_max_slippage_percent: default_direct_arb_max_slippage_percent(),
+                triangular_arb_max_slippage_percent: default_triangular_arb_max_slippage_percent(),
+                cross_dex_max_slippage_percent: default_cross_dex_max_slippage_percent(),
+                auto_recover_partial_execution: default_auto_recover_partial_execution(),
+                use_jito: false,
+                jito_tip_lamports: default_jito_tip_lamports(),
+                enable_flash_loans: false,
+                marginfi_group: default_marginfi_group(),
+                max_priority_fee_microlamports: default_max_priority_fee_microlamports(),
+                priority_fee_percentile: default_priority_fee_percentile(),
+                cache_pool_capacity: default_cache_pool_capacity(),
+                cache_reserve_capacity: default_cache_reserve_capacity(),
+                cache_file_path: None,
+                stable_mints: default_stable_mints(),
+                stable_profit_threshold_percent: default_stable_profit_threshold_percent(),
+                use_versioned_transactions: false,
+                address_lookup_tables: Vec::new(),
+                enable_multi_hop: false,
+                max_multi_hop: default_max_multi_hop(),
+                auto_create_token_accounts: false,
+                max_pool_age_secs: default_max_pool_age_secs(),
+                allow_skip_preflight: false,
+                route_cache_ttl_ms: default_route_cache_ttl_ms(),
+                max_pools_per_scan: default_max_pools_per_scan(),
+                max_plausible_profit_percent: default_max_plausible_profit_percent(),
+                min_liquidity_for_high_profit_usd: default_min_liquidity_for_high_profit_usd(),
+                pumpfun_max_graduation_progress: default_pumpfun_max_graduation_progress(),
+                max_price_impact_bps: default_max_price_impact_bps(),
+                min_wallet_sol: default_min_wallet_sol(),
+                opportunity_dedup_cooldown_secs: default_opportunity_dedup_cooldown_secs(),
+                min_product_threshold: default_min_product_threshold(),
+                max_oracle_age_slots: default_max_oracle_age_slots(),
+                token_whitelist: Vec::new(),
+                token_blacklist: Vec::new(),
             },
             rpc: RpcConfig {
                 solana_rpc_url: "https://api.mainnet-beta.solana.com".to_string(),
@@ -77,21 +447,31 @@ impl Default for Config {
                 quicknode_ws_url: None,
                 max_requests_per_second: 10,
                 burst_size: 20,
+                fallback_rpc_urls: Vec::new(),
             },
             dexs: DexConfig {
                 enabled: vec!["orca".to_string(), "raydium".to_string(), "phoenix".to_string()],
+                pumpfun_max_pools: default_pumpfun_max_pools(),
+                pumpfun_min_market_cap: default_pumpfun_min_market_cap(),
+                min_required_dexes: default_min_required_dexes(),
             },
             monitoring: MonitoringConfig {
                 min_whale_transaction_sol: 10.0,
                 mempool_enabled: true,
                 whale_tracking_enabled: true,
                 whale_wallet_addresses: vec![],
+                max_replay_signatures: default_max_replay_signatures(),
+                discord_webhook_url: None,
+                watched_wallets: vec![],
             },
             risk_management: RiskManagementConfig {
                 max_consecutive_losses: 5,
                 daily_loss_limit_sol: 10.0,
                 position_sizing_enabled: true,
             },
+            scoring: BotScoringConfig::default(),
+            storage_path: None,
+            api_port: default_api_port(),
         }
     }
 }
@@ -99,16 +479,143 @@ impl Default for Config {
 impl Config {
     pub fn load() -> Result<Self> {
         // Load .env file if it exists
-        if let Err(_) = dotenv() {
+        if dotenv().is_err() {
             tracing::warn!("No .env file found, using environment variables and config file");
         }
 
-        // Try to load from config.toml first
-        let config_path = "config.toml";
-        let mut config = if let Ok(content) = fs::read_to_string(config_path) {
-            toml::from_str::<Config>(&content)
-                .context("Failed to parse config.toml")?
+        let config = if Self::env_config_available() {
+            Self::from_env()?
         } else {
+            Self::load_from(Path::new(CONFIG_PATH))?
+        };
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Whether every variable `from_env` needs is set
+    fn env_config_available() -> bool {
+        REQUIRED_ENV_VARS.iter().all(|name| env::var(name).is_ok())
+    }
+
+    /// Build the whole configuration from `ARB_BOT_*` environment variables, for
+    /// deployments without a config file. Unset optional values keep their defaults.
+    pub fn from_env() -> Result<Self> {
+        let missing: Vec<&str> = REQUIRED_ENV_VARS.iter().copied().filter(|name| env::var(name).is_err()).collect();
+        if !missing.is_empty() {
+            anyhow::bail!("Missing required environment variables: {}", missing.join(", "));
+        }
+
+        fn parse<T: std::str::FromStr>(name: &str) -> Result<Option<T>>
+        where
+            T::Err: std::error::Error + Send + Sync + 'static,
+        {
+            env::var(name).ok()
+                .map(|val| val.trim().parse::<T>().with_context(|| format!("Invalid value for {}", name)))
+                .transpose()
+        }
+
+        let mut config = Config::default();
+
+        config.rpc.solana_rpc_url = env::var("ARB_BOT_RPC_URL")?;
+        config.rpc.solana_ws_url = match env::var("ARB_BOT_WS_URL") {
+            Ok(val) => val,
+            Err(_) => config.rpc.solana_rpc_url.replacen("http", "ws", 1),
+        };
+        config.dexs.enabled = env::var("ARB_BOT_ENABLED_DEXES")?
+            .split(',')
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+            .collect();
+
+        if let Ok(val) = env::var("ARB_BOT_PRIVATE_KEY") {
+            if !config.validate_private_key(&val) {
+                anyhow::bail!("Invalid private key format in ARB_BOT_PRIVATE_KEY");
+            }
+            config.bot.private_key = Some(val);
+        }
+        if let Ok(val) = env::var("ARB_BOT_KEYPAIR_FILE") {
+            config.bot.keypair_file_path = Some(PathBuf::from(val));
+        }
+        if let Some(val) = parse("ARB_BOT_PROFIT_THRESHOLD")? {
+            config.bot.profit_threshold_percent = val;
+        }
+        if let Some(val) = parse("ARB_BOT_MAX_POSITION_SOL")? {
+            config.bot.max_position_size_sol = val;
+        }
+        if let Some(val) = parse("ARB_BOT_EXECUTE_TRADES")? {
+            config.bot.execute_trades = val;
+        }
+        if let Some(val) = parse("ARB_BOT_SIMULATION_MODE")? {
+            config.bot.simulation_mode = val;
+        }
+        if let Some(val) = parse("ARB_BOT_MAX_SLIPPAGE")? {
+            config.bot.max_slippage_percent = val;
+        }
+        if let Some(val) = parse("ARB_BOT_MIN_LIQUIDITY")? {
+            config.bot.min_liquidity_usd = val;
+        }
+        if let Some(val) = parse("ARB_BOT_COOLDOWN_SECS")? {
+            config.bot.cooldown_seconds = val;
+        }
+        if let Some(val) = parse("ARB_BOT_API_PORT")? {
+            config.api_port = val;
+        }
+        if let Ok(val) = env::var("ARB_BOT_DISCORD_WEBHOOK_URL") {
+            config.monitoring.discord_webhook_url = Some(val);
+        }
+
+        info!("Loaded configuration from ARB_BOT_* environment variables");
+        Ok(config)
+    }
+
+    /// Reject settings that contradict each other or cannot work
+    pub fn validate(&self) -> Result<()> {
+        let has_key = self.bot.private_key.is_some()
+            || !self.bot.private_keys.is_empty()
+            || self.bot.keypair_file_path.is_some();
+        if self.bot.execute_trades && !self.bot.simulation_mode && !has_key {
+            anyhow::bail!("execute_trades is enabled but no private key or keypair file is configured");
+        }
+        if let Some(index) = self.bot.private_keys.iter().position(|key| !self.validate_private_key(key)) {
+            anyhow::bail!("Invalid private key format in bot.private_keys[{}]", index);
+        }
+        if self.rpc.solana_rpc_url.is_empty() {
+            anyhow::bail!("No RPC URL configured");
+        }
+        if self.dexs.enabled.is_empty() {
+            anyhow::bail!("No DEXes are enabled");
+        }
+        if !self.bot.max_position_size_sol.is_finite() || self.bot.max_position_size_sol <= 0.0 {
+            anyhow::bail!("max_position_size_sol must be positive, got {}", self.bot.max_position_size_sol);
+        }
+        if !(0.0..=100.0).contains(&self.bot.max_slippage_percent) {
+            anyhow::bail!("max_slippage_percent must be between 0 and 100, got {}", self.bot.max_slippage_percent);
+        }
+        if self.bot.profit_threshold_percent < 0.0 {
+            anyhow::bail!("profit_threshold_percent cannot be negative, got {}", self.bot.profit_threshold_percent);
+        }
+        if self.bot.priority_fee_percentile > 100 {
+            anyhow::bail!("priority_fee_percentile must be at most 100, got {}", self.bot.priority_fee_percentile);
+        }
+        self.scoring.validate()
+    }
+
+    /// Parse `path` and apply environment overrides; the file must exist and be readable
+    fn read_file(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let mut config = toml::from_str::<Config>(&content)
+            .with_context(|| format!("Failed to parse {}", path.display()))?;
+        config.override_with_env()?;
+        Ok(config)
+    }
+
+    fn load_from(path: &Path) -> Result<Self> {
+        // Only a missing file falls back to the defaults; one that can't be read is an error
+        if path.try_exists().with_context(|| format!("Failed to read {}", path.display()))? {
+            return Self::read_file(path);
+        }
+        let mut config = {
             // Default configuration
             Config {
                 bot: BotConfig {
@@ -120,6 +627,43 @@ impl Config {
                     execute_trades: false,
                     simulation_mode: true,
                     private_key: None,
+                    private_keys: vec![],
+                    keypair_file_path: None,
+                    dex_reinit_threshold: default_dex_reinit_threshold(),
+                    direct_arb_max_slippage_percent: default_direct_arb_max_slippage_percent(),
+                    triangular_arb_max_slippage_percent: default_triangular_arb_max_slippage_percent(),
+                    cross_dex_max_slippage_percent: default_cross_dex_max_slippage_percent(),
+                    auto_recover_partial_execution: default_auto_recover_partial_execution(),
+                    use_jito: false,
+                    jito_tip_lamports: default_jito_tip_lamports(),
+                    enable_flash_loans: false,
+                    marginfi_group: default_marginfi_group(),
+                    max_priority_fee_microlamports: default_max_priority_fee_microlamports(),
+                    priority_fee_percentile: default_priority_fee_percentile(),
+                    cache_pool_capacity: default_cache_pool_capacity(),
+                    cache_reserve_capacity: default_cache_reserve_capacity(),
+                    cache_file_path: None,
+                    stable_mints: default_stable_mints(),
+                    stable_profit_threshold_percent: default_stable_profit_threshold_percent(),
+                    use_versioned_transactions: false,
+                    address_lookup_tables: Vec::new(),
+                    enable_multi_hop: false,
+                    max_multi_hop: default_max_multi_hop(),
+                    auto_create_token_accounts: false,
+                    max_pool_age_secs: default_max_pool_age_secs(),
+                    allow_skip_preflight: false,
+                    route_cache_ttl_ms: default_route_cache_ttl_ms(),
+                    max_pools_per_scan: default_max_pools_per_scan(),
+                    max_plausible_profit_percent: default_max_plausible_profit_percent(),
+                    min_liquidity_for_high_profit_usd: default_min_liquidity_for_high_profit_usd(),
+                    pumpfun_max_graduation_progress: default_pumpfun_max_graduation_progress(),
+                    max_price_impact_bps: default_max_price_impact_bps(),
+                    min_wallet_sol: default_min_wallet_sol(),
+                    opportunity_dedup_cooldown_secs: default_opportunity_dedup_cooldown_secs(),
+                    min_product_threshold: default_min_product_threshold(),
+                    max_oracle_age_slots: default_max_oracle_age_slots(),
+                    token_whitelist: Vec::new(),
+                    token_blacklist: Vec::new(),
                 },
                 rpc: RpcConfig {
                     solana_rpc_url: "https://api.mainnet-beta.solana.com".to_string(),
@@ -128,21 +672,31 @@ impl Config {
                     quicknode_ws_url: None,
                     max_requests_per_second: 10,
                     burst_size: 20,
+                    fallback_rpc_urls: Vec::new(),
                 },
                 dexs: DexConfig {
                     enabled: vec!["orca".to_string(), "raydium".to_string(), "phoenix".to_string()],
+                    pumpfun_max_pools: default_pumpfun_max_pools(),
+                    pumpfun_min_market_cap: default_pumpfun_min_market_cap(),
+                    min_required_dexes: default_min_required_dexes(),
                 },
                 monitoring: MonitoringConfig {
                     min_whale_transaction_sol: 10.0,
                     mempool_enabled: true,
                     whale_tracking_enabled: true,
                     whale_wallet_addresses: vec![],
+                    max_replay_signatures: default_max_replay_signatures(),
+                    discord_webhook_url: None,
+                    watched_wallets: vec![],
                 },
                 risk_management: RiskManagementConfig {
                     max_consecutive_losses: 5,
                     daily_loss_limit_sol: 10.0,
                     position_sizing_enabled: true,
                 },
+                scoring: BotScoringConfig::default(),
+                storage_path: None,
+                api_port: default_api_port(),
             }
         };
 
@@ -152,6 +706,110 @@ impl Config {
         Ok(config)
     }
 
+    /// Load `path` and keep watching it: every valid change is published on the returned
+    /// channel. Settings only read at startup keep their original values, and the
+    /// published copies never carry the raw private key.
+    pub fn load_and_watch(path: &Path) -> Result<(Config, watch::Receiver<Config>)> {
+        if dotenv().is_err() {
+            warn!("No .env file found, using environment variables and config file");
+        }
+        if Self::env_config_available() {
+            // There is no file to watch, so the configuration stays as loaded
+            let config = Self::from_env()?;
+            config.validate()?;
+            let mut startup = config.clone();
+            startup.clear_private_key();
+            let (_, receiver) = watch::channel(startup);
+            return Ok((config, receiver));
+        }
+
+        let config = Self::load_from(path)?;
+        config.validate()?;
+        let wallet = config.get_keypair()?.map(|keypair| keypair.pubkey());
+
+        let mut startup = config.clone();
+        startup.clear_private_key();
+        let (sender, receiver) = watch::channel(startup.clone());
+
+        let (event_tx, mut event_rx) = mpsc::unbounded_channel();
+        let mut watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+            if let Ok(event) = event {
+                let _ = event_tx.send(event);
+            }
+        })?;
+        // Watch the directory, since many editors save by replacing the file
+        let dir = path.parent().filter(|dir| !dir.as_os_str().is_empty()).unwrap_or(Path::new("."));
+        watcher.watch(dir, RecursiveMode::NonRecursive)
+            .with_context(|| format!("Failed to watch {}", dir.display()))?;
+
+        let path = path.to_path_buf();
+        tokio::spawn(async move {
+            let _watcher = watcher;
+            while let Some(event) = event_rx.recv().await {
+                let touches_config = event.paths.iter().any(|changed| changed.file_name() == path.file_name());
+                if !touches_config || !matches!(event.kind, EventKind::Modify(_) | EventKind::Create(_)) {
+                    continue;
+                }
+
+                // Editors may briefly leave the file missing or half-written; skip those
+                // states rather than falling back to the defaults
+                let mut reloaded = match Self::read_file(&path).and_then(|mut reloaded| {
+                    startup.retain_startup_settings(&mut reloaded);
+                    reloaded.validate()?;
+                    Ok(reloaded)
+                }) {
+                    Ok(reloaded) => reloaded,
+                    Err(e) => {
+                        warn!("Ignoring invalid change to {}: {:#}", path.display(), e);
+                        continue;
+                    }
+                };
+
+                if reloaded.get_keypair().ok().flatten().map(|keypair| keypair.pubkey()) != wallet {
+                    warn!("The trading wallet cannot change at runtime; restart to use the new key");
+                }
+                reloaded.clear_private_key();
+
+                info!("Reloaded configuration from {}", path.display());
+                if sender.send(reloaded).is_err() {
+                    break;
+                }
+            }
+        });
+
+        Ok((config, receiver))
+    }
+
+    /// Put back settings that are only read at startup, warning about each one that changed
+    fn retain_startup_settings(&self, reloaded: &mut Config) {
+        fn retain<T: PartialEq + Clone>(name: &str, original: &T, reloaded: &mut T) {
+            if original != reloaded {
+                warn!("{} cannot change at runtime; restart to apply the new value", name);
+                *reloaded = original.clone();
+            }
+        }
+
+        retain("bot.keypair_file_path", &self.bot.keypair_file_path, &mut reloaded.bot.keypair_file_path);
+        retain("bot.cache_pool_capacity", &self.bot.cache_pool_capacity, &mut reloaded.bot.cache_pool_capacity);
+        retain("bot.cache_reserve_capacity", &self.bot.cache_reserve_capacity, &mut reloaded.bot.cache_reserve_capacity);
+        retain("bot.address_lookup_tables", &self.bot.address_lookup_tables, &mut reloaded.bot.address_lookup_tables);
+        retain("rpc", &self.rpc, &mut reloaded.rpc);
+        retain("storage_path", &self.storage_path, &mut reloaded.storage_path);
+        retain("api_port", &self.api_port, &mut reloaded.api_port);
+    }
+
+    /// Re-read config.toml and environment overrides, keeping the in-memory key material
+    pub fn reload_from_disk(&mut self) -> Result<()> {
+        let mut reloaded = Self::read_file(Path::new(CONFIG_PATH))?;
+
+        // The keys may already have been handed to the executor and cleared
+        reloaded.bot.private_key = self.bot.private_key.take();
+        reloaded.bot.private_keys = std::mem::take(&mut self.bot.private_keys);
+        *self = reloaded;
+        debug!("Reloaded configuration from {}", CONFIG_PATH);
+        Ok(())
+    }
+
     fn override_with_env(&mut self) -> Result<()> {
         // Bot configuration
         if let Ok(val) = env::var("PROFIT_THRESHOLD_PERCENT") {
@@ -184,6 +842,9 @@ impl Config {
                 return Err(anyhow::anyhow!("Invalid private key format"));
             }
         }
+        if let Ok(val) = env::var("KEYPAIR_FILE_PATH") {
+            self.bot.keypair_file_path = Some(PathBuf::from(val));
+        }
 
         // RPC configuration
         if let Ok(val) = env::var("SOLANA_RPC_URL") {
@@ -198,6 +859,13 @@ impl Config {
         if let Ok(val) = env::var("QUICKNODE_WS_URL") {
             self.rpc.quicknode_ws_url = Some(val);
         }
+        if let Ok(val) = env::var("FALLBACK_RPC_URLS") {
+            self.rpc.fallback_rpc_urls = val
+                .split(',')
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty())
+                .collect();
+        }
 
         // Monitoring configuration
         if let Ok(val) = env::var("MIN_WHALE_TRANSACTION_SOL") {
@@ -210,62 +878,149 @@ impl Config {
                 .filter(|s| !s.is_empty())
                 .collect();
         }
+        if let Ok(val) = env::var("DISCORD_WEBHOOK_URL") {
+            self.monitoring.discord_webhook_url = Some(val);
+        }
+        if let Ok(val) = env::var("WATCHED_WALLETS") {
+            self.monitoring.watched_wallets = val
+                .split(',')
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty())
+                .collect();
+        }
 
         Ok(())
     }
 
-    fn validate_private_key(&self, private_key: &str) -> bool {
-        // Try to parse as base58 encoded keypair
-        if let Ok(decoded) = bs58::decode(private_key).into_vec() {
-            if decoded.len() == 64 {
-                // Try to create a keypair from the decoded bytes
-                return Keypair::from_bytes(&decoded).is_ok();
+    pub fn validate_private_key(&self, private_key: &str) -> bool {
+        let private_key = private_key.trim();
+
+        // JSON array format [byte1, byte2, ...] as written by solana-keygen
+        if private_key.starts_with('[') && private_key.ends_with(']') {
+            debug!("Detected JSON array private key format");
+            let values = match serde_json::from_str::<Vec<i64>>(private_key) {
+                Ok(values) => values,
+                Err(e) => {
+                    warn!("Private key looks like a JSON array but failed to parse: {}", e);
+                    return false;
+                }
+            };
+            if values.len() != 64 {
+                warn!("JSON array private key has {} bytes, expected 64", values.len());
+                return false;
             }
+            if values.iter().any(|v| !(0..=255).contains(v)) {
+                warn!("JSON array private key contains values outside the 0-255 range");
+                return false;
+            }
+            let bytes: Vec<u8> = values.into_iter().map(|v| v as u8).collect();
+            return match Keypair::from_bytes(&bytes) {
+                Ok(_) => true,
+                Err(e) => {
+                    warn!("JSON array private key rejected by keypair construction: {}", e);
+                    false
+                }
+            };
         }
-        
-        // Try to parse as JSON array format [byte1, byte2, ...]
-        if private_key.starts_with('[') && private_key.ends_with(']') {
-            if let Ok(bytes_vec) = serde_json::from_str::<Vec<u8>>(private_key) {
-                if bytes_vec.len() == 64 {
-                    return Keypair::from_bytes(&bytes_vec).is_ok();
+
+        // Base58 encoded 64-byte keypair
+        match bs58::decode(private_key).into_vec() {
+            Ok(decoded) => {
+                debug!("Detected base58 private key format");
+                if decoded.len() != 64 {
+                    warn!("Base58 private key decodes to {} bytes, expected 64", decoded.len());
+                    return false;
+                }
+                match Keypair::from_bytes(&decoded) {
+                    Ok(_) => true,
+                    Err(e) => {
+                        warn!("Base58 private key rejected by keypair construction: {}", e);
+                        false
+                    }
                 }
             }
+            Err(_) => {
+                warn!("Unrecognized private key format (expected base58 or JSON byte array)");
+                false
+            }
         }
-        
-        false
     }
 
+    /// Builds the trading keypair from the inline private key, falling back to
+    /// `keypair_file_path`. The raw file contents are zeroed once parsed.
     pub fn get_keypair(&self) -> Result<Option<Keypair>> {
         if let Some(private_key) = &self.bot.private_key {
-            // Try base58 format first
-            if let Ok(decoded) = bs58::decode(private_key).into_vec() {
-                if decoded.len() == 64 {
-                    if let Ok(keypair) = Keypair::from_bytes(&decoded) {
-                        return Ok(Some(keypair));
-                    }
-                }
-            }
-            
-            // Try JSON array format
-            if private_key.starts_with('[') && private_key.ends_with(']') {
-                if let Ok(bytes_vec) = serde_json::from_str::<Vec<u8>>(private_key) {
-                    if bytes_vec.len() == 64 {
-                        if let Ok(keypair) = Keypair::from_bytes(&bytes_vec) {
-                            return Ok(Some(keypair));
-                        }
-                    }
-                }
+            return Self::parse_keypair(private_key).map(Some);
+        }
+
+        if let Some(path) = &self.bot.keypair_file_path {
+            let mut contents = fs::read_to_string(path)
+                .with_context(|| format!("Failed to read keypair file {}", path.display()))?;
+            let keypair = if self.validate_private_key(&contents) {
+                Self::parse_keypair(&contents)
+            } else {
+                Err(anyhow::anyhow!("Invalid keypair file format: {}", path.display()))
+            };
+            zero_string(&mut contents);
+            return keypair.map(Some);
+        }
+
+        Ok(None)
+    }
+
+    /// Every trading wallet: the one `get_keypair` builds, then those in `private_keys`.
+    /// A key listed twice is only used once.
+    pub fn get_keypairs(&self) -> Result<Vec<Keypair>> {
+        let mut keypairs: Vec<Keypair> = self.get_keypair()?.into_iter().collect();
+        for (index, private_key) in self.bot.private_keys.iter().enumerate() {
+            let keypair = Self::parse_keypair(private_key)
+                .with_context(|| format!("Invalid bot.private_keys[{}]", index))?;
+            if !keypairs.iter().any(|existing| existing.pubkey() == keypair.pubkey()) {
+                keypairs.push(keypair);
             }
-            
-            Err(anyhow::anyhow!("Failed to parse private key"))
-        } else {
-            Ok(None)
         }
+        Ok(keypairs)
+    }
+
+    /// Trade history database file, falling back to the default location
+    pub fn trade_history_path(&self) -> &str {
+        self.storage_path.as_deref().unwrap_or(DEFAULT_DB_PATH)
+    }
+
+    /// Zeroes and drops the inline private keys once the keypairs have been created
+    pub fn clear_private_key(&mut self) {
+        if let Some(mut private_key) = self.bot.private_key.take() {
+            zero_string(&mut private_key);
+        }
+        for mut private_key in std::mem::take(&mut self.bot.private_keys) {
+            zero_string(&mut private_key);
+        }
+    }
+
+    fn parse_keypair(private_key: &str) -> Result<Keypair> {
+        let private_key = private_key.trim();
+
+        // Try JSON array format
+        if private_key.starts_with('[') && private_key.ends_with(']') {
+            let mut bytes_vec = serde_json::from_str::<Vec<u8>>(private_key)
+                .context("Failed to parse JSON array private key")?;
+            let keypair = Keypair::from_bytes(&bytes_vec);
+            bytes_vec.fill(0);
+            return keypair.map_err(|e| anyhow::anyhow!("Failed to parse private key: {}", e));
+        }
+
+        // Base58 format
+        let mut decoded = bs58::decode(private_key)
+            .into_vec()
+            .context("Failed to decode base58 private key")?;
+        let keypair = Keypair::from_bytes(&decoded);
+        decoded.fill(0);
+        keypair.map_err(|e| anyhow::anyhow!("Failed to parse private key: {}", e))
     }
 
     pub fn validate_security_settings(&self) -> Result<()> {
         // Ensure simulation mode is enabled if no private key is provided
-        if self.bot.private_key.is_none() && self.bot.execute_trades {
+        if self.bot.private_key.is_none() && self.bot.private_keys.is_empty() && self.bot.keypair_file_path.is_none() && self.bot.execute_trades {
             warn!("No private key provided but execute_trades is enabled. Forcing simulation mode.");
         }
         
@@ -287,3 +1042,137 @@ impl Config {
         Ok(())
     }
 }
+
+/// Overwrites the string's bytes before releasing it so key material does not linger
+fn zero_string(value: &mut String) {
+    let mut bytes = std::mem::take(value).into_bytes();
+    bytes.fill(0);
+    std::hint::black_box(&bytes);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_private_key_formats() {
+        let config = Config::default();
+        let keypair = Keypair::new();
+
+        let base58 = bs58::encode(keypair.to_bytes()).into_string();
+        assert!(config.validate_private_key(&base58));
+
+        let json = serde_json::to_string(&keypair.to_bytes().to_vec()).unwrap();
+        assert!(config.validate_private_key(&json));
+
+        assert!(!config.validate_private_key("invalid_key"));
+        assert!(!config.validate_private_key(&bs58::encode([1u8; 32]).into_string()));
+        assert!(!config.validate_private_key("[1, 2, 3]"));
+
+        let mut out_of_range: Vec<i64> = keypair.to_bytes().iter().map(|b| *b as i64).collect();
+        out_of_range[0] = 256;
+        assert!(!config.validate_private_key(&serde_json::to_string(&out_of_range).unwrap()));
+    }
+
+    #[test]
+    fn test_get_keypair_clears_private_key() {
+        let keypair = Keypair::new();
+        let mut config = Config::default();
+        config.bot.private_key = Some(bs58::encode(keypair.to_bytes()).into_string());
+
+        let loaded = config.get_keypair().unwrap().unwrap();
+        assert_eq!(loaded.pubkey(), keypair.pubkey());
+
+        config.clear_private_key();
+        assert!(config.bot.private_key.is_none());
+    }
+
+    #[test]
+    fn test_get_keypairs_merges_legacy_key() {
+        let first = Keypair::new();
+        let second = Keypair::new();
+        let mut config = Config::default();
+        config.bot.private_key = Some(bs58::encode(first.to_bytes()).into_string());
+        config.bot.private_keys = vec![
+            bs58::encode(first.to_bytes()).into_string(),
+            bs58::encode(second.to_bytes()).into_string(),
+        ];
+
+        let pubkeys: Vec<_> = config.get_keypairs().unwrap().iter().map(|k| k.pubkey()).collect();
+        assert_eq!(pubkeys, vec![first.pubkey(), second.pubkey()]);
+
+        config.clear_private_key();
+        assert!(config.bot.private_keys.is_empty());
+    }
+
+    #[test]
+    fn test_score_weights_validation() {
+        assert!(BotScoringConfig::default().validate().is_ok());
+
+        let overweight = BotScoringConfig {
+            profit_weight: 0.6,
+            ..BotScoringConfig::default()
+        };
+        assert!(overweight.validate().is_err());
+
+        let negative = BotScoringConfig {
+            profit_weight: 1.2,
+            confidence_weight: -0.2,
+            risk_weight: 0.0,
+            persistence_bonus: 0.0,
+        };
+        assert!(negative.validate().is_err());
+
+        let profit_only = BotScoringConfig {
+            profit_weight: 0.995,
+            confidence_weight: 0.0,
+            risk_weight: 0.0,
+            persistence_bonus: 0.1,
+        };
+        assert!(profit_only.validate().is_ok());
+    }
+
+    #[test]
+    fn test_validate() {
+        let mut config = Config::default();
+        assert!(config.validate().is_ok());
+
+        // Live trading needs a key; simulation does not
+        config.bot.execute_trades = true;
+        assert!(config.validate().is_ok());
+        config.bot.simulation_mode = false;
+        assert!(config.validate().is_err());
+        config.bot.keypair_file_path = Some(PathBuf::from("keypair.json"));
+        assert!(config.validate().is_ok());
+
+        config.dexs.enabled.clear();
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_load_from_rejects_unreadable_file() {
+        let path = std::env::temp_dir().join(format!("config_{}.toml", uuid::Uuid::new_v4()));
+        // A missing file means the defaults, but only at startup
+        assert!(Config::load_from(&path).is_ok());
+        assert!(Config::read_file(&path).is_err());
+
+        // A directory exists but can't be read as a file
+        fs::create_dir(&path).unwrap();
+        assert!(Config::load_from(&path).is_err());
+        fs::remove_dir(&path).unwrap();
+    }
+
+    #[test]
+    fn test_retain_startup_settings() {
+        let startup = Config::default();
+        let mut reloaded = Config::default();
+        reloaded.rpc.solana_rpc_url = "https://example.com".to_string();
+        reloaded.api_port = startup.api_port + 1;
+        reloaded.bot.profit_threshold_percent = startup.bot.profit_threshold_percent + 1.0;
+
+        startup.retain_startup_settings(&mut reloaded);
+        assert_eq!(reloaded.rpc, startup.rpc);
+        assert_eq!(reloaded.api_port, startup.api_port);
+        assert_eq!(reloaded.bot.profit_threshold_percent, startup.bot.profit_threshold_percent + 1.0);
+    }
+}
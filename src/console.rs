@@ -1,13 +1,22 @@
 // src/console.rs
 
 use std::{
-    collections::HashMap,
-    io::{self, Write},
-    sync::Mutex,
-    time::SystemTime,
+    collections::{HashMap, VecDeque},
+    io::{self, Read, Write},
+    path::Path,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex, Weak,
+    },
+    time::{Duration, SystemTime},
 };
 use termion::{clear, cursor, raw::IntoRawMode, color, style};
+use anyhow::{Context, Result};
+use serde::Serialize;
+use tokio::sync::Notify;
+use tracing::info;
 use chrono::{DateTime, Utc};
+use crate::{portfolio::PnlReport, storage::ProfitDivergence, utils::rpc::RateLimitStats};
 
 #[derive(Debug, Clone)]
 pub struct ServiceStatus {
@@ -17,7 +26,7 @@ pub struct ServiceStatus {
     pub additional_info: Option<String>,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize)]
 pub enum ConnectionState {
     Connected,
     Connecting,
@@ -26,31 +35,231 @@ pub enum ConnectionState {
     Unknown,
 }
 
+/// Opportunities kept for the profit sparkline, one character each at full width
+const PROFIT_HISTORY_LEN: usize = 80;
+const SPARKLINE_WIDTH: usize = 60;
+const SPARK_BLOCKS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+/// How often the key handler polls stdin
+const KEY_POLL_INTERVAL: Duration = Duration::from_millis(50);
+/// Ctrl+C arrives as a plain byte while `KeyInputMode` has signals turned off
+const CTRL_C: u8 = 3;
+/// DEXes selected by keys `1`, `2` and `3`; `0` shows all of them again
+const DEX_FILTER_KEYS: [&str; 3] = ["Orca", "Raydium", "Phoenix"];
+/// Token balances listed in the WALLET panel
+const WALLET_TOP_TOKENS: usize = 3;
+const LAMPORTS_PER_SOL: f64 = 1_000_000_000.0;
+/// Opportunities shown on screen; the session report keeps every one
+const DISPLAYED_OPPORTUNITIES: usize = 20;
+/// Where the session report is written on exit unless `--export-report` says otherwise
+pub const DEFAULT_SESSION_REPORT_PATH: &str = "session_report.json";
+
 pub struct ConsoleManager {
     service_statuses: Mutex<HashMap<String, ServiceStatus>>,
     opportunities: Mutex<Vec<OpportunityDisplay>>,
+    /// Every opportunity seen this session, oldest first, for `export_session_report`
+    session_opportunities: Mutex<Vec<OpportunityDisplay>>,
+    /// Profit percent of recent opportunities, oldest first
+    profit_history: Mutex<VecDeque<f64>>,
+    rpc_stats: Mutex<Option<RateLimitStats>>,
+    /// 95th percentile RPC latency in milliseconds, shown in the header
+    rpc_latency_p95_ms: Mutex<Option<u64>>,
+    /// Host of the RPC endpoint in use; full URLs can carry API keys
+    rpc_endpoint: Mutex<Option<String>>,
+    profit_divergence: Mutex<Option<ProfitDivergence>>,
+    session_pnl: Mutex<Option<PnlReport>>,
+    wallet_balance: Mutex<Option<WalletBalance>>,
+    /// Wallet balances below this are shown in red
+    min_wallet_sol: f64,
     start_time: SystemTime,
+    /// Set with `p`; the main loop skips arbitrage cycles while paused
+    paused: AtomicBool,
+    /// Notified on `q` (or Ctrl+C) so the main loop can shut down cleanly
+    shutdown: Notify,
+    /// When set, only this DEX's statuses and opportunities are shown
+    dex_filter: Mutex<Option<String>>,
+}
+
+/// Terminal settings that deliver keys from stdin one at a time, without echo or Enter.
+/// Unlike raw mode, output processing stays on so log lines still start at column one.
+/// The previous settings are restored on drop.
+struct KeyInputMode {
+    original: libc::termios,
+}
+
+impl KeyInputMode {
+    fn enable() -> io::Result<Self> {
+        // SAFETY: `termios` is plain data that `tcgetattr` fills in completely on success
+        let mut original: libc::termios = unsafe { std::mem::zeroed() };
+        if unsafe { libc::tcgetattr(libc::STDIN_FILENO, &mut original) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        let mut keys = original;
+        keys.c_lflag &= !(libc::ICANON | libc::ECHO | libc::ISIG);
+        keys.c_cc[libc::VMIN] = 1;
+        keys.c_cc[libc::VTIME] = 0;
+        // SAFETY: `keys` is a valid `termios` copied from the current settings
+        if unsafe { libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &keys) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(Self { original })
+    }
 }
 
+impl Drop for KeyInputMode {
+    fn drop(&mut self) {
+        // SAFETY: `original` was read by `tcgetattr` in `enable`
+        unsafe { libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &self.original) };
+    }
+}
+
+/// The trading wallet's balances as last read from the chain
 #[derive(Debug, Clone)]
+pub struct WalletBalance {
+    pub sol_balance_lamports: u64,
+    /// (symbol, amount in base units), largest first
+    pub token_balances: Vec<(String, u64)>,
+    pub last_updated: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize)]
 pub struct OpportunityDisplay {
     pub id: String,
     pub dex_pair: String,
     pub token_pair: String,
+    /// Profit after transaction fees, as a percentage of the input
     pub profit_percent: f64,
-    pub profit_usd: f64,
+    /// Profit after transaction fees, in SOL
+    pub net_profit_sol: f64,
     pub timestamp: DateTime<Utc>,
 }
 
+/// A service's status at the time the session report was written
+#[derive(Debug, Clone, Serialize)]
+pub struct ServiceStatusSnapshot {
+    pub service: String,
+    pub status: String,
+    pub connection_state: ConnectionState,
+    pub last_updated: DateTime<Utc>,
+    pub additional_info: Option<String>,
+}
+
+/// Everything the console showed during one run, written by `export_session_report`
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionReport {
+    pub start_time: DateTime<Utc>,
+    pub end_time: DateTime<Utc>,
+    pub services: Vec<ServiceStatusSnapshot>,
+    /// Newest first, like the console
+    pub opportunities: Vec<OpportunityDisplay>,
+    pub total_opportunities_found: u64,
+}
+
 impl ConsoleManager {
     pub fn new() -> Self {
         Self {
             service_statuses: Mutex::new(HashMap::new()),
             opportunities: Mutex::new(Vec::new()),
+            session_opportunities: Mutex::new(Vec::new()),
+            profit_history: Mutex::new(VecDeque::with_capacity(PROFIT_HISTORY_LEN)),
+            rpc_stats: Mutex::new(None),
+            rpc_latency_p95_ms: Mutex::new(None),
+            rpc_endpoint: Mutex::new(None),
+            profit_divergence: Mutex::new(None),
+            session_pnl: Mutex::new(None),
+            wallet_balance: Mutex::new(None),
+            min_wallet_sol: 0.0,
             start_time: SystemTime::now(),
+            paused: AtomicBool::new(false),
+            shutdown: Notify::new(),
+            dex_filter: Mutex::new(None),
         }
     }
 
+    /// Show the wallet balance in red once it falls below `min_wallet_sol`
+    pub fn with_min_wallet_sol(mut self, min_wallet_sol: f64) -> Self {
+        self.min_wallet_sol = min_wallet_sol;
+        self
+    }
+
+    /// Listen for `p` (pause/resume), `q` (quit) and `0`-`3` (DEX filter) when attached
+    /// to a terminal. Must be called inside a tokio runtime; stops once the console is dropped.
+    pub fn start_key_handler(self: &Arc<Self>) {
+        if !termion::is_tty(&io::stdin()) {
+            return;
+        }
+
+        let console: Weak<Self> = Arc::downgrade(self);
+        tokio::spawn(async move {
+            // Restored when the task is dropped
+            let _key_mode = match KeyInputMode::enable() {
+                Ok(mode) => mode,
+                Err(e) => {
+                    info!("Console keys unavailable: {}", e);
+                    return;
+                }
+            };
+            let mut stdin = termion::async_stdin();
+            let mut buf = [0u8; 16];
+            loop {
+                let read = stdin.read(&mut buf).unwrap_or(0);
+                let Some(console) = console.upgrade() else {
+                    return;
+                };
+                for &key in &buf[..read] {
+                    match key {
+                        b'p' | b'P' => {
+                            console.toggle_pause();
+                            info!("Scanning {} from console", if console.is_paused() { "paused" } else { "resumed" });
+                        }
+                        b'q' | b'Q' | CTRL_C => {
+                            info!("Shutdown requested from console");
+                            console.shutdown.notify_one();
+                        }
+                        b'0' => console.set_dex_filter(None),
+                        b'1'..=b'3' => console.toggle_dex_filter(DEX_FILTER_KEYS[(key - b'1') as usize]),
+                        _ => {}
+                    }
+                }
+                drop(console);
+                tokio::time::sleep(KEY_POLL_INTERVAL).await;
+            }
+        });
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::SeqCst)
+    }
+
+    pub fn toggle_pause(&self) {
+        self.paused.fetch_xor(true, Ordering::SeqCst);
+        self.refresh_display();
+    }
+
+    /// Show only `filter`'s service statuses and opportunities, or everything on `None`
+    pub fn set_dex_filter(&self, filter: Option<String>) {
+        *self.dex_filter.lock().unwrap() = filter;
+        self.refresh_display();
+    }
+
+    pub fn dex_filter(&self) -> Option<String> {
+        self.dex_filter.lock().unwrap().clone()
+    }
+
+    /// Filter on `dex`, or clear the filter if it is already on `dex`
+    fn toggle_dex_filter(&self, dex: &str) {
+        {
+            let mut filter = self.dex_filter.lock().unwrap();
+            *filter = if filter.as_deref() == Some(dex) { None } else { Some(dex.to_string()) };
+        }
+        self.refresh_display();
+    }
+
+    /// Resolves once shutdown has been requested from the console
+    pub async fn shutdown_requested(&self) {
+        self.shutdown.notified().await;
+    }
+
     pub fn update_status(&self, service: &str, status: &str) {
         let mut statuses = self.service_statuses.lock().unwrap();
         
@@ -103,18 +312,137 @@ impl ConsoleManager {
     }
 
     pub fn add_opportunity(&self, opportunity: OpportunityDisplay) {
+        let mut history = self.profit_history.lock().unwrap();
+        if history.len() == PROFIT_HISTORY_LEN {
+            history.pop_front();
+        }
+        history.push_back(opportunity.profit_percent);
+        drop(history);
+
+        self.session_opportunities.lock().unwrap().push(opportunity.clone());
+
         let mut opportunities = self.opportunities.lock().unwrap();
         opportunities.insert(0, opportunity); // Insert at beginning for newest first
         
-        // Keep only last 20 opportunities
-        if opportunities.len() > 20 {
-            opportunities.truncate(20);
+        // Keep only the most recent opportunities on screen
+        if opportunities.len() > DISPLAYED_OPPORTUNITIES {
+            opportunities.truncate(DISPLAYED_OPPORTUNITIES);
         }
         drop(opportunities);
         
         self.refresh_display();
     }
 
+    /// Service statuses and every opportunity seen since startup
+    pub fn session_report(&self) -> SessionReport {
+        let mut services: Vec<ServiceStatusSnapshot> = self.service_statuses.lock().unwrap()
+            .iter()
+            .map(|(service, status)| ServiceStatusSnapshot {
+                service: service.clone(),
+                status: status.status.clone(),
+                connection_state: status.connection_state.clone(),
+                last_updated: status.last_updated,
+                additional_info: status.additional_info.clone(),
+            })
+            .collect();
+        services.sort_by(|a, b| a.service.cmp(&b.service));
+
+        let opportunities: Vec<OpportunityDisplay> = self.session_opportunities.lock().unwrap()
+            .iter()
+            .rev()
+            .cloned()
+            .collect();
+
+        SessionReport {
+            start_time: DateTime::<Utc>::from(self.start_time),
+            end_time: Utc::now(),
+            services,
+            total_opportunities_found: opportunities.len() as u64,
+            opportunities,
+        }
+    }
+
+    /// Write `session_report` to `path` as pretty-printed JSON
+    pub fn export_session_report(&self, path: &Path) -> Result<()> {
+        let report = self.session_report();
+        let json = serde_json::to_string_pretty(&report).context("Failed to serialize session report")?;
+        std::fs::write(path, json)
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+
+        info!("Wrote session report with {} opportunities to {}", report.total_opportunities_found, path.display());
+        Ok(())
+    }
+
+    /// The last `width` values of `history` as block characters scaled between their
+    /// min and max. A flat window is drawn at the lowest level.
+    pub fn draw_profit_sparkline(history: &[f64], width: usize) -> String {
+        let window = &history[history.len().saturating_sub(width)..];
+        let (min, max) = Self::min_max(window);
+        let range = max - min;
+
+        window.iter()
+            .map(|value| {
+                let level = if range > 0.0 {
+                    ((value - min) / range * (SPARK_BLOCKS.len() - 1) as f64).round() as usize
+                } else {
+                    0
+                };
+                SPARK_BLOCKS[level.min(SPARK_BLOCKS.len() - 1)]
+            })
+            .collect()
+    }
+
+    fn min_max(values: &[f64]) -> (f64, f64) {
+        values.iter().fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), &value| (min.min(value), max.max(value)))
+    }
+
+    /// Sparkline of recent profits with its min/max legend, if there is any history
+    fn profit_trend(&self) -> Option<(String, f64, f64)> {
+        let history: Vec<f64> = self.profit_history.lock().unwrap().iter().copied().collect();
+        if history.is_empty() {
+            return None;
+        }
+        let window = &history[history.len().saturating_sub(SPARKLINE_WIDTH)..];
+        let (min, max) = Self::min_max(window);
+        Some((Self::draw_profit_sparkline(window, SPARKLINE_WIDTH), min, max))
+    }
+
+    /// Record the latest RPC quota usage; shown on the next redraw
+    pub fn update_rpc_stats(&self, stats: RateLimitStats) {
+        *self.rpc_stats.lock().unwrap() = Some(stats);
+    }
+
+    /// Record the current p95 RPC latency; shown on the next redraw
+    pub fn update_rpc_latency_p95(&self, latency_ms: Option<u64>) {
+        *self.rpc_latency_p95_ms.lock().unwrap() = latency_ms;
+    }
+
+    /// Record which RPC endpoint requests are sent to after startup or a failover
+    pub fn update_rpc_endpoint(&self, endpoint: &str) {
+        let host = url::Url::parse(endpoint).ok()
+            .and_then(|url| url.host_str().map(str::to_string))
+            .unwrap_or_else(|| endpoint.to_string());
+        *self.rpc_endpoint.lock().unwrap() = Some(host);
+    }
+
+    /// Record realized against expected profit for recent trades
+    pub fn update_profit_divergence(&self, divergence: ProfitDivergence) {
+        *self.profit_divergence.lock().unwrap() = Some(divergence);
+        self.refresh_display();
+    }
+
+    /// Record the wallet's P&L since startup
+    pub fn update_session_pnl(&self, pnl: PnlReport) {
+        *self.session_pnl.lock().unwrap() = Some(pnl);
+        self.refresh_display();
+    }
+
+    /// Record the wallet's latest balances
+    pub fn update_wallet_balance(&self, balance: WalletBalance) {
+        *self.wallet_balance.lock().unwrap() = Some(balance);
+        self.refresh_display();
+    }
+
     pub fn clear_opportunities(&self) {
         let mut opportunities = self.opportunities.lock().unwrap();
         opportunities.clear();
@@ -123,6 +451,14 @@ impl ConsoleManager {
         self.refresh_display();
     }
 
+    /// The largest token balances as " │ SYMBOL amount" segments
+    fn format_top_tokens(balance: &WalletBalance) -> String {
+        balance.token_balances.iter()
+            .take(WALLET_TOP_TOKENS)
+            .map(|(symbol, amount)| format!(" │ {} {}", symbol, amount))
+            .collect()
+    }
+
     fn determine_connection_state(&self, status: &str) -> ConnectionState {
         let status_lower = status.to_lowercase();
         
@@ -150,8 +486,28 @@ impl ConsoleManager {
     }
 
     fn refresh_display(&self) {
-        let statuses = self.service_statuses.lock().unwrap();
-        let opportunities = self.opportunities.lock().unwrap();
+        let dex_filter = self.dex_filter();
+        let matches_filter = |name: &str, exact: bool| match &dex_filter {
+            Some(dex) if exact => name.eq_ignore_ascii_case(dex),
+            Some(dex) => name.to_lowercase().contains(&dex.to_lowercase()),
+            None => true,
+        };
+        let all_statuses = self.service_statuses.lock().unwrap();
+        let statuses: Vec<(&String, &ServiceStatus)> = all_statuses.iter()
+            .filter(|(service, _)| matches_filter(service, true))
+            .collect();
+        let all_opportunities = self.opportunities.lock().unwrap();
+        let opportunities: Vec<&OpportunityDisplay> = all_opportunities.iter()
+            .filter(|opportunity| matches_filter(&opportunity.dex_pair, false))
+            .collect();
+        let rpc_stats = *self.rpc_stats.lock().unwrap();
+        let rpc_latency = self.rpc_latency_p95_ms.lock().unwrap()
+            .map(|p95| format!(" | RPC p95: {}ms", p95))
+            .unwrap_or_default();
+        let rpc_endpoint = self.rpc_endpoint.lock().unwrap().clone().unwrap_or_default();
+        let profit_divergence = *self.profit_divergence.lock().unwrap();
+        let session_pnl = *self.session_pnl.lock().unwrap();
+        let wallet_balance = self.wallet_balance.lock().unwrap().clone();
         
         // Try to use raw mode, but fall back to regular stdout if it fails
         let stdout_result = io::stdout().into_raw_mode();
@@ -167,10 +523,16 @@ impl ConsoleManager {
                 (uptime.as_secs() % 3600) / 60,
                 uptime.as_secs() % 60
             );
-            println!("Uptime: {} | Time: {}", uptime_str, Utc::now().format("%H:%M:%S UTC"));
+            println!("Uptime: {}{} | Time: {}", uptime_str, rpc_latency, Utc::now().format("%H:%M:%S UTC"));
+            if self.is_paused() {
+                println!("[PAUSED - press P to resume]");
+            }
+            if let Some(dex) = &dex_filter {
+                println!("[Filter: {}]", dex);
+            }
             
             println!("\nDEX CONNECTIONS:");
-            let mut sorted_services: Vec<_> = statuses.iter().collect();
+            let mut sorted_services = statuses.clone();
             sorted_services.sort_by_key(|(name, _)| *name);
             
             for (service, service_status) in &sorted_services {
@@ -191,10 +553,43 @@ impl ConsoleManager {
                 }
             }
             
+            if let Some(stats) = rpc_stats {
+                println!("\nRPC {} | {}/{} rps | burst: {} | wait: {}ms",
+                    rpc_endpoint,
+                    stats.requests_last_second, stats.max_requests_per_second,
+                    stats.burst_remaining, stats.wait_time_ms);
+            }
+
+            if let Some(pnl) = session_pnl {
+                println!("Session P&L | {:+.6} SOL (realized {:+.6}, unrealized {:+.6}) | ROI: {:+.2}% | Sharpe: {:.2}",
+                    pnl.total_lamports() as f64 / 1_000_000_000.0,
+                    pnl.realized_lamports as f64 / 1_000_000_000.0,
+                    pnl.unrealized_lamports as f64 / 1_000_000_000.0,
+                    pnl.roi_percent, pnl.sharpe_ratio);
+            }
+
+            if let Some(divergence) = profit_divergence {
+                println!("Profit (last {} trades) | realized: {:.6} SOL | expected: {:.6} SOL | divergence: {}",
+                    divergence.trades,
+                    divergence.realized_profit_lamports as f64 / 1_000_000_000.0,
+                    divergence.expected_profit_lamports as f64 / 1_000_000_000.0,
+                    divergence.divergence_percent().map_or("n/a".to_string(), |pct| format!("{:+.1}%", pct)));
+            }
+
+            if let Some(balance) = &wallet_balance {
+                println!("\nWALLET: {:.4} SOL{} | {}s ago",
+                    balance.sol_balance_lamports as f64 / LAMPORTS_PER_SOL,
+                    Self::format_top_tokens(balance),
+                    (Utc::now() - balance.last_updated).num_seconds());
+            }
+            
             if opportunities.is_empty() {
                 println!("\nNo arbitrage opportunities detected yet...");
             } else {
                 println!("\nRecent opportunities: {}", opportunities.len());
+                if let Some((sparkline, min, max)) = self.profit_trend() {
+                    println!("Profit trend: {} (min {:.2}%, max {:.2}%)", sparkline, min, max);
+                }
             }
             
             return;
@@ -216,13 +611,21 @@ impl ConsoleManager {
         write!(stdout, "{}{}═══════════════════════════════════════════════════════════════════════════════{}", 
             style::Bold, color::Fg(color::Cyan), style::Reset).unwrap();
         let _ = write!(stdout, "\r\n");
-        write!(stdout, "{}{}  🚀 SOLANA ARBITRAGE BOT  {}│{}  Uptime: {}  {}│{}  {} {}", 
+        write!(stdout, "{}{}  🚀 SOLANA ARBITRAGE BOT  {}│{}  Uptime: {}{}  {}│{}  {} {}", 
             style::Bold, color::Fg(color::Cyan),
             style::Reset, color::Fg(color::White),
-            uptime_str, style::Reset,
+            uptime_str, rpc_latency, style::Reset,
             color::Fg(color::White), 
             Utc::now().format("%H:%M:%S UTC"),
             style::Reset).unwrap();
+        if self.is_paused() {
+            write!(stdout, "  {}{}[PAUSED - press P to resume]{}",
+                style::Bold, color::Fg(color::Yellow), style::Reset).unwrap();
+        }
+        if let Some(dex) = &dex_filter {
+            write!(stdout, "  {}{}[Filter: {}]{}",
+                style::Bold, color::Fg(color::Yellow), dex, style::Reset).unwrap();
+        }
         let _ = write!(stdout, "\r\n");
         write!(stdout, "{}{}═══════════════════════════════════════════════════════════════════════════════{}", 
             style::Bold, color::Fg(color::Cyan), style::Reset).unwrap();
@@ -233,7 +636,7 @@ impl ConsoleManager {
         let _ = write!(stdout, "\r\n");
         
         // Sort services for consistent display
-        let mut sorted_services: Vec<_> = statuses.iter().collect();
+        let mut sorted_services = statuses.clone();
         sorted_services.sort_by_key(|(name, _)| *name);
         
         for (service, service_status) in &sorted_services {
@@ -272,7 +675,84 @@ impl ConsoleManager {
                 let _ = write!(stdout, "\r\n");
             }
         }
+
+        if let Some(stats) = rpc_stats {
+            let usage = stats.usage_ratio();
+            let quota_color = if usage > 0.9 {
+                "\x1b[31m" // Red
+            } else if usage >= 0.7 {
+                "\x1b[33m" // Yellow
+            } else {
+                "\x1b[32m" // Green
+            };
+
+            write!(stdout, "  {}●\x1b[0m {}RPC QUOTA{}  │  {}{}/{} rps\x1b[0m │ burst: {} │ wait: {}ms │ {}",
+                quota_color,
+                style::Bold, style::Reset,
+                quota_color, stats.requests_last_second, stats.max_requests_per_second,
+                stats.burst_remaining,
+                stats.wait_time_ms,
+                rpc_endpoint).unwrap();
+            let _ = write!(stdout, "\r\n");
+        }
+
+        if let Some(pnl) = session_pnl {
+            let pnl_color = if pnl.total_lamports() >= 0 {
+                "\x1b[32m" // Green
+            } else {
+                "\x1b[31m" // Red
+            };
+
+            write!(stdout, "  {}●\x1b[0m {}SESSION P&L{}  │  {}{:+.6} SOL\x1b[0m │ realized: {:+.6} │ unrealized: {:+.6} │ ROI: {:+.2}% │ Sharpe: {:.2}",
+                pnl_color,
+                style::Bold, style::Reset,
+                pnl_color, pnl.total_lamports() as f64 / 1_000_000_000.0,
+                pnl.realized_lamports as f64 / 1_000_000_000.0,
+                pnl.unrealized_lamports as f64 / 1_000_000_000.0,
+                pnl.roi_percent,
+                pnl.sharpe_ratio).unwrap();
+            let _ = write!(stdout, "\r\n");
+        }
+
+        if let Some(divergence) = profit_divergence {
+            let divergence_color = match divergence.divergence_percent() {
+                Some(pct) if pct < -25.0 => "\x1b[31m", // Red
+                Some(pct) if pct < -10.0 => "\x1b[33m", // Yellow
+                _ => "\x1b[32m", // Green
+            };
+
+            write!(stdout, "  {}●\x1b[0m {}PROFIT{}  │  last {} trades │ realized: {:.6} SOL │ expected: {:.6} SOL │ {}{}\x1b[0m",
+                divergence_color,
+                style::Bold, style::Reset,
+                divergence.trades,
+                divergence.realized_profit_lamports as f64 / 1_000_000_000.0,
+                divergence.expected_profit_lamports as f64 / 1_000_000_000.0,
+                divergence_color,
+                divergence.divergence_percent().map_or("n/a".to_string(), |pct| format!("{:+.1}%", pct))).unwrap();
+            let _ = write!(stdout, "\r\n");
+        }
         
+        // Wallet Section
+        if let Some(balance) = &wallet_balance {
+            let _ = write!(stdout, "\r\n");
+            write!(stdout, "{}{}WALLET{}", style::Bold, color::Fg(color::White), style::Reset).unwrap();
+            let _ = write!(stdout, "\r\n");
+
+            let sol_balance = balance.sol_balance_lamports as f64 / LAMPORTS_PER_SOL;
+            let balance_color = if sol_balance >= self.min_wallet_sol {
+                "\x1b[32m" // Green
+            } else {
+                "\x1b[31m" // Red
+            };
+            write!(stdout, "  {}●\x1b[0m {}SOL{}  │  {}{:.4} SOL\x1b[0m{}  │  {}{}s ago{}",
+                balance_color,
+                style::Bold, style::Reset,
+                balance_color, sol_balance,
+                Self::format_top_tokens(balance),
+                color::Fg(color::LightBlack), (Utc::now() - balance.last_updated).num_seconds(), style::Reset).unwrap();
+            let _ = write!(stdout, "\r\n");
+        }
+
         // Opportunities Section
         let _ = write!(stdout, "\r\n");
         write!(stdout, "{}{}ARBITRAGE OPPORTUNITIES{}", style::Bold, color::Fg(color::White), style::Reset).unwrap();
@@ -284,12 +764,19 @@ impl ConsoleManager {
             let _ = write!(stdout, "\r\n");
         } else {
             // Table header
-            write!(stdout, "  {}{}TIME      │ DEX PAIR        │ TOKEN PAIR           │ PROFIT %  │ PROFIT USD{}", 
+            write!(stdout, "  {}{}TIME      │ DEX PAIR        │ TOKEN PAIR           │ NET %     │ NET SOL{}", 
                 style::Bold, color::Fg(color::White), style::Reset).unwrap();
             let _ = write!(stdout, "\r\n");
             write!(stdout, "  {}─────────────────────────────────────────────────────────────────────────────{}", 
                 color::Fg(color::LightBlack), style::Reset).unwrap();
             let _ = write!(stdout, "\r\n");
+
+            if let Some((sparkline, min, max)) = self.profit_trend() {
+                write!(stdout, "  {}{}{} {}min {:.2}% │ max {:.2}%{}",
+                    color::Fg(color::Cyan), sparkline, style::Reset,
+                    color::Fg(color::LightBlack), min, max, style::Reset).unwrap();
+                let _ = write!(stdout, "\r\n");
+            }
             
             for opportunity in opportunities.iter().take(15) {
                 let profit_color = if opportunity.profit_percent >= 1.0 {
@@ -305,7 +792,7 @@ impl ConsoleManager {
                     opportunity.dex_pair,
                     opportunity.token_pair,
                     profit_color, opportunity.profit_percent,
-                    profit_color, opportunity.profit_usd).unwrap();
+                    profit_color, opportunity.net_profit_sol).unwrap();
                 let _ = write!(stdout, "\r\n");
             }
         }
@@ -326,4 +813,90 @@ impl ConsoleManager {
         
         stdout.flush().unwrap();
     }
-}
\ No newline at end of file
+}
+
+impl Default for ConsoleManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_draw_profit_sparkline() {
+        assert_eq!(ConsoleManager::draw_profit_sparkline(&[], 10), "");
+
+        // Min and max land on the lowest and highest blocks
+        let sparkline = ConsoleManager::draw_profit_sparkline(&[0.5, 1.0, 0.75, 2.0], 10);
+        assert_eq!(sparkline.chars().count(), 4);
+        assert_eq!(sparkline.chars().next(), Some('▁'));
+        assert_eq!(sparkline.chars().last(), Some('█'));
+
+        // Only the newest `width` values are drawn, scaled to that window
+        let history: Vec<f64> = (0..100).map(f64::from).collect();
+        let sparkline = ConsoleManager::draw_profit_sparkline(&history, 60);
+        assert_eq!(sparkline.chars().count(), 60);
+        assert!(sparkline.starts_with('▁') && sparkline.ends_with('█'));
+
+        assert_eq!(ConsoleManager::draw_profit_sparkline(&[1.0, 1.0, 1.0], 10), "▁▁▁");
+    }
+
+    #[test]
+    fn test_export_session_report_keeps_every_opportunity() {
+        let console = ConsoleManager::new();
+        console.update_status("Application", "Running");
+        for i in 0..25 {
+            console.add_opportunity(OpportunityDisplay {
+                id: i.to_string(),
+                dex_pair: "orca/raydium".to_string(),
+                token_pair: "SOL/USDC".to_string(),
+                profit_percent: 0.5,
+                net_profit_sol: 0.01,
+                timestamp: Utc::now(),
+            });
+        }
+
+        let path = std::env::temp_dir().join(format!("session_report_{}.json", uuid::Uuid::new_v4()));
+        console.export_session_report(&path).unwrap();
+        let report: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(report["total_opportunities_found"], 25);
+        assert_eq!(report["opportunities"].as_array().unwrap().len(), 25);
+        assert_eq!(report["opportunities"][0]["id"], "24");
+        assert_eq!(report["services"][0]["service"], "Application");
+        assert_eq!(console.opportunities.lock().unwrap().len(), DISPLAYED_OPPORTUNITIES);
+    }
+
+    #[test]
+    fn test_dex_filter() {
+        let console = ConsoleManager::new();
+        assert_eq!(console.dex_filter(), None);
+
+        console.toggle_dex_filter("Orca");
+        assert_eq!(console.dex_filter().as_deref(), Some("Orca"));
+        console.toggle_dex_filter("Raydium");
+        assert_eq!(console.dex_filter().as_deref(), Some("Raydium"));
+        // The same key again shows every DEX
+        console.toggle_dex_filter("Raydium");
+        assert_eq!(console.dex_filter(), None);
+
+        console.set_dex_filter(Some("Phoenix".to_string()));
+        assert_eq!(console.dex_filter().as_deref(), Some("Phoenix"));
+        console.set_dex_filter(None);
+        assert_eq!(console.dex_filter(), None);
+    }
+
+    #[test]
+    fn test_toggle_pause() {
+        let console = ConsoleManager::new();
+        assert!(!console.is_paused());
+        console.toggle_pause();
+        assert!(console.is_paused());
+        console.toggle_pause();
+        assert!(!console.is_paused());
+    }
+}
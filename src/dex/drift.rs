@@ -0,0 +1,322 @@
+use crate::{
+    console::ConsoleManager,
+    dex::DexClient,
+    models::{Pool, TokenInfo},
+    utils::rpc::RpcClient,
+};
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use rust_decimal::Decimal;
+use solana_client::rpc_filter::{Memcmp, RpcFilterType};
+use solana_sdk::pubkey::Pubkey;
+use std::{collections::HashMap, str::FromStr, sync::Arc};
+use tracing::{debug, info};
+
+pub const DRIFT_PROGRAM_ID: &str = "dRiftyHA39MWEi3m9aunc5MzRF1JYuBsbn6VPcn33UH";
+
+/// sha256("account:SpotMarket")[..8]
+const SPOT_MARKET_DISCRIMINATOR: [u8; 8] = [100, 177, 8, 107, 168, 65, 65, 39];
+/// Serialized size of a `SpotMarket` account, discriminator included
+pub const SPOT_MARKET_ACCOUNT_LEN: usize = 776;
+const MINT_OFFSET: usize = 72;
+const VAULT_OFFSET: usize = 104;
+const NAME_OFFSET: usize = 136;
+/// `historical_oracle_data.last_oracle_price`, in `PRICE_PRECISION`
+const LAST_ORACLE_PRICE_OFFSET: usize = 168;
+const OPTIMAL_UTILIZATION_OFFSET: usize = 668;
+const OPTIMAL_BORROW_RATE_OFFSET: usize = 672;
+const DECIMALS_OFFSET: usize = 680;
+const MARKET_INDEX_OFFSET: usize = 684;
+const ORDERS_ENABLED_OFFSET: usize = 686;
+const FEE_ADJUSTMENT_OFFSET: usize = 692;
+
+/// Drift prices carry six decimals
+const PRICE_PRECISION: i64 = 1_000_000;
+/// Utilization and borrow rates are fractions of this
+pub const SPOT_UTILIZATION_PRECISION: u32 = 1_000_000;
+/// Spot market 0 is the USDC market every other market is quoted in
+const QUOTE_SPOT_MARKET_INDEX: u16 = 0;
+/// Tier 1 taker fee of the default spot fee structure, before the market's adjustment
+const SPOT_TAKER_FEE_BPS: i64 = 10;
+/// Offset of `amount` in an SPL token account
+const TOKEN_AMOUNT_OFFSET: usize = 64;
+/// `getMultipleAccounts` accepts at most this many addresses per call
+const MAX_ACCOUNTS_PER_BATCH: usize = 100;
+
+/// Fields of a Drift `SpotMarket` account used to price it against USDC
+#[derive(Debug, Clone)]
+pub struct DriftSpotMarket {
+    pub market_index: u16,
+    pub name: String,
+    pub mint: Pubkey,
+    pub vault: Pubkey,
+    pub decimals: u8,
+    /// Last oracle price in USDC, with `PRICE_PRECISION`
+    pub oracle_price: i64,
+    /// Utilization above which borrow rates climb steeply, in `SPOT_UTILIZATION_PRECISION`
+    pub optimal_utilization: u32,
+    /// Borrow rate at optimal utilization, in `SPOT_UTILIZATION_PRECISION`
+    pub optimal_borrow_rate: u32,
+    pub orders_enabled: bool,
+    /// Percent adjustment applied to the fee structure, e.g. -50 halves fees
+    pub fee_adjustment: i16,
+}
+
+impl DriftSpotMarket {
+    /// Taker fee as a fraction, the form `Pool::fee_percent` holds
+    pub fn taker_fee_rate(&self) -> Decimal {
+        let adjusted_bps = SPOT_TAKER_FEE_BPS * (100 + self.fee_adjustment as i64).max(0);
+        Decimal::new(adjusted_bps, 6)
+    }
+
+    /// Share of the vault treated as tradeable: withdrawing past optimal utilization
+    /// pushes borrow rates towards their maximum, so only that much counts as depth
+    pub fn effective_reserve(&self, vault_balance: u64) -> u64 {
+        (vault_balance as u128 * self.optimal_utilization.min(SPOT_UTILIZATION_PRECISION) as u128
+            / SPOT_UTILIZATION_PRECISION as u128) as u64
+    }
+
+    /// `amount` of this market's token valued in raw units of a quote token with `quote_decimals`
+    pub fn quote_value(&self, amount: u64, quote_decimals: u8) -> u64 {
+        if self.oracle_price <= 0 {
+            return 0;
+        }
+        let value = amount as u128 * self.oracle_price as u128 * 10u128.pow(quote_decimals as u32)
+            / (PRICE_PRECISION as u128 * 10u128.pow(self.decimals as u32));
+        value.min(u64::MAX as u128) as u64
+    }
+}
+
+fn read_u32(data: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap())
+}
+
+fn token_amount(data: &[u8]) -> Option<u64> {
+    data.get(TOKEN_AMOUNT_OFFSET..TOKEN_AMOUNT_OFFSET + 8)
+        .map(|bytes| u64::from_le_bytes(bytes.try_into().unwrap()))
+}
+
+/// Drift V2 spot markets, each priced as a pool against the USDC quote market
+pub struct DriftClient {
+    rpc_client: Arc<RpcClient>,
+    program_id: Pubkey,
+    pools_cache: tokio::sync::RwLock<HashMap<String, Pool>>,
+    console: Arc<ConsoleManager>,
+}
+
+impl DriftClient {
+    pub fn new(rpc_client: Arc<RpcClient>, console: Arc<ConsoleManager>) -> Result<Self> {
+        Ok(Self {
+            rpc_client,
+            program_id: Pubkey::from_str(DRIFT_PROGRAM_ID)?,
+            pools_cache: tokio::sync::RwLock::new(HashMap::new()),
+            console,
+        })
+    }
+
+    /// Parse a zero-copy `SpotMarket` account
+    pub fn parse_spot_market(data: &[u8]) -> Result<DriftSpotMarket> {
+        if data.len() < SPOT_MARKET_ACCOUNT_LEN {
+            anyhow::bail!("Invalid Drift spot market size: {} bytes", data.len());
+        }
+        if data[..8] != SPOT_MARKET_DISCRIMINATOR {
+            anyhow::bail!("Not a Drift spot market account");
+        }
+
+        let name = String::from_utf8_lossy(&data[NAME_OFFSET..NAME_OFFSET + 32])
+            .trim_end_matches(['\0', ' '])
+            .to_string();
+        Ok(DriftSpotMarket {
+            market_index: u16::from_le_bytes(data[MARKET_INDEX_OFFSET..MARKET_INDEX_OFFSET + 2].try_into()?),
+            name,
+            mint: Pubkey::try_from(&data[MINT_OFFSET..MINT_OFFSET + 32])?,
+            vault: Pubkey::try_from(&data[VAULT_OFFSET..VAULT_OFFSET + 32])?,
+            decimals: read_u32(data, DECIMALS_OFFSET).try_into().context("Drift spot market decimals out of range")?,
+            oracle_price: i64::from_le_bytes(data[LAST_ORACLE_PRICE_OFFSET..LAST_ORACLE_PRICE_OFFSET + 8].try_into()?),
+            optimal_utilization: read_u32(data, OPTIMAL_UTILIZATION_OFFSET),
+            optimal_borrow_rate: read_u32(data, OPTIMAL_BORROW_RATE_OFFSET),
+            orders_enabled: data[ORDERS_ENABLED_OFFSET] != 0,
+            fee_adjustment: i16::from_le_bytes(data[FEE_ADJUSTMENT_OFFSET..FEE_ADJUSTMENT_OFFSET + 2].try_into()?),
+        })
+    }
+
+    /// Every spot market account owned by the Drift program, skipping ones that don't parse
+    async fn get_spot_market_accounts(&self) -> Result<Vec<(Pubkey, DriftSpotMarket)>> {
+        let accounts = self.rpc_client.get_program_accounts_with_filters(&self.program_id, &[
+            RpcFilterType::DataSize(SPOT_MARKET_ACCOUNT_LEN as u64),
+            RpcFilterType::Memcmp(Memcmp::new_raw_bytes(0, SPOT_MARKET_DISCRIMINATOR.to_vec())),
+        ]).await.context("Failed to fetch Drift spot markets")?;
+
+        Ok(accounts.into_iter()
+            .filter_map(|(pubkey, account)| match Self::parse_spot_market(&account.data) {
+                Ok(market) => Some((pubkey, market)),
+                Err(e) => {
+                    debug!("Skipping Drift account {}: {}", pubkey, e);
+                    None
+                }
+            })
+            .collect())
+    }
+
+    /// Vault balance of each market, `None` where the vault can't be read
+    async fn fetch_vault_balances(&self, markets: &[&DriftSpotMarket]) -> Result<Vec<Option<u64>>> {
+        let vaults: Vec<Pubkey> = markets.iter().map(|market| market.vault).collect();
+        let mut balances = Vec::with_capacity(vaults.len());
+        for chunk in vaults.chunks(MAX_ACCOUNTS_PER_BATCH) {
+            let accounts = self.rpc_client.get_multiple_accounts(chunk).await?;
+            balances.extend(accounts.iter().map(|account| account.as_ref().and_then(|account| token_amount(&account.data))));
+        }
+        Ok(balances)
+    }
+
+    /// Pool trading `market`'s token against the quote token, sized by the market vault
+    fn market_pool(address: Pubkey, market: DriftSpotMarket, quote: &TokenInfo, vault_balance: u64) -> Pool {
+        let reserve_a = market.effective_reserve(vault_balance);
+        let reserve_b = market.quote_value(reserve_a, quote.decimals);
+        Pool {
+            address,
+            dex: "drift".to_string(),
+            token_a: TokenInfo {
+                mint: market.mint,
+                symbol: market.name.clone(),
+                decimals: market.decimals,
+                price_usd: Decimal::try_from(market.oracle_price as f64 / PRICE_PRECISION as f64).ok(),
+            },
+            token_b: quote.clone(),
+            reserve_a,
+            reserve_b,
+            fee_percent: market.taker_fee_rate(),
+            liquidity_usd: Decimal::from(reserve_b) * Decimal::from(2) / Decimal::from(10u64.pow(quote.decimals as u32)),
+            last_updated: chrono::Utc::now(),
+            metadata: Some(Arc::new(market)),
+        }
+    }
+}
+
+#[async_trait]
+impl DexClient for DriftClient {
+    async fn fetch_pools(&self) -> Result<Vec<Pool>> {
+        self.console.update_status(self.get_dex_name(), "Fetching spot markets");
+
+        let markets = self.get_spot_market_accounts().await?;
+        let quote = markets.iter()
+            .map(|(_, market)| market)
+            .find(|market| market.market_index == QUOTE_SPOT_MARKET_INDEX)
+            .map(|market| TokenInfo {
+                mint: market.mint,
+                symbol: market.name.clone(),
+                decimals: market.decimals,
+                price_usd: Some(Decimal::ONE),
+            })
+            .context("Drift quote spot market not found")?;
+
+        let tradeable: Vec<(Pubkey, DriftSpotMarket)> = markets.into_iter()
+            .filter(|(_, market)| market.market_index != QUOTE_SPOT_MARKET_INDEX && market.orders_enabled && market.oracle_price > 0)
+            .collect();
+        let balances = self.fetch_vault_balances(&tradeable.iter().map(|(_, market)| market).collect::<Vec<_>>()).await?;
+
+        let pools: Vec<Pool> = tradeable.into_iter()
+            .zip(balances)
+            .filter_map(|((address, market), balance)| Some(Self::market_pool(address, market, &quote, balance?)))
+            .filter(|pool| pool.reserve_a > 0 && pool.reserve_b > 0)
+            .collect();
+
+        let mut cache = self.pools_cache.write().await;
+        cache.clear();
+        for pool in &pools {
+            cache.insert(pool.address.to_string(), pool.clone());
+        }
+
+        info!("Fetched {} Drift spot markets", pools.len());
+        self.console.update_status_with_info(self.get_dex_name(), "Connected", &format!("{} spot markets cached", pools.len()));
+        Ok(pools)
+    }
+
+    async fn get_pool_by_tokens(&self, token_a: &str, token_b: &str) -> Result<Option<Pool>> {
+        let cache = self.pools_cache.read().await;
+        Ok(cache.values()
+            .find(|pool| {
+                let (mint_a, mint_b) = (pool.token_a.mint.to_string(), pool.token_b.mint.to_string());
+                (mint_a == token_a && mint_b == token_b) || (mint_a == token_b && mint_b == token_a)
+            })
+            .cloned())
+    }
+
+    async fn update_pool_reserves(&self, pool: &mut Pool) -> Result<()> {
+        let market = Self::parse_spot_market(&self.rpc_client.get_account_data(&pool.address).await?)?;
+        let vault_balance = self.rpc_client.get_token_account_balance(&market.vault).await?;
+        // The quote token never changes, so it is reused from the pool
+        let updated = Self::market_pool(pool.address, market, &pool.token_b, vault_balance);
+        pool.reserve_a = updated.reserve_a;
+        pool.reserve_b = updated.reserve_b;
+        pool.fee_percent = updated.fee_percent;
+        pool.token_a.price_usd = updated.token_a.price_usd;
+        pool.liquidity_usd = updated.liquidity_usd;
+        pool.metadata = updated.metadata;
+        pool.last_updated = updated.last_updated;
+        Ok(())
+    }
+
+    fn get_dex_name(&self) -> &'static str {
+        "drift"
+    }
+
+    fn set_console_manager(&mut self, console: Arc<ConsoleManager>) {
+        self.console = console;
+    }
+
+    fn liquidity_quality_multiplier(&self) -> f64 {
+        // Fills come from the order book and JIT makers, so quoted depth may only partly fill
+        0.8
+    }
+
+    async fn reinitialize(&self) -> Result<()> {
+        self.pools_cache.write().await.clear();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spot_market_data(market_index: u16, mint: &Pubkey, vault: &Pubkey, decimals: u32, oracle_price: i64) -> Vec<u8> {
+        let mut data = vec![0u8; SPOT_MARKET_ACCOUNT_LEN];
+        data[..8].copy_from_slice(&SPOT_MARKET_DISCRIMINATOR);
+        data[MINT_OFFSET..MINT_OFFSET + 32].copy_from_slice(mint.as_ref());
+        data[VAULT_OFFSET..VAULT_OFFSET + 32].copy_from_slice(vault.as_ref());
+        data[NAME_OFFSET..NAME_OFFSET + 3].copy_from_slice(b"SOL");
+        data[NAME_OFFSET + 3..NAME_OFFSET + 32].fill(b' ');
+        data[LAST_ORACLE_PRICE_OFFSET..LAST_ORACLE_PRICE_OFFSET + 8].copy_from_slice(&oracle_price.to_le_bytes());
+        data[OPTIMAL_UTILIZATION_OFFSET..OPTIMAL_UTILIZATION_OFFSET + 4].copy_from_slice(&800_000u32.to_le_bytes());
+        data[OPTIMAL_BORROW_RATE_OFFSET..OPTIMAL_BORROW_RATE_OFFSET + 4].copy_from_slice(&100_000u32.to_le_bytes());
+        data[DECIMALS_OFFSET..DECIMALS_OFFSET + 4].copy_from_slice(&decimals.to_le_bytes());
+        data[MARKET_INDEX_OFFSET..MARKET_INDEX_OFFSET + 2].copy_from_slice(&market_index.to_le_bytes());
+        data[ORDERS_ENABLED_OFFSET] = 1;
+        data[FEE_ADJUSTMENT_OFFSET..FEE_ADJUSTMENT_OFFSET + 2].copy_from_slice(&(-50i16).to_le_bytes());
+        data
+    }
+
+    #[test]
+    fn test_spot_market_pool() {
+        let (mint, vault) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let market = DriftClient::parse_spot_market(&spot_market_data(1, &mint, &vault, 9, 150 * PRICE_PRECISION)).unwrap();
+        assert_eq!((market.mint, market.vault, market.market_index), (mint, vault, 1));
+        assert_eq!((market.name.as_str(), market.decimals), ("SOL", 9));
+        assert_eq!((market.optimal_utilization, market.optimal_borrow_rate), (800_000, 100_000));
+        // Half of the 10 bps default
+        assert_eq!(market.taker_fee_rate(), Decimal::new(5, 4));
+
+        let quote = TokenInfo { mint: Pubkey::new_unique(), symbol: "USDC".to_string(), decimals: 6, price_usd: Some(Decimal::ONE) };
+        // 1,000 SOL in the vault, 80% of it usable, at 150 USDC
+        let pool = DriftClient::market_pool(Pubkey::new_unique(), market, &quote, 1_000_000_000_000);
+        assert_eq!(pool.reserve_a, 800_000_000_000);
+        assert_eq!(pool.reserve_b, 120_000_000_000);
+        assert_eq!(pool.liquidity_usd, Decimal::from(240_000));
+
+        let mut data = spot_market_data(1, &mint, &vault, 9, 1);
+        data[0] = 0;
+        assert!(DriftClient::parse_spot_market(&data).is_err());
+        assert!(DriftClient::parse_spot_market(&data[..500]).is_err());
+    }
+}
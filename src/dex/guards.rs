@@ -0,0 +1,51 @@
+use solana_sdk::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+};
+use std::str::FromStr;
+
+/// Lighthouse assertion program; a failed assertion fails the whole transaction
+pub const GUARD_PROGRAM_ID: &str = "L2TExMFKdjpN9kozasaurPirfHy9P8sbXoAN1qA3S95";
+
+/// Compute units a token account assertion needs
+pub const GUARD_COMPUTE_UNITS: u32 = 5_000;
+
+const ASSERT_TOKEN_ACCOUNT_DISCRIMINATOR: u8 = 8;
+/// Don't log the assertion result; failures are still reported by the runtime
+const LOG_LEVEL_SILENT: u8 = 0;
+const TOKEN_ACCOUNT_AMOUNT_ASSERTION: u8 = 2;
+const GREATER_THAN_OR_EQUAL: u8 = 4;
+
+pub fn guard_program_id() -> Pubkey {
+    Pubkey::from_str(GUARD_PROGRAM_ID).expect("valid program id")
+}
+
+/// Instruction that fails the transaction unless `token_account` holds at least
+/// `expected_min_output` once every earlier instruction has run
+pub fn build_minimum_output_check_instruction(expected_min_output: u64, token_account: &Pubkey) -> Instruction {
+    let mut data = vec![ASSERT_TOKEN_ACCOUNT_DISCRIMINATOR, LOG_LEVEL_SILENT, TOKEN_ACCOUNT_AMOUNT_ASSERTION];
+    data.extend_from_slice(&expected_min_output.to_le_bytes());
+    data.push(GREATER_THAN_OR_EQUAL);
+
+    Instruction {
+        program_id: guard_program_id(),
+        accounts: vec![AccountMeta::new_readonly(*token_account, false)],
+        data,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_minimum_output_check_instruction() {
+        let token_account = Pubkey::new_unique();
+        let instruction = build_minimum_output_check_instruction(1_234, &token_account);
+
+        assert_eq!(instruction.program_id, guard_program_id());
+        assert_eq!(instruction.accounts, vec![AccountMeta::new_readonly(token_account, false)]);
+        assert_eq!(&instruction.data[3..11], &1_234u64.to_le_bytes());
+        assert_eq!(instruction.data.last(), Some(&GREATER_THAN_OR_EQUAL));
+    }
+}
@@ -0,0 +1,534 @@
+use crate::models::{Pool, TokenInfo};
+use crate::dex::DexClient;
+use crate::console::ConsoleManager;
+use crate::utils::{
+    price_feed::JupiterPriceFeed,
+    token_registry::{TokenRegistry, UNKNOWN_SYMBOL},
+};
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use base64::Engine;
+use rust_decimal::Decimal;
+use rust_decimal::prelude::FromPrimitive;
+use serde_json::{json, Value};
+use solana_sdk::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+};
+use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
+use std::sync::Arc;
+use tracing::{debug, warn};
+
+pub const JUPITER_PROGRAM_ID: &str = "JUP6LkbZbjS1jKKwapdHNy74zcZ3tLUZoi5QNyVTaV4";
+pub const JUPITER_API_BASE: &str = "https://quote-api.jup.ag/v6";
+
+/// Input size used to probe a pair when building synthetic pools
+const PROBE_AMOUNT: u64 = 1_000_000_000;
+/// Upper bound on pairs quoted per refresh, each one costs a `/quote` call
+const MAX_SYNTHETIC_POOLS: usize = 20;
+/// Floor for the reported price impact so tiny quotes don't imply infinite depth
+const MIN_PRICE_IMPACT: f64 = 0.0001;
+/// Round-trip losses beyond this are treated as a broken quote rather than a fee
+const MAX_SYNTHETIC_FEE: f64 = 0.1;
+
+/// Liquid pairs quoted when `/markets` is unavailable or yields nothing usable
+const FALLBACK_PAIRS: &[(&str, &str)] = &[
+    ("So11111111111111111111111111111111111111112", "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v"), // SOL/USDC
+    ("So11111111111111111111111111111111111111112", "Es9vMFrzaCERmJfrF4H2FYD4KCoNkY11McCe8BenwNYB"), // SOL/USDT
+    ("EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v", "Es9vMFrzaCERmJfrF4H2FYD4KCoNkY11McCe8BenwNYB"), // USDC/USDT
+    ("4k3Dyjzvzp8eMZWUXbBCjEvwSkkk59S5iCNLY3QrkX6R", "So11111111111111111111111111111111111111112"), // RAY/SOL
+    ("JUPyiwrYJFskUPiHa7hkeR8VUtAeFoSYbKedZNsDvCN", "So11111111111111111111111111111111111111112"), // JUP/SOL
+];
+
+/// A quote returned by Jupiter's `/quote` endpoint
+#[derive(Debug, Clone)]
+pub struct JupiterQuote {
+    pub input_mint: Pubkey,
+    pub output_mint: Pubkey,
+    pub in_amount: u64,
+    pub out_amount: u64,
+    pub price_impact_pct: f64,
+    /// The untouched response, which `/swap-instructions` expects back verbatim
+    pub raw: Value,
+}
+
+impl JupiterQuote {
+    pub fn from_response(raw: Value) -> Result<Self> {
+        let mint = |key: &str| -> Result<Pubkey> {
+            let value = raw.get(key).and_then(|v| v.as_str())
+                .with_context(|| format!("Jupiter quote is missing {}", key))?;
+            Ok(Pubkey::from_str(value)?)
+        };
+        let amount = |key: &str| -> Result<u64> {
+            raw.get(key).and_then(|v| v.as_str())
+                .with_context(|| format!("Jupiter quote is missing {}", key))?
+                .parse::<u64>()
+                .with_context(|| format!("Invalid {} in Jupiter quote", key))
+        };
+
+        Ok(Self {
+            input_mint: mint("inputMint")?,
+            output_mint: mint("outputMint")?,
+            in_amount: amount("inAmount")?,
+            out_amount: amount("outAmount")?,
+            price_impact_pct: raw.get("priceImpactPct")
+                .and_then(|v| v.as_str())
+                .and_then(|v| v.parse::<f64>().ok())
+                .unwrap_or(0.0),
+            raw,
+        })
+    }
+
+    /// Output per unit of input with the price impact taken back out, fees still included
+    fn marginal_rate(&self) -> f64 {
+        let impact = (self.price_impact_pct / 100.0).clamp(0.0, 0.99);
+        self.out_amount as f64 / self.in_amount.max(1) as f64 / (1.0 - impact)
+    }
+}
+
+/// Reserves and fee of a constant-product pool that reproduces `forward` (A into B)
+/// and `reverse` (B back into A).
+///
+/// Both quotes have fees netted out, so whatever the round trip loses is charged as a
+/// fee each way, and the reserves sit at the mid price. With `x` the input after the
+/// fee, the price impact is `x / (R_a + x)`, which pins down `R_a`.
+pub fn synthetic_pool_params(forward: &JupiterQuote, reverse: &JupiterQuote) -> (u64, u64, Decimal) {
+    let (forward_rate, reverse_rate) = (forward.marginal_rate(), reverse.marginal_rate());
+    let fee = (1.0 - (forward_rate * reverse_rate).sqrt()).clamp(0.0, MAX_SYNTHETIC_FEE);
+    let mid_price = forward_rate / (1.0 - fee);
+
+    let impact = (forward.price_impact_pct / 100.0).clamp(MIN_PRICE_IMPACT, 0.99);
+    let reserve_a = forward.in_amount as f64 * (1.0 - fee) * (1.0 - impact) / impact;
+    let reserve_b = reserve_a * mid_price;
+    let fee = Decimal::from_f64(fee).unwrap_or_default().round_dp(6);
+    (reserve_a as u64, reserve_b as u64, fee)
+}
+
+/// Everything `/swap-instructions` returns for one quote
+#[derive(Debug, Clone)]
+pub struct JupiterSwapInstructions {
+    /// Token account creation and the like, run before the swap
+    pub setup_instructions: Vec<Instruction>,
+    pub swap_instruction: Instruction,
+    /// Unwraps SOL after the swap when Jupiter wrapped it
+    pub cleanup_instruction: Option<Instruction>,
+    /// Tables the swap's accounts are looked up in; the transaction must be a v0 message
+    pub address_lookup_table_addresses: Vec<Pubkey>,
+}
+
+impl JupiterSwapInstructions {
+    /// Setup, swap and cleanup instructions in execution order
+    pub fn instructions(&self) -> Vec<Instruction> {
+        let mut instructions = self.setup_instructions.clone();
+        instructions.push(self.swap_instruction.clone());
+        instructions.extend(self.cleanup_instruction.clone());
+        instructions
+    }
+}
+
+/// Thin wrapper over the Jupiter V6 quote and swap REST endpoints
+pub struct JupiterApi {
+    http_client: reqwest::Client,
+    base_url: String,
+}
+
+impl JupiterApi {
+    pub fn new() -> Self {
+        Self {
+            http_client: reqwest::Client::new(),
+            base_url: JUPITER_API_BASE.to_string(),
+        }
+    }
+
+    async fn get_json(&self, path: &str, query: &[(&str, String)]) -> Result<Value> {
+        self.http_client
+            .get(format!("{}{}", self.base_url, path))
+            .query(query)
+            .header("User-Agent", "solana-arbitrage-bot/1.0")
+            .timeout(std::time::Duration::from_secs(10))
+            .send()
+            .await
+            .with_context(|| format!("Failed to reach Jupiter {}", path))?
+            .error_for_status()
+            .with_context(|| format!("Jupiter {} request failed", path))?
+            .json()
+            .await
+            .with_context(|| format!("Failed to parse Jupiter {} response", path))
+    }
+
+    /// Quote `amount` of `input_mint` into `output_mint`. Any route is allowed; the
+    /// swap is sent as a v0 message against the lookup tables Jupiter returns.
+    pub async fn quote(&self, input_mint: &Pubkey, output_mint: &Pubkey, amount: u64, slippage_bps: u16) -> Result<JupiterQuote> {
+        let response = self.get_json("/quote", &[
+            ("inputMint", input_mint.to_string()),
+            ("outputMint", output_mint.to_string()),
+            ("amount", amount.to_string()),
+            ("slippageBps", slippage_bps.to_string()),
+        ]).await?;
+
+        JupiterQuote::from_response(response)
+    }
+
+    /// Token pairs listed by `/markets`, deduplicated regardless of side
+    pub async fn market_pairs(&self) -> Result<Vec<(Pubkey, Pubkey)>> {
+        let response = self.get_json("/markets", &[]).await?;
+        Ok(Self::parse_market_pairs(&response))
+    }
+
+    fn parse_market_pairs(response: &Value) -> Vec<(Pubkey, Pubkey)> {
+        let markets = match response {
+            Value::Array(markets) => markets.as_slice(),
+            _ => response.get("data").and_then(|d| d.as_array()).map(|d| d.as_slice()).unwrap_or_default(),
+        };
+
+        let mut seen = HashSet::new();
+        markets.iter()
+            .filter_map(|market| {
+                // Market params are keyed differently per underlying AMM
+                let params = market.get("params").unwrap_or(market);
+                let mint = |keys: &[&str]| keys.iter()
+                    .find_map(|key| params.get(*key).and_then(|v| v.as_str()))
+                    .and_then(|v| Pubkey::from_str(v).ok());
+                let a = mint(&["baseMint", "mintA", "tokenMintA", "inputMint"])?;
+                let b = mint(&["quoteMint", "mintB", "tokenMintB", "outputMint"])?;
+                let key = if a < b { (a, b) } else { (b, a) };
+                (a != b && seen.insert(key)).then_some((a, b))
+            })
+            .collect()
+    }
+
+    /// Build the instructions for a previously fetched quote. With `wrap_and_unwrap_sol`
+    /// Jupiter wraps a SOL input in its setup and unwraps a SOL output in its cleanup.
+    /// Its compute budget instructions are dropped; the caller sets its own.
+    pub async fn swap_instructions(&self, quote: &JupiterQuote, user: &Pubkey, wrap_and_unwrap_sol: bool) -> Result<JupiterSwapInstructions> {
+        let response: Value = self.http_client
+            .post(format!("{}/swap-instructions", self.base_url))
+            .header("User-Agent", "solana-arbitrage-bot/1.0")
+            .timeout(std::time::Duration::from_secs(10))
+            .json(&json!({
+                "quoteResponse": quote.raw,
+                "userPublicKey": user.to_string(),
+                "wrapAndUnwrapSol": wrap_and_unwrap_sol,
+            }))
+            .send()
+            .await
+            .context("Failed to reach Jupiter /swap-instructions")?
+            .error_for_status()
+            .context("Jupiter /swap-instructions request failed")?
+            .json()
+            .await
+            .context("Failed to parse Jupiter /swap-instructions response")?;
+
+        Self::parse_swap_instructions(&response)
+    }
+
+    fn parse_swap_instructions(response: &Value) -> Result<JupiterSwapInstructions> {
+        let setup_instructions = response.get("setupInstructions")
+            .and_then(|v| v.as_array())
+            .map(|instructions| instructions.iter().map(Self::parse_instruction).collect::<Result<Vec<_>>>())
+            .transpose()?
+            .unwrap_or_default();
+        let swap_instruction = Self::parse_instruction(
+            response.get("swapInstruction").context("Jupiter response has no swapInstruction")?,
+        )?;
+        let cleanup_instruction = response.get("cleanupInstruction")
+            .filter(|v| !v.is_null())
+            .map(Self::parse_instruction)
+            .transpose()?;
+        let address_lookup_table_addresses = response.get("addressLookupTableAddresses")
+            .and_then(|v| v.as_array())
+            .map(|addresses| addresses.iter()
+                .map(|address| {
+                    let address = address.as_str().context("Jupiter lookup table address is not a string")?;
+                    Ok(Pubkey::from_str(address)?)
+                })
+                .collect::<Result<Vec<_>>>())
+            .transpose()?
+            .unwrap_or_default();
+
+        Ok(JupiterSwapInstructions {
+            setup_instructions,
+            swap_instruction,
+            cleanup_instruction,
+            address_lookup_table_addresses,
+        })
+    }
+
+    fn parse_instruction(value: &Value) -> Result<Instruction> {
+        let program_id = value.get("programId").and_then(|v| v.as_str())
+            .context("Jupiter instruction is missing programId")?;
+        let accounts = value.get("accounts").and_then(|v| v.as_array())
+            .context("Jupiter instruction is missing accounts")?
+            .iter()
+            .map(|account| {
+                let pubkey = account.get("pubkey").and_then(|v| v.as_str())
+                    .context("Jupiter account is missing pubkey")?;
+                let is_signer = account.get("isSigner").and_then(|v| v.as_bool()).unwrap_or(false);
+                let is_writable = account.get("isWritable").and_then(|v| v.as_bool()).unwrap_or(false);
+                let pubkey = Pubkey::from_str(pubkey)?;
+                Ok(if is_writable {
+                    AccountMeta::new(pubkey, is_signer)
+                } else {
+                    AccountMeta::new_readonly(pubkey, is_signer)
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let data = value.get("data").and_then(|v| v.as_str())
+            .context("Jupiter instruction is missing data")?;
+
+        Ok(Instruction {
+            program_id: Pubkey::from_str(program_id)?,
+            accounts,
+            data: base64::engine::general_purpose::STANDARD.decode(data)
+                .context("Jupiter instruction data is not valid base64")?,
+        })
+    }
+}
+
+impl Default for JupiterApi {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Treats the Jupiter aggregator as one more pool source. Each pair becomes a
+/// synthetic pool whose reserves and fee reproduce Jupiter's best quotes for that
+/// pair in both directions.
+pub struct JupiterClient {
+    api: JupiterApi,
+    program_id: Pubkey,
+    console_manager: Option<Arc<ConsoleManager>>,
+    price_feed: JupiterPriceFeed,
+    token_registry: Arc<TokenRegistry>,
+}
+
+impl JupiterClient {
+    pub fn new(console_manager: Arc<ConsoleManager>, token_registry: Arc<TokenRegistry>) -> Result<Self> {
+        Ok(Self {
+            api: JupiterApi::new(),
+            program_id: Pubkey::from_str(JUPITER_PROGRAM_ID)?,
+            console_manager: Some(console_manager),
+            price_feed: JupiterPriceFeed::new(),
+            token_registry,
+        })
+    }
+
+    /// Stable per-pair address so repeated refreshes update the same pool
+    fn synthetic_address(&self, mint_a: &Pubkey, mint_b: &Pubkey) -> Pubkey {
+        Pubkey::find_program_address(&[b"jupiter", mint_a.as_ref(), mint_b.as_ref()], &self.program_id).0
+    }
+
+    /// Registry entry for `mint`, falling back to an unknown token with the mint's own decimals
+    async fn resolve_token(&self, mint: &Pubkey) -> Result<TokenInfo> {
+        match self.token_registry.resolve(mint).await {
+            Ok(token) => Ok(token),
+            Err(e) => {
+                debug!("Failed to resolve token {}: {}", mint, e);
+                Ok(TokenInfo {
+                    mint: *mint,
+                    symbol: UNKNOWN_SYMBOL.to_string(),
+                    decimals: self.token_registry.decimals(mint).await?,
+                    price_usd: None,
+                })
+            }
+        }
+    }
+
+    /// Quote the pair both ways; the reverse quote sells what the forward one bought
+    async fn quote_both_ways(&self, mint_a: &Pubkey, mint_b: &Pubkey) -> Result<(u64, u64, Decimal)> {
+        let forward = self.api.quote(mint_a, mint_b, PROBE_AMOUNT, 50).await?;
+        let reverse = self.api.quote(mint_b, mint_a, forward.out_amount, 50).await?;
+        Ok(synthetic_pool_params(&forward, &reverse))
+    }
+
+    async fn quote_pool(&self, mint_a: &Pubkey, mint_b: &Pubkey) -> Result<Pool> {
+        let (reserve_a, reserve_b, fee_percent) = self.quote_both_ways(mint_a, mint_b).await?;
+        let token_a = self.resolve_token(mint_a).await?;
+        let token_b = self.resolve_token(mint_b).await?;
+
+        Ok(Pool {
+            address: self.synthetic_address(mint_a, mint_b),
+            dex: "jupiter".to_string(),
+            liquidity_usd: Pool::estimate_liquidity_usd(reserve_a, reserve_b, token_a.decimals, token_b.decimals, None, None),
+            token_a,
+            token_b,
+            reserve_a,
+            reserve_b,
+            fee_percent,
+            last_updated: chrono::Utc::now(),
+            metadata: None,
+        })
+    }
+
+    /// Set USD prices on `pools` and value their liquidity with them, in one price request
+    async fn price_pools(&self, pools: &mut [Pool]) {
+        let mints: Vec<Pubkey> = pools.iter()
+            .flat_map(|pool| [pool.token_a.mint, pool.token_b.mint])
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect();
+        let prices: HashMap<Pubkey, f64> = match self.price_feed.get_prices(&mints).await {
+            Ok(prices) => prices,
+            Err(e) => {
+                warn!("Failed to fetch token prices, using quote-side liquidity estimate: {}", e);
+                return;
+            }
+        };
+
+        for pool in pools {
+            let price = |mint: &Pubkey| prices.get(mint).copied().and_then(Decimal::from_f64_retain);
+            pool.token_a.price_usd = price(&pool.token_a.mint).or(pool.token_a.price_usd);
+            pool.token_b.price_usd = price(&pool.token_b.mint).or(pool.token_b.price_usd);
+            pool.refresh_liquidity_usd();
+        }
+    }
+
+    async fn pairs(&self) -> Vec<(Pubkey, Pubkey)> {
+        match self.api.market_pairs().await {
+            Ok(pairs) if !pairs.is_empty() => pairs,
+            Ok(_) => {
+                warn!("Jupiter /markets returned no usable pairs, using fallback pairs");
+                Self::fallback_pairs()
+            }
+            Err(e) => {
+                warn!("Failed to fetch Jupiter markets: {}, using fallback pairs", e);
+                Self::fallback_pairs()
+            }
+        }
+    }
+
+    fn fallback_pairs() -> Vec<(Pubkey, Pubkey)> {
+        FALLBACK_PAIRS.iter()
+            .filter_map(|(a, b)| Some((Pubkey::from_str(a).ok()?, Pubkey::from_str(b).ok()?)))
+            .collect()
+    }
+}
+
+#[async_trait]
+impl DexClient for JupiterClient {
+    async fn fetch_pools(&self) -> Result<Vec<Pool>> {
+        let mut pools = Vec::new();
+
+        for (mint_a, mint_b) in self.pairs().await.into_iter().take(MAX_SYNTHETIC_POOLS) {
+            match self.quote_pool(&mint_a, &mint_b).await {
+                Ok(pool) => pools.push(pool),
+                Err(e) => debug!("No Jupiter route for {} -> {}: {}", mint_a, mint_b, e),
+            }
+        }
+        self.price_pools(&mut pools).await;
+
+        if let Some(console) = &self.console_manager {
+            console.update_service_status("Jupiter", "Connected", &format!("{} pools", pools.len()), None);
+        }
+
+        Ok(pools)
+    }
+
+    async fn get_pool_by_tokens(&self, token_a: &str, token_b: &str) -> Result<Option<Pool>> {
+        let mint_a = Pubkey::from_str(token_a)?;
+        let mint_b = Pubkey::from_str(token_b)?;
+
+        match self.quote_pool(&mint_a, &mint_b).await {
+            Ok(mut pool) => {
+                self.price_pools(std::slice::from_mut(&mut pool)).await;
+                Ok(Some(pool))
+            }
+            Err(e) => {
+                debug!("No Jupiter route for {} -> {}: {}", token_a, token_b, e);
+                Ok(None)
+            }
+        }
+    }
+
+    async fn update_pool_reserves(&self, pool: &mut Pool) -> Result<()> {
+        let (reserve_a, reserve_b, fee_percent) = self.quote_both_ways(&pool.token_a.mint, &pool.token_b.mint).await?;
+        pool.reserve_a = reserve_a;
+        pool.reserve_b = reserve_b;
+        pool.fee_percent = fee_percent;
+        self.price_pools(std::slice::from_mut(pool)).await;
+        pool.last_updated = chrono::Utc::now();
+        Ok(())
+    }
+
+    fn get_dex_name(&self) -> &'static str {
+        "jupiter"
+    }
+
+    fn set_console_manager(&mut self, console_manager: Arc<ConsoleManager>) {
+        self.console_manager = Some(console_manager);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::math::calculate_output_amount;
+
+    #[test]
+    fn test_synthetic_pool_reproduces_both_quotes() {
+        let (mint_a, mint_b) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let (reserve_a, reserve_b, fee) = (1_000_000_000_000u64, 150_000_000_000u64, Decimal::new(3, 3));
+        // What a 0.3% constant-product pool quotes, with impact measured after the fee
+        let quote = |input: Pubkey, output: Pubkey, amount: u64, reserve_in: u64, reserve_out: u64| {
+            let out = calculate_output_amount(amount, reserve_in, reserve_out, fee).unwrap();
+            let after_fee = amount as f64 * 0.997;
+            JupiterQuote::from_response(json!({
+                "inputMint": input.to_string(),
+                "outputMint": output.to_string(),
+                "inAmount": amount.to_string(),
+                "outAmount": out.to_string(),
+                "priceImpactPct": (after_fee / (reserve_in as f64 + after_fee) * 100.0).to_string(),
+            })).unwrap()
+        };
+        let forward = quote(mint_a, mint_b, 1_000_000_000, reserve_a, reserve_b);
+        let reverse = quote(mint_b, mint_a, forward.out_amount, reserve_b, reserve_a);
+
+        let (synthetic_a, synthetic_b, synthetic_fee) = synthetic_pool_params(&forward, &reverse);
+        assert!((synthetic_fee - fee).abs() < Decimal::new(1, 5), "fee {}", synthetic_fee);
+        for (quote, reserve_in, reserve_out) in [(&forward, synthetic_a, synthetic_b), (&reverse, synthetic_b, synthetic_a)] {
+            let output = calculate_output_amount(quote.in_amount, reserve_in, reserve_out, synthetic_fee).unwrap();
+            let error = (output as f64 - quote.out_amount as f64).abs() / quote.out_amount as f64;
+            assert!(error < 0.001, "synthetic pool quoted {} instead of {}", output, quote.out_amount);
+        }
+
+        assert!(JupiterQuote::from_response(json!({ "inputMint": mint_a.to_string() })).is_err());
+    }
+
+    #[test]
+    fn test_parse_instruction_and_market_pairs() {
+        let program = Pubkey::new_unique();
+        let signer = Pubkey::new_unique();
+        let ix = JupiterApi::parse_instruction(&json!({
+            "programId": program.to_string(),
+            "accounts": [
+                { "pubkey": signer.to_string(), "isSigner": true, "isWritable": true },
+                { "pubkey": program.to_string(), "isSigner": false, "isWritable": false },
+            ],
+            "data": "AQID",
+        })).unwrap();
+        assert_eq!(ix.program_id, program);
+        assert_eq!(ix.accounts, vec![AccountMeta::new(signer, true), AccountMeta::new_readonly(program, false)]);
+        assert_eq!(ix.data, vec![1, 2, 3]);
+
+        let table = Pubkey::new_unique();
+        let instruction = json!({ "programId": program.to_string(), "accounts": [], "data": "" });
+        let swap = JupiterApi::parse_swap_instructions(&json!({
+            "setupInstructions": [instruction.clone()],
+            "swapInstruction": instruction.clone(),
+            "cleanupInstruction": null,
+            "addressLookupTableAddresses": [table.to_string()],
+        })).unwrap();
+        assert_eq!(swap.instructions().len(), 2);
+        assert!(swap.cleanup_instruction.is_none());
+        assert_eq!(swap.address_lookup_table_addresses, vec![table]);
+
+        let (a, b) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let pairs = JupiterApi::parse_market_pairs(&json!([
+            { "params": { "baseMint": a.to_string(), "quoteMint": b.to_string() } },
+            { "mintA": b.to_string(), "mintB": a.to_string() },
+            { "params": {} },
+        ]));
+        assert_eq!(pairs, vec![(a, b)]);
+    }
+}
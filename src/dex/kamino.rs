@@ -0,0 +1,324 @@
+use crate::{
+    dex::{
+        orca::{OrcaClient, OrcaPoolMeta, WHIRLPOOL_PROGRAM_ID},
+        DexClient,
+    },
+    models::Pool,
+    utils::{rpc::RpcClient, token_registry::TokenRegistry},
+};
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use solana_client::rpc_filter::{Memcmp, RpcFilterType};
+use solana_sdk::{account::Account, pubkey::Pubkey};
+use std::{
+    collections::{hash_map::Entry, HashMap, HashSet},
+    str::FromStr,
+    sync::Arc,
+};
+use tracing::{debug, error, info};
+
+use crate::console::ConsoleManager;
+
+pub const KAMINO_PROGRAM_ID: &str = "6LtLpnUFNByNXLyCoK9wA2MykKAmQNZKBdY8s47dehDc";
+
+/// Anchor discriminator of the Kamino `WhirlpoolStrategy` account
+const WHIRLPOOL_STRATEGY_DISCRIMINATOR: [u8; 8] = [190, 178, 231, 184, 49, 186, 103, 13];
+
+// WhirlpoolStrategy layout: admin, global config, base vault authority and bump,
+// then the pool, its vaults and tick arrays, and the strategy's position
+const STRATEGY_POOL_OFFSET: usize = 112;
+const STRATEGY_POSITION_OFFSET: usize = 272;
+const STRATEGY_SHARES_ISSUED_OFFSET: usize = 800;
+const STRATEGY_MIN_LEN: usize = 808;
+
+/// Orca `Position` layout: the whirlpool and position mint precede the liquidity
+const POSITION_LIQUIDITY_OFFSET: usize = 72;
+
+/// `getMultipleAccounts` accepts at most this many addresses per call
+const MAX_ACCOUNTS_PER_BATCH: usize = 100;
+
+/// A Kamino strategy managing one concentrated liquidity position on a Whirlpool
+#[derive(Debug, Clone)]
+pub struct KaminoStrategy {
+    pub pool: Pubkey,
+    pub position: Pubkey,
+    pub shares_issued: u64,
+}
+
+impl KaminoStrategy {
+    pub fn from_account_data(data: &[u8]) -> Result<Self> {
+        if data.len() < STRATEGY_MIN_LEN || data[..8] != WHIRLPOOL_STRATEGY_DISCRIMINATOR {
+            anyhow::bail!("Not a Kamino strategy account");
+        }
+
+        let pubkey = |offset: usize| Pubkey::try_from(&data[offset..offset + 32]);
+        Ok(Self {
+            pool: pubkey(STRATEGY_POOL_OFFSET)?,
+            position: pubkey(STRATEGY_POSITION_OFFSET)?,
+            shares_issued: u64::from_le_bytes(
+                data[STRATEGY_SHARES_ISSUED_OFFSET..STRATEGY_SHARES_ISSUED_OFFSET + 8].try_into()?,
+            ),
+        })
+    }
+
+    /// The strategy's slice of the whirlpool's reserves: `strategy_shares` out of
+    /// `total_shares` of its liquidity, capped at the whole pool
+    pub fn effective_reserves(whirlpool: &Pool, strategy_shares: u64, total_shares: u64) -> (u64, u64) {
+        if total_shares == 0 {
+            return (0, 0);
+        }
+        let shares = strategy_shares.min(total_shares) as u128;
+        let scale = |reserve: u64| (reserve as u128 * shares / total_shares as u128) as u64;
+        (scale(whirlpool.reserve_a), scale(whirlpool.reserve_b))
+    }
+}
+
+/// Liquidity of an Orca `Position` account
+fn position_liquidity(data: &[u8]) -> Option<u128> {
+    data.get(POSITION_LIQUIDITY_OFFSET..POSITION_LIQUIDITY_OFFSET + 16)
+        .map(|bytes| u128::from_le_bytes(bytes.try_into().unwrap()))
+}
+
+pub struct KaminoClient {
+    rpc_client: Arc<RpcClient>,
+    pools_cache: tokio::sync::RwLock<HashMap<String, Pool>>,
+    console: Arc<ConsoleManager>,
+    /// Parses the whirlpools that strategies provide liquidity to
+    orca: OrcaClient,
+}
+
+impl KaminoClient {
+    pub fn new(
+        rpc_client: Arc<RpcClient>,
+        console: Arc<ConsoleManager>,
+        token_registry: Arc<TokenRegistry>,
+    ) -> Result<Self> {
+        Ok(Self {
+            orca: OrcaClient::new(rpc_client.clone(), console.clone(), token_registry)?,
+            rpc_client,
+            pools_cache: tokio::sync::RwLock::new(HashMap::new()),
+            console,
+        })
+    }
+
+    async fn fetch_kamino_strategies(&self) -> Result<Vec<Pool>> {
+        let program_id = Pubkey::from_str(KAMINO_PROGRAM_ID)
+            .context("Invalid Kamino program ID")?;
+        let accounts = self.rpc_client.get_program_accounts_with_filters(&program_id, &[
+            RpcFilterType::Memcmp(Memcmp::new_raw_bytes(0, WHIRLPOOL_STRATEGY_DISCRIMINATOR.to_vec())),
+        ]).await.context("Failed to fetch Kamino strategies")?;
+
+        let strategies: Vec<(Pubkey, KaminoStrategy)> = accounts.into_iter()
+            .filter_map(|(address, account)| match KaminoStrategy::from_account_data(&account.data) {
+                Ok(strategy) if strategy.shares_issued > 0 => Some((address, strategy)),
+                Ok(_) => None,
+                Err(e) => {
+                    debug!("Skipping Kamino account {}: {}", address, e);
+                    None
+                }
+            })
+            .collect();
+
+        // Strategies share whirlpools, so each one is read once
+        let mut addresses: Vec<Pubkey> = strategies.iter()
+            .flat_map(|(_, strategy)| [strategy.pool, strategy.position])
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect();
+        addresses.sort_unstable();
+        let mut accounts = HashMap::new();
+        for chunk in addresses.chunks(MAX_ACCOUNTS_PER_BATCH) {
+            let fetched = self.rpc_client.get_multiple_accounts(chunk).await?;
+            accounts.extend(chunk.iter().copied().zip(fetched).filter_map(|(address, account)| Some((address, account?))));
+        }
+
+        let mut whirlpools = HashMap::new();
+        let mut pools = Vec::new();
+        for (address, strategy) in strategies {
+            match self.strategy_pool(address, &strategy, &accounts, &mut whirlpools).await {
+                Ok(pool) => pools.push(pool),
+                Err(e) => debug!("Skipping Kamino strategy {}: {}", address, e),
+            }
+        }
+
+        info!("Fetched {} Kamino strategies from blockchain", pools.len());
+        Ok(pools)
+    }
+
+    /// Pool for one strategy, sized to its share of the whirlpool's liquidity. Parsed
+    /// whirlpools are kept in `whirlpools` for the other strategies on the same pool.
+    async fn strategy_pool(
+        &self,
+        address: Pubkey,
+        strategy: &KaminoStrategy,
+        accounts: &HashMap<Pubkey, Account>,
+        whirlpools: &mut HashMap<Pubkey, Pool>,
+    ) -> Result<Pool> {
+        if let Entry::Vacant(entry) = whirlpools.entry(strategy.pool) {
+            // Kamino also runs Raydium and Meteora strategies; only Orca pools are understood here
+            let account = accounts.get(&strategy.pool).context("Whirlpool account not found")?;
+            anyhow::ensure!(
+                account.owner == Pubkey::from_str(WHIRLPOOL_PROGRAM_ID)?,
+                "Pool {} is not a whirlpool", strategy.pool
+            );
+            let whirlpool = self.orca.parse_whirlpool_data(&strategy.pool, &account.data).await?;
+            entry.insert(whirlpool);
+        }
+        let whirlpool = &whirlpools[&strategy.pool];
+
+        let position_liquidity = accounts.get(&strategy.position)
+            .and_then(|account| position_liquidity(&account.data))
+            .context("Position account not found")?;
+        let pool_liquidity = whirlpool.get_metadata::<OrcaPoolMeta>()
+            .map(|meta| meta.liquidity)
+            .context("Whirlpool liquidity unknown")?;
+        let clamp = |liquidity: u128| u64::try_from(liquidity).unwrap_or(u64::MAX);
+        let (reserve_a, reserve_b) = KaminoStrategy::effective_reserves(
+            whirlpool,
+            clamp(position_liquidity),
+            clamp(pool_liquidity),
+        );
+        anyhow::ensure!(reserve_a > 0 && reserve_b > 0, "Position is out of range");
+
+        Ok(Pool {
+            address,
+            dex: "kamino".to_string(),
+            token_a: whirlpool.token_a.clone(),
+            token_b: whirlpool.token_b.clone(),
+            reserve_a,
+            reserve_b,
+            fee_percent: whirlpool.fee_percent,
+            liquidity_usd: whirlpool.liquidity_usd,
+            last_updated: chrono::Utc::now(),
+            metadata: Some(Arc::new(strategy.clone())),
+        })
+    }
+}
+
+#[async_trait]
+impl DexClient for KaminoClient {
+    async fn fetch_pools(&self) -> Result<Vec<Pool>> {
+        info!("Fetching Kamino strategies...");
+        self.console.update_status(self.get_dex_name(), "Connecting to RPC");
+
+        match self.fetch_kamino_strategies().await {
+            Ok(pools) => {
+                let mut cache = self.pools_cache.write().await;
+                cache.clear();
+                for pool in &pools {
+                    cache.insert(pool.address.to_string(), pool.clone());
+                }
+
+                info!("Successfully fetched {} Kamino strategies", pools.len());
+                self.console.update_status_with_info(
+                    self.get_dex_name(),
+                    "Connected",
+                    &format!("{} strategies cached", pools.len())
+                );
+                Ok(pools)
+            }
+            Err(e) => {
+                error!("Failed to fetch Kamino strategies: {}", e);
+                self.console.update_status_with_info(self.get_dex_name(), "Error", "0 strategies");
+                Err(e)
+            }
+        }
+    }
+
+    async fn get_pool_by_tokens(&self, token_a: &str, token_b: &str) -> Result<Option<Pool>> {
+        let cache = self.pools_cache.read().await;
+
+        for pool in cache.values() {
+            let pool_token_a = pool.token_a.mint.to_string();
+            let pool_token_b = pool.token_b.mint.to_string();
+
+            if (pool_token_a == token_a && pool_token_b == token_b) ||
+               (pool_token_a == token_b && pool_token_b == token_a) {
+                return Ok(Some(pool.clone()));
+            }
+        }
+
+        Ok(None)
+    }
+
+    async fn update_pool_reserves(&self, pool: &mut Pool) -> Result<()> {
+        let strategy = match pool.get_metadata::<KaminoStrategy>() {
+            Some(strategy) => strategy.clone(),
+            None => KaminoStrategy::from_account_data(&self.rpc_client.get_account_data(&pool.address).await?)?,
+        };
+
+        let addresses = [strategy.pool, strategy.position];
+        let accounts: HashMap<Pubkey, Account> = addresses.iter().copied()
+            .zip(self.rpc_client.get_multiple_accounts(&addresses).await?)
+            .filter_map(|(address, account)| Some((address, account?)))
+            .collect();
+        let updated = self.strategy_pool(pool.address, &strategy, &accounts, &mut HashMap::new()).await?;
+
+        pool.reserve_a = updated.reserve_a;
+        pool.reserve_b = updated.reserve_b;
+        pool.fee_percent = updated.fee_percent;
+        pool.metadata = updated.metadata;
+        pool.last_updated = chrono::Utc::now();
+        Ok(())
+    }
+
+    fn get_dex_name(&self) -> &'static str {
+        "kamino"
+    }
+
+    fn set_console_manager(&mut self, console: Arc<ConsoleManager>) {
+        self.console = console;
+    }
+
+    async fn reinitialize(&self) -> Result<()> {
+        self.pools_cache.write().await.clear();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::TokenInfo;
+    use rust_decimal::Decimal;
+
+    #[test]
+    fn test_parse_strategy_account() {
+        let (pool, position) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let mut data = vec![0u8; 1024];
+        data[..8].copy_from_slice(&WHIRLPOOL_STRATEGY_DISCRIMINATOR);
+        data[STRATEGY_POOL_OFFSET..STRATEGY_POOL_OFFSET + 32].copy_from_slice(pool.as_ref());
+        data[STRATEGY_POSITION_OFFSET..STRATEGY_POSITION_OFFSET + 32].copy_from_slice(position.as_ref());
+        data[STRATEGY_SHARES_ISSUED_OFFSET..STRATEGY_SHARES_ISSUED_OFFSET + 8].copy_from_slice(&42u64.to_le_bytes());
+
+        let strategy = KaminoStrategy::from_account_data(&data).unwrap();
+        assert_eq!((strategy.pool, strategy.position, strategy.shares_issued), (pool, position, 42));
+        assert!(KaminoStrategy::from_account_data(&data[..STRATEGY_MIN_LEN - 1]).is_err());
+    }
+
+    #[test]
+    fn test_effective_reserves() {
+        let token = |symbol: &str| TokenInfo {
+            mint: Pubkey::new_unique(),
+            symbol: symbol.to_string(),
+            decimals: 6,
+            price_usd: None,
+        };
+        let whirlpool = Pool {
+            address: Pubkey::new_unique(),
+            dex: "orca".to_string(),
+            token_a: token("SOL"),
+            token_b: token("USDC"),
+            reserve_a: 1_000_000,
+            reserve_b: 4_000_000,
+            fee_percent: Decimal::new(3, 3),
+            liquidity_usd: Decimal::ZERO,
+            last_updated: chrono::Utc::now(),
+            metadata: None,
+        };
+
+        assert_eq!(KaminoStrategy::effective_reserves(&whirlpool, 25, 100), (250_000, 1_000_000));
+        assert_eq!(KaminoStrategy::effective_reserves(&whirlpool, 200, 100), (1_000_000, 4_000_000));
+        assert_eq!(KaminoStrategy::effective_reserves(&whirlpool, 25, 0), (0, 0));
+    }
+}
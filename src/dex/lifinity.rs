@@ -2,10 +2,10 @@ use crate::models::{Pool, TokenInfo};
 use crate::dex::DexClient;
 use crate::console::ConsoleManager;
 
-use crate::utils::rpc::RpcClient;
+use crate::utils::{price_feed::PythPrice, rpc::RpcClient};
 use solana_sdk::pubkey::Pubkey;
 use std::str::FromStr;
-use anyhow::Result;
+use anyhow::{Context, Result};
 use async_trait::async_trait;
 use std::sync::Arc;
 // use std::collections::HashMap; // Unused
@@ -13,10 +13,14 @@ use std::sync::Arc;
 // use tracing::{info, error, warn}; // Unused
 use chrono;
 use rust_decimal::Decimal;
-use rust_decimal::prelude::FromPrimitive;
+use rust_decimal::prelude::{FromPrimitive, ToPrimitive};
+use tracing::debug;
 
 pub const LIFINITY_PROGRAM_ID: &str = "EewxydAPCCVuNEyrVN68PuSYdQ7wKn27V9Gjeoi8dy3S";
 
+/// Oracle prices whose confidence interval is wider than this fraction of the price are unreliable
+const MAX_ORACLE_CONFIDENCE_RATIO: f64 = 0.02;
+
 // Lifinity pool discriminator
 
 
@@ -64,46 +68,69 @@ impl LifinityDex {
 
     pub async fn fetch_pools(&self) -> Result<Vec<Pool>, anyhow::Error> {
         let accounts = self.client.get_program_accounts(&self.program_id).await?;
+        let parsed: Vec<_> = accounts.into_iter()
+            .filter(|(_, account)| account.data.len() >= 8 && self.is_lifinity_pool_account(&account.data))
+            .filter_map(|(pubkey, account)| Some((pubkey, self.parse_lifinity_pool_data(&account.data).ok()?)))
+            .collect();
+
+        // Get every vault balance in one call
+        let vaults: Vec<Pubkey> = parsed.iter()
+            .flat_map(|(_, pool_data)| [pool_data.token_a_vault, pool_data.token_b_vault])
+            .collect();
+        let balances = self.client.get_multiple_token_account_balances(&vaults).await.unwrap_or_default();
+
         let mut pools = Vec::new();
-        
-        for (pubkey, account) in accounts {
-            if account.data.len() >= 8 && self.is_lifinity_pool_account(&account.data) {
-                if let Ok(pool_data) = self.parse_lifinity_pool_data(&account.data) {
-                    // Get vault balances
-                    let reserve_a = self.get_token_account_balance(&pool_data.token_a_vault).await.unwrap_or(0.0);
-                    let reserve_b = self.get_token_account_balance(&pool_data.token_b_vault).await.unwrap_or(0.0);
-                    
-                    // Get oracle prices for better pricing
-                    let _oracle_price_a = self.get_oracle_price(&pool_data.oracle_a).await.unwrap_or(1.0);
-                    let _oracle_price_b = self.get_oracle_price(&pool_data.oracle_b).await.unwrap_or(1.0);
-                    
-                    let fee_rate = pool_data.fee_rate as f64 / 10000.0;
-                    
-                    let pool = Pool {
-                        address: pubkey,
-                        dex: "Lifinity".to_string(),
-                        token_a: TokenInfo {
-                            mint: pool_data.token_a_mint,
-                            symbol: self.get_token_symbol(&pool_data.token_a_mint),
-                            decimals: 6,
-                            price_usd: None,
-                        },
-                        token_b: TokenInfo {
-                            mint: pool_data.token_b_mint,
-                            symbol: self.get_token_symbol(&pool_data.token_b_mint),
-                            decimals: 6,
-                            price_usd: None,
-                        },
-                        reserve_a: reserve_a as u64,
-                        reserve_b: reserve_b as u64,
-                        fee_percent: Decimal::from_f64(fee_rate).unwrap_or_default(),
-                        liquidity_usd: Decimal::from((reserve_a + reserve_b) as u64),
-                        last_updated: chrono::Utc::now(),
-                    };
-                    
-                    pools.push(pool);
+        for (index, (pubkey, pool_data)) in parsed.into_iter().enumerate() {
+            let reserve_a = Self::ui_amount(balances.get(2 * index).copied().flatten());
+            let reserve_b = Self::ui_amount(balances.get(2 * index + 1).copied().flatten());
+            
+            // Lifinity quotes around the oracle price, so price the pool the way it will trade
+            let reserve_b = match (
+                self.get_oracle_price(&pool_data.oracle_a).await,
+                self.get_oracle_price(&pool_data.oracle_b).await,
+            ) {
+                (Ok(oracle_price_a), Ok(oracle_price_b)) if reserve_a > 0.0 && reserve_b > 0.0 => {
+                    let proactive_price = self.calculate_proactive_price(
+                        oracle_price_a / oracle_price_b,
+                        reserve_b / reserve_a,
+                        chrono::Utc::now().timestamp() - pool_data.last_rebalance_time,
+                        pool_data.rebalance_threshold as f64 / 10000.0,
+                    );
+                    reserve_a * proactive_price
                 }
-            }
+                (Err(e), _) | (_, Err(e)) => {
+                    debug!("Using vault reserves for Lifinity pool {}: {}", pubkey, e);
+                    reserve_b
+                }
+                _ => reserve_b,
+            };
+            
+            let fee_rate = pool_data.fee_rate as f64 / 10000.0;
+            
+            let pool = Pool {
+                address: pubkey,
+                dex: "Lifinity".to_string(),
+                token_a: TokenInfo {
+                    mint: pool_data.token_a_mint,
+                    symbol: self.get_token_symbol(&pool_data.token_a_mint),
+                    decimals: 6,
+                    price_usd: None,
+                },
+                token_b: TokenInfo {
+                    mint: pool_data.token_b_mint,
+                    symbol: self.get_token_symbol(&pool_data.token_b_mint),
+                    decimals: 6,
+                    price_usd: None,
+                },
+                reserve_a: reserve_a as u64,
+                reserve_b: reserve_b as u64,
+                fee_percent: Decimal::from_f64(fee_rate).unwrap_or_default(),
+                liquidity_usd: Decimal::from((reserve_a + reserve_b) as u64),
+                last_updated: chrono::Utc::now(),
+                metadata: None,
+            };
+            
+            pools.push(pool);
         }
         
         Ok(pools)
@@ -199,35 +226,30 @@ impl LifinityDex {
     }
 
     async fn get_oracle_price(&self, oracle_pubkey: &Pubkey) -> Result<f64, anyhow::Error> {
-        // Simplified oracle price fetching
-        // In practice, this would parse Pyth, Switchboard, or other oracle data
-        match self.client.try_get_account(oracle_pubkey).await {
-            Ok(Some(account)) => {
-                if account.data.len() >= 8 {
-                    // Mock oracle price parsing
-                    let price_bytes = &account.data[8..16];
-                    let price = f64::from_le_bytes([
-                        price_bytes[0], price_bytes[1], price_bytes[2], price_bytes[3],
-                        price_bytes[4], price_bytes[5], price_bytes[6], price_bytes[7],
-                    ]);
-                    Ok(price.abs()) // Ensure positive price
-                } else {
-                    Ok(1.0) // Default price
-                }
-            }
-            Ok(None) | Err(_) => Ok(1.0), // Default price if oracle not accessible or account not found
-        }
+        self.get_oracle_price_from_pyth(oracle_pubkey).await
     }
 
-    async fn get_token_account_balance(&self, vault_pubkey: &Pubkey) -> Result<f64, anyhow::Error> {
-        match self.client.try_get_token_account_balance(vault_pubkey).await {
-            Ok(Some(balance)) => {
-                let amount = balance as f64 / 1e6; // Convert from raw amount to UI amount
-                Ok(amount)
-            }
-            Ok(None) => Ok(0.0), // Account not found or invalid
-            Err(_) => Ok(0.0), // Other errors
+    /// USD price from a Pyth price account, rejected when its confidence interval is over 2%
+    pub async fn get_oracle_price_from_pyth(&self, oracle_pubkey: &Pubkey) -> Result<f64> {
+        let account = self.client.try_get_account(oracle_pubkey).await?
+            .with_context(|| format!("Oracle account {} not found", oracle_pubkey))?;
+        let pyth_price = PythPrice::parse(&account.data)?;
+        let price = pyth_price.to_decimal()?.to_f64()
+            .context("Pyth price out of range")?;
+
+        let confidence_ratio = pyth_price.conf as f64 / pyth_price.price.unsigned_abs() as f64;
+        if confidence_ratio > MAX_ORACLE_CONFIDENCE_RATIO {
+            anyhow::bail!(
+                "Oracle {} price is unreliable: confidence interval is {:.2}% of the price",
+                oracle_pubkey, confidence_ratio * 100.0
+            );
         }
+        Ok(price)
+    }
+
+    /// Vault balance in UI units; missing or invalid vaults count as empty
+    fn ui_amount(balance: Option<u64>) -> f64 {
+        balance.map_or(0.0, |balance| balance as f64 / 1e6) // Convert from raw amount to UI amount
     }
 
     fn get_token_symbol(&self, mint: &Pubkey) -> String {
@@ -287,8 +309,12 @@ impl DexClient for LifinityDex {
         // Fetch updated pool data
         if let Ok(Some(account)) = self.client.try_get_account(&pool.address).await {
             if let Ok(pool_data) = self.parse_lifinity_pool_data(&account.data) {
-                let reserve_a = self.get_token_account_balance(&pool_data.token_a_vault).await.unwrap_or(0.0);
-                let reserve_b = self.get_token_account_balance(&pool_data.token_b_vault).await.unwrap_or(0.0);
+                let balances = self.client
+                    .get_multiple_token_account_balances(&[pool_data.token_a_vault, pool_data.token_b_vault])
+                    .await
+                    .unwrap_or_default();
+                let reserve_a = Self::ui_amount(balances.first().copied().flatten());
+                let reserve_b = Self::ui_amount(balances.get(1).copied().flatten());
                 
                 pool.reserve_a = reserve_a as u64;
                 pool.reserve_b = reserve_b as u64;
@@ -1,205 +1,588 @@
 use crate::models::{Pool, TokenInfo};
+use crate::utils::{
+    math::{calculate_dlmm_output, Bin},
+    token_registry::{TokenRegistry, UNKNOWN_SYMBOL},
+};
 use solana_client::rpc_client::RpcClient;
-use solana_sdk::pubkey::Pubkey;
+use solana_client::rpc_filter::{Memcmp, RpcFilterType};
+use solana_sdk::{account::Account, pubkey::Pubkey};
+use std::collections::{HashMap, HashSet};
 use std::str::FromStr;
 // use anchor_lang::prelude::*; // Unused
 use async_trait::async_trait;
 use std::sync::Arc;
 use rust_decimal::Decimal;
-use rust_decimal::prelude::FromPrimitive;
+use rust_decimal::prelude::ToPrimitive;
 use crate::dex::DexClient;
 use crate::console::ConsoleManager;
-use anyhow::Result;
+use crate::utils::rpc::MintInfo;
+use anyhow::{Context, Result};
+use tracing::{debug, info, warn};
 
 pub const METEORA_DLMM_PROGRAM_ID: &str = "LBUZKhRxPF3XUpBCjp4YzTKgLccjZhTSDM9YuVaPwxo";
 pub const METEORA_DAMM_PROGRAM_ID: &str = "Eo7WjKq67rjJQSZxS6z3YkapzY3eMj6Xy8X5EQVn5UaB";
+/// Dynamic vault program that holds DAMM pool liquidity
+pub const METEORA_VAULT_PROGRAM_ID: &str = "24Uqj9JCLxUeoC3hGfh5W3s9FM9uCHDS2SG3LYwBpyTi";
 
-// Meteora DLMM (Dynamic Liquidity Market Maker) account discriminator
-const DLMM_POOL_DISCRIMINATOR: [u8; 8] = [247, 237, 227, 245, 215, 195, 222, 70];
+/// Anchor discriminator of the DLMM `LbPair` account
+const LB_PAIR_DISCRIMINATOR: [u8; 8] = [33, 11, 49, 98, 181, 101, 177, 13];
+/// Anchor discriminator of the DLMM `BinArray` account
+const BIN_ARRAY_DISCRIMINATOR: [u8; 8] = [92, 142, 92, 220, 5, 148, 70, 181];
 
-#[derive(Debug)]
-pub struct MeteoraPool {
+// LbPair layout: static and variable fee parameters (32 bytes each), seeds and
+// pair type, active bin, bin step, status and activation flags, then mints and vaults
+const BASE_FACTOR_OFFSET: usize = 8;
+const ACTIVE_ID_OFFSET: usize = 76;
+const BIN_STEP_OFFSET: usize = 80;
+const TOKEN_X_MINT_OFFSET: usize = 88;
+const TOKEN_Y_MINT_OFFSET: usize = 120;
+const RESERVE_X_OFFSET: usize = 152;
+const RESERVE_Y_OFFSET: usize = 184;
+const LB_PAIR_MIN_LEN: usize = 216;
+
+// BinArray layout: index, version and padding, the pair, then fixed-size bins
+// whose first fields are the X and Y amounts
+const BIN_ARRAY_INDEX_OFFSET: usize = 8;
+const BINS_OFFSET: usize = 56;
+const BIN_LEN: usize = 144;
+/// Bins covered by one bin-array account
+const BINS_PER_ARRAY: i32 = 70;
+/// Bin arrays fetched on each side of the active one
+const BIN_ARRAYS_PER_SIDE: i32 = 1;
+
+/// Anchor discriminator of the DAMM `Pool` account
+const DAMM_POOL_DISCRIMINATOR: [u8; 8] = [241, 154, 109, 4, 17, 177, 109, 188];
+
+// DAMM Pool layout: LP mint, token mints, the dynamic vaults and the pool's LP token
+// account in each, the LP bump and enabled flag, protocol fee accounts, the fee update
+// time and padding, then the trade fee fraction
+const DAMM_TOKEN_A_MINT_OFFSET: usize = 40;
+const DAMM_TOKEN_B_MINT_OFFSET: usize = 72;
+const DAMM_A_VAULT_OFFSET: usize = 104;
+const DAMM_B_VAULT_OFFSET: usize = 136;
+const DAMM_A_VAULT_LP_OFFSET: usize = 168;
+const DAMM_B_VAULT_LP_OFFSET: usize = 200;
+const DAMM_ENABLED_OFFSET: usize = 233;
+const DAMM_TRADE_FEE_NUMERATOR_OFFSET: usize = 330;
+const DAMM_TRADE_FEE_DENOMINATOR_OFFSET: usize = 338;
+const DAMM_POOL_MIN_LEN: usize = 346;
+
+/// Anchor discriminator of the dynamic vault `Vault` account
+const VAULT_DISCRIMINATOR: [u8; 8] = [211, 8, 232, 43, 2, 152, 117, 119];
+
+// Vault layout: enabled flag and bumps, total amount, token and fee vaults, mints,
+// 30 strategies, base, admin and operator keys, then the locked profit tracker
+const VAULT_TOTAL_AMOUNT_OFFSET: usize = 11;
+const VAULT_LP_MINT_OFFSET: usize = 115;
+const VAULT_LOCKED_PROFIT_OFFSET: usize = 1203;
+const VAULT_LAST_REPORT_OFFSET: usize = 1211;
+const VAULT_DEGRADATION_OFFSET: usize = 1219;
+const VAULT_MIN_LEN: usize = 1227;
+/// `locked_profit_degradation` is the fraction of the locked profit released per
+/// second, in units of this
+const LOCKED_PROFIT_DEGRADATION_DENOMINATOR: u128 = 1_000_000_000_000;
+
+/// SPL token account layout: amount follows the mint and owner
+const TOKEN_AMOUNT_OFFSET: usize = 64;
+const TOKEN_ACCOUNT_LEN: usize = 165;
+/// `getMultipleAccounts` accepts at most this many addresses per call
+const MAX_ACCOUNTS_PER_BATCH: usize = 100;
+
+/// Fields of a DLMM `LbPair` account
+#[derive(Debug, Clone)]
+pub struct DlmmLbPair {
     pub token_x_mint: Pubkey,
     pub token_y_mint: Pubkey,
+    /// Token vaults holding the pair's liquidity
+    pub reserve_x: Pubkey,
+    pub reserve_y: Pubkey,
+    pub active_id: i32,
     pub bin_step: u16,
-    pub base_fee_percentage: u16,
-    pub protocol_fee_percentage: u16,
-    pub liquidity: u128,
-    pub reward_infos: Vec<RewardInfo>,
+    pub base_factor: u16,
 }
 
-#[derive(Debug)]
-pub struct RewardInfo {
-    pub mint: Pubkey,
-    pub vault: Pubkey,
-    pub funder: Pubkey,
-    pub reward_duration: u64,
-    pub reward_duration_end: u64,
-    pub reward_rate: u128,
-    pub last_update_time: u64,
+impl DlmmLbPair {
+    /// Base fee in basis points: `base_factor * bin_step * 10` in billionths. The
+    /// volatility-driven variable fee comes on top and isn't modelled.
+    pub fn fee_bps(&self) -> Decimal {
+        Decimal::from(self.base_factor as u32 * self.bin_step as u32) / Decimal::from(10_000)
+    }
+}
+
+/// Fields of a DAMM `Pool` account
+#[derive(Debug, Clone)]
+pub struct DammPool {
+    pub token_a_mint: Pubkey,
+    pub token_b_mint: Pubkey,
+    /// Dynamic vaults holding the pool's liquidity, shared with other pools and lenders
+    pub a_vault: Pubkey,
+    pub b_vault: Pubkey,
+    /// The pool's LP token accounts of each vault; their share of the vault's LP
+    /// supply is the pool's share of the vault
+    pub a_vault_lp: Pubkey,
+    pub b_vault_lp: Pubkey,
+    pub enabled: bool,
+    pub trade_fee_numerator: u64,
+    pub trade_fee_denominator: u64,
+}
+
+impl DammPool {
+    /// Trade fee as a fraction of the input; the protocol's cut is paid out of it
+    pub fn fee_percent(&self) -> Decimal {
+        if self.trade_fee_denominator == 0 {
+            return Decimal::ZERO;
+        }
+        Decimal::from(self.trade_fee_numerator) / Decimal::from(self.trade_fee_denominator)
+    }
+}
+
+/// Fields of a dynamic vault `Vault` account
+#[derive(Debug, Clone)]
+pub struct DynamicVault {
+    /// Tokens held by the vault and lent out through its strategies
+    pub total_amount: u64,
+    pub lp_mint: Pubkey,
+    /// Profit from the last strategy report, released gradually after it
+    pub last_updated_locked_profit: u64,
+    pub last_report: u64,
+    pub locked_profit_degradation: u64,
+}
+
+impl DynamicVault {
+    /// Tokens the vault can pay out at unix time `now`, excluding profit still locked
+    pub fn unlocked_amount(&self, now: i64) -> u64 {
+        let elapsed = u128::try_from(now).unwrap_or(0).saturating_sub(self.last_report as u128);
+        let released = elapsed.saturating_mul(self.locked_profit_degradation as u128);
+        let locked = if released >= LOCKED_PROFIT_DEGRADATION_DENOMINATOR {
+            0
+        } else {
+            self.last_updated_locked_profit as u128 * (LOCKED_PROFIT_DEGRADATION_DENOMINATOR - released)
+                / LOCKED_PROFIT_DEGRADATION_DENOMINATOR
+        };
+        self.total_amount.saturating_sub(locked as u64)
+    }
+
+    /// Tokens claimed at `now` by `lp_amount` of the vault's `lp_supply` LP tokens
+    pub fn share(&self, lp_amount: u64, lp_supply: u64, now: i64) -> Option<u64> {
+        if lp_supply == 0 {
+            return None;
+        }
+        u64::try_from(self.unlocked_amount(now) as u128 * lp_amount as u128 / lp_supply as u128).ok()
+    }
+}
+
+/// Vaults of a DAMM pool and its LP token accounts in them, for refreshing its reserves
+#[derive(Debug, Clone)]
+pub struct MeteoraDammPoolMeta {
+    pub a_vault: Pubkey,
+    pub b_vault: Pubkey,
+    pub a_vault_lp: Pubkey,
+    pub b_vault_lp: Pubkey,
+}
+
+/// State needed to quote a swap through a DLMM pair's bins
+#[derive(Debug, Clone)]
+pub struct MeteoraDlmmPoolMeta {
+    pub reserve_x: Pubkey,
+    pub reserve_y: Pubkey,
+    pub active_id: i32,
+    pub bin_step: u16,
+    /// Bins with liquidity around the active one, empty if they couldn't be fetched
+    pub bins: Vec<Bin>,
+}
+
+impl MeteoraDlmmPoolMeta {
+    /// Output of swapping `input_amount` through the pair's bins, token X into Y when
+    /// `a_to_b`. `fee_percent` is the pool fee as a fraction, taken from the input.
+    pub fn swap_output(&self, input_amount: u64, a_to_b: bool, fee_percent: Decimal) -> u64 {
+        let after_fee = (Decimal::from(input_amount) * (Decimal::ONE - fee_percent)).floor().to_u64().unwrap_or(0);
+        calculate_dlmm_output(self.bin_step, self.active_id, &self.bins, after_fee, a_to_b)
+    }
 }
 
 pub struct MeteoraDex {
     pub client: RpcClient,
     pub dlmm_program_id: Pubkey,
     pub damm_program_id: Pubkey,
+    pub vault_program_id: Pubkey,
+    rpc_client: Arc<crate::utils::rpc::RpcClient>,
+    token_registry: Arc<TokenRegistry>,
     console_manager: Option<Arc<ConsoleManager>>,
 }
 
 impl MeteoraDex {
-    pub fn new(rpc_client: Arc<crate::utils::rpc::RpcClient>, console: Arc<ConsoleManager>) -> Result<Self> {
+    pub fn new(
+        rpc_client: Arc<crate::utils::rpc::RpcClient>,
+        console: Arc<ConsoleManager>,
+        token_registry: Arc<TokenRegistry>,
+    ) -> Result<Self> {
         let dlmm_program_id = Pubkey::from_str(METEORA_DLMM_PROGRAM_ID)?;
         let damm_program_id = Pubkey::from_str(METEORA_DAMM_PROGRAM_ID)?;
-        
+        let vault_program_id = Pubkey::from_str(METEORA_VAULT_PROGRAM_ID)?;
+
         Ok(Self {
             client: RpcClient::new(rpc_client.get_url().to_string()),
             dlmm_program_id,
             damm_program_id,
+            vault_program_id,
+            rpc_client,
+            token_registry,
             console_manager: Some(console),
         })
     }
 
+    /// DLMM and DAMM pools together; one program failing to load still returns the
+    /// other's pools, and only both failing is an error
     pub async fn fetch_pools(&self) -> Result<Vec<Pool>> {
-        let mut pools = Vec::new();
-        
-        // Fetch DLMM pools
-        let dlmm_pools = self.fetch_dlmm_pools().await?;
-        pools.extend(dlmm_pools);
-        
-        // Fetch DAMM pools
-        let damm_pools = self.fetch_damm_pools().await?;
-        pools.extend(damm_pools);
-        
+        let (dlmm_pools, damm_pools) = tokio::join!(self.fetch_dlmm_pools(), self.fetch_damm_pools());
+        match (dlmm_pools, damm_pools) {
+            (Ok(mut dlmm_pools), Ok(damm_pools)) => {
+                dlmm_pools.extend(damm_pools);
+                Ok(dlmm_pools)
+            }
+            (Ok(pools), Err(e)) => {
+                warn!("Failed to fetch Meteora DAMM pools: {}", e);
+                Ok(pools)
+            }
+            (Err(e), Ok(pools)) => {
+                warn!("Failed to fetch Meteora DLMM pools: {}", e);
+                Ok(pools)
+            }
+            (Err(dlmm_error), Err(damm_error)) => {
+                anyhow::bail!("Failed to fetch Meteora pools: {}; {}", dlmm_error, damm_error)
+            }
+        }
+    }
+
+    /// Funded DLMM pairs, with reserves read from their vaults and the bins around
+    /// the active one loaded for swap quotes
+    pub async fn fetch_dlmm_pools(&self) -> Result<Vec<Pool>> {
+        let accounts = self.rpc_client.get_program_accounts_with_filters(&self.dlmm_program_id, &[
+            RpcFilterType::Memcmp(Memcmp::new_raw_bytes(0, LB_PAIR_DISCRIMINATOR.to_vec())),
+        ]).await.context("Failed to fetch Meteora DLMM pairs")?;
+
+        let pairs: Vec<(Pubkey, DlmmLbPair)> = accounts.into_iter()
+            .filter_map(|(address, account)| match Self::parse_dlmm_pool_data(&account.data) {
+                Ok(pair) => Some((address, pair)),
+                Err(e) => {
+                    debug!("Skipping Meteora DLMM account {}: {}", address, e);
+                    None
+                }
+            })
+            .collect();
+
+        let pools = self.load_dlmm_pools(pairs).await?;
+        info!("Fetched {} Meteora DLMM pools from blockchain", pools.len());
+        Ok(pools)
+    }
+
+    /// Build pools for `pairs`, dropping those whose vaults are empty or unreadable
+    async fn load_dlmm_pools(&self, pairs: Vec<(Pubkey, DlmmLbPair)>) -> Result<Vec<Pool>> {
+        let vaults: Vec<Pubkey> = pairs.iter()
+            .flat_map(|(_, pair)| [pair.reserve_x, pair.reserve_y])
+            .collect();
+        let balances = self.vault_balances(&vaults).await?;
+
+        let pairs: Vec<(Pubkey, DlmmLbPair, (u64, u64))> = pairs.into_iter()
+            .zip(balances)
+            .filter_map(|((address, pair), balances)| match balances {
+                Some((x, y)) if x > 0 && y > 0 => Some((address, pair, (x, y))),
+                _ => None,
+            })
+            .collect();
+
+        let bins = self.fetch_bins(pairs.iter().map(|(address, pair, _)| (address, pair))).await;
+
+        let mints: Vec<Pubkey> = pairs.iter()
+            .flat_map(|(_, pair, _)| [pair.token_x_mint, pair.token_y_mint])
+            .collect();
+        if let Err(e) = self.token_registry.resolve_many(&mints).await {
+            warn!("Failed to resolve Meteora DLMM token metadata: {}", e);
+        }
+
+        let mut pools = Vec::with_capacity(pairs.len());
+        for ((address, pair, (reserve_a, reserve_b)), bins) in pairs.into_iter().zip(bins) {
+            pools.push(Pool {
+                address,
+                dex: "Meteora DLMM".to_string(),
+                token_a: self.resolve_token(&pair.token_x_mint).await,
+                token_b: self.resolve_token(&pair.token_y_mint).await,
+                reserve_a,
+                reserve_b,
+                fee_percent: pair.fee_bps() / Decimal::from(10_000),
+                liquidity_usd: Decimal::ZERO, // Will be calculated separately
+                last_updated: chrono::Utc::now(),
+                metadata: Some(Arc::new(MeteoraDlmmPoolMeta {
+                    reserve_x: pair.reserve_x,
+                    reserve_y: pair.reserve_y,
+                    active_id: pair.active_id,
+                    bin_step: pair.bin_step,
+                    bins,
+                })),
+            });
+        }
         Ok(pools)
     }
 
-    async fn fetch_dlmm_pools(&self) -> Result<Vec<Pool>> {
-        let accounts = self.client.get_program_accounts(&self.dlmm_program_id)?;
-        let mut pools = Vec::new();
-        
-        for (pubkey, account) in accounts {
-            if account.data.len() >= 8 && self.is_dlmm_pool_account(&account.data) {
-                if let Ok(pool_data) = self.parse_dlmm_pool_data(&account.data) {
-
-                    
-                    let pool = Pool {
-                        address: pubkey,
-                        dex: "Meteora DLMM".to_string(),
-                        token_a: TokenInfo {
-                            mint: pool_data.token_x_mint,
-                            symbol: "UNKNOWN".to_string(),
-                            decimals: 6,
-                            price_usd: None,
-                        },
-                        token_b: TokenInfo {
-                            mint: pool_data.token_y_mint,
-                            symbol: "UNKNOWN".to_string(),
-                            decimals: 6,
-                            price_usd: None,
-                        },
-                        reserve_a: ((pool_data.liquidity / 2) / 1_000_000) as u64,
-                        reserve_b: ((pool_data.liquidity / 2) / 1_000_000) as u64,
-                        fee_percent: Decimal::from_f64(pool_data.base_fee_percentage as f64 / 100.0).unwrap_or_default(),
-                        liquidity_usd: Decimal::from((pool_data.liquidity / 1_000_000) as u64),
-                        last_updated: chrono::Utc::now(),
-                    };
-                    
-                    pools.push(pool);
+    /// Bins with liquidity in the arrays around each pair's active bin. A pair whose
+    /// arrays can't be read gets no bins and is quoted as constant product.
+    async fn fetch_bins<'a>(&self, pairs: impl IntoIterator<Item = (&'a Pubkey, &'a DlmmLbPair)>) -> Vec<Vec<Bin>> {
+        let arrays_per_pair = (2 * BIN_ARRAYS_PER_SIDE + 1) as usize;
+        let addresses: Vec<Pubkey> = pairs.into_iter()
+            .flat_map(|(address, pair)| {
+                let active_index = bin_array_index(pair.active_id);
+                (active_index - BIN_ARRAYS_PER_SIDE as i64..=active_index + BIN_ARRAYS_PER_SIDE as i64)
+                    .map(move |index| Self::bin_array_address(address, index, &self.dlmm_program_id))
+            })
+            .collect();
+
+        let mut bins = Vec::with_capacity(addresses.len() / arrays_per_pair);
+        for chunk in addresses.chunks(MAX_ACCOUNTS_PER_BATCH / arrays_per_pair * arrays_per_pair) {
+            let accounts = match self.rpc_client.get_multiple_accounts(chunk).await {
+                Ok(accounts) => accounts,
+                Err(e) => {
+                    warn!("Failed to fetch Meteora DLMM bin arrays: {}", e);
+                    vec![None; chunk.len()]
                 }
+            };
+            for arrays in accounts.chunks(arrays_per_pair) {
+                bins.push(arrays.iter()
+                    .flatten()
+                    .filter_map(|account| Self::parse_bin_array(&account.data).ok())
+                    .flatten()
+                    .collect());
             }
         }
-        
+        bins
+    }
+
+    pub fn bin_array_address(lb_pair: &Pubkey, index: i64, program_id: &Pubkey) -> Pubkey {
+        Pubkey::find_program_address(
+            &[b"bin_array", lb_pair.as_ref(), &index.to_le_bytes()],
+            program_id,
+        ).0
+    }
+
+    /// Registry entry for `mint`, or an unknown 6-decimal token if it can't be read
+    async fn resolve_token(&self, mint: &Pubkey) -> TokenInfo {
+        self.token_registry.resolve(mint).await.unwrap_or_else(|e| {
+            debug!("Failed to resolve token {}: {}", mint, e);
+            TokenInfo {
+                mint: *mint,
+                symbol: UNKNOWN_SYMBOL.to_string(),
+                decimals: 6,
+                price_usd: None,
+            }
+        })
+    }
+
+    /// Funded DAMM pools, with reserves valued as the pool's share of each dynamic vault.
+    /// Stable-curve pools are quoted as constant product like the rest.
+    pub async fn fetch_damm_pools(&self) -> Result<Vec<Pool>> {
+        let accounts = self.rpc_client.get_program_accounts_with_filters(&self.damm_program_id, &[
+            RpcFilterType::Memcmp(Memcmp::new_raw_bytes(0, DAMM_POOL_DISCRIMINATOR.to_vec())),
+        ]).await.context("Failed to fetch Meteora DAMM pools")?;
+
+        let damm_pools: Vec<(Pubkey, DammPool)> = accounts.into_iter()
+            .filter_map(|(address, account)| match Self::parse_damm_pool_data(&account.data) {
+                Ok(pool) => Some((address, pool)),
+                Err(e) => {
+                    debug!("Skipping Meteora DAMM account {}: {}", address, e);
+                    None
+                }
+            })
+            .collect();
+
+        let pools = self.load_damm_pools(damm_pools).await?;
+        info!("Fetched {} Meteora DAMM pools from blockchain", pools.len());
         Ok(pools)
     }
 
-    async fn fetch_damm_pools(&self) -> Result<Vec<Pool>> {
-        let accounts = self.client.get_program_accounts(&self.damm_program_id)?;
-        let mut pools = Vec::new();
-        
-        for (pubkey, account) in accounts {
-            if account.data.len() >= 8 {
-                // Basic pool parsing for DAMM - would need proper discriminator
-                if account.data.len() > 100 { // Basic size check
-                    let pool = Pool {
-                        address: pubkey,
-                        dex: "Meteora DAMM".to_string(),
-                        token_a: TokenInfo {
-                            mint: Pubkey::from_str("So11111111111111111111111111111111111111112").unwrap(),
-                            symbol: "SOL".to_string(),
-                            decimals: 9,
-                            price_usd: None,
-                        },
-                        token_b: TokenInfo {
-                            mint: Pubkey::from_str("EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v").unwrap(),
-                            symbol: "USDC".to_string(),
-                            decimals: 6,
-                            price_usd: None,
-                        },
-                        reserve_a: 100000000000,
-                        reserve_b: 50000000,
-                        fee_percent: Decimal::from_f64(0.3).unwrap_or_default(),
-                        liquidity_usd: Decimal::from(5000000),
-                        last_updated: chrono::Utc::now(),
-                    };
-                    
-                    pools.push(pool);
-                    
-                    if pools.len() >= 5 { // Limit for now
-                        break;
-                    }
+    /// Build pools for enabled `damm_pools`, dropping those whose vault shares are
+    /// empty or unreadable
+    async fn load_damm_pools(&self, damm_pools: Vec<(Pubkey, DammPool)>) -> Result<Vec<Pool>> {
+        let damm_pools: Vec<(Pubkey, DammPool)> = damm_pools.into_iter()
+            .filter(|(_, pool)| pool.enabled)
+            .collect();
+
+        let vaults: HashMap<Pubkey, DynamicVault> = self.fetch_accounts(
+            damm_pools.iter().flat_map(|(_, pool)| [pool.a_vault, pool.b_vault]),
+        ).await?
+            .into_iter()
+            .filter(|(_, account)| account.owner == self.vault_program_id)
+            .filter_map(|(address, account)| Some((address, Self::parse_vault_data(&account.data).ok()?)))
+            .collect();
+        let lp_accounts = self.fetch_accounts(
+            damm_pools.iter()
+                .flat_map(|(_, pool)| [pool.a_vault_lp, pool.b_vault_lp])
+                .chain(vaults.values().map(|vault| vault.lp_mint)),
+        ).await?;
+
+        let now = chrono::Utc::now().timestamp();
+        let reserve = |vault: &Pubkey, vault_lp: &Pubkey| -> Option<u64> {
+            let vault = vaults.get(vault)?;
+            let lp_amount = token_amount(lp_accounts.get(vault_lp)?)?;
+            let lp_supply = lp_accounts.get(&vault.lp_mint)
+                .filter(|mint| mint.owner == spl_token::id())
+                .and_then(|mint| MintInfo::parse(&mint.data).ok())?
+                .supply;
+            vault.share(lp_amount, lp_supply, now)
+        };
+        let damm_pools: Vec<(Pubkey, DammPool, (u64, u64))> = damm_pools.into_iter()
+            .filter_map(|(address, pool)| {
+                let reserves = reserve(&pool.a_vault, &pool.a_vault_lp).zip(reserve(&pool.b_vault, &pool.b_vault_lp));
+                match reserves {
+                    Some((a, b)) if a > 0 && b > 0 => Some((address, pool, (a, b))),
+                    _ => None,
                 }
-            }
+            })
+            .collect();
+
+        let mints: Vec<Pubkey> = damm_pools.iter()
+            .flat_map(|(_, pool, _)| [pool.token_a_mint, pool.token_b_mint])
+            .collect();
+        if let Err(e) = self.token_registry.resolve_many(&mints).await {
+            warn!("Failed to resolve Meteora DAMM token metadata: {}", e);
+        }
+
+        let mut pools = Vec::with_capacity(damm_pools.len());
+        for (address, damm_pool, (reserve_a, reserve_b)) in damm_pools {
+            pools.push(Pool {
+                address,
+                dex: "Meteora DAMM".to_string(),
+                token_a: self.resolve_token(&damm_pool.token_a_mint).await,
+                token_b: self.resolve_token(&damm_pool.token_b_mint).await,
+                reserve_a,
+                reserve_b,
+                fee_percent: damm_pool.fee_percent(),
+                liquidity_usd: Decimal::ZERO, // Will be calculated separately
+                last_updated: chrono::Utc::now(),
+                metadata: Some(Arc::new(MeteoraDammPoolMeta {
+                    a_vault: damm_pool.a_vault,
+                    b_vault: damm_pool.b_vault,
+                    a_vault_lp: damm_pool.a_vault_lp,
+                    b_vault_lp: damm_pool.b_vault_lp,
+                })),
+            });
         }
-        
         Ok(pools)
     }
 
-    fn is_dlmm_pool_account(&self, data: &[u8]) -> bool {
-        if data.len() < 8 {
-            return false;
-        }
-        
-        &data[0..8] == DLMM_POOL_DISCRIMINATOR
-    }
-
-    fn parse_dlmm_pool_data(&self, data: &[u8]) -> Result<MeteoraPool> {
-        if data.len() < 200 { // Minimum expected size
-            return Err(anyhow::anyhow!("Invalid pool data size"));
-        }
-        
-        // Parse the pool data structure
-        let token_x_mint = Pubkey::try_from(&data[8..40])?;
-        let token_y_mint = Pubkey::try_from(&data[40..72])?;
-        
-        let bin_step = u16::from_le_bytes([data[72], data[73]]);
-        let base_fee_percentage = u16::from_le_bytes([data[74], data[75]]);
-        let protocol_fee_percentage = u16::from_le_bytes([data[76], data[77]]);
-        
-        // Simplified liquidity calculation
-        let liquidity = u128::from_le_bytes([
-            data[80], data[81], data[82], data[83],
-            data[84], data[85], data[86], data[87],
-            data[88], data[89], data[90], data[91],
-            data[92], data[93], data[94], data[95],
-        ]);
-        
-        Ok(MeteoraPool {
-            token_x_mint,
-            token_y_mint,
-            bin_step,
-            base_fee_percentage,
-            protocol_fee_percentage,
-            liquidity,
-            reward_infos: Vec::new(), // Simplified for now
+    /// Accounts at `addresses`, deduplicated and fetched in batches; missing ones are left out
+    async fn fetch_accounts(&self, addresses: impl IntoIterator<Item = Pubkey>) -> Result<HashMap<Pubkey, Account>> {
+        let addresses: Vec<Pubkey> = addresses.into_iter().collect::<HashSet<_>>().into_iter().collect();
+        let mut accounts = HashMap::with_capacity(addresses.len());
+        for chunk in addresses.chunks(MAX_ACCOUNTS_PER_BATCH) {
+            let fetched = self.rpc_client.get_multiple_accounts(chunk).await?;
+            accounts.extend(chunk.iter().zip(fetched).filter_map(|(address, account)| Some((*address, account?))));
+        }
+        Ok(accounts)
+    }
+
+    /// Token balances of consecutive vault pairs, `None` for a pair with an unreadable vault
+    async fn vault_balances(&self, vaults: &[Pubkey]) -> Result<Vec<Option<(u64, u64)>>> {
+        let mut balances = Vec::with_capacity(vaults.len() / 2);
+        for chunk in vaults.chunks(MAX_ACCOUNTS_PER_BATCH) {
+            let accounts = self.rpc_client.get_multiple_accounts(chunk).await?;
+            for pair in accounts.chunks(2) {
+                let amount = |account: &Option<Account>| account.as_ref().and_then(token_amount);
+                balances.push(amount(&pair[0]).zip(amount(&pair[1])));
+            }
+        }
+        Ok(balances)
+    }
+
+    fn parse_damm_pool_data(data: &[u8]) -> Result<DammPool> {
+        if data.len() < DAMM_POOL_MIN_LEN || data[..8] != DAMM_POOL_DISCRIMINATOR {
+            anyhow::bail!("Not a Meteora DAMM pool account");
+        }
+
+        let pubkey = |offset: usize| Pubkey::try_from(&data[offset..offset + 32]);
+        let u64_at = |offset: usize| data[offset..offset + 8].try_into().map(u64::from_le_bytes);
+        Ok(DammPool {
+            token_a_mint: pubkey(DAMM_TOKEN_A_MINT_OFFSET)?,
+            token_b_mint: pubkey(DAMM_TOKEN_B_MINT_OFFSET)?,
+            a_vault: pubkey(DAMM_A_VAULT_OFFSET)?,
+            b_vault: pubkey(DAMM_B_VAULT_OFFSET)?,
+            a_vault_lp: pubkey(DAMM_A_VAULT_LP_OFFSET)?,
+            b_vault_lp: pubkey(DAMM_B_VAULT_LP_OFFSET)?,
+            enabled: data[DAMM_ENABLED_OFFSET] != 0,
+            trade_fee_numerator: u64_at(DAMM_TRADE_FEE_NUMERATOR_OFFSET)?,
+            trade_fee_denominator: u64_at(DAMM_TRADE_FEE_DENOMINATOR_OFFSET)?,
+        })
+    }
+
+    fn parse_vault_data(data: &[u8]) -> Result<DynamicVault> {
+        if data.len() < VAULT_MIN_LEN || data[..8] != VAULT_DISCRIMINATOR {
+            anyhow::bail!("Not a Meteora dynamic vault account");
+        }
+
+        let u64_at = |offset: usize| data[offset..offset + 8].try_into().map(u64::from_le_bytes);
+        Ok(DynamicVault {
+            total_amount: u64_at(VAULT_TOTAL_AMOUNT_OFFSET)?,
+            lp_mint: Pubkey::try_from(&data[VAULT_LP_MINT_OFFSET..VAULT_LP_MINT_OFFSET + 32])?,
+            last_updated_locked_profit: u64_at(VAULT_LOCKED_PROFIT_OFFSET)?,
+            last_report: u64_at(VAULT_LAST_REPORT_OFFSET)?,
+            locked_profit_degradation: u64_at(VAULT_DEGRADATION_OFFSET)?,
+        })
+    }
+
+    fn parse_dlmm_pool_data(data: &[u8]) -> Result<DlmmLbPair> {
+        if data.len() < LB_PAIR_MIN_LEN || data[..8] != LB_PAIR_DISCRIMINATOR {
+            anyhow::bail!("Not a Meteora DLMM pair account");
+        }
+
+        let pubkey = |offset: usize| Pubkey::try_from(&data[offset..offset + 32]);
+        Ok(DlmmLbPair {
+            token_x_mint: pubkey(TOKEN_X_MINT_OFFSET)?,
+            token_y_mint: pubkey(TOKEN_Y_MINT_OFFSET)?,
+            reserve_x: pubkey(RESERVE_X_OFFSET)?,
+            reserve_y: pubkey(RESERVE_Y_OFFSET)?,
+            active_id: i32::from_le_bytes(data[ACTIVE_ID_OFFSET..ACTIVE_ID_OFFSET + 4].try_into()?),
+            bin_step: u16::from_le_bytes(data[BIN_STEP_OFFSET..BIN_STEP_OFFSET + 2].try_into()?),
+            base_factor: u16::from_le_bytes(data[BASE_FACTOR_OFFSET..BASE_FACTOR_OFFSET + 2].try_into()?),
         })
     }
 
+    /// Bins of a `BinArray` account that hold any liquidity
+    fn parse_bin_array(data: &[u8]) -> Result<Vec<Bin>> {
+        let bins_end = BINS_OFFSET + BINS_PER_ARRAY as usize * BIN_LEN;
+        if data.len() < bins_end || data[..8] != BIN_ARRAY_DISCRIMINATOR {
+            anyhow::bail!("Not a Meteora DLMM bin array account");
+        }
+
+        let index = i64::from_le_bytes(data[BIN_ARRAY_INDEX_OFFSET..BIN_ARRAY_INDEX_OFFSET + 8].try_into()?);
+        let first_bin_id = i32::try_from(index * BINS_PER_ARRAY as i64)?;
+        Ok(data[BINS_OFFSET..bins_end].chunks(BIN_LEN)
+            .zip(first_bin_id..)
+            .map(|(bin, id)| Bin {
+                id,
+                amount_x: u64::from_le_bytes(bin[0..8].try_into().unwrap()),
+                amount_y: u64::from_le_bytes(bin[8..16].try_into().unwrap()),
+            })
+            .filter(|bin| bin.amount_x > 0 || bin.amount_y > 0)
+            .collect())
+    }
+
     pub fn is_healthy(&self) -> bool {
         // Check if we can connect to the RPC
         self.client.get_latest_blockhash().is_ok()
     }
 }
 
+/// Index of the bin array holding `bin_id`, rounding towards negative infinity
+fn bin_array_index(bin_id: i32) -> i64 {
+    bin_id.div_euclid(BINS_PER_ARRAY) as i64
+}
+
+/// Balance of an SPL token account, `None` for accounts the token program doesn't own
+fn token_amount(account: &Account) -> Option<u64> {
+    if account.owner != spl_token::id() || account.data.len() < TOKEN_ACCOUNT_LEN {
+        return None;
+    }
+    Some(u64::from_le_bytes(account.data[TOKEN_AMOUNT_OFFSET..TOKEN_AMOUNT_OFFSET + 8].try_into().unwrap()))
+}
+
 #[async_trait]
 impl DexClient for MeteoraDex {
     async fn fetch_pools(&self) -> Result<Vec<Pool>> {
@@ -208,18 +591,46 @@ impl DexClient for MeteoraDex {
 
     async fn get_pool_by_tokens(&self, token_a: &str, token_b: &str) -> Result<Option<Pool>> {
         let pools = self.fetch_pools().await?;
-        
+
         for pool in pools {
             if (pool.token_a.mint.to_string() == token_a && pool.token_b.mint.to_string() == token_b) ||
                (pool.token_a.mint.to_string() == token_b && pool.token_b.mint.to_string() == token_a) {
                 return Ok(Some(pool));
             }
         }
-        
+
         Ok(None)
     }
 
     async fn update_pool_reserves(&self, pool: &mut Pool) -> Result<()> {
+        if pool.get_metadata::<MeteoraDlmmPoolMeta>().is_some() {
+            let data = self.rpc_client.get_account_data(&pool.address).await?;
+            let pair = Self::parse_dlmm_pool_data(&data)?;
+            let updated = self.load_dlmm_pools(vec![(pool.address, pair)]).await?
+                .pop()
+                .with_context(|| format!("Meteora DLMM pair {} has no liquidity", pool.address))?;
+            pool.reserve_a = updated.reserve_a;
+            pool.reserve_b = updated.reserve_b;
+            pool.fee_percent = updated.fee_percent;
+            pool.metadata = updated.metadata;
+            pool.last_updated = chrono::Utc::now();
+            return Ok(());
+        }
+
+        if pool.get_metadata::<MeteoraDammPoolMeta>().is_some() {
+            let data = self.rpc_client.get_account_data(&pool.address).await?;
+            let damm_pool = Self::parse_damm_pool_data(&data)?;
+            let updated = self.load_damm_pools(vec![(pool.address, damm_pool)]).await?
+                .pop()
+                .with_context(|| format!("Meteora DAMM pool {} has no liquidity", pool.address))?;
+            pool.reserve_a = updated.reserve_a;
+            pool.reserve_b = updated.reserve_b;
+            pool.fee_percent = updated.fee_percent;
+            pool.metadata = updated.metadata;
+            pool.last_updated = chrono::Utc::now();
+            return Ok(());
+        }
+
         // For Meteora, we would need to fetch the latest pool state
         // This is a simplified implementation
         if let Some(updated_pool) = self.get_pool_by_tokens(&pool.token_a.mint.to_string(), &pool.token_b.mint.to_string()).await? {
@@ -237,4 +648,130 @@ impl DexClient for MeteoraDex {
     fn set_console_manager(&mut self, console_manager: Arc<ConsoleManager>) {
         self.console_manager = Some(console_manager);
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_dlmm_pool_data() {
+        let (token_x_mint, reserve_y) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let mut data = vec![0u8; 904];
+        data[..8].copy_from_slice(&LB_PAIR_DISCRIMINATOR);
+        data[BASE_FACTOR_OFFSET..BASE_FACTOR_OFFSET + 2].copy_from_slice(&10_000u16.to_le_bytes());
+        data[ACTIVE_ID_OFFSET..ACTIVE_ID_OFFSET + 4].copy_from_slice(&(-71i32).to_le_bytes());
+        data[BIN_STEP_OFFSET..BIN_STEP_OFFSET + 2].copy_from_slice(&25u16.to_le_bytes());
+        data[TOKEN_X_MINT_OFFSET..TOKEN_X_MINT_OFFSET + 32].copy_from_slice(token_x_mint.as_ref());
+        data[RESERVE_Y_OFFSET..RESERVE_Y_OFFSET + 32].copy_from_slice(reserve_y.as_ref());
+
+        let pair = MeteoraDex::parse_dlmm_pool_data(&data).unwrap();
+        assert_eq!(pair.token_x_mint, token_x_mint);
+        assert_eq!(pair.reserve_y, reserve_y);
+        assert_eq!((pair.active_id, pair.bin_step), (-71, 25));
+        assert_eq!(pair.fee_bps(), Decimal::from(25));
+        assert_eq!(bin_array_index(pair.active_id), -2);
+
+        assert!(MeteoraDex::parse_dlmm_pool_data(&data[..LB_PAIR_MIN_LEN - 1]).is_err());
+        assert!(MeteoraDex::parse_dlmm_pool_data(&[0u8; 904]).is_err());
+    }
+
+    /// Anchor discriminator: the first 8 bytes of `sha256("account:<name>")`
+    fn discriminator(name: &str) -> [u8; 8] {
+        solana_sdk::hash::hash(format!("account:{}", name).as_bytes()).to_bytes()[..8].try_into().unwrap()
+    }
+
+    /// A DAMM `Pool` packed field by field in declaration order, independent of the offsets
+    fn damm_pool_account(keys: [Pubkey; 7], enabled: bool, trade_fee: (u64, u64)) -> Vec<u8> {
+        let mut data = discriminator("Pool").to_vec();
+        // lp_mint, token mints, vaults, the pool's vault LP accounts
+        keys.iter().for_each(|key| data.extend(key.as_ref()));
+        data.push(254); // a_vault_lp_bump
+        data.push(enabled as u8);
+        data.extend([7u8; 64]); // protocol_token_a_fee, protocol_token_b_fee
+        data.extend(1_700_000_000u64.to_le_bytes()); // fee_last_updated_at
+        data.extend([0u8; 24]); // _padding0
+        data.extend(trade_fee.0.to_le_bytes());
+        data.extend(trade_fee.1.to_le_bytes());
+        data.extend(20u64.to_le_bytes()); // protocol_trade_fee_numerator
+        data.extend(100u64.to_le_bytes()); // protocol_trade_fee_denominator
+        data.extend([0u8; 600]); // pool type, stake, locked LP, partner info, curve
+        data
+    }
+
+    #[test]
+    fn test_parse_damm_pool_data() {
+        assert_eq!(DAMM_POOL_DISCRIMINATOR, discriminator("Pool"));
+        let keys: [Pubkey; 7] = std::array::from_fn(|_| Pubkey::new_unique());
+        let data = damm_pool_account(keys, true, (25, 10_000));
+
+        let pool = MeteoraDex::parse_damm_pool_data(&data).unwrap();
+        assert_eq!((pool.token_a_mint, pool.token_b_mint), (keys[1], keys[2]));
+        assert_eq!((pool.a_vault, pool.b_vault), (keys[3], keys[4]));
+        assert_eq!((pool.a_vault_lp, pool.b_vault_lp), (keys[5], keys[6]));
+        assert!(pool.enabled);
+        assert_eq!(pool.fee_percent(), Decimal::new(25, 4));
+
+        assert!(!MeteoraDex::parse_damm_pool_data(&damm_pool_account(keys, false, (25, 10_000))).unwrap().enabled);
+        assert!(MeteoraDex::parse_damm_pool_data(&data[..DAMM_POOL_MIN_LEN - 1]).is_err());
+        assert!(MeteoraDex::parse_damm_pool_data(&vec![0u8; data.len()]).is_err());
+    }
+
+    #[test]
+    fn test_parse_vault_data() {
+        assert_eq!(VAULT_DISCRIMINATOR, discriminator("Vault"));
+        let lp_mint = Pubkey::new_unique();
+        let mut data = discriminator("Vault").to_vec();
+        data.extend([1, 255, 254]); // enabled, vault_bump, token_vault_bump
+        data.extend(5_000_000u64.to_le_bytes()); // total_amount
+        data.extend([3u8; 64]); // token_vault, fee_vault
+        data.extend([4u8; 32]); // token_mint
+        data.extend(lp_mint.as_ref());
+        data.extend([5u8; 30 * 32 + 3 * 32]); // strategies, base, admin, operator
+        data.extend(1_000_000u64.to_le_bytes()); // last_updated_locked_profit
+        data.extend(1_700_000_000u64.to_le_bytes()); // last_report
+        data.extend(1_000_000_000u64.to_le_bytes()); // locked_profit_degradation
+        data.extend([0u8; 200]);
+
+        let vault = MeteoraDex::parse_vault_data(&data).unwrap();
+        assert_eq!((vault.total_amount, vault.lp_mint), (5_000_000, lp_mint));
+        // A thousandth of the locked profit is released each second
+        assert_eq!(vault.unlocked_amount(1_700_000_000), 4_000_000);
+        assert_eq!(vault.unlocked_amount(1_700_000_500), 4_500_000);
+        assert_eq!(vault.unlocked_amount(1_700_001_000), 5_000_000);
+        // A pool holding a quarter of the LP supply owns a quarter of what's unlocked
+        assert_eq!(vault.share(250, 1_000, 1_700_001_000), Some(1_250_000));
+        assert_eq!(vault.share(250, 0, 1_700_001_000), None);
+
+        assert!(MeteoraDex::parse_vault_data(&data[..VAULT_MIN_LEN - 1]).is_err());
+        assert!(MeteoraDex::parse_vault_data(&damm_pool_account([Pubkey::new_unique(); 7], true, (0, 1))).is_err());
+    }
+
+    #[test]
+    fn test_token_amount_requires_token_program_owner() {
+        let mut data = vec![0u8; TOKEN_ACCOUNT_LEN];
+        data[TOKEN_AMOUNT_OFFSET..TOKEN_AMOUNT_OFFSET + 8].copy_from_slice(&42u64.to_le_bytes());
+        let mut account = Account { lamports: 1, data, owner: spl_token::id(), executable: false, rent_epoch: 0 };
+        assert_eq!(token_amount(&account), Some(42));
+
+        account.owner = Pubkey::from_str(METEORA_VAULT_PROGRAM_ID).unwrap();
+        assert_eq!(token_amount(&account), None);
+        account.owner = spl_token::id();
+        account.data.truncate(TOKEN_AMOUNT_OFFSET + 8);
+        assert_eq!(token_amount(&account), None);
+    }
+
+    #[test]
+    fn test_parse_bin_array() {
+        let mut data = vec![0u8; BINS_OFFSET + BINS_PER_ARRAY as usize * BIN_LEN];
+        data[..8].copy_from_slice(&BIN_ARRAY_DISCRIMINATOR);
+        data[BIN_ARRAY_INDEX_OFFSET..BIN_ARRAY_INDEX_OFFSET + 8].copy_from_slice(&(-1i64).to_le_bytes());
+        let third = BINS_OFFSET + 2 * BIN_LEN;
+        data[third..third + 8].copy_from_slice(&500u64.to_le_bytes());
+        data[third + 8..third + 16].copy_from_slice(&700u64.to_le_bytes());
+
+        let bins = MeteoraDex::parse_bin_array(&data).unwrap();
+        assert_eq!(bins, vec![Bin { id: -68, amount_x: 500, amount_y: 700 }]);
+        assert!(MeteoraDex::parse_bin_array(&data[..BINS_OFFSET]).is_err());
+    }
+}
@@ -4,20 +4,190 @@ pub mod phoenix;
 pub mod meteora;
 pub mod saber;
 pub mod serum;
+pub mod openbook;
+pub mod kamino;
 pub mod lifinity;
 pub mod pumpfun;
+pub mod jupiter;
+pub mod drift;
+pub mod guards;
 
-use crate::models::Pool;
-use anyhow::Result;
+use crate::models::{Pool, SwapQuote};
+use anyhow::{Context, Result};
 use async_trait::async_trait;
 use crate::console::ConsoleManager;
-use std::sync::Arc;
+use serde::Serialize;
+use std::{sync::Arc, time::Instant};
+
+/// Result of a connectivity probe against one DEX
+#[derive(Debug, Clone, Serialize)]
+pub struct HealthStatus {
+    pub latency_ms: u64,
+    pub pool_count: usize,
+    pub is_healthy: bool,
+    pub error: Option<String>,
+}
+
+impl HealthStatus {
+    /// Build a status from a probe that started at `started`; `pool_count` is only
+    /// meaningful when the probe succeeded.
+    pub fn from_probe(started: Instant, probe: Result<usize>) -> Self {
+        let latency_ms = started.elapsed().as_millis() as u64;
+        match probe {
+            Ok(pool_count) => Self { latency_ms, pool_count, is_healthy: true, error: None },
+            Err(e) => Self { latency_ms, pool_count: 0, is_healthy: false, error: Some(e.to_string()) },
+        }
+    }
+}
+
+/// DEX a client or pool belongs to, whichever spelling of its name is used
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DexId {
+    Orca,
+    Raydium,
+    Phoenix,
+    Meteora,
+    Saber,
+    Serum,
+    OpenBook,
+    Kamino,
+    Lifinity,
+    PumpFun,
+    Jupiter,
+    Drift,
+}
+
+impl DexId {
+    /// Recognizes both `DexClient::get_dex_name` and `Pool::dex` names, which differ for
+    /// some DEXes: "PumpFun" and "Pump.fun", or "Meteora" and "Meteora DLMM"
+    pub fn from_name(name: &str) -> Option<Self> {
+        let normalized: String = name
+            .chars()
+            .filter(|c| c.is_ascii_alphanumeric())
+            .map(|c| c.to_ascii_lowercase())
+            .collect();
+        let id = match normalized.as_str() {
+            "orca" => Self::Orca,
+            "raydium" => Self::Raydium,
+            "phoenix" => Self::Phoenix,
+            "meteora" | "meteoradlmm" | "meteoradamm" => Self::Meteora,
+            "saber" => Self::Saber,
+            "serum" => Self::Serum,
+            "openbook" => Self::OpenBook,
+            "kamino" => Self::Kamino,
+            "lifinity" => Self::Lifinity,
+            "pumpfun" => Self::PumpFun,
+            "jupiter" => Self::Jupiter,
+            "drift" => Self::Drift,
+            _ => return None,
+        };
+        Some(id)
+    }
+}
+
+/// Whether two DEX names refer to the same DEX; names `DexId` doesn't know must match exactly
+pub fn same_dex(a: &str, b: &str) -> bool {
+    a == b || DexId::from_name(a).is_some_and(|id| DexId::from_name(b) == Some(id))
+}
 
 #[async_trait]
 pub trait DexClient: Send + Sync {
     async fn fetch_pools(&self) -> Result<Vec<Pool>>;
     async fn get_pool_by_tokens(&self, token_a: &str, token_b: &str) -> Result<Option<Pool>>;
     async fn update_pool_reserves(&self, pool: &mut Pool) -> anyhow::Result<()>;
+
+    /// Refresh reserves from pool account data that was already fetched, e.g. in bulk via
+    /// `getMultipleAccounts`. DEXes whose reserves live outside the pool account keep this
+    /// default, and callers fall back to `update_pool_reserves`.
+    fn parse_account_data(&self, pool: &mut Pool, _data: &[u8]) -> Result<()> {
+        anyhow::bail!("{} pool {} can't be refreshed from account data", self.get_dex_name(), pool.address)
+    }
     fn get_dex_name(&self) -> &'static str;
     fn set_console_manager(&mut self, console: Arc<ConsoleManager>);
+
+    /// Quote a swap on this DEX without building a full opportunity.
+    /// Looks up the pool for the pair and applies constant-product reserve math.
+    async fn get_swap_quote(&self, input_token: &str, output_token: &str, amount: u64) -> Result<SwapQuote> {
+        let pool = self.get_pool_by_tokens(input_token, output_token).await?
+            .with_context(|| format!("No {} pool for {} -> {}", self.get_dex_name(), input_token, output_token))?;
+        SwapQuote::from_pool(&pool, input_token, amount)
+    }
+
+    /// Check that the DEX is reachable. The default fetches every pool, so clients
+    /// override it with something cheaper where they can.
+    async fn health_check(&self) -> Result<HealthStatus> {
+        let started = Instant::now();
+        let probe = match self.fetch_pools().await {
+            Ok(pools) if pools.is_empty() => Err(anyhow::anyhow!("{} returned no pools", self.get_dex_name())),
+            Ok(pools) => Ok(pools.len()),
+            Err(e) => Err(e),
+        };
+        Ok(HealthStatus::from_probe(started, probe))
+    }
+
+    /// Reset internal client state after repeated fetch failures.
+    /// Clients are shared behind `Arc`, so implementations use interior mutability.
+    async fn reinitialize(&self) -> Result<()> {
+        Ok(())
+    }
+
+    /// How reliably quoted liquidity turns into fills: 1.0 for constant-product AMMs,
+    /// higher for concentrated liquidity, lower for order books that may fill partially.
+    fn liquidity_quality_multiplier(&self) -> f64 {
+        1.0
+    }
+
+    /// Active liquidity and sqrt price (token B per token A) of a concentrated liquidity
+    /// pool, for `Pool::effective_liquidity`. `None` values the pool from its reserves.
+    fn active_liquidity(&self, _pool: &Pool) -> Option<(f64, f64)> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct EmptyDex;
+
+    #[async_trait]
+    impl DexClient for EmptyDex {
+        async fn fetch_pools(&self) -> Result<Vec<Pool>> {
+            Ok(Vec::new())
+        }
+        async fn get_pool_by_tokens(&self, _token_a: &str, _token_b: &str) -> Result<Option<Pool>> {
+            Ok(None)
+        }
+        async fn update_pool_reserves(&self, _pool: &mut Pool) -> Result<()> {
+            Ok(())
+        }
+        fn get_dex_name(&self) -> &'static str {
+            "empty"
+        }
+        fn set_console_manager(&mut self, _console: Arc<ConsoleManager>) {}
+    }
+
+    #[tokio::test]
+    async fn test_default_health_check_requires_pools() {
+        let health = EmptyDex.health_check().await.unwrap();
+        assert!(!health.is_healthy);
+        assert_eq!(health.pool_count, 0);
+        assert!(health.error.unwrap().contains("no pools"));
+
+        let healthy = HealthStatus::from_probe(Instant::now(), Ok(12));
+        assert!(healthy.is_healthy);
+        assert_eq!(healthy.pool_count, 12);
+        assert!(healthy.error.is_none());
+    }
+
+    #[test]
+    fn test_pool_and_client_dex_names_match() {
+        assert!(same_dex("PumpFun", "Pump.fun"));
+        assert!(same_dex("Saber", "saber"));
+        assert!(same_dex("Meteora", "Meteora DLMM"));
+        assert!(same_dex("Meteora", "Meteora DAMM"));
+        assert!(!same_dex("orca", "raydium"));
+        assert!(same_dex("empty", "empty"));
+        assert!(!same_dex("empty", "Empty"));
+    }
 }
@@ -0,0 +1,463 @@
+use crate::{
+    dex::DexClient,
+    models::{Pool, TokenInfo},
+    utils::{
+        rpc::RpcClient,
+        token_registry::{TokenRegistry, UNKNOWN_SYMBOL},
+    },
+};
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use rust_decimal::Decimal;
+use solana_client::rpc_filter::{Memcmp, RpcFilterType};
+use solana_sdk::pubkey::Pubkey;
+use std::{collections::HashMap, str::FromStr, sync::Arc};
+use tracing::{debug, error, info, warn};
+
+use crate::console::ConsoleManager;
+
+pub const OPENBOOK_V2_PROGRAM_ID: &str = "opnb2LAfJYbRMAHHvqjCwQxanZn7n1a9GUZmBT7tSNp";
+
+/// Anchor discriminator of the OpenBook V2 `Market` account
+const MARKET_DISCRIMINATOR: [u8; 8] = [219, 190, 213, 55, 0, 227, 198, 154];
+
+// Market layout after the discriminator: bump and decimals, authorities and
+// admins, name, bids, asks, event heap, oracles and their config, stable price
+// model, lot sizes, fees and volumes, then mints and vaults
+const BASE_DECIMALS_OFFSET: usize = 9;
+const QUOTE_DECIMALS_OFFSET: usize = 10;
+const BIDS_OFFSET: usize = 200;
+const ASKS_OFFSET: usize = 232;
+const QUOTE_LOT_SIZE_OFFSET: usize = 736;
+const BASE_LOT_SIZE_OFFSET: usize = 744;
+const MAKER_FEE_OFFSET: usize = 768;
+const TAKER_FEE_OFFSET: usize = 776;
+const BASE_MINT_OFFSET: usize = 864;
+const QUOTE_MINT_OFFSET: usize = 896;
+const BASE_VAULT_OFFSET: usize = 928;
+const QUOTE_VAULT_OFFSET: usize = 968;
+const MARKET_MIN_LEN: usize = 1008;
+
+/// Market fees are fixed-point with this many units per 1.0
+const FEES_SCALE_FACTOR: i64 = 1_000_000;
+
+// BookSide layout after the discriminator: fixed and oracle-pegged tree roots,
+// reserved roots and padding, the node pool header, then 88-byte nodes
+const FIXED_ROOT_OFFSET: usize = 8;
+const NODES_OFFSET: usize = 840;
+const NODE_LEN: usize = 88;
+const INNER_NODE_TAG: u8 = 1;
+const LEAF_NODE_TAG: u8 = 2;
+const NODE_KEY_OFFSET: usize = 8;
+const INNER_NODE_CHILDREN_OFFSET: usize = 24;
+
+/// SPL token account layout: amount follows the mint and owner
+const TOKEN_AMOUNT_OFFSET: usize = 64;
+/// `getMultipleAccounts` accepts at most this many addresses per call
+const MAX_ACCOUNTS_PER_BATCH: usize = 100;
+/// Book sides are ~90KB each, so they're fetched in much smaller batches
+const BOOK_SIDES_PER_BATCH: usize = 20;
+
+/// Fields of an OpenBook V2 `Market` account
+#[derive(Debug, Clone)]
+pub struct OpenBookMarket {
+    pub base_mint: Pubkey,
+    pub quote_mint: Pubkey,
+    pub base_vault: Pubkey,
+    pub quote_vault: Pubkey,
+    pub bids: Pubkey,
+    pub asks: Pubkey,
+    pub base_decimals: u8,
+    pub quote_decimals: u8,
+    pub base_lot_size: i64,
+    pub quote_lot_size: i64,
+    /// Maker fee in millionths, negative for a rebate
+    pub maker_fee: i64,
+    /// Taker fee in millionths
+    pub taker_fee: i64,
+}
+
+impl OpenBookMarket {
+    /// Taker fee as a fraction of the traded amount
+    pub fn taker_fee_rate(&self) -> Decimal {
+        Decimal::new(self.taker_fee, 0) / Decimal::new(FEES_SCALE_FACTOR, 0)
+    }
+}
+
+pub fn parse_openbook_market_data(data: &[u8]) -> Result<OpenBookMarket> {
+    if data.len() < MARKET_MIN_LEN || data[..8] != MARKET_DISCRIMINATOR {
+        anyhow::bail!("Not an OpenBook V2 market account");
+    }
+
+    let pubkey = |offset: usize| Pubkey::try_from(&data[offset..offset + 32]);
+    let read_i64 = |offset: usize| -> Result<i64> {
+        Ok(i64::from_le_bytes(data[offset..offset + 8].try_into()?))
+    };
+
+    Ok(OpenBookMarket {
+        base_mint: pubkey(BASE_MINT_OFFSET)?,
+        quote_mint: pubkey(QUOTE_MINT_OFFSET)?,
+        base_vault: pubkey(BASE_VAULT_OFFSET)?,
+        quote_vault: pubkey(QUOTE_VAULT_OFFSET)?,
+        bids: pubkey(BIDS_OFFSET)?,
+        asks: pubkey(ASKS_OFFSET)?,
+        base_decimals: data[BASE_DECIMALS_OFFSET],
+        quote_decimals: data[QUOTE_DECIMALS_OFFSET],
+        base_lot_size: read_i64(BASE_LOT_SIZE_OFFSET)?,
+        quote_lot_size: read_i64(QUOTE_LOT_SIZE_OFFSET)?,
+        maker_fee: read_i64(MAKER_FEE_OFFSET)?,
+        taker_fee: read_i64(TAKER_FEE_OFFSET)?,
+    })
+}
+
+/// Price in quote lots per base lot of the best fixed-price order in a `BookSide`
+/// account: the highest key for bids, the lowest for asks. Oracle-pegged orders
+/// live in a separate tree and are ignored.
+fn best_price_lots(data: &[u8], highest: bool) -> Option<u64> {
+    let read_u32 = |offset: usize| {
+        data.get(offset..offset + 4).map(|bytes| u32::from_le_bytes(bytes.try_into().unwrap()))
+    };
+
+    let mut node = read_u32(FIXED_ROOT_OFFSET)?;
+    if read_u32(FIXED_ROOT_OFFSET + 4)? == 0 {
+        return None;
+    }
+
+    // Each inner node is visited once at most, so this bounds a corrupt cycle
+    for _ in 0..(data.len().saturating_sub(NODES_OFFSET) / NODE_LEN) {
+        let start = NODES_OFFSET + node as usize * NODE_LEN;
+        let bytes = data.get(start..start + NODE_LEN)?;
+        match bytes[0] {
+            INNER_NODE_TAG => {
+                let child = if highest { INNER_NODE_CHILDREN_OFFSET + 4 } else { INNER_NODE_CHILDREN_OFFSET };
+                node = u32::from_le_bytes(bytes[child..child + 4].try_into().ok()?);
+            }
+            LEAF_NODE_TAG => {
+                let key = u128::from_le_bytes(bytes[NODE_KEY_OFFSET..NODE_KEY_OFFSET + 16].try_into().ok()?);
+                return Some((key >> 64) as u64);
+            }
+            _ => return None,
+        }
+    }
+    None
+}
+
+/// Taker fee plus half the bid/ask spread: what crossing the book costs compared
+/// to a mid-price fill. Falls back to the taker fee when either side is empty.
+fn effective_fee(market: &OpenBookMarket, bids: Option<&[u8]>, asks: Option<&[u8]>) -> Decimal {
+    let taker_fee = market.taker_fee_rate();
+    let best_bid = bids.and_then(|data| best_price_lots(data, true));
+    let best_ask = asks.and_then(|data| best_price_lots(data, false));
+
+    match (best_bid, best_ask) {
+        (Some(bid), Some(ask)) if ask > bid => {
+            let (bid, ask) = (Decimal::from(bid), Decimal::from(ask));
+            let half_spread = (ask - bid) / (ask + bid);
+            taker_fee + half_spread
+        }
+        _ => taker_fee,
+    }
+}
+
+fn token_amount(data: &[u8]) -> Option<u64> {
+    data.get(TOKEN_AMOUNT_OFFSET..TOKEN_AMOUNT_OFFSET + 8)
+        .map(|bytes| u64::from_le_bytes(bytes.try_into().unwrap()))
+}
+
+pub struct OpenBookClient {
+    rpc_client: Arc<RpcClient>,
+    pools_cache: tokio::sync::RwLock<HashMap<String, Pool>>,
+    console: Arc<ConsoleManager>,
+    token_registry: Arc<TokenRegistry>,
+}
+
+impl OpenBookClient {
+    pub fn new(
+        rpc_client: Arc<RpcClient>,
+        console: Arc<ConsoleManager>,
+        token_registry: Arc<TokenRegistry>,
+    ) -> Result<Self> {
+        Ok(Self {
+            rpc_client,
+            pools_cache: tokio::sync::RwLock::new(HashMap::new()),
+            console,
+            token_registry,
+        })
+    }
+
+    async fn fetch_openbook_markets(&self) -> Result<Vec<Pool>> {
+        let program_id = Pubkey::from_str(OPENBOOK_V2_PROGRAM_ID)
+            .context("Invalid OpenBook V2 program ID")?;
+        let accounts = self.rpc_client.get_program_accounts_with_filters(&program_id, &[
+            RpcFilterType::Memcmp(Memcmp::new_raw_bytes(0, MARKET_DISCRIMINATOR.to_vec())),
+        ]).await.context("Failed to fetch OpenBook V2 markets")?;
+
+        let markets: Vec<(Pubkey, OpenBookMarket)> = accounts.into_iter()
+            .filter_map(|(address, account)| match parse_openbook_market_data(&account.data) {
+                Ok(market) => Some((address, market)),
+                Err(e) => {
+                    debug!("Skipping OpenBook V2 account {}: {}", address, e);
+                    None
+                }
+            })
+            .collect();
+
+        // Most markets are abandoned; only fetch order books for those holding funds
+        let balances = self.fetch_vault_balances(markets.iter().map(|(_, market)| market)).await?;
+        let markets: Vec<(Pubkey, OpenBookMarket, (u64, u64))> = markets.into_iter()
+            .zip(balances)
+            .filter_map(|((address, market), balances)| match balances {
+                Some((base, quote)) if base > 0 && quote > 0 => Some((address, market, (base, quote))),
+                _ => None,
+            })
+            .collect();
+
+        let fees = self.fetch_effective_fees(markets.iter().map(|(_, market, _)| market)).await?;
+
+        let mints: Vec<Pubkey> = markets.iter()
+            .flat_map(|(_, market, _)| [market.base_mint, market.quote_mint])
+            .collect();
+        if let Err(e) = self.token_registry.resolve_many(&mints).await {
+            warn!("Failed to resolve OpenBook V2 token metadata: {}", e);
+        }
+
+        let mut pools = Vec::with_capacity(markets.len());
+        for ((address, market, (reserve_a, reserve_b)), fee_percent) in markets.into_iter().zip(fees) {
+            pools.push(Pool {
+                address,
+                dex: "openbook".to_string(),
+                token_a: self.resolve_token(&market.base_mint, market.base_decimals).await,
+                token_b: self.resolve_token(&market.quote_mint, market.quote_decimals).await,
+                reserve_a,
+                reserve_b,
+                fee_percent,
+                liquidity_usd: Decimal::ZERO, // Will be calculated separately
+                last_updated: chrono::Utc::now(),
+                metadata: Some(Arc::new(market) as Arc<dyn std::any::Any + Send + Sync>),
+            });
+        }
+
+        info!("Fetched {} OpenBook V2 markets from blockchain", pools.len());
+        Ok(pools)
+    }
+
+    /// Decimals come from the market account; only the symbol needs the registry
+    async fn resolve_token(&self, mint: &Pubkey, decimals: u8) -> TokenInfo {
+        let symbol = match self.token_registry.resolve(mint).await {
+            Ok(token) => token.symbol,
+            Err(e) => {
+                debug!("Failed to resolve token {}: {}", mint, e);
+                UNKNOWN_SYMBOL.to_string()
+            }
+        };
+        TokenInfo {
+            mint: *mint,
+            symbol,
+            decimals,
+            price_usd: None,
+        }
+    }
+
+    /// Base and quote vault balances of each market, `None` where either vault can't be read
+    async fn fetch_vault_balances<'a>(
+        &self,
+        markets: impl IntoIterator<Item = &'a OpenBookMarket>,
+    ) -> Result<Vec<Option<(u64, u64)>>> {
+        let vaults: Vec<Pubkey> = markets.into_iter()
+            .flat_map(|market| [market.base_vault, market.quote_vault])
+            .collect();
+
+        let mut balances = Vec::with_capacity(vaults.len() / 2);
+        for chunk in vaults.chunks(MAX_ACCOUNTS_PER_BATCH) {
+            let accounts = self.rpc_client.get_multiple_accounts(chunk).await?;
+            for pair in accounts.chunks(2) {
+                let amount = |account: &Option<solana_sdk::account::Account>| {
+                    account.as_ref().and_then(|account| token_amount(&account.data))
+                };
+                balances.push(amount(&pair[0]).zip(amount(&pair[1])));
+            }
+        }
+        Ok(balances)
+    }
+
+    /// Effective fee of each market from its current bids and asks
+    async fn fetch_effective_fees<'a>(
+        &self,
+        markets: impl IntoIterator<Item = &'a OpenBookMarket>,
+    ) -> Result<Vec<Decimal>> {
+        let markets: Vec<&OpenBookMarket> = markets.into_iter().collect();
+
+        let mut fees = Vec::with_capacity(markets.len());
+        for chunk in markets.chunks(BOOK_SIDES_PER_BATCH / 2) {
+            let book_sides: Vec<Pubkey> = chunk.iter().flat_map(|market| [market.bids, market.asks]).collect();
+            let accounts = self.rpc_client.get_multiple_accounts(&book_sides).await?;
+            for (market, sides) in chunk.iter().zip(accounts.chunks(2)) {
+                let data = |index: usize| sides[index].as_ref().map(|account| account.data.as_slice());
+                fees.push(effective_fee(market, data(0), data(1)));
+            }
+        }
+        Ok(fees)
+    }
+}
+
+#[async_trait]
+impl DexClient for OpenBookClient {
+    async fn fetch_pools(&self) -> Result<Vec<Pool>> {
+        info!("Fetching OpenBook V2 markets...");
+        self.console.update_status(self.get_dex_name(), "Connecting to RPC");
+
+        match self.fetch_openbook_markets().await {
+            Ok(pools) => {
+                let mut cache = self.pools_cache.write().await;
+                cache.clear();
+                for pool in &pools {
+                    cache.insert(pool.address.to_string(), pool.clone());
+                }
+
+                info!("Successfully fetched {} OpenBook V2 markets", pools.len());
+                self.console.update_status_with_info(
+                    self.get_dex_name(),
+                    "Connected",
+                    &format!("{} markets cached", pools.len())
+                );
+                Ok(pools)
+            }
+            Err(e) => {
+                error!("Failed to fetch OpenBook V2 markets: {}", e);
+                self.console.update_status_with_info(self.get_dex_name(), "Error", "0 markets");
+                Err(e)
+            }
+        }
+    }
+
+    async fn get_pool_by_tokens(&self, token_a: &str, token_b: &str) -> Result<Option<Pool>> {
+        let cache = self.pools_cache.read().await;
+
+        for pool in cache.values() {
+            let pool_token_a = pool.token_a.mint.to_string();
+            let pool_token_b = pool.token_b.mint.to_string();
+
+            if (pool_token_a == token_a && pool_token_b == token_b) ||
+               (pool_token_a == token_b && pool_token_b == token_a) {
+                return Ok(Some(pool.clone()));
+            }
+        }
+
+        Ok(None)
+    }
+
+    async fn update_pool_reserves(&self, pool: &mut Pool) -> Result<()> {
+        let market = match pool.metadata.as_ref().and_then(|meta| meta.downcast_ref::<OpenBookMarket>()) {
+            Some(market) => market.clone(),
+            None => parse_openbook_market_data(&self.rpc_client.get_account_data(&pool.address).await?)?,
+        };
+
+        let (reserve_a, reserve_b) = self.fetch_vault_balances([&market]).await?
+            .into_iter()
+            .next()
+            .flatten()
+            .with_context(|| format!("OpenBook V2 vaults not found for {}", pool.address))?;
+        let fee_percent = self.fetch_effective_fees([&market]).await?
+            .into_iter()
+            .next()
+            .unwrap_or_else(|| market.taker_fee_rate());
+
+        pool.reserve_a = reserve_a;
+        pool.reserve_b = reserve_b;
+        pool.fee_percent = fee_percent;
+        pool.metadata = Some(Arc::new(market));
+        pool.last_updated = chrono::Utc::now();
+        Ok(())
+    }
+
+    fn get_dex_name(&self) -> &'static str {
+        "openbook"
+    }
+
+    fn set_console_manager(&mut self, console: Arc<ConsoleManager>) {
+        self.console = console;
+    }
+
+    fn liquidity_quality_multiplier(&self) -> f64 {
+        // Vault balances include funds resting away from the touch, so fills are thinner than quoted
+        0.8
+    }
+
+    async fn reinitialize(&self) -> Result<()> {
+        self.pools_cache.write().await.clear();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn market_data(taker_fee: i64) -> (Vec<u8>, Pubkey, Pubkey) {
+        let (base_mint, quote_vault) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let mut data = vec![0u8; MARKET_MIN_LEN + 128];
+        data[..8].copy_from_slice(&MARKET_DISCRIMINATOR);
+        data[BASE_DECIMALS_OFFSET] = 9;
+        data[QUOTE_DECIMALS_OFFSET] = 6;
+        data[BASE_LOT_SIZE_OFFSET..BASE_LOT_SIZE_OFFSET + 8].copy_from_slice(&1_000_000i64.to_le_bytes());
+        data[QUOTE_LOT_SIZE_OFFSET..QUOTE_LOT_SIZE_OFFSET + 8].copy_from_slice(&1i64.to_le_bytes());
+        data[MAKER_FEE_OFFSET..MAKER_FEE_OFFSET + 8].copy_from_slice(&(-200i64).to_le_bytes());
+        data[TAKER_FEE_OFFSET..TAKER_FEE_OFFSET + 8].copy_from_slice(&taker_fee.to_le_bytes());
+        data[BASE_MINT_OFFSET..BASE_MINT_OFFSET + 32].copy_from_slice(base_mint.as_ref());
+        data[QUOTE_VAULT_OFFSET..QUOTE_VAULT_OFFSET + 32].copy_from_slice(quote_vault.as_ref());
+        (data, base_mint, quote_vault)
+    }
+
+    /// Book side whose fixed tree is one inner node over leaves at `prices`
+    fn book_side(prices: [u64; 2]) -> Vec<u8> {
+        let mut data = vec![0u8; NODES_OFFSET + 3 * NODE_LEN];
+        data[FIXED_ROOT_OFFSET + 4..FIXED_ROOT_OFFSET + 8].copy_from_slice(&2u32.to_le_bytes());
+
+        data[NODES_OFFSET] = INNER_NODE_TAG;
+        let children = NODES_OFFSET + INNER_NODE_CHILDREN_OFFSET;
+        data[children..children + 4].copy_from_slice(&1u32.to_le_bytes());
+        data[children + 4..children + 8].copy_from_slice(&2u32.to_le_bytes());
+
+        for (index, price) in prices.into_iter().enumerate() {
+            let start = NODES_OFFSET + (index + 1) * NODE_LEN;
+            data[start] = LEAF_NODE_TAG;
+            let key = (price as u128) << 64 | 7;
+            data[start + NODE_KEY_OFFSET..start + NODE_KEY_OFFSET + 16].copy_from_slice(&key.to_le_bytes());
+        }
+        data
+    }
+
+    #[test]
+    fn test_parse_openbook_market_data() {
+        let (data, base_mint, quote_vault) = market_data(400);
+        let market = parse_openbook_market_data(&data).unwrap();
+
+        assert_eq!(market.base_mint, base_mint);
+        assert_eq!(market.quote_vault, quote_vault);
+        assert_eq!((market.base_decimals, market.quote_decimals), (9, 6));
+        assert_eq!((market.base_lot_size, market.quote_lot_size), (1_000_000, 1));
+        assert_eq!((market.maker_fee, market.taker_fee), (-200, 400));
+        assert_eq!(market.taker_fee_rate(), Decimal::new(4, 4));
+
+        assert!(parse_openbook_market_data(&data[..MARKET_MIN_LEN - 1]).is_err());
+        assert!(parse_openbook_market_data(&vec![0u8; MARKET_MIN_LEN]).is_err());
+    }
+
+    #[test]
+    fn test_effective_fee_includes_half_spread() {
+        let market = parse_openbook_market_data(&market_data(400).0).unwrap();
+        let (bids, asks) = (book_side([99, 100]), book_side([101, 102]));
+
+        assert_eq!(best_price_lots(&bids, true), Some(100));
+        assert_eq!(best_price_lots(&asks, false), Some(101));
+        // (101 - 100) / (101 + 100) on top of the 0.04% taker fee
+        assert_eq!(
+            effective_fee(&market, Some(&bids), Some(&asks)),
+            Decimal::new(4, 4) + Decimal::ONE / Decimal::from(201)
+        );
+
+        let empty = vec![0u8; NODES_OFFSET];
+        assert_eq!(best_price_lots(&empty, true), None);
+        assert_eq!(effective_fee(&market, Some(&empty), Some(&asks)), Decimal::new(4, 4));
+    }
+}
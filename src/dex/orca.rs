@@ -1,11 +1,18 @@
 use crate::{
-    dex::DexClient,
+    dex::{DexClient, HealthStatus},
     models::{Pool, TokenInfo},
-    utils::rpc::RpcClient,
+    utils::{
+        cache::PoolCache,
+        math::{calculate_whirlpool_output, invert_sqrt_price_x64, Tick, TickArray, TICK_ARRAY_SIZE},
+        rpc::RpcClient,
+        token_registry::{TokenRegistry, UNKNOWN_SYMBOL},
+    },
 };
 use anyhow::{Context, Result};
 use async_trait::async_trait;
 use rust_decimal::Decimal;
+use solana_account_decoder::UiDataSliceConfig;
+use solana_client::rpc_filter::{Memcmp, RpcFilterType};
 use solana_sdk::pubkey::Pubkey;
 use std::{collections::HashMap, str::FromStr, sync::Arc};
 use tracing::{debug, error, info, warn};
@@ -14,36 +21,177 @@ use crate::console::ConsoleManager;
 
 // Removed old API structs - now fetching directly from blockchain
 
+pub const WHIRLPOOL_PROGRAM_ID: &str = "whirLbMiicVdio4qvUfM5KAg6Ct8VwpYzGff3uctyCc";
+/// Anchor discriminator of `Whirlpool` accounts, the first 8 bytes of sha256("account:Whirlpool")
+const WHIRLPOOL_DISCRIMINATOR: [u8; 8] = [63, 149, 209, 12, 225, 128, 99, 9];
+/// Serialized size of a `Whirlpool` account; tick arrays and positions differ
+const WHIRLPOOL_ACCOUNT_LEN: usize = 653;
+/// Serialized size of a `WhirlpoolsConfig` account: discriminator, three authorities
+/// and the default protocol fee rate
+const WHIRLPOOLS_CONFIG_ACCOUNT_LEN: usize = 108;
+/// Offset of `default_protocol_fee_rate` in a `WhirlpoolsConfig` account
+const DEFAULT_PROTOCOL_FEE_RATE_OFFSET: usize = 104;
+
+/// Fee settings of a whirlpool, combined from the pool and its `WhirlpoolsConfig`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WhirlpoolConfig {
+    /// The `WhirlpoolsConfig` account the pool belongs to
+    pub whirlpools_config: Pubkey,
+    /// Pool trade fee in hundredths of a basis point
+    pub fee_rate: u16,
+    /// Share of the trade fee the config sends to the protocol for new pools, in basis
+    /// points of the fee
+    pub default_protocol_fee_rate: u16,
+}
+
+impl WhirlpoolConfig {
+    /// Fee a swap pays, in hundredths of a basis point. The protocol fee is taken out of
+    /// this trade fee rather than charged on top, so it doesn't change the swap price
+    pub fn effective_fee_rate(&self) -> u32 {
+        self.fee_rate as u32
+    }
+
+    /// Part of `effective_fee_rate` that goes to the protocol, in hundredths of a basis point
+    pub fn protocol_fee_rate(&self) -> u32 {
+        self.fee_rate as u32 * self.default_protocol_fee_rate as u32 / 10_000
+    }
+}
+
+/// Whirlpool accounts needed to build swap instructions
+#[derive(Debug, Clone)]
+pub struct OrcaPoolMeta {
+    pub vault_a: Pubkey,
+    pub vault_b: Pubkey,
+    pub oracle: Pubkey,
+    pub tick_spacing: u16,
+    /// Liquidity active at the current tick
+    pub liquidity: u128,
+    /// Current sqrt price as a Q64.64 fixed-point number
+    pub sqrt_price: u128,
+    pub tick_current_index: i32,
+    /// Trade fee in hundredths of a basis point
+    pub fee_rate: u16,
+    /// Tick arrays around the current price, empty if they couldn't be fetched
+    pub tick_arrays: Vec<TickArray>,
+}
+
+impl OrcaPoolMeta {
+    /// Liquidity active at the current tick and the sqrt price as a plain ratio, or `None`
+    /// for an empty whirlpool
+    pub fn active_liquidity(&self) -> Option<(f64, f64)> {
+        (self.liquidity > 0 && self.sqrt_price > 0)
+            .then(|| (self.liquidity as f64, self.sqrt_price as f64 / 2f64.powi(64)))
+    }
+
+    /// Output of swapping `input_amount` through the whirlpool, token A into B when `a_to_b`
+    pub fn swap_output(&self, input_amount: u64, a_to_b: bool) -> Result<u64> {
+        if a_to_b {
+            calculate_whirlpool_output(input_amount, self.sqrt_price, self.liquidity, self.fee_rate, &self.tick_arrays)
+        } else {
+            let inverted: Vec<TickArray> = self.tick_arrays.iter().map(TickArray::inverted).collect();
+            calculate_whirlpool_output(
+                input_amount,
+                invert_sqrt_price_x64(self.sqrt_price),
+                self.liquidity,
+                self.fee_rate,
+                &inverted,
+            )
+        }
+    }
+}
+
+/// Size of one serialized tick: initialized flag, liquidity net and gross, fee and reward growth
+const TICK_LEN: usize = 113;
+/// Tick arrays fetched on each side of the current one; a swap may cross up to three
+const TICK_ARRAYS_PER_SIDE: i32 = 2;
+
 pub struct OrcaClient {
     rpc_client: Arc<RpcClient>,
     pools_cache: tokio::sync::RwLock<HashMap<String, Pool>>,
     console: Arc<ConsoleManager>,
+    token_registry: Arc<TokenRegistry>,
+    default_fee: Decimal,
+    /// Holds the `WhirlpoolsConfig` accounts pools point to
+    cache: PoolCache,
 }
 
 impl OrcaClient {
-    pub fn new(rpc_client: Arc<RpcClient>, console: Arc<ConsoleManager>) -> Result<Self> {
+    pub fn new(
+        rpc_client: Arc<RpcClient>,
+        console: Arc<ConsoleManager>,
+        token_registry: Arc<TokenRegistry>,
+    ) -> Result<Self> {
         Ok(Self {
             rpc_client,
             pools_cache: tokio::sync::RwLock::new(HashMap::new()),
             console,
+            token_registry,
+            default_fee: Decimal::new(3, 3),
+            cache: PoolCache::new(),
         })
     }
 
+    /// Fee, as a fraction, for pools whose exact fee isn't available on chain
+    pub fn with_default_fee(mut self, default_fee: Decimal) -> Self {
+        self.default_fee = default_fee;
+        self
+    }
+
+    /// Fee settings of the whirlpool in `whirlpool_data`, fetching its `WhirlpoolsConfig`
+    /// (bytes 8-40) from chain unless cached
+    pub async fn resolve_whirlpool_config(&self, whirlpool_data: &[u8]) -> Result<WhirlpoolConfig> {
+        if whirlpool_data.len() < WHIRLPOOL_ACCOUNT_LEN {
+            anyhow::bail!("Whirlpool account data too short");
+        }
+        let whirlpools_config = Pubkey::try_from(&whirlpool_data[8..40])
+            .context("Invalid Whirlpools config")?;
+        let fee_rate = u16::from_le_bytes([whirlpool_data[45], whirlpool_data[46]]);
+
+        let config_key = whirlpools_config.to_string();
+        let default_protocol_fee_rate = match self.cache.get_whirlpools_config(&config_key).await {
+            Some(rate) => rate,
+            None => {
+                let config_data = self.rpc_client.get_account_data(&whirlpools_config).await
+                    .with_context(|| format!("Failed to fetch Whirlpools config {}", whirlpools_config))?;
+                let rate = parse_default_protocol_fee_rate(&config_data)?;
+                self.cache.set_whirlpools_config(&config_key, rate).await;
+                rate
+            }
+        };
+
+        Ok(WhirlpoolConfig { whirlpools_config, fee_rate, default_protocol_fee_rate })
+    }
+
     async fn fetch_orca_pools_from_blockchain(&self) -> Result<Vec<Pool>> {
-        let whirlpool_program_id = Pubkey::from_str("whirLbMiicVdio4qvUfM5KAg6Ct8VwpYzGff3uctyCc")
+        let whirlpool_program_id = Pubkey::from_str(WHIRLPOOL_PROGRAM_ID)
             .context("Invalid Whirlpool program ID")?;
 
-        // Get all Whirlpool accounts
+        // Only Whirlpool accounts, leaving out the far more numerous tick arrays and positions
+        let filters = [
+            RpcFilterType::DataSize(WHIRLPOOL_ACCOUNT_LEN as u64),
+            RpcFilterType::Memcmp(Memcmp::new_raw_bytes(0, WHIRLPOOL_DISCRIMINATOR.to_vec())),
+        ];
+        let data_slice = UiDataSliceConfig { offset: 0, length: WHIRLPOOL_ACCOUNT_LEN };
         let accounts = self.rpc_client
-            .get_program_accounts(&whirlpool_program_id)
+            .get_program_accounts_with_data_slice(&whirlpool_program_id, &filters, data_slice)
             .await
             .context("Failed to fetch Whirlpool accounts")?;
 
+        // Resolve every pool's mints up front so parsing hits the registry cache
+        let mints: Vec<Pubkey> = accounts.iter()
+            .filter(|(_, account)| account.data.len() >= WHIRLPOOL_ACCOUNT_LEN && self.is_whirlpool_account(&account.data))
+            .flat_map(|(_, account)| [&account.data[101..133], &account.data[133..165]])
+            .filter_map(|bytes| Pubkey::try_from(bytes).ok())
+            .collect();
+        if let Err(e) = self.token_registry.resolve_many(&mints).await {
+            warn!("Failed to resolve Whirlpool token metadata: {}", e);
+        }
+
         let mut pools = Vec::new();
         
         for (pubkey, account) in accounts {
             // Filter for Whirlpool accounts by checking discriminator and data length
-            if account.data.len() >= 653 && self.is_whirlpool_account(&account.data) {
+            if account.data.len() >= WHIRLPOOL_ACCOUNT_LEN && self.is_whirlpool_account(&account.data) {
                 match self.parse_whirlpool_data(&pubkey, &account.data).await {
                     Ok(pool) => {
                         pools.push(pool);
@@ -60,23 +208,30 @@ impl OrcaClient {
         Ok(pools)
     }
 
-    fn is_whirlpool_account(&self, account_data: &[u8]) -> bool {
-        // Check if this is a Whirlpool account by examining the discriminator
-        // Whirlpool accounts have a specific 8-byte discriminator at the beginning
-        if account_data.len() < 8 {
-            return false;
+    /// Registry entry for `mint`, or an unknown token with the mint's own decimals
+    /// (6 if even the mint account can't be read)
+    async fn resolve_token(&self, mint: &Pubkey) -> TokenInfo {
+        match self.token_registry.resolve(mint).await {
+            Ok(token) => token,
+            Err(e) => {
+                debug!("Failed to resolve token {}: {}", mint, e);
+                TokenInfo {
+                    mint: *mint,
+                    symbol: UNKNOWN_SYMBOL.to_string(),
+                    decimals: self.token_registry.decimals(mint).await.unwrap_or(6),
+                    price_usd: None,
+                }
+            }
         }
-        
-        // Whirlpool discriminator (first 8 bytes)
-        // This is the hash of "account:Whirlpool"
-        let whirlpool_discriminator = [0x63, 0xd9, 0x96, 0xf2, 0x8c, 0x26, 0x8b, 0x8a];
-        
-        &account_data[0..8] == whirlpool_discriminator
     }
 
-    async fn parse_whirlpool_data(&self, pool_address: &Pubkey, account_data: &[u8]) -> Result<Pool> {
+    fn is_whirlpool_account(&self, account_data: &[u8]) -> bool {
+        account_data.starts_with(&WHIRLPOOL_DISCRIMINATOR)
+    }
+
+    pub(crate) async fn parse_whirlpool_data(&self, pool_address: &Pubkey, account_data: &[u8]) -> Result<Pool> {
         // Parse Whirlpool account data structure
-        if account_data.len() < 653 {
+        if account_data.len() < WHIRLPOOL_ACCOUNT_LEN {
             anyhow::bail!("Whirlpool account data too short");
         }
 
@@ -106,37 +261,121 @@ impl OrcaClient {
         let reserve_a = self.get_token_account_balance(&token_a_vault).await.unwrap_or(0);
         let reserve_b = self.get_token_account_balance(&token_b_vault).await.unwrap_or(0);
 
-        // Extract fee rate (bytes 229-231, 2 bytes as u16)
-        let fee_rate_bytes = &account_data[229..231];
-        let fee_rate_raw = u16::from_le_bytes([fee_rate_bytes[0], fee_rate_bytes[1]]);
-        let fee_rate = fee_rate_raw as f64 / 1_000_000.0; // Convert from basis points
+        // Fee rate (bytes 45-47), in hundredths of a basis point; zero means it wasn't set
+        let fee_rate_raw = u16::from_le_bytes([account_data[45], account_data[46]]);
+        let fee_percent = if fee_rate_raw == 0 {
+            self.default_fee
+        } else {
+            match self.resolve_whirlpool_config(account_data).await {
+                Ok(config) => {
+                    debug!(
+                        "Whirlpool {} fee {} of which protocol {} (hundredths of a bp)",
+                        pool_address, config.effective_fee_rate(), config.protocol_fee_rate()
+                    );
+                    Decimal::new(config.effective_fee_rate() as i64, 6)
+                }
+                Err(e) => {
+                    debug!("Failed to resolve Whirlpools config for {}: {}", pool_address, e);
+                    Decimal::new(fee_rate_raw as i64, 6)
+                }
+            }
+        };
+
+        // Tick spacing: bytes 41-43, right after the config pubkey and bump
+        let tick_spacing = u16::from_le_bytes([account_data[41], account_data[42]]);
+
+        // Active liquidity (bytes 49-65) and sqrt price (bytes 65-81), both u128
+        let liquidity = u128::from_le_bytes(account_data[49..65].try_into().unwrap());
+        let sqrt_price = u128::from_le_bytes(account_data[65..81].try_into().unwrap());
+        // Current tick index (bytes 81-85)
+        let tick_current_index = i32::from_le_bytes(account_data[81..85].try_into().unwrap());
+        let tick_arrays = self.fetch_tick_arrays(pool_address, tick_current_index, tick_spacing).await
+            .unwrap_or_else(|e| {
+                debug!("Failed to fetch tick arrays for {}: {}", pool_address, e);
+                Vec::new()
+            });
+
+        // The oracle is a PDA seeded by the whirlpool address
+        let whirlpool_program_id = Pubkey::from_str(WHIRLPOOL_PROGRAM_ID)?;
+        let (oracle, _) = Pubkey::find_program_address(
+            &[b"oracle", pool_address.as_ref()],
+            &whirlpool_program_id,
+        );
 
         let pool = Pool {
             address: *pool_address,
             dex: "orca".to_string(),
-            token_a: TokenInfo {
-                mint: token_a_mint,
-                symbol: "UNK".to_string(), // Will be resolved later
-                decimals: 6, // Default, will be resolved later
-                price_usd: None,
-            },
-            token_b: TokenInfo {
-                mint: token_b_mint,
-                symbol: "UNK".to_string(), // Will be resolved later
-                decimals: 6, // Default, will be resolved later
-                price_usd: None,
-            },
+            token_a: self.resolve_token(&token_a_mint).await,
+            token_b: self.resolve_token(&token_b_mint).await,
             reserve_a,
             reserve_b,
-            fee_percent: Decimal::from_f64_retain(fee_rate)
-                .unwrap_or(Decimal::from_f64_retain(0.003).unwrap()),
+            fee_percent,
             liquidity_usd: Decimal::ZERO, // Will be calculated later
             last_updated: chrono::Utc::now(),
+            metadata: Some(Arc::new(OrcaPoolMeta {
+                vault_a: token_a_vault,
+                vault_b: token_b_vault,
+                oracle,
+                tick_spacing,
+                liquidity,
+                sqrt_price,
+                tick_current_index,
+                fee_rate: fee_rate_raw,
+                tick_arrays,
+            })),
         };
 
         Ok(pool)
     }
 
+    /// Tick arrays covering the current tick and `TICK_ARRAYS_PER_SIDE` on either side.
+    /// Arrays that were never initialized on chain are skipped.
+    async fn fetch_tick_arrays(&self, whirlpool: &Pubkey, tick_current_index: i32, tick_spacing: u16) -> Result<Vec<TickArray>> {
+        if tick_spacing == 0 {
+            anyhow::bail!("Whirlpool has zero tick spacing");
+        }
+        let whirlpool_program_id = Pubkey::from_str(WHIRLPOOL_PROGRAM_ID)?;
+        let ticks_per_array = TICK_ARRAY_SIZE * tick_spacing as i32;
+        let current_start = tick_current_index.div_euclid(ticks_per_array) * ticks_per_array;
+
+        let addresses: Vec<Pubkey> = (-TICK_ARRAYS_PER_SIDE..=TICK_ARRAYS_PER_SIDE)
+            .map(|offset| {
+                let start = current_start + offset * ticks_per_array;
+                Pubkey::find_program_address(
+                    &[b"tick_array", whirlpool.as_ref(), start.to_string().as_bytes()],
+                    &whirlpool_program_id,
+                ).0
+            })
+            .collect();
+
+        let accounts = self.rpc_client.get_multiple_accounts(&addresses).await?;
+        Ok(accounts.into_iter()
+            .flatten()
+            .filter_map(|account| Self::parse_tick_array(&account.data, tick_spacing).ok())
+            .collect())
+    }
+
+    fn parse_tick_array(data: &[u8], tick_spacing: u16) -> Result<TickArray> {
+        // Discriminator, start tick index, then TICK_ARRAY_SIZE ticks
+        if data.len() < 12 + TICK_ARRAY_SIZE as usize * TICK_LEN {
+            anyhow::bail!("Tick array account data too short");
+        }
+        let start_tick_index = i32::from_le_bytes(data[8..12].try_into()?);
+
+        let ticks = (0..TICK_ARRAY_SIZE as usize)
+            .filter_map(|i| {
+                let offset = 12 + i * TICK_LEN;
+                let initialized = data[offset] != 0;
+                initialized.then(|| Tick {
+                    index: start_tick_index + i as i32 * tick_spacing as i32,
+                    liquidity_net: i128::from_le_bytes(data[offset + 1..offset + 17].try_into().unwrap()),
+                })
+            })
+            .collect();
+
+        Ok(TickArray { start_tick_index, tick_spacing, ticks })
+    }
+
     // Removed old fetch_pool_reserves and parse_whirlpool_account methods
     // Now using parse_whirlpool_data which handles everything in one place
 
@@ -166,6 +405,15 @@ impl OrcaClient {
     }
 }
 
+/// `default_protocol_fee_rate` of a `WhirlpoolsConfig` account
+fn parse_default_protocol_fee_rate(config_data: &[u8]) -> Result<u16> {
+    if config_data.len() < WHIRLPOOLS_CONFIG_ACCOUNT_LEN {
+        anyhow::bail!("Whirlpools config account data too short");
+    }
+    let offset = DEFAULT_PROTOCOL_FEE_RATE_OFFSET;
+    Ok(u16::from_le_bytes([config_data[offset], config_data[offset + 1]]))
+}
+
 #[async_trait]
 impl DexClient for OrcaClient {
     async fn fetch_pools(&self) -> Result<Vec<Pool>> {
@@ -234,6 +482,7 @@ impl DexClient for OrcaClient {
                     Ok(updated_pool) => {
                         pool.reserve_a = updated_pool.reserve_a;
                         pool.reserve_b = updated_pool.reserve_b;
+                        pool.metadata = updated_pool.metadata;
                         pool.last_updated = chrono::Utc::now();
                         Ok(())
                     }
@@ -261,4 +510,74 @@ impl DexClient for OrcaClient {
     fn set_console_manager(&mut self, console: Arc<ConsoleManager>) {
         self.console = console;
     }
+
+    fn liquidity_quality_multiplier(&self) -> f64 {
+        // CLMM liquidity sits near the current price, so fills are tighter
+        1.5
+    }
+
+    fn active_liquidity(&self, pool: &Pool) -> Option<(f64, f64)> {
+        pool.get_metadata::<OrcaPoolMeta>()?.active_liquidity()
+    }
+
+    /// Whirlpools are read straight from chain, so RPC health is enough
+    async fn health_check(&self) -> Result<HealthStatus> {
+        let started = std::time::Instant::now();
+        let probe = self.rpc_client.get_health().await;
+        let pool_count = self.pools_cache.read().await.len();
+        Ok(HealthStatus::from_probe(started, probe.map(|_| pool_count)))
+    }
+
+    async fn reinitialize(&self) -> Result<()> {
+        // HTTP clients are created per request, so only the pool cache needs resetting
+        self.pools_cache.write().await.clear();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_whirlpool_discriminator() {
+        let expected = solana_sdk::hash::hash(b"account:Whirlpool").to_bytes();
+        assert_eq!(WHIRLPOOL_DISCRIMINATOR, expected[..8]);
+    }
+
+    #[test]
+    fn test_parse_default_protocol_fee_rate() {
+        let mut config_data = vec![0u8; WHIRLPOOLS_CONFIG_ACCOUNT_LEN];
+        config_data[104..106].copy_from_slice(&300u16.to_le_bytes());
+        assert_eq!(parse_default_protocol_fee_rate(&config_data).unwrap(), 300);
+        assert!(parse_default_protocol_fee_rate(&config_data[..100]).is_err());
+
+        let config = WhirlpoolConfig {
+            whirlpools_config: Pubkey::new_unique(),
+            fee_rate: 3000,
+            default_protocol_fee_rate: 300,
+        };
+        // 0.3% trade fee, 3% of which goes to the protocol
+        assert_eq!(config.effective_fee_rate(), 3000);
+        assert_eq!(config.protocol_fee_rate(), 90);
+    }
+
+    #[test]
+    fn test_active_liquidity() {
+        let mut meta = OrcaPoolMeta {
+            vault_a: Pubkey::new_unique(),
+            vault_b: Pubkey::new_unique(),
+            oracle: Pubkey::new_unique(),
+            tick_spacing: 1,
+            liquidity: 10_000_000_000,
+            sqrt_price: 1u128 << 64,
+            tick_current_index: 0,
+            fee_rate: 3_000,
+            tick_arrays: Vec::new(),
+        };
+        assert_eq!(meta.active_liquidity(), Some((10_000_000_000.0, 1.0)));
+
+        meta.liquidity = 0;
+        assert_eq!(meta.active_liquidity(), None);
+    }
 }
@@ -1,6 +1,6 @@
 use crate::{
-    dex::DexClient,
-    models::{Pool, TokenInfo},
+    dex::{DexClient, HealthStatus},
+    models::{OrderBookData, Pool, TokenInfo},
     utils::rpc::RpcClient,
 };
 use anyhow::{Context, Result};
@@ -24,10 +24,42 @@ struct PhoenixMarket {
     pub _min_base_order_size: f64,
 }
 
+pub const PHOENIX_PROGRAM_ID: &str = "PhoeNiXZ8ByJGLkxNfZRnkUfjvmuYqLR89jjFHGqdXY";
+
+// MarketHeader layout: discriminant, status, size params (3 x u64), then
+// base TokenParams (72 bytes), base_lot_size, quote TokenParams, quote_lot_size
+const BASE_LOT_SIZE_OFFSET: usize = 112;
+const QUOTE_LOT_SIZE_OFFSET: usize = 192;
+
+/// Lot sizes Phoenix orders are denominated in
+#[derive(Debug, Clone)]
+pub struct PhoenixPoolMeta {
+    pub base_lot_size: u64,
+    pub quote_lot_size: u64,
+    /// Best bid and ask when the book could be read
+    pub order_book: Option<OrderBookData>,
+}
+
+impl PhoenixPoolMeta {
+    fn from_market_data(data: &[u8]) -> Option<Self> {
+        let read_u64 = |offset: usize| {
+            data.get(offset..offset + 8)
+                .map(|bytes| u64::from_le_bytes(bytes.try_into().unwrap()))
+        };
+        let base_lot_size = read_u64(BASE_LOT_SIZE_OFFSET)?;
+        let quote_lot_size = read_u64(QUOTE_LOT_SIZE_OFFSET)?;
+        if base_lot_size == 0 || quote_lot_size == 0 {
+            return None;
+        }
+        Some(Self { base_lot_size, quote_lot_size, order_book: None })
+    }
+}
+
 pub struct PhoenixClient {
     rpc_client: Arc<RpcClient>,
     pools_cache: tokio::sync::RwLock<HashMap<String, Pool>>,
     console: Arc<ConsoleManager>,
+    default_fee: Decimal,
 }
 
 impl PhoenixClient {
@@ -36,9 +68,29 @@ impl PhoenixClient {
             rpc_client,
             pools_cache: tokio::sync::RwLock::new(HashMap::new()),
             console,
+            default_fee: Decimal::new(1, 4),
         })
     }
 
+    /// Fee, as a fraction, for pools whose exact fee isn't available on chain
+    pub fn with_default_fee(mut self, default_fee: Decimal) -> Self {
+        self.default_fee = default_fee;
+        self
+    }
+
+    /// Convert a lot count into native token units
+    pub fn lots_to_native(lots: u64, lot_size: u64) -> u64 {
+        lots.saturating_mul(lot_size)
+    }
+
+    /// Convert native token units into whole lots, rounding down
+    pub fn native_to_lots(native: u64, lot_size: u64) -> u64 {
+        if lot_size == 0 {
+            return 0;
+        }
+        native / lot_size
+    }
+
     async fn fetch_phoenix_markets_from_api(&self) -> Result<Vec<PhoenixMarket>> {
         let client = reqwest::Client::new();
         
@@ -147,6 +199,16 @@ impl PhoenixClient {
 
         // For Phoenix (orderbook DEX), we need to fetch the current bid/ask book
         let (base_liquidity, quote_liquidity) = self.fetch_orderbook_liquidity(&market_address).await?;
+        let lot_sizes = self.fetch_lot_sizes(&market_address).await;
+        let order_book = match self.get_best_bid_ask(&market_address).await? {
+            (Some(best_bid), Some(best_ask)) => Some(OrderBookData { best_bid, best_ask }),
+            _ => None,
+        };
+        let metadata: Option<Arc<dyn std::any::Any + Send + Sync>> = match (lot_sizes, order_book) {
+            (Some(meta), order_book) => Some(Arc::new(PhoenixPoolMeta { order_book, ..meta })),
+            (None, Some(order_book)) => Some(Arc::new(order_book)),
+            (None, None) => None,
+        };
 
         let pool = Pool {
             address: market_address,
@@ -165,14 +227,32 @@ impl PhoenixClient {
             },
             reserve_a: base_liquidity,
             reserve_b: quote_liquidity,
-            fee_percent: Decimal::from_f64_retain(0.0001).unwrap(), // Phoenix typically uses lower fees
+            fee_percent: self.default_fee, // Taker fees are per market and not in the market list
             liquidity_usd: Decimal::ZERO, // Will be calculated separately
             last_updated: chrono::Utc::now(),
+            metadata,
         };
 
         Ok(pool)
     }
 
+    async fn fetch_lot_sizes(&self, market_address: &Pubkey) -> Option<PhoenixPoolMeta> {
+        match self.rpc_client.try_get_account(market_address).await {
+            Ok(Some(account)) => {
+                let meta = PhoenixPoolMeta::from_market_data(&account.data);
+                if meta.is_none() {
+                    warn!("Could not parse lot sizes for Phoenix market {}", market_address);
+                }
+                meta
+            }
+            Ok(None) => None,
+            Err(e) => {
+                warn!("Failed to fetch Phoenix market header for {}: {}", market_address, e);
+                None
+            }
+        }
+    }
+
     async fn fetch_orderbook_liquidity(&self, market_address: &Pubkey) -> Result<(u64, u64)> {
         // Phoenix uses orderbook model, so we need to sum up the liquidity in the book
         // This is a simplified implementation that would need to parse the actual orderbook
@@ -204,7 +284,7 @@ impl PhoenixClient {
         }
     }
 
-    async fn _get_best_bid_ask(&self, market_address: &Pubkey) -> Result<(Option<f64>, Option<f64>)> {
+    async fn get_best_bid_ask(&self, market_address: &Pubkey) -> Result<(Option<f64>, Option<f64>)> {
         // Fetch the best bid and ask prices from the orderbook
         // This would involve parsing the Phoenix orderbook data structure
         match self.rpc_client.try_get_account(market_address).await {
@@ -310,4 +390,54 @@ impl DexClient for PhoenixClient {
     fn set_console_manager(&mut self, console: Arc<ConsoleManager>) {
         self.console = console;
     }
+
+    fn liquidity_quality_multiplier(&self) -> f64 {
+        // IOC orders against a thin book may only partially fill
+        0.8
+    }
+
+    /// Lists markets but converts only the first, instead of reading every market account
+    async fn health_check(&self) -> Result<HealthStatus> {
+        let started = std::time::Instant::now();
+        let probe = async {
+            let markets = self.fetch_phoenix_markets_from_api().await?;
+            let market = markets.first().context("Phoenix market list is empty")?;
+            self.convert_phoenix_market(market).await?;
+            Ok(markets.len())
+        }
+        .await;
+        Ok(HealthStatus::from_probe(started, probe))
+    }
+
+    async fn reinitialize(&self) -> Result<()> {
+        // HTTP clients are created per request, so only the pool cache needs resetting
+        self.pools_cache.write().await.clear();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lot_size_conversion() {
+        assert_eq!(PhoenixClient::native_to_lots(1_234_567, 1_000), 1_234);
+        assert_eq!(PhoenixClient::lots_to_native(1_234, 1_000), 1_234_000);
+        assert_eq!(PhoenixClient::native_to_lots(500, 0), 0);
+    }
+
+    #[test]
+    fn test_lot_sizes_parsed_from_market_header() {
+        let mut data = vec![0u8; 256];
+        data[BASE_LOT_SIZE_OFFSET..BASE_LOT_SIZE_OFFSET + 8].copy_from_slice(&1_000u64.to_le_bytes());
+        data[QUOTE_LOT_SIZE_OFFSET..QUOTE_LOT_SIZE_OFFSET + 8].copy_from_slice(&10u64.to_le_bytes());
+
+        let meta = PhoenixPoolMeta::from_market_data(&data).unwrap();
+        assert_eq!(meta.base_lot_size, 1_000);
+        assert_eq!(meta.quote_lot_size, 10);
+
+        assert!(PhoenixPoolMeta::from_market_data(&data[..150]).is_none());
+        assert!(PhoenixPoolMeta::from_market_data(&[0u8; 256]).is_none());
+    }
 }
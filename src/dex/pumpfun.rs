@@ -18,10 +18,39 @@ use rust_decimal::prelude::FromPrimitive;
 
 pub const PUMPFUN_PROGRAM_ID: &str = "6EF8rrecthR5Dkzon8Nwu78hRvfCKubJ14M5uBEwF6P";
 pub const PUMPFUN_API_BASE: &str = "https://frontend-api.pump.fun";
+/// Real SOL a bonding curve holds when the token graduates (85 SOL)
+pub const PUMPFUN_GRADUATION_LAMPORTS: f64 = 85_000_000_000.0;
 
 // Pump.fun bonding curve discriminator
 
 
+/// Bonding curve accounts needed to build buy/sell instructions
+#[derive(Debug, Clone)]
+pub struct PumpFunPoolMeta {
+    pub bonding_curve: Pubkey,
+    pub associated_bonding_curve: Pubkey,
+    /// Curve state; only known for pools read from the chain
+    pub curve: Option<PumpFunData>,
+}
+
+/// Bonding curve state the screener can use for confidence scoring
+#[derive(Debug, Clone, Copy)]
+pub struct PumpFunData {
+    pub graduation_progress: f64,
+    pub real_sol_reserves: u64,
+    pub virtual_sol_reserves: u64,
+}
+
+impl PumpFunData {
+    fn from_curve(curve: &PumpFunCurve) -> Self {
+        Self {
+            graduation_progress: PumpFunDex::graduation_progress(curve),
+            real_sol_reserves: curve.real_sol_reserves,
+            virtual_sol_reserves: curve.virtual_sol_reserves,
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct PumpFunCurve {
     pub mint: Pubkey,
@@ -40,6 +69,9 @@ pub struct PumpFunDex {
     pub client: Arc<RpcClient>,
     pub program_id: Pubkey,
     pub console_manager: Option<Arc<ConsoleManager>>,
+    pub max_pools: usize,
+    pub min_market_cap: f64,
+    pub max_graduation_progress: f64,
 }
 
 impl PumpFunDex {
@@ -50,9 +82,30 @@ impl PumpFunDex {
             client: rpc_client,
             program_id,
             console_manager: Some(console_manager),
+            max_pools: 20,
+            min_market_cap: 10000.0,
+            max_graduation_progress: 0.90,
         })
     }
 
+    /// Only track the `max_pools` largest coins with at least `min_market_cap` USD market cap
+    pub fn with_limits(mut self, max_pools: usize, min_market_cap: f64) -> Self {
+        self.max_pools = max_pools;
+        self.min_market_cap = min_market_cap;
+        self
+    }
+
+    /// Skip curves filled beyond `max_graduation_progress` of the graduation threshold
+    pub fn with_max_graduation_progress(mut self, max_graduation_progress: f64) -> Self {
+        self.max_graduation_progress = max_graduation_progress;
+        self
+    }
+
+    /// Share of the graduation threshold the curve has filled; 1.0 means it graduates
+    pub fn graduation_progress(curve: &PumpFunCurve) -> f64 {
+        curve.real_sol_reserves as f64 / PUMPFUN_GRADUATION_LAMPORTS
+    }
+
     pub async fn fetch_pools(&self) -> Result<Vec<Pool>> {
         let mut pools = Vec::new();
         
@@ -68,7 +121,7 @@ impl PumpFunDex {
     }
 
     async fn fetch_pools_from_api(&self) -> Result<Vec<Pool>> {
-        let url = format!("{}/coins", PUMPFUN_API_BASE);
+        let url = format!("{}/coins?offset=0&limit=50&sort=market_cap&order=DESC", PUMPFUN_API_BASE);
         
         match reqwest::get(&url).await {
             Ok(response) => {
@@ -76,12 +129,19 @@ impl PumpFunDex {
                     let mut pools = Vec::new();
                     
                     if let Some(coins_array) = coins.as_array() {
-                        for coin in coins_array.iter().take(20) { // Limit to top 20
+                        // Skip micro-caps; they rarely offer arbitrage and bloat the pool cache
+                        let large_caps = coins_array.iter()
+                            .filter(|coin| coin["market_cap"].as_f64().unwrap_or(0.0) >= self.min_market_cap);
+                        for coin in large_caps {
                             if let Some(pool) = self.api_coin_to_pool(coin) {
                                 pools.push(pool);
                             }
                         }
                     }
+
+                    // The API sort is a request, not a guarantee
+                    pools.sort_by_key(|pool| std::cmp::Reverse(pool.liquidity_usd));
+                    pools.truncate(self.max_pools);
                     
                     Ok(pools)
                 } else {
@@ -102,8 +162,11 @@ impl PumpFunDex {
         for (pubkey, account) in accounts {
             if account.data.len() >= 8 && self.is_pumpfun_curve_account(&account.data) {
                 if let Ok(curve_data) = self.parse_pumpfun_curve_data(&account.data) {
-                    // Only include active (incomplete) curves
-                    if !curve_data.complete && curve_data.real_sol_reserves > 0 {
+                    // Only include active (incomplete) curves; near-graduates trade with extreme slippage
+                    if !curve_data.complete
+                        && curve_data.real_sol_reserves > 0
+                        && Self::graduation_progress(&curve_data) <= self.max_graduation_progress
+                    {
                         let pool = self.curve_to_pool(&pubkey, &curve_data)?;
                         pools.push(pool);
                         
@@ -123,6 +186,8 @@ impl PumpFunDex {
         let _name = coin["name"].as_str().unwrap_or("Unknown");
         let symbol = coin["symbol"].as_str().unwrap_or("UNKNOWN");
         let market_cap = coin["market_cap"].as_f64().unwrap_or(0.0);
+        let bonding_curve = coin["bonding_curve"].as_str().and_then(|s| Pubkey::from_str(s).ok());
+        let associated_bonding_curve = coin["associated_bonding_curve"].as_str().and_then(|s| Pubkey::from_str(s).ok());
         
         // Calculate virtual reserves based on market cap
         let virtual_sol_reserves = market_cap / 50.0; // Rough estimate
@@ -152,6 +217,14 @@ impl PumpFunDex {
             fee_percent: Decimal::from_f64(0.01).unwrap(), // 1% fee typical for pump.fun
             liquidity_usd: Decimal::from(market_cap as u64),
             last_updated: chrono::Utc::now(),
+            metadata: match (bonding_curve, associated_bonding_curve) {
+                (Some(bonding_curve), Some(associated_bonding_curve)) => Some(Arc::new(PumpFunPoolMeta {
+                    bonding_curve,
+                    associated_bonding_curve,
+                    curve: None,
+                })),
+                _ => None,
+            },
         })
     }
 
@@ -181,8 +254,13 @@ impl PumpFunDex {
             reserve_a: curve_data.virtual_token_reserves,
             reserve_b: curve_data.virtual_sol_reserves,
             fee_percent: Decimal::from_f64(0.01).unwrap(), // 1% fee
-            liquidity_usd: Decimal::from((curve_data.virtual_token_reserves + curve_data.virtual_sol_reserves) as u64),
+            liquidity_usd: Decimal::from(curve_data.virtual_token_reserves + curve_data.virtual_sol_reserves),
             last_updated: chrono::Utc::now(),
+            metadata: Some(Arc::new(PumpFunPoolMeta {
+                bonding_curve: *curve_pubkey,
+                associated_bonding_curve: curve_data.associated_bonding_curve,
+                curve: Some(PumpFunData::from_curve(curve_data)),
+            })),
         })
     }
 
@@ -283,6 +361,20 @@ impl PumpFunDex {
     pub async fn is_healthy(&self) -> bool {
         self.client.get_health().await.is_ok()
     }
+
+    /// Copy fresh curve reserves into the pool, keeping its curve metadata current
+    fn apply_curve(pool: &mut Pool, curve_data: &PumpFunCurve) {
+        pool.reserve_a = curve_data.virtual_token_reserves;
+        pool.reserve_b = curve_data.virtual_sol_reserves;
+        pool.last_updated = chrono::Utc::now();
+        if let Some(meta) = pool.get_metadata::<PumpFunPoolMeta>() {
+            let meta = PumpFunPoolMeta {
+                curve: Some(PumpFunData::from_curve(curve_data)),
+                ..meta.clone()
+            };
+            pool.metadata = Some(Arc::new(meta));
+        }
+    }
 }
 
 #[async_trait]
@@ -319,9 +411,7 @@ impl DexClient for PumpFunDex {
             // Fallback to blockchain data
             if let Ok(Some(account)) = self.client.try_get_account(&pool.address).await {
                 if let Ok(curve_data) = self.parse_pumpfun_curve_data(&account.data) {
-                    pool.reserve_a = curve_data.virtual_token_reserves;
-                    pool.reserve_b = curve_data.virtual_sol_reserves;
-                    pool.last_updated = chrono::Utc::now();
+                    Self::apply_curve(pool, &curve_data);
                 }
             }
         }
@@ -329,6 +419,12 @@ impl DexClient for PumpFunDex {
         Ok(())
     }
 
+    fn parse_account_data(&self, pool: &mut Pool, data: &[u8]) -> Result<()> {
+        let curve_data = self.parse_pumpfun_curve_data(data)?;
+        Self::apply_curve(pool, &curve_data);
+        Ok(())
+    }
+
     fn get_dex_name(&self) -> &'static str {
         "PumpFun"
     }
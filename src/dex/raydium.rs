@@ -1,18 +1,36 @@
 use crate::{
-    dex::DexClient,
-    models::{Pool, TokenInfo},
-    utils::rpc::RpcClient,
+    dex::{DexClient, HealthStatus},
+    models::{Pool, SwapQuote, TokenInfo},
+    utils::{
+        math::{calculate_clmm_output_amount, invert_sqrt_price_x64, CLMM_FEE_RATE_DENOMINATOR},
+        price_feed::JupiterPriceFeed,
+        rpc::RpcClient,
+        token_registry::{TokenRegistry, UNKNOWN_SYMBOL},
+    },
 };
 use anyhow::{Context, Result};
 use async_trait::async_trait;
-use rust_decimal::Decimal;
+use rust_decimal::{prelude::ToPrimitive, Decimal};
 use serde::Deserialize;
+use solana_client::rpc_filter::{Memcmp, RpcFilterType};
 use solana_sdk::pubkey::Pubkey;
-use std::{collections::HashMap, str::FromStr, sync::Arc};
-use tracing::{debug, error, info};
+use std::{
+    collections::{HashMap, HashSet},
+    str::FromStr,
+    sync::Arc,
+};
+use tracing::{debug, error, info, warn};
 
 use crate::console::ConsoleManager;
 
+pub const RAYDIUM_CLMM_PROGRAM_ID: &str = "CAMMCzo5YL8w4VFF8KVHrK22GGUsp5VTaW7grrKgrWqK";
+
+/// Anchor discriminator of the CLMM `PoolState` account
+const CLMM_POOL_STATE_DISCRIMINATOR: [u8; 8] = [247, 237, 227, 245, 215, 195, 222, 70];
+const CLMM_POOL_STATE_LEN: u64 = 1544;
+/// `trade_fee_rate` offset inside the CLMM `AmmConfig` account
+const AMM_CONFIG_TRADE_FEE_RATE_OFFSET: usize = 47;
+
 #[derive(Debug, Clone, Deserialize)]
 struct RaydiumPool {
     pub id: String,
@@ -21,7 +39,11 @@ struct RaydiumPool {
     pub base_reserve: u64,
     pub quote_reserve: u64,
     pub _lp_mint: String,
-    pub _open_orders: String,
+    pub open_orders: String,
+    #[serde(default)]
+    pub base_vault: String,
+    #[serde(default)]
+    pub quote_vault: String,
     pub _target_orders: String,
     pub _base_decimals: u8,
     pub _quote_decimals: u8,
@@ -37,33 +59,165 @@ struct RaydiumPool {
     pub _system_decimal_value: u64,
 }
 
+/// AMM accounts needed to build swap instructions
+#[derive(Debug, Clone)]
+pub struct RaydiumPoolMeta {
+    pub vault_a: Pubkey,
+    pub vault_b: Pubkey,
+    pub open_orders: Pubkey,
+}
+
+impl RaydiumPoolMeta {
+    fn from_pool(raydium_pool: &RaydiumPool) -> Option<Self> {
+        Some(Self {
+            vault_a: Pubkey::from_str(&raydium_pool.base_vault).ok()?,
+            vault_b: Pubkey::from_str(&raydium_pool.quote_vault).ok()?,
+            open_orders: Pubkey::from_str(&raydium_pool.open_orders).ok()?,
+        })
+    }
+}
+
+/// Concentrated liquidity state of a Raydium CLMM pool. Token 0 is the pool's
+/// `token_a` and token 1 its `token_b`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RaydiumClmmPoolMeta {
+    pub amm_config: Pubkey,
+    pub vault_0: Pubkey,
+    pub vault_1: Pubkey,
+    pub observation: Pubkey,
+    pub tick_spacing: u16,
+    pub tick_current: i32,
+    pub liquidity: u128,
+    /// Q64.64 square root of the token 1 per token 0 price
+    pub sqrt_price_x64: u128,
+    /// Trade fee in hundredths of a basis point
+    pub fee_rate: u32,
+}
+
+impl RaydiumClmmPoolMeta {
+    /// Constant-product reserves that trade identically to the current tick range
+    pub fn virtual_reserves(&self) -> (u64, u64) {
+        let sqrt_price = self.sqrt_price_x64 as f64 / 2f64.powi(64);
+        if sqrt_price <= 0.0 {
+            return (0, 0);
+        }
+        let liquidity = self.liquidity as f64;
+        ((liquidity / sqrt_price) as u64, (liquidity * sqrt_price) as u64)
+    }
+
+    /// Fee tier implied by the tick spacing, for when the AMM config can't be read
+    fn default_fee_rate(tick_spacing: u16) -> u32 {
+        match tick_spacing {
+            1 => 100,
+            10 => 500,
+            120 => 10_000,
+            _ => 2_500,
+        }
+    }
+}
+
+/// Fields of a CLMM `PoolState` account
+#[derive(Debug, Clone)]
+struct ClmmPoolState {
+    token_mint_0: Pubkey,
+    token_mint_1: Pubkey,
+    decimals_0: u8,
+    decimals_1: u8,
+    meta: RaydiumClmmPoolMeta,
+}
+
+impl ClmmPoolState {
+    fn from_account_data(data: &[u8]) -> Result<Self> {
+        if data.len() < 273 || data[..8] != CLMM_POOL_STATE_DISCRIMINATOR {
+            anyhow::bail!("Not a Raydium CLMM pool state account");
+        }
+
+        let pubkey = |offset: usize| Pubkey::try_from(&data[offset..offset + 32]);
+        let tick_spacing = u16::from_le_bytes(data[235..237].try_into()?);
+
+        Ok(Self {
+            token_mint_0: pubkey(73)?,
+            token_mint_1: pubkey(105)?,
+            decimals_0: data[233],
+            decimals_1: data[234],
+            meta: RaydiumClmmPoolMeta {
+                amm_config: pubkey(9)?,
+                vault_0: pubkey(137)?,
+                vault_1: pubkey(169)?,
+                observation: pubkey(201)?,
+                tick_spacing,
+                liquidity: u128::from_le_bytes(data[237..253].try_into()?),
+                sqrt_price_x64: u128::from_le_bytes(data[253..269].try_into()?),
+                tick_current: i32::from_le_bytes(data[269..273].try_into()?),
+                fee_rate: RaydiumClmmPoolMeta::default_fee_rate(tick_spacing),
+            },
+        })
+    }
+}
+
 #[derive(Debug, Clone, Deserialize)]
 struct RaydiumPoolsResponse {
     pub official: Vec<RaydiumPool>,
     pub un_official: Vec<RaydiumPool>,
 }
 
+/// Full AMM pool list; large, so health checks only send a HEAD request
+const RAYDIUM_LIQUIDITY_URL: &str = "https://api.raydium.io/v2/sdk/liquidity/mainnet.json";
+
 pub struct RaydiumClient {
     rpc_client: Arc<RpcClient>,
     pools_cache: tokio::sync::RwLock<HashMap<String, Pool>>,
     console: Arc<ConsoleManager>,
+    price_feed: JupiterPriceFeed,
+    token_registry: Arc<TokenRegistry>,
+    default_fee: Decimal,
 }
 
 impl RaydiumClient {
-    pub fn new(rpc_client: Arc<RpcClient>, console: Arc<ConsoleManager>) -> Result<Self> {
+    pub fn new(
+        rpc_client: Arc<RpcClient>,
+        console: Arc<ConsoleManager>,
+        token_registry: Arc<TokenRegistry>,
+    ) -> Result<Self> {
         Ok(Self {
             rpc_client,
             pools_cache: tokio::sync::RwLock::new(HashMap::new()),
             console,
+            price_feed: JupiterPriceFeed::new(),
+            token_registry,
+            default_fee: Decimal::new(25, 4),
         })
     }
 
+    /// Fee, as a fraction, for pools whose exact fee isn't available on chain
+    pub fn with_default_fee(mut self, default_fee: Decimal) -> Self {
+        self.default_fee = default_fee;
+        self
+    }
+
+    /// Registry entry for `mint`, falling back to an unknown token with the mint's
+    /// own decimals, or `decimals` if the mint account can't be read either
+    async fn resolve_token(&self, mint: &Pubkey, decimals: u8) -> TokenInfo {
+        match self.token_registry.resolve(mint).await {
+            Ok(token) => token,
+            Err(e) => {
+                debug!("Failed to resolve token {}: {}", mint, e);
+                TokenInfo {
+                    mint: *mint,
+                    symbol: UNKNOWN_SYMBOL.to_string(),
+                    decimals: self.token_registry.decimals(mint).await.unwrap_or(decimals),
+                    price_usd: None,
+                }
+            }
+        }
+    }
+
     async fn fetch_raydium_pools_from_api(&self) -> Result<Vec<RaydiumPool>> {
         let client = reqwest::Client::new();
         
         // Use the new v2 SDK endpoint with proper timeout handling
         let response = client
-            .get("https://api.raydium.io/v2/sdk/liquidity/mainnet.json")
+            .get(RAYDIUM_LIQUIDITY_URL)
             .header("User-Agent", "solana-arbitrage-bot/1.0")
             .header("Accept", "application/json")
             .timeout(std::time::Duration::from_secs(120)) // Increased timeout for large file
@@ -105,7 +259,9 @@ impl RaydiumClient {
                     base_reserve: 1000000000, // 1 SOL
                     quote_reserve: 100000000, // 100 USDC
                     _lp_mint: "".to_string(),
-                    _open_orders: "".to_string(),
+                    open_orders: "".to_string(),
+                    base_vault: "".to_string(),
+                    quote_vault: "".to_string(),
                     _target_orders: "".to_string(),
                     _state: 0,
                     _reset_flag: 0,
@@ -138,7 +294,22 @@ impl RaydiumClient {
         Ok(all_pools)
     }
 
-    async fn convert_raydium_pool(&self, raydium_pool: &RaydiumPool) -> Result<Pool> {
+    async fn fetch_token_prices(&self, mints: impl IntoIterator<Item = Pubkey>) -> HashMap<Pubkey, f64> {
+        let mints: Vec<Pubkey> = mints.into_iter()
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect();
+
+        match self.price_feed.get_prices(&mints).await {
+            Ok(prices) => prices,
+            Err(e) => {
+                warn!("Failed to fetch token prices, using quote-side liquidity estimate: {}", e);
+                HashMap::new()
+            }
+        }
+    }
+
+    async fn convert_raydium_pool(&self, raydium_pool: &RaydiumPool, prices: &HashMap<Pubkey, f64>) -> Result<Pool> {
         let pool_address = Pubkey::from_str(&raydium_pool.id)
             .context("Invalid pool address")?;
 
@@ -151,63 +322,166 @@ impl RaydiumClient {
         // Get current reserves (Raydium provides them in the API response)
         let (reserve_a, reserve_b) = (raydium_pool.base_reserve, raydium_pool.quote_reserve);
 
-        // Calculate liquidity in USD (simplified)
-        let liquidity_usd = self.estimate_liquidity_usd(reserve_a, reserve_b, raydium_pool._base_decimals, raydium_pool._quote_decimals).await;
+        let price_a = prices.get(&base_mint).copied();
+        let price_b = prices.get(&quote_mint).copied();
+        // Raydium API doesn't provide symbols, and its decimals are only a fallback
+        let token_a = TokenInfo {
+            price_usd: price_a.and_then(Decimal::from_f64_retain),
+            ..self.resolve_token(&base_mint, raydium_pool._base_decimals).await
+        };
+        let token_b = TokenInfo {
+            price_usd: price_b.and_then(Decimal::from_f64_retain),
+            ..self.resolve_token(&quote_mint, raydium_pool._quote_decimals).await
+        };
+        let liquidity_usd = Pool::estimate_liquidity_usd(
+            reserve_a,
+            reserve_b,
+            token_a.decimals,
+            token_b.decimals,
+            price_a,
+            price_b,
+        );
 
         let pool = Pool {
             address: pool_address,
             dex: "raydium".to_string(),
+            token_a,
+            token_b,
+            reserve_a,
+            reserve_b,
+            fee_percent: self.default_fee, // The v2 pool list carries no fee
+            liquidity_usd,
+            last_updated: chrono::Utc::now(),
+            metadata: RaydiumPoolMeta::from_pool(raydium_pool)
+                .map(|meta| Arc::new(meta) as Arc<dyn std::any::Any + Send + Sync>),
+        };
+
+        Ok(pool)
+    }
+
+    /// Active pools of the Raydium CLMM program, with fee tiers read from their AMM configs
+    pub async fn fetch_raydium_clmm_pools(&self) -> Result<Vec<Pool>> {
+        let program_id = Pubkey::from_str(RAYDIUM_CLMM_PROGRAM_ID)?;
+        let accounts = self.rpc_client.get_program_accounts_with_filters(&program_id, &[
+            RpcFilterType::DataSize(CLMM_POOL_STATE_LEN),
+            RpcFilterType::Memcmp(Memcmp::new_raw_bytes(0, CLMM_POOL_STATE_DISCRIMINATOR.to_vec())),
+        ]).await?;
+
+        let mut states: Vec<(Pubkey, ClmmPoolState)> = accounts.into_iter()
+            .filter_map(|(address, account)| match ClmmPoolState::from_account_data(&account.data) {
+                Ok(state) if state.meta.liquidity > 0 => Some((address, state)),
+                Ok(_) => None,
+                Err(e) => {
+                    debug!("Skipping Raydium CLMM account {}: {}", address, e);
+                    None
+                }
+            })
+            .collect();
+
+        let fee_rates = self.fetch_clmm_fee_rates(states.iter().map(|(_, state)| state.meta.amm_config)).await;
+        for (_, state) in &mut states {
+            if let Some(&fee_rate) = fee_rates.get(&state.meta.amm_config) {
+                state.meta.fee_rate = fee_rate;
+            }
+        }
+
+        let prices = self.fetch_token_prices(
+            states.iter().flat_map(|(_, state)| [state.token_mint_0, state.token_mint_1])
+        ).await;
+        let mut pools: Vec<Pool> = states.into_iter()
+            .map(|(address, state)| Self::convert_clmm_pool(address, state, &prices))
+            .collect();
+
+        // Decimals come from the pool state; only the symbols need the registry
+        let mints: Vec<Pubkey> = pools.iter().flat_map(|pool| [pool.token_a.mint, pool.token_b.mint]).collect();
+        if let Err(e) = self.token_registry.resolve_many(&mints).await {
+            warn!("Failed to resolve Raydium CLMM token metadata: {}", e);
+        }
+        for pool in &mut pools {
+            for token in [&mut pool.token_a, &mut pool.token_b] {
+                if let Ok(resolved) = self.token_registry.resolve(&token.mint).await {
+                    token.symbol = resolved.symbol;
+                }
+            }
+        }
+
+        debug!("Fetched {} Raydium CLMM pools", pools.len());
+        Ok(pools)
+    }
+
+    /// `trade_fee_rate` of each AMM config; configs that can't be read are left out
+    async fn fetch_clmm_fee_rates(&self, configs: impl IntoIterator<Item = Pubkey>) -> HashMap<Pubkey, u32> {
+        let configs: Vec<Pubkey> = configs.into_iter().collect::<HashSet<_>>().into_iter().collect();
+        let mut fee_rates = HashMap::new();
+
+        for chunk in configs.chunks(100) {
+            match self.rpc_client.get_multiple_accounts(chunk).await {
+                Ok(accounts) => {
+                    for (config, account) in chunk.iter().zip(accounts) {
+                        let fee_rate = account
+                            .and_then(|account| account.data.get(AMM_CONFIG_TRADE_FEE_RATE_OFFSET..AMM_CONFIG_TRADE_FEE_RATE_OFFSET + 4)
+                                .and_then(|bytes| bytes.try_into().ok())
+                                .map(u32::from_le_bytes));
+                        if let Some(fee_rate) = fee_rate.filter(|rate| *rate < CLMM_FEE_RATE_DENOMINATOR) {
+                            fee_rates.insert(*config, fee_rate);
+                        }
+                    }
+                }
+                Err(e) => warn!("Failed to fetch Raydium CLMM configs, using tick spacing fee tiers: {}", e),
+            }
+        }
+
+        fee_rates
+    }
+
+    fn convert_clmm_pool(address: Pubkey, state: ClmmPoolState, prices: &HashMap<Pubkey, f64>) -> Pool {
+        let (reserve_a, reserve_b) = state.meta.virtual_reserves();
+        let price_a = prices.get(&state.token_mint_0).copied();
+        let price_b = prices.get(&state.token_mint_1).copied();
+
+        Pool {
+            address,
+            dex: "raydium".to_string(),
             token_a: TokenInfo {
-                mint: base_mint,
-                symbol: "UNK".to_string(), // Raydium API doesn't always provide symbols
-                decimals: raydium_pool._base_decimals,
-                price_usd: None,
+                mint: state.token_mint_0,
+                symbol: "UNK".to_string(),
+                decimals: state.decimals_0,
+                price_usd: price_a.and_then(Decimal::from_f64_retain),
             },
             token_b: TokenInfo {
-                mint: quote_mint,
+                mint: state.token_mint_1,
                 symbol: "UNK".to_string(),
-                decimals: raydium_pool._quote_decimals,
-                price_usd: None,
+                decimals: state.decimals_1,
+                price_usd: price_b.and_then(Decimal::from_f64_retain),
             },
             reserve_a,
             reserve_b,
-            fee_percent: Decimal::from_f64_retain(0.0025).unwrap(), // Raydium typically uses 0.25%
-            liquidity_usd,
+            fee_percent: Decimal::new(state.meta.fee_rate as i64, 6),
+            liquidity_usd: Pool::estimate_liquidity_usd(
+                reserve_a,
+                reserve_b,
+                state.decimals_0,
+                state.decimals_1,
+                price_a,
+                price_b,
+            ),
             last_updated: chrono::Utc::now(),
-        };
-
-        Ok(pool)
+            metadata: Some(Arc::new(state.meta)),
+        }
     }
 
-    async fn estimate_liquidity_usd(&self, reserve_a: u64, reserve_b: u64, decimals_a: u8, decimals_b: u8) -> Decimal {
-        // Simplified liquidity estimation
-        // In a real implementation, you'd fetch token prices from a price feed
-        let _reserve_a_normalized = reserve_a as f64 / 10_f64.powi(decimals_a as i32);
-        let reserve_b_normalized = reserve_b as f64 / 10_f64.powi(decimals_b as i32);
-        
-        // Assume the quote token (token B) might be USDC/USDT with ~$1 value
-        // This is a rough approximation and should be replaced with real price data
-        let estimated_liquidity = reserve_b_normalized * 2.0; // Double the quote token value
-        
-        Decimal::from_f64_retain(estimated_liquidity).unwrap_or(Decimal::ZERO)
+    /// Parse Raydium AMM account data to extract reserves
+    // This is a simplified implementation - real parsing would be more complex
+    fn reserves_from_account_data(data: &[u8]) -> Option<(u64, u64)> {
+        let reserve_a = u64::from_le_bytes(data.get(0..8)?.try_into().ok()?);
+        let reserve_b = u64::from_le_bytes(data.get(8..16)?.try_into().ok()?);
+        Some((reserve_a, reserve_b))
     }
 
     async fn fetch_pool_reserves(&self, pool_address: &Pubkey) -> Result<(u64, u64)> {
         match self.rpc_client.try_get_account(pool_address).await {
             Ok(Some(account)) => {
-                // Parse Raydium AMM account data to extract reserves
-                // This is a simplified implementation - real parsing would be more complex
-                if account.data.len() >= 16 {
-                    let reserve_a = u64::from_le_bytes(
-                        account.data[0..8].try_into().unwrap_or([0; 8])
-                    );
-                    let reserve_b = u64::from_le_bytes(
-                        account.data[8..16].try_into().unwrap_or([0; 8])
-                    );
-                    Ok((reserve_a, reserve_b))
-                } else {
-                    Ok((0, 0))
-                }
+                Ok(Self::reserves_from_account_data(&account.data).unwrap_or((0, 0)))
             }
             Ok(None) => {
                 debug!("Pool account not found for {}, using zero reserves", pool_address);
@@ -237,11 +511,23 @@ impl DexClient for RaydiumClient {
                     &format!("{} pools from API", raydium_pools.len())
                 );
                 
+                let prices = self.fetch_token_prices(
+                    raydium_pools.iter()
+                        .flat_map(|pool| [&pool.base_mint, &pool.quote_mint])
+                        .filter_map(|mint| Pubkey::from_str(mint).ok())
+                ).await;
+                let mints: Vec<Pubkey> = raydium_pools.iter()
+                    .flat_map(|pool| [&pool.base_mint, &pool.quote_mint])
+                    .filter_map(|mint| Pubkey::from_str(mint).ok())
+                    .collect();
+                if let Err(e) = self.token_registry.resolve_many(&mints).await {
+                    warn!("Failed to resolve Raydium token metadata: {}", e);
+                }
                 let mut pools = Vec::new();
                 let mut _processed = 0;
 
                 for raydium_pool in raydium_pools.iter() {
-                    match self.convert_raydium_pool(raydium_pool).await {
+                    match self.convert_raydium_pool(raydium_pool, &prices).await {
                         Ok(pool) => {
                             pools.push(pool);
                             _processed += 1;
@@ -253,6 +539,11 @@ impl DexClient for RaydiumClient {
                     }
                 }
 
+                match self.fetch_raydium_clmm_pools().await {
+                    Ok(clmm_pools) => pools.extend(clmm_pools),
+                    Err(e) => warn!("Failed to fetch Raydium CLMM pools: {}", e),
+                }
+
                 // Update cache
                 let mut cache = self.pools_cache.write().await;
                 cache.clear();
@@ -292,6 +583,11 @@ impl DexClient for RaydiumClient {
     }
 
     async fn update_pool_reserves(&self, pool: &mut Pool) -> anyhow::Result<()> {
+        if pool.get_metadata::<RaydiumClmmPoolMeta>().is_some() {
+            let account = self.rpc_client.get_account(&pool.address).await?;
+            return self.parse_account_data(pool, &account.data);
+        }
+
         let (reserve_a, reserve_b) = self.fetch_pool_reserves(&pool.address).await?;
         pool.reserve_a = reserve_a;
         pool.reserve_b = reserve_b;
@@ -299,11 +595,128 @@ impl DexClient for RaydiumClient {
         Ok(())
     }
 
+    fn parse_account_data(&self, pool: &mut Pool, data: &[u8]) -> Result<()> {
+        let previous_fee_rate = pool.get_metadata::<RaydiumClmmPoolMeta>().map(|meta| meta.fee_rate);
+        // Pools restored from the disk cache have no metadata, so recognise CLMM state by its discriminator
+        if previous_fee_rate.is_some() || data.starts_with(&CLMM_POOL_STATE_DISCRIMINATOR) {
+            let mut meta = ClmmPoolState::from_account_data(data)?.meta;
+            // The fee tier comes from the AMM config, not the pool account
+            meta.fee_rate = previous_fee_rate.unwrap_or_else(|| {
+                (pool.fee_percent * Decimal::from(CLMM_FEE_RATE_DENOMINATOR)).to_u32().unwrap_or(meta.fee_rate)
+            });
+            (pool.reserve_a, pool.reserve_b) = meta.virtual_reserves();
+            pool.metadata = Some(Arc::new(meta));
+            pool.last_updated = chrono::Utc::now();
+            return Ok(());
+        }
+
+        let (reserve_a, reserve_b) = Self::reserves_from_account_data(data)
+            .context("Raydium pool account data too short")?;
+        pool.reserve_a = reserve_a;
+        pool.reserve_b = reserve_b;
+        pool.last_updated = chrono::Utc::now();
+        Ok(())
+    }
+
     fn get_dex_name(&self) -> &'static str {
         "raydium"
     }
 
+    async fn health_check(&self) -> Result<HealthStatus> {
+        let started = std::time::Instant::now();
+        let probe = async {
+            let response = reqwest::Client::new()
+                .head(RAYDIUM_LIQUIDITY_URL)
+                .header("User-Agent", "solana-arbitrage-bot/1.0")
+                .timeout(std::time::Duration::from_secs(10))
+                .send()
+                .await
+                .context("Failed to reach Raydium API")?;
+            if !response.status().is_success() {
+                anyhow::bail!("Raydium API returned {}", response.status());
+            }
+            Ok(self.pools_cache.read().await.len())
+        }
+        .await;
+        Ok(HealthStatus::from_probe(started, probe))
+    }
+
+    async fn get_swap_quote(&self, input_token: &str, output_token: &str, amount: u64) -> Result<SwapQuote> {
+        let pool = self.get_pool_by_tokens(input_token, output_token).await?
+            .with_context(|| format!("No raydium pool for {} -> {}", input_token, output_token))?;
+        let Some(meta) = pool.get_metadata::<RaydiumClmmPoolMeta>() else {
+            return SwapQuote::from_pool(&pool, input_token, amount);
+        };
+
+        let sqrt_price = if pool.token_a.mint.to_string() == input_token {
+            meta.sqrt_price_x64
+        } else {
+            invert_sqrt_price_x64(meta.sqrt_price_x64)
+        };
+        let output_amount = calculate_clmm_output_amount(sqrt_price, meta.liquidity, amount, meta.fee_rate)?;
+        let fee_amount = (amount as u128 * meta.fee_rate as u128 / CLMM_FEE_RATE_DENOMINATOR as u128) as u64;
+
+        // Shortfall against the spot price, after fees
+        let spot_price = (sqrt_price as f64 / 2f64.powi(64)).powi(2);
+        let spot_output = (amount - fee_amount) as f64 * spot_price;
+        let price_impact_percent = if spot_output > 0.0 {
+            (1.0 - output_amount as f64 / spot_output).max(0.0) * 100.0
+        } else {
+            0.0
+        };
+
+        Ok(SwapQuote {
+            input_amount: amount,
+            output_amount,
+            fee_amount,
+            price_impact_percent,
+        })
+    }
+
     fn set_console_manager(&mut self, console: Arc<ConsoleManager>) {
         self.console = console;
     }
+
+    async fn reinitialize(&self) -> Result<()> {
+        // HTTP clients are created per request, so only the pool cache needs resetting
+        self.pools_cache.write().await.clear();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_clmm_pool_state() {
+        let (mint_0, mint_1, config) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let mut data = vec![0u8; CLMM_POOL_STATE_LEN as usize];
+        data[..8].copy_from_slice(&CLMM_POOL_STATE_DISCRIMINATOR);
+        data[9..41].copy_from_slice(config.as_ref());
+        data[73..105].copy_from_slice(mint_0.as_ref());
+        data[105..137].copy_from_slice(mint_1.as_ref());
+        data[233] = 9;
+        data[234] = 6;
+        data[235..237].copy_from_slice(&10u16.to_le_bytes());
+        data[237..253].copy_from_slice(&1_000_000_000u128.to_le_bytes());
+        // Price of 4 token 1 per token 0
+        data[253..269].copy_from_slice(&(2u128 << 64).to_le_bytes());
+        data[269..273].copy_from_slice(&(-13_863i32).to_le_bytes());
+
+        let state = ClmmPoolState::from_account_data(&data).unwrap();
+        assert_eq!((state.token_mint_0, state.token_mint_1), (mint_0, mint_1));
+        assert_eq!((state.decimals_0, state.decimals_1), (9, 6));
+        assert_eq!(state.meta.amm_config, config);
+        assert_eq!(state.meta.tick_current, -13_863);
+        // Tick spacing 10 is the 0.05% tier
+        assert_eq!(state.meta.fee_rate, 500);
+        assert_eq!(state.meta.virtual_reserves(), (500_000_000, 2_000_000_000));
+
+        let pool = RaydiumClient::convert_clmm_pool(Pubkey::new_unique(), state, &HashMap::new());
+        assert_eq!(pool.fee_percent, Decimal::new(5, 4));
+
+        data[0] = 0;
+        assert!(ClmmPoolState::from_account_data(&data).is_err());
+    }
 }
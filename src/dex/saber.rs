@@ -1,11 +1,13 @@
 use crate::models::{Pool, TokenInfo};
-use anyhow::Result;
+use crate::utils::math::calculate_stable_swap_output;
+use anyhow::{Context, Result};
 
+use solana_client::rpc_filter::RpcFilterType;
 use solana_sdk::pubkey::Pubkey;
 use std::str::FromStr;
 use chrono;
 use rust_decimal::Decimal;
-use rust_decimal::prelude::FromPrimitive;
+use tracing::{debug, info};
 use crate::dex::DexClient;
 use crate::console::ConsoleManager;
 use async_trait::async_trait;
@@ -14,10 +16,27 @@ use crate::utils::rpc::RpcClient as CustomRpcClient;
 
 pub const SABER_PROGRAM_ID: &str = "SSwpkEEcbUqx4vtoEByFjSkhKdCT862DNVb52nZg1UZ";
 
-// Saber Stable Swap pool discriminator
+/// Size of a packed `SwapInfo` account; Saber predates Anchor, so there is no discriminator
+pub const SWAP_INFO_LEN: usize = 395;
+const INITIAL_AMP_OFFSET: usize = 3;
+const TARGET_AMP_OFFSET: usize = 11;
+const START_RAMP_TS_OFFSET: usize = 19;
+const STOP_RAMP_TS_OFFSET: usize = 27;
+const TOKEN_A_RESERVES_OFFSET: usize = 107;
+const TOKEN_B_RESERVES_OFFSET: usize = 139;
+const POOL_MINT_OFFSET: usize = 171;
+const TOKEN_A_MINT_OFFSET: usize = 203;
+const TOKEN_B_MINT_OFFSET: usize = 235;
+const ADMIN_TRADE_FEE_NUMERATOR_OFFSET: usize = 331;
+const ADMIN_TRADE_FEE_DENOMINATOR_OFFSET: usize = 339;
+const TRADE_FEE_NUMERATOR_OFFSET: usize = 363;
+const TRADE_FEE_DENOMINATOR_OFFSET: usize = 371;
+/// Offset of `amount` in an SPL token account
+const TOKEN_AMOUNT_OFFSET: usize = 64;
+/// `getMultipleAccounts` accepts at most this many addresses per call
+const MAX_ACCOUNTS_PER_BATCH: usize = 100;
 
-
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct SaberPool {
     pub token_a_mint: Pubkey,
     pub token_b_mint: Pubkey,
@@ -28,7 +47,58 @@ pub struct SaberPool {
     pub fee_denominator: u64,
     pub admin_fee_numerator: u64,
     pub admin_fee_denominator: u64,
-    pub amp_factor: u64,
+    /// Amplification at the start of the current ramp
+    pub initial_amp_factor: u64,
+    /// Amplification once the ramp ends; the only value for pools that never ramped
+    pub target_amp_factor: u64,
+    pub start_ramp_ts: i64,
+    pub stop_ramp_ts: i64,
+}
+
+impl SaberPool {
+    /// Amplification in effect at unix time `now`, interpolated while a ramp is running
+    pub fn amp_factor(&self, now: i64) -> u64 {
+        if now >= self.stop_ramp_ts || self.stop_ramp_ts <= self.start_ramp_ts {
+            return self.target_amp_factor;
+        }
+        let elapsed = (now - self.start_ramp_ts).max(0) as i128;
+        let duration = (self.stop_ramp_ts - self.start_ramp_ts) as i128;
+        let (initial, target) = (self.initial_amp_factor as i128, self.target_amp_factor as i128);
+        (initial + (target - initial) * elapsed / duration) as u64
+    }
+
+    /// Trade fee as a fraction, the form `Pool::fee_percent` holds
+    pub fn fee_rate(&self) -> Decimal {
+        if self.fee_denominator == 0 {
+            return Decimal::ZERO;
+        }
+        Decimal::from(self.fee_numerator) / Decimal::from(self.fee_denominator)
+    }
+
+    /// Output of swapping `input_amount` against the given reserves on the StableSwap curve
+    pub fn swap_output(&self, input_amount: u64, reserve_in: u64, reserve_out: u64) -> Result<u64> {
+        calculate_stable_swap_output(
+            input_amount,
+            reserve_in,
+            reserve_out,
+            self.amp_factor(chrono::Utc::now().timestamp()),
+            self.fee_numerator,
+            self.fee_denominator.max(1),
+        )
+    }
+}
+
+fn read_u64(data: &[u8], offset: usize) -> u64 {
+    u64::from_le_bytes(data[offset..offset + 8].try_into().unwrap())
+}
+
+fn read_pubkey(data: &[u8], offset: usize) -> Result<Pubkey> {
+    Ok(Pubkey::try_from(&data[offset..offset + 32])?)
+}
+
+fn token_amount(data: &[u8]) -> Option<u64> {
+    data.get(TOKEN_AMOUNT_OFFSET..TOKEN_AMOUNT_OFFSET + 8)
+        .map(|bytes| u64::from_le_bytes(bytes.try_into().unwrap()))
 }
 
 pub struct SaberDex {
@@ -49,142 +119,105 @@ impl SaberDex {
     }
 
     pub async fn fetch_pools(&self) -> Result<Vec<Pool>> {
-        let accounts = self.client.get_program_accounts(&self.program_id).await?;
-        let mut pools = Vec::new();
-        
-        for (pubkey, account) in accounts {
-            if account.data.len() >= 8 && self.is_saber_pool_account(&account.data) {
-                if let Ok(pool_data) = self.parse_saber_pool_data(&account.data) {
-                    // Get vault balances
-                    let reserve_a = self.get_token_account_balance(&pool_data.token_a_vault).await.unwrap_or(0.0);
-                    let reserve_b = self.get_token_account_balance(&pool_data.token_b_vault).await.unwrap_or(0.0);
-                    
-                    let fee_rate = pool_data.fee_numerator as f64 / pool_data.fee_denominator as f64;
-                    
-                    let pool = Pool {
-                         address: pubkey,
-                         dex: "Saber".to_string(),
-                         token_a: TokenInfo {
-                             mint: pool_data.token_a_mint,
-                             symbol: "UNKNOWN".to_string(),
-                             decimals: 6,
-                             price_usd: None,
-                         },
-                         token_b: TokenInfo {
-                             mint: pool_data.token_b_mint,
-                             symbol: "UNKNOWN".to_string(),
-                             decimals: 6,
-                             price_usd: None,
-                         },
-                         reserve_a: reserve_a as u64,
-                         reserve_b: reserve_b as u64,
-                         fee_percent: Decimal::from_f64(fee_rate).unwrap_or_default(),
-                         liquidity_usd: Decimal::from((reserve_a + reserve_b) as u64),
-                         last_updated: chrono::Utc::now(),
-                     };
-                    
-                    pools.push(pool);
+        let accounts = self.client.get_program_accounts_with_filters(&self.program_id, &[
+            RpcFilterType::DataSize(SWAP_INFO_LEN as u64),
+        ]).await.context("Failed to fetch Saber swaps")?;
+
+        let swaps: Vec<(Pubkey, SaberPool)> = accounts.into_iter()
+            .filter_map(|(pubkey, account)| match Self::parse_saber_pool_data(&account.data) {
+                Ok(pool_data) => Some((pubkey, pool_data)),
+                Err(e) => {
+                    debug!("Skipping Saber account {}: {}", pubkey, e);
+                    None
                 }
-            }
+            })
+            .collect();
+        let balances = self.fetch_vault_balances(swaps.iter().map(|(_, pool_data)| pool_data)).await?;
+
+        let mut pools = Vec::new();
+        for ((pubkey, pool_data), balances) in swaps.into_iter().zip(balances) {
+            let Some((reserve_a, reserve_b)) = balances.filter(|&(a, b)| a > 0 && b > 0) else {
+                continue;
+            };
+            pools.push(Pool {
+                address: pubkey,
+                dex: "saber".to_string(),
+                token_a: TokenInfo {
+                    mint: pool_data.token_a_mint,
+                    symbol: "UNKNOWN".to_string(),
+                    decimals: 6,
+                    price_usd: None,
+                },
+                token_b: TokenInfo {
+                    mint: pool_data.token_b_mint,
+                    symbol: "UNKNOWN".to_string(),
+                    decimals: 6,
+                    price_usd: None,
+                },
+                reserve_a,
+                reserve_b,
+                fee_percent: pool_data.fee_rate(),
+                liquidity_usd: Decimal::ZERO, // Will be calculated separately
+                last_updated: chrono::Utc::now(),
+                metadata: Some(Arc::new(pool_data) as Arc<dyn std::any::Any + Send + Sync>),
+            });
         }
         
+        info!("Fetched {} Saber pools from blockchain", pools.len());
         Ok(pools)
     }
 
-    fn is_saber_pool_account(&self, data: &[u8]) -> bool {
-        if data.len() < 8 {
-            return false;
+    /// Parse a packed Saber `SwapInfo` account
+    pub fn parse_saber_pool_data(data: &[u8]) -> Result<SaberPool> {
+        if data.len() < SWAP_INFO_LEN {
+            anyhow::bail!("Invalid Saber pool data size: {} bytes", data.len());
         }
-        
-        // For now, we'll use a size-based heuristic since we don't have the exact discriminator
-        data.len() >= 300 && data.len() <= 500
-    }
-
-    fn parse_saber_pool_data(&self, data: &[u8]) -> Result<SaberPool> {
-        if data.len() < 300 {
-            return Err(anyhow::anyhow!("Invalid Saber pool data size"));
+        if data[0] != 1 {
+            anyhow::bail!("Saber swap is not initialized");
         }
-        
-        // Parse the Saber stable swap pool structure
-        let token_a_mint = Pubkey::try_from(&data[8..40])?;
-        let token_b_mint = Pubkey::try_from(&data[40..72])?;
-        let token_a_vault = Pubkey::try_from(&data[72..104])?;
-        let token_b_vault = Pubkey::try_from(&data[104..136])?;
-        let pool_mint = Pubkey::try_from(&data[136..168])?;
-        
-        let fee_numerator = u64::from_le_bytes([
-            data[168], data[169], data[170], data[171],
-            data[172], data[173], data[174], data[175],
-        ]);
-        
-        let fee_denominator = u64::from_le_bytes([
-            data[176], data[177], data[178], data[179],
-            data[180], data[181], data[182], data[183],
-        ]);
-        
-        let admin_fee_numerator = u64::from_le_bytes([
-            data[184], data[185], data[186], data[187],
-            data[188], data[189], data[190], data[191],
-        ]);
-        
-        let admin_fee_denominator = u64::from_le_bytes([
-            data[192], data[193], data[194], data[195],
-            data[196], data[197], data[198], data[199],
-        ]);
-        
-        let amp_factor = u64::from_le_bytes([
-            data[200], data[201], data[202], data[203],
-            data[204], data[205], data[206], data[207],
-        ]);
-        
+
         Ok(SaberPool {
-            token_a_mint,
-            token_b_mint,
-            token_a_vault,
-            token_b_vault,
-            pool_mint,
-            fee_numerator,
-            fee_denominator,
-            admin_fee_numerator,
-            admin_fee_denominator,
-            amp_factor,
+            token_a_mint: read_pubkey(data, TOKEN_A_MINT_OFFSET)?,
+            token_b_mint: read_pubkey(data, TOKEN_B_MINT_OFFSET)?,
+            token_a_vault: read_pubkey(data, TOKEN_A_RESERVES_OFFSET)?,
+            token_b_vault: read_pubkey(data, TOKEN_B_RESERVES_OFFSET)?,
+            pool_mint: read_pubkey(data, POOL_MINT_OFFSET)?,
+            fee_numerator: read_u64(data, TRADE_FEE_NUMERATOR_OFFSET),
+            fee_denominator: read_u64(data, TRADE_FEE_DENOMINATOR_OFFSET),
+            admin_fee_numerator: read_u64(data, ADMIN_TRADE_FEE_NUMERATOR_OFFSET),
+            admin_fee_denominator: read_u64(data, ADMIN_TRADE_FEE_DENOMINATOR_OFFSET),
+            initial_amp_factor: read_u64(data, INITIAL_AMP_OFFSET),
+            target_amp_factor: read_u64(data, TARGET_AMP_OFFSET),
+            start_ramp_ts: read_u64(data, START_RAMP_TS_OFFSET) as i64,
+            stop_ramp_ts: read_u64(data, STOP_RAMP_TS_OFFSET) as i64,
         })
     }
 
-    async fn get_token_account_balance(&self, vault_pubkey: &Pubkey) -> Result<f64> {
-        match self.client.try_get_token_account_balance(vault_pubkey).await {
-            Ok(Some(balance)) => {
-                let amount = balance as f64 / 1e6; // Convert from raw amount to UI amount
-                Ok(amount)
+    /// Reserve vault balances of each swap, `None` where either vault can't be read
+    async fn fetch_vault_balances<'a>(
+        &self,
+        swaps: impl IntoIterator<Item = &'a SaberPool>,
+    ) -> Result<Vec<Option<(u64, u64)>>> {
+        let vaults: Vec<Pubkey> = swaps.into_iter()
+            .flat_map(|pool_data| [pool_data.token_a_vault, pool_data.token_b_vault])
+            .collect();
+
+        let mut balances = Vec::with_capacity(vaults.len() / 2);
+        for chunk in vaults.chunks(MAX_ACCOUNTS_PER_BATCH) {
+            let accounts = self.client.get_multiple_accounts(chunk).await?;
+            for pair in accounts.chunks(2) {
+                let amount = |account: &Option<solana_sdk::account::Account>| {
+                    account.as_ref().and_then(|account| token_amount(&account.data))
+                };
+                balances.push(amount(&pair[0]).zip(amount(&pair[1])));
             }
-            Ok(None) => Ok(0.0), // Account not found or invalid
-            Err(_) => Ok(0.0), // Other errors
         }
+        Ok(balances)
     }
 
     pub async fn is_healthy(&self) -> bool {
         self.client.get_latest_blockhash().await.is_ok()
     }
-
-    // Saber-specific stable swap calculation
-    pub fn calculate_stable_swap_output(
-        &self,
-        input_amount: f64,
-        input_reserve: f64,
-        output_reserve: f64,
-        amp_factor: u64,
-    ) -> f64 {
-        // Simplified stable swap formula
-        // In a real implementation, this would use the full StableSwap invariant
-        let _amp = amp_factor as f64;
-        let d = input_reserve + output_reserve;
-        
-        // Simplified calculation - in practice, this requires iterative solving
-        let new_input_reserve = input_reserve + input_amount;
-        let new_output_reserve = d - new_input_reserve;
-        
-        output_reserve - new_output_reserve
-    }
 }
 
 #[async_trait]
@@ -207,13 +240,22 @@ impl DexClient for SaberDex {
     }
 
     async fn update_pool_reserves(&self, pool: &mut Pool) -> Result<()> {
-        // For Saber, we would need to fetch the latest vault balances
-        // This is a simplified implementation
-        if let Some(updated_pool) = self.get_pool_by_tokens(&pool.token_a.mint.to_string(), &pool.token_b.mint.to_string()).await? {
-            pool.reserve_a = updated_pool.reserve_a;
-            pool.reserve_b = updated_pool.reserve_b;
-            pool.last_updated = chrono::Utc::now();
-        }
+        let pool_data = match pool.get_metadata::<SaberPool>() {
+            Some(pool_data) => pool_data.clone(),
+            None => Self::parse_saber_pool_data(&self.client.get_account_data(&pool.address).await?)?,
+        };
+
+        let (reserve_a, reserve_b) = self.fetch_vault_balances([&pool_data]).await?
+            .into_iter()
+            .next()
+            .flatten()
+            .with_context(|| format!("Saber vaults not found for {}", pool.address))?;
+
+        pool.reserve_a = reserve_a;
+        pool.reserve_b = reserve_b;
+        pool.fee_percent = pool_data.fee_rate();
+        pool.metadata = Some(Arc::new(pool_data));
+        pool.last_updated = chrono::Utc::now();
         Ok(())
     }
 
@@ -224,4 +266,55 @@ impl DexClient for SaberDex {
     fn set_console_manager(&mut self, console_manager: Arc<ConsoleManager>) {
         self.console_manager = Some(console_manager);
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_saber_pool_data() {
+        let (mint_a, mint_b, vault_a, vault_b, pool_mint) = (
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        );
+        let mut data = vec![0u8; SWAP_INFO_LEN];
+        data[0] = 1;
+        data[INITIAL_AMP_OFFSET..INITIAL_AMP_OFFSET + 8].copy_from_slice(&100u64.to_le_bytes());
+        data[TARGET_AMP_OFFSET..TARGET_AMP_OFFSET + 8].copy_from_slice(&200u64.to_le_bytes());
+        data[START_RAMP_TS_OFFSET..START_RAMP_TS_OFFSET + 8].copy_from_slice(&1_000i64.to_le_bytes());
+        data[STOP_RAMP_TS_OFFSET..STOP_RAMP_TS_OFFSET + 8].copy_from_slice(&2_000i64.to_le_bytes());
+        for (offset, key) in [
+            (TOKEN_A_RESERVES_OFFSET, vault_a),
+            (TOKEN_B_RESERVES_OFFSET, vault_b),
+            (POOL_MINT_OFFSET, pool_mint),
+            (TOKEN_A_MINT_OFFSET, mint_a),
+            (TOKEN_B_MINT_OFFSET, mint_b),
+        ] {
+            data[offset..offset + 32].copy_from_slice(key.as_ref());
+        }
+        data[TRADE_FEE_NUMERATOR_OFFSET..TRADE_FEE_NUMERATOR_OFFSET + 8].copy_from_slice(&4u64.to_le_bytes());
+        data[TRADE_FEE_DENOMINATOR_OFFSET..TRADE_FEE_DENOMINATOR_OFFSET + 8].copy_from_slice(&10_000u64.to_le_bytes());
+
+        let pool = SaberDex::parse_saber_pool_data(&data).unwrap();
+        assert_eq!((pool.token_a_mint, pool.token_b_mint), (mint_a, mint_b));
+        assert_eq!((pool.token_a_vault, pool.token_b_vault), (vault_a, vault_b));
+        assert_eq!(pool.pool_mint, pool_mint);
+        assert_eq!(pool.fee_rate(), Decimal::new(4, 4));
+
+        // Amplification ramps linearly from 100 to 200
+        assert_eq!(pool.amp_factor(500), 100);
+        assert_eq!(pool.amp_factor(1_500), 150);
+        assert_eq!(pool.amp_factor(3_000), 200);
+
+        // Balanced stable reserves trade close to 1:1, unlike constant product
+        let output = pool.swap_output(1_000_000_000, 100_000_000_000, 100_000_000_000).unwrap();
+        assert!(output > 999_000_000 && output < 1_000_000_000, "output {}", output);
+
+        data[0] = 0;
+        assert!(SaberDex::parse_saber_pool_data(&data).is_err());
+        assert!(SaberDex::parse_saber_pool_data(&data[..300]).is_err());
+    }
+}
@@ -1,6 +1,6 @@
 use crate::{
     dex::DexClient,
-    models::{Pool, TokenInfo},
+    models::{OrderBookData, Pool, TokenInfo},
     utils::rpc::RpcClient,
     // config::Config, // Unused
     console::ConsoleManager,
@@ -8,16 +8,51 @@ use crate::{
 use anyhow::Result;
 use async_trait::async_trait;
 use solana_sdk::pubkey::Pubkey;
-use std::sync::Arc;
+use std::sync::{Arc, RwLock};
 use std::collections::HashMap;
 // use serde::{Deserialize, Serialize}; // Unused
-// use tracing::{info, error, warn}; // Unused
+use tracing::{info, warn};
 use chrono;
 use rust_decimal::Decimal;
 use rust_decimal::prelude::FromPrimitive;
 use std::str::FromStr;
 
 pub const SERUM_PROGRAM_ID: &str = "9xQeWvG816bUx9EPjHmaT23yvVM2ZWbrrpZb9PusVFin";
+pub const OPENBOOK_PROGRAM_ID: &str = "srmqPvymJeFKQ4zGQed1GFppgkRHL9kaELCbyksJtPX";
+
+/// Which orderbook program owns a market. Serum V3 markets were migrated to OpenBook.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SerumVersion {
+    SerumV3,
+    OpenBook,
+}
+
+impl SerumVersion {
+    pub fn program_id(&self) -> Pubkey {
+        let id = match self {
+            SerumVersion::SerumV3 => SERUM_PROGRAM_ID,
+            SerumVersion::OpenBook => OPENBOOK_PROGRAM_ID,
+        };
+        Pubkey::from_str(id).expect("valid program id")
+    }
+}
+
+/// Instruction data prefix: layout version byte followed by the u32 instruction tag.
+/// OpenBook kept the Serum V3 encoding, so both programs share it.
+pub fn instruction_header(tag: u32) -> Vec<u8> {
+    let mut data = vec![0u8];
+    data.extend_from_slice(&tag.to_le_bytes());
+    data
+}
+
+/// Market details kept on each Serum pool
+#[derive(Debug, Clone)]
+pub struct SerumPoolMeta {
+    /// Program that owns the market, and so executes swaps against it
+    pub version: SerumVersion,
+    /// Best bid and ask when both sides of the book could be read
+    pub order_book: Option<OrderBookData>,
+}
 
 // Serum market discriminator
 
@@ -46,6 +81,7 @@ pub struct SerumDex {
     pub client: RpcClient,
     pub program_id: Pubkey,
     pub known_markets: HashMap<String, Pubkey>,
+    market_versions: RwLock<HashMap<Pubkey, SerumVersion>>,
     console_manager: Option<Arc<ConsoleManager>>,
 }
 
@@ -53,7 +89,7 @@ impl SerumDex {
     pub fn new(rpc_client: Arc<crate::utils::rpc::RpcClient>, console_manager: Arc<ConsoleManager>) -> Result<Self> {
         let program_id = Pubkey::from_str(SERUM_PROGRAM_ID)?;
         
-        // Initialize with some well-known Serum markets
+        // Initialize with well-known markets from both the legacy Serum V3 and OpenBook programs
         let mut known_markets = HashMap::new();
         known_markets.insert(
             "SOL/USDC".to_string(),
@@ -63,22 +99,63 @@ impl SerumDex {
             "SOL/USDT".to_string(),
             Pubkey::from_str("HWHvQhFmJB3NUcu1aihKmrKegfVxBEHzwVX6yZCKEsi1").unwrap(),
         );
+        known_markets.insert(
+            "SOL/USDC (OpenBook)".to_string(),
+            Pubkey::from_str("8BnEgHoWFysVcuFFX7QztDmzuH8r5ZFvyP3sYwn1XTh6").unwrap(),
+        );
+        known_markets.insert(
+            "SOL/USDT (OpenBook)".to_string(),
+            Pubkey::from_str("2AdaV97p6SfkuMQJdu8DHhBhmJe7oWdvbm52MJfYQmfA").unwrap(),
+        );
         
         Ok(Self {
             client: (*rpc_client).clone(),
             program_id,
             known_markets,
+            market_versions: RwLock::new(HashMap::new()),
             console_manager: Some(console_manager),
         })
     }
 
+    /// Determine whether a market is owned by the legacy Serum V3 program or OpenBook
+    pub async fn detect_program_version(market_pubkey: &Pubkey, rpc: &RpcClient) -> Result<SerumVersion> {
+        let account = rpc.get_account(market_pubkey).await?;
+
+        if account.owner == SerumVersion::OpenBook.program_id() {
+            Ok(SerumVersion::OpenBook)
+        } else if account.owner == SerumVersion::SerumV3.program_id() {
+            Ok(SerumVersion::SerumV3)
+        } else {
+            anyhow::bail!("Market {} is owned by unknown program {}", market_pubkey, account.owner)
+        }
+    }
+
+    /// Cached version lookup; the first detection for each market is logged
+    pub async fn market_version(&self, market_name: &str, market_pubkey: &Pubkey) -> Result<SerumVersion> {
+        if let Some(version) = self.market_versions.read().unwrap().get(market_pubkey) {
+            return Ok(*version);
+        }
+
+        let version = Self::detect_program_version(market_pubkey, &self.client).await?;
+        info!("Serum market {} ({}) detected as {:?}", market_name, market_pubkey, version);
+        self.market_versions.write().unwrap().insert(*market_pubkey, version);
+        Ok(version)
+    }
+
     pub async fn fetch_pools(&self) -> Result<Vec<Pool>> {
         let mut pools = Vec::new();
         
         // Fetch from known markets first
         for (market_name, market_pubkey) in &self.known_markets {
+            let version = match self.market_version(market_name, market_pubkey).await {
+                Ok(version) => version,
+                Err(e) => {
+                    warn!("Could not detect program version for {}: {}", market_name, e);
+                    continue;
+                }
+            };
             if let Ok(market_data) = self.fetch_market_data(market_pubkey).await {
-                let pool = self.market_to_pool(market_name, market_pubkey, &market_data).await?;
+                let pool = self.market_to_pool(market_name, market_pubkey, &market_data, version).await?;
                 pools.push(pool);
             }
         }
@@ -111,7 +188,8 @@ impl SerumDex {
                         self.get_token_symbol(&market_data.quote_mint)
                     );
                     
-                    let pool = self.market_to_pool(&market_name, &pubkey, &market_data).await?;
+                    // Discovery only scans the legacy Serum V3 program
+                    let pool = self.market_to_pool(&market_name, &pubkey, &market_data, SerumVersion::SerumV3).await?;
                     pools.push(pool);
                     
                     if pools.len() >= 10 { // Limit discovery
@@ -129,9 +207,14 @@ impl SerumDex {
         _market_name: &str,
         market_pubkey: &Pubkey,
         market_data: &SerumMarket,
+        version: SerumVersion,
     ) -> Result<Pool> {
-        let base_balance = self.get_token_account_balance(&market_data.base_vault).await.unwrap_or(0.0);
-        let quote_balance = self.get_token_account_balance(&market_data.quote_vault).await.unwrap_or(0.0);
+        let balances = self.client
+            .get_multiple_token_account_balances(&[market_data.base_vault, market_data.quote_vault])
+            .await
+            .unwrap_or_default();
+        let base_balance = Self::ui_amount(balances.first().copied().flatten());
+        let quote_balance = Self::ui_amount(balances.get(1).copied().flatten());
         
         let token_a_info = TokenInfo {
             mint: market_data.base_mint,
@@ -159,9 +242,22 @@ impl SerumDex {
             fee_percent: Decimal::from_f64(fee_rate).unwrap_or_default(),
             liquidity_usd: Decimal::from((base_balance + quote_balance) as u64),
             last_updated: chrono::Utc::now(),
+            metadata: Some(Arc::new(SerumPoolMeta {
+                version,
+                order_book: self.best_bid_ask(market_data).await,
+            })),
         })
     }
 
+    /// Top of the market's book, if both sides have orders
+    async fn best_bid_ask(&self, market_data: &SerumMarket) -> Option<OrderBookData> {
+        let bids = self.parse_order_book(&market_data.bids, true).await.ok()?;
+        let asks = self.parse_order_book(&market_data.asks, false).await.ok()?;
+        let best_bid = bids.iter().map(|level| level.price).reduce(f64::max)?;
+        let best_ask = asks.iter().map(|level| level.price).reduce(f64::min)?;
+        Some(OrderBookData { best_bid, best_ask })
+    }
+
     fn is_serum_market_account(&self, data: &[u8]) -> bool {
         if data.len() < 8 {
             return false;
@@ -215,15 +311,10 @@ impl SerumDex {
         })
     }
 
-    async fn get_token_account_balance(&self, vault_pubkey: &Pubkey) -> Result<f64> {
-        match self.client.try_get_token_account_balance(vault_pubkey).await {
-            Ok(Some(balance)) => {
-                let decimals = 6; // Default decimals, should be fetched from mint
-                Ok(balance as f64 / 10_f64.powi(decimals as i32))
-            }
-            Ok(None) => Ok(0.0), // Account not found or invalid
-            Err(_) => Ok(0.0), // Other errors
-        }
+    /// Vault balance in UI units; missing or invalid vaults count as empty
+    fn ui_amount(balance: Option<u64>) -> f64 {
+        let decimals = 6; // Default decimals, should be fetched from mint
+        balance.map_or(0.0, |balance| balance as f64 / 10_f64.powi(decimals))
     }
 
     fn get_token_symbol(&self, mint: &Pubkey) -> String {
@@ -305,4 +396,9 @@ impl DexClient for SerumDex {
     fn set_console_manager(&mut self, console_manager: Arc<ConsoleManager>) {
         self.console_manager = Some(console_manager);
     }
+
+    fn liquidity_quality_multiplier(&self) -> f64 {
+        // Order book depth can vanish between quote and fill
+        0.8
+    }
 }
\ No newline at end of file
@@ -1,26 +1,147 @@
+use anyhow::{anyhow, Context, Result};
+use rust_decimal::Decimal;
+use serde::Deserialize;
 use solana_sdk::pubkey::Pubkey;
+use std::path::Path;
 use std::str::FromStr;
 
+/// Default location of the runtime DEX list, relative to the working directory.
+pub const DEX_CONFIG_FILE: &str = "dex_config.json";
+/// Fallback fee, in percent, for DEXes missing from the list
+const DEFAULT_FEE_PERCENT: f64 = 0.3;
+/// Fallback reliability for DEXes missing from the list
+const DEFAULT_RELIABILITY: f64 = 0.8;
+
 #[derive(Debug, Clone)]
 pub struct DexConfig {
     pub name: String,
     pub program_id: Pubkey,
     pub enabled: bool,
     pub description: String,
+    /// Optional HTTP endpoint for DEXs quoted through an API.
+    pub api_url: Option<String>,
+    /// Swap fee in percent for pools whose exact fee can't be read on chain
+    pub default_fee_percent: f64,
+    /// How far the DEX's quotes can be trusted to fill, from 0 to 1; scales opportunity confidence
+    pub reliability: f64,
+}
+
+/// One entry of the DEX list file.
+#[derive(Debug, Deserialize)]
+struct DexConfigEntry {
+    name: String,
+    enabled: bool,
+    #[serde(default)]
+    program_id: Option<String>,
+    #[serde(default)]
+    api_url: Option<String>,
+    fee_percent: f64,
+    #[serde(default)]
+    reliability: Option<f64>,
+}
+
+/// TOML has no top-level arrays, so TOML files list entries under `[[dex]]`.
+#[derive(Debug, Deserialize)]
+struct DexConfigFile {
+    dex: Vec<DexConfigEntry>,
+}
+
+impl DexConfigEntry {
+    fn into_config(self) -> Result<DexConfig> {
+        let builtin = DexConfigs::get_dex_by_name(&self.name);
+        let program_id = match self.program_id.as_deref() {
+            Some(id) => Pubkey::from_str(id)
+                .map_err(|e| anyhow!("Invalid program_id {} for DEX {}: {}", id, self.name, e))?,
+            None => builtin
+                .as_ref()
+                .map(|dex| dex.program_id)
+                .ok_or_else(|| anyhow!("DEX {} is not built in and needs a program_id", self.name))?,
+        };
+
+        let reliability = self.reliability
+            .or(builtin.as_ref().map(|dex| dex.reliability))
+            .unwrap_or(DEFAULT_RELIABILITY);
+
+        Ok(DexConfig {
+            description: builtin.map(|dex| dex.description).unwrap_or_default(),
+            name: self.name,
+            program_id,
+            enabled: self.enabled,
+            api_url: self.api_url,
+            default_fee_percent: self.fee_percent,
+            reliability,
+        })
+    }
 }
 
-pub struct DexConfigs;
+/// DEX list used to build clients. Holds the file contents when loaded with
+/// [`DexConfigs::load_from_file`], otherwise falls back to the compiled-in list.
+#[derive(Debug, Clone, Default)]
+pub struct DexConfigs {
+    configured: Option<Vec<DexConfig>>,
+}
 
 impl DexConfigs {
     pub fn new() -> Self {
-        DexConfigs
+        Self::default()
     }
-    
+
+    /// Load the DEX list from a JSON (top-level array) or TOML (`[[dex]]`) file.
+    pub fn load_from_file(path: &Path) -> Result<DexConfigs> {
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read DEX config {}", path.display()))?;
+
+        let entries: Vec<DexConfigEntry> = match path.extension().and_then(|ext| ext.to_str()) {
+            Some("toml") => toml::from_str::<DexConfigFile>(&contents)
+                .with_context(|| format!("Failed to parse DEX config {}", path.display()))?
+                .dex,
+            _ => serde_json::from_str(&contents)
+                .with_context(|| format!("Failed to parse DEX config {}", path.display()))?,
+        };
+
+        let configured = entries
+            .into_iter()
+            .map(DexConfigEntry::into_config)
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(DexConfigs { configured: Some(configured) })
+    }
+
+    /// Default swap fee of `dex_name` as a fraction, the form `Pool::fee_percent` holds.
+    /// The loaded file wins over the built-in list; names match case-insensitively.
+    pub fn get_fee_for_dex(&self, dex_name: &str) -> Decimal {
+        let matches = |dex: &DexConfig| dex.name.eq_ignore_ascii_case(dex_name);
+        let fee_percent = self.configured.as_ref()
+            .and_then(|configured| configured.iter().find(|dex| matches(dex)).map(|dex| dex.default_fee_percent))
+            .or_else(|| Self::get_all_dexs().into_iter().find(matches).map(|dex| dex.default_fee_percent))
+            .unwrap_or(DEFAULT_FEE_PERCENT);
+
+        Decimal::try_from(fee_percent).unwrap_or_default() / Decimal::ONE_HUNDRED
+    }
+
+    /// Reliability of `dex_name` from 0 to 1, the loaded file winning over the built-in list.
+    /// Names match ignoring case and punctuation, so pool DEX names like "PumpFun" find "Pump.fun".
+    pub fn get_reliability_for_dex(&self, dex_name: &str) -> f64 {
+        let normalize = |name: &str| -> String {
+            name.chars().filter(|c| c.is_ascii_alphanumeric()).map(|c| c.to_ascii_lowercase()).collect()
+        };
+        let wanted = normalize(dex_name);
+        let matches = |dex: &DexConfig| normalize(&dex.name) == wanted;
+        self.configured.as_ref()
+            .and_then(|configured| configured.iter().find(|dex| matches(dex)).map(|dex| dex.reliability))
+            .or_else(|| Self::get_all_dexs().into_iter().find(matches).map(|dex| dex.reliability))
+            .unwrap_or(DEFAULT_RELIABILITY)
+    }
+
     pub fn get_enabled(&self) -> Vec<DexConfig> {
+        if let Some(configured) = &self.configured {
+            return configured.iter().filter(|dex| dex.enabled).cloned().collect();
+        }
+
         // Enable all DEXs to maximize arbitrage opportunities across the ecosystem
         static ENABLED_DEXS: &[&str] = &[
             "Orca", "Raydium", "Phoenix", "Meteora", "Meteora DAMM",
-            "Pump.fun", "Saber", "Serum", "Lifinity"
+            "Pump.fun", "Saber", "Serum", "OpenBook", "Lifinity", "Kamino", "Jupiter", "Drift"
         ];
         
         Self::get_all_dexs().into_iter().filter(|dex| {
@@ -35,6 +156,9 @@ impl DexConfigs {
                 name: "Raydium".to_string(),
                 program_id: Pubkey::from_str("675kPX9MHTjS2zt1qfr1NYHuzeLXfQM9H24wFSUt1Mp8").unwrap(),
                 enabled: true,
+                api_url: None,
+                default_fee_percent: 0.25,
+                reliability: 0.9,
                 description: "First automated market maker built on Solana".to_string(),
             },
             // 2. Orca - Whirlpool concentrated liquidity
@@ -42,6 +166,9 @@ impl DexConfigs {
                 name: "Orca".to_string(),
                 program_id: Pubkey::from_str("whirLbMiicVdio4qvUfM5KAg6Ct8VwpYzGff3uctyCc").unwrap(),
                 enabled: true,
+                api_url: None,
+                default_fee_percent: 0.3,
+                reliability: 0.9,
                 description: "Concentrated liquidity DEX with Whirlpools".to_string(),
             },
             // 3. Meteora - DLMM (Dynamic Liquidity Market Maker)
@@ -49,6 +176,9 @@ impl DexConfigs {
                 name: "Meteora".to_string(),
                 program_id: Pubkey::from_str("LBUZKhRxPF3XUpBCjp4YzTKgLccjZhTSDM9YuVaPwxo").unwrap(),
                 enabled: true,
+                api_url: None,
+                default_fee_percent: 0.25,
+                reliability: 0.85,
                 description: "Dynamic Liquidity Market Maker with optimized capital efficiency".to_string(),
             },
 
@@ -57,6 +187,9 @@ impl DexConfigs {
                 name: "Phoenix".to_string(),
                 program_id: Pubkey::from_str("PhoeNiXZ8ByJGLkxNfZRnkUfjvmuYqLR89jjFHGqdXY").unwrap(),
                 enabled: true,
+                api_url: None,
+                default_fee_percent: 0.01,
+                reliability: 0.85,
                 description: "High-performance order book DEX".to_string(),
             },
             // 6. Pump.fun - Meme token launchpad and DEX
@@ -64,6 +197,9 @@ impl DexConfigs {
                 name: "Pump.fun".to_string(),
                 program_id: Pubkey::from_str("6EF8rrecthR5Dkzon8Nwu78hRvfCKubJ14M5uBEwF6P").unwrap(),
                 enabled: true,
+                api_url: None,
+                default_fee_percent: 1.0,
+                reliability: 0.6,
                 description: "Meme token launchpad with integrated trading".to_string(),
             },
             // 7. Saber - Stable swap DEX
@@ -71,6 +207,9 @@ impl DexConfigs {
                 name: "Saber".to_string(),
                 program_id: Pubkey::from_str("SSwpkEEcbUqx4vtoEByFjSkhKdCT862DNVb52nZg1UZ").unwrap(),
                 enabled: true,
+                api_url: None,
+                default_fee_percent: 0.04,
+                reliability: 0.8,
                 description: "Stable swap protocol for pegged assets".to_string(),
             },
             // 8. Serum - Order book DEX (used by Aldrin and others)
@@ -78,6 +217,9 @@ impl DexConfigs {
                 name: "Serum".to_string(),
                 program_id: Pubkey::from_str("9xQeWvG816bUx9EPjHmaT23yvVM2ZWbrrpZb9PusVFin").unwrap(),
                 enabled: true,
+                api_url: None,
+                default_fee_percent: 0.22,
+                reliability: 0.7,
                 description: "Decentralized order book exchange".to_string(),
             },
             // 9. Lifinity - Oracle-based proactive market maker
@@ -85,6 +227,9 @@ impl DexConfigs {
                 name: "Lifinity".to_string(),
                 program_id: Pubkey::from_str("EewxydAPCCVuNEyrVN68PuSYdQ7wKn27V9Gjeoi8dy3S").unwrap(),
                 enabled: true,
+                api_url: None,
+                default_fee_percent: 0.3,
+                reliability: 0.8,
                 description: "First proactive market maker with oracle-based pricing".to_string(),
             },
             // 10. Meteora DAMM - Dynamic AMM Pools
@@ -92,8 +237,51 @@ impl DexConfigs {
                 name: "Meteora DAMM".to_string(),
                 program_id: Pubkey::from_str("Eo7WjKq67rjJQSZxS6z3YkapzY3eMj6Xy8X5EQVn5UaB").unwrap(),
                 enabled: true,
+                api_url: None,
+                default_fee_percent: 0.25,
+                reliability: 0.8,
                 description: "Meteora Dynamic AMM Pools for enhanced liquidity".to_string(),
             },
+            // 11. Jupiter - Aggregator routing across all of the above
+            DexConfig {
+                name: "Jupiter".to_string(),
+                program_id: Pubkey::from_str("JUP6LkbZbjS1jKKwapdHNy74zcZ3tLUZoi5QNyVTaV4").unwrap(),
+                enabled: true,
+                api_url: None,
+                default_fee_percent: 0.0,
+                reliability: 0.9,
+                description: "Swap aggregator quoting the best route across DEXs".to_string(),
+            },
+            // 12. OpenBook V2 - Community successor to Serum's order book
+            DexConfig {
+                name: "OpenBook".to_string(),
+                program_id: Pubkey::from_str("opnb2LAfJYbRMAHHvqjCwQxanZn7n1a9GUZmBT7tSNp").unwrap(),
+                enabled: true,
+                api_url: None,
+                default_fee_percent: 0.1,
+                reliability: 0.75,
+                description: "Community-run order book DEX succeeding Serum".to_string(),
+            },
+            // 13. Kamino - Automated liquidity strategies on Orca whirlpools
+            DexConfig {
+                name: "Kamino".to_string(),
+                program_id: Pubkey::from_str("6LtLpnUFNByNXLyCoK9wA2MykKAmQNZKBdY8s47dehDc").unwrap(),
+                enabled: true,
+                api_url: None,
+                default_fee_percent: 0.3,
+                reliability: 0.8,
+                description: "Managed concentrated liquidity strategies".to_string(),
+            },
+            // 14. Drift - Spot markets of the perpetuals exchange, quoted in USDC
+            DexConfig {
+                name: "Drift".to_string(),
+                program_id: Pubkey::from_str("dRiftyHA39MWEi3m9aunc5MzRF1JYuBsbn6VPcn33UH").unwrap(),
+                enabled: true,
+                api_url: None,
+                default_fee_percent: 0.1,
+                reliability: 0.75,
+                description: "Order book and JIT-filled spot markets against USDC".to_string(),
+            },
         ]
     }
     
@@ -113,4 +301,98 @@ impl DexConfigs {
     pub fn get_dex_by_program_id(program_id: &Pubkey) -> Option<DexConfig> {
         Self::get_all_dexs().into_iter().find(|dex| dex.program_id == *program_id)
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::math::calculate_output_amount;
+
+    fn write_temp(ext: &str, contents: &str) -> std::path::PathBuf {
+        let path = std::env::temp_dir().join(format!("dex_config_{}.{}", uuid::Uuid::new_v4(), ext));
+        std::fs::write(&path, contents).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_load_from_file_overrides_enabled_flags() {
+        let path = write_temp("json", r#"[
+            { "name": "Orca", "enabled": false, "fee_percent": 0.3 },
+            { "name": "Raydium", "enabled": true, "fee_percent": 0.25 },
+            { "name": "NewDex", "enabled": true, "program_id": "JUP6LkbZbjS1jKKwapdHNy74zcZ3tLUZoi5QNyVTaV4",
+              "api_url": "https://example.com", "fee_percent": 0.1 }
+        ]"#);
+        let configs = DexConfigs::load_from_file(&path).unwrap();
+        std::fs::remove_file(&path).ok();
+
+        let enabled = configs.get_enabled();
+        let names: Vec<_> = enabled.iter().map(|dex| dex.name.as_str()).collect();
+        assert_eq!(names, vec!["Raydium", "NewDex"]);
+        assert_eq!(enabled[0].program_id, DexConfigs::get_dex_by_name("Raydium").unwrap().program_id);
+        assert_eq!(enabled[1].api_url.as_deref(), Some("https://example.com"));
+        assert_eq!(enabled[1].default_fee_percent, 0.1);
+    }
+
+    #[test]
+    fn test_fee_override_applies_to_profit() {
+        let built_in = DexConfigs::new();
+        assert_eq!(built_in.get_fee_for_dex("raydium"), Decimal::new(25, 4));
+        assert_eq!(built_in.get_fee_for_dex("Unknown DEX"), Decimal::new(3, 3));
+
+        let path = write_temp("json", r#"[{ "name": "Raydium", "enabled": true, "fee_percent": 1.0 }]"#);
+        let configs = DexConfigs::load_from_file(&path).unwrap();
+        std::fs::remove_file(&path).ok();
+        let fee = configs.get_fee_for_dex("raydium");
+        assert_eq!(fee, Decimal::new(1, 2));
+        // DEXes missing from the file keep their built-in fee
+        assert_eq!(configs.get_fee_for_dex("Orca"), Decimal::new(3, 3));
+
+        // Buy 1% below and sell at par: a 0.25% fee each way leaves profit, 1% does not
+        let round_trip = |fee: Decimal| {
+            let reserve = 1_000_000_000_000;
+            let bought = calculate_output_amount(1_000_000, reserve, reserve * 101 / 100, fee).unwrap();
+            calculate_output_amount(bought, reserve, reserve, fee).unwrap() as i64 - 1_000_000
+        };
+        assert!(round_trip(built_in.get_fee_for_dex("Raydium")) > 0);
+        assert!(round_trip(fee) < 0);
+    }
+
+    #[test]
+    fn test_reliability_for_dex() {
+        let built_in = DexConfigs::new();
+        assert_eq!(built_in.get_reliability_for_dex("orca"), 0.9);
+        assert_eq!(built_in.get_reliability_for_dex("PumpFun"), 0.6);
+        assert_eq!(built_in.get_reliability_for_dex("Serum"), 0.7);
+        assert_eq!(built_in.get_reliability_for_dex("Unknown DEX"), DEFAULT_RELIABILITY);
+
+        let path = write_temp("json", r#"[{ "name": "Orca", "enabled": true, "fee_percent": 0.3, "reliability": 0.5 }]"#);
+        let configs = DexConfigs::load_from_file(&path).unwrap();
+        std::fs::remove_file(&path).ok();
+        assert_eq!(configs.get_reliability_for_dex("orca"), 0.5);
+        assert_eq!(configs.get_reliability_for_dex("raydium"), 0.9);
+    }
+
+    #[test]
+    fn test_load_from_toml_file() {
+        let path = write_temp("toml", r#"
+            [[dex]]
+            name = "Phoenix"
+            enabled = true
+            fee_percent = 0.02
+        "#);
+        let configs = DexConfigs::load_from_file(&path).unwrap();
+        std::fs::remove_file(&path).ok();
+
+        assert_eq!(configs.get_enabled().len(), 1);
+    }
+
+    #[test]
+    fn test_load_from_file_rejects_invalid_program_id() {
+        let path = write_temp("json", r#"[
+            { "name": "Orca", "enabled": true, "program_id": "not-a-pubkey", "fee_percent": 0.3 }
+        ]"#);
+        let result = DexConfigs::load_from_file(&path);
+        std::fs::remove_file(&path).ok();
+
+        assert!(result.is_err());
+    }
+}
@@ -1,89 +1,1094 @@
 use crate::{
+    api::BotState,
     config::Config,
-    models::ArbitrageOpportunity,
-    utils::rpc::RpcClient,
+    engine::{
+        flash_loan::{FlashLoanConfig, MARGINFI_PROGRAM_ID},
+        risk::RiskManager,
+        screener::{Screener, DEFAULT_PRIORITY_FEE_MICROLAMPORTS, MAX_MULTI_HOP},
+    },
+    metrics::MetricsCollector,
+    monitor::mempool::MempoolMonitor,
+    dex::{
+        guards::{build_minimum_output_check_instruction, guard_program_id, GUARD_COMPUTE_UNITS},
+        jupiter::{JupiterApi, JUPITER_PROGRAM_ID},
+        orca::{OrcaPoolMeta, WHIRLPOOL_PROGRAM_ID},
+        phoenix::{PhoenixClient, PhoenixPoolMeta, PHOENIX_PROGRAM_ID},
+        pumpfun::{PumpFunPoolMeta, PUMPFUN_PROGRAM_ID},
+        raydium::RaydiumPoolMeta,
+        serum::{instruction_header, SerumPoolMeta, SerumVersion},
+        same_dex, DexClient, DexId,
+    },
+    models::{ArbitrageOpportunity, TradeStep, BASE_TX_FEE_LAMPORTS, MAX_COMPUTE_UNITS},
+    notifications::DiscordNotifier,
+    storage::{ProfitDivergence, TradeHistory},
+    types::TradeDirection,
+    utils::{
+        math::{calculate_output_amount, calculate_price_impact_bps, calculate_slippage, minimum_amount_out},
+        price_feed::JupiterPriceFeed,
+        rpc::RpcClient,
+    },
 };
 use anyhow::{Context, Result};
 use solana_client::rpc_response::RpcSimulateTransactionResult;
+use rand::seq::SliceRandom;
+use serde_json::{json, Value};
 use solana_sdk::{
+    address_lookup_table::{state::AddressLookupTable, AddressLookupTableAccount},
     compute_budget::ComputeBudgetInstruction,
-    instruction::Instruction,
-    message::Message,
+    hash::Hash,
+    instruction::{Instruction, InstructionError},
+    message::{v0, Message, VersionedMessage},
     pubkey::Pubkey,
     signature::{Keypair, Signature},
     signer::Signer,
-    transaction::Transaction,
+    system_instruction,
+    transaction::{Transaction, TransactionError, VersionedTransaction},
 };
-use std::{collections::HashSet, str::FromStr, sync::Arc};
+use solana_transaction_status::UiInnerInstructions;
+use rust_decimal::Decimal;
+use std::{
+    borrow::Cow,
+    collections::{HashMap, HashSet},
+    str::FromStr,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+use tokio::sync::watch;
 use tracing::{debug, info, warn};
 
+pub const JITO_BUNDLE_URL: &str = "https://mainnet.block-engine.jito.wtf/api/v1/bundles";
+const RAYDIUM_AMM_PROGRAM_ID: &str = "675kPX9MHTjS2zt1qfr1NYHuzeLXfQM9H24wFSUt1Mp8";
+const PRIORITY_FEE_TTL: Duration = Duration::from_secs(10);
+
+
+/// Recent trades compared when reporting realized against expected profit
+const PROFIT_DIVERGENCE_WINDOW: usize = 10;
+/// Trades older than this no longer count towards Kelly position sizing, so a bad run
+/// that sized positions down to zero ages out instead of halting trading for good
+const KELLY_WINDOW_HOURS: i64 = 24;
+/// Below this many recent trades the full `max_position_size_sol` is allowed
+const KELLY_MIN_TRADES: usize = 10;
+/// SOL left in the wallet after fees: the rent-exempt minimum (890,880 lamports) plus
+/// the rent for opening one token account (2,039,280)
+const RENT_RESERVE_LAMPORTS: u64 = 2_930_160;
+/// Opportunities this safe may skip RPC preflight when `bot.allow_skip_preflight` is set
+const SKIP_PREFLIGHT_MAX_RISK: f64 = 0.3;
+const SKIP_PREFLIGHT_MIN_CONFIDENCE: f64 = 0.8;
+/// Cached wallet balances older than this are refreshed in the background
+const WALLET_BALANCE_TTL: Duration = Duration::from_secs(15);
+/// Creating the wrapped SOL account (a no-op when it exists), the transfer and `SyncNative`
+const WRAP_SOL_COMPUTE_UNITS: u32 = 30_000;
+/// `getMultipleAccounts` accepts at most this many addresses per call
+const MAX_ACCOUNTS_PER_BATCH: usize = 100;
+/// Jupiter lookup tables kept between trades before the cache is cleared
+const MAX_CACHED_JUPITER_LOOKUP_TABLES: usize = 256;
+/// DEXes swaps can be built for; the screener drops routes through any other
+pub const EXECUTABLE_DEXES: [DexId; 6] = [
+    DexId::Orca,
+    DexId::Raydium,
+    DexId::Phoenix,
+    DexId::Serum,
+    DexId::PumpFun,
+    DexId::Jupiter,
+];
+/// Compute unit limit and price
+const COMPUTE_BUDGET_INSTRUCTIONS: usize = 2;
+/// Around the swaps: wrapping SOL (3) and the minimum-output guard of an atomic route,
+/// or the four MarginFi flash loan instructions
+const ROUTE_OVERHEAD_INSTRUCTIONS: usize = 4;
+/// A Jupiter step's token account setup, swap and cleanup
+const MAX_JUPITER_STEP_INSTRUCTIONS: usize = 6;
+
+/// The wallet can't fund a trade; the opportunity is skipped rather than the cycle
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum InsufficientBalanceError {
+    #[error("Insufficient SOL: have {available} lamports, need {required} for fees and rent")]
+    Sol { available: u64, required: u64 },
+    #[error("Insufficient {mint} balance: have {available}, need {required}")]
+    Token { mint: Pubkey, available: u64, required: u64 },
+}
+
+/// Wallets that sign and fund trades, with their balances cached so picking one for a
+/// trade doesn't cost an RPC call per wallet
+pub struct WalletPool {
+    keypairs: Vec<Keypair>,
+    balances: Arc<std::sync::RwLock<WalletBalances>>,
+    /// Set while a background refresh runs, so refreshes don't pile up
+    refreshing: Arc<AtomicBool>,
+    /// Where the next wallet search starts, so trades rotate across funded wallets
+    current_index: AtomicUsize,
+}
+
+/// Balances of every wallet as of the last fetch
+#[derive(Debug, Clone, Default)]
+struct WalletBalances {
+    /// Lamports, in `WalletPool::keypairs` order
+    sol: Vec<u64>,
+    /// Token balances by wallet and mint, for each mint a route has started from
+    tokens: HashMap<(Pubkey, Pubkey), u64>,
+    fetched_at: Option<Instant>,
+}
+
+impl WalletPool {
+    pub fn new(keypairs: Vec<Keypair>) -> Self {
+        Self {
+            keypairs,
+            balances: Arc::new(std::sync::RwLock::new(WalletBalances::default())),
+            refreshing: Arc::new(AtomicBool::new(false)),
+            current_index: AtomicUsize::new(0),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.keypairs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keypairs.is_empty()
+    }
+
+    /// Each wallet's address with its SOL balance as of the last fetch
+    pub fn balances(&self) -> Vec<(Pubkey, u64)> {
+        let balances = self.balances.read().unwrap();
+        self.keypairs.iter()
+            .enumerate()
+            .map(|(index, keypair)| (keypair.pubkey(), balances.sol.get(index).copied().unwrap_or(0)))
+            .collect()
+    }
+
+    fn pubkeys(&self) -> Vec<Pubkey> {
+        self.keypairs.iter().map(|keypair| keypair.pubkey()).collect()
+    }
+
+    /// Mints whose balances are cached, plus `mint`
+    fn tracked_mints(&self, mint: Option<Pubkey>) -> Vec<Pubkey> {
+        let balances = self.balances.read().unwrap();
+        let mints: HashSet<Pubkey> = balances.tokens.keys().map(|(_, mint)| *mint).chain(mint).collect();
+        mints.into_iter().collect()
+    }
+
+    /// First wallet, starting after the one picked last, whose cached balances cover
+    /// `fee_lamports` and `input`, the start mint and amount of a trade funded from
+    /// the wallet (`None` when the input is borrowed)
+    fn select(&self, fee_lamports: u64, input: Option<(Pubkey, u64)>) -> Option<&Keypair> {
+        let count = self.keypairs.len();
+        if count == 0 {
+            return None;
+        }
+        let balances = self.balances.read().unwrap();
+        let start = self.current_index.fetch_add(1, Ordering::Relaxed) % count;
+        (0..count).map(|offset| (start + offset) % count).find_map(|index| {
+            let keypair = &self.keypairs[index];
+            let held = input.map_or(0, |(mint, _)| balances.tokens.get(&(keypair.pubkey(), mint)).copied().unwrap_or(0));
+            let sol = balances.sol.get(index).copied().unwrap_or(0);
+            Executor::wallet_covers(sol, held, fee_lamports, input).then_some(keypair)
+        })
+    }
+}
+
+/// SOL balance of each of `owners`, and each owner's balance of every one of `mints`,
+/// in one batched call per 100 accounts
+async fn fetch_wallet_balances(rpc_client: &RpcClient, owners: &[Pubkey], mints: &[Pubkey]) -> Result<WalletBalances> {
+    let mut sol = Vec::with_capacity(owners.len());
+    for chunk in owners.chunks(MAX_ACCOUNTS_PER_BATCH) {
+        let accounts = rpc_client.get_multiple_accounts(chunk).await?;
+        sol.extend(accounts.iter().map(|account| account.as_ref().map_or(0, |account| account.lamports)));
+    }
+
+    let keys: Vec<(Pubkey, Pubkey)> = mints.iter()
+        .flat_map(|mint| owners.iter().map(move |owner| (*owner, *mint)))
+        .collect();
+    let mut tokens = HashMap::with_capacity(keys.len());
+    for chunk in keys.chunks(MAX_ACCOUNTS_PER_BATCH) {
+        let atas: Vec<Pubkey> = chunk.iter()
+            .map(|(owner, mint)| spl_associated_token_account::get_associated_token_address(owner, mint))
+            .collect();
+        let amounts = rpc_client.get_multiple_token_account_balances(&atas).await?;
+        tokens.extend(chunk.iter().copied().zip(amounts.into_iter().map(|amount| amount.unwrap_or(0))));
+    }
+
+    Ok(WalletBalances { sol, tokens, fetched_at: Some(Instant::now()) })
+}
+
 pub struct Executor {
-    config: Config,
+    config: watch::Receiver<Config>,
     rpc_client: Arc<RpcClient>,
-    trading_keypair: Option<Keypair>,
+    wallets: WalletPool,
+    partial_execution_recoveries: AtomicU64,
+    http_client: reqwest::Client,
+    jupiter_api: JupiterApi,
+    /// Values realized profit in SOL for the stop-loss
+    price_feed: JupiterPriceFeed,
+    /// Resolved MarginFi accounts per wallet and borrowed mint
+    flash_loans: tokio::sync::Mutex<HashMap<(Pubkey, Pubkey), FlashLoanConfig>>,
+    /// Token accounts known to exist, so each is only looked up once
+    token_accounts: tokio::sync::Mutex<HashSet<Pubkey>>,
+    /// Last priority fee estimate, before the cap, and when it was taken
+    fee_cache: Arc<tokio::sync::RwLock<(u64, Instant)>>,
+    /// Source of the priority fee estimate that profits were netted against
+    screener: Option<Arc<Screener>>,
+    /// Confirmed trades are recorded here when live trading is enabled
+    trade_history: Option<std::sync::Mutex<TradeHistory>>,
+    notifier: Option<Arc<DiscordNotifier>>,
+    bot_state: Option<Arc<BotState>>,
+    risk_manager: RiskManager,
+    /// Used to re-read pool reserves right before a trade is sent
+    dex_clients: Vec<Arc<dyn DexClient>>,
+    /// Checked for sandwich setups right before a trade is sent
+    mempool_monitor: Option<Arc<MempoolMonitor>>,
+    /// Tables that v0 transactions reference instead of inlining account keys
+    lookup_tables: Arc<Vec<AddressLookupTableAccount>>,
+    /// Tables Jupiter swaps were built against, by address
+    jupiter_lookup_tables: tokio::sync::RwLock<HashMap<Pubkey, AddressLookupTableAccount>>,
+}
+
+/// Outcome of simulating a transaction, with any error decoded for the logs
+#[derive(Debug, Clone)]
+pub struct SimulationReport {
+    pub success: bool,
+    /// Readable failure, naming the DEX error when its code is known
+    pub error: Option<String>,
+    pub logs: Vec<String>,
+    pub compute_units: Option<u64>,
+    pub inner_instructions: Option<Vec<UiInnerInstructions>>,
+}
+
+impl SimulationReport {
+    fn from_result(result: RpcSimulateTransactionResult, instructions: &[Instruction]) -> Self {
+        Self {
+            success: result.err.is_none(),
+            error: result.err.as_ref().map(|err| decode_simulation_error(err, instructions)),
+            logs: result.logs.unwrap_or_default(),
+            compute_units: result.units_consumed,
+            inner_instructions: result.inner_instructions,
+        }
+    }
+}
+
+/// Name of a custom error raised by one of the programs our routes call
+fn known_program_error(program_id: &Pubkey, code: u32) -> Option<&'static str> {
+    if *program_id == spl_token::id() {
+        return (code == 0x1).then_some("InsufficientFunds");
+    }
+    match (program_id.to_string().as_str(), code) {
+        (WHIRLPOOL_PROGRAM_ID, 0x1787) => Some("SlippageToleranceExceeded"),
+        (RAYDIUM_AMM_PROGRAM_ID, 0x25) => Some("ExceededSlippage"),
+        _ => None,
+    }
+}
+
+/// Readable form of a simulation error. `InstructionError` indexes into `instructions`,
+/// which tells whose custom error code it is.
+fn decode_simulation_error(err: &TransactionError, instructions: &[Instruction]) -> String {
+    match err {
+        TransactionError::InstructionError(index, InstructionError::Custom(code)) => {
+            let program_id = instructions.get(*index as usize).map(|instruction| instruction.program_id);
+            match program_id.and_then(|program_id| known_program_error(&program_id, *code)) {
+                Some(name) => format!("Instruction {} failed: {} ({:#x})", index, name, code),
+                None => format!("Instruction {} failed with custom error {:#x}", index, code),
+            }
+        }
+        TransactionError::InstructionError(index, err) => format!("Instruction {} failed: {}", index, err),
+        err => err.to_string(),
+    }
+}
+
+/// Swaps that unwind a route which stopped part-way through
+#[derive(Debug)]
+pub struct RecoveryPlan {
+    /// Index of the first step that did not execute
+    pub failed_step: usize,
+    pub stranded_mint: Pubkey,
+    pub stranded_amount: u64,
+    pub instructions: Vec<Instruction>,
 }
 
 impl Executor {
-    pub fn new(config: Config, rpc_client: Arc<RpcClient>) -> Result<Self> {
-        let trading_keypair = if let Some(private_key) = &config.bot.private_key {
-            Some(Self::keypair_from_private_key(private_key)?)
+    pub fn new(mut config: Config, rpc_client: Arc<RpcClient>) -> Result<Self> {
+        let wallets = WalletPool::new(config.get_keypairs()?);
+
+        // The keypairs are all we need from here on
+        config.clear_private_key();
+
+        let trade_history = if config.bot.execute_trades && !config.bot.simulation_mode {
+            Some(std::sync::Mutex::new(TradeHistory::open(config.trade_history_path())?))
         } else {
             None
         };
+        let risk_manager = RiskManager::new(&config.risk_management);
 
         Ok(Self {
-            config,
+            config: watch::channel(config).1,
             rpc_client,
-            trading_keypair,
+            wallets,
+            partial_execution_recoveries: AtomicU64::new(0),
+            http_client: reqwest::Client::new(),
+            jupiter_api: JupiterApi::new(),
+            price_feed: JupiterPriceFeed::new(),
+            flash_loans: tokio::sync::Mutex::new(HashMap::new()),
+            token_accounts: tokio::sync::Mutex::new(HashSet::new()),
+            // Starts out expired so the first build takes a fresh estimate
+            fee_cache: Arc::new(tokio::sync::RwLock::new((
+                DEFAULT_PRIORITY_FEE_MICROLAMPORTS,
+                Instant::now().checked_sub(PRIORITY_FEE_TTL).unwrap_or_else(Instant::now),
+            ))),
+            screener: None,
+            trade_history,
+            notifier: None,
+            bot_state: None,
+            risk_manager,
+            dex_clients: Vec::new(),
+            mempool_monitor: None,
+            lookup_tables: Arc::new(Vec::new()),
+            jupiter_lookup_tables: tokio::sync::RwLock::new(HashMap::new()),
         })
     }
 
+    /// Follow configuration changes published by `Config::load_and_watch`
+    pub fn with_config_updates(mut self, config: watch::Receiver<Config>) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Latest configuration; keep the guard out of `.await` points
+    fn config(&self) -> watch::Ref<'_, Config> {
+        self.config.borrow()
+    }
+
+    /// Count confirmed trades and their realized profit for the metrics endpoint
+    pub fn with_bot_state(mut self, bot_state: Arc<BotState>) -> Self {
+        self.bot_state = Some(bot_state);
+        self
+    }
+
+    /// DEX clients that decode fresh pool state for the pre-submission slippage check
+    pub fn with_dex_clients(mut self, dex_clients: Vec<Arc<dyn DexClient>>) -> Self {
+        self.dex_clients = dex_clients;
+        self
+    }
+
+    /// Pay the priority fee `screener` estimates each scan
+    pub fn with_screener(mut self, screener: Arc<Screener>) -> Self {
+        self.screener = Some(screener);
+        self
+    }
+
+    /// Abort, or keep to the Jito path, when the mempool shows a likely sandwich setup
+    pub fn with_mempool_monitor(mut self, mempool_monitor: Arc<MempoolMonitor>) -> Self {
+        self.mempool_monitor = Some(mempool_monitor);
+        self
+    }
+
+    /// Announce confirmed trades through `notifier`
+    pub fn with_notifier(mut self, notifier: Arc<DiscordNotifier>) -> Self {
+        self.notifier = Some(notifier);
+        self
+    }
+
+    /// Fetch the lookup tables listed in `bot.address_lookup_tables`. Returns how many
+    /// were loaded; a table that can't be read fails startup rather than bloating trades.
+    pub async fn load_lookup_tables(&mut self) -> Result<usize> {
+        let addresses = self.config().bot.address_lookup_tables.clone();
+        let mut tables = Vec::with_capacity(addresses.len());
+        for address in addresses {
+            let key = Pubkey::from_str(&address)
+                .with_context(|| format!("Invalid address lookup table {}", address))?;
+            tables.push(self.fetch_lookup_table(key).await?);
+        }
+
+        let covered: HashSet<&Pubkey> = tables.iter().flat_map(|table| &table.addresses).collect();
+        let missing = self.get_allowed_program_ids().into_iter().filter(|id| !covered.contains(id)).count();
+        if missing > 0 {
+            debug!("{} known program ids are not in any lookup table", missing);
+        }
+
+        let loaded = tables.len();
+        self.lookup_tables = Arc::new(tables);
+        Ok(loaded)
+    }
+
+    async fn fetch_lookup_table(&self, key: Pubkey) -> Result<AddressLookupTableAccount> {
+        let account = self.rpc_client.get_account(&key).await?;
+        let table = AddressLookupTable::deserialize(&account.data)
+            .map_err(|e| anyhow::anyhow!("{} is not an address lookup table: {}", key, e))?;
+        Ok(AddressLookupTableAccount { key, addresses: table.addresses.to_vec() })
+    }
+
+    /// Configured lookup tables plus the cached Jupiter tables holding any account of
+    /// the Jupiter instructions in `instructions`
+    async fn transaction_lookup_tables(&self, instructions: &[Instruction]) -> Vec<AddressLookupTableAccount> {
+        let mut lookup_tables = self.lookup_tables.to_vec();
+        let Ok(jupiter_id) = Pubkey::from_str(JUPITER_PROGRAM_ID) else {
+            return lookup_tables;
+        };
+        let jupiter_accounts: HashSet<Pubkey> = instructions.iter()
+            .filter(|instruction| instruction.program_id == jupiter_id)
+            .flat_map(|instruction| instruction.accounts.iter().map(|account| account.pubkey))
+            .collect();
+        if jupiter_accounts.is_empty() {
+            return lookup_tables;
+        }
+        let cached = self.jupiter_lookup_tables.read().await;
+        lookup_tables.extend(cached.values()
+            .filter(|table| table.addresses.iter().any(|address| jupiter_accounts.contains(address)))
+            .cloned());
+        lookup_tables
+    }
+
+    /// v0 when configured, and always once a Jupiter route brought its own lookup tables
+    fn use_versioned_transaction(&self, lookup_tables: &[AddressLookupTableAccount]) -> bool {
+        self.config().bot.use_versioned_transactions || lookup_tables.len() > self.lookup_tables.len()
+    }
+
+    /// Main trading wallet, the first configured
+    pub fn trading_pubkey(&self) -> Option<Pubkey> {
+        self.wallets.keypairs.first().map(|keypair| keypair.pubkey())
+    }
+
+    pub fn wallets(&self) -> &WalletPool {
+        &self.wallets
+    }
+
+    /// Wallet to fund `opportunity`, picked from the cached balances. The cache is only
+    /// fetched inline when it is empty or lacks the route's start token; otherwise a
+    /// stale cache is refreshed in the background.
+    pub async fn select_wallet_for_trade(&self, opportunity: &ArbitrageOpportunity) -> Result<&Keypair> {
+        let input = self.wallet_funded_input(opportunity)?;
+        let fee_lamports = self.trade_fee_lamports(opportunity).await;
+
+        let (fetched_at, mint_tracked) = {
+            let balances = self.wallets.balances.read().unwrap();
+            let mint_tracked = input.is_none_or(|(mint, _)| balances.tokens.keys().any(|(_, tracked)| *tracked == mint));
+            (balances.fetched_at, mint_tracked)
+        };
+        match fetched_at {
+            Some(fetched_at) if mint_tracked => {
+                if fetched_at.elapsed() > WALLET_BALANCE_TTL {
+                    self.spawn_wallet_balance_refresh();
+                }
+            }
+            _ => self.refresh_wallet_balances(input.map(|(mint, _)| mint)).await?,
+        }
+
+        self.wallets.select(fee_lamports, input)
+            .context("No trading wallet has enough balance for this trade")
+    }
+
+    /// Re-fetch every wallet's balances, adding `mint` to the tokens tracked
+    pub async fn refresh_wallet_balances(&self, mint: Option<Pubkey>) -> Result<()> {
+        let balances = fetch_wallet_balances(&self.rpc_client, &self.wallets.pubkeys(), &self.wallets.tracked_mints(mint)).await?;
+        *self.wallets.balances.write().unwrap() = balances;
+        Ok(())
+    }
+
+    /// Refresh the wallet balance cache in the background unless a refresh is running
+    fn spawn_wallet_balance_refresh(&self) {
+        if self.wallets.refreshing.swap(true, Ordering::AcqRel) {
+            return;
+        }
+        let rpc_client = self.rpc_client.clone();
+        let (owners, mints) = (self.wallets.pubkeys(), self.wallets.tracked_mints(None));
+        let (balances, refreshing) = (self.wallets.balances.clone(), self.wallets.refreshing.clone());
+        tokio::spawn(async move {
+            match fetch_wallet_balances(&rpc_client, &owners, &mints).await {
+                Ok(fetched) => *balances.write().unwrap() = fetched,
+                Err(e) => debug!("Could not refresh wallet balances: {}", e),
+            }
+            refreshing.store(false, Ordering::Release);
+        });
+    }
+
+    /// Stop-loss state; trading halts while its limits are exceeded
+    pub fn risk_manager(&self) -> &RiskManager {
+        &self.risk_manager
+    }
+
+    /// Number of partially executed routes that were successfully unwound
+    pub fn partial_execution_recoveries(&self) -> u64 {
+        self.partial_execution_recoveries.load(Ordering::Relaxed)
+    }
+
     pub async fn execute_arbitrage(&self, opportunity: &ArbitrageOpportunity) -> Result<String> {
-        if self.config.bot.simulation_mode {
+        if self.config().bot.simulation_mode {
             return self.simulate_arbitrage(opportunity).await;
         }
 
-        if !self.config.bot.execute_trades {
+        if !self.config().bot.execute_trades {
             info!("Trade execution disabled in configuration");
             return Ok("execution_disabled".to_string());
         }
 
-        let trading_keypair = self.trading_keypair.as_ref()
-            .context("No trading keypair configured")?;
+        // Refuse to trade while on a losing streak or past the daily loss limit
+        self.risk_manager.check()?;
+
+        if self.wallets.is_empty() {
+            anyhow::bail!("No trading keypair configured");
+        }
+        let sized = self.kelly_sized(opportunity)?;
+        let opportunity = sized.as_ref();
+        let trading_keypair = self.select_wallet_for_trade(opportunity).await?;
 
-        info!("Executing arbitrage opportunity: {}", opportunity.id);
+        info!("Executing arbitrage opportunity: {} with wallet {}", opportunity.id, trading_keypair.pubkey());
 
         // Validate opportunity before execution
         self.validate_arbitrage_opportunity(opportunity)?;
+        self.check_wallet_balance_sufficient(opportunity, trading_keypair).await?;
 
-        // Build transaction instructions
-        let instructions = self.build_arbitrage_instructions(opportunity).await?;
+        // Build transaction instructions, borrowing the starting capital if flash loans are enabled
+        let instructions = if self.config().bot.enable_flash_loans {
+            self.build_flash_loan_arbitrage_instructions(opportunity, opportunity.input_amount, trading_keypair).await?
+        } else {
+            self.build_atomic_arbitrage_transaction(opportunity, trading_keypair).await?
+        };
         
+        // Prices may have moved since the scan; bail before broadcasting a stale route
+        if let Err(e) = self.validate_slippage_pre_submission(opportunity).await {
+            if let Some(bot_state) = &self.bot_state {
+                bot_state.record_slippage_revalidation_failed();
+            }
+            return Err(e);
+        }
+
         // Validate transaction security
-        self.validate_transaction_security(&instructions, trading_keypair)?;
+        self.validate_transaction_security(&instructions, &opportunity.route.steps, trading_keypair)?;
         
         // Simulate transaction first
-        let simulation_result = self.simulate_transaction(&instructions, trading_keypair).await?;
+        let simulation = self.simulate_with_logs_decoded(&instructions, trading_keypair).await?;
         
-        if !self.is_simulation_successful(&simulation_result) {
-            anyhow::bail!("Transaction simulation failed: {:?}", simulation_result.err);
+        if !simulation.success {
+            anyhow::bail!("Transaction simulation failed: {}", simulation.error.as_deref().unwrap_or("unknown error"));
         }
 
         // Validate simulation results
-        self.validate_simulation_results(&simulation_result)?;
+        self.validate_simulation_results(&simulation)?;
 
         info!("Simulation successful, proceeding with execution");
 
-        // Execute the transaction
-        let signature = self.send_transaction(instructions, trading_keypair).await?;
-        
-        // Wait for confirmation
-        self.wait_for_confirmation(&signature).await?;
+        // Snapshot intermediate balances so a failure can tell route output from existing holdings
+        let balances_before = if self.config().bot.auto_recover_partial_execution {
+            match self.intermediate_balances(opportunity, &trading_keypair.pubkey()).await {
+                Ok(balances) => Some(balances),
+                Err(e) => {
+                    warn!("Could not snapshot balances, partial execution recovery disabled for this trade: {}", e);
+                    None
+                }
+            }
+        } else {
+            None
+        };
+
+        // Starting token balance, so realized profit can be measured once confirmed
+        let start_balance = match self.start_token_balance(opportunity, &trading_keypair.pubkey()).await {
+            Ok(balance) => Some(balance),
+            Err(e) => {
+                warn!("Could not snapshot starting balance, realized profit will not be measured: {}", e);
+                None
+            }
+        };
+
+        // A bundle lands atomically and out of the public mempool, so only plain sends are
+        // exposed to a sandwich forming around the route
+        if self.sandwich_risk(opportunity).await {
+            if let Some(bot_state) = &self.bot_state {
+                bot_state.record_sandwich_risk_detected();
+            }
+            if !self.config().bot.use_jito {
+                anyhow::bail!("Aborting {}: a matching swap in the mempool looks like a sandwich setup", opportunity.id);
+            }
+            warn!("Possible sandwich setup around {}, relying on the Jito bundle", opportunity.id);
+        }
+
+        // Execute the transaction and wait for confirmation
+        let execution = async {
+            let signature = if self.config().bot.use_jito {
+                let tip_lamports = self.config().bot.jito_tip_lamports;
+                self.send_jito_bundle(instructions, tip_lamports, trading_keypair).await?
+            } else {
+                // Our own simulation above already passed, so preflight would only repeat it
+                let skip_preflight = Self::should_skip_preflight(
+                    opportunity.risk_score,
+                    opportunity.confidence_score,
+                    self.config().bot.allow_skip_preflight,
+                );
+                if skip_preflight {
+                    debug!("Skipping preflight for {}", opportunity.id);
+                }
+                self.send_transaction(instructions, trading_keypair, skip_preflight).await?
+            };
+            let (signature, blockhash) = signature;
+            self.wait_for_confirmation(&signature, &blockhash).await?;
+            Ok::<_, anyhow::Error>(signature)
+        }.await;
+        self.spawn_wallet_balance_refresh();
+
+        let signature = match execution {
+            Ok(signature) => signature,
+            Err(e) => {
+                MetricsCollector::global().increment_trades_failed();
+                if let Some(balances_before) = &balances_before {
+                    self.recover_partial_execution(opportunity, &e, balances_before, trading_keypair).await;
+                }
+                return Err(e);
+            }
+        };
         
         info!("Arbitrage executed successfully: {}", signature);
+        let mut realized_profit = 0;
+
+        if let Some(balance_before) = start_balance {
+            match self.start_token_balance(opportunity, &trading_keypair.pubkey()).await {
+                Ok(balance_after) => {
+                    let actual_profit = balance_after as i64 - balance_before as i64;
+                    info!(
+                        "Realized profit {} vs expected {} for {}",
+                        actual_profit, opportunity.expected_profit, opportunity.id
+                    );
+                    let fee = match self.transaction_fee(&signature).await {
+                        Ok(fee) => fee,
+                        Err(e) => {
+                            warn!("Could not read the fee of {}, assuming the base fee: {}", signature, e);
+                            BASE_TX_FEE_LAMPORTS
+                        }
+                    };
+                    let tip = {
+                        let config = self.config();
+                        if config.bot.use_jito { config.bot.jito_tip_lamports } else { 0 }
+                    };
+                    // The stop-loss limits are in SOL and count what the trade cost to send
+                    match self.start_token_in_lamports(opportunity, actual_profit).await {
+                        Ok(Some(profit)) => self.risk_manager.record_trade(profit - (fee + tip) as i64),
+                        Ok(None) => warn!("No price for the start token of {}, not counted towards the stop-loss", opportunity.id),
+                        Err(e) => warn!("Could not value the profit of {} in SOL: {}", opportunity.id, e),
+                    }
+                    realized_profit = actual_profit;
+                    if let Err(e) = self.record_trade(opportunity, &signature, actual_profit, fee + tip) {
+                        warn!("Failed to record trade {}: {}", signature, e);
+                    }
+                }
+                Err(e) => warn!("Could not measure realized profit for {}: {}", signature, e),
+            }
+        }
+        MetricsCollector::global().increment_trades_executed(realized_profit);
+
+        if let Some(notifier) = &self.notifier {
+            if let Err(e) = notifier.notify_trade_executed(opportunity, &signature.to_string()).await {
+                warn!("Failed to send trade notification: {}", e);
+            }
+        }
+
         Ok(signature.to_string())
     }
 
+    /// Realized against expected profit over the most recent recorded trades
+    pub fn profit_divergence(&self) -> Result<Option<ProfitDivergence>> {
+        let Some(history) = &self.trade_history else {
+            return Ok(None);
+        };
+        let history = history.lock().map_err(|_| anyhow::anyhow!("Trade history lock poisoned"))?;
+        history.profit_divergence(PROFIT_DIVERGENCE_WINDOW)
+    }
+
+    /// Half-Kelly position size from recent trade outcomes, capped at `max_position_size_sol`.
+    /// None when position sizing is disabled or there is too little history.
+    pub fn kelly_position_size_sol(&self) -> Result<Option<f64>> {
+        if !self.config().risk_management.position_sizing_enabled {
+            return Ok(None);
+        }
+        let Some(history) = &self.trade_history else {
+            return Ok(None);
+        };
+        let since = chrono::Utc::now() - chrono::Duration::hours(KELLY_WINDOW_HOURS);
+        let outcomes = history.lock()
+            .map_err(|_| anyhow::anyhow!("Trade history lock poisoned"))?
+            .trade_outcomes(since)?;
+        let Some(outcomes) = outcomes.filter(|outcomes| outcomes.trades >= KELLY_MIN_TRADES) else {
+            return Ok(None);
+        };
+
+        let fraction = RiskManager::compute_kelly_fraction(
+            outcomes.win_rate, outcomes.avg_profit_ratio, outcomes.avg_loss_ratio,
+        );
+        let max_position_size_sol = self.config().bot.max_position_size_sol;
+        let size = (fraction * 0.5 * max_position_size_sol).min(max_position_size_sol);
+        debug!("Half-Kelly position size {:.4} SOL from {} trades (win rate {:.2})",
+               size, outcomes.trades, outcomes.win_rate);
+        Ok(Some(size))
+    }
+
+    /// `opportunity` re-quoted at the half-Kelly size when its input is larger. A flash
+    /// loan borrows the input, so the wallet-based Kelly size doesn't apply to it.
+    fn kelly_sized<'a>(&self, opportunity: &'a ArbitrageOpportunity) -> Result<Cow<'a, ArbitrageOpportunity>> {
+        if self.config().bot.enable_flash_loans {
+            return Ok(Cow::Borrowed(opportunity));
+        }
+        let Some(size_sol) = self.kelly_position_size_sol()? else {
+            return Ok(Cow::Borrowed(opportunity));
+        };
+        let size = (size_sol * 1_000_000_000.0) as u64;
+        if opportunity.input_amount <= size {
+            return Ok(Cow::Borrowed(opportunity));
+        }
+        if size == 0 {
+            anyhow::bail!("Half-Kelly position size is zero after recent losses");
+        }
+
+        let sized = self.requote(opportunity, size)
+            .with_context(|| format!("Opportunity {} at half-Kelly size {:.4} SOL", opportunity.id, size_sol))?;
+        info!("Sized {} down from {} to {} lamports (half-Kelly), expected profit {} lamports",
+              opportunity.id, opportunity.input_amount, size, sized.expected_profit);
+        Ok(Cow::Owned(sized))
+    }
+
+    /// `opportunity` with every step priced again for an input of `input_amount`
+    fn requote(&self, opportunity: &ArbitrageOpportunity, input_amount: u64) -> Result<ArbitrageOpportunity> {
+        let max_slippage_percent = self.config().bot.max_slippage_for(&opportunity.route.route_type);
+        let mut requoted = opportunity.clone();
+        let mut amount = input_amount;
+        for step in &mut requoted.route.steps {
+            let a_to_b = matches!(step.direction, TradeDirection::Buy);
+            let (reserve_in, reserve_out) = if a_to_b {
+                (step.pool.reserve_a, step.pool.reserve_b)
+            } else {
+                (step.pool.reserve_b, step.pool.reserve_a)
+            };
+            let output = Screener::swap_output(&step.pool, amount, a_to_b)?;
+            step.input_amount = amount;
+            step.expected_output = output;
+            step.price_impact = calculate_price_impact_bps(amount, reserve_in, reserve_out);
+            step.slippage = calculate_slippage(output, reserve_out, max_slippage_percent)?;
+            amount = output;
+        }
+        if amount <= input_amount {
+            anyhow::bail!("Route is not profitable with {} lamports in", input_amount);
+        }
+
+        let profit = amount - input_amount;
+        requoted.input_amount = input_amount;
+        requoted.expected_output = amount;
+        requoted.expected_profit = profit;
+        requoted.expected_profit_percent = profit as f64 / input_amount as f64 * 100.0;
+        Ok(requoted)
+    }
+
+    /// Re-read every step's pool and check the route still clears each step's slippage limit
+    pub async fn validate_slippage_pre_submission(&self, opportunity: &ArbitrageOpportunity) -> Result<()> {
+        let max_slippage_percent = self.config().bot.max_slippage_for(&opportunity.route.route_type);
+        let steps = &opportunity.route.steps;
+
+        let addresses: Vec<Pubkey> = steps.iter().map(|step| step.pool.address).collect();
+        let accounts = self.rpc_client.get_multiple_accounts(&addresses).await?;
+
+        for (step, account) in steps.iter().zip(accounts) {
+            let mut pool = step.pool.clone();
+            let client = self.dex_clients.iter()
+                .find(|client| same_dex(client.get_dex_name(), &pool.dex))
+                .with_context(|| format!("No {} client to refresh pool {}", pool.dex, pool.address))?;
+
+            let parsed = account.is_some_and(|account| client.parse_account_data(&mut pool, &account.data).is_ok());
+            if !parsed {
+                client.update_pool_reserves(&mut pool).await
+                    .with_context(|| format!("Failed to refresh pool {}", pool.address))?;
+            }
+
+            let a_to_b = matches!(step.direction, TradeDirection::Buy);
+            let fresh_output = Screener::swap_output(&pool, step.input_amount, a_to_b)?;
+            let min_output = step.expected_output as f64 * (1.0 - max_slippage_percent / 100.0);
+            if (fresh_output as f64) < min_output {
+                anyhow::bail!(
+                    "Slippage check failed on {} pool {}: fresh output {} below minimum {:.0} (expected {})",
+                    pool.dex, pool.address, fresh_output, min_output, step.expected_output
+                );
+            }
+            debug!("Pool {} still returns {} (expected {})", pool.address, fresh_output, step.expected_output);
+        }
+
+        Ok(())
+    }
+
+    /// Fail with `InsufficientBalanceError` unless the wallet holds enough SOL for fees
+    /// and rent, and enough of the route's start token for its input. Later steps spend
+    /// what earlier ones produce, and a flash loan borrows the input, so only the
+    /// start token is checked. A wrapped SOL input the wallet's wrapped SOL account
+    /// doesn't cover is wrapped from its SOL balance, so it counts against that instead.
+    pub async fn check_wallet_balance_sufficient(&self, opportunity: &ArbitrageOpportunity, trading_keypair: &Keypair) -> Result<()> {
+        let owner = trading_keypair.pubkey();
+        let input = self.wallet_funded_input(opportunity)?;
+        let fee_lamports = self.trade_fee_lamports(opportunity).await;
+
+        let available = self.rpc_client.get_sol_balance(&owner).await?;
+        let held = match input {
+            Some((mint, _)) => {
+                let ata = spl_associated_token_account::get_associated_token_address(&owner, &mint);
+                self.rpc_client.get_multiple_token_account_balances(&[ata]).await?
+                    .first().copied().flatten().unwrap_or(0)
+            }
+            None => 0,
+        };
+
+        let required = Self::required_sol(fee_lamports, input, held);
+        if available < required {
+            return Err(InsufficientBalanceError::Sol { available, required }.into());
+        }
+        if let Some((mint, amount)) = input.filter(|(mint, _)| *mint != spl_token::native_mint::id()) {
+            if held < amount {
+                return Err(InsufficientBalanceError::Token { mint, available: held, required: amount }.into());
+            }
+        }
+        Ok(())
+    }
+
+    /// Start mint and amount the wallet itself must supply; `None` when a flash loan
+    /// borrows the input
+    fn wallet_funded_input(&self, opportunity: &ArbitrageOpportunity) -> Result<Option<(Pubkey, u64)>> {
+        let first_step = opportunity.route.steps.first()
+            .context("Opportunity has no steps")?;
+        if self.config().bot.enable_flash_loans {
+            return Ok(None);
+        }
+        Ok(Some((Self::step_input_mint(first_step), opportunity.input_amount)))
+    }
+
+    /// Fees, tip and rent reserve `opportunity` needs from the wallet, in lamports
+    async fn trade_fee_lamports(&self, opportunity: &ArbitrageOpportunity) -> u64 {
+        let jito_tip = {
+            let config = self.config();
+            if config.bot.use_jito { config.bot.jito_tip_lamports } else { 0 }
+        };
+        let priority_fee = self.priority_fee().await;
+        Self::required_lamports(opportunity.estimated_compute_units(), priority_fee, jito_tip)
+    }
+
+    /// SOL a wallet needs for a trade: `fee_lamports`, plus whatever part of a wrapped
+    /// SOL input its wrapped SOL account (`held`) doesn't already cover
+    fn required_sol(fee_lamports: u64, input: Option<(Pubkey, u64)>, held: u64) -> u64 {
+        match input {
+            Some((mint, amount)) if mint == spl_token::native_mint::id() => fee_lamports.saturating_add(amount.saturating_sub(held)),
+            _ => fee_lamports,
+        }
+    }
+
+    /// Whether a wallet holding `sol` lamports and `held` of the start token can fund a trade
+    fn wallet_covers(sol: u64, held: u64, fee_lamports: u64, input: Option<(Pubkey, u64)>) -> bool {
+        let token_covered = match input {
+            Some((mint, amount)) if mint != spl_token::native_mint::id() => held >= amount,
+            _ => true,
+        };
+        token_covered && sol >= Self::required_sol(fee_lamports, input, held)
+    }
+
+    /// SOL a trade needs: the base and priority fees, any Jito tip, and the rent reserve
+    fn required_lamports(compute_units: u32, priority_fee_microlamports: u64, jito_tip: u64) -> u64 {
+        let priority_fee = (priority_fee_microlamports as u128 * compute_units as u128 / 1_000_000) as u64;
+        BASE_TX_FEE_LAMPORTS + priority_fee + jito_tip + RENT_RESERVE_LAMPORTS
+    }
+
+    /// Wallet balance of the token the route starts and ends with
+    async fn start_token_balance(&self, opportunity: &ArbitrageOpportunity, owner: &Pubkey) -> Result<u64> {
+        let first_step = opportunity.route.steps.first()
+            .context("Opportunity has no steps")?;
+
+        let mint = Self::step_input_mint(first_step);
+        let ata = spl_associated_token_account::get_associated_token_address(owner, &mint);
+        Ok(self.rpc_client.try_get_token_account_balance(&ata).await?.unwrap_or(0))
+    }
+
+    /// Persist a confirmed trade. Realized profit is the change in the starting token
+    /// balance; the network fee and any Jito tip are recorded separately as gas.
+    fn record_trade(&self, opportunity: &ArbitrageOpportunity, signature: &Signature, actual_profit: i64, gas_lamports: u64) -> Result<()> {
+        let Some(history) = &self.trade_history else {
+            return Ok(());
+        };
+        let history = history.lock().map_err(|_| anyhow::anyhow!("Trade history lock poisoned"))?;
+        history.record_trade(opportunity, &signature.to_string(), actual_profit, gas_lamports)
+    }
+
+    /// Network fee a confirmed transaction paid, in lamports
+    async fn transaction_fee(&self, signature: &Signature) -> Result<u64> {
+        let transaction = self.rpc_client.get_transaction_info(&signature.to_string()).await?;
+        transaction.get("meta")
+            .and_then(|meta| meta.get("fee"))
+            .and_then(|fee| fee.as_u64())
+            .context("Transaction has no fee")
+    }
+
+    /// `amount` of the route's start token in lamports at current prices; None when
+    /// the token or SOL has no price
+    async fn start_token_in_lamports(&self, opportunity: &ArbitrageOpportunity, amount: i64) -> Result<Option<i64>> {
+        let first_step = opportunity.route.steps.first()
+            .context("Opportunity has no steps")?;
+        let mint = Self::step_input_mint(first_step);
+        let sol = spl_token::native_mint::id();
+        if mint == sol {
+            return Ok(Some(amount));
+        }
+        let decimals = match first_step.direction {
+            TradeDirection::Buy => first_step.pool.token_a.decimals,
+            TradeDirection::Sell => first_step.pool.token_b.decimals,
+        };
+
+        let prices = self.price_feed.get_prices(&[mint, sol]).await?;
+        Ok(match (prices.get(&mint), prices.get(&sol)) {
+            (Some(price), Some(sol_price)) => Some(Self::token_amount_in_lamports(amount, decimals, *price, *sol_price)),
+            _ => None,
+        })
+    }
+
+    fn token_amount_in_lamports(amount: i64, decimals: u8, price_usd: f64, sol_price_usd: f64) -> i64 {
+        let tokens = amount as f64 / 10_f64.powi(decimals as i32);
+        (tokens * price_usd / sol_price_usd * 1_000_000_000.0).round() as i64
+    }
+
+    /// Whether any step of the route matches a recent same-pair swap of similar size
+    async fn sandwich_risk(&self, opportunity: &ArbitrageOpportunity) -> bool {
+        let Some(mempool_monitor) = &self.mempool_monitor else {
+            return false;
+        };
+        for step in &opportunity.route.steps {
+            let token_in = Self::step_input_mint(step).to_string();
+            let token_out = Self::step_output_mint(step).to_string();
+            if mempool_monitor.detect_sandwich_risk(&token_in, &token_out, step.input_amount).await {
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Mint spent by a swap step
+    fn step_input_mint(step: &TradeStep) -> Pubkey {
+        match step.direction {
+            TradeDirection::Buy => step.pool.token_a.mint,
+            TradeDirection::Sell => step.pool.token_b.mint,
+        }
+    }
+
+    /// Mint received by a swap step
+    fn step_output_mint(step: &TradeStep) -> Pubkey {
+        match step.direction {
+            TradeDirection::Buy => step.pool.token_b.mint,
+            TradeDirection::Sell => step.pool.token_a.mint,
+        }
+    }
+
+    /// `owner`'s balances of every token the route passes through, excluding the final output
+    async fn intermediate_balances(&self, opportunity: &ArbitrageOpportunity, owner: &Pubkey) -> Result<HashMap<Pubkey, u64>> {
+        let steps = &opportunity.route.steps;
+
+        let mut balances = HashMap::new();
+        for step in steps.iter().take(steps.len().saturating_sub(1)) {
+            let mint = Self::step_output_mint(step);
+            let ata = spl_associated_token_account::get_associated_token_address(owner, &mint);
+            let balance = self.rpc_client.try_get_token_account_balance(&ata).await?.unwrap_or(0);
+            balances.insert(mint, balance);
+        }
+        Ok(balances)
+    }
+
+    /// Check whether a failed route left an intermediate token behind and, if so,
+    /// build the swaps that walk it back through the executed steps to the start token.
+    /// `balances_before` comes from `intermediate_balances` of `owner` taken before sending.
+    pub async fn detect_partial_execution(
+        &self,
+        opportunity: &ArbitrageOpportunity,
+        error: &anyhow::Error,
+        balances_before: &HashMap<Pubkey, u64>,
+        owner: &Pubkey,
+    ) -> Result<Option<RecoveryPlan>> {
+        debug!("Checking route {} for partial execution after error: {}", opportunity.id, error);
+        let balances_after = self.intermediate_balances(opportunity, owner).await?;
+
+        // The last step whose output grew is where the route stopped
+        let steps = &opportunity.route.steps;
+        for (index, step) in steps.iter().enumerate().take(steps.len().saturating_sub(1)).rev() {
+            let mint = Self::step_output_mint(step);
+            let before = balances_before.get(&mint).copied().unwrap_or(0);
+            let after = balances_after.get(&mint).copied().unwrap_or(0);
+            let stranded_amount = after.saturating_sub(before);
+            if stranded_amount == 0 {
+                continue;
+            }
+
+            let max_slippage_percent = self.config().bot.max_slippage_for(&opportunity.route.route_type);
+            let mut instructions = Vec::new();
+            for recovery_step in Self::recovery_steps(&steps[..=index], stranded_amount)? {
+                let is_first = instructions.is_empty();
+                instructions.extend(self.build_step_instructions(&recovery_step, is_first, max_slippage_percent, owner).await?);
+            }
+
+            return Ok(Some(RecoveryPlan {
+                failed_step: index + 1,
+                stranded_mint: mint,
+                stranded_amount,
+                instructions,
+            }));
+        }
+
+        Ok(None)
+    }
+
+    /// Reverse `executed` steps, last first, starting from `amount` of the last step's output
+    fn recovery_steps(executed: &[TradeStep], amount: u64) -> Result<Vec<TradeStep>> {
+        let mut steps = Vec::new();
+        let mut current_amount = amount;
+        for step in executed.iter().rev() {
+            let (direction, reserve_in, reserve_out) = match step.direction {
+                TradeDirection::Buy => (TradeDirection::Sell, step.pool.reserve_b, step.pool.reserve_a),
+                TradeDirection::Sell => (TradeDirection::Buy, step.pool.reserve_a, step.pool.reserve_b),
+            };
+            let expected_output = calculate_output_amount(current_amount, reserve_in, reserve_out, step.pool.fee_percent)?;
+
+            steps.push(TradeStep {
+                pool: step.pool.clone(),
+                direction,
+                input_amount: current_amount,
+                expected_output,
+                price_impact: 0,
+                slippage: Decimal::ZERO,
+            });
+            current_amount = expected_output;
+        }
+        Ok(steps)
+    }
+
+    async fn recover_partial_execution(
+        &self,
+        opportunity: &ArbitrageOpportunity,
+        error: &anyhow::Error,
+        balances_before: &HashMap<Pubkey, u64>,
+        trading_keypair: &Keypair,
+    ) {
+        let plan = match self.detect_partial_execution(opportunity, error, balances_before, &trading_keypair.pubkey()).await {
+            Ok(Some(plan)) => plan,
+            Ok(None) => return,
+            Err(e) => {
+                warn!("Could not check route {} for partial execution: {}", opportunity.id, e);
+                return;
+            }
+        };
+
+        warn!(
+            "Route {} stopped before step {}: {} of {} stranded, attempting recovery",
+            opportunity.id, plan.failed_step + 1, plan.stranded_amount, plan.stranded_mint
+        );
+
+        let recovery = async {
+            self.validate_transaction_security(&plan.instructions, &opportunity.route.steps, trading_keypair)?;
+            let (signature, blockhash) = self.send_transaction(plan.instructions, trading_keypair, false).await?;
+            self.wait_for_confirmation(&signature, &blockhash).await?;
+            Ok::<_, anyhow::Error>(signature)
+        }.await;
+
+        match recovery {
+            Ok(signature) => {
+                self.partial_execution_recoveries.fetch_add(1, Ordering::Relaxed);
+                info!("Recovered stranded {} with transaction {}", plan.stranded_mint, signature);
+            }
+            Err(e) => warn!("Recovery of stranded {} failed: {}", plan.stranded_mint, e),
+        }
+    }
+
     async fn simulate_arbitrage(&self, opportunity: &ArbitrageOpportunity) -> Result<String> {
         info!("Simulating arbitrage opportunity: {}", opportunity.id);
         
@@ -102,73 +1107,285 @@ impl Executor {
         Ok(format!("simulated_{}", opportunity.id))
     }
 
-    async fn build_arbitrage_instructions(&self, opportunity: &ArbitrageOpportunity) -> Result<Vec<Instruction>> {
-        let mut instructions = Vec::new();
+    /// The whole route as one all-or-nothing transaction: a compute budget covering every
+    /// swap, the priority fee, the swaps, and finally a guard that fails the transaction
+    /// unless the start token balance ends at least where the route's minimum output puts it
+    pub async fn build_atomic_arbitrage_transaction(&self, opportunity: &ArbitrageOpportunity, trading_keypair: &Keypair) -> Result<Vec<Instruction>> {
+        let owner = trading_keypair.pubkey();
+        self.ensure_route_token_accounts(opportunity, trading_keypair).await?;
+        let start_balance = self.start_token_balance(opportunity, &owner).await?;
+        self.atomic_route_instructions(opportunity, &owner, start_balance).await
+    }
+
+    /// `build_atomic_arbitrage_transaction` for an `owner` holding `start_balance` of the
+    /// start token
+    async fn atomic_route_instructions(&self, opportunity: &ArbitrageOpportunity, owner: &Pubkey, start_balance: u64) -> Result<Vec<Instruction>> {
+        let first_step = opportunity.route.steps.first()
+            .context("Opportunity has no steps")?;
+        let last_step = opportunity.route.steps.last()
+            .context("Opportunity has no steps")?;
 
-        // Add compute budget instruction to ensure enough compute units
-        let compute_units = self.estimate_compute_units(opportunity)?;
-        instructions.push(ComputeBudgetInstruction::set_compute_unit_limit(compute_units));
+        // The first swap spends the input from the start token account and the last refills it
+        let max_slippage_percent = self.config().bot.max_slippage_for(&opportunity.route.route_type);
+        let min_output = minimum_amount_out(last_step.expected_output, max_slippage_percent);
+        let start_mint = Self::step_input_mint(first_step);
+        // A wrapped SOL input beyond what the account holds is wrapped from the wallet's SOL
+        let wrap_lamports = if start_mint == spl_token::native_mint::id() {
+            opportunity.input_amount.saturating_sub(start_balance)
+        } else {
+            0
+        };
+        let expected_min_output = (start_balance + wrap_lamports).saturating_sub(opportunity.input_amount).saturating_add(min_output);
+        let token_account = spl_associated_token_account::get_associated_token_address(owner, &start_mint);
+
+        let mut compute_units = self.estimate_compute_units(opportunity)?.saturating_add(GUARD_COMPUTE_UNITS);
+        if wrap_lamports > 0 {
+            compute_units = compute_units.saturating_add(WRAP_SOL_COMPUTE_UNITS);
+        }
+        let mut instructions = self.compute_budget_instructions(compute_units.min(MAX_COMPUTE_UNITS)).await;
+        if wrap_lamports > 0 {
+            instructions.extend(Self::wrap_sol_instructions(owner, wrap_lamports)?);
+        }
+        instructions.extend(self.build_swap_instructions(opportunity, owner).await?);
+        instructions.push(build_minimum_output_check_instruction(expected_min_output, &token_account));
+        Ok(instructions)
+    }
 
+    /// Move `lamports` into `owner`'s wrapped SOL account, creating it if needed
+    fn wrap_sol_instructions(owner: &Pubkey, lamports: u64) -> Result<Vec<Instruction>> {
+        let native_mint = spl_token::native_mint::id();
+        let ata = spl_associated_token_account::get_associated_token_address(owner, &native_mint);
+        Ok(vec![
+            spl_associated_token_account::instruction::create_associated_token_account_idempotent(
+                owner,
+                owner,
+                &native_mint,
+                &spl_token::id(),
+            ),
+            system_instruction::transfer(owner, &ata, lamports),
+            spl_token::instruction::sync_native(&spl_token::id(), &ata)?,
+        ])
+    }
+
+    /// Create any missing token account for the tokens the route trades, when
+    /// `bot.auto_create_token_accounts` is set
+    async fn ensure_route_token_accounts(&self, opportunity: &ArbitrageOpportunity, trading_keypair: &Keypair) -> Result<()> {
+        if !self.config().bot.auto_create_token_accounts {
+            return Ok(());
+        }
+
+        for step in &opportunity.route.steps {
+            for mint in [step.pool.token_a.mint, step.pool.token_b.mint] {
+                self.ensure_token_account_exists(&mint, trading_keypair).await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Associated token account of `keypair` for `token_mint`, created and confirmed
+    /// first if it doesn't exist yet
+    pub async fn ensure_token_account_exists(&self, token_mint: &Pubkey, keypair: &Keypair) -> Result<Pubkey> {
+        let owner = keypair.pubkey();
+        let ata = spl_associated_token_account::get_associated_token_address(&owner, token_mint);
+        if self.token_accounts.lock().await.contains(&ata) {
+            return Ok(ata);
+        }
+
+        if self.rpc_client.try_get_account(&ata).await?.is_none() {
+            info!("Creating token account {} for mint {}", ata, token_mint);
+            // Idempotent, so a concurrent creation can't fail the transaction
+            let instruction = spl_associated_token_account::instruction::create_associated_token_account_idempotent(
+                &owner,
+                &owner,
+                token_mint,
+                &spl_token::id(),
+            );
+            let recent_blockhash = self.rpc_client.get_latest_blockhash().await?;
+            let transaction = Transaction::new_signed_with_payer(&[instruction], Some(&owner), &[keypair], recent_blockhash);
+            let signature = self.rpc_client.send_and_confirm_transaction(&transaction).await
+                .with_context(|| format!("Failed to create token account for mint {}", token_mint))?;
+            debug!("Created token account {}: {}", ata, signature);
+        }
+
+        self.token_accounts.lock().await.insert(ata);
+        Ok(ata)
+    }
+
+    /// Compute unit limit for the whole transaction, then the priority fee
+    async fn compute_budget_instructions(&self, compute_units: u32) -> Vec<Instruction> {
         // Add priority fee instruction for faster processing
-        let priority_fee = 1000; // microlamports per compute unit
-        instructions.push(ComputeBudgetInstruction::set_compute_unit_price(priority_fee));
+        let priority_fee = self.priority_fee().await;
+
+        vec![
+            ComputeBudgetInstruction::set_compute_unit_limit(compute_units),
+            ComputeBudgetInstruction::set_compute_unit_price(priority_fee),
+        ]
+    }
+
+    /// Fee paid at `percentile` of recent slots, in microlamports per CU, uncapped. The
+    /// screener's last estimate is reused when it was taken at the same percentile, so
+    /// trades pay the fee their profit was netted against
+    pub async fn estimate_priority_fee(&self, percentile: u8) -> Result<u64> {
+        if let Some(screener) = &self.screener {
+            if percentile == self.config().bot.priority_fee_percentile {
+                if let Some(fee) = screener.recent_priority_fee().await {
+                    return Ok(fee);
+                }
+            }
+        }
+        self.rpc_client.get_recent_priority_fees(percentile).await
+    }
+
+    /// Microlamports per compute unit, from recent network fees and capped by config
+    async fn priority_fee(&self) -> u64 {
+        let (cap, percentile) = {
+            let config = self.config();
+            (config.bot.max_priority_fee_microlamports, config.bot.priority_fee_percentile)
+        };
+        {
+            let (fee, fetched_at) = *self.fee_cache.read().await;
+            if fetched_at.elapsed() < PRIORITY_FEE_TTL {
+                return fee.min(cap);
+            }
+        }
+
+        let mut cached = self.fee_cache.write().await;
+        // Another build may have refreshed it while we waited for the lock
+        if cached.1.elapsed() < PRIORITY_FEE_TTL {
+            return cached.0.min(cap);
+        }
 
+        match self.estimate_priority_fee(percentile).await {
+            Ok(fee) => {
+                debug!("Recent priority fee: {} microlamports per CU", fee);
+                if fee > cap {
+                    warn!("Priority fee estimate {} exceeds the {} microlamport cap, using the cap", fee, cap);
+                }
+                cached.0 = fee;
+            }
+            // Keep the previous estimate, but don't retry until the TTL passes again
+            Err(e) => warn!("Failed to estimate priority fee, using {}: {}", cached.0, e),
+        }
+        cached.1 = Instant::now();
+        cached.0.min(cap)
+    }
+
+    async fn build_swap_instructions(&self, opportunity: &ArbitrageOpportunity, trader: &Pubkey) -> Result<Vec<Instruction>> {
         // Build swap instructions for each step in the route
+        let max_slippage_percent = self.config().bot.max_slippage_for(&opportunity.route.route_type);
+        let mut instructions = Vec::new();
         for (i, step) in opportunity.route.steps.iter().enumerate() {
-            let swap_instruction = self.build_swap_instruction(step, i == 0).await?;
-            instructions.push(swap_instruction);
+            instructions.extend(self.build_step_instructions(step, i == 0, max_slippage_percent, trader).await?);
         }
+        Ok(instructions)
+    }
+
+    /// Route instructions wrapped in a MarginFi flash loan: borrow `borrow_amount` of the
+    /// starting token, run the swaps, then repay the loan plus fee in the same transaction
+    pub async fn build_flash_loan_arbitrage_instructions(
+        &self,
+        opportunity: &ArbitrageOpportunity,
+        borrow_amount: u64,
+        trading_keypair: &Keypair,
+    ) -> Result<Vec<Instruction>> {
+        let authority = trading_keypair.pubkey();
+        let first_step = opportunity.route.steps.first()
+            .context("Opportunity has no steps")?;
+
+        let mint = Self::step_input_mint(first_step);
+        let flash_loan = self.flash_loan_config(&authority, mint).await?;
+        let token_account = spl_associated_token_account::get_associated_token_address(&authority, &mint);
+        self.ensure_route_token_accounts(opportunity, trading_keypair).await?;
+
+        let mut instructions = self.compute_budget_instructions(self.estimate_compute_units(opportunity)?).await;
+        let swaps = self.build_swap_instructions(opportunity, &authority).await?;
+
+        // Start, borrow, swaps and repay come before the end instruction
+        let end_index = (instructions.len() + 2 + swaps.len() + 1) as u64;
+        instructions.push(flash_loan.start_flashloan(&authority, end_index));
+        instructions.push(flash_loan.borrow(&authority, &token_account, borrow_amount));
+        instructions.extend(swaps);
+        instructions.push(flash_loan.repay(&authority, &token_account, flash_loan.repay_amount(borrow_amount)));
+        instructions.push(flash_loan.end_flashloan(&authority));
 
         Ok(instructions)
     }
 
-    async fn build_swap_instruction(&self, step: &crate::models::TradeStep, _is_first: bool) -> Result<Instruction> {
+    async fn flash_loan_config(&self, authority: &Pubkey, mint: Pubkey) -> Result<FlashLoanConfig> {
+        let mut flash_loans = self.flash_loans.lock().await;
+        if let Some(config) = flash_loans.get(&(*authority, mint)) {
+            return Ok(config.clone());
+        }
+
+        let group = Pubkey::from_str(&self.config().bot.marginfi_group)
+            .context("Invalid marginfi_group")?;
+        let config = FlashLoanConfig::resolve(&self.rpc_client, group, *authority, mint).await?;
+        debug!("Resolved MarginFi bank {} for flash loans of {}", config.bank, mint);
+        flash_loans.insert((*authority, mint), config.clone());
+        Ok(config)
+    }
+
+    /// Instructions for one route step; one swap, except Jupiter steps which may come
+    /// with setup and cleanup instructions
+    async fn build_step_instructions(&self, step: &crate::models::TradeStep, is_first: bool, max_slippage_percent: f64, trader: &Pubkey) -> Result<Vec<Instruction>> {
+        if DexId::from_name(&step.pool.dex) == Some(DexId::Jupiter) {
+            return self.build_jupiter_swap_instructions(step, max_slippage_percent, trader).await;
+        }
+        Ok(vec![self.build_swap_instruction(step, is_first, max_slippage_percent, trader).await?])
+    }
+
+    async fn build_swap_instruction(&self, step: &crate::models::TradeStep, _is_first: bool, max_slippage_percent: f64, trader: &Pubkey) -> Result<Instruction> {
         // This is a placeholder implementation
         // In a real implementation, you would build actual swap instructions
         // based on the DEX (Orca, Raydium, Phoenix) and the specific program interfaces
 
-        match step.pool.dex.as_str() {
-            "orca" => self.build_orca_swap_instruction(step).await,
-            "raydium" => self.build_raydium_swap_instruction(step).await,
-            "phoenix" => self.build_phoenix_swap_instruction(step).await,
+        // Lowest output we accept for this step (Orca's other_amount_threshold, Raydium's minimum_amount_out)
+        let min_amount_out = minimum_amount_out(step.expected_output, max_slippage_percent);
+
+        match DexId::from_name(&step.pool.dex) {
+            Some(DexId::Orca) => self.build_orca_swap_instruction(step, min_amount_out, trader).await,
+            Some(DexId::Raydium) => self.build_raydium_swap_instruction(step, min_amount_out, trader).await,
+            Some(DexId::Phoenix) => self.build_phoenix_swap_instruction(step, min_amount_out, trader).await,
+            Some(DexId::Serum) => self.build_serum_swap_instruction(step, min_amount_out, trader).await,
+            Some(DexId::PumpFun) => self.build_pumpfun_swap_instruction(step, min_amount_out, trader).await,
             _ => anyhow::bail!("Unsupported DEX: {}", step.pool.dex),
         }
     }
 
-    async fn build_orca_swap_instruction(&self, step: &crate::models::TradeStep) -> Result<Instruction> {
+    async fn build_orca_swap_instruction(&self, step: &crate::models::TradeStep, min_amount_out: u64, trader: &Pubkey) -> Result<Instruction> {
         use solana_sdk::instruction::AccountMeta;
         
-        let program_id = Pubkey::from_str("whirLbMiicVdio4qvUfM5KAg6Ct8VwpYzGff3uctyCc")?; // Orca Whirlpool program ID
-        
-        let trading_keypair = self.trading_keypair.as_ref()
-            .context("No trading keypair configured")?;
+        let program_id = Pubkey::from_str(WHIRLPOOL_PROGRAM_ID)?;
+        let meta = step.pool.get_metadata::<OrcaPoolMeta>()
+            .context("Orca pool is missing whirlpool metadata")?;
         
         // Get associated token accounts for the trader
         let token_a_ata = spl_associated_token_account::get_associated_token_address(
-            &trading_keypair.pubkey(),
+            trader,
             &step.pool.token_a.mint,
         );
         let token_b_ata = spl_associated_token_account::get_associated_token_address(
-            &trading_keypair.pubkey(),
+            trader,
             &step.pool.token_b.mint,
         );
         
         // Build Orca Whirlpool swap instruction
         let accounts = vec![
             AccountMeta::new_readonly(spl_token::id(), false), // Token program
-            AccountMeta::new(trading_keypair.pubkey(), true), // Trader
+            AccountMeta::new(*trader, true), // Trader
             AccountMeta::new(step.pool.address, false), // Whirlpool
             AccountMeta::new(token_a_ata, false), // Token A account
+            AccountMeta::new(meta.vault_a, false), // Token A vault
             AccountMeta::new(token_b_ata, false), // Token B account
-            AccountMeta::new_readonly(step.pool.token_a.mint, false), // Token A mint
-            AccountMeta::new_readonly(step.pool.token_b.mint, false), // Token B mint
+            AccountMeta::new(meta.vault_b, false), // Token B vault
+            AccountMeta::new_readonly(meta.oracle, false), // Oracle
         ];
         
         // Simplified instruction data for Orca swap
         // In production, use proper Orca SDK instruction builders
         let mut instruction_data = vec![0x09]; // Swap instruction discriminator
         instruction_data.extend_from_slice(&step.input_amount.to_le_bytes());
-        instruction_data.extend_from_slice(&step.expected_output.to_le_bytes());
+        instruction_data.extend_from_slice(&min_amount_out.to_le_bytes());
         
         Ok(Instruction {
             program_id,
@@ -177,21 +1394,20 @@ impl Executor {
         })
     }
 
-    async fn build_raydium_swap_instruction(&self, step: &crate::models::TradeStep) -> Result<Instruction> {
+    async fn build_raydium_swap_instruction(&self, step: &crate::models::TradeStep, min_amount_out: u64, trader: &Pubkey) -> Result<Instruction> {
         use solana_sdk::instruction::AccountMeta;
         
-        let program_id = Pubkey::from_str("675kPX9MHTjS2zt1qfr1NYHuzeLXfQM9H24wFSUt1Mp8")?; // Raydium AMM program ID
-        
-        let trading_keypair = self.trading_keypair.as_ref()
-            .context("No trading keypair configured")?;
+        let program_id = Pubkey::from_str(RAYDIUM_AMM_PROGRAM_ID)?; // Raydium AMM program ID
+        let meta = step.pool.get_metadata::<RaydiumPoolMeta>()
+            .context("Raydium pool is missing AMM metadata")?;
         
         // Get associated token accounts for the trader
         let token_a_ata = spl_associated_token_account::get_associated_token_address(
-            &trading_keypair.pubkey(),
+            trader,
             &step.pool.token_a.mint,
         );
         let token_b_ata = spl_associated_token_account::get_associated_token_address(
-            &trading_keypair.pubkey(),
+            trader,
             &step.pool.token_b.mint,
         );
         
@@ -199,18 +1415,19 @@ impl Executor {
         let accounts = vec![
             AccountMeta::new_readonly(spl_token::id(), false), // Token program
             AccountMeta::new(step.pool.address, false), // AMM pool
-            AccountMeta::new_readonly(trading_keypair.pubkey(), true), // User authority
+            AccountMeta::new(meta.open_orders, false), // AMM open orders
+            AccountMeta::new(meta.vault_a, false), // Pool coin vault
+            AccountMeta::new(meta.vault_b, false), // Pool pc vault
+            AccountMeta::new_readonly(*trader, true), // User authority
             AccountMeta::new(token_a_ata, false), // User token A account
             AccountMeta::new(token_b_ata, false), // User token B account
-            AccountMeta::new_readonly(step.pool.token_a.mint, false), // Token A mint
-            AccountMeta::new_readonly(step.pool.token_b.mint, false), // Token B mint
         ];
         
         // Simplified instruction data for Raydium swap
         // In production, use proper Raydium SDK instruction builders
         let mut instruction_data = vec![0x09]; // Swap instruction discriminator
         instruction_data.extend_from_slice(&step.input_amount.to_le_bytes());
-        instruction_data.extend_from_slice(&step.expected_output.to_le_bytes());
+        instruction_data.extend_from_slice(&min_amount_out.to_le_bytes());
         
         Ok(Instruction {
             program_id,
@@ -219,28 +1436,27 @@ impl Executor {
         })
     }
 
-    async fn build_phoenix_swap_instruction(&self, step: &crate::models::TradeStep) -> Result<Instruction> {
+    async fn build_phoenix_swap_instruction(&self, step: &crate::models::TradeStep, min_amount_out: u64, trader: &Pubkey) -> Result<Instruction> {
         use solana_sdk::instruction::AccountMeta;
         
-        let program_id = Pubkey::from_str("PhoeNiXZ8ByJGLkxNfZRnkUfjvmuYqLR89jjFHGqdXY")?; // Phoenix program ID
-        
-        let trading_keypair = self.trading_keypair.as_ref()
-            .context("No trading keypair configured")?;
+        let program_id = Pubkey::from_str(PHOENIX_PROGRAM_ID)?;
+        let meta = step.pool.get_metadata::<PhoenixPoolMeta>()
+            .context("Phoenix market is missing lot size metadata")?;
         
         // Get associated token accounts for the trader
         let token_a_ata = spl_associated_token_account::get_associated_token_address(
-            &trading_keypair.pubkey(),
+            trader,
             &step.pool.token_a.mint,
         );
         let token_b_ata = spl_associated_token_account::get_associated_token_address(
-            &trading_keypair.pubkey(),
+            trader,
             &step.pool.token_b.mint,
         );
         
         // Build Phoenix swap instruction
         let accounts = vec![
             AccountMeta::new_readonly(spl_token::id(), false), // Token program
-            AccountMeta::new(trading_keypair.pubkey(), true), // Trader
+            AccountMeta::new(*trader, true), // Trader
             AccountMeta::new(step.pool.address, false), // Phoenix market
             AccountMeta::new(token_a_ata, false), // Token A account
             AccountMeta::new(token_b_ata, false), // Token B account
@@ -248,11 +1464,30 @@ impl Executor {
             AccountMeta::new_readonly(step.pool.token_b.mint, false), // Token B mint
         ];
         
+        // Phoenix IOC orders are sized in lots, so convert from native units.
+        // Buy steps spend token A (base) for quote, which is an ask on the book.
+        let (side, num_base_lots, num_quote_lots) = match step.direction {
+            TradeDirection::Buy => (
+                1u8,
+                PhoenixClient::native_to_lots(step.input_amount, meta.base_lot_size),
+                PhoenixClient::native_to_lots(min_amount_out, meta.quote_lot_size),
+            ),
+            TradeDirection::Sell => (
+                0u8,
+                PhoenixClient::native_to_lots(min_amount_out, meta.base_lot_size),
+                PhoenixClient::native_to_lots(step.input_amount, meta.quote_lot_size),
+            ),
+        };
+        if num_base_lots == 0 || num_quote_lots == 0 {
+            anyhow::bail!("Phoenix order for market {} is smaller than one lot", step.pool.address);
+        }
+
         // Simplified instruction data for Phoenix swap
         // In production, use proper Phoenix SDK instruction builders
         let mut instruction_data = vec![0x01]; // Swap instruction discriminator
-        instruction_data.extend_from_slice(&step.input_amount.to_le_bytes());
-        instruction_data.extend_from_slice(&step.expected_output.to_le_bytes());
+        instruction_data.push(side);
+        instruction_data.extend_from_slice(&num_base_lots.to_le_bytes());
+        instruction_data.extend_from_slice(&num_quote_lots.to_le_bytes());
         
         Ok(Instruction {
             program_id,
@@ -261,46 +1496,259 @@ impl Executor {
         })
     }
 
+    async fn build_serum_swap_instruction(&self, step: &crate::models::TradeStep, min_amount_out: u64, trader: &Pubkey) -> Result<Instruction> {
+        use solana_sdk::instruction::AccountMeta;
+
+        // Migrated markets live under OpenBook, so route to whichever program owns the market
+        let version = step.pool.get_metadata::<SerumPoolMeta>()
+            .map(|meta| meta.version)
+            .with_context(|| format!("Serum market {} has no program version", step.pool.address))?;
+        let program_id = version.program_id();
+        debug!("Building {:?} swap for market {}", version, step.pool.address);
+
+        // Get associated token accounts for the trader
+        let token_a_ata = spl_associated_token_account::get_associated_token_address(
+            trader,
+            &step.pool.token_a.mint,
+        );
+        let token_b_ata = spl_associated_token_account::get_associated_token_address(
+            trader,
+            &step.pool.token_b.mint,
+        );
+
+        let accounts = vec![
+            AccountMeta::new(step.pool.address, false), // Market
+            AccountMeta::new(*trader, true), // Trader
+            AccountMeta::new(token_a_ata, false), // Base token account
+            AccountMeta::new(token_b_ata, false), // Quote token account
+            AccountMeta::new_readonly(spl_token::id(), false), // Token program
+        ];
+
+        // SendTake (tag 13) fills immediately against the book without open orders
+        let mut instruction_data = instruction_header(13);
+        instruction_data.extend_from_slice(&step.input_amount.to_le_bytes());
+        instruction_data.extend_from_slice(&min_amount_out.to_le_bytes());
+
+        Ok(Instruction {
+            program_id,
+            accounts,
+            data: instruction_data,
+        })
+    }
+
+    /// Jupiter routes are re-quoted at execution time and the instructions come from its
+    /// swap API; slippage is enforced by Jupiter against the fresh quote. The lookup
+    /// tables the swap needs are fetched once and cached for building the v0 message.
+    async fn build_jupiter_swap_instructions(&self, step: &TradeStep, max_slippage_percent: f64, trader: &Pubkey) -> Result<Vec<Instruction>> {
+        let input_mint = Self::step_input_mint(step);
+        let output_mint = Self::step_output_mint(step);
+        let slippage_bps = (max_slippage_percent * 100.0).round().clamp(0.0, u16::MAX as f64) as u16;
+
+        let quote = self.jupiter_api
+            .quote(&input_mint, &output_mint, step.input_amount, slippage_bps)
+            .await?;
+        debug!(
+            "Jupiter quote {} -> {}: {} in, {} out (expected {})",
+            input_mint, output_mint, quote.in_amount, quote.out_amount, step.expected_output
+        );
+
+        // SOL stays wrapped between steps: later steps and the minimum-output check read
+        // the wrapped SOL account, and the executor wraps any SOL input itself
+        let swap = self.jupiter_api.swap_instructions(&quote, trader, false).await?;
+
+        let missing: Vec<Pubkey> = {
+            let cached = self.jupiter_lookup_tables.read().await;
+            swap.address_lookup_table_addresses.iter().filter(|key| !cached.contains_key(key)).copied().collect()
+        };
+        for key in missing {
+            let table = self.fetch_lookup_table(key).await
+                .with_context(|| format!("Failed to load Jupiter lookup table {}", key))?;
+            let mut cached = self.jupiter_lookup_tables.write().await;
+            if cached.len() >= MAX_CACHED_JUPITER_LOOKUP_TABLES {
+                cached.clear();
+            }
+            cached.insert(key, table);
+        }
+
+        Ok(swap.instructions())
+    }
+
+    async fn build_pumpfun_swap_instruction(&self, step: &crate::models::TradeStep, min_amount_out: u64, trader: &Pubkey) -> Result<Instruction> {
+        use solana_sdk::instruction::AccountMeta;
+
+        let program_id = Pubkey::from_str(PUMPFUN_PROGRAM_ID)?;
+        let meta = step.pool.get_metadata::<PumpFunPoolMeta>()
+            .context("Pump.fun pool is missing bonding curve metadata")?;
+
+        // token_a is the meme token, token_b is SOL
+        let token_ata = spl_associated_token_account::get_associated_token_address(
+            trader,
+            &step.pool.token_a.mint,
+        );
+
+        let accounts = vec![
+            AccountMeta::new_readonly(step.pool.token_a.mint, false), // Token mint
+            AccountMeta::new(meta.bonding_curve, false), // Bonding curve
+            AccountMeta::new(meta.associated_bonding_curve, false), // Bonding curve token account
+            AccountMeta::new(token_ata, false), // User token account
+            AccountMeta::new(*trader, true), // User
+            AccountMeta::new_readonly(solana_sdk::system_program::id(), false), // System program
+            AccountMeta::new_readonly(spl_token::id(), false), // Token program
+        ];
+
+        // Simplified instruction data for a Pump.fun buy/sell
+        let mut instruction_data = match step.direction {
+            crate::types::TradeDirection::Buy => vec![0x66, 0x06, 0x3d, 0x12, 0x01, 0xda, 0xeb, 0xea],
+            crate::types::TradeDirection::Sell => vec![0x33, 0xe6, 0x85, 0xa4, 0x01, 0x7f, 0x83, 0xad],
+        };
+        instruction_data.extend_from_slice(&step.input_amount.to_le_bytes());
+        instruction_data.extend_from_slice(&min_amount_out.to_le_bytes());
+
+        Ok(Instruction {
+            program_id,
+            accounts,
+            data: instruction_data,
+        })
+    }
+
     fn estimate_compute_units(&self, opportunity: &ArbitrageOpportunity) -> Result<u32> {
-        // Estimate compute units based on the number of steps and complexity
-        let base_units = 50_000u32;
-        let per_step_units = 100_000u32;
-        
-        let total_units = base_units + (opportunity.route.steps.len() as u32 * per_step_units);
-        
-        // Cap at maximum allowed compute units
-        Ok(total_units.min(1_400_000))
+        Ok(opportunity.estimated_compute_units())
     }
 
     async fn simulate_transaction(&self, instructions: &[Instruction], keypair: &Keypair) -> Result<RpcSimulateTransactionResult> {
-        let recent_blockhash = self.rpc_client.get_latest_blockhash().await?;
-        
-        let message = Message::new(instructions, Some(&keypair.pubkey()));
-        let transaction = Transaction::new(&[keypair], message, recent_blockhash);
-        
-        let simulation_result = self.rpc_client.simulate_transaction(&transaction).await?;
+        let lookup_tables = self.transaction_lookup_tables(instructions).await;
+        let simulation_result = if self.use_versioned_transaction(&lookup_tables) {
+            let transaction = self.build_versioned_transaction_v0(instructions, keypair, &lookup_tables).await?;
+            self.rpc_client.simulate_transaction(&transaction).await?
+        } else {
+            let recent_blockhash = self.rpc_client.get_latest_blockhash().await?;
+            let message = Message::new(instructions, Some(&keypair.pubkey()));
+            let transaction = Transaction::new(&[keypair], message, recent_blockhash);
+            self.rpc_client.simulate_transaction(&transaction).await?
+        };
         
         debug!("Transaction simulation result: {:?}", simulation_result);
         Ok(simulation_result)
     }
 
-    fn is_simulation_successful(&self, result: &RpcSimulateTransactionResult) -> bool {
-        result.err.is_none()
+    /// Simulate `instructions`, decoding a failure against the known DEX error codes
+    pub async fn simulate_with_logs_decoded(&self, instructions: &[Instruction], keypair: &Keypair) -> Result<SimulationReport> {
+        let result = self.simulate_transaction(instructions, keypair).await?;
+        let report = SimulationReport::from_result(result, instructions);
+        if let Some(error) = &report.error {
+            warn!("Simulation failed: {}", error);
+        }
+        Ok(report)
     }
 
-    async fn send_transaction(&self, instructions: Vec<Instruction>, keypair: &Keypair) -> Result<Signature> {
-        let recent_blockhash = self.rpc_client.get_latest_blockhash().await?;
-        
-        let message = Message::new(&instructions, Some(&keypair.pubkey()));
-        let transaction = Transaction::new(&[keypair], message, recent_blockhash);
-        
-        let signature = self.rpc_client.send_transaction(&transaction).await?;
+    fn should_skip_preflight(risk_score: f64, confidence_score: f64, allowed: bool) -> bool {
+        allowed && risk_score < SKIP_PREFLIGHT_MAX_RISK && confidence_score > SKIP_PREFLIGHT_MIN_CONFIDENCE
+    }
+
+    /// Send the transaction, returning its signature and the blockhash it was signed with
+    async fn send_transaction(&self, instructions: Vec<Instruction>, keypair: &Keypair, skip_preflight: bool) -> Result<(Signature, Hash)> {
+        let lookup_tables = self.transaction_lookup_tables(&instructions).await;
+        let (signature, recent_blockhash) = if self.use_versioned_transaction(&lookup_tables) {
+            let transaction = self.build_versioned_transaction_v0(&instructions, keypair, &lookup_tables).await?;
+            let recent_blockhash = *transaction.message.recent_blockhash();
+            if skip_preflight {
+                (self.rpc_client.send_transaction_skip_preflight(&transaction).await?, recent_blockhash)
+            } else {
+                (self.rpc_client.send_transaction(&transaction).await?, recent_blockhash)
+            }
+        } else {
+            let recent_blockhash = self.rpc_client.get_latest_blockhash().await?;
+            let message = Message::new(&instructions, Some(&keypair.pubkey()));
+            let transaction = Transaction::new(&[keypair], message, recent_blockhash);
+            if skip_preflight {
+                (self.rpc_client.send_transaction_skip_preflight(&transaction).await?, recent_blockhash)
+            } else {
+                (self.rpc_client.send_transaction(&transaction).await?, recent_blockhash)
+            }
+        };
         
         debug!("Transaction sent with signature: {}", signature);
-        Ok(signature)
+        Ok((signature, recent_blockhash))
+    }
+
+    /// Sign a v0 transaction whose account keys are looked up in `lookup_tables` where
+    /// possible, so routes with 3+ steps stay under the 1232-byte packet limit
+    pub async fn build_versioned_transaction_v0(
+        &self,
+        instructions: &[Instruction],
+        keypair: &Keypair,
+        lookup_tables: &[AddressLookupTableAccount],
+    ) -> Result<VersionedTransaction> {
+        let recent_blockhash = self.rpc_client.get_latest_blockhash().await?;
+        Self::compile_versioned_transaction(instructions, keypair, lookup_tables, recent_blockhash)
+    }
+
+    fn compile_versioned_transaction(
+        instructions: &[Instruction],
+        keypair: &Keypair,
+        lookup_tables: &[AddressLookupTableAccount],
+        recent_blockhash: Hash,
+    ) -> Result<VersionedTransaction> {
+        let message = v0::Message::try_compile(&keypair.pubkey(), instructions, lookup_tables, recent_blockhash)
+            .context("Failed to compile v0 message")?;
+        VersionedTransaction::try_new(VersionedMessage::V0(message), &[keypair])
+            .context("Failed to sign v0 transaction")
+    }
+
+    /// Submit the instructions plus a Jito tip as a single-transaction bundle.
+    /// Returns the transaction signature and blockhash so the caller can wait for confirmation.
+    pub async fn send_jito_bundle(&self, mut instructions: Vec<Instruction>, tip_lamports: u64, keypair: &Keypair) -> Result<(Signature, Hash)> {
+        let tip_account = self.sample_jito_tip_account().await?;
+        instructions.push(system_instruction::transfer(&keypair.pubkey(), &tip_account, tip_lamports));
+
+        let lookup_tables = self.transaction_lookup_tables(&instructions).await;
+        let transaction = self.build_versioned_transaction_v0(&instructions, keypair, &lookup_tables).await?;
+        let signature = transaction.signatures[0];
+
+        let serialized = bincode::serialize(&transaction).context("Failed to serialize bundle transaction")?;
+        let bundle = vec![bs58::encode(serialized).into_string()];
+        let bundle_id = self.jito_request("sendBundle", json!([bundle])).await?;
+
+        info!("Submitted Jito bundle {} (tx {}, tip {} lamports to {})", bundle_id, signature, tip_lamports, tip_account);
+        Ok((signature, *transaction.message.recent_blockhash()))
+    }
+
+    async fn sample_jito_tip_account(&self) -> Result<Pubkey> {
+        let accounts = self.jito_request("getTipAccounts", json!([])).await?;
+        let accounts: Vec<String> = serde_json::from_value(accounts)
+            .context("Unexpected getTipAccounts response")?;
+        let account = accounts.choose(&mut rand::thread_rng())
+            .context("Jito returned no tip accounts")?;
+        Pubkey::from_str(account).context("Invalid Jito tip account")
+    }
+
+    async fn jito_request(&self, method: &str, params: Value) -> Result<Value> {
+        let response: Value = self.http_client
+            .post(JITO_BUNDLE_URL)
+            .json(&json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "method": method,
+                "params": params,
+            }))
+            .send()
+            .await
+            .with_context(|| format!("Failed to send Jito {} request", method))?
+            .json()
+            .await
+            .with_context(|| format!("Failed to parse Jito {} response", method))?;
+
+        if let Some(error) = response.get("error") {
+            anyhow::bail!("Jito {} error: {}", method, error);
+        }
+        response.get("result")
+            .cloned()
+            .with_context(|| format!("No result in Jito {} response", method))
     }
 
-    async fn wait_for_confirmation(&self, signature: &Signature) -> Result<()> {
+    /// Poll until `signature` confirms, giving up early once `blockhash` has expired,
+    /// since the transaction can no longer land
+    async fn wait_for_confirmation(&self, signature: &Signature, blockhash: &Hash) -> Result<()> {
         let max_retries = 30;
         let retry_delay = tokio::time::Duration::from_secs(2);
         
@@ -312,6 +1760,14 @@ impl Executor {
                 }
                 Ok(false) => {
                     debug!("Transaction not yet confirmed, attempt {}/{}", attempt + 1, max_retries);
+                    match self.rpc_client.is_blockhash_valid(blockhash).await {
+                        // It may have landed just before the blockhash expired
+                        Ok(false) if !self.rpc_client.get_signature_status(signature).await.unwrap_or(false) => {
+                            anyhow::bail!("Transaction expired: blockhash no longer valid");
+                        }
+                        Ok(_) => {}
+                        Err(e) => debug!("Could not check blockhash validity: {}", e),
+                    }
                 }
                 Err(e) => {
                     warn!("Error checking transaction status: {}", e);
@@ -326,35 +1782,22 @@ impl Executor {
         anyhow::bail!("Transaction confirmation timeout after {} attempts", max_retries)
     }
 
-    fn keypair_from_private_key(private_key: &str) -> Result<Keypair> {
-        // Handle different private key formats
-        if private_key.starts_with('[') && private_key.ends_with(']') {
-            // JSON array format
-            let bytes: Vec<u8> = serde_json::from_str(private_key)?;
-            Ok(Keypair::from_bytes(&bytes)?)
-        } else if private_key.len() == 88 || private_key.len() == 87 {
-            // Base58 format
-            let bytes = bs58::decode(private_key).into_vec()?;
-            Ok(Keypair::from_bytes(&bytes)?)
-        } else {
-            anyhow::bail!("Unsupported private key format");
-        }
-    }
-
     fn validate_arbitrage_opportunity(&self, opportunity: &ArbitrageOpportunity) -> Result<()> {
         // Validate profit threshold
-        if opportunity.expected_profit_percent < self.config.bot.profit_threshold_percent {
+        let profit_threshold_percent = self.config().bot.profit_threshold_for(&opportunity.route.route_type);
+        if opportunity.expected_profit_percent < profit_threshold_percent {
             anyhow::bail!("Opportunity profit {:.2}% below threshold {:.2}%", 
                          opportunity.expected_profit_percent, 
-                         self.config.bot.profit_threshold_percent);
+                         profit_threshold_percent);
         }
 
-        // Validate position size
+        // Validate position size; flash-loan routes borrow their input and are sized by
+        // pool depth instead of the wallet
         let position_size_sol = opportunity.input_amount as f64 / 1_000_000_000.0;
-        if position_size_sol > self.config.bot.max_position_size_sol {
+        if !self.config().bot.enable_flash_loans && position_size_sol > self.config().bot.max_position_size_sol {
             anyhow::bail!("Position size {:.2} SOL exceeds maximum {:.2} SOL", 
                          position_size_sol, 
-                         self.config.bot.max_position_size_sol);
+                         self.config().bot.max_position_size_sol);
         }
 
         // Validate confidence and risk scores
@@ -371,17 +1814,43 @@ impl Executor {
             anyhow::bail!("Empty arbitrage route");
         }
 
-        if opportunity.route.steps.len() > 5 {
+        if opportunity.route.steps.len() > usize::from(MAX_MULTI_HOP) {
             anyhow::bail!("Arbitrage route too complex: {} steps", opportunity.route.steps.len());
         }
 
+        let price_impact_bps: u32 = opportunity.route.steps.iter().map(|step| step.price_impact).sum();
+        if price_impact_bps > self.config().bot.max_price_impact_bps {
+            anyhow::bail!("Route price impact {} bps exceeds maximum {} bps",
+                         price_impact_bps,
+                         self.config().bot.max_price_impact_bps);
+        }
+
         Ok(())
     }
 
-    fn validate_transaction_security(&self, instructions: &[Instruction], keypair: &Keypair) -> Result<()> {
-        // Validate instruction count
-        if instructions.len() > 10 {
-            anyhow::bail!("Too many instructions in transaction: {}", instructions.len());
+    /// Whether swaps on `dex`, by pool or client name, can be built
+    pub fn supports_dex(dex: &str) -> bool {
+        DexId::from_name(dex).is_some_and(|id| EXECUTABLE_DEXES.contains(&id))
+    }
+
+    /// Most instructions a transaction for `steps` may hold: the compute budget, the
+    /// instructions around the swaps, and each step's swap
+    fn max_route_instructions(steps: &[TradeStep]) -> usize {
+        let swaps: usize = steps.iter()
+            .map(|step| match DexId::from_name(&step.pool.dex) {
+                Some(DexId::Jupiter) => MAX_JUPITER_STEP_INSTRUCTIONS,
+                _ => 1,
+            })
+            .sum();
+        COMPUTE_BUDGET_INSTRUCTIONS + ROUTE_OVERHEAD_INSTRUCTIONS + swaps
+    }
+
+    fn validate_transaction_security(&self, instructions: &[Instruction], steps: &[TradeStep], keypair: &Keypair) -> Result<()> {
+        // Validate instruction count against what the route can need
+        let max_instructions = Self::max_route_instructions(steps);
+        if instructions.len() > max_instructions {
+            anyhow::bail!("Too many instructions in transaction: {} (at most {} for {} steps)",
+                         instructions.len(), max_instructions, steps.len());
         }
 
         // Validate program IDs - only allow known DEX programs
@@ -396,10 +1865,8 @@ impl Executor {
         // Validate that all writable accounts belong to the trader
         for instruction in instructions {
             for account_meta in &instruction.accounts {
-                if account_meta.is_writable && account_meta.is_signer {
-                    if account_meta.pubkey != keypair.pubkey() {
-                        anyhow::bail!("Unauthorized signer account: {}", account_meta.pubkey);
-                    }
+                if account_meta.is_writable && account_meta.is_signer && account_meta.pubkey != keypair.pubkey() {
+                    anyhow::bail!("Unauthorized signer account: {}", account_meta.pubkey);
                 }
             }
         }
@@ -414,25 +1881,23 @@ impl Executor {
         Ok(())
     }
 
-    fn validate_simulation_results(&self, result: &RpcSimulateTransactionResult) -> Result<()> {
+    fn validate_simulation_results(&self, report: &SimulationReport) -> Result<()> {
         // Check for any errors
-        if let Some(err) = &result.err {
-            anyhow::bail!("Simulation error: {:?}", err);
+        if let Some(err) = &report.error {
+            anyhow::bail!("Simulation error: {}", err);
         }
 
         // Validate compute units consumed
-        if let Some(units_consumed) = result.units_consumed {
+        if let Some(units_consumed) = report.compute_units {
             if units_consumed > 1_400_000 {
                 anyhow::bail!("Transaction consumes too many compute units: {}", units_consumed);
             }
         }
 
         // Check for suspicious log messages
-        if let Some(logs) = &result.logs {
-            for log in logs {
-                if log.contains("error") || log.contains("failed") || log.contains("insufficient") {
-                    warn!("Suspicious log message: {}", log);
-                }
+        for log in &report.logs {
+            if log.contains("error") || log.contains("failed") || log.contains("insufficient") {
+                warn!("Suspicious log message: {}", log);
             }
         }
 
@@ -443,15 +1908,27 @@ impl Executor {
         let mut allowed = HashSet::new();
         
         // Add known DEX program IDs
-        if let Ok(orca_id) = Pubkey::from_str("whirLbMiicVdio4qvUfM5KAg6Ct8VwpYzGff3uctyCc") {
+        if let Ok(orca_id) = Pubkey::from_str(WHIRLPOOL_PROGRAM_ID) {
             allowed.insert(orca_id);
         }
-        if let Ok(raydium_id) = Pubkey::from_str("675kPX9MHTjS2zt1qfr1NYHuzeLXfQM9H24wFSUt1Mp8") {
+        if let Ok(raydium_id) = Pubkey::from_str(RAYDIUM_AMM_PROGRAM_ID) {
             allowed.insert(raydium_id);
         }
-        if let Ok(phoenix_id) = Pubkey::from_str("PhoeNiXZ8ByJGLkxNfZRnkUfjvmuYqLR89jjFHGqdXY") {
+        if let Ok(phoenix_id) = Pubkey::from_str(PHOENIX_PROGRAM_ID) {
             allowed.insert(phoenix_id);
         }
+        if let Ok(pumpfun_id) = Pubkey::from_str(PUMPFUN_PROGRAM_ID) {
+            allowed.insert(pumpfun_id);
+        }
+        if let Ok(jupiter_id) = Pubkey::from_str(JUPITER_PROGRAM_ID) {
+            allowed.insert(jupiter_id);
+        }
+        if let Ok(marginfi_id) = Pubkey::from_str(MARGINFI_PROGRAM_ID) {
+            allowed.insert(marginfi_id);
+        }
+        allowed.insert(SerumVersion::SerumV3.program_id());
+        allowed.insert(SerumVersion::OpenBook.program_id());
+        allowed.insert(guard_program_id());
         
         // Add system programs
         allowed.insert(spl_token::id());
@@ -462,3 +1939,348 @@ impl Executor {
         allowed
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Pool, TokenInfo};
+
+    fn pool(token_a: Pubkey, token_b: Pubkey, reserve_a: u64, reserve_b: u64) -> Pool {
+        let token = |mint| TokenInfo {
+            mint,
+            symbol: "TEST".to_string(),
+            decimals: 9,
+            price_usd: None,
+        };
+        Pool {
+            address: Pubkey::new_unique(),
+            dex: "raydium".to_string(),
+            token_a: token(token_a),
+            token_b: token(token_b),
+            reserve_a,
+            reserve_b,
+            fee_percent: Decimal::ZERO,
+            liquidity_usd: Decimal::ZERO,
+            last_updated: chrono::Utc::now(),
+            metadata: None,
+        }
+    }
+
+    #[test]
+    fn test_recovery_steps_unwind_executed_route() {
+        let (sol, usdc, ray) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let step = |pool, direction| TradeStep {
+            pool,
+            direction,
+            input_amount: 1_000,
+            expected_output: 1_000,
+            price_impact: 0,
+            slippage: Decimal::ZERO,
+        };
+        // SOL -> USDC, then USDC -> RAY (RAY is token_a of the second pool, reached via Sell)
+        let executed = vec![
+            step(pool(sol, usdc, 1_000_000, 1_000_000), TradeDirection::Buy),
+            step(pool(ray, usdc, 1_000_000, 1_000_000), TradeDirection::Sell),
+        ];
+        assert_eq!(Executor::step_output_mint(&executed[1]), ray);
+
+        let recovery = Executor::recovery_steps(&executed, 500).unwrap();
+        assert_eq!(recovery.len(), 2);
+        assert!(matches!(recovery[0].direction, TradeDirection::Buy));
+        assert_eq!(recovery[0].pool.address, executed[1].pool.address);
+        assert_eq!(recovery[0].input_amount, 500);
+        assert!(matches!(recovery[1].direction, TradeDirection::Sell));
+        assert_eq!(recovery[1].input_amount, recovery[0].expected_output);
+        assert_eq!(Executor::step_output_mint(&recovery[1]), sol);
+    }
+
+    /// A cycle of `hops` Raydium swaps starting and ending in wrapped SOL
+    fn sol_route(hops: usize) -> ArbitrageOpportunity {
+        let mut mints: Vec<Pubkey> = (1..hops).map(|_| Pubkey::new_unique()).collect();
+        mints.insert(0, spl_token::native_mint::id());
+        mints.push(spl_token::native_mint::id());
+        let steps: Vec<TradeStep> = mints.windows(2).map(|pair| {
+            let mut pool = pool(pair[0], pair[1], 1_000_000_000_000, 1_000_000_000_000);
+            pool.metadata = Some(Arc::new(RaydiumPoolMeta {
+                vault_a: Pubkey::new_unique(),
+                vault_b: Pubkey::new_unique(),
+                open_orders: Pubkey::new_unique(),
+            }));
+            TradeStep {
+                pool,
+                direction: TradeDirection::Buy,
+                input_amount: 100_000_000,
+                expected_output: 101_000_000,
+                price_impact: 0,
+                slippage: Decimal::ZERO,
+            }
+        }).collect();
+        ArbitrageOpportunity {
+            id: format!("{}-hop", hops),
+            route: crate::models::ArbitrageRoute {
+                route_type: crate::types::ArbitrageType::MultiHop(hops as u8),
+                from_token: mints[0].to_string(),
+                to_token: mints[0].to_string(),
+                intermediate_token: None,
+                steps,
+                total_fee_percent: Decimal::ZERO,
+            },
+            input_amount: 100_000_000,
+            expected_output: 101_000_000,
+            expected_profit: 1_000_000,
+            expected_profit_percent: 1.0,
+            confidence_score: 0.9,
+            risk_score: 0.1,
+            timestamp: chrono::Utc::now(),
+            expiry: chrono::Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_longest_scanned_route_passes_validation() {
+        let config = Config::default();
+        let executor = Executor::new(config.clone(), Arc::new(RpcClient::new(&config).unwrap())).unwrap();
+
+        executor.validate_arbitrage_opportunity(&sol_route(usize::from(MAX_MULTI_HOP))).unwrap();
+        assert!(executor.validate_arbitrage_opportunity(&sol_route(usize::from(MAX_MULTI_HOP) + 1)).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_long_sol_route_passes_transaction_validation() {
+        let config = Config::default();
+        let executor = Executor::new(config.clone(), Arc::new(RpcClient::new(&config).unwrap())).unwrap();
+        let keypair = Keypair::new();
+
+        for hops in 5..=usize::from(MAX_MULTI_HOP) {
+            let opportunity = sol_route(hops);
+            // Nothing wrapped yet, so the input is wrapped in the same transaction
+            let instructions = executor.atomic_route_instructions(&opportunity, &keypair.pubkey(), 0).await.unwrap();
+            assert_eq!(instructions.len(), COMPUTE_BUDGET_INSTRUCTIONS + 3 + hops + 1);
+            executor.validate_arbitrage_opportunity(&opportunity).unwrap();
+            executor.validate_transaction_security(&instructions, &opportunity.route.steps, &keypair).unwrap();
+        }
+
+        // An instruction the route doesn't account for is still refused
+        let opportunity = sol_route(5);
+        let mut instructions = executor.atomic_route_instructions(&opportunity, &keypair.pubkey(), 0).await.unwrap();
+        instructions.push(ComputeBudgetInstruction::set_compute_unit_price(1));
+        instructions.push(ComputeBudgetInstruction::set_compute_unit_price(1));
+        assert!(executor.validate_transaction_security(&instructions, &opportunity.route.steps, &keypair).is_err());
+    }
+
+    #[test]
+    fn test_kelly_sizing_shrinks_oversized_opportunity() {
+        let mut config = Config::default();
+        config.bot.max_position_size_sol = 1.0;
+        config.risk_management.position_sizing_enabled = true;
+        let mut executor = Executor::new(config.clone(), Arc::new(RpcClient::new(&config).unwrap())).unwrap();
+
+        // SOL buys 2% more of the token on the first pool than it costs on the second
+        let mut route = sol_route(2);
+        route.route.steps[1].pool.reserve_b = 1_020_000_000_000;
+        let opportunity = executor.requote(&route, 1_000_000_000).unwrap();
+        assert_eq!(executor.kelly_sized(&opportunity).unwrap().input_amount, 1_000_000_000);
+
+        // Six 1% wins and four 1% losses: Kelly fraction 0.2, so half-Kelly is 0.1 SOL
+        let history = TradeHistory::in_memory().unwrap();
+        for i in 0..10 {
+            let profit = if i < 6 { 1_000_000 } else { -1_000_000 };
+            history.record_trade(&route, &format!("sig{}", i), profit, 5_000).unwrap();
+        }
+        executor.trade_history = Some(std::sync::Mutex::new(history));
+
+        let sized = executor.kelly_sized(&opportunity).unwrap();
+        assert_eq!(sized.input_amount, 100_000_000);
+        assert_eq!(sized.route.steps[0].input_amount, 100_000_000);
+        assert_eq!(sized.route.steps[1].input_amount, sized.route.steps[0].expected_output);
+        assert_eq!(sized.expected_output, sized.route.steps[1].expected_output);
+        assert!(sized.expected_profit > 0 && sized.expected_profit < opportunity.expected_profit);
+        executor.validate_arbitrage_opportunity(&sized).unwrap();
+    }
+
+    #[test]
+    fn test_flash_loan_opportunity_skips_wallet_size_cap() {
+        let (sol, usdc) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let step = TradeStep {
+            pool: pool(sol, usdc, 1_000_000_000_000, 1_000_000_000_000),
+            direction: TradeDirection::Buy,
+            input_amount: 20_000_000_000,
+            expected_output: 20_200_000_000,
+            price_impact: 0,
+            slippage: Decimal::ZERO,
+        };
+        // 20 SOL, sized by pool depth, against the default 1 SOL wallet cap
+        let opportunity = ArbitrageOpportunity {
+            id: "flash".to_string(),
+            route: crate::models::ArbitrageRoute {
+                route_type: crate::types::ArbitrageType::Direct,
+                from_token: sol.to_string(),
+                to_token: sol.to_string(),
+                intermediate_token: Some(usdc.to_string()),
+                steps: vec![step],
+                total_fee_percent: Decimal::ZERO,
+            },
+            input_amount: 20_000_000_000,
+            expected_output: 20_200_000_000,
+            expected_profit: 200_000_000,
+            expected_profit_percent: 1.0,
+            confidence_score: 0.9,
+            risk_score: 0.1,
+            timestamp: chrono::Utc::now(),
+            expiry: chrono::Utc::now(),
+        };
+
+        let mut config = Config::default();
+        let rpc_client = Arc::new(RpcClient::new(&config).unwrap());
+        let executor = Executor::new(config.clone(), rpc_client.clone()).unwrap();
+        assert!(executor.validate_arbitrage_opportunity(&opportunity).is_err());
+
+        config.bot.enable_flash_loans = true;
+        let executor = Executor::new(config, rpc_client).unwrap();
+        executor.validate_arbitrage_opportunity(&opportunity).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_serum_swap_uses_market_program_from_metadata() {
+        let mut market = pool(Pubkey::new_unique(), Pubkey::new_unique(), 1_000_000, 1_000_000);
+        market.dex = "Serum".to_string();
+        let mut step = TradeStep {
+            pool: market,
+            direction: TradeDirection::Buy,
+            input_amount: 1_000,
+            expected_output: 1_000,
+            price_impact: 0,
+            slippage: Decimal::ZERO,
+        };
+        let config = Config::default();
+        let executor = Executor::new(config.clone(), Arc::new(RpcClient::new(&config).unwrap())).unwrap();
+        let trader = Pubkey::new_unique();
+
+        // Without a recorded version there is no program to send the swap to
+        assert!(executor.build_serum_swap_instruction(&step, 990, &trader).await.is_err());
+
+        step.pool.metadata = Some(Arc::new(SerumPoolMeta { version: SerumVersion::OpenBook, order_book: None }));
+        let instruction = executor.build_serum_swap_instruction(&step, 990, &trader).await.unwrap();
+        assert_eq!(instruction.program_id, SerumVersion::OpenBook.program_id());
+        assert_eq!(instruction.data[..5], instruction_header(13)[..]);
+    }
+
+    #[test]
+    fn test_wallet_pool_tracks_each_wallet() {
+        let keypairs = vec![Keypair::new(), Keypair::new()];
+        let pubkeys: Vec<Pubkey> = keypairs.iter().map(|keypair| keypair.pubkey()).collect();
+        let wallets = WalletPool::new(keypairs);
+
+        assert_eq!(wallets.len(), 2);
+        assert_eq!(wallets.balances(), vec![(pubkeys[0], 0), (pubkeys[1], 0)]);
+
+        wallets.balances.write().unwrap().sol = vec![0, 5_000];
+        assert_eq!(wallets.balances(), vec![(pubkeys[0], 0), (pubkeys[1], 5_000)]);
+    }
+
+    #[test]
+    fn test_wallet_pool_selects_from_cached_balances() {
+        let keypairs = vec![Keypair::new(), Keypair::new()];
+        let pubkeys: Vec<Pubkey> = keypairs.iter().map(|keypair| keypair.pubkey()).collect();
+        let wallets = WalletPool::new(keypairs);
+        let (usdc, wsol) = (Pubkey::new_unique(), spl_token::native_mint::id());
+        {
+            let mut balances = wallets.balances.write().unwrap();
+            balances.sol = vec![10_000, 2_000_000];
+            balances.tokens.insert((pubkeys[0], usdc), 500);
+            balances.tokens.insert((pubkeys[1], wsol), 1_000_000);
+        }
+        let selected = |input| wallets.select(5_000, input).map(|keypair| keypair.pubkey());
+
+        // Only the first wallet holds the USDC input; the second can't pay it from SOL
+        assert_eq!(selected(Some((usdc, 500))), Some(pubkeys[0]));
+        assert_eq!(selected(Some((usdc, 501))), None);
+        // A SOL input beyond the wrapped balance comes out of SOL, on top of the fees
+        assert_eq!(selected(Some((wsol, 1_500_000))), Some(pubkeys[1]));
+        assert_eq!(selected(Some((wsol, 3_000_000))), None);
+        // A borrowed input only needs the fees, so the wallets take turns
+        let first = selected(None).unwrap();
+        assert_ne!(selected(None).unwrap(), first);
+    }
+
+    #[test]
+    fn test_token_amount_in_lamports() {
+        // 5 USDC of profit at $150 per SOL
+        assert_eq!(Executor::token_amount_in_lamports(5_000_000, 6, 1.0, 150.0), 33_333_333);
+        assert_eq!(Executor::token_amount_in_lamports(-1_500_000, 6, 1.0, 150.0), -10_000_000);
+    }
+
+    #[test]
+    fn test_required_lamports() {
+        // 5_000 base + 10_000 microlamports * 250_000 CU / 1e6 + rent reserve
+        assert_eq!(Executor::required_lamports(250_000, 10_000, 0), 5_000 + 2_500 + RENT_RESERVE_LAMPORTS);
+        assert_eq!(Executor::required_lamports(250_000, 0, 10_000), 15_000 + RENT_RESERVE_LAMPORTS);
+    }
+
+    #[test]
+    fn test_should_skip_preflight() {
+        assert!(Executor::should_skip_preflight(0.1, 0.9, true));
+        assert!(!Executor::should_skip_preflight(0.1, 0.9, false));
+        assert!(!Executor::should_skip_preflight(0.3, 0.9, true));
+        assert!(!Executor::should_skip_preflight(0.1, 0.8, true));
+    }
+
+    #[test]
+    fn test_decode_simulation_error() {
+        let orca = Pubkey::from_str(WHIRLPOOL_PROGRAM_ID).unwrap();
+        let instructions = vec![
+            ComputeBudgetInstruction::set_compute_unit_limit(200_000),
+            Instruction::new_with_bytes(orca, &[], vec![]),
+        ];
+
+        let slippage = TransactionError::InstructionError(1, InstructionError::Custom(0x1787));
+        assert_eq!(
+            decode_simulation_error(&slippage, &instructions),
+            "Instruction 1 failed: SlippageToleranceExceeded (0x1787)"
+        );
+        // The same code from another program isn't Orca's slippage error
+        let unknown = TransactionError::InstructionError(0, InstructionError::Custom(0x1787));
+        assert_eq!(decode_simulation_error(&unknown, &instructions), "Instruction 0 failed with custom error 0x1787");
+
+        let report = SimulationReport::from_result(
+            RpcSimulateTransactionResult {
+                err: Some(slippage),
+                logs: Some(vec!["Program log: slippage".to_string()]),
+                accounts: None,
+                units_consumed: Some(42_000),
+                return_data: None,
+                inner_instructions: None,
+            },
+            &instructions,
+        );
+        assert!(!report.success);
+        assert_eq!(report.compute_units, Some(42_000));
+        assert_eq!(report.logs.len(), 1);
+    }
+
+    #[test]
+    fn test_lookup_tables_shrink_v0_transactions() {
+        use solana_sdk::instruction::AccountMeta;
+
+        let keypair = Keypair::new();
+        let program_id = Pubkey::new_unique();
+        let accounts: Vec<Pubkey> = (0..20).map(|_| Pubkey::new_unique()).collect();
+        let instructions = vec![Instruction::new_with_bytes(
+            program_id,
+            &[0; 16],
+            accounts.iter().map(|account| AccountMeta::new(*account, false)).collect(),
+        )];
+        let table = AddressLookupTableAccount { key: Pubkey::new_unique(), addresses: accounts };
+
+        let size = |tables: &[AddressLookupTableAccount]| {
+            let transaction = Executor::compile_versioned_transaction(&instructions, &keypair, tables, Hash::default()).unwrap();
+            let VersionedMessage::V0(message) = &transaction.message else { unreachable!() };
+            (message.address_table_lookups.len(), bincode::serialize(&transaction).unwrap().len())
+        };
+
+        let (inline_lookups, inline_size) = size(&[]);
+        let (table_lookups, table_size) = size(std::slice::from_ref(&table));
+        assert_eq!((inline_lookups, table_lookups), (0, 1));
+        assert!(table_size + 500 < inline_size, "{} vs {}", table_size, inline_size);
+    }
+}
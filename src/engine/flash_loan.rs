@@ -0,0 +1,187 @@
+use crate::utils::rpc::RpcClient;
+use anyhow::{Context, Result};
+use solana_client::rpc_filter::{Memcmp, RpcFilterType};
+use solana_sdk::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+    sysvar,
+};
+use std::str::FromStr;
+
+pub const MARGINFI_PROGRAM_ID: &str = "MFv2hWf31Z9kbCa1snEPYctwafyhdvnV7FZnsebVacA";
+
+/// MarginFi doesn't charge for flash loans today; kept explicit so repayment stays
+/// correct if that changes
+pub const MARGINFI_FLASH_LOAN_FEE_BPS: u16 = 0;
+
+// Anchor instruction discriminators
+const START_FLASHLOAN_DISCRIMINATOR: [u8; 8] = [14, 131, 33, 220, 81, 186, 180, 107];
+const END_FLASHLOAN_DISCRIMINATOR: [u8; 8] = [105, 124, 201, 106, 153, 2, 8, 156];
+const BORROW_DISCRIMINATOR: [u8; 8] = [4, 126, 116, 53, 48, 5, 212, 31];
+const REPAY_DISCRIMINATOR: [u8; 8] = [79, 209, 172, 177, 222, 51, 173, 151];
+
+// Account layouts: MarginfiAccount is (group, authority) and Bank is (mint, decimals, group)
+// right after the discriminator
+const MARGINFI_ACCOUNT_GROUP_OFFSET: usize = 8;
+const MARGINFI_ACCOUNT_AUTHORITY_OFFSET: usize = 40;
+const BANK_MINT_OFFSET: usize = 8;
+const BANK_GROUP_OFFSET: usize = 41;
+
+/// Accounts needed to flash-borrow one mint from a MarginFi bank
+#[derive(Debug, Clone, PartialEq)]
+pub struct FlashLoanConfig {
+    pub program_id: Pubkey,
+    pub group: Pubkey,
+    /// The trader's MarginFi account, which records the loan until it is repaid
+    pub marginfi_account: Pubkey,
+    pub bank: Pubkey,
+    pub liquidity_vault: Pubkey,
+    pub liquidity_vault_authority: Pubkey,
+    pub fee_bps: u16,
+}
+
+impl FlashLoanConfig {
+    /// Derive the bank's liquidity vault PDAs
+    pub fn new(group: Pubkey, marginfi_account: Pubkey, bank: Pubkey) -> Result<Self> {
+        let program_id = Pubkey::from_str(MARGINFI_PROGRAM_ID)?;
+        let (liquidity_vault, _) = Pubkey::find_program_address(&[b"liquidity_vault", bank.as_ref()], &program_id);
+        let (liquidity_vault_authority, _) = Pubkey::find_program_address(&[b"liquidity_vault_auth", bank.as_ref()], &program_id);
+
+        Ok(Self {
+            program_id,
+            group,
+            marginfi_account,
+            bank,
+            liquidity_vault,
+            liquidity_vault_authority,
+            fee_bps: MARGINFI_FLASH_LOAN_FEE_BPS,
+        })
+    }
+
+    /// Look up the trader's MarginFi account in `group` and the bank lending `mint`
+    pub async fn resolve(rpc_client: &RpcClient, group: Pubkey, authority: Pubkey, mint: Pubkey) -> Result<Self> {
+        let program_id = Pubkey::from_str(MARGINFI_PROGRAM_ID)?;
+
+        let accounts = rpc_client.get_program_accounts_with_filters(&program_id, &[
+            RpcFilterType::Memcmp(Memcmp::new_raw_bytes(MARGINFI_ACCOUNT_GROUP_OFFSET, group.to_bytes().to_vec())),
+            RpcFilterType::Memcmp(Memcmp::new_raw_bytes(MARGINFI_ACCOUNT_AUTHORITY_OFFSET, authority.to_bytes().to_vec())),
+        ]).await?;
+        let (marginfi_account, _) = accounts.into_iter().next()
+            .with_context(|| format!("No MarginFi account for {} in group {}", authority, group))?;
+
+        let banks = rpc_client.get_program_accounts_with_filters(&program_id, &[
+            RpcFilterType::Memcmp(Memcmp::new_raw_bytes(BANK_MINT_OFFSET, mint.to_bytes().to_vec())),
+            RpcFilterType::Memcmp(Memcmp::new_raw_bytes(BANK_GROUP_OFFSET, group.to_bytes().to_vec())),
+        ]).await?;
+        let (bank, _) = banks.into_iter().next()
+            .with_context(|| format!("No MarginFi bank lends {} in group {}", mint, group))?;
+
+        Self::new(group, marginfi_account, bank)
+    }
+
+    /// Amount owed back for borrowing `borrow_amount`, fee rounded up
+    pub fn repay_amount(&self, borrow_amount: u64) -> u64 {
+        let fee = (borrow_amount as u128 * self.fee_bps as u128).div_ceil(10_000) as u64;
+        borrow_amount.saturating_add(fee)
+    }
+
+    /// Opens the flash loan; `end_index` is the transaction index of the matching end instruction
+    pub fn start_flashloan(&self, authority: &Pubkey, end_index: u64) -> Instruction {
+        let mut data = START_FLASHLOAN_DISCRIMINATOR.to_vec();
+        data.extend_from_slice(&end_index.to_le_bytes());
+
+        Instruction {
+            program_id: self.program_id,
+            accounts: vec![
+                AccountMeta::new(self.marginfi_account, false),
+                AccountMeta::new_readonly(*authority, true),
+                AccountMeta::new_readonly(sysvar::instructions::id(), false),
+            ],
+            data,
+        }
+    }
+
+    pub fn borrow(&self, authority: &Pubkey, destination: &Pubkey, amount: u64) -> Instruction {
+        let mut data = BORROW_DISCRIMINATOR.to_vec();
+        data.extend_from_slice(&amount.to_le_bytes());
+
+        Instruction {
+            program_id: self.program_id,
+            accounts: vec![
+                AccountMeta::new_readonly(self.group, false),
+                AccountMeta::new(self.marginfi_account, false),
+                AccountMeta::new_readonly(*authority, true),
+                AccountMeta::new(self.bank, false),
+                AccountMeta::new(*destination, false),
+                AccountMeta::new(self.liquidity_vault_authority, false),
+                AccountMeta::new(self.liquidity_vault, false),
+                AccountMeta::new_readonly(spl_token::id(), false),
+            ],
+            data,
+        }
+    }
+
+    /// Repays the whole liability so the account carries no balance into the health check
+    pub fn repay(&self, authority: &Pubkey, source: &Pubkey, amount: u64) -> Instruction {
+        let mut data = REPAY_DISCRIMINATOR.to_vec();
+        data.extend_from_slice(&amount.to_le_bytes());
+        // repay_all: Some(true)
+        data.extend_from_slice(&[1, 1]);
+
+        Instruction {
+            program_id: self.program_id,
+            accounts: vec![
+                AccountMeta::new_readonly(self.group, false),
+                AccountMeta::new(self.marginfi_account, false),
+                AccountMeta::new_readonly(*authority, true),
+                AccountMeta::new(self.bank, false),
+                AccountMeta::new(*source, false),
+                AccountMeta::new(self.liquidity_vault, false),
+                AccountMeta::new_readonly(spl_token::id(), false),
+            ],
+            data,
+        }
+    }
+
+    /// Closes the flash loan. Health is checked here, so the account must not hold
+    /// other active balances (they would need their bank and oracle accounts appended).
+    pub fn end_flashloan(&self, authority: &Pubkey) -> Instruction {
+        Instruction {
+            program_id: self.program_id,
+            accounts: vec![
+                AccountMeta::new(self.marginfi_account, false),
+                AccountMeta::new_readonly(*authority, true),
+            ],
+            data: END_FLASHLOAN_DISCRIMINATOR.to_vec(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_flash_loan_instructions() {
+        let mut config = FlashLoanConfig::new(Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique()).unwrap();
+        let authority = Pubkey::new_unique();
+        let token_account = Pubkey::new_unique();
+
+        let start = config.start_flashloan(&authority, 6);
+        assert_eq!(start.data[..8], START_FLASHLOAN_DISCRIMINATOR);
+        assert_eq!(start.data[8..], 6u64.to_le_bytes());
+
+        let borrow = config.borrow(&authority, &token_account, 1_000);
+        assert_eq!(borrow.accounts[4].pubkey, token_account);
+        assert_eq!(borrow.accounts[6].pubkey, config.liquidity_vault);
+
+        let repay = config.repay(&authority, &token_account, 1_000);
+        assert_eq!(repay.data.len(), 8 + 8 + 2);
+        assert!(config.end_flashloan(&authority).accounts[1].is_signer);
+
+        assert_eq!(config.repay_amount(1_000_000), 1_000_000);
+        config.fee_bps = 9;
+        assert_eq!(config.repay_amount(1_000_000), 1_000_900);
+        assert_eq!(config.repay_amount(1), 2);
+    }
+}
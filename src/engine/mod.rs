@@ -1,5 +1,8 @@
 pub mod screener;
 pub mod executor;
+pub mod flash_loan;
+pub mod risk;
+pub mod opportunity_deduplicator;
 
 pub use screener::Screener;
 pub use executor::Executor;
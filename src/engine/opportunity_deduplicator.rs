@@ -0,0 +1,143 @@
+use crate::models::ArbitrageOpportunity;
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap},
+    hash::{Hash, Hasher},
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+/// Suppresses a route that was already offered recently, so a persisting price
+/// imbalance is not traded (and paid for) on every scan
+pub struct OpportunityDeduplicator {
+    cooldown: Duration,
+    /// Route hash to when the route was first offered
+    seen: Mutex<HashMap<u64, Instant>>,
+}
+
+impl OpportunityDeduplicator {
+    pub fn new(cooldown_secs: u64) -> Self {
+        Self {
+            cooldown: Duration::from_secs(cooldown_secs),
+            seen: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Whether the same route was offered within the cooldown; otherwise remember it
+    pub fn is_duplicate(&self, opportunity: &ArbitrageOpportunity) -> bool {
+        let key = Self::route_hash(opportunity);
+        let now = Instant::now();
+        let mut seen = self.seen.lock().unwrap();
+        match seen.get(&key) {
+            Some(first_seen) if now.duration_since(*first_seen) < self.cooldown => true,
+            _ => {
+                seen.insert(key, now);
+                false
+            }
+        }
+    }
+
+    /// Forget routes whose cooldown has passed
+    pub fn cleanup_expired(&self) {
+        let cooldown = self.cooldown;
+        self.seen.lock().unwrap().retain(|_, first_seen| first_seen.elapsed() < cooldown);
+    }
+
+    pub fn len(&self) -> usize {
+        self.seen.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Hash of the pool addresses in route-step order
+    fn route_hash(opportunity: &ArbitrageOpportunity) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        for step in &opportunity.route.steps {
+            step.pool.address.hash(&mut hasher);
+        }
+        hasher.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        models::{ArbitrageRoute, Pool, TokenInfo, TradeStep},
+        types::{ArbitrageType, TradeDirection},
+    };
+    use rust_decimal::Decimal;
+    use solana_sdk::pubkey::Pubkey;
+
+    fn opportunity(pools: &[Pubkey]) -> ArbitrageOpportunity {
+        let token = TokenInfo {
+            mint: Pubkey::new_unique(),
+            symbol: "TEST".to_string(),
+            decimals: 9,
+            price_usd: None,
+        };
+        let step = |address: &Pubkey| TradeStep {
+            pool: Pool {
+                address: *address,
+                dex: "orca".to_string(),
+                token_a: token.clone(),
+                token_b: token.clone(),
+                reserve_a: 1_000_000,
+                reserve_b: 1_000_000,
+                fee_percent: Decimal::ZERO,
+                liquidity_usd: Decimal::ZERO,
+                last_updated: chrono::Utc::now(),
+                metadata: None,
+            },
+            direction: TradeDirection::Buy,
+            input_amount: 1_000,
+            expected_output: 1_000,
+            price_impact: 0,
+            slippage: Decimal::ZERO,
+        };
+        ArbitrageOpportunity {
+            id: uuid::Uuid::new_v4().to_string(),
+            route: ArbitrageRoute {
+                route_type: ArbitrageType::Direct,
+                from_token: token.mint.to_string(),
+                to_token: token.mint.to_string(),
+                intermediate_token: None,
+                steps: pools.iter().map(step).collect(),
+                total_fee_percent: Decimal::ZERO,
+            },
+            input_amount: 1_000,
+            expected_output: 1_010,
+            expected_profit: 10,
+            expected_profit_percent: 1.0,
+            confidence_score: 0.5,
+            risk_score: 0.1,
+            timestamp: chrono::Utc::now(),
+            expiry: chrono::Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_repeated_route_is_duplicate() {
+        let deduplicator = OpportunityDeduplicator::new(60);
+        let (a, b) = (Pubkey::new_unique(), Pubkey::new_unique());
+
+        assert!(!deduplicator.is_duplicate(&opportunity(&[a, b])));
+        // A new opportunity id over the same pools is still the same route
+        assert!(deduplicator.is_duplicate(&opportunity(&[a, b])));
+        // The reverse route trades the pools the other way round
+        assert!(!deduplicator.is_duplicate(&opportunity(&[b, a])));
+        assert_eq!(deduplicator.len(), 2);
+    }
+
+    #[test]
+    fn test_expired_routes_are_forgotten() {
+        let deduplicator = OpportunityDeduplicator::new(0);
+        let route = opportunity(&[Pubkey::new_unique(), Pubkey::new_unique()]);
+
+        assert!(!deduplicator.is_duplicate(&route));
+        assert!(!deduplicator.is_duplicate(&route));
+        deduplicator.cleanup_expired();
+        assert!(deduplicator.is_empty());
+    }
+}
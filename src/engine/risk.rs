@@ -0,0 +1,151 @@
+use crate::config::RiskManagementConfig;
+use chrono::{DateTime, Duration, Utc};
+use std::sync::Mutex;
+use tracing::{info, warn};
+
+const LAMPORTS_PER_SOL: f64 = 1_000_000_000.0;
+
+/// Trading halts while a limit is exceeded; the main loop counts each refusal as a failed cycle
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum CircuitBreakerError {
+    #[error("Circuit breaker tripped: {0} consecutive losing trades")]
+    ConsecutiveLosses(u32),
+    #[error("Circuit breaker tripped: lost {0} lamports today")]
+    DailyLossLimit(i64),
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct RiskState {
+    pub consecutive_losses: u32,
+    pub session_loss_lamports: i64,
+    pub daily_loss_lamports: i64,
+}
+
+/// Stop-loss limits on realized trade results
+pub struct RiskManager {
+    max_consecutive_losses: u32,
+    daily_loss_limit_lamports: i64,
+    state: Mutex<RiskState>,
+}
+
+impl RiskManager {
+    pub fn new(config: &RiskManagementConfig) -> Self {
+        Self {
+            max_consecutive_losses: config.max_consecutive_losses,
+            daily_loss_limit_lamports: (config.daily_loss_limit_sol * LAMPORTS_PER_SOL) as i64,
+            state: Mutex::new(RiskState::default()),
+        }
+    }
+
+    /// Count a confirmed trade; a loss extends the losing streak, a win ends it
+    pub fn record_trade(&self, realized_profit_lamports: i64) {
+        let mut state = self.state.lock().unwrap();
+        if realized_profit_lamports < 0 {
+            let loss = -realized_profit_lamports;
+            state.consecutive_losses += 1;
+            state.session_loss_lamports += loss;
+            state.daily_loss_lamports += loss;
+            warn!(
+                "Losing trade: -{} lamports ({} in a row, {} lost today)",
+                loss, state.consecutive_losses, state.daily_loss_lamports
+            );
+        } else {
+            state.consecutive_losses = 0;
+        }
+    }
+
+    /// Err while either limit is exceeded. A zero limit disables that check.
+    pub fn check(&self) -> Result<(), CircuitBreakerError> {
+        let state = self.state.lock().unwrap();
+        if self.max_consecutive_losses > 0 && state.consecutive_losses >= self.max_consecutive_losses {
+            return Err(CircuitBreakerError::ConsecutiveLosses(state.consecutive_losses));
+        }
+        if self.daily_loss_limit_lamports > 0 && state.daily_loss_lamports >= self.daily_loss_limit_lamports {
+            return Err(CircuitBreakerError::DailyLossLimit(state.daily_loss_lamports));
+        }
+        Ok(())
+    }
+
+    pub fn reset_daily_counters(&self) {
+        let mut state = self.state.lock().unwrap();
+        info!("Resetting daily loss counter ({} lamports lost yesterday)", state.daily_loss_lamports);
+        state.daily_loss_lamports = 0;
+    }
+
+    pub fn state(&self) -> RiskState {
+        *self.state.lock().unwrap()
+    }
+
+    /// Kelly criterion fraction of capital to stake given the historical win rate and the
+    /// average win and loss as fractions of the stake. Zero when there is no edge.
+    pub fn compute_kelly_fraction(win_rate: f64, avg_profit_ratio: f64, avg_loss_ratio: f64) -> f64 {
+        if avg_profit_ratio <= 0.0 {
+            return 0.0;
+        }
+        let fraction = (win_rate * avg_profit_ratio - (1.0 - win_rate) * avg_loss_ratio) / avg_profit_ratio;
+        fraction.clamp(0.0, 1.0)
+    }
+}
+
+/// Time left until the next UTC midnight
+pub fn duration_until_utc_midnight(now: DateTime<Utc>) -> std::time::Duration {
+    let tomorrow = now.date_naive() + Duration::days(1);
+    let midnight = tomorrow.and_hms_opt(0, 0, 0).unwrap().and_utc();
+    (midnight - now).to_std().unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn risk_manager() -> RiskManager {
+        RiskManager::new(&RiskManagementConfig {
+            max_consecutive_losses: 3,
+            daily_loss_limit_sol: 1.0,
+            position_sizing_enabled: true,
+        })
+    }
+
+    #[test]
+    fn test_consecutive_losses_trip_breaker() {
+        let risk = risk_manager();
+        risk.record_trade(-100);
+        risk.record_trade(-100);
+        risk.record_trade(500);
+        assert_eq!(risk.state().consecutive_losses, 0);
+
+        risk.record_trade(-100);
+        risk.record_trade(-100);
+        assert!(risk.check().is_ok());
+        risk.record_trade(-100);
+        assert_eq!(risk.check(), Err(CircuitBreakerError::ConsecutiveLosses(3)));
+        assert_eq!(risk.state().session_loss_lamports, 500);
+    }
+
+    #[test]
+    fn test_kelly_fraction() {
+        // 60% wins of +1% against 40% losses of -1%
+        assert!((RiskManager::compute_kelly_fraction(0.6, 0.01, 0.01) - 0.2).abs() < 1e-9);
+        // Losing edge or no recorded wins never stakes anything
+        assert_eq!(RiskManager::compute_kelly_fraction(0.3, 0.01, 0.01), 0.0);
+        assert_eq!(RiskManager::compute_kelly_fraction(0.0, 0.0, 0.01), 0.0);
+        assert_eq!(RiskManager::compute_kelly_fraction(1.0, 0.02, 0.0), 1.0);
+    }
+
+    #[test]
+    fn test_daily_loss_limit_resets_at_midnight() {
+        let risk = risk_manager();
+        risk.record_trade(-600_000_000);
+        risk.record_trade(1);
+        risk.record_trade(-400_000_000);
+        assert_eq!(risk.check(), Err(CircuitBreakerError::DailyLossLimit(1_000_000_000)));
+
+        risk.reset_daily_counters();
+        assert!(risk.check().is_ok());
+        assert_eq!(risk.state().session_loss_lamports, 1_000_000_000);
+
+        let now = Utc.with_ymd_and_hms(2024, 3, 1, 23, 30, 0).unwrap();
+        assert_eq!(duration_until_utc_midnight(now), std::time::Duration::from_secs(1800));
+    }
+}
@@ -1,86 +1,162 @@
 use crate::{
-    config::Config,
-    dex::{DexClient},
-    models::{ArbitrageOpportunity, ArbitrageRoute, Pool, TradeStep},
+    api::BotState,
+    config::{BotScoringConfig, Config},
+    dex_config::DexConfigs,
+    dex::{
+        meteora::MeteoraDlmmPoolMeta, orca::OrcaPoolMeta, raydium::RaydiumClmmPoolMeta, saber::SaberPool,
+        same_dex, DexClient,
+    },
+    engine::{
+        executor::Executor, flash_loan::MARGINFI_FLASH_LOAN_FEE_BPS,
+        opportunity_deduplicator::OpportunityDeduplicator,
+    },
+    metrics::MetricsCollector,
+    models::{ArbitrageOpportunity, ArbitrageRoute, Pool, SwapQuote, TradeStep, WhaleSignal},
+    monitor::whales::WhaleMonitor,
     types::{ArbitrageType, TradeDirection},
     utils::{
         cache::PoolCache,
-        math::{calculate_output_amount, calculate_price_impact, calculate_slippage},
+        price_feed::PythPriceFeed,
+        math::{
+            calculate_optimal_trade_size, calculate_output_amount, calculate_price_impact_bps, calculate_slippage,
+            calculate_triangular_optimal_amount, sqrt_price_x64_to_price,
+        },
+        rpc::RpcClient,
     },
 };
-use anyhow::Result;
+use anyhow::{Context, Result};
 use rust_decimal::Decimal;
-use rust_decimal::prelude::ToPrimitive;
-use std::sync::Arc;
+use rust_decimal::prelude::{FromPrimitive, ToPrimitive};
+use solana_sdk::pubkey::Pubkey;
+use std::{
+    borrow::Cow,
+    collections::{HashMap, HashSet},
+    sync::{
+        atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering},
+        Arc, Mutex, RwLock,
+    },
+    time::{Duration, Instant},
+};
+use tokio::sync::watch;
 use tracing::{debug, info, warn};
 use uuid::Uuid;
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::config::Config;
-    use crate::dex::DexClient;
-    use std::sync::Arc;
-    use async_trait::async_trait;
-
-    // Mock DexClient for testing
-    pub struct MockDexClient {
-        name: &'static str,
-    }
-
-    impl MockDexClient {
-        pub fn new(name: &'static str) -> Self {
-            MockDexClient { name }
-        }
-    }
-
-    #[async_trait]
-    impl DexClient for MockDexClient {
-        async fn fetch_pools(&self) -> Result<Vec<Pool>> {
-            Ok(vec![])
-        }
-        async fn get_pool_by_tokens(&self, _token_a: &str, _token_b: &str) -> Result<Option<Pool>> {
-            Ok(None)
-        }
-        async fn update_pool_reserves(&self, _pool: &mut Pool) -> anyhow::Result<()> {
-            Ok(())
-        }
-        fn get_dex_name(&self) -> &'static str {
-            self.name
-        }
-        fn set_console_manager(&mut self, _console: Arc<crate::console::ConsoleManager>) {
-            // Mock implementation, does nothing
-        }
-    }
-
-    #[tokio::test]
-    async fn test_screener_new() {
-        let config = Config::load().unwrap();
-
-        let orca_client = Arc::new(MockDexClient::new("orca")) as Arc<dyn DexClient>;
-        let raydium_client = Arc::new(MockDexClient::new("raydium")) as Arc<dyn DexClient>;
-        let phoenix_client = Arc::new(MockDexClient::new("phoenix")) as Arc<dyn DexClient>;
-
-        let dex_clients: Vec<Arc<dyn DexClient>> = vec![
-            orca_client,
-            raydium_client,
-            phoenix_client,
-        ];
-
-        let screener = Screener::new(
-            config,
-            dex_clients,
-        );
+/// Busy pools change every slot; refreshing more often than this just burns RPC quota
+const MIN_SUBSCRIPTION_REFRESH_MS: u64 = 1_000;
+/// While reserves are streamed, pools are still polled this often to discover new ones
+/// and to catch accounts whose subscription the node didn't confirm
+const SUBSCRIBED_POOL_POLL_INTERVAL: Duration = Duration::from_secs(60);
+/// `getMultipleAccounts` accepts at most this many addresses per call
+const MAX_ACCOUNTS_PER_BATCH: usize = 100;
+/// Compute unit price used until the first fee estimate comes back
+pub const DEFAULT_PRIORITY_FEE_MICROLAMPORTS: u64 = 1000;
+/// Smallest trade the input-size search considers
+const MIN_INPUT_AMOUNT: u64 = 1_000_000;
+/// Base fee plus a typical priority fee, charged once per route regardless of size
+const ESTIMATED_GAS_COST: u64 = 10_000;
+const MAX_INPUT_SEARCH_ITERATIONS: usize = 32;
+/// Stable pairs barely move against each other, so their routes carry less risk
+const STABLE_RISK_FACTOR: f64 = 0.5;
+/// Whale trades older than this no longer affect opportunity ranking
+const WHALE_SIGNAL_WINDOW_SECS: u64 = 60;
+/// Above this profit every pool in the route must meet `min_liquidity_for_high_profit_usd`
+const HIGH_PROFIT_PERCENT: f64 = 2.0;
+/// Pools priced further than this factor from the oracle are treated as manipulated
+const MAX_ORACLE_DEVIATION: f64 = 3.0;
+/// Shorter cycles are covered by the direct and triangular scans
+const MIN_MULTI_HOP: u8 = 4;
+/// Each extra hop multiplies the search space and the transaction size; also the
+/// longest route the executor will send
+pub const MAX_MULTI_HOP: u8 = 6;
+/// Branches less than 0.1% ahead before fees are abandoned
+const MULTI_HOP_PRUNE_RATIO: f64 = 1.001;
+/// Pool edges the multi-hop search may follow in one scan
+const MAX_MULTI_HOP_EXPANSIONS: usize = 10_000;
+/// Pools refreshed this recently get full confidence, which then falls to zero at `STALE_POOL_SECS`
+const FRESH_POOL_SECS: f64 = 5.0;
+const STALE_POOL_SECS: f64 = 300.0;
+/// A pool whose smaller reserve is below this fraction of the larger is nearly drained on one side
+const IMBALANCED_RESERVE_RATIO: f64 = 0.01;
+const IMBALANCED_POOL_PENALTY: f64 = 0.5;
+/// A reserve ratio moving more than this percent in 30 seconds suggests the pool is being
+/// pushed around to fake an arbitrage signal
+const RAPID_RESERVE_CHANGE_PERCENT: f64 = 5.0;
+const RAPID_RESERVE_CHANGE_RISK: f64 = 0.2;
+/// Reported liquidity this many times what the reserves are worth is a data anomaly
+const WASH_TRADING_LIQUIDITY_RATIO: f64 = 10.0;
+/// More reserve updates than this in a minute suggests volume manufactured by one trader
+const WASH_TRADING_MAX_UPDATES_PER_MINUTE: usize = 100;
+/// Scan timings are logged once every this many cycles
+const BENCHMARK_LOG_INTERVAL: u64 = 10;
+
+/// Wall-clock time of each phase of one opportunity scan, in milliseconds
+#[derive(Debug, Clone, Default)]
+pub struct ScanBenchmark {
+    /// Pool and reserve refresh, plus the priority fee estimate
+    pub pool_update_ms: f64,
+    pub direct_scan_ms: f64,
+    pub triangular_scan_ms: f64,
+    pub cross_dex_scan_ms: f64,
+    pub stable_scan_ms: f64,
+    /// Zero unless `bot.enable_multi_hop` is set
+    pub multi_hop_scan_ms: f64,
+    pub filter_ms: f64,
+    pub total_ms: f64,
+    pub opportunities_found: usize,
+    /// Share of direct routes served from the route cache this scan, 0.0 to 1.0
+    pub route_cache_hit_rate: f64,
+}
 
-        assert!(screener.is_ok());
+impl ScanBenchmark {
+    /// Per-phase durations labeled as they're exported in `/metrics`
+    pub fn phases(&self) -> [(&'static str, f64); 8] {
+        [
+            ("pool_update", self.pool_update_ms),
+            ("direct_scan", self.direct_scan_ms),
+            ("triangular_scan", self.triangular_scan_ms),
+            ("cross_dex_scan", self.cross_dex_scan_ms),
+            ("stable_scan", self.stable_scan_ms),
+            ("multi_hop_scan", self.multi_hop_scan_ms),
+            ("filter", self.filter_ms),
+            ("total", self.total_ms),
+        ]
     }
 }
 
 pub struct Screener {
-    config: Config,
+    config: watch::Receiver<Config>,
     dex_clients: Vec<Arc<dyn DexClient>>,
     all_pools: tokio::sync::RwLock<Vec<Pool>>,
     cache: PoolCache,
+    failure_counters: HashMap<String, Arc<AtomicU32>>,
+    insane_pools_filtered: AtomicU64,
+    scoring: Arc<RwLock<BotScoringConfig>>,
+    previous_routes: Mutex<HashSet<Vec<Pubkey>>>,
+    rpc_client: Option<Arc<RpcClient>>,
+    price_feed: Option<PythPriceFeed>,
+    live_subscriptions: AtomicBool,
+    /// Streamed pools whose DEX can't parse account data, refreshed together every
+    /// `MIN_SUBSCRIPTION_REFRESH_MS`
+    pending_pool_refreshes: Mutex<HashSet<Pubkey>>,
+    /// Last time `update_all_pools` ran
+    last_pool_poll: Mutex<Option<Instant>>,
+    bot_state: Option<Arc<BotState>>,
+    /// Set while a background save of the pool cache is running
+    saving_cache: Arc<AtomicBool>,
+    whale_monitor: Option<Arc<WhaleMonitor>>,
+    /// Recent priority fee in microlamports per CU before the cap, refreshed once per scan;
+    /// `None` until the first estimate
+    priority_fee: tokio::sync::RwLock<Option<u64>>,
+    scan_cycles: AtomicU64,
+    /// Direct routes keyed by `direct_route_key`, with the time they were priced
+    route_cache: tokio::sync::RwLock<HashMap<String, (ArbitrageOpportunity, Instant)>>,
+    route_cache_hits: AtomicU64,
+    route_cache_misses: AtomicU64,
+    deduplicator: OpportunityDeduplicator,
+    /// Mints whose freeze authority was looked up, `true` for those without one
+    mint_freeze_checks: Mutex<HashMap<Pubkey, bool>>,
+    /// Per-DEX reliability that scales confidence scores
+    dex_configs: DexConfigs,
 }
 
 impl Screener {
@@ -88,42 +164,392 @@ impl Screener {
         config: Config,
         dex_clients: Vec<Arc<dyn DexClient>>,
     ) -> Result<Self> {
-        let cache = PoolCache::new();
+        let cache = PoolCache::with_capacity(config.bot.cache_pool_capacity, config.bot.cache_reserve_capacity);
         
         // Start background cache cleanup task
         cache.start_cleanup_task();
+
+        let failure_counters = dex_clients
+            .iter()
+            .map(|client| (client.get_dex_name().to_string(), Arc::new(AtomicU32::new(0))))
+            .collect();
         
+        let scoring = Arc::new(RwLock::new(config.scoring.clone()));
+        let deduplicator = OpportunityDeduplicator::new(config.bot.opportunity_dedup_cooldown_secs);
         Ok(Self {
-            config,
+            config: watch::channel(config).1,
             dex_clients,
             all_pools: tokio::sync::RwLock::new(Vec::new()),
             cache,
+            failure_counters,
+            insane_pools_filtered: AtomicU64::new(0),
+            scoring,
+            previous_routes: Mutex::new(HashSet::new()),
+            rpc_client: None,
+            price_feed: None,
+            live_subscriptions: AtomicBool::new(false),
+            pending_pool_refreshes: Mutex::new(HashSet::new()),
+            last_pool_poll: Mutex::new(None),
+            bot_state: None,
+            saving_cache: Arc::new(AtomicBool::new(false)),
+            whale_monitor: None,
+            priority_fee: tokio::sync::RwLock::new(None),
+            scan_cycles: AtomicU64::new(0),
+            route_cache: tokio::sync::RwLock::new(HashMap::new()),
+            route_cache_hits: AtomicU64::new(0),
+            route_cache_misses: AtomicU64::new(0),
+            deduplicator,
+            mint_freeze_checks: Mutex::new(HashMap::new()),
+            dex_configs: DexConfigs::new(),
         })
     }
 
+    /// DEX list whose reliability scores weigh opportunity confidence, instead of the built-in one
+    pub fn with_dex_configs(mut self, dex_configs: DexConfigs) -> Self {
+        self.dex_configs = dex_configs;
+        self
+    }
+
+    /// Raise the confidence of routes trading with recent whale price pressure
+    pub fn with_whale_monitor(mut self, whale_monitor: Arc<WhaleMonitor>) -> Self {
+        self.whale_monitor = Some(whale_monitor);
+        self
+    }
+
+    /// Follow configuration changes published by `Config::load_and_watch`
+    pub fn with_config_updates(mut self, config: watch::Receiver<Config>) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Latest configuration; keep the guard out of `.await` points
+    fn config(&self) -> watch::Ref<'_, Config> {
+        self.config.borrow()
+    }
+
+    /// RPC client whose WebSocket endpoint backs `start_pool_subscriptions`
+    pub fn with_rpc_client(mut self, rpc_client: Arc<RpcClient>) -> Self {
+        self.price_feed = Some(PythPriceFeed::new(rpc_client.clone()));
+        self.rpc_client = Some(rpc_client);
+        self
+    }
+
+    /// Report each completed scan to the health and metrics endpoints
+    pub fn with_bot_state(mut self, bot_state: Arc<BotState>) -> Self {
+        self.bot_state = Some(bot_state);
+        self
+    }
+
+    /// Pool lists and reserves cached between scans
+    pub fn cache(&self) -> &PoolCache {
+        &self.cache
+    }
+
+    /// Write the pool cache to `bot.cache_file_path` in the background, unless a save
+    /// is already in flight
+    fn save_cache_in_background(&self) {
+        let Some(path) = self.config().bot.cache_file_path.clone() else {
+            return;
+        };
+        if self.saving_cache.swap(true, Ordering::AcqRel) {
+            return;
+        }
+
+        let cache = self.cache.clone();
+        let saving_cache = self.saving_cache.clone();
+        tokio::spawn(async move {
+            if let Err(e) = cache.save_to_disk(std::path::Path::new(&path)).await {
+                warn!("Failed to save pool cache: {}", e);
+            }
+            saving_cache.store(false, Ordering::Release);
+        });
+    }
+
+    /// Whether pool reserves are currently streamed rather than polled
+    pub fn has_live_subscriptions(&self) -> bool {
+        self.live_subscriptions.load(Ordering::Acquire)
+    }
+
+    /// Subscribe to every known pool account and refresh pools in place as notifications
+    /// arrive. Runs until the WebSocket drops; `scan_opportunities` polls again meanwhile.
+    /// Until the node confirms the subscriptions, polling carries on as before.
+    pub async fn start_pool_subscriptions(&self) -> Result<()> {
+        let rpc_client = self.rpc_client.as_ref()
+            .context("Pool subscriptions need an RPC client")?;
+
+        let addresses: Vec<Pubkey> = self.all_pools.read().await.iter().map(|pool| pool.address).collect();
+        if addresses.is_empty() {
+            anyhow::bail!("No pools to subscribe to yet");
+        }
+
+        let mut updates = rpc_client.subscribe_accounts(&addresses).await?;
+        self.live_subscriptions.store(true, Ordering::Release);
+        info!("Subscribed to {} pool accounts, polling slowed to every {:?}", addresses.len(), SUBSCRIBED_POOL_POLL_INTERVAL);
+
+        let mut refresh_interval = tokio::time::interval(Duration::from_millis(MIN_SUBSCRIPTION_REFRESH_MS));
+        loop {
+            tokio::select! {
+                update = updates.recv() => match update {
+                    Some(update) => self.refresh_pool(&update.pubkey, &update.data).await,
+                    None => break,
+                },
+                _ = refresh_interval.tick() => self.refresh_pending_pools().await,
+            }
+        }
+
+        self.live_subscriptions.store(false, Ordering::Release);
+        self.pending_pool_refreshes.lock().unwrap().clear();
+        warn!("Pool subscriptions closed, falling back to polling");
+        Ok(())
+    }
+
+    /// Apply a pool account change. Pools whose DEX can't parse the account are queued
+    /// for `refresh_pending_pools` instead of being re-read on every notification.
+    async fn refresh_pool(&self, address: &Pubkey, data: &[u8]) {
+        let Some(mut pool) = self.all_pools.read().await.iter().find(|pool| pool.address == *address).cloned() else {
+            return;
+        };
+        let Some(client) = self.client_for(&pool.dex) else {
+            return;
+        };
+
+        if client.parse_account_data(&mut pool, data).is_err() {
+            self.pending_pool_refreshes.lock().unwrap().insert(*address);
+            return;
+        }
+        self.cache.set_pool_reserves(&address.to_string(), (pool.reserve_a, pool.reserve_b)).await;
+        self.invalidate_route_cache_for_pool(address).await;
+
+        let mut pools = self.all_pools.write().await;
+        if let Some(existing) = pools.iter_mut().find(|existing| existing.address == *address) {
+            *existing = pool;
+        }
+    }
+
+    /// Re-read reserves of the pools queued by `refresh_pool`, once each however many
+    /// notifications arrived for them since the last call
+    async fn refresh_pending_pools(&self) {
+        let pending: HashSet<Pubkey> = std::mem::take(&mut *self.pending_pool_refreshes.lock().unwrap());
+        if pending.is_empty() {
+            return;
+        }
+
+        let mut pools: Vec<Pool> = self.all_pools.read().await.iter()
+            .filter(|pool| pending.contains(&pool.address))
+            .cloned()
+            .collect();
+        let indices: Vec<usize> = (0..pools.len()).collect();
+        self.update_reserves_individually(&mut pools, &indices).await;
+
+        let mut all_pools = self.all_pools.write().await;
+        for pool in pools {
+            if let Some(existing) = all_pools.iter_mut().find(|existing| existing.address == pool.address) {
+                *existing = pool;
+            }
+        }
+    }
+
     pub async fn scan_opportunities(&self) -> Result<Vec<ArbitrageOpportunity>> {
-        // Update pool data from all DEXs
-        self.update_all_pools().await?;
+        let (opportunities, benchmark) = self.timed_scan().await?;
+
+        let metrics = MetricsCollector::global();
+        metrics.increment_scans();
+        metrics.observe_scan_duration(Duration::from_secs_f64(benchmark.total_ms / 1_000.0));
+        for opportunity in &opportunities {
+            metrics.record_opportunity(opportunity.expected_profit_percent);
+        }
+
+        let cycle = self.scan_cycles.fetch_add(1, Ordering::Relaxed) + 1;
+        if cycle.is_multiple_of(BENCHMARK_LOG_INTERVAL) {
+            debug!("Scan cycle {} timing: {:?}", cycle, benchmark);
+        }
+
+        Ok(opportunities)
+    }
+
+    /// Direct routes between the pools of one token pair only, with their reserves re-read
+    /// first. Much cheaper than a full scan, for reacting to a large pending swap.
+    pub async fn scan_opportunities_for_pair(&self, token_a: &Pubkey, token_b: &Pubkey) -> Result<Vec<ArbitrageOpportunity>> {
+        let mut pools = self.cache.get_pool_pairs_for_token_pair(token_a, token_b).await;
+        if pools.is_empty() {
+            // Not indexed, e.g. the cached list expired while reserves were streamed
+            pools = self.all_pools.read().await.iter()
+                .filter(|pool| {
+                    (pool.token_a.mint == *token_a && pool.token_b.mint == *token_b)
+                        || (pool.token_a.mint == *token_b && pool.token_b.mint == *token_a)
+                })
+                .cloned()
+                .collect();
+        }
+        if pools.len() < 2 {
+            return Ok(Vec::new());
+        }
+
+        let stale: Vec<usize> = (0..pools.len()).collect();
+        self.refresh_reserves(&mut pools, &stale).await;
+        {
+            let mut all_pools = self.all_pools.write().await;
+            for pool in &pools {
+                if let Some(existing) = all_pools.iter_mut().find(|existing| existing.address == pool.address) {
+                    *existing = pool.clone();
+                }
+            }
+        }
+
+        let opportunities = self.scan_direct_arbitrage(&pools).await?;
+        let filtered_opportunities = self.filter_opportunities(opportunities, &pools).await?;
+        debug!("Pair scan of {} pools for {}/{} found {} opportunities", pools.len(), token_a, token_b, filtered_opportunities.len());
+        Ok(filtered_opportunities)
+    }
+
+    /// Run a full scan and report how long each phase took
+    pub async fn benchmark_scan_cycle(&self) -> Result<ScanBenchmark> {
+        let (_, benchmark) = self.timed_scan().await?;
+        Ok(benchmark)
+    }
+
+    async fn timed_scan(&self) -> Result<(Vec<ArbitrageOpportunity>, ScanBenchmark)> {
+        let started = Instant::now();
+        let mut phase_started = started;
+        let mut lap = || {
+            let now = Instant::now();
+            let elapsed = now.duration_since(phase_started).as_secs_f64() * 1_000.0;
+            phase_started = now;
+            elapsed
+        };
+        let mut benchmark = ScanBenchmark::default();
+
+        // Update pool data from all DEXs; while subscriptions keep reserves fresh, only
+        // poll now and then to pick up new pools
+        let poll_due = !self.has_live_subscriptions()
+            || self.last_pool_poll.lock().unwrap().is_none_or(|polled| polled.elapsed() >= SUBSCRIBED_POOL_POLL_INTERVAL);
+        if poll_due {
+            self.update_all_pools().await?;
+            *self.last_pool_poll.lock().unwrap() = Some(Instant::now());
+        } else {
+            debug!("Pool reserves are streamed, skipping poll");
+        }
         
+        self.refresh_priority_fee().await;
+        benchmark.pool_update_ms = lap();
+
         let pools = self.all_pools.read().await;
         let mut opportunities = Vec::new();
+        let route_cache_hits = self.route_cache_hits.load(Ordering::Relaxed);
+        let route_cache_misses = self.route_cache_misses.load(Ordering::Relaxed);
 
         info!("Scanning {} pools for arbitrage opportunities", pools.len());
 
         // Scan for direct arbitrage opportunities
         opportunities.extend(self.scan_direct_arbitrage(&pools).await?);
+        benchmark.direct_scan_ms = lap();
+        let hits = self.route_cache_hits.load(Ordering::Relaxed) - route_cache_hits;
+        let lookups = hits + self.route_cache_misses.load(Ordering::Relaxed) - route_cache_misses;
+        if lookups > 0 {
+            benchmark.route_cache_hit_rate = hits as f64 / lookups as f64;
+        }
         
         // Scan for triangular arbitrage opportunities
         opportunities.extend(self.scan_triangular_arbitrage(&pools).await?);
+        benchmark.triangular_scan_ms = lap();
         
         // Scan for cross-DEX arbitrage opportunities
         opportunities.extend(self.scan_cross_dex_arbitrage(&pools).await?);
+        benchmark.cross_dex_scan_ms = lap();
+
+        // Scan stablecoin pairs with the StableSwap curve
+        opportunities.extend(self.scan_stable_arbitrage(&pools).await?);
+        benchmark.stable_scan_ms = lap();
+
+        // Scan for cycles of four or more hops
+        let (enable_multi_hop, max_multi_hop) = {
+            let config = self.config();
+            (config.bot.enable_multi_hop, config.bot.max_multi_hop)
+        };
+        if enable_multi_hop {
+            opportunities.extend(self.scan_multi_hop_arbitrage(&pools, max_multi_hop).await?);
+        }
+        benchmark.multi_hop_scan_ms = lap();
 
         // Filter and sort opportunities
-        let filtered_opportunities = self.filter_opportunities(opportunities).await?;
+        let filtered_opportunities = self.filter_opportunities(opportunities, &pools).await?;
+        benchmark.filter_ms = lap();
+        benchmark.total_ms = started.elapsed().as_secs_f64() * 1_000.0;
+        benchmark.opportunities_found = filtered_opportunities.len();
         
         info!("Found {} profitable opportunities", filtered_opportunities.len());
-        Ok(filtered_opportunities)
+
+        if let Some(bot_state) = &self.bot_state {
+            let hit_rate = self.cache.get_cache_stats().await.hit_rate();
+            bot_state.record_scan(self.connected_dex_count(), hit_rate);
+            bot_state.record_scan_phases(&benchmark.phases(), benchmark.opportunities_found);
+        }
+
+        Ok((filtered_opportunities, benchmark))
+    }
+
+    /// Priority fee, in microlamports per CU, that profits were netted against in the last scan
+    pub async fn estimated_priority_fee(&self) -> u64 {
+        let estimate = self.recent_priority_fee().await.unwrap_or(DEFAULT_PRIORITY_FEE_MICROLAMPORTS);
+        estimate.min(self.config().bot.max_priority_fee_microlamports)
+    }
+
+    /// Last scan's estimate at `bot.priority_fee_percentile` before the cap, if one was made
+    pub async fn recent_priority_fee(&self) -> Option<u64> {
+        *self.priority_fee.read().await
+    }
+
+    /// Re-estimate the priority fee from recent network fees, capped by config; the
+    /// previous estimate is kept if the RPC call fails
+    async fn refresh_priority_fee(&self) {
+        let Some(rpc_client) = &self.rpc_client else {
+            return;
+        };
+        let (cap, percentile) = {
+            let config = self.config();
+            (config.bot.max_priority_fee_microlamports, config.bot.priority_fee_percentile)
+        };
+        match rpc_client.get_recent_priority_fees(percentile).await {
+            Ok(fee) => {
+                debug!("Recent priority fee: {} microlamports per CU", fee);
+                if fee > cap {
+                    warn!("Priority fee estimate {} exceeds the {} microlamport cap, using the cap", fee, cap);
+                }
+                *self.priority_fee.write().await = Some(fee);
+            }
+            Err(e) => debug!("Failed to estimate priority fee: {}", e),
+        }
+    }
+
+    /// Fill token USD prices from Pyth and re-value each priced pool's liquidity
+    async fn apply_usd_prices(&self, pools: &mut [Pool]) {
+        let Some(price_feed) = &self.price_feed else {
+            return;
+        };
+
+        let mints: HashSet<Pubkey> = pools.iter()
+            .flat_map(|pool| [pool.token_a.mint, pool.token_b.mint])
+            .filter(|mint| price_feed.supports(mint))
+            .collect();
+        let max_age_slots = self.config().bot.max_oracle_age_slots;
+        let mut prices = HashMap::new();
+        for mint in mints {
+            match price_feed.get_price(&mint, max_age_slots).await {
+                Ok(price) => {
+                    prices.insert(mint, price);
+                }
+                Err(e) => debug!("No Pyth price for {}: {}", mint, e),
+            }
+        }
+
+        for pool in pools.iter_mut() {
+            for token in [&mut pool.token_a, &mut pool.token_b] {
+                if let Some(&price) = prices.get(&token.mint) {
+                    token.price_usd = Some(price);
+                }
+            }
+            pool.refresh_liquidity_usd();
+        }
     }
 
     async fn update_all_pools(&self) -> Result<()> {
@@ -132,7 +558,7 @@ impl Screener {
         // Fetch pools from all enabled DEXs with caching
         for client in &self.dex_clients {
             let dex_name = client.get_dex_name();
-            if self.config.dexs.enabled.contains(&dex_name.to_string()) {
+            if self.config().dexs.enabled.contains(&dex_name.to_string()) {
                 // Try to get from cache first
                 if let Some(cached_pools) = self.cache.get_pools(dex_name).await {
                     debug!("Using cached pools for {}", dex_name);
@@ -142,6 +568,7 @@ impl Screener {
                     match client.fetch_pools().await {
                         Ok(pools) => {
                             debug!("Fetched {} pools from {}", pools.len(), dex_name);
+                            self.reset_failure_count(dex_name);
                             self.cache.set_pools(dex_name, pools.clone()).await;
                             all_pools.extend(pools);
                         },
@@ -149,43 +576,73 @@ impl Screener {
                             warn!("Failed to fetch {} pools: {}", dex_name, e);
                             // Invalidate cache on error
                             self.cache.invalidate_dex(dex_name).await;
+                            self.record_fetch_failure(client, dex_name).await;
                         },
                     }
                 }
             }
         }
 
-        // Update pool reserves with caching
-        for pool in &mut all_pools {
-            let pool_address = pool.address.to_string();
-            
-            // Try to get reserves from cache first
-            if let Some((reserve_a, reserve_b)) = self.cache.get_pool_reserves(&pool_address).await {
+        // Leave out pools the token lists rule out before spending RPC calls on them
+        let fetched = all_pools.len();
+        all_pools.retain(|pool| self.pool_tokens_allowed(pool));
+        if all_pools.len() < fetched {
+            debug!("Token lists excluded {} pools", fetched - all_pools.len());
+        }
+
+        // Use cached reserves where possible and refresh the rest in bulk
+        let mut stale = Vec::new();
+        for (index, pool) in all_pools.iter_mut().enumerate() {
+            if let Some((reserve_a, reserve_b)) = self.cache.get_pool_reserves(&pool.address.to_string()).await {
                 pool.reserve_a = reserve_a;
                 pool.reserve_b = reserve_b;
             } else {
-                // Fetch fresh reserves and cache them
-                for client in &self.dex_clients {
-                    if client.get_dex_name() == pool.dex {
-                        if let Err(e) = client.update_pool_reserves(pool).await {
-                            warn!("Failed to update reserves for pool {}: {}", pool_address, e);
-                            self.cache.invalidate_pool(&pool_address).await;
-                        } else {
-                            // Cache the updated reserves
-                            self.cache.set_pool_reserves(&pool_address, (pool.reserve_a, pool.reserve_b)).await;
-                        }
-                        break;
-                    }
-                }
+                stale.push(index);
+            }
+        }
+        self.refresh_reserves(&mut all_pools, &stale).await;
+        self.apply_usd_prices(&mut all_pools).await;
+
+        // Drop pools with implausible data before they reach the arbitrage math
+        all_pools.retain(|pool| match pool.sanity_check() {
+            Ok(()) => true,
+            Err(reason) => {
+                debug!("Skipping {} pool {}: {}", pool.dex, pool.address, reason);
+                self.insane_pools_filtered.fetch_add(1, Ordering::Relaxed);
+                false
+            }
+        });
+
+        // Drop pools whose liquidity or activity looks manufactured
+        let mut genuine_pools = Vec::with_capacity(all_pools.len());
+        for pool in all_pools {
+            if self.detect_wash_trading(&pool).await {
+                warn!("Skipping {} pool {}: looks like wash trading", pool.dex, pool.address);
+            } else {
+                genuine_pools.push(pool);
             }
         }
+        let all_pools = genuine_pools;
 
         // Filter pools by minimum liquidity
-        let filtered_pools: Vec<Pool> = all_pools
+        let min_liquidity_usd = Decimal::from_f64_retain(self.config().bot.min_liquidity_usd).unwrap();
+        let mut filtered_pools: Vec<Pool> = all_pools
             .into_iter()
-            .filter(|pool| pool.liquidity_usd >= Decimal::from_f64_retain(self.config.bot.min_liquidity_usd).unwrap())
+            .filter(|pool| pool.liquidity_usd >= min_liquidity_usd)
             .collect();
 
+        // Keep the scan to the most liquid pools, ranked with this cycle's USD prices
+        let max_pools_per_scan = self.config().bot.max_pools_per_scan;
+        if filtered_pools.len() > max_pools_per_scan {
+            self.cache.update_liquidity(&filtered_pools).await;
+            let most_liquid: HashSet<Pubkey> = self.cache.get_most_liquid_pools(max_pools_per_scan).await
+                .iter()
+                .map(|pool| pool.address)
+                .collect();
+            filtered_pools.retain(|pool| most_liquid.contains(&pool.address));
+            debug!("Scanning the {} most liquid pools", filtered_pools.len());
+        }
+
         let mut pools_lock = self.all_pools.write().await;
         *pools_lock = filtered_pools;
 
@@ -197,75 +654,311 @@ impl Screener {
                cache_stats.pool_entries, 
                cache_stats.reserve_entries,
                cache_stats.hit_rate() * 100.0);
-        
+
+        self.save_cache_in_background();
         Ok(())
     }
 
-    async fn scan_direct_arbitrage(&self, pools: &[Pool]) -> Result<Vec<ArbitrageOpportunity>> {
-        let mut opportunities = Vec::new();
-
-        // Group pools by token pairs
-        let mut token_pair_pools: std::collections::HashMap<(String, String), Vec<&Pool>> = 
-            std::collections::HashMap::new();
+    /// Client for a pool's `dex`, which may be spelled differently from `get_dex_name`
+    fn client_for(&self, dex: &str) -> Option<&Arc<dyn DexClient>> {
+        self.dex_clients.iter().find(|client| same_dex(client.get_dex_name(), dex))
+    }
 
-        for pool in pools {
-            let token_a = pool.token_a.mint.to_string();
-            let token_b = pool.token_b.mint.to_string();
-            let pair = if token_a < token_b {
-                (token_a, token_b)
-            } else {
-                (token_b, token_a)
-            };
-            
-            token_pair_pools.entry(pair).or_insert_with(Vec::new).push(pool);
+    /// Refresh reserves for the pools at `stale`, reading pool accounts in batches of
+    /// `getMultipleAccounts`. Pools whose DEX can't parse reserves from the account
+    /// fall back to one `update_pool_reserves` call each.
+    async fn refresh_reserves(&self, pools: &mut [Pool], stale: &[usize]) {
+        let mut fallback = Vec::new();
+
+        match &self.rpc_client {
+            Some(rpc_client) => {
+                for chunk in stale.chunks(MAX_ACCOUNTS_PER_BATCH) {
+                    let addresses: Vec<Pubkey> = chunk.iter().map(|&index| pools[index].address).collect();
+                    let accounts = match rpc_client.get_multiple_accounts(&addresses).await {
+                        Ok(accounts) => accounts,
+                        Err(e) => {
+                            warn!("Batched reserve refresh failed for {} pools: {}", chunk.len(), e);
+                            fallback.extend_from_slice(chunk);
+                            continue;
+                        }
+                    };
+
+                    for (&index, account) in chunk.iter().zip(accounts) {
+                        let pool = &mut pools[index];
+                        let parsed = match (account, self.client_for(&pool.dex)) {
+                            (Some(account), Some(client)) => client.parse_account_data(pool, &account.data).is_ok(),
+                            _ => false,
+                        };
+                        if parsed {
+                            self.cache.set_pool_reserves(&pool.address.to_string(), (pool.reserve_a, pool.reserve_b)).await;
+                            self.invalidate_route_cache_for_pool(&pool.address).await;
+                        } else {
+                            fallback.push(index);
+                        }
+                    }
+                }
+            }
+            None => fallback.extend_from_slice(stale),
         }
 
-        // Look for arbitrage opportunities between different pools for the same pair
-        for (_pair, pair_pools) in token_pair_pools {
-            if pair_pools.len() < 2 {
+        self.update_reserves_individually(pools, &fallback).await;
+    }
+
+    /// Refresh reserves of the pools at `indices` with one `update_pool_reserves` call each
+    async fn update_reserves_individually(&self, pools: &mut [Pool], indices: &[usize]) {
+        for &index in indices {
+            let pool = &mut pools[index];
+            let pool_address = pool.address.to_string();
+            let Some(client) = self.client_for(&pool.dex) else {
                 continue;
+            };
+            if let Err(e) = client.update_pool_reserves(pool).await {
+                warn!("Failed to update reserves for pool {}: {}", pool_address, e);
+                self.cache.invalidate_pool(&pool_address).await;
+            } else {
+                // Cache the updated reserves
+                self.cache.set_pool_reserves(&pool_address, (pool.reserve_a, pool.reserve_b)).await;
+                self.invalidate_route_cache_for_pool(&pool.address).await;
             }
+        }
+    }
 
-            for i in 0..pair_pools.len() {
-                for j in i + 1..pair_pools.len() {
-                    let pool1 = pair_pools[i];
-                    let pool2 = pair_pools[j];
+    /// Whether `bot.token_blacklist` and `bot.token_whitelist` allow trading `pool`: neither
+    /// token blacklisted and, when there is a whitelist, at least one token on it
+    fn pool_tokens_allowed(&self, pool: &Pool) -> bool {
+        let config = self.config();
+        let mints = [pool.token_a.mint.to_string(), pool.token_b.mint.to_string()];
+        if mints.iter().any(|mint| config.bot.token_blacklist.contains(mint)) {
+            return false;
+        }
+        config.bot.token_whitelist.is_empty()
+            || mints.iter().any(|mint| config.bot.token_whitelist.contains(mint))
+    }
 
-                    // Skip if same DEX
-                    if pool1.dex == pool2.dex {
-                        continue;
-                    }
+    /// Whether `mint` may be traded: not blacklisted, on the whitelist if there is one,
+    /// and without a freeze authority that could lock our token account mid-trade.
+    /// A mint whose account can't be read is treated as unsafe; lookups that succeed are
+    /// cached per mint.
+    pub async fn is_token_safe(&self, mint: &Pubkey) -> bool {
+        {
+            let config = self.config();
+            let mint = mint.to_string();
+            if config.bot.token_blacklist.contains(&mint) {
+                return false;
+            }
+            if !config.bot.token_whitelist.is_empty() && !config.bot.token_whitelist.contains(&mint) {
+                return false;
+            }
+        }
 
-                    // Calculate potential arbitrage
-                    if let Ok(opportunity) = self.calculate_direct_arbitrage(pool1, pool2).await {
-                        opportunities.push(opportunity);
-                    }
+        if let Some(&safe) = self.mint_freeze_checks.lock().unwrap().get(mint) {
+            return safe;
+        }
+        let Some(rpc_client) = &self.rpc_client else {
+            return false;
+        };
+        match rpc_client.get_token_mint_info(mint).await {
+            Ok(info) => {
+                let safe = info.freeze_authority.is_none();
+                if !safe {
+                    debug!("Skipping {}: it has a freeze authority", mint);
                 }
+                self.mint_freeze_checks.lock().unwrap().insert(*mint, safe);
+                safe
+            }
+            Err(e) => {
+                warn!("Could not check freeze authority of {}: {}", mint, e);
+                false
             }
         }
-
-        debug!("Found {} direct arbitrage opportunities", opportunities.len());
-        Ok(opportunities)
     }
 
-    async fn scan_triangular_arbitrage(&self, pools: &[Pool]) -> Result<Vec<ArbitrageOpportunity>> {
-        let mut opportunities = Vec::new();
-
-        // This is computationally expensive, so we limit the search
-        const MAX_TRIANGULAR_COMBINATIONS: usize = 1000;
-        let mut combinations_checked = 0;
+    /// Whether the pool's volume or liquidity looks artificial: reserves whose product is
+    /// below `bot.min_product_threshold`, a `liquidity_usd` over ten times what the reserves
+    /// are worth, or more than 100 reserve updates in the last minute
+    pub async fn detect_wash_trading(&self, pool: &Pool) -> bool {
+        let min_product = self.config().bot.min_product_threshold;
+        if (pool.reserve_a as f64) * (pool.reserve_b as f64) < min_product {
+            return true;
+        }
 
-        for pool1 in pools.iter() {
-            if combinations_checked >= MAX_TRIANGULAR_COMBINATIONS {
-                break;
+        // Without any token price the reserves can't be valued independently
+        let price_a = pool.token_a.price_usd.and_then(|price| price.to_f64());
+        let price_b = pool.token_b.price_usd.and_then(|price| price.to_f64());
+        if price_a.is_some() || price_b.is_some() {
+            let reserves_usd = Pool::estimate_liquidity_usd(
+                pool.reserve_a,
+                pool.reserve_b,
+                pool.token_a.decimals,
+                pool.token_b.decimals,
+                price_a,
+                price_b,
+            ).to_f64().unwrap_or(0.0);
+            let liquidity_usd = pool.liquidity_usd.to_f64().unwrap_or(0.0);
+            if reserves_usd > 0.0 && liquidity_usd / reserves_usd > WASH_TRADING_LIQUIDITY_RATIO {
+                return true;
             }
+        }
 
-            for pool2 in pools.iter() {
-                if combinations_checked >= MAX_TRIANGULAR_COMBINATIONS {
-                    break;
-                }
+        self.cache.reserve_update_count(&pool.address.to_string()).await > WASH_TRADING_MAX_UPDATES_PER_MINUTE
+    }
 
-                if pool1.address == pool2.address {
+    /// Total pools skipped by sanity checks since startup
+    pub fn insane_pools_filtered(&self) -> u64 {
+        self.insane_pools_filtered.load(Ordering::Relaxed)
+    }
+
+    /// DEXes whose last pool fetch succeeded
+    pub fn connected_dex_count(&self) -> usize {
+        self.failure_counters.values()
+            .filter(|counter| counter.load(Ordering::Relaxed) == 0)
+            .count()
+    }
+
+    fn reset_failure_count(&self, dex_name: &str) {
+        if let Some(counter) = self.failure_counters.get(dex_name) {
+            counter.store(0, Ordering::Relaxed);
+        }
+    }
+
+    async fn record_fetch_failure(&self, client: &Arc<dyn DexClient>, dex_name: &str) {
+        let Some(counter) = self.failure_counters.get(dex_name) else {
+            return;
+        };
+
+        let failures = counter.fetch_add(1, Ordering::Relaxed) + 1;
+        if failures < self.config().bot.dex_reinit_threshold {
+            return;
+        }
+
+        warn!("Re-initializing {} client after {} consecutive fetch failures", dex_name, failures);
+        if let Err(e) = client.reinitialize().await {
+            warn!("Failed to re-initialize {} client: {}", dex_name, e);
+        }
+        counter.store(0, Ordering::Relaxed);
+        self.cache.invalidate_dex(dex_name).await;
+    }
+
+    async fn scan_direct_arbitrage(&self, pools: &[Pool]) -> Result<Vec<ArbitrageOpportunity>> {
+        let mut opportunities = Vec::new();
+
+        // Stable pairs are priced by `scan_stable_arbitrage`
+        let candidates: Vec<&Pool> = pools.iter().filter(|pool| !self.is_stable_pool(pool)).collect();
+        let live: HashMap<Pubkey, &Pool> = candidates.iter().map(|pool| (pool.address, *pool)).collect();
+        let mut checked_pairs = HashSet::new();
+        let (max_pool_age_secs, route_cache_ttl_ms) = {
+            let config = self.config();
+            (config.bot.max_pool_age_secs, config.bot.route_cache_ttl_ms)
+        };
+        self.route_cache.write().await
+            .retain(|_, (_, priced_at)| (priced_at.elapsed().as_millis() as u64) < route_cache_ttl_ms);
+
+        for pool in &candidates {
+            let (mint_a, mint_b) = (pool.token_a.mint, pool.token_b.mint);
+            if !checked_pairs.insert(if mint_a < mint_b { (mint_a, mint_b) } else { (mint_b, mint_a) }) {
+                continue;
+            }
+
+            // The cache's token index finds the pair's pools; prices come from the live list
+            let mut pair_pools: Vec<&Pool> = self.cache.get_pool_pairs_for_token_pair(&mint_a, &mint_b).await
+                .iter()
+                .filter_map(|cached| live.get(&cached.address).copied())
+                .collect();
+            if !pair_pools.iter().any(|cached| cached.address == pool.address) {
+                // Not indexed, e.g. the cached list expired while reserves were streamed
+                pair_pools = candidates.iter()
+                    .filter(|other| {
+                        (other.token_a.mint == mint_a && other.token_b.mint == mint_b)
+                            || (other.token_a.mint == mint_b && other.token_b.mint == mint_a)
+                    })
+                    .copied()
+                    .collect();
+            }
+            if pair_pools.len() < 2 {
+                continue;
+            }
+
+            for i in 0..pair_pools.len() {
+                for j in i + 1..pair_pools.len() {
+                    let pool1 = pair_pools[i];
+                    let pool2 = pair_pools[j];
+
+                    // Skip if same DEX
+                    if pool1.dex == pool2.dex {
+                        continue;
+                    }
+
+                    if pool1.is_stale(max_pool_age_secs) || pool2.is_stale(max_pool_age_secs) {
+                        debug!("Skipping stale pool pair {} / {}", pool1.address, pool2.address);
+                        continue;
+                    }
+
+                    // Calculate potential arbitrage
+                    if let Ok(opportunity) = self.calculate_direct_arbitrage(pool1, pool2).await {
+                        opportunities.push(opportunity);
+                    }
+                }
+            }
+        }
+
+        debug!("Found {} direct arbitrage opportunities", opportunities.len());
+        Ok(opportunities)
+    }
+
+    /// Two-pool routes between stablecoin pools on different DEXes, priced with each
+    /// pool's own curve and held to `bot.stable_profit_threshold_percent`
+    pub async fn scan_stable_arbitrage(&self, pools: &[Pool]) -> Result<Vec<ArbitrageOpportunity>> {
+        let stable_pools: Vec<&Pool> = pools.iter().filter(|pool| self.is_stable_pool(pool)).collect();
+        let profit_threshold_percent = self.config().bot.stable_profit_threshold_percent;
+        let same_pair = |pool1: &Pool, pool2: &Pool| {
+            (pool1.token_a.mint == pool2.token_a.mint && pool1.token_b.mint == pool2.token_b.mint)
+                || (pool1.token_a.mint == pool2.token_b.mint && pool1.token_b.mint == pool2.token_a.mint)
+        };
+
+        let mut opportunities = Vec::new();
+        for (i, pool1) in stable_pools.iter().enumerate() {
+            for pool2 in &stable_pools[i + 1..] {
+                if pool1.dex == pool2.dex || !same_pair(pool1, pool2) {
+                    continue;
+                }
+                if let Ok(opportunity) = self.calculate_stable_arbitrage(pool1, pool2).await {
+                    if opportunity.expected_profit_percent >= profit_threshold_percent {
+                        opportunities.push(opportunity);
+                    }
+                }
+            }
+        }
+
+        debug!("Found {} stable pair opportunities across {} pools", opportunities.len(), stable_pools.len());
+        Ok(opportunities)
+    }
+
+    /// Whether both mints of `pool` are listed in `bot.stable_mints`; symbols are
+    /// chosen by token creators, so they can't be trusted here
+    fn is_stable_pool(&self, pool: &Pool) -> bool {
+        let config = self.config();
+        let is_stable = |mint: &Pubkey| config.bot.stable_mints.iter().any(|stable| *stable == mint.to_string());
+        is_stable(&pool.token_a.mint) && is_stable(&pool.token_b.mint)
+    }
+
+    async fn scan_triangular_arbitrage(&self, pools: &[Pool]) -> Result<Vec<ArbitrageOpportunity>> {
+        let mut opportunities = Vec::new();
+
+        // This is computationally expensive, so we limit the search
+        const MAX_TRIANGULAR_COMBINATIONS: usize = 1000;
+        let mut combinations_checked = 0;
+
+        for pool1 in pools.iter() {
+            if combinations_checked >= MAX_TRIANGULAR_COMBINATIONS {
+                break;
+            }
+
+            for pool2 in pools.iter() {
+                if combinations_checked >= MAX_TRIANGULAR_COMBINATIONS {
+                    break;
+                }
+
+                if pool1.address == pool2.address {
                     continue;
                 }
 
@@ -286,7 +979,16 @@ impl Screener {
                     }
 
                     if let Ok(opportunity) = self.calculate_triangular_arbitrage(pool1, pool2, pool3).await {
-                        opportunities.push(opportunity);
+                        let path: Vec<(Pool, bool)> = opportunity.route.steps
+                            .iter()
+                            .map(|step| (step.pool.clone(), matches!(step.direction, TradeDirection::Buy)))
+                            .collect();
+
+                        if Self::validate_triangular_path(&path) {
+                            opportunities.push(opportunity);
+                        } else {
+                            debug!("Discarding degenerate triangular path through {} pools", path.len());
+                        }
                     }
                 }
             }
@@ -296,6 +998,55 @@ impl Screener {
         Ok(opportunities)
     }
 
+    /// Depth-first search of the pool graph (tokens as nodes, pools as edges) for
+    /// profitable cycles of 4 to `max_hops` swaps through wrapped SOL, the token the
+    /// trade size is denominated in
+    pub async fn scan_multi_hop_arbitrage(&self, pools: &[Pool], max_hops: u8) -> Result<Vec<ArbitrageOpportunity>> {
+        let max_hops = max_hops.clamp(MIN_MULTI_HOP, MAX_MULTI_HOP);
+        let mut graph: HashMap<Pubkey, Vec<(&Pool, bool)>> = HashMap::new();
+        for pool in pools {
+            graph.entry(pool.token_a.mint).or_default().push((pool, true));
+            graph.entry(pool.token_b.mint).or_default().push((pool, false));
+        }
+
+        let start = spl_token::native_mint::id();
+        let mut search = MultiHopSearch {
+            graph: &graph,
+            start,
+            input_amount: self.wallet_trade_size(),
+            start_value_usd: None,
+            max_hops,
+            path: Vec::new(),
+            visited: HashSet::from([start]),
+            cycles: Vec::new(),
+            expansions: 0,
+        };
+        search.start_value_usd = graph.get(&start)
+            .and_then(|edges| edges.iter().find_map(|&(pool, a_to_b)| {
+                let token = if a_to_b { &pool.token_a } else { &pool.token_b };
+                token_value_usd(token, search.input_amount as f64)
+            }));
+        search.extend(start, search.input_amount, 1.0);
+
+        let mut opportunities = Vec::new();
+        for path in &search.cycles {
+            let route_type = ArbitrageType::MultiHop(path.len() as u8);
+            let input_amount = self.optimal_input_amount(self.wallet_trade_size(), |input| {
+                path.iter().try_fold(input, |amount, (pool, a_to_b)| Self::swap_output(pool, amount, *a_to_b).ok())
+            });
+            if let Ok(opportunity) = self.calculate_cycle_arbitrage(path, route_type, input_amount).await {
+                opportunities.push(opportunity);
+            }
+        }
+
+        debug!(
+            "Found {} multi-hop arbitrage opportunities after following {} pool edges",
+            opportunities.len(),
+            search.expansions
+        );
+        Ok(opportunities)
+    }
+
     async fn scan_cross_dex_arbitrage(&self, pools: &[Pool]) -> Result<Vec<ArbitrageOpportunity>> {
         let mut opportunities = Vec::new();
 
@@ -312,28 +1063,21 @@ impl Screener {
                 (token_b, token_a)
             };
             
-            cross_dex_pairs.entry(pair).or_insert_with(Vec::new).push(pool);
+            cross_dex_pairs.entry(pair).or_default().push(pool);
         }
 
         // Look for cross-DEX arbitrage opportunities
         for (_pair, pair_pools) in cross_dex_pairs {
-            let mut dex_pools: std::collections::HashMap<&str, &Pool> = std::collections::HashMap::new();
-            
-            // Get one pool per DEX for this pair
-            for pool in pair_pools {
-                if !dex_pools.contains_key(pool.dex.as_str()) {
-                    dex_pools.insert(&pool.dex, pool);
-                }
-            }
+            let pools_vec = Self::dedupe_by_fee_tier(pair_pools);
 
-            // If we have pools from multiple DEXs, check for arbitrage
-            if dex_pools.len() >= 2 {
-                let pools_vec: Vec<&Pool> = dex_pools.values().cloned().collect();
-                for i in 0..pools_vec.len() {
-                    for j in i + 1..pools_vec.len() {
-                        if let Ok(opportunity) = self.calculate_cross_dex_arbitrage(pools_vec[i], pools_vec[j]).await {
-                            opportunities.push(opportunity);
-                        }
+            // Pools on the same DEX are covered by direct arbitrage; only pair across DEXs
+            for i in 0..pools_vec.len() {
+                for j in i + 1..pools_vec.len() {
+                    if pools_vec[i].dex == pools_vec[j].dex {
+                        continue;
+                    }
+                    if let Ok(opportunity) = self.calculate_cross_dex_arbitrage(pools_vec[i], pools_vec[j]).await {
+                        opportunities.push(opportunity);
                     }
                 }
             }
@@ -343,36 +1087,200 @@ impl Screener {
         Ok(opportunities)
     }
 
+    /// Keep one pool per (DEX, fee tier), preferring the most recently fetched.
+    /// Distinct fee tiers on the same DEX are all kept since they quote different prices.
+    fn dedupe_by_fee_tier(pools: Vec<&Pool>) -> Vec<&Pool> {
+        let mut tiers: HashMap<(&str, u32), &Pool> = HashMap::new();
+        for pool in pools {
+            let fee_bps = (pool.fee_percent * Decimal::from(10000)).round().to_u32().unwrap_or(0);
+            tiers.entry((pool.dex.as_str(), fee_bps))
+                .and_modify(|existing| {
+                    if pool.last_updated > existing.last_updated {
+                        *existing = pool;
+                    }
+                })
+                .or_insert(pool);
+        }
+        tiers.into_values().collect()
+    }
+
+    /// Output of swapping `input_amount` through `pool`, token A into B when `a_to_b`.
+    /// Orca whirlpools use tick math and Meteora DLMM pairs with loaded bins use bin
+    /// math; everything else is treated as constant product.
+    pub(crate) fn swap_output(pool: &Pool, input_amount: u64, a_to_b: bool) -> Result<u64> {
+        if pool.dex == "orca" {
+            if let Some(meta) = pool.get_metadata::<OrcaPoolMeta>() {
+                return meta.swap_output(input_amount, a_to_b);
+            }
+        }
+        if let Some(meta) = pool.get_metadata::<MeteoraDlmmPoolMeta>().filter(|meta| !meta.bins.is_empty()) {
+            return Ok(meta.swap_output(input_amount, a_to_b, pool.fee_percent));
+        }
+
+        let (reserve_in, reserve_out) = if a_to_b {
+            (pool.reserve_a, pool.reserve_b)
+        } else {
+            (pool.reserve_b, pool.reserve_a)
+        };
+        // Saber pools are StableSwap, priced with the amplification parsed from the pool
+        if let Some(meta) = pool.get_metadata::<SaberPool>() {
+            return meta.swap_output(input_amount, reserve_in, reserve_out);
+        }
+        calculate_output_amount(input_amount, reserve_in, reserve_out, pool.fee_percent)
+    }
+
+    /// Trade size funded from the wallet
+    fn wallet_trade_size(&self) -> u64 {
+        (self.config().bot.max_position_size_sol * 1_000_000_000.0) as u64 // Convert SOL to lamports
+    }
+
+    /// Flash-borrowed capital isn't capped by the wallet, so size the trade by how much
+    /// the first pool absorbs within the route's slippage limit
+    fn flash_loan_trade_size(&self, pool: &Pool, route_type: &ArbitrageType) -> Result<u64> {
+        let max_price_impact = Decimal::from_f64_retain(self.config().bot.max_slippage_for(route_type) / 100.0)
+            .unwrap_or_default();
+        let size = calculate_optimal_trade_size(pool.reserve_a, pool.reserve_b, max_price_impact)?;
+        Ok(if size == 0 { self.wallet_trade_size() } else { size })
+    }
+
+    /// What the route has to return to break even: the input, plus the flash loan fee
+    /// when the input is borrowed
+    fn capital_cost(&self, input_amount: u64) -> u64 {
+        if !self.config().bot.enable_flash_loans {
+            return input_amount;
+        }
+        let fee = (input_amount as u128 * MARGINFI_FLASH_LOAN_FEE_BPS as u128).div_ceil(10_000) as u64;
+        input_amount.saturating_add(fee)
+    }
+
+    /// Input in `[MIN_INPUT_AMOUNT, max_input]` that maximizes the profit of swapping
+    /// A into B through `pool1` and back through `pool2`
+    pub fn calculate_optimal_input_amount(&self, pool1: &Pool, pool2: &Pool, max_input: u64) -> u64 {
+        self.optimal_input_amount(max_input, |input| {
+            let intermediate = Self::swap_output(pool1, input, true).ok()?;
+            Self::swap_output(pool2, intermediate, false).ok()
+        })
+    }
+
+    /// Binary search on the sign of the marginal profit of `route_output`, which is
+    /// concave in the input for constant-product and tick-math pools alike
+    fn optimal_input_amount(&self, max_input: u64, route_output: impl Fn(u64) -> Option<u64>) -> u64 {
+        if max_input <= MIN_INPUT_AMOUNT {
+            return max_input;
+        }
+
+        let profit = |input: u64| -> i128 {
+            match route_output(input) {
+                Some(output) => output as i128 - self.capital_cost(input) as i128 - ESTIMATED_GAS_COST as i128,
+                None => i128::MIN,
+            }
+        };
+
+        let (mut low, mut high) = (MIN_INPUT_AMOUNT, max_input);
+        for _ in 0..MAX_INPUT_SEARCH_ITERATIONS {
+            // Stop once the interval is within 0.1% of its upper bound
+            if high - low <= high / 1_000 {
+                break;
+            }
+            let mid = low + (high - low) / 2;
+            let step = (mid / 1_000).max(1);
+            if profit(mid.saturating_add(step)) > profit(mid) {
+                low = mid;
+            } else {
+                high = mid;
+            }
+        }
+
+        [low, low + (high - low) / 2, high].into_iter()
+            .max_by_key(|&input| profit(input))
+            .unwrap_or(low)
+    }
+
+    /// Route cache key for buying on `pool1` and selling on `pool2`
+    fn direct_route_key(pool1: &Pool, pool2: &Pool) -> String {
+        format!("{}:{}:a_to_b", pool1.address, pool2.address)
+    }
+
+    /// Drop cached routes through `pool_address` once its reserves have changed
+    pub async fn invalidate_route_cache_for_pool(&self, pool_address: &Pubkey) {
+        self.route_cache.write().await
+            .retain(|_, (opportunity, _)| !opportunity.route.steps.iter().any(|step| step.pool.address == *pool_address));
+    }
+
     async fn calculate_direct_arbitrage(&self, pool1: &Pool, pool2: &Pool) -> Result<ArbitrageOpportunity> {
-        let input_amount = (self.config.bot.max_position_size_sol * 1_000_000_000.0) as u64; // Convert SOL to lamports
+        let key = Self::direct_route_key(pool1, pool2);
+        let route_cache_ttl_ms = self.config().bot.route_cache_ttl_ms;
+        if let Some((opportunity, priced_at)) = self.route_cache.read().await.get(&key) {
+            if (priced_at.elapsed().as_millis() as u64) < route_cache_ttl_ms {
+                self.route_cache_hits.fetch_add(1, Ordering::Relaxed);
+                return Ok(opportunity.clone());
+            }
+        }
+        self.route_cache_misses.fetch_add(1, Ordering::Relaxed);
+
+        let max_input = if self.config().bot.enable_flash_loans {
+            self.flash_loan_trade_size(pool1, &ArbitrageType::Direct)?
+        } else {
+            self.wallet_trade_size()
+        };
+        // Crossing an order book's spread costs like a fee on top of the taker fee
+        let (pool1, pool2) = (Self::with_spread_as_fee(pool1), Self::with_spread_as_fee(pool2));
+        let input_amount = self.calculate_optimal_input_amount(&pool1, &pool2, max_input);
+        let opportunity = self.calculate_two_pool_arbitrage(&pool1, &pool2, ArbitrageType::Direct, input_amount).await?;
+        if route_cache_ttl_ms > 0 {
+            self.route_cache.write().await.insert(key, (opportunity.clone(), Instant::now()));
+        }
+        Ok(opportunity)
+    }
+
+    /// `pool` with its order-book spread, if any, added to `fee_percent`
+    fn with_spread_as_fee(pool: &Pool) -> Cow<'_, Pool> {
+        match pool.spread_bps() {
+            0 => Cow::Borrowed(pool),
+            spread_bps => {
+                let mut pool = pool.clone();
+                pool.fee_percent += Decimal::new(spread_bps as i64, 4);
+                Cow::Owned(pool)
+            }
+        }
+    }
+
+    async fn calculate_stable_arbitrage(&self, pool1: &Pool, pool2: &Pool) -> Result<ArbitrageOpportunity> {
+        let max_input = if self.config().bot.enable_flash_loans {
+            self.flash_loan_trade_size(pool1, &ArbitrageType::Stable)?
+        } else {
+            self.wallet_trade_size()
+        };
+        let input_amount = self.optimal_input_amount(max_input, |input| {
+            let intermediate = Self::swap_output(pool1, input, true).ok()?;
+            Self::swap_output(pool2, intermediate, false).ok()
+        });
+
+        let mut opportunity = self.calculate_two_pool_arbitrage(pool1, pool2, ArbitrageType::Stable, input_amount).await?;
+        opportunity.risk_score *= STABLE_RISK_FACTOR;
+        Ok(opportunity)
+    }
+
+    async fn calculate_two_pool_arbitrage(&self, pool1: &Pool, pool2: &Pool, route_type: ArbitrageType, input_amount: u64) -> Result<ArbitrageOpportunity> {
+        let max_slippage_percent = self.config().bot.max_slippage_for(&route_type);
         
         // Calculate price difference between pools
         let _price1 = self.calculate_pool_price(pool1, true)?; // token_a -> token_b
         let _price2 = self.calculate_pool_price(pool2, false)?; // token_b -> token_a
 
-        let expected_output1 = calculate_output_amount(
-            input_amount,
-            pool1.reserve_a,
-            pool1.reserve_b,
-            pool1.fee_percent,
-        )?;
-
-        let expected_output2 = calculate_output_amount(
-            expected_output1,
-            pool2.reserve_b,
-            pool2.reserve_a,
-            pool2.fee_percent,
-        )?;
-
-        if expected_output2 <= input_amount {
+        let expected_output1 = Self::swap_output(pool1, input_amount, true)?;
+        let expected_output2 = Self::swap_output(pool2, expected_output1, false)?;
+
+        let cost = self.capital_cost(input_amount);
+        if expected_output2 <= cost {
             anyhow::bail!("Not profitable");
         }
 
-        let profit = expected_output2 - input_amount;
+        let profit = expected_output2 - cost;
         let profit_percent = (profit as f64 / input_amount as f64) * 100.0;
 
         let route = ArbitrageRoute {
-            route_type: ArbitrageType::Direct,
+            route_type,
             from_token: pool1.token_a.mint.to_string(),
             to_token: pool1.token_a.mint.to_string(),
             intermediate_token: Some(pool1.token_b.mint.to_string()),
@@ -382,21 +1290,22 @@ impl Screener {
                     direction: TradeDirection::Buy,
                     input_amount,
                     expected_output: expected_output1,
-                    price_impact: calculate_price_impact(input_amount, pool1.reserve_a, pool1.reserve_b)?,
-                    slippage: calculate_slippage(expected_output1, pool1.reserve_b, self.config.bot.max_slippage_percent)?,
+                    price_impact: calculate_price_impact_bps(input_amount, pool1.reserve_a, pool1.reserve_b),
+                    slippage: calculate_slippage(expected_output1, pool1.reserve_b, max_slippage_percent)?,
                 },
                 TradeStep {
                     pool: pool2.clone(),
                     direction: TradeDirection::Sell,
                     input_amount: expected_output1,
                     expected_output: expected_output2,
-                    price_impact: calculate_price_impact(expected_output1, pool2.reserve_b, pool2.reserve_a)?,
-                    slippage: calculate_slippage(expected_output2, pool2.reserve_a, self.config.bot.max_slippage_percent)?,
+                    price_impact: calculate_price_impact_bps(expected_output1, pool2.reserve_b, pool2.reserve_a),
+                    slippage: calculate_slippage(expected_output2, pool2.reserve_a, max_slippage_percent)?,
                 },
             ],
             total_fee_percent: pool1.fee_percent + pool2.fee_percent,
         };
 
+        let confidence_score = self.calculate_confidence_score(&route.steps);
         let opportunity = ArbitrageOpportunity {
             id: Uuid::new_v4().to_string(),
             route,
@@ -404,8 +1313,8 @@ impl Screener {
             expected_output: expected_output2,
             expected_profit: profit,
             expected_profit_percent: profit_percent,
-            confidence_score: self.calculate_confidence_score(&[pool1, pool2]),
-            risk_score: self.calculate_risk_score(&[pool1, pool2]),
+            confidence_score,
+            risk_score: self.calculate_risk_score(&[pool1, pool2]).await,
             timestamp: chrono::Utc::now(),
             expiry: chrono::Utc::now() + chrono::Duration::seconds(30), // 30-second expiry
         };
@@ -420,33 +1329,37 @@ impl Screener {
             anyhow::bail!("No valid triangular path found");
         }
 
-        let input_amount = (self.config.bot.max_position_size_sol * 1_000_000_000.0) as u64;
+        let [(pool1, dir1), (pool2, dir2), (pool3, dir3)] = path[..] else {
+            anyhow::bail!("Triangular path must have three hops, got {}", path.len());
+        };
+        let input_amount = calculate_triangular_optimal_amount(pool1, dir1, pool2, dir2, pool3, dir3, self.wallet_trade_size());
+        self.calculate_cycle_arbitrage(&path, ArbitrageType::Triangular, input_amount).await
+    }
+
+    /// Price a closed cycle of swaps of `input_amount`, failing if it doesn't return
+    /// more than it costs
+    async fn calculate_cycle_arbitrage(&self, path: &[(&Pool, bool)], route_type: ArbitrageType, input_amount: u64) -> Result<ArbitrageOpportunity> {
+        let max_slippage = self.config().bot.max_slippage_for(&route_type);
         let mut current_amount = input_amount;
         let mut steps = Vec::new();
         let mut total_fees = Decimal::ZERO;
 
-        // Execute the triangular path
-        for (_i, (pool, direction)) in path.iter().enumerate() {
+        for (pool, direction) in path.iter() {
             let (reserve_in, reserve_out) = if *direction {
                 (pool.reserve_a, pool.reserve_b)
             } else {
                 (pool.reserve_b, pool.reserve_a)
             };
 
-            let output_amount = calculate_output_amount(
-                current_amount,
-                reserve_in,
-                reserve_out,
-                pool.fee_percent,
-            )?;
+            let output_amount = Self::swap_output(pool, current_amount, *direction)?;
 
             steps.push(TradeStep {
                 pool: (*pool).clone(),
                 direction: if *direction { TradeDirection::Buy } else { TradeDirection::Sell },
                 input_amount: current_amount,
                 expected_output: output_amount,
-                price_impact: calculate_price_impact(current_amount, reserve_in, reserve_out)?,
-                slippage: calculate_slippage(output_amount, reserve_out, self.config.bot.max_slippage_percent)?,
+                price_impact: calculate_price_impact_bps(current_amount, reserve_in, reserve_out),
+                slippage: calculate_slippage(output_amount, reserve_out, max_slippage)?,
             });
 
             current_amount = output_amount;
@@ -454,22 +1367,32 @@ impl Screener {
         }
 
         // Check if profitable
-        if current_amount <= input_amount {
-            anyhow::bail!("Triangular arbitrage not profitable");
+        let cost = self.capital_cost(input_amount);
+        if current_amount <= cost {
+            anyhow::bail!("{:?} arbitrage not profitable", route_type);
         }
 
-        let profit = current_amount - input_amount;
+        let profit = current_amount - cost;
         let profit_percent = (profit as f64 / input_amount as f64) * 100.0;
 
+        let (first_pool, first_a_to_b) = path[0];
+        let (start_token, first_intermediate) = if first_a_to_b {
+            (first_pool.token_a.mint, first_pool.token_b.mint)
+        } else {
+            (first_pool.token_b.mint, first_pool.token_a.mint)
+        };
+        let pools: Vec<&Pool> = path.iter().map(|(pool, _)| *pool).collect();
+
         let route = ArbitrageRoute {
-            route_type: ArbitrageType::Triangular,
-            from_token: steps[0].pool.token_a.mint.to_string(),
-            to_token: steps[0].pool.token_a.mint.to_string(),
-            intermediate_token: Some(steps[1].pool.token_a.mint.to_string()),
+            route_type,
+            from_token: start_token.to_string(),
+            to_token: start_token.to_string(),
+            intermediate_token: Some(first_intermediate.to_string()),
             steps,
             total_fee_percent: total_fees,
         };
 
+        let confidence_score = self.calculate_confidence_score(&route.steps);
         let opportunity = ArbitrageOpportunity {
             id: Uuid::new_v4().to_string(),
             route,
@@ -477,8 +1400,8 @@ impl Screener {
             expected_output: current_amount,
             expected_profit: profit,
             expected_profit_percent: profit_percent,
-            confidence_score: self.calculate_confidence_score(&[pool1, pool2, pool3]),
-            risk_score: self.calculate_risk_score(&[pool1, pool2, pool3]),
+            confidence_score,
+            risk_score: self.calculate_risk_score(&pools).await,
             timestamp: chrono::Utc::now(),
             expiry: chrono::Utc::now() + chrono::Duration::seconds(30),
         };
@@ -547,9 +1470,47 @@ impl Screener {
         None
     }
 
+    /// Check that a path is a genuine three-token cycle. `true` in each step means
+    /// token_a -> token_b. Rejects repeated pairs that are really a direct arb in disguise.
+    pub fn validate_triangular_path(path: &[(Pool, bool)]) -> bool {
+        if path.len() != 3 {
+            return false;
+        }
+
+        let legs: Vec<(Pubkey, Pubkey)> = path
+            .iter()
+            .map(|(pool, a_to_b)| {
+                if *a_to_b {
+                    (pool.token_a.mint, pool.token_b.mint)
+                } else {
+                    (pool.token_b.mint, pool.token_a.mint)
+                }
+            })
+            .collect();
+
+        // Every step must actually swap one token for another
+        if legs.iter().any(|(input, output)| input == output) {
+            return false;
+        }
+
+        // Each step must consume what the previous one produced
+        if legs.windows(2).any(|pair| pair[0].1 != pair[1].0) {
+            return false;
+        }
+
+        // The cycle must return to the starting token
+        if legs[2].1 != legs[0].0 {
+            return false;
+        }
+
+        // All three tokens visited must be distinct
+        let (a, b, c) = (legs[0].0, legs[1].0, legs[2].0);
+        a != b && b != c && a != c
+    }
+
     async fn calculate_cross_dex_arbitrage(&self, pool1: &Pool, pool2: &Pool) -> Result<ArbitrageOpportunity> {
         // Similar to direct arbitrage but across different DEXs
-        self.calculate_direct_arbitrage(pool1, pool2).await
+        self.calculate_two_pool_arbitrage(pool1, pool2, ArbitrageType::CrossDex, self.wallet_trade_size()).await
     }
 
     fn find_common_token(&self, pool1: &Pool, pool2: &Pool) -> Option<String> {
@@ -566,51 +1527,184 @@ impl Screener {
         None
     }
 
+    /// Spot price in whole tokens: token B per token A when `direction`, else the inverse.
+    /// Concentrated liquidity pools have no global reserves, so they are priced from the
+    /// sqrt price in their metadata.
     fn calculate_pool_price(&self, pool: &Pool, direction: bool) -> Result<Decimal> {
-        if direction {
-            // token_a -> token_b
-            if pool.reserve_a == 0 {
-                return Ok(Decimal::ZERO);
+        let sqrt_price_x64 = pool.get_metadata::<OrcaPoolMeta>().map(|meta| meta.sqrt_price)
+            .or_else(|| pool.get_metadata::<RaydiumClmmPoolMeta>().map(|meta| meta.sqrt_price_x64));
+
+        let price = match sqrt_price_x64 {
+            Some(sqrt_price_x64) => sqrt_price_x64_to_price(sqrt_price_x64, pool.token_a.decimals, pool.token_b.decimals),
+            None if pool.reserve_a == 0 => Decimal::ZERO,
+            None => {
+                let raw = Decimal::from(pool.reserve_b) / Decimal::from(pool.reserve_a);
+                let unit_a = Decimal::from(10u64.pow(pool.token_a.decimals as u32));
+                let unit_b = Decimal::from(10u64.pow(pool.token_b.decimals as u32));
+                raw * unit_a / unit_b
             }
-            Ok(Decimal::from(pool.reserve_b) / Decimal::from(pool.reserve_a))
+        };
+
+        if direction || price.is_zero() {
+            Ok(price)
         } else {
-            // token_b -> token_a
-            if pool.reserve_b == 0 {
-                return Ok(Decimal::ZERO);
-            }
-            Ok(Decimal::from(pool.reserve_a) / Decimal::from(pool.reserve_b))
+            Ok(Decimal::ONE / price)
         }
     }
 
-    fn calculate_confidence_score(&self, pools: &[&Pool]) -> f64 {
-        // Calculate confidence based on liquidity, age of data, etc.
-        let total_liquidity: f64 = pools.iter()
-            .map(|p| p.liquidity_usd.to_f64().unwrap_or(0.0))
+    fn calculate_confidence_score(&self, steps: &[TradeStep]) -> f64 {
+        // Calculate confidence from the liquidity each step can actually rely on
+        let total_liquidity: f64 = steps.iter()
+            .map(|step| {
+                let pool = &step.pool;
+                // effective_liquidity expects token A units; Sell steps spend token B
+                let trade_size = match step.direction {
+                    TradeDirection::Buy => step.input_amount,
+                    TradeDirection::Sell if pool.reserve_b > 0 => {
+                        (step.input_amount as u128 * pool.reserve_a as u128 / pool.reserve_b as u128) as u64
+                    }
+                    TradeDirection::Sell => step.input_amount,
+                };
+                match self.client_for(&pool.dex) {
+                    Some(client) => {
+                        pool.effective_liquidity(trade_size, client.active_liquidity(pool))
+                            * client.liquidity_quality_multiplier()
+                    }
+                    None => pool.effective_liquidity(trade_size, None),
+                }
+            })
             .sum();
         
         // Higher liquidity = higher confidence
-        (total_liquidity / 100000.0).min(1.0)
+        let liquidity_factor = (total_liquidity / 100000.0).min(1.0);
+
+        // The oldest, most lopsided and least reliable pool bounds the whole route
+        let now = chrono::Utc::now();
+        let time_factor = steps.iter()
+            .map(|step| {
+                let age_secs = (now - step.pool.last_updated).num_milliseconds() as f64 / 1_000.0;
+                Self::freshness_factor(age_secs)
+            })
+            .fold(1.0, f64::min);
+        let balance_factor = if steps.iter().any(|step| Self::is_imbalanced(&step.pool)) {
+            IMBALANCED_POOL_PENALTY
+        } else {
+            1.0
+        };
+        let dex_reliability = steps.iter()
+            .map(|step| self.dex_configs.get_reliability_for_dex(&step.pool.dex))
+            .fold(1.0, f64::min);
+
+        liquidity_factor * time_factor * balance_factor * dex_reliability
+    }
+
+    /// 1 for pools updated within `FRESH_POOL_SECS`, falling linearly to 0 at `STALE_POOL_SECS`
+    fn freshness_factor(age_secs: f64) -> f64 {
+        if age_secs <= FRESH_POOL_SECS {
+            return 1.0;
+        }
+        ((STALE_POOL_SECS - age_secs) / (STALE_POOL_SECS - FRESH_POOL_SECS)).clamp(0.0, 1.0)
+    }
+
+    fn is_imbalanced(pool: &Pool) -> bool {
+        let (smaller, larger) = (pool.reserve_a.min(pool.reserve_b), pool.reserve_a.max(pool.reserve_b));
+        larger > 0 && (smaller as f64) < larger as f64 * IMBALANCED_RESERVE_RATIO
     }
 
-    fn calculate_risk_score(&self, pools: &[&Pool]) -> f64 {
+    async fn calculate_risk_score(&self, pools: &[&Pool]) -> f64 {
         // Calculate risk based on volatility, slippage, etc.
         let avg_liquidity: f64 = pools.iter()
             .map(|p| p.liquidity_usd.to_f64().unwrap_or(0.0))
             .sum::<f64>() / pools.len() as f64;
         
         // Lower liquidity = higher risk
-        if avg_liquidity < 10000.0 {
+        let mut risk: f64 = if avg_liquidity < 10000.0 {
             0.8
         } else if avg_liquidity < 50000.0 {
             0.5
         } else {
             0.2
+        };
+
+        for pool in pools {
+            let change_rate = self.cache.get_reserve_change_rate(&pool.address.to_string()).await;
+            if change_rate.is_some_and(|rate| rate > RAPID_RESERVE_CHANGE_PERCENT) {
+                risk += RAPID_RESERVE_CHANGE_RISK;
+                break;
+            }
         }
+        risk.min(1.0)
     }
 
-    async fn filter_opportunities(&self, mut opportunities: Vec<ArbitrageOpportunity>) -> Result<Vec<ArbitrageOpportunity>> {
+    /// Quote the swap on every DEX concurrently and return the DEX with the largest output
+    pub async fn best_swap_quote(&self, input_token: &str, output_token: &str, amount: u64) -> Option<(&'static str, SwapQuote)> {
+        let quotes = futures_util::future::join_all(self.dex_clients.iter().map(|client| async move {
+            match client.get_swap_quote(input_token, output_token, amount).await {
+                Ok(quote) => Some((client.get_dex_name(), quote)),
+                Err(e) => {
+                    debug!("No {} quote for {} -> {}: {}", client.get_dex_name(), input_token, output_token, e);
+                    None
+                }
+            }
+        }))
+        .await;
+
+        quotes.into_iter().flatten().max_by_key(|(_, quote)| quote.output_amount)
+    }
+
+    /// Shared score weights; writes take effect on the next scan
+    pub fn scoring_weights(&self) -> Arc<RwLock<BotScoringConfig>> {
+        self.scoring.clone()
+    }
+
+    fn route_key(opportunity: &ArbitrageOpportunity) -> Vec<Pubkey> {
+        opportunity.route.steps.iter().map(|step| step.pool.address).collect()
+    }
+
+    async fn filter_opportunities(&self, mut opportunities: Vec<ArbitrageOpportunity>, pools: &[Pool]) -> Result<Vec<ArbitrageOpportunity>> {
         // Filter by profitability threshold
-        opportunities.retain(|opp| opp.expected_profit_percent >= self.config.bot.profit_threshold_percent);
+        let bot_config = self.config().bot.clone();
+        opportunities.retain(|opp| opp.expected_profit_percent >= bot_config.profit_threshold_for(&opp.route.route_type));
+
+        // Drop routes priced from pools that missed recent refreshes
+        opportunities.retain(|opp| !opp.route.steps.iter().any(|step| step.pool.is_stale(bot_config.max_pool_age_secs)));
+
+        // Drop routes the executor has no swap builder for
+        opportunities.retain(|opp| opp.route.steps.iter().all(|step| Executor::supports_dex(&step.pool.dex)));
+
+        // Drop routes that look like stale or manipulated pool data
+        let mut plausible = Vec::with_capacity(opportunities.len());
+        for opportunity in opportunities {
+            match self.price_manipulation_reason(&opportunity).await {
+                Some(reason) => warn!("Discarding opportunity {}: {}", opportunity.id, reason),
+                None => plausible.push(opportunity),
+            }
+        }
+        let mut opportunities = plausible;
+
+        // Drop routes whose profit doesn't cover the transaction fees
+        let priority_fee = self.estimated_priority_fee().await;
+        opportunities.retain(|opp| opp.net_profit_after_gas(priority_fee, opp.estimated_compute_units()) > 0);
+
+        // Drop routes through tokens the lists rule out or that can be frozen
+        let mints: HashSet<Pubkey> = opportunities.iter()
+            .flat_map(|opp| opp.route.steps.iter().flat_map(|step| [step.pool.token_a.mint, step.pool.token_b.mint]))
+            .collect();
+        let mut unsafe_mints = HashSet::new();
+        for mint in mints {
+            if !self.is_token_safe(&mint).await {
+                unsafe_mints.insert(mint);
+            }
+        }
+        opportunities.retain(|opp| !opp.route.steps.iter()
+            .any(|step| unsafe_mints.contains(&step.pool.token_a.mint) || unsafe_mints.contains(&step.pool.token_b.mint)));
+
+        if let Some(whale_monitor) = &self.whale_monitor {
+            let signals = whale_monitor.get_recent_signals(WHALE_SIGNAL_WINDOW_SECS, pools).await;
+            for opportunity in &mut opportunities {
+                Self::apply_whale_signals(opportunity, &signals);
+            }
+        }
         
         // Filter by confidence score
         opportunities.retain(|opp| opp.confidence_score >= 0.3);
@@ -618,12 +1712,818 @@ impl Screener {
         // Filter by risk score
         opportunities.retain(|opp| opp.risk_score <= 0.7);
         
-        // Sort by expected profit percentage (descending)
-        opportunities.sort_by(|a, b| b.expected_profit_percent.partial_cmp(&a.expected_profit_percent).unwrap());
-        
-        // Limit to top opportunities
-        opportunities.truncate(10);
+        // Sort by score (descending); routes that survived the previous scan get a bonus
+        let weights = self.scoring.read().unwrap().clone();
+        let current_routes: HashSet<Vec<Pubkey>> = opportunities.iter().map(Self::route_key).collect();
+        let previous_routes = std::mem::replace(&mut *self.previous_routes.lock().unwrap(), current_routes);
+        let mut scored: Vec<(f64, ArbitrageOpportunity)> = opportunities
+            .into_iter()
+            .map(|opp| (opp.score(&weights, previous_routes.contains(&Self::route_key(&opp))), opp))
+            .collect();
+        scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+        // Limit to top opportunities, skipping routes already offered within the cooldown.
+        // Only routes that make the cut start a cooldown.
+        self.deduplicator.cleanup_expired();
+        let opportunities: Vec<ArbitrageOpportunity> = scored.into_iter()
+            .map(|(_, opp)| opp)
+            .filter(|opp| !self.deduplicator.is_duplicate(opp))
+            .take(10)
+            .collect();
         
         Ok(opportunities)
     }
+
+    /// Whether the opportunity's profit is more likely bad pool data than real arbitrage
+    pub async fn detect_price_manipulation(&self, opportunity: &ArbitrageOpportunity) -> bool {
+        self.price_manipulation_reason(opportunity).await.is_some()
+    }
+
+    /// Why `detect_price_manipulation` flags the opportunity, if it does
+    async fn price_manipulation_reason(&self, opportunity: &ArbitrageOpportunity) -> Option<String> {
+        let bot_config = self.config().bot.clone();
+        if opportunity.expected_profit_percent > bot_config.max_plausible_profit_percent {
+            return Some(format!(
+                "profit {:.2}% exceeds plausible maximum {:.2}%",
+                opportunity.expected_profit_percent, bot_config.max_plausible_profit_percent
+            ));
+        }
+
+        if opportunity.expected_profit_percent > HIGH_PROFIT_PERCENT {
+            let min_liquidity = Decimal::from_f64(bot_config.min_liquidity_for_high_profit_usd).unwrap_or_default();
+            if let Some(step) = opportunity.route.steps.iter().find(|step| step.pool.liquidity_usd < min_liquidity) {
+                return Some(format!(
+                    "profit {:.2}% through pool {} with only ${} liquidity",
+                    opportunity.expected_profit_percent, step.pool.address, step.pool.liquidity_usd
+                ));
+            }
+        }
+
+        let price_feed = self.price_feed.as_ref()?;
+        let max_age_slots = self.config().bot.max_oracle_age_slots;
+        for step in &opportunity.route.steps {
+            let pool = &step.pool;
+            if !price_feed.supports(&pool.token_a.mint) || !price_feed.supports(&pool.token_b.mint) {
+                continue;
+            }
+            let (Ok(price_a), Ok(price_b)) = (
+                price_feed.get_price(&pool.token_a.mint, max_age_slots).await,
+                price_feed.get_price(&pool.token_b.mint, max_age_slots).await,
+            ) else {
+                continue;
+            };
+            let (Some(oracle_price), Some(implied_price)) = (
+                (price_a / price_b).to_f64(),
+                implied_pool_price(pool),
+            ) else {
+                continue;
+            };
+
+            let deviation = implied_price / oracle_price;
+            if !(1.0 / MAX_ORACLE_DEVIATION..=MAX_ORACLE_DEVIATION).contains(&deviation) {
+                return Some(format!(
+                    "pool {} prices {} at {:.6} {} but the oracle says {:.6}",
+                    pool.address, pool.token_a.symbol, implied_price, pool.token_b.symbol, oracle_price
+                ));
+            }
+        }
+
+        None
+    }
+
+    /// Scale confidence by the strongest whale signal on a pool the route trades in the
+    /// same direction as the whale
+    fn apply_whale_signals(opportunity: &mut ArbitrageOpportunity, signals: &[WhaleSignal]) {
+        let boost = opportunity.route.steps.iter()
+            .flat_map(|step| signals.iter().filter(move |signal| {
+                signal.pool_address == step.pool.address && signal.direction == step.direction
+            }))
+            .map(|signal| 1.0 + signal.confidence * signal.estimated_impact_percent / 100.0)
+            .fold(1.0, f64::max);
+        opportunity.confidence_score = (opportunity.confidence_score * boost).min(1.0);
+    }
+}
+
+/// Token B per whole token A implied by the pool's reserves
+fn implied_pool_price(pool: &Pool) -> Option<f64> {
+    if pool.reserve_a == 0 {
+        return None;
+    }
+    let reserve_a = pool.reserve_a as f64 / 10f64.powi(pool.token_a.decimals as i32);
+    let reserve_b = pool.reserve_b as f64 / 10f64.powi(pool.token_b.decimals as i32);
+    Some(reserve_b / reserve_a)
+}
+
+/// USD value of `amount` base units of `token`, if it has a price
+fn token_value_usd(token: &crate::models::TokenInfo, amount: f64) -> Option<f64> {
+    let price = token.price_usd?.to_f64()?;
+    Some(amount / 10f64.powi(token.decimals as i32) * price)
+}
+
+/// State of the depth-first walk behind `Screener::scan_multi_hop_arbitrage`
+struct MultiHopSearch<'a> {
+    graph: &'a HashMap<Pubkey, Vec<(&'a Pool, bool)>>,
+    start: Pubkey,
+    input_amount: u64,
+    /// Branches can only be pruned mid-path when the start token has a USD price
+    start_value_usd: Option<f64>,
+    max_hops: u8,
+    path: Vec<(&'a Pool, bool)>,
+    visited: HashSet<Pubkey>,
+    cycles: Vec<Vec<(&'a Pool, bool)>>,
+    expansions: usize,
+}
+
+impl<'a> MultiHopSearch<'a> {
+    /// Follow every pool out of `token`, holding `amount` of it; `fee_factor` is the
+    /// share of value left after the fees paid so far
+    fn extend(&mut self, token: Pubkey, amount: u64, fee_factor: f64) {
+        let graph = self.graph;
+        let Some(edges) = graph.get(&token) else {
+            return;
+        };
+        let hops = self.path.len() as u8 + 1;
+
+        for &(pool, a_to_b) in edges {
+            if self.expansions >= MAX_MULTI_HOP_EXPANSIONS {
+                return;
+            }
+            let next_token = if a_to_b { pool.token_b.mint } else { pool.token_a.mint };
+            let closes_cycle = next_token == self.start;
+            if (closes_cycle && hops < MIN_MULTI_HOP) || (!closes_cycle && (hops >= self.max_hops || self.visited.contains(&next_token))) {
+                continue;
+            }
+            self.expansions += 1;
+
+            let Ok(output) = Screener::swap_output(pool, amount, a_to_b) else {
+                continue;
+            };
+
+            if closes_cycle {
+                if output as f64 >= self.input_amount as f64 * MULTI_HOP_PRUNE_RATIO {
+                    let mut cycle = self.path.clone();
+                    cycle.push((pool, a_to_b));
+                    self.cycles.push(cycle);
+                }
+                continue;
+            }
+
+            // Value the branch at oracle prices with the fees added back
+            let fee_factor = fee_factor * (1.0 - pool.fee_percent.to_f64().unwrap_or(0.0));
+            let next_info = if a_to_b { &pool.token_b } else { &pool.token_a };
+            if let (Some(start_value), Some(value)) = (self.start_value_usd, token_value_usd(next_info, output as f64)) {
+                if fee_factor > 0.0 && value / fee_factor < start_value * MULTI_HOP_PRUNE_RATIO {
+                    continue;
+                }
+            }
+
+            self.path.push((pool, a_to_b));
+            self.visited.insert(next_token);
+            self.extend(next_token, output, fee_factor);
+            self.visited.remove(&next_token);
+            self.path.pop();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::dex::DexClient;
+    use std::sync::Arc;
+    use async_trait::async_trait;
+
+    // Mock DexClient for testing
+    pub struct MockDexClient {
+        name: &'static str,
+        pool: Option<Pool>,
+        pools: Vec<Pool>,
+    }
+
+    impl MockDexClient {
+        pub fn new(name: &'static str) -> Self {
+            MockDexClient { name, pool: None, pools: vec![] }
+        }
+
+        pub fn with_pool(name: &'static str, pool: Pool) -> Self {
+            MockDexClient { name, pool: Some(pool), pools: vec![] }
+        }
+
+        pub fn with_pools(name: &'static str, pools: Vec<Pool>) -> Self {
+            MockDexClient { name, pool: None, pools }
+        }
+    }
+
+    #[async_trait]
+    impl DexClient for MockDexClient {
+        async fn fetch_pools(&self) -> Result<Vec<Pool>> {
+            Ok(self.pools.clone())
+        }
+        async fn get_pool_by_tokens(&self, _token_a: &str, _token_b: &str) -> Result<Option<Pool>> {
+            Ok(self.pool.clone())
+        }
+        async fn update_pool_reserves(&self, _pool: &mut Pool) -> anyhow::Result<()> {
+            Ok(())
+        }
+        fn get_dex_name(&self) -> &'static str {
+            self.name
+        }
+        fn set_console_manager(&mut self, _console: Arc<crate::console::ConsoleManager>) {
+            // Mock implementation, does nothing
+        }
+    }
+
+    #[tokio::test]
+    async fn test_screener_new() {
+        let config = Config::load().unwrap();
+
+        let orca_client = Arc::new(MockDexClient::new("orca")) as Arc<dyn DexClient>;
+        let raydium_client = Arc::new(MockDexClient::new("raydium")) as Arc<dyn DexClient>;
+        let phoenix_client = Arc::new(MockDexClient::new("phoenix")) as Arc<dyn DexClient>;
+
+        let dex_clients: Vec<Arc<dyn DexClient>> = vec![
+            orca_client,
+            raydium_client,
+            phoenix_client,
+        ];
+
+        let screener = Screener::new(
+            config,
+            dex_clients,
+        );
+
+        assert!(screener.is_ok());
+    }
+
+    struct FailingDexClient {
+        reinit_count: Arc<AtomicU32>,
+    }
+
+    #[async_trait]
+    impl DexClient for FailingDexClient {
+        async fn fetch_pools(&self) -> Result<Vec<Pool>> {
+            anyhow::bail!("connection reset")
+        }
+        async fn get_pool_by_tokens(&self, _token_a: &str, _token_b: &str) -> Result<Option<Pool>> {
+            Ok(None)
+        }
+        async fn update_pool_reserves(&self, _pool: &mut Pool) -> anyhow::Result<()> {
+            Ok(())
+        }
+        fn get_dex_name(&self) -> &'static str {
+            "failing"
+        }
+        fn set_console_manager(&mut self, _console: Arc<crate::console::ConsoleManager>) {}
+        async fn reinitialize(&self) -> Result<()> {
+            self.reinit_count.fetch_add(1, Ordering::Relaxed);
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_dex_reinitialized_after_repeated_failures() {
+        let mut config = Config::default();
+        config.dexs.enabled = vec!["failing".to_string()];
+        config.bot.dex_reinit_threshold = 3;
+
+        let reinit_count = Arc::new(AtomicU32::new(0));
+        let client = Arc::new(FailingDexClient { reinit_count: reinit_count.clone() }) as Arc<dyn DexClient>;
+        let screener = Screener::new(config, vec![client]).unwrap();
+
+        for _ in 0..2 {
+            screener.update_all_pools().await.unwrap();
+        }
+        assert_eq!(reinit_count.load(Ordering::Relaxed), 0);
+
+        screener.update_all_pools().await.unwrap();
+        assert_eq!(reinit_count.load(Ordering::Relaxed), 1);
+        assert_eq!(screener.failure_counters["failing"].load(Ordering::Relaxed), 0);
+    }
+
+    #[tokio::test]
+    async fn test_clmm_pool_price_uses_sqrt_price() {
+        let screener = Screener::new(Config::default(), vec![]).unwrap();
+        let mut pool = test_pool(Pubkey::new_unique(), Pubkey::new_unique());
+        pool.token_b.decimals = 6;
+        // Reserves would claim a price of a million; the whirlpool's sqrt price says otherwise
+        pool.reserve_b = 1_000_000_000;
+        pool.metadata = Some(std::sync::Arc::new(OrcaPoolMeta {
+            vault_a: Pubkey::new_unique(),
+            vault_b: Pubkey::new_unique(),
+            oracle: Pubkey::new_unique(),
+            tick_spacing: 64,
+            liquidity: 10_000_000_000,
+            sqrt_price: crate::utils::math::tick_to_sqrt_price_x64(-46_054),
+            tick_current_index: -46_054,
+            fee_rate: 3_000,
+            tick_arrays: Vec::new(),
+        }));
+
+        // 1.0001^-46054 * 10^(9 - 6) is roughly 10 token B per token A
+        let price = screener.calculate_pool_price(&pool, true).unwrap().to_f64().unwrap();
+        assert!((price - 10.0).abs() < 0.01, "price {}", price);
+        let inverse = screener.calculate_pool_price(&pool, false).unwrap().to_f64().unwrap();
+        assert!((inverse * price - 1.0).abs() < 1e-9);
+
+        pool.metadata = None;
+        let reserve_price = screener.calculate_pool_price(&pool, true).unwrap();
+        assert_eq!(reserve_price, Decimal::from(1_000_000));
+    }
+
+    #[tokio::test]
+    async fn test_optimal_input_matches_brute_force() {
+        let screener = Screener::new(Config::default(), vec![]).unwrap();
+        let (sol, usdc) = (Pubkey::new_unique(), Pubkey::new_unique());
+        // B is 10% cheaper in the first pool than in the second
+        let mut cheap = test_pool(sol, usdc);
+        (cheap.reserve_a, cheap.reserve_b) = (1_000_000_000_000, 1_100_000_000_000);
+        cheap.fee_percent = Decimal::new(3, 3);
+        let mut rich = test_pool(sol, usdc);
+        (rich.reserve_a, rich.reserve_b) = (1_000_000_000_000, 1_000_000_000_000);
+        rich.fee_percent = Decimal::new(3, 3);
+
+        let max_input = 200_000_000_000;
+        let profit = |input: u64| {
+            let intermediate = Screener::swap_output(&cheap, input, true).unwrap();
+            Screener::swap_output(&rich, intermediate, false).unwrap() as i128 - input as i128
+        };
+        let brute_force = (1..=2_000u64)
+            .map(|i| i * max_input / 2_000)
+            .max_by_key(|&input| profit(input))
+            .unwrap();
+
+        let optimal = screener.calculate_optimal_input_amount(&cheap, &rich, max_input);
+        assert!(optimal > MIN_INPUT_AMOUNT && optimal < max_input);
+        let error = (optimal as f64 - brute_force as f64).abs() / brute_force as f64;
+        assert!(error < 0.01, "optimal {} vs brute force {}", optimal, brute_force);
+
+        // Below the search floor the cap is used as-is
+        assert_eq!(screener.calculate_optimal_input_amount(&cheap, &rich, 500_000), 500_000);
+    }
+
+    fn test_pool(token_a: Pubkey, token_b: Pubkey) -> Pool {
+        let token = |mint| crate::models::TokenInfo {
+            mint,
+            symbol: "TEST".to_string(),
+            decimals: 9,
+            price_usd: None,
+        };
+        Pool {
+            address: Pubkey::new_unique(),
+            dex: "orca".to_string(),
+            token_a: token(token_a),
+            token_b: token(token_b),
+            reserve_a: 1_000_000,
+            reserve_b: 1_000_000,
+            fee_percent: Decimal::ZERO,
+            liquidity_usd: Decimal::ZERO,
+            last_updated: chrono::Utc::now(),
+            metadata: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_token_lists_filter_pools() {
+        let (sol, usdc, scam) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let mut config = Config::default();
+        config.bot.token_blacklist = vec![scam.to_string()];
+        let screener = Screener::new(config.clone(), vec![]).unwrap();
+
+        assert!(screener.pool_tokens_allowed(&test_pool(sol, usdc)));
+        assert!(!screener.pool_tokens_allowed(&test_pool(sol, scam)));
+        assert!(!screener.is_token_safe(&scam).await);
+
+        config.bot.token_whitelist = vec![usdc.to_string()];
+        let screener = Screener::new(config, vec![]).unwrap();
+        assert!(screener.pool_tokens_allowed(&test_pool(sol, usdc)));
+        assert!(!screener.pool_tokens_allowed(&test_pool(sol, Pubkey::new_unique())));
+        assert!(!screener.is_token_safe(&sol).await);
+    }
+
+    #[tokio::test]
+    async fn test_detect_wash_trading() {
+        let screener = Screener::new(Config::default(), vec![]).unwrap();
+        let mut pool = test_pool(Pubkey::new_unique(), Pubkey::new_unique());
+        pool.reserve_a = 1_000_000_000;
+        pool.reserve_b = 1_000_000_000;
+        assert!(!screener.detect_wash_trading(&pool).await);
+
+        let mut thin = pool.clone();
+        thin.reserve_a = 100;
+        assert!(screener.detect_wash_trading(&thin).await);
+
+        // One token at $1 on each side is worth $2, far from the $1,000 reported
+        let mut inflated = pool.clone();
+        inflated.token_a.price_usd = Some(Decimal::ONE);
+        inflated.token_b.price_usd = Some(Decimal::ONE);
+        inflated.liquidity_usd = Decimal::from(1_000);
+        assert!(screener.detect_wash_trading(&inflated).await);
+
+        let address = pool.address.to_string();
+        let now = std::time::Instant::now();
+        for _ in 0..=WASH_TRADING_MAX_UPDATES_PER_MINUTE {
+            screener.cache.record_reserve_snapshot(&address, (pool.reserve_a, pool.reserve_b), now).await;
+        }
+        assert!(screener.detect_wash_trading(&pool).await);
+    }
+
+    #[tokio::test]
+    async fn test_route_cache_reuses_direct_routes_until_invalidated() {
+        let screener = Screener::new(Config::default(), vec![]).unwrap();
+        let (sol, usdc) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let mut cheap = test_pool(sol, usdc);
+        (cheap.reserve_a, cheap.reserve_b) = (1_000_000_000_000, 1_100_000_000_000);
+        let mut rich = test_pool(sol, usdc);
+        rich.dex = "raydium".to_string();
+        (rich.reserve_a, rich.reserve_b) = (1_000_000_000_000, 1_000_000_000_000);
+
+        let first = screener.calculate_direct_arbitrage(&cheap, &rich).await.unwrap();
+        let cached = screener.calculate_direct_arbitrage(&cheap, &rich).await.unwrap();
+        assert_eq!(cached.id, first.id);
+        assert_eq!(screener.route_cache_hits.load(Ordering::Relaxed), 1);
+
+        screener.invalidate_route_cache_for_pool(&rich.address).await;
+        let repriced = screener.calculate_direct_arbitrage(&cheap, &rich).await.unwrap();
+        assert_ne!(repriced.id, first.id);
+        assert_eq!(screener.route_cache_misses.load(Ordering::Relaxed), 2);
+    }
+
+    #[test]
+    fn test_validate_triangular_path() {
+        let (sol, usdc, ray) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+
+        // SOL -> USDC -> RAY -> SOL
+        let valid = vec![
+            (test_pool(sol, usdc), true),
+            (test_pool(ray, usdc), false),
+            (test_pool(ray, sol), true),
+        ];
+        assert!(Screener::validate_triangular_path(&valid));
+
+        // Same pair three times is a repeated direct arb, not a triangle
+        let degenerate = vec![
+            (test_pool(sol, usdc), true),
+            (test_pool(sol, usdc), false),
+            (test_pool(sol, usdc), true),
+        ];
+        assert!(!Screener::validate_triangular_path(&degenerate));
+
+        // Pool with the same token on both sides
+        let self_swap = vec![
+            (test_pool(sol, sol), true),
+            (test_pool(sol, usdc), true),
+            (test_pool(usdc, sol), true),
+        ];
+        assert!(!Screener::validate_triangular_path(&self_swap));
+
+        // Does not return to the starting token
+        let open_path = vec![
+            (test_pool(sol, usdc), true),
+            (test_pool(usdc, ray), true),
+            (test_pool(ray, usdc), true),
+        ];
+        assert!(!Screener::validate_triangular_path(&open_path));
+
+        assert!(!Screener::validate_triangular_path(&valid[..2]));
+    }
+
+    #[test]
+    fn test_dedupe_by_fee_tier() {
+        let (sol, usdc) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let tier = |dex: &str, fee: f64, age_secs: i64| {
+            let mut pool = test_pool(sol, usdc);
+            pool.dex = dex.to_string();
+            pool.fee_percent = Decimal::from_f64_retain(fee).unwrap();
+            pool.last_updated = chrono::Utc::now() - chrono::Duration::seconds(age_secs);
+            pool
+        };
+        let orca_5bps = tier("orca", 0.0005, 0);
+        let orca_30bps_old = tier("orca", 0.003, 60);
+        let orca_30bps_new = tier("orca", 0.003, 0);
+        let raydium_25bps = tier("raydium", 0.0025, 0);
+
+        let kept = Screener::dedupe_by_fee_tier(vec![&orca_5bps, &orca_30bps_old, &orca_30bps_new, &raydium_25bps]);
+        assert_eq!(kept.len(), 3);
+        assert!(kept.iter().any(|pool| pool.address == orca_5bps.address));
+        assert!(kept.iter().any(|pool| pool.address == orca_30bps_new.address));
+        assert!(!kept.iter().any(|pool| pool.address == orca_30bps_old.address));
+    }
+
+    #[tokio::test]
+    async fn test_best_swap_quote_picks_largest_output() {
+        let (sol, usdc) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let mut shallow = test_pool(sol, usdc);
+        shallow.reserve_b = 900_000;
+        let deep = test_pool(sol, usdc);
+
+        let dex_clients: Vec<Arc<dyn DexClient>> = vec![
+            Arc::new(MockDexClient::with_pool("orca", shallow)),
+            Arc::new(MockDexClient::with_pool("raydium", deep)),
+            Arc::new(MockDexClient::new("phoenix")),
+        ];
+        let screener = Screener::new(Config::default(), dex_clients).unwrap();
+
+        let (dex, quote) = screener
+            .best_swap_quote(&sol.to_string(), &usdc.to_string(), 10_000)
+            .await
+            .unwrap();
+        assert_eq!(dex, "raydium");
+        assert_eq!(quote.input_amount, 10_000);
+        assert_eq!(quote.output_amount, 9_900);
+        assert_eq!(quote.fee_amount, 0);
+        assert!(quote.price_impact_percent > 0.0);
+
+        // A token the pools don't hold yields no quote at all
+        let unknown = Pubkey::new_unique().to_string();
+        assert!(screener.best_swap_quote(&unknown, &usdc.to_string(), 10_000).await.is_none());
+    }
+
+    #[test]
+    fn test_whale_signal_boosts_matching_direction() {
+        let pool = test_pool(Pubkey::new_unique(), Pubkey::new_unique());
+        let step = |direction| TradeStep {
+            pool: pool.clone(),
+            direction,
+            input_amount: 1_000,
+            expected_output: 1_000,
+            price_impact: 0,
+            slippage: Decimal::ZERO,
+        };
+        let mut opportunity = ArbitrageOpportunity {
+            id: "whale".to_string(),
+            route: ArbitrageRoute {
+                route_type: ArbitrageType::Direct,
+                from_token: pool.token_a.mint.to_string(),
+                to_token: pool.token_a.mint.to_string(),
+                intermediate_token: None,
+                steps: vec![step(TradeDirection::Buy), step(TradeDirection::Sell)],
+                total_fee_percent: Decimal::ZERO,
+            },
+            input_amount: 1_000,
+            expected_output: 1_010,
+            expected_profit: 10,
+            expected_profit_percent: 1.0,
+            confidence_score: 0.5,
+            risk_score: 0.1,
+            timestamp: chrono::Utc::now(),
+            expiry: chrono::Utc::now(),
+        };
+        let signal = |pool_address, direction, estimated_impact_percent| WhaleSignal {
+            pool_address,
+            direction,
+            estimated_impact_percent,
+            confidence: 0.5,
+            timestamp: chrono::Utc::now(),
+        };
+
+        // Signals on other pools leave confidence untouched
+        Screener::apply_whale_signals(&mut opportunity, &[signal(Pubkey::new_unique(), TradeDirection::Buy, 20.0)]);
+        assert_eq!(opportunity.confidence_score, 0.5);
+
+        // The strongest matching signal wins: 0.5 * (1 + 0.5 * 20%)
+        Screener::apply_whale_signals(&mut opportunity, &[
+            signal(pool.address, TradeDirection::Buy, 10.0),
+            signal(pool.address, TradeDirection::Sell, 20.0),
+        ]);
+        assert!((opportunity.confidence_score - 0.55).abs() < 1e-9);
+
+        // Confidence never exceeds 1.0
+        Screener::apply_whale_signals(&mut opportunity, &[signal(pool.address, TradeDirection::Buy, 500.0)]);
+        assert_eq!(opportunity.confidence_score, 1.0);
+    }
+
+    #[tokio::test]
+    async fn test_detect_price_manipulation() {
+        let screener = Screener::new(Config::default(), vec![]).unwrap();
+        let mut pool = test_pool(Pubkey::new_unique(), Pubkey::new_unique());
+        pool.liquidity_usd = Decimal::from(100_000);
+        let step = |pool: &Pool| TradeStep {
+            pool: pool.clone(),
+            direction: TradeDirection::Buy,
+            input_amount: 1_000,
+            expected_output: 1_000,
+            price_impact: 0,
+            slippage: Decimal::ZERO,
+        };
+        let opportunity = |pool: &Pool, expected_profit_percent| ArbitrageOpportunity {
+            id: "manipulated".to_string(),
+            route: ArbitrageRoute {
+                route_type: ArbitrageType::Direct,
+                from_token: pool.token_a.mint.to_string(),
+                to_token: pool.token_a.mint.to_string(),
+                intermediate_token: None,
+                steps: vec![step(pool), step(pool)],
+                total_fee_percent: Decimal::ZERO,
+            },
+            input_amount: 1_000,
+            expected_output: 1_000,
+            expected_profit: 0,
+            expected_profit_percent,
+            confidence_score: 0.5,
+            risk_score: 0.1,
+            timestamp: chrono::Utc::now(),
+            expiry: chrono::Utc::now(),
+        };
+
+        assert!(!screener.detect_price_manipulation(&opportunity(&pool, 5.0)).await);
+        assert!(screener.detect_price_manipulation(&opportunity(&pool, 15.0)).await);
+
+        // High profit through a shallow pool is suspicious, low profit is not
+        pool.liquidity_usd = Decimal::from(1_000);
+        assert!(screener.detect_price_manipulation(&opportunity(&pool, 5.0)).await);
+        assert!(!screener.detect_price_manipulation(&opportunity(&pool, 1.0)).await);
+    }
+
+    #[tokio::test]
+    async fn test_scan_stable_arbitrage() {
+        let screener = Screener::new(Config::default(), vec![]).unwrap();
+        let usdc = "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v".parse::<Pubkey>().unwrap();
+        let usdt = "Es9vMFrzaCERmJfrF4H2FYD4KCoNkY11McCe8BenwNYB".parse::<Pubkey>().unwrap();
+        let saber = SaberPool {
+            token_a_mint: usdc,
+            token_b_mint: usdt,
+            token_a_vault: Pubkey::new_unique(),
+            token_b_vault: Pubkey::new_unique(),
+            pool_mint: Pubkey::new_unique(),
+            fee_numerator: 1,
+            fee_denominator: 10_000,
+            admin_fee_numerator: 0,
+            admin_fee_denominator: 1,
+            initial_amp_factor: 100,
+            target_amp_factor: 100,
+            start_ramp_ts: 0,
+            stop_ramp_ts: 0,
+        };
+        let stable_pool = |dex: &str, reserve_a: u64, reserve_b: u64| {
+            let mut pool = test_pool(usdc, usdt);
+            pool.dex = dex.to_string();
+            pool.reserve_a = reserve_a;
+            pool.reserve_b = reserve_b;
+            pool.fee_percent = Decimal::new(1, 4);
+            pool
+        };
+        // USDT is cheap on orca and dear on saber
+        let mut saber_pool = stable_pool("saber", 1_300_000_000_000, 1_000_000_000_000);
+        saber_pool.metadata = Some(Arc::new(saber.clone()));
+        let mut spoofed = test_pool(Pubkey::new_unique(), usdt);
+        spoofed.token_a.symbol = "USDC".to_string();
+        let pools = vec![stable_pool("orca", 1_000_000_000_000, 1_300_000_000_000), saber_pool, spoofed];
+
+        assert!(screener.is_stable_pool(&pools[0]));
+        assert!(!screener.is_stable_pool(&pools[2]));
+
+        let opportunities = screener.scan_stable_arbitrage(&pools).await.unwrap();
+        assert_eq!(opportunities.len(), 1);
+        let opportunity = &opportunities[0];
+        assert_eq!(opportunity.route.route_type, ArbitrageType::Stable);
+        assert!(opportunity.expected_profit_percent >= Config::default().bot.stable_profit_threshold_percent);
+
+        // Only the Saber leg is priced on the StableSwap curve, with the pool's own amplification
+        let (first, second) = (&opportunity.route.steps[0], &opportunity.route.steps[1]);
+        assert_eq!(first.pool.dex, "orca");
+        let constant_product = calculate_output_amount(first.input_amount, 1_000_000_000_000, 1_300_000_000_000, first.pool.fee_percent);
+        assert_eq!(first.expected_output, constant_product.unwrap());
+        let stable_swap = saber.swap_output(first.expected_output, 1_000_000_000_000, 1_300_000_000_000).unwrap();
+        assert_eq!(second.expected_output, stable_swap);
+    }
+
+    #[tokio::test]
+    async fn test_scan_multi_hop_arbitrage() {
+        let screener = Screener::new(Config::default(), vec![]).unwrap();
+        let sol = spl_token::native_mint::id();
+        let (a, b, c) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let deep_pool = |token_a, token_b, reserve_b: u64| {
+            let mut pool = test_pool(token_a, token_b);
+            pool.reserve_a = 1_000_000_000_000_000;
+            pool.reserve_b = reserve_b;
+            pool
+        };
+        // SOL -> A -> B -> C -> SOL, with C overpriced in SOL on the last pool
+        let mut pools = vec![
+            deep_pool(sol, a, 1_000_000_000_000_000),
+            deep_pool(a, b, 1_000_000_000_000_000),
+            deep_pool(b, c, 1_000_000_000_000_000),
+            deep_pool(c, sol, 1_020_000_000_000_000),
+        ];
+
+        // Four hops is the shortest cycle the scan reports
+        let opportunities = screener.scan_multi_hop_arbitrage(&pools, 3).await.unwrap();
+        assert_eq!(opportunities.len(), 1);
+        let opportunity = &opportunities[0];
+        assert_eq!(opportunity.route.route_type, ArbitrageType::MultiHop(4));
+        assert_eq!(opportunity.route.steps.len(), 4);
+        assert_eq!(opportunity.route.from_token, sol.to_string());
+        assert_eq!(opportunity.route.intermediate_token, Some(a.to_string()));
+        assert!(opportunity.expected_output > opportunity.input_amount);
+
+        // A branch that is already behind at oracle prices is dropped before it closes
+        pools[0].token_a.price_usd = Some(Decimal::from(100));
+        pools[0].token_b.price_usd = Some(Decimal::from(90));
+        assert!(screener.scan_multi_hop_arbitrage(&pools, 6).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_benchmark_scan_cycle() {
+        let mut config = Config::default();
+        config.bot.min_liquidity_usd = 1_000.0;
+        config.dexs.enabled = vec!["orca".to_string(), "raydium".to_string()];
+
+        let sol = spl_token::native_mint::id();
+        let (mut orca_pools, mut raydium_pools) = (Vec::new(), Vec::new());
+        for i in 0..100u64 {
+            let token = Pubkey::new_unique();
+            for (pools, dex, reserve_b) in [(&mut orca_pools, "orca", 100_000_000 + i), (&mut raydium_pools, "raydium", 105_000_000 + i)] {
+                let mut pool = test_pool(sol, token);
+                pool.dex = dex.to_string();
+                pool.reserve_a = 1_000_000_000;
+                pool.reserve_b = reserve_b;
+                pool.fee_percent = Decimal::new(3, 3);
+                pool.liquidity_usd = Decimal::from(10_000);
+                pools.push(pool);
+            }
+        }
+
+        let dex_clients: Vec<Arc<dyn DexClient>> = vec![
+            Arc::new(MockDexClient::with_pools("orca", orca_pools)),
+            Arc::new(MockDexClient::with_pools("raydium", raydium_pools)),
+        ];
+        let screener = Screener::new(config, dex_clients).unwrap();
+
+        let benchmark = screener.benchmark_scan_cycle().await.unwrap();
+        assert_eq!(screener.all_pools.read().await.len(), 200);
+        assert!(benchmark.total_ms < 500.0, "scan of 200 pools took {:.1}ms", benchmark.total_ms);
+        let phases: f64 = benchmark.phases().iter().filter(|(name, _)| *name != "total").map(|(_, ms)| ms).sum();
+        assert!(phases <= benchmark.total_ms + 1e-6);
+    }
+
+    #[tokio::test]
+    async fn test_scan_opportunities_for_pair() {
+        let mut config = Config::default();
+        config.bot.min_liquidity_usd = 1_000.0;
+        let sol = spl_token::native_mint::id();
+        let (token, other) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let pool = |dex: &str, token: Pubkey, reserve_b: u64| {
+            let mut pool = test_pool(sol, token);
+            pool.dex = dex.to_string();
+            (pool.reserve_a, pool.reserve_b) = (1_000_000_000_000, reserve_b);
+            pool.liquidity_usd = Decimal::from(100_000);
+            pool
+        };
+
+        let screener = Screener::new(config, vec![
+            Arc::new(MockDexClient::new("orca")),
+            Arc::new(MockDexClient::new("raydium")),
+        ]).unwrap();
+        screener.cache.set_pools("orca", vec![pool("orca", token, 1_015_000_000_000), pool("orca", other, 1_015_000_000_000)]).await;
+        screener.cache.set_pools("raydium", vec![pool("raydium", token, 1_000_000_000_000), pool("raydium", other, 1_000_000_000_000)]).await;
+
+        // A token with a freeze authority is never traded
+        screener.mint_freeze_checks.lock().unwrap().extend([(sol, true), (token, false)]);
+        assert!(screener.scan_opportunities_for_pair(&token, &sol).await.unwrap().is_empty());
+
+        screener.mint_freeze_checks.lock().unwrap().insert(token, true);
+        let opportunities = screener.scan_opportunities_for_pair(&token, &sol).await.unwrap();
+        assert_eq!(opportunities.len(), 1);
+        assert!(opportunities[0].route.steps.iter().all(|step| step.pool.token_b.mint == token));
+        assert!(screener.scan_opportunities_for_pair(&token, &other).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_routes_through_untradeable_dex_are_dropped() {
+        let mut config = Config::default();
+        config.bot.min_liquidity_usd = 1_000.0;
+        let (sol, token) = (spl_token::native_mint::id(), Pubkey::new_unique());
+        let pool = |dex: &str, reserve_b: u64| {
+            let mut pool = test_pool(sol, token);
+            pool.dex = dex.to_string();
+            (pool.reserve_a, pool.reserve_b) = (1_000_000_000_000, reserve_b);
+            pool.liquidity_usd = Decimal::from(100_000);
+            pool
+        };
+
+        // Same prices as `test_scan_opportunities_for_pair`, with the cheap side on Lifinity
+        let screener = Screener::new(config, vec![
+            Arc::new(MockDexClient::new("Lifinity")),
+            Arc::new(MockDexClient::new("raydium")),
+        ]).unwrap();
+        screener.cache.set_pools("Lifinity", vec![pool("Lifinity", 1_015_000_000_000)]).await;
+        screener.cache.set_pools("raydium", vec![pool("raydium", 1_000_000_000_000)]).await;
+        screener.mint_freeze_checks.lock().unwrap().extend([(sol, true), (token, true)]);
+
+        assert!(!Executor::supports_dex("Lifinity"));
+        assert!(screener.scan_opportunities_for_pair(&token, &sol).await.unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_confidence_factors() {
+        assert_eq!(Screener::freshness_factor(2.0), 1.0);
+        assert!((Screener::freshness_factor(152.5) - 0.5).abs() < 1e-9);
+        assert_eq!(Screener::freshness_factor(600.0), 0.0);
+
+        let mut pool = test_pool(Pubkey::new_unique(), Pubkey::new_unique());
+        assert!(!Screener::is_imbalanced(&pool));
+        pool.reserve_b = pool.reserve_a / 200;
+        assert!(Screener::is_imbalanced(&pool));
+    }
 }
@@ -96,6 +96,7 @@ mod tests {
             liquidity_usd: Decimal::from_f64_retain(liquidity_usd).unwrap(),
             fee_percent: Decimal::from_f64_retain(0.003).unwrap(), // 0.3% fee
             last_updated: chrono::Utc::now(),
+            metadata: None,
         }
     }
 
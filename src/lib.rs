@@ -7,6 +7,11 @@ pub mod engine;
 pub mod monitor;
 pub mod utils;
 pub mod console;
+pub mod storage;
+pub mod notifications;
+pub mod api;
+pub mod portfolio;
+pub mod metrics;
 pub mod tests;
 
 pub use config::Config;
@@ -1,7 +1,10 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
+use chrono::{NaiveDate, TimeZone};
+use clap::Parser;
 use solana_arbitrage_bot::{
-    config::Config,
-    console::{ConsoleManager, OpportunityDisplay},
+    api::{server, BotState},
+    config::{Config, CONFIG_PATH},
+    console::{ConsoleManager, OpportunityDisplay, WalletBalance, DEFAULT_SESSION_REPORT_PATH},
     dex::{
         orca::OrcaClient,
         raydium::RaydiumClient,
@@ -9,62 +12,161 @@ use solana_arbitrage_bot::{
         meteora::MeteoraDex,
         saber::SaberDex,
         serum::SerumDex,
+        openbook::OpenBookClient,
+        kamino::KaminoClient,
         lifinity::LifinityDex,
         pumpfun::PumpFunDex,
+        jupiter::JupiterClient,
+        drift::DriftClient,
         DexClient,
     },
-    dex_config::DexConfigs,
-    engine::{executor::Executor, screener::Screener},
+    dex_config::{DexConfigs, DEX_CONFIG_FILE},
+    engine::{
+        executor::{Executor, InsufficientBalanceError},
+        risk::{duration_until_utc_midnight, CircuitBreakerError},
+        screener::Screener,
+    },
+    metrics::MetricsCollector,
     monitor::{mempool::MempoolMonitor, whales::WhaleMonitor},
+    notifications::DiscordNotifier,
+    portfolio::Portfolio,
+    storage::{
+        report::{ReportFormat, TradeReport},
+        trade_history::DEFAULT_DB_PATH,
+        TradeHistory,
+    },
     tests,
-    utils::rpc::RpcClient,
+    utils::{
+        cache::PoolCache,
+        rpc::RpcClient,
+        token_registry::{TokenRegistry, COMMON_TOKENS},
+    },
+};
+use solana_sdk::{program_pack::Pack, pubkey::Pubkey};
+use spl_token::state::Account as TokenAccount;
+use std::{
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::Arc,
 };
-use std::sync::Arc;
-use tokio::time::{interval, Duration};
-use tracing::{info, error, warn};
+use tokio::{
+    sync::watch,
+    time::{interval, Duration},
+};
+use tracing::{debug, info, error, warn};
 use chrono::Utc;
-use uuid;
+
+/// Deadline for the startup DEX health check as a whole
+const DEX_HEALTH_CHECK_TIMEOUT: Duration = Duration::from_secs(15);
+
+#[derive(Parser, Debug)]
+#[command(name = "solana-arbitrage-bot", about = "Multi-DEX arbitrage bot for Solana")]
+struct Cli {
+    /// Print a trading report from the trade history database and exit
+    #[arg(long)]
+    report: bool,
+
+    /// Only include trades executed on or after this date (YYYY-MM-DD)
+    #[arg(long, requires = "report")]
+    since: Option<NaiveDate>,
+
+    /// Report output format
+    #[arg(long, value_enum, default_value_t = ReportFormat::Table, requires = "report")]
+    format: ReportFormat,
+
+    /// Show what would be reported without opening the database
+    #[arg(long, requires = "report")]
+    dry_run: bool,
+
+    /// Where to write the session report on exit
+    #[arg(long, value_name = "PATH", default_value = DEFAULT_SESSION_REPORT_PATH)]
+    export_report: PathBuf,
+
+    /// Ask the running bot, through its HTTP API, to start watching this whale wallet
+    #[arg(long, value_name = "PUBKEY", conflicts_with = "report")]
+    add_whale: Option<Pubkey>,
+}
 
 #[tokio::main]
 async fn main() -> Result<()> {
+    let cli = Cli::parse();
+
     // Initialize logging
     tracing_subscriber::fmt()
         .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
         .init();
 
+    if cli.report {
+        return run_report(&cli).await;
+    }
+    if let Some(address) = cli.add_whale {
+        return add_whale(address).await;
+    }
+
     info!("Starting Solana Arbitrage Bot");
 
     // Load configuration
-    let config = Config::load()?;
+    // Thresholds, position limits and enabled DEXes follow edits to config.toml
+    let (mut config, config_updates) = Config::load_and_watch(Path::new(CONFIG_PATH))?;
     info!("Configuration loaded successfully");
 
     // Initialize console manager early
-    let console_manager = Arc::new(ConsoleManager::new());
+    let console_manager = Arc::new(ConsoleManager::new().with_min_wallet_sol(config.bot.min_wallet_sol));
+    console_manager.start_key_handler();
     console_manager.update_status("Application", "Started");
 
     // Initialize RPC client
-    let rpc_client = Arc::new(RpcClient::new(&config)?);
+    let rpc_client = Arc::new(RpcClient::new(&config)?.with_console(console_manager.clone()));
     info!("RPC client initialized");
 
     // Initialize DEX clients dynamically from config
     info!("Initializing DEX clients...");
     
     let mut dex_clients: Vec<Arc<dyn DexClient>> = Vec::new();
-    let dex_configs = DexConfigs::new();
+    let dex_configs = match DexConfigs::load_from_file(Path::new(DEX_CONFIG_FILE)) {
+        Ok(configs) => {
+            info!("Loaded DEX list from {}", DEX_CONFIG_FILE);
+            configs
+        }
+        Err(e) => {
+            if Path::new(DEX_CONFIG_FILE).exists() {
+                warn!("{:#}; using built-in DEX list", e);
+            }
+            DexConfigs::new()
+        }
+    };
+    let token_registry = Arc::new(TokenRegistry::new(rpc_client.clone()));
     
     for dex_config in dex_configs.get_enabled() {
         info!("Initializing {} DEX...", dex_config.name);
         
         let client: Arc<dyn DexClient> = match dex_config.name.as_str() {
-            "Orca" => Arc::new(OrcaClient::new(rpc_client.clone(), console_manager.clone())?),
-            "Raydium" => Arc::new(RaydiumClient::new(rpc_client.clone(), console_manager.clone())?),
-            "Phoenix" => Arc::new(PhoenixClient::new(rpc_client.clone(), console_manager.clone())?),
-            "Meteora" => Arc::new(MeteoraDex::new(rpc_client.clone(), console_manager.clone())?),
-            "Meteora DAMM" => Arc::new(MeteoraDex::new(rpc_client.clone(), console_manager.clone())?),
+            "Orca" => Arc::new(
+                OrcaClient::new(rpc_client.clone(), console_manager.clone(), token_registry.clone())?
+                    .with_default_fee(dex_configs.get_fee_for_dex("Orca")),
+            ),
+            "Raydium" => Arc::new(
+                RaydiumClient::new(rpc_client.clone(), console_manager.clone(), token_registry.clone())?
+                    .with_default_fee(dex_configs.get_fee_for_dex("Raydium")),
+            ),
+            "Phoenix" => Arc::new(
+                PhoenixClient::new(rpc_client.clone(), console_manager.clone())?
+                    .with_default_fee(dex_configs.get_fee_for_dex("Phoenix")),
+            ),
+            "Meteora" => Arc::new(MeteoraDex::new(rpc_client.clone(), console_manager.clone(), token_registry.clone())?),
+            "Meteora DAMM" => Arc::new(MeteoraDex::new(rpc_client.clone(), console_manager.clone(), token_registry.clone())?),
             "Saber" => Arc::new(SaberDex::new(rpc_client.clone(), console_manager.clone())?),
             "Serum" => Arc::new(SerumDex::new(rpc_client.clone(), console_manager.clone())?),
+            "OpenBook" => Arc::new(OpenBookClient::new(rpc_client.clone(), console_manager.clone(), token_registry.clone())?),
+            "Kamino" => Arc::new(KaminoClient::new(rpc_client.clone(), console_manager.clone(), token_registry.clone())?),
             "Lifinity" => Arc::new(LifinityDex::new(rpc_client.clone(), console_manager.clone())?),
-            "Pump.fun" => Arc::new(PumpFunDex::new(rpc_client.clone(), console_manager.clone())?),
+            "Jupiter" => Arc::new(JupiterClient::new(console_manager.clone(), token_registry.clone())?),
+            "Drift" => Arc::new(DriftClient::new(rpc_client.clone(), console_manager.clone())?),
+            "Pump.fun" => Arc::new(
+                PumpFunDex::new(rpc_client.clone(), console_manager.clone())?
+                    .with_limits(config.dexs.pumpfun_max_pools, config.dexs.pumpfun_min_market_cap)
+                    .with_max_graduation_progress(config.bot.pumpfun_max_graduation_progress),
+            ),
             _ => {
                 warn!("Unknown DEX: {}, skipping...", dex_config.name);
                 continue;
@@ -77,35 +179,85 @@ async fn main() -> Result<()> {
 
     // Initialize core components
 
-    let screener = Arc::new(Screener::new(
+    let whale_monitor = Arc::new(WhaleMonitor::new(
         config.clone(),
-        dex_clients.clone(),
+        rpc_client.clone(),
+        console_manager.clone(),
     )?);
 
-    let executor = Arc::new(Executor::new(
+    let bot_state = Arc::new(
+        BotState::new()
+            .with_dex_clients(dex_clients.clone())
+            .with_whale_monitor(whale_monitor.clone()),
+    );
+
+    let notifier = config.monitoring.discord_webhook_url
+        .as_deref()
+        .map(|url| Arc::new(DiscordNotifier::new(url)));
+
+    // Only the executor needs the raw key; it keeps the keypair and drops the rest
+    let executor_config = config.clone();
+    config.clear_private_key();
+
+    let screener = Arc::new(Screener::new(
         config.clone(),
-        rpc_client.clone(),
-    )?);
+        dex_clients.clone(),
+    )?
+    .with_rpc_client(rpc_client.clone())
+    .with_bot_state(bot_state.clone())
+    .with_config_updates(config_updates.clone())
+    .with_whale_monitor(whale_monitor.clone())
+    .with_dex_configs(dex_configs.clone()));
+    bot_state.set_score_weights(screener.scoring_weights());
 
     // Initialize monitoring components
     let mempool_monitor = Arc::new(MempoolMonitor::new(
         config.clone(),
         rpc_client.clone(),
         console_manager.clone(),
-    )?);
+    )?
+    .with_screener(screener.clone()));
 
-    let whale_monitor = Arc::new(WhaleMonitor::new(
-        config.clone(),
+    let mut executor = Executor::new(
+        executor_config,
         rpc_client.clone(),
-        console_manager.clone(),
-    )?);
+    )?
+    .with_bot_state(bot_state.clone())
+    .with_dex_clients(dex_clients.clone())
+    .with_config_updates(config_updates.clone())
+    .with_mempool_monitor(mempool_monitor.clone())
+    .with_screener(screener.clone());
+    if let Some(notifier) = &notifier {
+        executor = executor.with_notifier(notifier.clone());
+    }
+    if config.bot.use_versioned_transactions {
+        let loaded = executor.load_lookup_tables().await?;
+        info!("Loaded {} address lookup tables for v0 transactions", loaded);
+    }
+    let executor = Arc::new(executor);
 
     info!("All components initialized successfully");
 
+    // Warm-start from the pool lists saved by the previous run
+    if let Some(path) = config.bot.cache_file_path.as_deref() {
+        match PoolCache::load_from_disk(std::path::Path::new(path)) {
+            Ok(saved) => {
+                let restored = screener.cache().restore_pools_from(&saved).await;
+                info!("Restored {} cached pool lists from {}", restored, path);
+            }
+            Err(e) => warn!("No pool cache restored from {}: {:#}", path, e),
+        }
+    }
+
     // Test DEX connections at startup using the actual DEX clients and cache pools
     info!("Testing DEX connections and caching pools...");
     
-    let connection_tester = tests::DexConnectionTester::new(rpc_client.clone(), console_manager.clone());
+    let connection_tester = tests::DexConnectionTester::new(
+        rpc_client.clone(),
+        console_manager.clone(),
+        config.dexs.min_required_dexes,
+    )
+    .with_dex_clients(dex_clients.clone());
     let (test_results, cached_pools) = connection_tester.test_and_cache_dex_clients(&dex_clients).await?;
     
     info!("Cached {} pools from {} DEX clients", cached_pools.len(), dex_clients.len());
@@ -143,6 +295,18 @@ async fn main() -> Result<()> {
         }
     }
 
+    let health_report = connection_tester.parallel_health_check(DEX_HEALTH_CHECK_TIMEOUT).await?;
+    console_manager.update_service_status(
+        "DEX Health",
+        &health_report.recommended_action,
+        "Startup health check",
+        Some(format!(
+            "{} healthy, {} degraded, {} failed, {} pools",
+            health_report.healthy_count, health_report.degraded_count, health_report.failed_count, health_report.total_pools
+        )),
+    );
+    bot_state.record_dex_health(health_report);
+
     // Start monitoring tasks
     let mempool_handle = {
         let monitor = mempool_monitor.clone();
@@ -162,10 +326,80 @@ async fn main() -> Result<()> {
         })
     };
 
+    let api_handle = {
+        let bot_state = bot_state.clone();
+        let port = config.api_port;
+        tokio::spawn(async move {
+            if let Err(e) = server::serve(port, bot_state).await {
+                error!("API server error: {:#}", e);
+            }
+        })
+    };
+
+    // Clear the daily loss counter at each UTC midnight
+    let risk_reset_handle = {
+        let executor = executor.clone();
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(duration_until_utc_midnight(Utc::now())).await;
+                executor.risk_manager().reset_daily_counters();
+            }
+        })
+    };
+
+    // Snapshot the trading wallet once a minute for the session P&L
+    let portfolio_handle = executor.trading_pubkey().map(|owner| {
+        let portfolio = Portfolio::new(rpc_client.clone(), owner);
+        let console = console_manager.clone();
+        tokio::spawn(async move {
+            let mut interval = interval(Duration::from_secs(60));
+            loop {
+                interval.tick().await;
+                match portfolio.snapshot_balance().await {
+                    Ok(_) => console.update_session_pnl(portfolio.compute_pnl(portfolio.started_at())),
+                    Err(e) => warn!("Portfolio snapshot failed: {}", e),
+                }
+            }
+        })
+    });
+
+    // Show the trading wallet's balances on the console
+    let wallet_handle = executor.trading_pubkey().map(|owner| {
+        let rpc_client = rpc_client.clone();
+        let console = console_manager.clone();
+        tokio::spawn(async move {
+            let mut interval = interval(Duration::from_secs(30));
+            loop {
+                interval.tick().await;
+                match fetch_wallet_balance(&rpc_client, &owner).await {
+                    Ok(balance) => console.update_wallet_balance(balance),
+                    Err(e) => warn!("Wallet balance refresh failed: {}", e),
+                }
+            }
+        })
+    });
+
+    // Publish RPC latency percentiles to the metrics and the console header
+    let latency_handle = MetricsCollector::global().spawn_rpc_latency_updates(rpc_client.clone(), console_manager.clone());
+
+    // Stream pool reserves once the first scan has populated the pool list
+    let subscription_handle = {
+        let screener = screener.clone();
+        tokio::spawn(async move {
+            loop {
+                if let Err(e) = screener.start_pool_subscriptions().await {
+                    debug!("Pool subscriptions unavailable: {}", e);
+                }
+                tokio::time::sleep(Duration::from_secs(5)).await;
+            }
+        })
+    };
+
     // Main arbitrage loop
     let mut interval = interval(Duration::from_secs(config.bot.cooldown_seconds));
     let mut consecutive_failures = 0;
     const MAX_CONSECUTIVE_FAILURES: u32 = 10;
+    const PAUSED_POLL_INTERVAL: Duration = Duration::from_millis(500);
 
     info!("Starting main arbitrage loop");
     
@@ -173,9 +407,21 @@ async fn main() -> Result<()> {
     console_manager.update_service_status("Application", "Running", "Healthy", None);
 
     loop {
-        interval.tick().await;
+        tokio::select! {
+            _ = interval.tick() => {}
+            _ = console_manager.shutdown_requested() => {
+                info!("Stopping main arbitrage loop");
+                break;
+            }
+        }
+
+        if console_manager.is_paused() {
+            console_manager.update_status("ArbitrageCycle", "Paused");
+            tokio::time::sleep(PAUSED_POLL_INTERVAL).await;
+            continue;
+        }
 
-        match run_arbitrage_cycle(&screener, &executor, &config, &console_manager).await {
+        match run_arbitrage_cycle(&screener, &executor, &config_updates, &console_manager).await {
             Ok(()) => {
                 consecutive_failures = 0;
                 info!("Arbitrage cycle completed successfully.");
@@ -185,8 +431,21 @@ async fn main() -> Result<()> {
                 consecutive_failures += 1;
                 error!("Arbitrage cycle failed: {} (consecutive failures: {})", e, consecutive_failures);
                 console_manager.update_status("ArbitrageCycle", &format!("Failed: {}", e));
-                
-                if consecutive_failures >= MAX_CONSECUTIVE_FAILURES {
+
+                let shutting_down = consecutive_failures >= MAX_CONSECUTIVE_FAILURES;
+                if let Some(notifier) = &notifier {
+                    let error = format!("{:#}", e);
+                    let sent = if shutting_down {
+                        notifier.notify_fatal("Shutting down after repeated failures", &error).await
+                    } else {
+                        notifier.notify_error("Arbitrage cycle failed", &error).await
+                    };
+                    if let Err(e) = sent {
+                        warn!("Failed to send error notification: {}", e);
+                    }
+                }
+
+                if shutting_down {
                     error!("Too many consecutive failures, shutting down");
                     break;
                 }
@@ -203,6 +462,20 @@ async fn main() -> Result<()> {
     // Cleanup
     mempool_handle.abort();
     whale_handle.abort();
+    subscription_handle.abort();
+    latency_handle.abort();
+    api_handle.abort();
+    risk_reset_handle.abort();
+    if let Some(handle) = portfolio_handle {
+        handle.abort();
+    }
+    if let Some(handle) = wallet_handle {
+        handle.abort();
+    }
+
+    if let Err(e) = console_manager.export_session_report(&cli.export_report) {
+        warn!("Failed to export session report: {}", e);
+    }
     
     info!("Solana Arbitrage Bot shutting down");
     Ok(())
@@ -211,9 +484,11 @@ async fn main() -> Result<()> {
 async fn run_arbitrage_cycle(
     screener: &Arc<Screener>,
     executor: &Arc<Executor>,
-    config: &Config,
+    config: &watch::Receiver<Config>,
     console: &Arc<ConsoleManager>,
 ) -> Result<()> {
+    let bot_config = config.borrow().bot.clone();
+
     // Screen for arbitrage opportunities
     console.update_status("ArbitrageCycle", "Scanning opportunities");
     let opportunities = screener.scan_opportunities().await?;
@@ -231,10 +506,12 @@ async fn run_arbitrage_cycle(
         &format!("{} opportunities", opportunities.len())
     );
 
-    // Display opportunities in console
+    // Display opportunities in console, net of transaction fees
+    let priority_fee = screener.estimated_priority_fee().await;
     for opportunity in &opportunities {
+        let net_profit = opportunity.net_profit_after_gas(priority_fee, opportunity.estimated_compute_units());
         let opportunity_display = OpportunityDisplay {
-            id: format!("arb_{}", uuid::Uuid::new_v4().to_string()[..8].to_string()),
+            id: format!("arb_{}", &uuid::Uuid::new_v4().to_string()[..8]),
             dex_pair: format!("{} -> {}", 
                 opportunity.route.steps[0].pool.dex,
                 opportunity.route.steps.last().unwrap().pool.dex
@@ -243,8 +520,8 @@ async fn run_arbitrage_cycle(
                 opportunity.route.from_token,
                 opportunity.route.to_token
             ),
-            profit_percent: opportunity.expected_profit_percent,
-            profit_usd: opportunity.expected_profit as f64 / 1_000_000_000.0, // Convert lamports to SOL
+            profit_percent: opportunity.net_profit_percent(priority_fee),
+            net_profit_sol: net_profit as f64 / 1_000_000_000.0, // Convert lamports to SOL
             timestamp: Utc::now(),
         };
         console.add_opportunity(opportunity_display);
@@ -253,7 +530,7 @@ async fn run_arbitrage_cycle(
     // Execute profitable opportunities
     let mut executed_count = 0;
     for opportunity in opportunities {
-        if opportunity.expected_profit_percent >= config.bot.profit_threshold_percent {
+        if opportunity.expected_profit_percent >= bot_config.profit_threshold_for(&opportunity.route.route_type) {
             info!(
                 "Executing arbitrage: {} -> {} (expected profit: {:.2}%)",
                 opportunity.route.from_token,
@@ -273,16 +550,30 @@ async fn run_arbitrage_cycle(
                     executed_count += 1;
                 }
                 Err(e) => {
+                    // A tripped breaker fails the whole cycle so the main loop backs off and eventually stops
+                    if e.downcast_ref::<CircuitBreakerError>().is_some() {
+                        return Err(e);
+                    }
+                    if let Some(shortfall) = e.downcast_ref::<InsufficientBalanceError>() {
+                        warn!("Skipping {}: {}", opportunity.id, shortfall);
+                        console.update_status_with_info("ArbitrageCycle", "Skipped trade", &shortfall.to_string());
+                        continue;
+                    }
                     error!("Trade execution failed: {}", e);
                 }
             }
 
             // Cooldown between trades
-            tokio::time::sleep(Duration::from_secs(config.bot.cooldown_seconds)).await;
+            tokio::time::sleep(Duration::from_secs(bot_config.cooldown_seconds)).await;
         }
     }
 
     if executed_count > 0 {
+        match executor.profit_divergence() {
+            Ok(Some(divergence)) => console.update_profit_divergence(divergence),
+            Ok(None) => {}
+            Err(e) => warn!("Failed to read trade history: {}", e),
+        }
         console.update_status_with_info(
             "ArbitrageCycle", 
             "Completed", 
@@ -294,3 +585,75 @@ async fn run_arbitrage_cycle(
 
     Ok(())
 }
+
+/// SOL balance of `owner` plus its associated token accounts for the common mints
+async fn fetch_wallet_balance(rpc_client: &RpcClient, owner: &Pubkey) -> Result<WalletBalance> {
+    let sol_balance_lamports = rpc_client.get_sol_balance(owner).await?;
+
+    let tokens: Vec<(Pubkey, &str)> = COMMON_TOKENS.iter()
+        .filter(|(_, symbol, _)| *symbol != "SOL")
+        .map(|(mint, symbol, _)| (Pubkey::from_str(mint).unwrap(), *symbol))
+        .collect();
+    let atas: Vec<Pubkey> = tokens.iter()
+        .map(|(mint, _)| spl_associated_token_account::get_associated_token_address(owner, mint))
+        .collect();
+    let accounts = rpc_client.get_multiple_accounts(&atas).await?;
+
+    let mut token_balances: Vec<(String, u64)> = tokens.iter()
+        .zip(accounts)
+        .filter_map(|((_, symbol), account)| {
+            let amount = TokenAccount::unpack(&account?.data).ok()?.amount;
+            (amount > 0).then(|| (symbol.to_string(), amount))
+        })
+        .collect();
+    token_balances.sort_by_key(|(_, amount)| std::cmp::Reverse(*amount));
+
+    Ok(WalletBalance {
+        sol_balance_lamports,
+        token_balances,
+        last_updated: Utc::now(),
+    })
+}
+
+/// Add `address` to the whale list of the bot serving the API on `api_port`
+async fn add_whale(address: Pubkey) -> Result<()> {
+    let port = Config::load().unwrap_or_default().api_port;
+    let url = format!("http://127.0.0.1:{}/whale/watch/{}", port, address);
+
+    let response = reqwest::Client::new().post(&url).send().await
+        .with_context(|| format!("Failed to reach the bot's API at {}", url))?;
+    if !response.status().is_success() {
+        let status = response.status();
+        anyhow::bail!("Bot refused whale {}: {} {}", address, status, response.text().await.unwrap_or_default());
+    }
+
+    println!("Now watching whale {}", address);
+    Ok(())
+}
+
+async fn run_report(cli: &Cli) -> Result<()> {
+    let since = match cli.since {
+        Some(date) => Utc.from_utc_datetime(&date.and_hms_opt(0, 0, 0).unwrap()),
+        None => Utc.timestamp_opt(0, 0).unwrap(),
+    };
+
+    let db_path = Config::load()
+        .map(|config| config.trade_history_path().to_string())
+        .unwrap_or_else(|_| DEFAULT_DB_PATH.to_string());
+
+    if cli.dry_run {
+        println!("Dry run: trading report");
+        println!("  database: {}", db_path);
+        println!("  since:    {}", since.format("%Y-%m-%d"));
+        println!("  format:   {:?}", cli.format);
+        println!("No database connection was made.");
+        return Ok(());
+    }
+
+    let history = TradeHistory::open_read_only(&db_path)?;
+    let trades = history.get_trades(since, i64::MAX as usize)?;
+    let report = TradeReport::build(trades);
+
+    print!("{}", report.render(cli.format)?);
+    Ok(())
+}
@@ -0,0 +1,277 @@
+use crate::{console::ConsoleManager, utils::rpc::RpcClient};
+use std::{
+    collections::BTreeMap,
+    fmt::Write,
+    sync::{
+        atomic::{AtomicI64, AtomicU64, Ordering},
+        Arc, Mutex, OnceLock,
+    },
+    time::Duration,
+};
+use tokio::task::JoinHandle;
+
+/// Upper bounds, in seconds, of the scan duration histogram buckets
+const SCAN_DURATION_BUCKETS_SECS: [f64; 10] = [0.01, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0];
+/// Upper bounds of the opportunity profit histogram buckets, in percent
+const PROFIT_PERCENT_BUCKETS: [f64; 9] = [0.1, 0.25, 0.5, 1.0, 2.0, 3.0, 5.0, 10.0, 25.0];
+const RPC_LATENCY_UPDATE_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Cumulative Prometheus histogram over fixed bucket bounds
+#[derive(Debug, Clone)]
+pub(crate) struct Histogram {
+    bounds: &'static [f64],
+    bucket_counts: Vec<u64>,
+    count: u64,
+    sum: f64,
+}
+
+impl Histogram {
+    pub(crate) fn new(bounds: &'static [f64]) -> Self {
+        Self {
+            bounds,
+            bucket_counts: vec![0; bounds.len()],
+            count: 0,
+            sum: 0.0,
+        }
+    }
+
+    pub(crate) fn observe(&mut self, value: f64) {
+        for (count, bound) in self.bucket_counts.iter_mut().zip(self.bounds) {
+            if value <= *bound {
+                *count += 1;
+            }
+        }
+        self.count += 1;
+        self.sum += value;
+    }
+
+    /// Write the bucket, sum and count series; `labels` is either empty or ends with a comma
+    pub(crate) fn render(&self, output: &mut String, name: &str, labels: &str) {
+        for (count, bound) in self.bucket_counts.iter().zip(self.bounds) {
+            let _ = writeln!(output, "{}_bucket{{{}le=\"{}\"}} {}", name, labels, bound, count);
+        }
+        let _ = writeln!(output, "{}_bucket{{{}le=\"+Inf\"}} {}", name, labels, self.count);
+        let labels = labels.trim_end_matches(',');
+        if labels.is_empty() {
+            let _ = writeln!(output, "{}_sum {}", name, self.sum);
+            let _ = writeln!(output, "{}_count {}", name, self.count);
+        } else {
+            let _ = writeln!(output, "{}_sum{{{}}} {}", name, labels, self.sum);
+            let _ = writeln!(output, "{}_count{{{}}} {}", name, labels, self.count);
+        }
+    }
+}
+
+/// Process-wide trading and RPC metrics, served by `GET /metrics`
+pub struct MetricsCollector {
+    scans_total: AtomicU64,
+    opportunities_found: AtomicU64,
+    trades_executed: AtomicU64,
+    trades_failed: AtomicU64,
+    profit_lamports_total: AtomicI64,
+    pool_cache_hits: AtomicU64,
+    pool_cache_misses: AtomicU64,
+    /// Requests per JSON-RPC method
+    rpc_calls_total: Mutex<BTreeMap<String, u64>>,
+    rpc_errors_total: Mutex<BTreeMap<String, u64>>,
+    scan_duration_seconds: Mutex<Histogram>,
+    opportunity_profit_percent: Mutex<Histogram>,
+    /// p50, p95 and p99 RPC latency in milliseconds, once measured
+    rpc_latency_ms: Mutex<Option<[u64; 3]>>,
+}
+
+impl MetricsCollector {
+    pub fn new() -> Self {
+        Self {
+            scans_total: AtomicU64::new(0),
+            opportunities_found: AtomicU64::new(0),
+            trades_executed: AtomicU64::new(0),
+            trades_failed: AtomicU64::new(0),
+            profit_lamports_total: AtomicI64::new(0),
+            pool_cache_hits: AtomicU64::new(0),
+            pool_cache_misses: AtomicU64::new(0),
+            rpc_calls_total: Mutex::new(BTreeMap::new()),
+            rpc_errors_total: Mutex::new(BTreeMap::new()),
+            scan_duration_seconds: Mutex::new(Histogram::new(&SCAN_DURATION_BUCKETS_SECS)),
+            opportunity_profit_percent: Mutex::new(Histogram::new(&PROFIT_PERCENT_BUCKETS)),
+            rpc_latency_ms: Mutex::new(None),
+        }
+    }
+
+    /// The collector shared by the whole process
+    pub fn global() -> &'static MetricsCollector {
+        static GLOBAL: OnceLock<MetricsCollector> = OnceLock::new();
+        GLOBAL.get_or_init(MetricsCollector::new)
+    }
+
+    pub fn increment_scans(&self) {
+        self.scans_total.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn observe_scan_duration(&self, duration: Duration) {
+        self.scan_duration_seconds.lock().unwrap().observe(duration.as_secs_f64());
+    }
+
+    /// Count an opportunity that passed filtering and add its profit to the histogram
+    pub fn record_opportunity(&self, profit_percent: f64) {
+        self.opportunities_found.fetch_add(1, Ordering::Relaxed);
+        self.opportunity_profit_percent.lock().unwrap().observe(profit_percent);
+    }
+
+    /// Count a confirmed trade and its realized profit, which may be negative
+    pub fn increment_trades_executed(&self, profit_lamports: i64) {
+        self.trades_executed.fetch_add(1, Ordering::Relaxed);
+        self.profit_lamports_total.fetch_add(profit_lamports, Ordering::Relaxed);
+    }
+
+    pub fn increment_trades_failed(&self) {
+        self.trades_failed.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_pool_cache_hit(&self) {
+        self.pool_cache_hits.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_pool_cache_miss(&self) {
+        self.pool_cache_misses.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_rpc_call(&self, method: &str) {
+        Self::increment_labeled(&self.rpc_calls_total, method);
+    }
+
+    pub fn record_rpc_error(&self, method: &str) {
+        Self::increment_labeled(&self.rpc_errors_total, method);
+    }
+
+    pub fn set_rpc_latency_percentiles(&self, p50_ms: u64, p95_ms: u64, p99_ms: u64) {
+        *self.rpc_latency_ms.lock().unwrap() = Some([p50_ms, p95_ms, p99_ms]);
+    }
+
+    /// Copy `rpc_client`'s latency percentiles into the gauges, and p95 onto the console,
+    /// every `RPC_LATENCY_UPDATE_INTERVAL`
+    pub fn spawn_rpc_latency_updates(&'static self, rpc_client: Arc<RpcClient>, console: Arc<ConsoleManager>) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(RPC_LATENCY_UPDATE_INTERVAL);
+            loop {
+                interval.tick().await;
+                let percentiles = [50.0, 95.0, 99.0].map(|p| rpc_client.get_latency_percentile(p));
+                if let [Some(p50), Some(p95), Some(p99)] = percentiles {
+                    self.set_rpc_latency_percentiles(p50, p95, p99);
+                }
+                console.update_rpc_latency_p95(percentiles[1]);
+            }
+        })
+    }
+
+    fn increment_labeled(counts: &Mutex<BTreeMap<String, u64>>, label: &str) {
+        let mut counts = counts.lock().unwrap();
+        match counts.get_mut(label) {
+            Some(count) => *count += 1,
+            None => {
+                counts.insert(label.to_string(), 1);
+            }
+        }
+    }
+
+    /// Prometheus text exposition format
+    pub fn render(&self) -> String {
+        let counters: [(&str, &str, String); 7] = [
+            ("arb_scans_total", "Completed opportunity scans", self.scans_total.load(Ordering::Relaxed).to_string()),
+            ("arb_opportunities_found_total", "Opportunities that passed filtering", self.opportunities_found.load(Ordering::Relaxed).to_string()),
+            ("arb_trades_executed_total", "Trades confirmed on chain", self.trades_executed.load(Ordering::Relaxed).to_string()),
+            ("arb_trades_failed_total", "Trades that were sent but did not confirm", self.trades_failed.load(Ordering::Relaxed).to_string()),
+            ("arb_profit_lamports_total", "Realized profit of executed trades", self.profit_lamports_total.load(Ordering::Relaxed).to_string()),
+            ("pool_cache_hits_total", "Pool list lookups served from cache", self.pool_cache_hits.load(Ordering::Relaxed).to_string()),
+            ("pool_cache_misses_total", "Pool list lookups that had to fetch from the DEX", self.pool_cache_misses.load(Ordering::Relaxed).to_string()),
+        ];
+
+        let mut output = String::new();
+        for (name, help, value) in counters {
+            let _ = writeln!(output, "# HELP {} {}", name, help);
+            let _ = writeln!(output, "# TYPE {} counter", name);
+            let _ = writeln!(output, "{} {}", name, value);
+        }
+
+        for (name, help, counts) in [
+            ("rpc_calls_total", "RPC requests sent, by method", &self.rpc_calls_total),
+            ("rpc_errors_total", "RPC requests that failed, by method", &self.rpc_errors_total),
+        ] {
+            let _ = writeln!(output, "# HELP {} {}", name, help);
+            let _ = writeln!(output, "# TYPE {} counter", name);
+            for (method, count) in counts.lock().unwrap().iter() {
+                let _ = writeln!(output, "{}{{method=\"{}\"}} {}", name, method, count);
+            }
+        }
+
+        for (name, help, histogram) in [
+            ("scan_duration_seconds", "Time taken by a full opportunity scan", &self.scan_duration_seconds),
+            ("opportunity_profit_percent", "Expected profit of opportunities that passed filtering", &self.opportunity_profit_percent),
+        ] {
+            let _ = writeln!(output, "# HELP {} {}", name, help);
+            let _ = writeln!(output, "# TYPE {} histogram", name);
+            histogram.lock().unwrap().render(&mut output, name, "");
+        }
+
+        if let Some(latencies) = *self.rpc_latency_ms.lock().unwrap() {
+            for (percentile, latency_ms) in ["p50", "p95", "p99"].iter().zip(latencies) {
+                let name = format!("rpc_latency_{}_ms", percentile);
+                let _ = writeln!(output, "# HELP {} {} latency of recent RPC requests", name, percentile);
+                let _ = writeln!(output, "# TYPE {} gauge", name);
+                let _ = writeln!(output, "{} {}", name, latency_ms);
+            }
+        }
+        output
+    }
+}
+
+impl Default for MetricsCollector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_metrics() {
+        let metrics = MetricsCollector::new();
+        metrics.increment_scans();
+        metrics.increment_scans();
+        metrics.increment_trades_executed(12_000);
+        metrics.increment_trades_executed(-2_000);
+        metrics.increment_trades_failed();
+        metrics.record_pool_cache_hit();
+        metrics.record_rpc_call("getAccountInfo");
+        metrics.record_rpc_call("getAccountInfo");
+        metrics.record_rpc_call("getBalance");
+        metrics.record_rpc_error("getBalance");
+        metrics.record_opportunity(0.8);
+        metrics.observe_scan_duration(Duration::from_millis(300));
+
+        let output = metrics.render();
+        assert!(output.contains("# TYPE arb_scans_total counter\narb_scans_total 2\n"));
+        assert!(output.contains("\narb_trades_executed_total 2\n"));
+        assert!(output.contains("\narb_trades_failed_total 1\n"));
+        assert!(output.contains("\narb_profit_lamports_total 10000\n"));
+        assert!(output.contains("\npool_cache_hits_total 1\n"));
+        assert!(output.contains("\npool_cache_misses_total 0\n"));
+        assert!(output.contains("\nrpc_calls_total{method=\"getAccountInfo\"} 2\n"));
+        assert!(output.contains("\nrpc_errors_total{method=\"getBalance\"} 1\n"));
+        assert!(output.contains("\narb_opportunities_found_total 1\n"));
+        assert!(output.contains("opportunity_profit_percent_bucket{le=\"0.5\"} 0\n"));
+        assert!(output.contains("opportunity_profit_percent_bucket{le=\"1\"} 1\n"));
+        assert!(output.contains("scan_duration_seconds_bucket{le=\"0.25\"} 0\n"));
+        assert!(output.contains("scan_duration_seconds_bucket{le=\"0.5\"} 1\n"));
+        assert!(output.contains("\nscan_duration_seconds_count 1\n"));
+        assert!(!output.contains("rpc_latency_p50_ms"));
+
+        metrics.set_rpc_latency_percentiles(40, 180, 900);
+        let output = metrics.render();
+        assert!(output.contains("# TYPE rpc_latency_p50_ms gauge\nrpc_latency_p50_ms 40\n"));
+        assert!(output.contains("\nrpc_latency_p95_ms 180\n"));
+        assert!(output.contains("\nrpc_latency_p99_ms 900\n"));
+    }
+}
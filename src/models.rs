@@ -1,9 +1,16 @@
-use crate::types::{ArbitrageType, DexName, TokenMint, TradeDirection};
+use crate::{
+    config::BotScoringConfig,
+    dex::{phoenix::PhoenixPoolMeta, serum::SerumPoolMeta},
+    types::{ArbitrageType, DexName, TokenMint, TradeDirection},
+    utils::math::{calculate_output_amount, calculate_price_impact},
+};
+use rust_decimal::prelude::ToPrimitive;
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 use solana_sdk::pubkey::Pubkey;
+use std::{any::Any, fmt, str::FromStr, sync::Arc};
 
-#[derive(Debug, Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Pool {
     pub address: Pubkey,
     pub dex: DexName,
@@ -11,16 +18,168 @@ pub struct Pool {
     pub token_b: TokenInfo,
     pub reserve_a: u64,
     pub reserve_b: u64,
+    // Explicit string encoding so non-self-describing formats like bincode can read it back
+    #[serde(with = "rust_decimal::serde::str")]
     pub fee_percent: Decimal,
+    #[serde(with = "rust_decimal::serde::str")]
     pub liquidity_usd: Decimal,
     pub last_updated: chrono::DateTime<chrono::Utc>,
+    /// DEX-specific data captured during pool conversion (vaults, curves, oracles).
+    /// Not persisted; clients rebuild it when the pool is next refreshed.
+    #[serde(skip)]
+    pub metadata: Option<Arc<dyn Any + Send + Sync>>,
 }
 
-#[derive(Debug, Clone)]
+impl Pool {
+    /// Whether the reserves were last refreshed more than `max_age_secs` ago
+    pub fn is_stale(&self, max_age_secs: u64) -> bool {
+        (chrono::Utc::now() - self.last_updated).num_seconds() > max_age_secs as i64
+    }
+
+    /// Typed access to the DEX-specific metadata; `None` if absent or of another type
+    pub fn get_metadata<T: Any>(&self) -> Option<&T> {
+        self.metadata.as_ref()?.downcast_ref::<T>()
+    }
+
+    /// Bid-ask spread of an order-book market, in basis points of the bid. AMM pools
+    /// price from reserves and have none, so they, and books with a missing side, return 0.
+    pub fn spread_bps(&self) -> u32 {
+        let order_book = self.get_metadata::<SerumPoolMeta>()
+            .and_then(|meta| meta.order_book.as_ref())
+            .or_else(|| self.get_metadata::<PhoenixPoolMeta>()?.order_book.as_ref());
+        match order_book {
+            Some(book) if book.best_bid > 0.0 && book.best_ask > book.best_bid => {
+                ((book.best_ask - book.best_bid) / book.best_bid * 10_000.0).round() as u32
+            }
+            _ => 0,
+        }
+    }
+
+    /// Reject pools with implausible on-chain values before they reach the math
+    pub fn is_sane(&self) -> bool {
+        self.sanity_check().is_ok()
+    }
+
+    /// Like `is_sane`, but names the first check that failed
+    pub fn sanity_check(&self) -> Result<(), &'static str> {
+        if self.reserve_a == 0 || self.reserve_b == 0 {
+            return Err("empty reserve");
+        }
+        if self.fee_percent < Decimal::ZERO || self.fee_percent >= Decimal::from_f64_retain(0.2).unwrap() {
+            return Err("fee out of range");
+        }
+        if self.reserve_a >= u64::MAX / 2 || self.reserve_b >= u64::MAX / 2 {
+            return Err("reserve overflow risk");
+        }
+        if self.last_updated <= chrono::Utc::now() - chrono::Duration::minutes(60) {
+            return Err("stale pool data");
+        }
+        Ok(())
+    }
+
+    /// USD value of both reserves; a pool with one known price is valued at twice that side,
+    /// and one with none assumes the quote token is a ~$1 stablecoin
+    pub fn estimate_liquidity_usd(
+        reserve_a: u64,
+        reserve_b: u64,
+        decimals_a: u8,
+        decimals_b: u8,
+        price_a: Option<f64>,
+        price_b: Option<f64>,
+    ) -> Decimal {
+        let reserve_a_normalized = reserve_a as f64 / 10_f64.powi(decimals_a as i32);
+        let reserve_b_normalized = reserve_b as f64 / 10_f64.powi(decimals_b as i32);
+
+        let estimated_liquidity = match (price_a, price_b) {
+            (Some(price_a), Some(price_b)) => reserve_a_normalized * price_a + reserve_b_normalized * price_b,
+            // Both sides of an AMM pool hold equal value, so one known price is enough
+            (Some(price_a), None) => reserve_a_normalized * price_a * 2.0,
+            (None, Some(price_b)) => reserve_b_normalized * price_b * 2.0,
+            (None, None) => reserve_b_normalized * 2.0,
+        };
+
+        Decimal::from_f64_retain(estimated_liquidity).unwrap_or(Decimal::ZERO)
+    }
+
+    /// Re-value `liquidity_usd` from the current reserves and token prices.
+    /// Leaves it untouched when neither token has a price.
+    pub fn refresh_liquidity_usd(&mut self) {
+        let price_a = self.token_a.price_usd.and_then(|price| price.to_f64());
+        let price_b = self.token_b.price_usd.and_then(|price| price.to_f64());
+        if price_a.is_none() && price_b.is_none() {
+            return;
+        }
+
+        self.liquidity_usd = Self::estimate_liquidity_usd(
+            self.reserve_a,
+            self.reserve_b,
+            self.token_a.decimals,
+            self.token_b.decimals,
+            price_a,
+            price_b,
+        );
+    }
+
+    /// USD liquidity a trade of `trade_size` (token A units) can rely on.
+    ///
+    /// Measures the depth within ±1% of the current price that is left after the
+    /// trade, expressed as the TVL a full-range pool would need for the same depth.
+    /// CLMM pools pass their `DexClient::active_liquidity`, so concentrated ranges score
+    /// higher; other pools use the constant-product invariant implied by their reserves.
+    pub fn effective_liquidity(&self, trade_size: u64, active_liquidity: Option<(f64, f64)>) -> f64 {
+        let liquidity_usd = self.liquidity_usd.to_f64().unwrap_or(0.0);
+        if self.reserve_a == 0 || self.reserve_b == 0 || liquidity_usd <= 0.0 {
+            return 0.0;
+        }
+
+        let reserve_a = self.reserve_a as f64;
+        let reserve_b = self.reserve_b as f64;
+        let (liquidity, sqrt_price) = active_liquidity
+            .unwrap_or_else(|| ((reserve_a * reserve_b).sqrt(), (reserve_b / reserve_a).sqrt()));
+
+        // Token A that moves the price down 1% plus token B (valued in A) that moves it up 1%
+        let band_factor = (1.0 / 0.99f64.sqrt() - 1.0) + (1.01f64.sqrt() - 1.0);
+        let depth_a = liquidity / sqrt_price * band_factor;
+        let remaining_a = (depth_a - trade_size as f64).max(0.0);
+
+        // A full-range pool holds half its value on each side, so its band depth is
+        // band_factor / 2 of its TVL
+        let value_a = reserve_a + reserve_b / (sqrt_price * sqrt_price);
+        let usd_per_a = liquidity_usd / value_a;
+        remaining_a * usd_per_a / (band_factor / 2.0)
+    }
+}
+
+impl fmt::Debug for Pool {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Pool")
+            .field("address", &self.address)
+            .field("dex", &self.dex)
+            .field("token_a", &self.token_a)
+            .field("token_b", &self.token_b)
+            .field("reserve_a", &self.reserve_a)
+            .field("reserve_b", &self.reserve_b)
+            .field("fee_percent", &self.fee_percent)
+            .field("liquidity_usd", &self.liquidity_usd)
+            .field("last_updated", &self.last_updated)
+            .field("metadata", &self.metadata.is_some())
+            .finish()
+    }
+}
+
+/// Top of an order-book market (Phoenix, Serum/OpenBook), in quote per base
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OrderBookData {
+    pub best_bid: f64,
+    pub best_ask: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TokenInfo {
     pub mint: Pubkey,
     pub symbol: String,
     pub decimals: u8,
+    #[serde(with = "rust_decimal::serde::str_option")]
     pub price_usd: Option<Decimal>,
 }
 
@@ -40,7 +199,8 @@ pub struct TradeStep {
     pub direction: TradeDirection,
     pub input_amount: u64,
     pub expected_output: u64,
-    pub price_impact: Decimal,
+    /// Price impact in basis points
+    pub price_impact: u32,
     pub slippage: Decimal,
 }
 
@@ -58,6 +218,87 @@ pub struct ArbitrageOpportunity {
     pub expiry: chrono::DateTime<chrono::Utc>,
 }
 
+/// Profit percentage that earns the full profit component of `score`
+const PROFIT_SCORE_CAP_PERCENT: f64 = 5.0;
+/// Lamports charged per signature, independent of compute
+pub const BASE_TX_FEE_LAMPORTS: u64 = 5_000;
+/// Compute budget requested for a route, before its per-step cost
+const BASE_COMPUTE_UNITS: u32 = 50_000;
+const COMPUTE_UNITS_PER_STEP: u32 = 100_000;
+/// Most compute units a single transaction may request
+pub const MAX_COMPUTE_UNITS: u32 = 1_400_000;
+
+impl ArbitrageOpportunity {
+    /// Compute units to request for this route's transaction
+    pub fn estimated_compute_units(&self) -> u32 {
+        let steps = self.route.steps.len() as u32;
+        BASE_COMPUTE_UNITS.saturating_add(steps.saturating_mul(COMPUTE_UNITS_PER_STEP)).min(MAX_COMPUTE_UNITS)
+    }
+
+    /// Expected profit in lamports after the base fee and a priority fee of
+    /// `priority_fee_microlamports` per compute unit
+    pub fn net_profit_after_gas(&self, priority_fee_microlamports: u64, compute_units: u32) -> i64 {
+        let priority_fee = (priority_fee_microlamports as u128 * compute_units as u128 / 1_000_000) as i64;
+        self.expected_profit as i64 - priority_fee - BASE_TX_FEE_LAMPORTS as i64
+    }
+
+    /// `net_profit_after_gas` as a percentage of the input
+    pub fn net_profit_percent(&self, priority_fee_microlamports: u64) -> f64 {
+        if self.input_amount == 0 {
+            return 0.0;
+        }
+        let net = self.net_profit_after_gas(priority_fee_microlamports, self.estimated_compute_units());
+        net as f64 / self.input_amount as f64 * 100.0
+    }
+
+    /// Rank this opportunity with the given weights; `persisted` marks a route
+    /// that was also found in the previous scan
+    pub fn score(&self, weights: &BotScoringConfig, persisted: bool) -> f64 {
+        let profit = (self.expected_profit_percent / PROFIT_SCORE_CAP_PERCENT).clamp(0.0, 1.0);
+        let safety = (1.0 - self.risk_score).clamp(0.0, 1.0);
+        let mut score = weights.profit_weight * profit
+            + weights.confidence_weight * self.confidence_score.clamp(0.0, 1.0)
+            + weights.risk_weight * safety;
+        if persisted {
+            score += weights.persistence_bonus;
+        }
+        score
+    }
+}
+
+/// Expected result of swapping `input_amount` through a single pool
+#[derive(Debug, Clone, PartialEq)]
+pub struct SwapQuote {
+    pub input_amount: u64,
+    pub output_amount: u64,
+    pub fee_amount: u64,
+    pub price_impact_percent: f64,
+}
+
+impl SwapQuote {
+    /// Quote a swap of `amount` of `input_token` (a mint address) against the pool's reserves
+    pub fn from_pool(pool: &Pool, input_token: &str, amount: u64) -> anyhow::Result<Self> {
+        let (input_reserve, output_reserve) = if pool.token_a.mint.to_string() == input_token {
+            (pool.reserve_a, pool.reserve_b)
+        } else if pool.token_b.mint.to_string() == input_token {
+            (pool.reserve_b, pool.reserve_a)
+        } else {
+            anyhow::bail!("Token {} is not in pool {}", input_token, pool.address);
+        };
+
+        let output_amount = calculate_output_amount(amount, input_reserve, output_reserve, pool.fee_percent)?;
+        let fee_amount = (Decimal::from(amount) * pool.fee_percent).to_u64().unwrap_or(0);
+        let price_impact = calculate_price_impact(amount, input_reserve, output_reserve)?;
+
+        Ok(Self {
+            input_amount: amount,
+            output_amount,
+            fee_amount,
+            price_impact_percent: (price_impact * Decimal::from(100)).to_f64().unwrap_or(0.0),
+        })
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct ProfitabilityAnalysis {
     pub gross_profit: Decimal,
@@ -78,6 +319,80 @@ pub struct MempoolTransaction {
     pub token_mint: Option<String>,
     pub program_id: String,
     pub timestamp: chrono::DateTime<chrono::Utc>,
+    /// Program log lines of the transaction
+    pub logs: Vec<String>,
+    /// Swap parameters decoded from the instruction data, when the instructions were available
+    #[serde(default)]
+    pub decoded_swap: Option<DecodedSwap>,
+}
+
+/// Swap parameters read from a DEX instruction. Instruction data carries no mints, so the
+/// tokens are `Pubkey::default()` unless they could be filled in from token balance changes.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DecodedSwap {
+    pub input_token: Pubkey,
+    pub output_token: Pubkey,
+    /// Raw input amount; base or quote lots for Phoenix
+    pub input_amount: u64,
+    /// Slippage-protected minimum output, in the same units as `input_amount`
+    pub min_output_amount: u64,
+    pub dex: String,
+}
+
+impl DecodedSwap {
+    /// Whether both mints are known
+    pub fn has_tokens(&self) -> bool {
+        self.input_token != Pubkey::default() && self.output_token != Pubkey::default()
+    }
+
+    /// Input in SOL when the swap spends wrapped SOL. Phoenix amounts are lots, and other
+    /// mints would need a price, so those give `None`.
+    pub fn input_sol(&self) -> Option<f64> {
+        (self.input_token == spl_token::native_mint::id() && self.dex != "phoenix")
+            .then(|| self.input_amount as f64 / 1_000_000_000.0)
+    }
+}
+
+impl MempoolTransaction {
+    /// Input and output mints of the swap: the decoded instruction's when known, otherwise
+    /// read from `<key>: <mint>` or `<key>=<mint>` fields in the program logs. Keys naming a side (`input_mint`, `mint_out`, ...) win; otherwise
+    /// the first two distinct mints are taken as input and output.
+    pub fn extract_swap_tokens(&self) -> Option<(String, String)> {
+        if let Some(swap) = self.decoded_swap.as_ref().filter(|swap| swap.has_tokens()) {
+            return Some((swap.input_token.to_string(), swap.output_token.to_string()));
+        }
+
+        let mut input = None;
+        let mut output = None;
+        let mut unlabeled: Vec<&str> = Vec::new();
+
+        for log in &self.logs {
+            let mut previous = "";
+            for word in log.split(|c: char| c.is_whitespace() || c == ',').filter(|word| !word.is_empty()) {
+                let (key, value) = word.split_once('=').unwrap_or((previous, word));
+                previous = word.trim_end_matches(':');
+
+                let key = key.trim_end_matches(':').to_ascii_lowercase();
+                if !key.contains("mint") || Pubkey::from_str(value).is_err() {
+                    continue;
+                }
+                // "mint" itself contains "in", so look at the rest of the key
+                let side = key.replace("mint", "");
+                if side.contains("out") || side.contains("dest") {
+                    output.get_or_insert(value);
+                } else if side.contains("in") || side.contains("src") || side.contains("source") {
+                    input.get_or_insert(value);
+                } else if !unlabeled.contains(&value) {
+                    unlabeled.push(value);
+                }
+            }
+        }
+
+        let mut unlabeled = unlabeled.into_iter();
+        let input = input.or_else(|| unlabeled.next())?;
+        let output = output.or_else(|| unlabeled.find(|mint| *mint != input))?;
+        (input != output).then(|| (input.to_string(), output.to_string()))
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -92,6 +407,17 @@ pub struct WhaleActivity {
     pub price_impact: Option<Decimal>,
 }
 
+/// Price pressure on a pool inferred from a recent whale trade in one of its tokens
+#[derive(Debug, Clone)]
+pub struct WhaleSignal {
+    pub pool_address: Pubkey,
+    pub direction: TradeDirection,
+    pub estimated_impact_percent: f64,
+    /// Fades from 1.0 to 0.0 as the trade ages out of the signal window
+    pub confidence: f64,
+    pub timestamp: chrono::DateTime<chrono::Utc>,
+}
+
 #[derive(Debug, Clone)]
 pub struct TradeExecution {
     pub opportunity_id: String,
@@ -107,3 +433,231 @@ pub struct TradeExecution {
 }
 
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dex::serum::SerumVersion;
+
+    #[derive(Debug, PartialEq)]
+    struct TestMeta {
+        vault: Pubkey,
+    }
+
+    #[test]
+    fn test_pool_metadata_downcast() {
+        let vault = Pubkey::new_unique();
+        let token = TokenInfo {
+            mint: Pubkey::new_unique(),
+            symbol: "SOL".to_string(),
+            decimals: 9,
+            price_usd: None,
+        };
+        let mut pool = Pool {
+            address: Pubkey::new_unique(),
+            dex: "orca".to_string(),
+            token_a: token.clone(),
+            token_b: token,
+            reserve_a: 0,
+            reserve_b: 0,
+            fee_percent: Decimal::ZERO,
+            liquidity_usd: Decimal::ZERO,
+            last_updated: chrono::Utc::now(),
+            metadata: None,
+        };
+        assert!(pool.get_metadata::<TestMeta>().is_none());
+
+        pool.metadata = Some(Arc::new(TestMeta { vault }));
+        assert_eq!(pool.get_metadata::<TestMeta>(), Some(&TestMeta { vault }));
+        assert!(pool.get_metadata::<u64>().is_none());
+    }
+
+    #[test]
+    fn test_spread_bps() {
+        let token = TokenInfo {
+            mint: Pubkey::new_unique(),
+            symbol: "SOL".to_string(),
+            decimals: 9,
+            price_usd: None,
+        };
+        let mut pool = Pool {
+            address: Pubkey::new_unique(),
+            dex: "orca".to_string(),
+            token_a: token.clone(),
+            token_b: token,
+            reserve_a: 1_000_000,
+            reserve_b: 2_000_000,
+            fee_percent: Decimal::ZERO,
+            liquidity_usd: Decimal::ZERO,
+            last_updated: chrono::Utc::now(),
+            metadata: None,
+        };
+        assert_eq!(pool.spread_bps(), 0);
+
+        pool.dex = "Serum".to_string();
+        pool.metadata = Some(Arc::new(SerumPoolMeta {
+            version: SerumVersion::OpenBook,
+            order_book: Some(OrderBookData { best_bid: 100.0, best_ask: 100.5 }),
+        }));
+        assert_eq!(pool.spread_bps(), 50);
+
+        pool.dex = "phoenix".to_string();
+        pool.metadata = Some(Arc::new(PhoenixPoolMeta {
+            base_lot_size: 1_000,
+            quote_lot_size: 1,
+            order_book: Some(OrderBookData { best_bid: 20.0, best_ask: 20.2 }),
+        }));
+        assert_eq!(pool.spread_bps(), 100);
+
+        // A crossed or one-sided book has no meaningful spread
+        pool.metadata = Some(Arc::new(SerumPoolMeta {
+            version: SerumVersion::OpenBook,
+            order_book: Some(OrderBookData { best_bid: 0.0, best_ask: 1.0 }),
+        }));
+        assert_eq!(pool.spread_bps(), 0);
+    }
+
+    #[test]
+    fn test_pool_sanity_checks() {
+        let token = TokenInfo {
+            mint: Pubkey::new_unique(),
+            symbol: "SOL".to_string(),
+            decimals: 9,
+            price_usd: None,
+        };
+        let pool = Pool {
+            address: Pubkey::new_unique(),
+            dex: "orca".to_string(),
+            token_a: token.clone(),
+            token_b: token,
+            reserve_a: 1_000_000,
+            reserve_b: 2_000_000,
+            fee_percent: Decimal::from_f64_retain(0.003).unwrap(),
+            liquidity_usd: Decimal::from(1_000_000),
+            last_updated: chrono::Utc::now(),
+            metadata: None,
+        };
+        assert!(pool.is_sane());
+
+        let mut empty = pool.clone();
+        empty.reserve_a = 0;
+        assert_eq!(empty.sanity_check(), Err("empty reserve"));
+
+        let mut greedy = pool.clone();
+        greedy.fee_percent = Decimal::from_f64_retain(0.25).unwrap();
+        assert_eq!(greedy.sanity_check(), Err("fee out of range"));
+
+        let mut huge = pool.clone();
+        huge.reserve_b = u64::MAX - 1;
+        assert_eq!(huge.sanity_check(), Err("reserve overflow risk"));
+
+        assert!(!pool.is_stale(60));
+        let mut lagging = pool.clone();
+        lagging.last_updated = chrono::Utc::now() - chrono::Duration::seconds(90);
+        assert!(lagging.is_stale(60));
+        assert!(!lagging.is_stale(120));
+
+        let mut stale = pool;
+        stale.last_updated = chrono::Utc::now() - chrono::Duration::hours(2);
+        assert_eq!(stale.sanity_check(), Err("stale pool data"));
+    }
+
+    #[test]
+    fn test_effective_liquidity_rewards_concentration() {
+        let token = TokenInfo {
+            mint: Pubkey::new_unique(),
+            symbol: "SOL".to_string(),
+            decimals: 9,
+            price_usd: None,
+        };
+        let amm = Pool {
+            address: Pubkey::new_unique(),
+            dex: "raydium".to_string(),
+            token_a: token.clone(),
+            token_b: token,
+            reserve_a: 1_000_000_000,
+            reserve_b: 1_000_000_000,
+            fee_percent: Decimal::from_f64_retain(0.003).unwrap(),
+            liquidity_usd: Decimal::from(50_000),
+            last_updated: chrono::Utc::now(),
+            metadata: None,
+        };
+
+        // A full-range pool is worth its TVL until the trade eats into the ±1% band
+        assert!((amm.effective_liquidity(0, None) - 50_000.0).abs() < 1.0);
+        assert!(amm.effective_liquidity(5_000_000, None) < amm.effective_liquidity(0, None));
+        assert_eq!(amm.effective_liquidity(50_000_000, None), 0.0);
+
+        // Same reserves, but ten times the active liquidity around the current price
+        assert!((amm.effective_liquidity(0, Some((10_000_000_000.0, 1.0))) - 500_000.0).abs() < 10.0);
+    }
+
+    #[test]
+    fn test_estimate_liquidity_uses_token_prices() {
+        // 1,000 RAY (6 decimals) against 10 SOL (9 decimals)
+        let (reserve_a, reserve_b) = (1_000_000_000, 10_000_000_000);
+        let liquidity = |price_a, price_b| {
+            Pool::estimate_liquidity_usd(reserve_a, reserve_b, 6, 9, price_a, price_b)
+        };
+
+        assert_eq!(liquidity(Some(2.0), Some(150.0)), Decimal::from(3_500));
+        assert_eq!(liquidity(None, Some(150.0)), Decimal::from(3_000));
+        // Without prices the quote side is valued at $1
+        assert_eq!(liquidity(None, None), Decimal::from(20));
+
+        let token = |decimals, price_usd| TokenInfo {
+            mint: Pubkey::new_unique(),
+            symbol: "UNK".to_string(),
+            decimals,
+            price_usd,
+        };
+        let mut pool = Pool {
+            address: Pubkey::new_unique(),
+            dex: "orca".to_string(),
+            token_a: token(6, None),
+            token_b: token(9, None),
+            reserve_a,
+            reserve_b,
+            fee_percent: Decimal::ZERO,
+            liquidity_usd: Decimal::ZERO,
+            last_updated: chrono::Utc::now(),
+            metadata: None,
+        };
+        // No prices: keep whatever the DEX client reported
+        pool.refresh_liquidity_usd();
+        assert_eq!(pool.liquidity_usd, Decimal::ZERO);
+
+        pool.token_b.price_usd = Some(Decimal::from(150));
+        pool.refresh_liquidity_usd();
+        assert_eq!(pool.liquidity_usd, Decimal::from(3_000));
+    }
+
+    #[test]
+    fn test_net_profit_after_gas() {
+        let opportunity = ArbitrageOpportunity {
+            id: "test".to_string(),
+            route: ArbitrageRoute {
+                route_type: ArbitrageType::Direct,
+                from_token: "A".to_string(),
+                to_token: "A".to_string(),
+                intermediate_token: None,
+                steps: Vec::new(),
+                total_fee_percent: Decimal::ZERO,
+            },
+            input_amount: 1_000_000_000,
+            expected_output: 1_000_020_000,
+            expected_profit: 20_000,
+            expected_profit_percent: 0.002,
+            confidence_score: 1.0,
+            risk_score: 0.0,
+            timestamp: chrono::Utc::now(),
+            expiry: chrono::Utc::now(),
+        };
+
+        // 20_000 - 10_000 microlamports * 250_000 CU / 1e6 - 5_000
+        assert_eq!(opportunity.net_profit_after_gas(10_000, 250_000), 12_500);
+        // Gross profit that a busy fee market turns into a loss
+        assert_eq!(opportunity.net_profit_after_gas(100_000, 250_000), -10_000);
+        assert_eq!(opportunity.estimated_compute_units(), 50_000);
+    }
+}
@@ -1,33 +1,146 @@
 use crate::{
     config::Config,
     console::ConsoleManager,
-    models::MempoolTransaction,
+    dex::{orca::WHIRLPOOL_PROGRAM_ID, phoenix::PHOENIX_PROGRAM_ID},
+    engine::screener::Screener,
+    models::{DecodedSwap, MempoolTransaction},
     utils::rpc::RpcClient,
 };
 use anyhow::{Context, Result};
-use futures_util::{SinkExt, StreamExt};
+use chrono::Utc;
+use futures_util::{stream::SplitSink, SinkExt, StreamExt};
 use serde_json::{json, Value};
-use std::sync::Arc;
-use tokio_tungstenite::{connect_async, tungstenite::Message};
+use solana_sdk::pubkey::Pubkey;
+use std::{
+    collections::{HashMap, HashSet},
+    str::FromStr,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
+use tokio::{net::TcpStream, sync::{Mutex, RwLock}};
+use tokio_tungstenite::{connect_async, tungstenite::Message, MaybeTlsStream, WebSocketStream};
 use tracing::{debug, error, info, warn};
 
+/// Swaps seen this recently can still land ahead of a trade we are about to send
+const SANDWICH_WINDOW_MS: i64 = 2_000;
+/// A front-run within this factor of our size, either way, is enough to move our price
+const SANDWICH_SIZE_RATIO: f64 = 5.0;
+
+const RAYDIUM_AMM_PROGRAM_ID: &str = "675kPX9MHTjS2zt1qfr1NYHuzeLXfQM9H24wFSUt1Mp8";
+/// Anchor discriminator of the Whirlpool `swap` instruction
+const WHIRLPOOL_SWAP_DISCRIMINATOR: [u8; 8] = [248, 198, 158, 145, 225, 117, 135, 200];
+/// Raydium AMM v4 instruction tags: exact input, then exact output
+const RAYDIUM_SWAP_BASE_IN: u8 = 9;
+const RAYDIUM_SWAP_BASE_OUT: u8 = 11;
+/// Phoenix `Swap` instruction tag, and the `ImmediateOrCancel` order packet it carries
+const PHOENIX_SWAP: u8 = 0;
+const PHOENIX_IMMEDIATE_OR_CANCEL: u8 = 2;
+const PHOENIX_SIDE_BID: u8 = 0;
+
+/// Request id of the `logsSubscribe` call; wallet subscriptions count up from the next one
+const LOGS_SUBSCRIPTION_REQUEST_ID: u64 = 1;
+/// Watched-wallet balance moves below this are fees and rent rather than trades
+const WALLET_FEE_NOISE_LAMPORTS: i64 = 100_000;
+
+type WsSink = SplitSink<WebSocketStream<MaybeTlsStream<TcpStream>>, Message>;
+
+/// Watched-wallet subscriptions on the current WebSocket connection
+#[derive(Default)]
+struct WalletSubscriptions {
+    /// `accountSubscribe` request ids still waiting for their subscription id
+    pending: HashMap<u64, Pubkey>,
+    /// Subscription id to the wallet it reports on
+    active: HashMap<u64, Pubkey>,
+}
+
 pub struct MempoolMonitor {
     config: Config,
-    _rpc_client: Arc<RpcClient>,
+    rpc_client: Arc<RpcClient>,
     detected_transactions: tokio::sync::RwLock<Vec<MempoolTransaction>>,
     console: Arc<ConsoleManager>,
+    screener: Option<Arc<Screener>>,
+    /// Starts from `monitoring.watched_wallets`; extended through `subscribe_to_wallet`
+    watched_wallets: RwLock<HashSet<Pubkey>>,
+    /// Write half of the open WebSocket, if connected
+    ws_sender: Arc<Mutex<Option<WsSink>>>,
+    wallet_subscriptions: Mutex<WalletSubscriptions>,
+    /// Last lamport balance seen per watched wallet
+    wallet_lamports: Mutex<HashMap<Pubkey, u64>>,
+    next_request_id: AtomicU64,
 }
 
 impl MempoolMonitor {
-    pub fn new(config: Config, _rpc_client: Arc<RpcClient>, console: Arc<ConsoleManager>) -> Result<Self> {
+    pub fn new(config: Config, rpc_client: Arc<RpcClient>, console: Arc<ConsoleManager>) -> Result<Self> {
+        let watched_wallets = config
+            .monitoring
+            .watched_wallets
+            .iter()
+            .filter_map(|addr| Pubkey::from_str(addr).ok())
+            .collect();
+
         Ok(Self {
             config,
-            _rpc_client,
+            rpc_client,
             detected_transactions: tokio::sync::RwLock::new(Vec::new()),
             console,
+            screener: None,
+            watched_wallets: RwLock::new(watched_wallets),
+            ws_sender: Arc::new(Mutex::new(None)),
+            wallet_subscriptions: Mutex::new(WalletSubscriptions::default()),
+            wallet_lamports: Mutex::new(HashMap::new()),
+            next_request_id: AtomicU64::new(LOGS_SUBSCRIPTION_REQUEST_ID + 1),
         })
     }
 
+    /// Re-scan a token pair right away when a large swap on it is pending
+    pub fn with_screener(mut self, screener: Arc<Screener>) -> Self {
+        self.screener = Some(screener);
+        self
+    }
+
+    /// Follow `wallet` with an `accountSubscribe` on the open connection.
+    /// Without a connection it is subscribed on the next (re)connect.
+    pub async fn subscribe_to_wallet(&self, wallet: Pubkey) -> Result<()> {
+        if !self.watched_wallets.write().await.insert(wallet) {
+            return Ok(());
+        }
+        info!("Watching wallet {}", wallet);
+
+        if let Some(ws_sender) = self.ws_sender.lock().await.as_mut() {
+            self.send_wallet_subscription(ws_sender, wallet).await?;
+        }
+        Ok(())
+    }
+
+    async fn send_wallet_subscription(&self, ws_sender: &mut WsSink, wallet: Pubkey) -> Result<()> {
+        let request_id = self.next_request_id.fetch_add(1, Ordering::Relaxed);
+        let subscription_request = json!({
+            "jsonrpc": "2.0",
+            "id": request_id,
+            "method": "accountSubscribe",
+            "params": [
+                wallet.to_string(),
+                {
+                    "commitment": "confirmed",
+                    "encoding": "jsonParsed"
+                }
+            ]
+        });
+
+        self.wallet_subscriptions.lock().await.pending.insert(request_id, wallet);
+        ws_sender.send(Message::Text(subscription_request.to_string())).await
+            .context("Failed to send wallet subscription")
+    }
+
+    /// Send on the open connection
+    async fn send_message(&self, message: Message) -> Result<()> {
+        let mut ws_sender = self.ws_sender.lock().await;
+        let ws_sender = ws_sender.as_mut().context("WebSocket not connected")?;
+        ws_sender.send(message).await.context("Failed to send WebSocket message")
+    }
+
     pub async fn start(&self) -> Result<()> {
         if !self.config.monitoring.mempool_enabled {
             info!("Mempool monitoring disabled");
@@ -75,7 +188,7 @@ impl MempoolMonitor {
                     // Subscribe to logs for DEX program IDs
                     let subscription_request = json!({
                         "jsonrpc": "2.0",
-                        "id": 1,
+                        "id": LOGS_SUBSCRIPTION_REQUEST_ID,
                         "method": "logsSubscribe",
                         "params": [
                             {
@@ -94,6 +207,19 @@ impl MempoolMonitor {
 
                     info!("Subscribed to mempool logs");
 
+                    // Hold the sender slot while subscribing, so wallets added meanwhile wait
+                    // and are subscribed exactly once
+                    {
+                        let mut sender_slot = self.ws_sender.lock().await;
+                        let watched_wallets: Vec<Pubkey> = self.watched_wallets.read().await.iter().copied().collect();
+                        for wallet in watched_wallets {
+                            if let Err(e) = self.send_wallet_subscription(&mut ws_sender, wallet).await {
+                                warn!("Failed to subscribe to wallet {}: {}", wallet, e);
+                            }
+                        }
+                        *sender_slot = Some(ws_sender);
+                    }
+
                     // Process incoming messages with heartbeat check
                     let mut last_message_time = tokio::time::Instant::now();
                     
@@ -113,7 +239,7 @@ impl MempoolMonitor {
                                         }
                                     }
                                     Ok(Message::Ping(data)) => {
-                                        if let Err(e) = ws_sender.send(Message::Pong(data)).await {
+                                        if let Err(e) = self.send_message(Message::Pong(data)).await {
                                             error!("Failed to send Pong: {}", e);
                                             break;
                                         }
@@ -145,12 +271,14 @@ impl MempoolMonitor {
                         // Check if we've received any messages recently
                         if last_message_time.elapsed() > tokio::time::Duration::from_secs(20) {
                             warn!("No messages received for 20 seconds, sending ping");
-                            if let Err(e) = ws_sender.send(Message::Ping(vec![])).await {
+                            if let Err(e) = self.send_message(Message::Ping(vec![])).await {
                                 error!("Failed to send Ping: {}", e);
                                 break;
                             }
                         }
                     }
+                    *self.ws_sender.lock().await = None;
+                    *self.wallet_subscriptions.lock().await = WalletSubscriptions::default();
                     warn!("Mempool monitor connection lost, attempting to reconnect...");
                     self.console.update_service_status("MempoolMonitor", "Reconnecting", "Connection lost", None);
                 }
@@ -170,7 +298,31 @@ impl MempoolMonitor {
 
     async fn process_log_message(&self, message: &str) -> Result<()> {
         let parsed: Value = serde_json::from_str(message)?;
-        
+
+        // Subscription confirmations tell which wallet later notifications are about
+        if let (Some(request_id), Some(subscription_id)) = (
+            parsed.get("id").and_then(|id| id.as_u64()),
+            parsed.get("result").and_then(|result| result.as_u64()),
+        ) {
+            let mut subscriptions = self.wallet_subscriptions.lock().await;
+            if let Some(wallet) = subscriptions.pending.remove(&request_id) {
+                subscriptions.active.insert(subscription_id, wallet);
+            }
+            return Ok(());
+        }
+
+        if parsed.get("method").and_then(|method| method.as_str()) == Some("accountNotification") {
+            let subscription_id = parsed.pointer("/params/subscription").and_then(|id| id.as_u64());
+            let wallet = match subscription_id {
+                Some(id) => self.wallet_subscriptions.lock().await.active.get(&id).copied(),
+                None => None,
+            };
+            if let Some(wallet) = wallet {
+                self.analyze_wallet_activity(wallet, &parsed).await;
+            }
+            return Ok(());
+        }
+
         if let Some(params) = parsed.get("params") {
             if let Some(result) = params.get("result") {
                 if let Some(value) = result.get("value") {
@@ -191,8 +343,17 @@ impl MempoolMonitor {
             .and_then(|l| l.as_array())
             .context("No logs array")?;
 
-        // Analyze logs for swap activities
-        let mut is_swap = false;
+        // Decode the swap instruction when the notification carries the transaction.
+        // `logsSubscribe` notifications don't, so a successful watched-DEX call is fetched
+        // when there is a screener to re-scan its pair; log patterns are the fallback.
+        let decoded_swap = match Self::decode_transaction_swap(log_data) {
+            Some(swap) => Some(swap),
+            None if self.screener.is_some()
+                && log_data.get("err").is_none_or(Value::is_null)
+                && self.invokes_watched_dex(logs) => self.fetch_transaction_swap(signature).await,
+            None => None,
+        };
+        let mut is_swap = decoded_swap.is_some();
         let mut amount_info = None;
         let mut token_info = None;
 
@@ -218,14 +379,23 @@ impl MempoolMonitor {
         }
 
         if is_swap {
+            if let Some(swap) = &decoded_swap {
+                self.scan_after_large_swap(signature, swap).await;
+            }
+
             let mempool_tx = MempoolTransaction {
                 signature: signature.to_string(),
                 from_address: "unknown".to_string(), // Would need to extract from transaction
                 to_address: None,
-                amount_sol: amount_info.unwrap_or(0.0),
+                amount_sol: decoded_swap.as_ref()
+                    .map(|swap| swap.input_amount as f64 / 1_000_000_000.0)
+                    .or(amount_info)
+                    .unwrap_or(0.0),
                 token_mint: token_info,
                 program_id: self.extract_program_id(log_data)?,
                 timestamp: chrono::Utc::now(),
+                logs: logs.iter().filter_map(|log| log.as_str()).map(String::from).collect(),
+                decoded_swap,
             };
 
             self.store_detected_transaction(mempool_tx).await;
@@ -235,6 +405,178 @@ impl MempoolMonitor {
         Ok(())
     }
 
+    /// Lamport balance change of a watched wallet since its previous notification, logging
+    /// moves large enough to be a trade. A wallet's own account carries only its SOL
+    /// balance, so token-side changes are not visible here.
+    async fn analyze_wallet_activity(&self, wallet: Pubkey, notification: &Value) -> Option<i64> {
+        let lamports = notification.pointer("/params/result/value/lamports")?.as_u64()?;
+        let previous = self.wallet_lamports.lock().await.insert(wallet, lamports)?;
+        let delta = lamports as i64 - previous as i64;
+
+        if delta.abs() < WALLET_FEE_NOISE_LAMPORTS {
+            debug!("Watched wallet {} balance changed by {} lamports", wallet, delta);
+        } else if delta < 0 {
+            info!("Watched wallet {} spent {:.4} SOL, likely buying", wallet, -delta as f64 / 1_000_000_000.0);
+        } else {
+            info!("Watched wallet {} received {:.4} SOL, likely selling", wallet, delta as f64 / 1_000_000_000.0);
+        }
+        Some(delta)
+    }
+
+    /// A whale-sized pending swap is about to move its pair's price, so check that pair
+    /// for arbitrage before the next full scan would
+    async fn scan_after_large_swap(&self, signature: &str, swap: &DecodedSwap) {
+        let Some(screener) = &self.screener else {
+            return;
+        };
+        // Other inputs would need a price to compare against the SOL threshold
+        let Some(amount_sol) = swap.input_sol() else {
+            return;
+        };
+        if amount_sol <= self.config.monitoring.min_whale_transaction_sol || !swap.has_tokens() {
+            return;
+        }
+
+        match screener.scan_opportunities_for_pair(&swap.input_token, &swap.output_token).await {
+            Ok(opportunities) if !opportunities.is_empty() => info!(
+                "Large {} swap {} ({:.2} SOL) opened {} opportunities on {}/{}",
+                swap.dex, signature, amount_sol, opportunities.len(), swap.input_token, swap.output_token
+            ),
+            Ok(_) => {}
+            Err(e) => warn!("Pair scan after large swap {} failed: {}", signature, e),
+        }
+    }
+
+    /// Whether the logs show one of `get_dex_program_ids` being invoked
+    fn invokes_watched_dex(&self, logs: &[Value]) -> bool {
+        let program_ids = self.get_dex_program_ids();
+        logs.iter().filter_map(Value::as_str).any(|log| {
+            log.strip_prefix("Program ")
+                .and_then(|rest| rest.split_once(" invoke"))
+                .is_some_and(|(program_id, _)| program_ids.iter().any(|id| id == program_id))
+        })
+    }
+
+    /// Swap decoded from the confirmed transaction behind `signature`
+    async fn fetch_transaction_swap(&self, signature: &str) -> Option<DecodedSwap> {
+        match self.rpc_client.get_transaction_info(signature).await {
+            Ok(transaction) => Self::decode_transaction_swap(&transaction),
+            Err(e) => {
+                debug!("Could not fetch transaction {}: {}", signature, e);
+                None
+            }
+        }
+    }
+
+    /// Swap parameters of one DEX instruction, dispatched on the program it targets.
+    /// The mints aren't part of the instruction data and are left unset.
+    pub fn decode_swap_instruction(instruction_data: &[u8], program_id: &Pubkey) -> Option<DecodedSwap> {
+        let read_u64 = |offset: usize| {
+            instruction_data.get(offset..offset + 8).map(|bytes| u64::from_le_bytes(bytes.try_into().unwrap()))
+        };
+        let swap = |dex: &str, input_amount: u64, min_output_amount: u64| DecodedSwap {
+            input_token: Pubkey::default(),
+            output_token: Pubkey::default(),
+            input_amount,
+            min_output_amount,
+            dex: dex.to_string(),
+        };
+
+        match program_id.to_string().as_str() {
+            WHIRLPOOL_PROGRAM_ID => {
+                if instruction_data.get(..8)? != WHIRLPOOL_SWAP_DISCRIMINATOR {
+                    return None;
+                }
+                let (amount, other_amount_threshold) = (read_u64(8)?, read_u64(16)?);
+                // Byte 40 follows the u128 sqrt price limit; exact-output swaps cap the input instead
+                let amount_specified_is_input = *instruction_data.get(40)? != 0;
+                Some(if amount_specified_is_input {
+                    swap("orca", amount, other_amount_threshold)
+                } else {
+                    swap("orca", other_amount_threshold, amount)
+                })
+            }
+            RAYDIUM_AMM_PROGRAM_ID => match *instruction_data.first()? {
+                // amount_in and minimum_amount_out, or max_amount_in and amount_out
+                RAYDIUM_SWAP_BASE_IN | RAYDIUM_SWAP_BASE_OUT => Some(swap("raydium", read_u64(1)?, read_u64(9)?)),
+                _ => None,
+            },
+            PHOENIX_PROGRAM_ID => {
+                if instruction_data.first()? != &PHOENIX_SWAP || instruction_data.get(1)? != &PHOENIX_IMMEDIATE_OR_CANCEL {
+                    return None;
+                }
+                let side = *instruction_data.get(2)?;
+                // price_in_ticks is an Option<u64>: a tag byte, then the value if present
+                let lots = if *instruction_data.get(3)? == 0 { 4 } else { 12 };
+                let (base_lots, quote_lots) = (read_u64(lots)?, read_u64(lots + 8)?);
+                let (min_base_lots, min_quote_lots) = (read_u64(lots + 16)?, read_u64(lots + 24)?);
+                // Bids spend quote for base, asks the reverse
+                Some(if side == PHOENIX_SIDE_BID {
+                    swap("phoenix", quote_lots, min_base_lots)
+                } else {
+                    swap("phoenix", base_lots, min_quote_lots)
+                })
+            }
+            _ => None,
+        }
+    }
+
+    /// First decodable swap among the top-level instructions of a `getTransaction`-shaped
+    /// (`json` encoding) payload. Plain `logsSubscribe` notifications carry no transaction.
+    fn decode_transaction_swap(transaction: &Value) -> Option<DecodedSwap> {
+        let message = transaction.get("transaction")?.get("message")?;
+        let account_keys: Vec<Pubkey> = message.get("accountKeys")?.as_array()?
+            .iter()
+            .map(|key| key.as_str().and_then(|key| Pubkey::from_str(key).ok()))
+            .collect::<Option<_>>()?;
+
+        let mut swap = message.get("instructions")?.as_array()?.iter().find_map(|instruction| {
+            let program_id = account_keys.get(instruction.get("programIdIndex")?.as_u64()? as usize)?;
+            let data = bs58::decode(instruction.get("data")?.as_str()?).into_vec().ok()?;
+            Self::decode_swap_instruction(&data, program_id)
+        })?;
+
+        // The fee payer's token balances show which mint went in and which came out
+        if let Some((input_token, output_token)) = transaction.get("meta")
+            .and_then(|meta| Self::swap_mints_from_balances(meta, account_keys.first()?))
+        {
+            swap.input_token = input_token;
+            swap.output_token = output_token;
+        }
+        Some(swap)
+    }
+
+    /// Mints whose balance held by `owner` fell and rose the most across the transaction
+    fn swap_mints_from_balances(meta: &Value, owner: &Pubkey) -> Option<(Pubkey, Pubkey)> {
+        let owner = owner.to_string();
+        let balances = |key: &str| -> Vec<(u64, Pubkey, i128)> {
+            meta.get(key).and_then(Value::as_array).into_iter().flatten()
+                .filter(|balance| balance.get("owner").and_then(Value::as_str) == Some(owner.as_str()))
+                .filter_map(|balance| Some((
+                    balance.get("accountIndex")?.as_u64()?,
+                    Pubkey::from_str(balance.get("mint")?.as_str()?).ok()?,
+                    balance.get("uiTokenAmount")?.get("amount")?.as_str()?.parse().ok()?,
+                )))
+                .collect()
+        };
+
+        let (pre, post) = (balances("preTokenBalances"), balances("postTokenBalances"));
+        let amount_at = |balances: &[(u64, Pubkey, i128)], index: u64| {
+            balances.iter().find(|(i, _, _)| *i == index).map_or(0, |(_, _, amount)| *amount)
+        };
+        // Accounts opened or closed by the transaction only show up on one side
+        let changes: Vec<(Pubkey, i128)> = post.iter()
+            .map(|(index, mint, amount)| (*mint, amount - amount_at(&pre, *index)))
+            .chain(pre.iter()
+                .filter(|(index, _, _)| !post.iter().any(|(i, _, _)| i == index))
+                .map(|(_, mint, amount)| (*mint, -amount)))
+            .collect();
+
+        let (input, spent) = changes.iter().min_by_key(|(_, change)| *change)?;
+        let (output, received) = changes.iter().max_by_key(|(_, change)| *change)?;
+        (*spent < 0 && *received > 0 && input != output).then_some((*input, *output))
+    }
+
     async fn store_detected_transaction(&self, transaction: MempoolTransaction) {
         let mut transactions = self.detected_transactions.write().await;
         transactions.push(transaction);
@@ -254,6 +596,32 @@ impl MempoolMonitor {
             .collect()
     }
 
+    /// Whether a swap of the same token pair, within `SANDWICH_SIZE_RATIO` of our amount, was seen
+    /// in the last `SANDWICH_WINDOW_MS`: the front-running leg of a sandwich around our trade
+    pub async fn detect_sandwich_risk(&self, our_swap_token_in: &str, our_swap_token_out: &str, our_amount: u64) -> bool {
+        let cutoff = Utc::now() - chrono::Duration::milliseconds(SANDWICH_WINDOW_MS);
+        let transactions = self.detected_transactions.read().await;
+        transactions.iter()
+            .rev()
+            .take_while(|transaction| transaction.timestamp >= cutoff)
+            .any(|transaction| Self::is_sandwich_setup(transaction, our_swap_token_in, our_swap_token_out, our_amount))
+    }
+
+    fn is_sandwich_setup(transaction: &MempoolTransaction, token_in: &str, token_out: &str, amount: u64) -> bool {
+        let Some((their_in, their_out)) = transaction.extract_swap_tokens() else {
+            return false;
+        };
+        let same_pair = (their_in == token_in && their_out == token_out)
+            || (their_in == token_out && their_out == token_in);
+
+        // Logged amounts are stored divided by 1e9, so scale back to raw units
+        let theirs = transaction.amount_sol * 1_000_000_000.0;
+        let ours = amount as f64;
+        same_pair && theirs > 0.0 && ours > 0.0
+            && theirs <= ours * SANDWICH_SIZE_RATIO
+            && ours <= theirs * SANDWICH_SIZE_RATIO
+    }
+
     fn get_dex_program_ids(&self) -> Vec<String> {
         vec![
             "whirLbMiicVdio4qvUfM5KAg6Ct8VwpYzGff3uctyCc".to_string(), // Orca Whirlpools
@@ -291,3 +659,197 @@ impl MempoolMonitor {
         Ok("unknown".to_string()) // Placeholder
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn transaction(logs: &[String], amount_sol: f64) -> MempoolTransaction {
+        MempoolTransaction {
+            signature: "sig".to_string(),
+            from_address: "unknown".to_string(),
+            to_address: None,
+            amount_sol,
+            token_mint: None,
+            program_id: "unknown".to_string(),
+            timestamp: Utc::now(),
+            logs: logs.to_vec(),
+            decoded_swap: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_watched_wallet_notifications() {
+        let config = Config::default();
+        let rpc_client = Arc::new(RpcClient::new(&config).unwrap());
+        let monitor = MempoolMonitor::new(config, rpc_client, Arc::new(ConsoleManager::new())).unwrap();
+        let wallet = Pubkey::new_unique();
+
+        // Not connected: the wallet is only recorded, to be subscribed on connect
+        monitor.subscribe_to_wallet(wallet).await.unwrap();
+        assert!(monitor.watched_wallets.read().await.contains(&wallet));
+
+        monitor.wallet_subscriptions.lock().await.pending.insert(5, wallet);
+        monitor.process_log_message(r#"{"jsonrpc":"2.0","result":4242,"id":5}"#).await.unwrap();
+        assert_eq!(monitor.wallet_subscriptions.lock().await.active.get(&4242), Some(&wallet));
+
+        let notification = |lamports: u64| json!({
+            "jsonrpc": "2.0",
+            "method": "accountNotification",
+            "params": {"subscription": 4242, "result": {"context": {"slot": 1}, "value": {"lamports": lamports}}}
+        });
+        // The first notification only sets the baseline balance
+        assert_eq!(monitor.analyze_wallet_activity(wallet, &notification(5_000_000_000)).await, None);
+        assert_eq!(monitor.analyze_wallet_activity(wallet, &notification(3_000_000_000)).await, Some(-2_000_000_000));
+        monitor.process_log_message(&notification(3_500_000_000).to_string()).await.unwrap();
+        assert_eq!(monitor.wallet_lamports.lock().await.get(&wallet), Some(&3_500_000_000));
+    }
+
+    #[test]
+    fn test_decode_swap_instruction() {
+        let orca = Pubkey::from_str(WHIRLPOOL_PROGRAM_ID).unwrap();
+        let mut data = WHIRLPOOL_SWAP_DISCRIMINATOR.to_vec();
+        data.extend(1_000u64.to_le_bytes());
+        data.extend(990u64.to_le_bytes());
+        data.extend(0u128.to_le_bytes());
+        data.extend([1, 1]);
+        let swap = MempoolMonitor::decode_swap_instruction(&data, &orca).unwrap();
+        assert_eq!((swap.input_amount, swap.min_output_amount, swap.dex.as_str()), (1_000, 990, "orca"));
+        assert!(!swap.has_tokens());
+
+        // Exact output: the threshold is the most that can go in
+        data[40] = 0;
+        let swap = MempoolMonitor::decode_swap_instruction(&data, &orca).unwrap();
+        assert_eq!((swap.input_amount, swap.min_output_amount), (990, 1_000));
+        assert!(MempoolMonitor::decode_swap_instruction(&data[..30], &orca).is_none());
+
+        let raydium = Pubkey::from_str(RAYDIUM_AMM_PROGRAM_ID).unwrap();
+        let mut data = vec![RAYDIUM_SWAP_BASE_IN];
+        data.extend(5_000u64.to_le_bytes());
+        data.extend(4_900u64.to_le_bytes());
+        let swap = MempoolMonitor::decode_swap_instruction(&data, &raydium).unwrap();
+        assert_eq!((swap.input_amount, swap.min_output_amount), (5_000, 4_900));
+        data[0] = 3;
+        assert!(MempoolMonitor::decode_swap_instruction(&data, &raydium).is_none());
+
+        // Phoenix ask with a limit price: sells base lots for at least the quote lots
+        let phoenix = Pubkey::from_str(PHOENIX_PROGRAM_ID).unwrap();
+        let mut data = vec![PHOENIX_SWAP, PHOENIX_IMMEDIATE_OR_CANCEL, 1, 1];
+        for value in [42u64, 70, 0, 0, 65] {
+            data.extend(value.to_le_bytes());
+        }
+        let swap = MempoolMonitor::decode_swap_instruction(&data, &phoenix).unwrap();
+        assert_eq!((swap.input_amount, swap.min_output_amount), (70, 65));
+
+        assert!(MempoolMonitor::decode_swap_instruction(&data, &Pubkey::new_unique()).is_none());
+    }
+
+    #[test]
+    fn test_decode_transaction_swap_fills_mints() {
+        let (payer, sol, usdc) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let mut data = vec![RAYDIUM_SWAP_BASE_IN];
+        data.extend(2_000_000_000u64.to_le_bytes());
+        data.extend(290_000_000u64.to_le_bytes());
+        let balance = |index: u64, mint: &Pubkey, amount: u64| json!({
+            "accountIndex": index,
+            "mint": mint.to_string(),
+            "owner": payer.to_string(),
+            "uiTokenAmount": { "amount": amount.to_string() },
+        });
+
+        let payload = json!({
+            "transaction": { "message": {
+                "accountKeys": [payer.to_string(), RAYDIUM_AMM_PROGRAM_ID],
+                "instructions": [{ "programIdIndex": 1, "accounts": [], "data": bs58::encode(&data).into_string() }],
+            }},
+            "meta": {
+                // The wrapped SOL account is closed by the swap
+                "preTokenBalances": [balance(2, &sol, 2_000_000_000), balance(3, &usdc, 0)],
+                "postTokenBalances": [balance(3, &usdc, 300_000_000)],
+            },
+        });
+
+        let swap = MempoolMonitor::decode_transaction_swap(&payload).unwrap();
+        assert_eq!((swap.input_token, swap.output_token), (sol, usdc));
+        assert_eq!(swap.input_amount, 2_000_000_000);
+
+        let mut mempool_tx = transaction(&[], 2.0);
+        mempool_tx.decoded_swap = Some(swap);
+        assert_eq!(mempool_tx.extract_swap_tokens(), Some((sol.to_string(), usdc.to_string())));
+
+        assert!(MempoolMonitor::decode_transaction_swap(&json!({ "signature": "sig", "logs": [] })).is_none());
+    }
+
+    #[test]
+    fn test_invokes_watched_dex() {
+        let config = Config::default();
+        let rpc_client = Arc::new(RpcClient::new(&config).unwrap());
+        let monitor = MempoolMonitor::new(config, rpc_client, Arc::new(ConsoleManager::new())).unwrap();
+
+        let logs = |program_id: &str| vec![
+            json!("Program ComputeBudget111111111111111111111111111111 invoke [1]"),
+            json!(format!("Program {} invoke [1]", program_id)),
+            json!("Program log: Instruction: Swap"),
+        ];
+        assert!(monitor.invokes_watched_dex(&logs(RAYDIUM_AMM_PROGRAM_ID)));
+        assert!(monitor.invokes_watched_dex(&logs(WHIRLPOOL_PROGRAM_ID)));
+        assert!(!monitor.invokes_watched_dex(&logs(&Pubkey::new_unique().to_string())));
+    }
+
+    #[test]
+    fn test_whale_size_only_read_from_sol_inputs() {
+        let swap = |input_token: Pubkey, dex: &str| DecodedSwap {
+            input_token,
+            output_token: Pubkey::new_unique(),
+            input_amount: 2_000_000_000,
+            min_output_amount: 0,
+            dex: dex.to_string(),
+        };
+        let sol = spl_token::native_mint::id();
+
+        assert_eq!(swap(sol, "raydium").input_sol(), Some(2.0));
+        // 2,000 USDC is not 2 SOL, and Phoenix amounts are lots
+        assert_eq!(swap(Pubkey::new_unique(), "raydium").input_sol(), None);
+        assert_eq!(swap(sol, "phoenix").input_sol(), None);
+    }
+
+    #[test]
+    fn test_extract_swap_tokens() {
+        let (sol, usdc) = (Pubkey::new_unique().to_string(), Pubkey::new_unique().to_string());
+
+        // Labeled sides win regardless of order
+        let labeled = transaction(&[
+            "Program log: Instruction: Swap".to_string(),
+            format!("Program log: output_mint: {}, input_mint: {}", usdc, sol),
+        ], 1.0);
+        assert_eq!(labeled.extract_swap_tokens(), Some((sol.clone(), usdc.clone())));
+
+        let key_value = transaction(&[format!("Program log: mint_in={} mint_out={}", sol, usdc)], 1.0);
+        assert_eq!(key_value.extract_swap_tokens(), Some((sol.clone(), usdc.clone())));
+
+        // Unlabeled mints are taken in order; non-mint values are ignored
+        let unlabeled = transaction(&[
+            format!("Program log: mint: {}", usdc),
+            "Program log: mint: not-a-pubkey".to_string(),
+            format!("Program log: mint: {}", usdc),
+            format!("Program log: mint: {}", sol),
+        ], 1.0);
+        assert_eq!(unlabeled.extract_swap_tokens(), Some((usdc.clone(), sol.clone())));
+
+        let single = transaction(&[format!("Program log: mint: {}", sol)], 1.0);
+        assert_eq!(single.extract_swap_tokens(), None);
+    }
+
+    #[test]
+    fn test_is_sandwich_setup() {
+        let (sol, usdc, bonk) = (Pubkey::new_unique().to_string(), Pubkey::new_unique().to_string(), Pubkey::new_unique().to_string());
+        let swap = transaction(&[format!("Program log: input_mint: {} output_mint: {}", usdc, sol)], 2.0);
+
+        // Same pair in either direction, within 5x of our 1e9 raw units
+        assert!(MempoolMonitor::is_sandwich_setup(&swap, &sol, &usdc, 1_000_000_000));
+        assert!(MempoolMonitor::is_sandwich_setup(&swap, &usdc, &sol, 400_000_000));
+        assert!(!MempoolMonitor::is_sandwich_setup(&swap, &sol, &usdc, 100_000_000));
+        assert!(!MempoolMonitor::is_sandwich_setup(&swap, &sol, &usdc, 20_000_000_000));
+        assert!(!MempoolMonitor::is_sandwich_setup(&swap, &sol, &bonk, 1_000_000_000));
+    }
+}
@@ -1,24 +1,56 @@
 use crate::{
     config::Config,
-    models::WhaleActivity,
+    models::{Pool, WhaleActivity, WhaleSignal},
     types::TradeDirection,
-    utils::rpc::RpcClient,
+    utils::{math::calculate_price_impact, rpc::RpcClient},
     console::ConsoleManager,
 };
 use anyhow::{Context, Result};
-use futures_util::{SinkExt, StreamExt};
+use chrono::{DateTime, Utc};
+use futures_util::{stream::SplitSink, SinkExt, StreamExt};
+use rust_decimal::prelude::ToPrimitive;
 use serde_json::{json, Value};
 use solana_sdk::pubkey::Pubkey;
-use std::{collections::HashSet, str::FromStr, sync::Arc};
-use tokio_tungstenite::{connect_async, tungstenite::Message};
+use std::{
+    collections::{HashMap, HashSet},
+    str::FromStr,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+use tokio::{net::TcpStream, sync::{Mutex, RwLock}};
+use tokio_tungstenite::{connect_async, tungstenite::Message, MaybeTlsStream, WebSocketStream};
 use tracing::{debug, error, info, warn};
 
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+/// Request id of the `logsSubscribe` call; account subscriptions count up from the next one
+const LOGS_SUBSCRIPTION_REQUEST_ID: u64 = 2;
+
+type WsSink = SplitSink<WebSocketStream<MaybeTlsStream<TcpStream>>, Message>;
+
+/// Account subscriptions on the current WebSocket connection
+#[derive(Default)]
+struct AccountSubscriptions {
+    /// `accountSubscribe` request ids still waiting for their subscription id
+    pending: HashMap<u64, Pubkey>,
+    active: HashMap<Pubkey, u64>,
+}
+
 pub struct WhaleMonitor {
     config: Config,
     rpc_client: Arc<RpcClient>,
-    whale_addresses: HashSet<Pubkey>,
+    /// Starts from `monitoring.whale_wallet_addresses`; changed at runtime through
+    /// `add_wallet_address` and `remove_wallet_address`
+    whale_addresses: Arc<RwLock<HashSet<Pubkey>>>,
     detected_activities: tokio::sync::RwLock<Vec<WhaleActivity>>,
+    last_seen_signature: tokio::sync::RwLock<Option<String>>,
     console: Arc<ConsoleManager>,
+    /// Write half of the open WebSocket, if connected
+    ws_sender: Arc<Mutex<Option<WsSink>>>,
+    subscriptions: Mutex<AccountSubscriptions>,
+    next_request_id: AtomicU64,
 }
 
 impl WhaleMonitor {
@@ -33,58 +65,146 @@ impl WhaleMonitor {
         Ok(Self {
             config,
             rpc_client,
-            whale_addresses,
+            whale_addresses: Arc::new(RwLock::new(whale_addresses)),
             detected_activities: tokio::sync::RwLock::new(Vec::new()),
+            last_seen_signature: tokio::sync::RwLock::new(None),
             console,
+            ws_sender: Arc::new(Mutex::new(None)),
+            subscriptions: Mutex::new(AccountSubscriptions::default()),
+            next_request_id: AtomicU64::new(LOGS_SUBSCRIPTION_REQUEST_ID + 1),
         })
     }
 
+    /// Addresses currently watched
+    pub async fn wallet_addresses(&self) -> Vec<Pubkey> {
+        self.whale_addresses.read().await.iter().copied().collect()
+    }
+
+    /// Start watching `address`, subscribing on the open connection right away.
+    /// Without a connection it is subscribed on the next (re)connect.
+    pub async fn add_wallet_address(&self, address: Pubkey) -> Result<()> {
+        if !self.whale_addresses.write().await.insert(address) {
+            return Ok(());
+        }
+        info!("Watching whale {}", address);
+
+        if let Some(ws_sender) = self.ws_sender.lock().await.as_mut() {
+            self.subscribe_account(ws_sender, address).await?;
+        }
+        Ok(())
+    }
+
+    /// Stop watching `address` and drop its subscription on the open connection
+    pub async fn remove_wallet_address(&self, address: Pubkey) -> Result<()> {
+        if !self.whale_addresses.write().await.remove(&address) {
+            return Ok(());
+        }
+        info!("Stopped watching whale {}", address);
+
+        let subscription_id = {
+            let mut subscriptions = self.subscriptions.lock().await;
+            subscriptions.pending.retain(|_, pending| *pending != address);
+            subscriptions.active.remove(&address)
+        };
+        let Some(subscription_id) = subscription_id else {
+            return Ok(());
+        };
+        if let Some(ws_sender) = self.ws_sender.lock().await.as_mut() {
+            let unsubscribe_request = json!({
+                "jsonrpc": "2.0",
+                "id": self.next_request_id.fetch_add(1, Ordering::Relaxed),
+                "method": "accountUnsubscribe",
+                "params": [subscription_id]
+            });
+            ws_sender.send(Message::Text(unsubscribe_request.to_string())).await
+                .context("Failed to send whale address unsubscription")?;
+        }
+        Ok(())
+    }
+
+    async fn subscribe_account(&self, ws_sender: &mut WsSink, address: Pubkey) -> Result<()> {
+        let request_id = self.next_request_id.fetch_add(1, Ordering::Relaxed);
+        let subscription_request = json!({
+            "jsonrpc": "2.0",
+            "id": request_id,
+            "method": "accountSubscribe",
+            "params": [
+                address.to_string(),
+                {
+                    "commitment": "confirmed",
+                    "encoding": "base64"
+                }
+            ]
+        });
+
+        self.subscriptions.lock().await.pending.insert(request_id, address);
+        ws_sender.send(Message::Text(subscription_request.to_string())).await
+            .context("Failed to send whale address subscription")
+    }
+
     pub async fn start(&self) -> Result<()> {
         if !self.config.monitoring.whale_tracking_enabled {
             info!("Whale tracking disabled");
             return Ok(());
         }
 
-        if self.whale_addresses.is_empty() {
-            warn!("No whale addresses configured for monitoring");
-            return Ok(());
+        let whale_count = self.whale_addresses.read().await.len();
+        if whale_count == 0 {
+            warn!("No whale addresses configured yet, add them with POST /whale/watch/{{address}}");
         }
 
-        info!("Starting whale monitor for {} addresses", self.whale_addresses.len());
+        info!("Starting whale monitor for {} addresses", whale_count);
 
+        // Set when a connection drops and cleared once the gap has been replayed
+        let mut disconnect_time: Option<Instant> = None;
+        loop {
+            if let Err(e) = self.connect_and_monitor(&mut disconnect_time).await {
+                error!("Whale monitor connection failed: {}", e);
+            }
+            *self.ws_sender.lock().await = None;
+            *self.subscriptions.lock().await = AccountSubscriptions::default();
+
+            disconnect_time.get_or_insert_with(Instant::now);
+            warn!("Whale monitor disconnected, reconnecting in {}s", RECONNECT_DELAY.as_secs());
+            self.console.update_service_status("WhaleMonitor", "Reconnecting", "Waiting to reconnect", None);
+            tokio::time::sleep(RECONNECT_DELAY).await;
+        }
+    }
+
+    async fn connect_and_monitor(&self, disconnect_time: &mut Option<Instant>) -> Result<()> {
         self.console.update_service_status("WhaleMonitor", "Connecting", "Connecting to WebSocket", None);
         let ws_url = &self.config.rpc.solana_ws_url;
         let (ws_stream, _) = connect_async(ws_url).await
             .context("Failed to connect to Solana WebSocket")?;
 
-        self.console.update_service_status("WhaleMonitor", "Connected", &format!("Monitoring {} whale addresses", self.whale_addresses.len()), None);
-
         let (mut ws_sender, mut ws_receiver) = ws_stream.split();
 
-        // Subscribe to account changes for whale addresses
-        for whale_address in &self.whale_addresses {
-            let subscription_request = json!({
-                "jsonrpc": "2.0",
-                "id": 1,
-                "method": "accountSubscribe",
-                "params": [
-                    whale_address.to_string(),
-                    {
-                        "commitment": "confirmed",
-                        "encoding": "base64"
-                    }
-                ]
-            });
+        // Hold the sender slot while subscribing, so addresses added meanwhile wait
+        // and are subscribed exactly once
+        {
+            let mut sender_slot = self.ws_sender.lock().await;
+            let whale_addresses = self.wallet_addresses().await;
+            self.console.update_service_status("WhaleMonitor", "Connected", &format!("Monitoring {} whale addresses", whale_addresses.len()), None);
 
-            ws_sender.send(Message::Text(subscription_request.to_string())).await
-                .context("Failed to send whale address subscription")?;
-        }
+            // Subscribe to account changes for whale addresses
+            for whale_address in whale_addresses {
+                self.subscribe_account(&mut ws_sender, whale_address).await?;
+            }
 
-        // Also subscribe to signature notifications
-        self.subscribe_to_signature_notifications(&mut ws_sender).await?;
+            // Also subscribe to signature notifications
+            self.subscribe_to_signature_notifications(&mut ws_sender).await?;
+            *sender_slot = Some(ws_sender);
+        }
 
         info!("Subscribed to whale account changes");
 
+        // Catch up on anything the whales did while we were offline
+        if let Some(since) = disconnect_time.take() {
+            if let Err(e) = self.replay_missed_signatures(since).await {
+                warn!("Failed to replay missed whale signatures: {}", e);
+            }
+        }
+
         // Process incoming messages
         while let Some(message) = ws_receiver.next().await {
             match message {
@@ -107,16 +227,74 @@ impl WhaleMonitor {
             }
         }
 
-        warn!("Whale monitor stopped");
-        self.console.update_service_status("WhaleMonitor", "Stopped", "Monitor stopped", None);
         Ok(())
     }
 
-    async fn subscribe_to_signature_notifications(&self, ws_sender: &mut futures_util::stream::SplitSink<tokio_tungstenite::WebSocketStream<tokio_tungstenite::MaybeTlsStream<tokio::net::TcpStream>>, tokio_tungstenite::tungstenite::Message>) -> Result<()> {
+    /// Re-process whale transactions that landed between `disconnect_time` and now.
+    /// Returns the number of whale activities recovered.
+    pub async fn replay_missed_signatures(&self, disconnect_time: Instant) -> Result<u32> {
+        let until = self.last_seen_signature.read().await.clone();
+        let cutoff = chrono::Utc::now().timestamp() - disconnect_time.elapsed().as_secs() as i64;
+        let mut budget = self.config.monitoring.max_replay_signatures;
+        let mut replayed = 0;
+        let mut recovered = 0;
+
+        for whale_address in &self.wallet_addresses().await {
+            if budget == 0 {
+                warn!("Replay cap of {} signatures reached, older whale activity skipped",
+                      self.config.monitoring.max_replay_signatures);
+                break;
+            }
+
+            let signatures = match self.rpc_client
+                .get_signatures_for_address(whale_address, until.as_deref(), budget)
+                .await
+            {
+                Ok(signatures) => signatures,
+                Err(e) => {
+                    warn!("Failed to fetch missed signatures for whale {}: {}", whale_address, e);
+                    continue;
+                }
+            };
+
+            // Results are newest first, so stop at the first one from before the outage
+            for status in signatures {
+                if status.block_time.is_some_and(|block_time| block_time < cutoff) {
+                    break;
+                }
+                if status.err.is_some() || self.is_known_activity(&status.signature).await {
+                    continue;
+                }
+
+                budget = budget.saturating_sub(1);
+                replayed += 1;
+                match self.analyze_transaction_for_whales(&status.signature).await {
+                    Ok(Some(whale_activity)) => {
+                        self.store_whale_activity(whale_activity).await;
+                        recovered += 1;
+                    }
+                    Ok(None) => {}
+                    Err(e) => debug!("Failed to replay signature {}: {}", status.signature, e),
+                }
+            }
+        }
+
+        info!("Replayed {} signatures after {}s offline, recovered {} whale activities",
+              replayed, disconnect_time.elapsed().as_secs(), recovered);
+        Ok(recovered)
+    }
+
+    async fn is_known_activity(&self, signature: &str) -> bool {
+        self.detected_activities.read().await
+            .iter()
+            .any(|activity| activity.transaction_signature == signature)
+    }
+
+    async fn subscribe_to_signature_notifications(&self, ws_sender: &mut WsSink) -> Result<()> {
         // Subscribe to program logs that might indicate whale activity
         let subscription_request = json!({
             "jsonrpc": "2.0",
-            "id": 2,
+            "id": LOGS_SUBSCRIPTION_REQUEST_ID,
             "method": "logsSubscribe",
             "params": [
                 {
@@ -136,6 +314,18 @@ impl WhaleMonitor {
 
     async fn process_whale_message(&self, message: &str) -> Result<()> {
         let parsed: Value = serde_json::from_str(message)?;
+
+        // Subscription confirmations carry the id needed to unsubscribe later
+        if let (Some(request_id), Some(subscription_id)) = (
+            parsed.get("id").and_then(|id| id.as_u64()),
+            parsed.get("result").and_then(|result| result.as_u64()),
+        ) {
+            let mut subscriptions = self.subscriptions.lock().await;
+            if let Some(address) = subscriptions.pending.remove(&request_id) {
+                subscriptions.active.insert(address, subscription_id);
+            }
+            return Ok(());
+        }
         
         if let Some(method) = parsed.get("method") {
             match method.as_str() {
@@ -176,11 +366,11 @@ impl WhaleMonitor {
             if let Some(result) = params.get("result") {
                 if let Some(value) = result.get("value") {
                     if let Some(signature) = value.get("signature").and_then(|s| s.as_str()) {
+                        *self.last_seen_signature.write().await = Some(signature.to_string());
+
                         // Get transaction details to check if it involves whale addresses
-                        if let Ok(tx_info) = self.analyze_transaction_for_whales(signature).await {
-                            if let Some(whale_activity) = tx_info {
-                                self.store_whale_activity(whale_activity).await;
-                            }
+                        if let Ok(Some(whale_activity)) = self.analyze_transaction_for_whales(signature).await {
+                            self.store_whale_activity(whale_activity).await;
                         }
                     }
                 }
@@ -202,7 +392,7 @@ impl WhaleMonitor {
         let transaction_info = self.rpc_client.get_transaction_info(signature).await?;
         
         // Check if transaction involves any whale addresses
-        for whale_address in &self.whale_addresses {
+        for whale_address in &self.wallet_addresses().await {
             if self.transaction_involves_address(&transaction_info, whale_address) {
                 // Parse transaction to extract trading details
                 if let Some(whale_activity) = self.extract_whale_activity(&transaction_info, whale_address, signature).await? {
@@ -295,11 +485,9 @@ impl WhaleMonitor {
             .and_then(|i| i.as_array()) {
             
             for instruction in instructions {
-                if let Some(program_id_index) = instruction.get("programIdIndex").and_then(|i| i.as_u64()) {
+                if instruction.get("programIdIndex").and_then(|i| i.as_u64()).is_some() {
                     // Map program ID to DEX name (simplified)
-                    match program_id_index {
-                        _ => return Some("unknown".to_string()),
-                    }
+                    return Some("unknown".to_string());
                 }
             }
         }
@@ -325,6 +513,67 @@ impl WhaleMonitor {
             .collect()
     }
 
+    /// Signals for pools trading a token that whales bought or sold in the last `window_secs`
+    pub async fn get_recent_signals(&self, window_secs: u64, pools: &[Pool]) -> Vec<WhaleSignal> {
+        let activities = self.detected_activities.read().await;
+        Self::signals_from_activities(&activities, pools, window_secs, Utc::now())
+    }
+
+    fn signals_from_activities(
+        activities: &[WhaleActivity],
+        pools: &[Pool],
+        window_secs: u64,
+        now: DateTime<Utc>,
+    ) -> Vec<WhaleSignal> {
+        let window = window_secs as f64;
+        let mut signals = Vec::new();
+
+        for activity in activities {
+            let age = (now - activity.timestamp).num_milliseconds() as f64 / 1000.0;
+            if window <= 0.0 || age < 0.0 || age > window {
+                continue;
+            }
+            let Ok(mint) = Pubkey::from_str(&activity.token_mint) else {
+                continue;
+            };
+
+            for pool in pools.iter().filter(|pool| pool.token_a.mint == mint || pool.token_b.mint == mint) {
+                let Some(estimated_impact_percent) = Self::estimate_impact_percent(activity, pool) else {
+                    continue;
+                };
+                signals.push(WhaleSignal {
+                    pool_address: pool.address,
+                    direction: activity.direction.clone(),
+                    estimated_impact_percent,
+                    confidence: 1.0 - age / window,
+                    timestamp: activity.timestamp,
+                });
+            }
+        }
+
+        signals
+    }
+
+    /// The observed price impact if known, otherwise the impact of swapping the trade's
+    /// SOL amount through the pool's SOL side
+    fn estimate_impact_percent(activity: &WhaleActivity, pool: &Pool) -> Option<f64> {
+        if let Some(impact) = activity.price_impact {
+            return impact.to_f64().map(|impact| impact * 100.0);
+        }
+
+        let sol = spl_token::native_mint::id();
+        let (sol_reserve, other_reserve) = if pool.token_a.mint == sol {
+            (pool.reserve_a, pool.reserve_b)
+        } else if pool.token_b.mint == sol {
+            (pool.reserve_b, pool.reserve_a)
+        } else {
+            return None;
+        };
+        calculate_price_impact(activity.amount, sol_reserve, other_reserve).ok()?
+            .to_f64()
+            .map(|impact| impact * 100.0)
+    }
+
     fn get_monitored_programs(&self) -> Vec<String> {
         vec![
             "whirLbMiicVdio4qvUfM5KAg6Ct8VwpYzGff3uctyCc".to_string(), // Orca Whirlpools
@@ -333,3 +582,88 @@ impl WhaleMonitor {
         ]
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::TokenInfo;
+    use rust_decimal::Decimal;
+
+    fn pool(token_a: Pubkey, token_b: Pubkey) -> Pool {
+        let token = |mint| TokenInfo {
+            mint,
+            symbol: "TEST".to_string(),
+            decimals: 9,
+            price_usd: None,
+        };
+        Pool {
+            address: Pubkey::new_unique(),
+            dex: "orca".to_string(),
+            token_a: token(token_a),
+            token_b: token(token_b),
+            reserve_a: 100_000_000_000,
+            reserve_b: 100_000_000_000,
+            fee_percent: Decimal::ZERO,
+            liquidity_usd: Decimal::ZERO,
+            last_updated: Utc::now(),
+            metadata: None,
+        }
+    }
+
+    fn activity(token_mint: Pubkey, age_secs: i64) -> WhaleActivity {
+        WhaleActivity {
+            wallet_address: Pubkey::new_unique(),
+            transaction_signature: "sig".to_string(),
+            token_mint: token_mint.to_string(),
+            amount: 10_000_000_000,
+            direction: TradeDirection::Buy,
+            dex: "orca".to_string(),
+            timestamp: Utc::now() - chrono::Duration::seconds(age_secs),
+            price_impact: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_runtime_wallet_addresses() {
+        let config = Config::default();
+        let rpc_client = Arc::new(RpcClient::new(&config).unwrap());
+        let monitor = WhaleMonitor::new(config, rpc_client, Arc::new(ConsoleManager::new())).unwrap();
+        let whale = Pubkey::new_unique();
+
+        // Not connected: the address is only recorded, to be subscribed on connect
+        monitor.add_wallet_address(whale).await.unwrap();
+        assert_eq!(monitor.wallet_addresses().await, vec![whale]);
+
+        monitor.subscriptions.lock().await.pending.insert(7, whale);
+        monitor.process_whale_message(r#"{"jsonrpc":"2.0","result":23784,"id":7}"#).await.unwrap();
+        assert_eq!(monitor.subscriptions.lock().await.active.get(&whale), Some(&23784));
+
+        monitor.remove_wallet_address(whale).await.unwrap();
+        assert!(monitor.wallet_addresses().await.is_empty());
+        assert!(monitor.subscriptions.lock().await.active.is_empty());
+    }
+
+    #[test]
+    fn test_signals_from_activities() {
+        let (sol, bonk, usdc) = (spl_token::native_mint::id(), Pubkey::new_unique(), Pubkey::new_unique());
+        let sol_bonk = pool(sol, bonk);
+        let pools = vec![sol_bonk.clone(), pool(usdc, Pubkey::new_unique())];
+
+        let activities = vec![
+            activity(bonk, 15),
+            // Outside the window
+            activity(bonk, 120),
+            // No pool trades this token
+            activity(Pubkey::new_unique(), 0),
+        ];
+        let signals = WhaleMonitor::signals_from_activities(&activities, &pools, 60, Utc::now());
+
+        assert_eq!(signals.len(), 1);
+        let signal = &signals[0];
+        assert_eq!(signal.pool_address, sol_bonk.address);
+        assert_eq!(signal.direction, TradeDirection::Buy);
+        assert!((signal.confidence - 0.75).abs() < 0.01);
+        // 10 SOL into a 100 SOL reserve moves the price by roughly 17%
+        assert!(signal.estimated_impact_percent > 15.0 && signal.estimated_impact_percent < 20.0);
+    }
+}
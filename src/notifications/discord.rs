@@ -0,0 +1,137 @@
+use crate::models::ArbitrageOpportunity;
+use anyhow::{Context, Result};
+use chrono::Utc;
+use serde_json::{json, Value};
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+use tracing::debug;
+
+/// Minimum gap between two error alerts with the same context, so error storms don't
+/// flood the channel
+const MIN_NOTIFICATION_INTERVAL: Duration = Duration::from_secs(30);
+
+const COLOR_SUCCESS: u32 = 0x2ecc71;
+const COLOR_ERROR: u32 = 0xe74c3c;
+
+/// Posts trade and error alerts to a Discord webhook
+pub struct DiscordNotifier {
+    http_client: reqwest::Client,
+    webhook_url: String,
+    /// When an error alert last went out, per context
+    last_sent: Mutex<HashMap<String, Instant>>,
+}
+
+impl DiscordNotifier {
+    pub fn new(webhook_url: &str) -> Self {
+        Self {
+            http_client: reqwest::Client::new(),
+            webhook_url: webhook_url.to_string(),
+            last_sent: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Always sent; every confirmed trade is worth a message
+    pub async fn notify_trade_executed(&self, opportunity: &ArbitrageOpportunity, signature: &str) -> Result<()> {
+        self.send(Self::trade_payload(opportunity, signature)).await
+    }
+
+    /// Dropped if an error with the same `context` went out within the last 30 seconds
+    pub async fn notify_error(&self, context: &str, error: &str) -> Result<()> {
+        if !self.claim_slot(context) {
+            return Ok(());
+        }
+        self.send(Self::error_payload(context, error)).await
+    }
+
+    /// Always sent, for errors that stop the bot
+    pub async fn notify_fatal(&self, context: &str, error: &str) -> Result<()> {
+        self.send(Self::error_payload(context, error)).await
+    }
+
+    /// Whether an alert for `context` may go out now, recording it as sent if so.
+    /// The lock is released before posting, so a slow webhook doesn't hold up other alerts.
+    fn claim_slot(&self, context: &str) -> bool {
+        let mut last_sent = self.last_sent.lock().unwrap();
+        if let Some(sent_at) = last_sent.get(context).filter(|sent_at| sent_at.elapsed() < MIN_NOTIFICATION_INTERVAL) {
+            debug!("Skipping Discord notification for {}, last one was {:?} ago", context, sent_at.elapsed());
+            return false;
+        }
+        last_sent.insert(context.to_string(), Instant::now());
+        true
+    }
+
+    async fn send(&self, payload: Value) -> Result<()> {
+        self.http_client
+            .post(&self.webhook_url)
+            .json(&payload)
+            .timeout(Duration::from_secs(10))
+            .send()
+            .await
+            .context("Failed to send Discord notification")?
+            .error_for_status()
+            .context("Discord webhook rejected notification")?;
+
+        Ok(())
+    }
+
+    fn trade_payload(opportunity: &ArbitrageOpportunity, signature: &str) -> Value {
+        let route = &opportunity.route;
+        let dexes = route.steps.iter()
+            .map(|step| step.pool.dex.as_str())
+            .collect::<Vec<_>>()
+            .join(" → ");
+
+        json!({
+            "embeds": [{
+                "title": "Arbitrage executed",
+                "color": COLOR_SUCCESS,
+                "fields": [
+                    { "name": "Route", "value": format!("{:?}: {} → {}", route.route_type, route.from_token, route.to_token), "inline": false },
+                    { "name": "DEXes", "value": dexes, "inline": true },
+                    { "name": "Expected profit", "value": format!("{} ({:.2}%)", opportunity.expected_profit, opportunity.expected_profit_percent), "inline": true },
+                    { "name": "Signature", "value": format!("[{}](https://solscan.io/tx/{})", signature, signature), "inline": false },
+                ],
+                "timestamp": Utc::now().to_rfc3339(),
+            }]
+        })
+    }
+
+    fn error_payload(context: &str, error: &str) -> Value {
+        // Embed descriptions are capped at 4096 characters
+        let description: String = error.chars().take(4000).collect();
+
+        json!({
+            "embeds": [{
+                "title": format!("Error: {}", context),
+                "description": format!("```{}```", description),
+                "color": COLOR_ERROR,
+                "timestamp": Utc::now().to_rfc3339(),
+            }]
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_error_payload_and_rate_limit() {
+        let payload = DiscordNotifier::error_payload("Arbitrage cycle", &"x".repeat(5000));
+        let embed = &payload["embeds"][0];
+        assert_eq!(embed["title"], "Error: Arbitrage cycle");
+        assert_eq!(embed["color"], COLOR_ERROR);
+        assert_eq!(embed["description"].as_str().unwrap().len(), 4006);
+
+        // A recent error suppresses the next one with the same context, without touching the network
+        let notifier = DiscordNotifier::new("http://127.0.0.1:9/webhook");
+        assert!(notifier.claim_slot("test"));
+        assert!(notifier.notify_error("test", "boom").await.is_ok());
+        // Other contexts have their own window, and fatal alerts aren't rate limited at all
+        assert!(notifier.claim_slot("other"));
+        assert!(notifier.notify_fatal("test", "boom").await.is_err());
+    }
+}
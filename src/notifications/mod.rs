@@ -0,0 +1,3 @@
+pub mod discord;
+
+pub use discord::DiscordNotifier;
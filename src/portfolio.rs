@@ -0,0 +1,304 @@
+use crate::utils::{price_feed::JupiterPriceFeed, rpc::RpcClient};
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use solana_sdk::{account::Account, program_pack::Pack, pubkey::Pubkey};
+use spl_token::state::{Account as TokenAccount, Mint};
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{Arc, RwLock},
+    time::{Duration, Instant},
+};
+use tokio::sync::Mutex;
+use tracing::{debug, warn};
+
+/// 24 hours of snapshots at one per minute
+pub const MAX_SNAPSHOTS: usize = 1440;
+
+const LAMPORTS_PER_SOL: f64 = 1_000_000_000.0;
+const PRICE_TTL: Duration = Duration::from_secs(60);
+/// New token accounts only appear when a trade opens one, so the list is re-read rarely
+const TOKEN_ACCOUNTS_TTL: Duration = Duration::from_secs(600);
+/// `getMultipleAccounts` accepts at most this many addresses per call
+const MAX_ACCOUNTS_PER_BATCH: usize = 100;
+
+/// Wallet holdings at one point in time
+#[derive(Debug, Clone, PartialEq)]
+pub struct PortfolioSnapshot {
+    pub timestamp: DateTime<Utc>,
+    /// Native balance plus wrapped SOL
+    pub sol_lamports: u64,
+    /// Raw balances of every other SPL token held
+    pub token_balances: HashMap<Pubkey, u64>,
+    /// Token holdings valued in lamports at the snapshot's SOL price; unpriced tokens count as zero
+    pub token_value_lamports: u64,
+    pub total_value_usd: f64,
+}
+
+impl PortfolioSnapshot {
+    pub fn total_value_lamports(&self) -> u64 {
+        self.sol_lamports.saturating_add(self.token_value_lamports)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct PnlReport {
+    /// Change in the SOL balance
+    pub realized_lamports: i64,
+    /// Change in the SOL value of token holdings
+    pub unrealized_lamports: i64,
+    pub roi_percent: f64,
+    /// Mean return between snapshots over its standard deviation, scaled to one day
+    pub sharpe_ratio: f64,
+}
+
+impl PnlReport {
+    pub fn total_lamports(&self) -> i64 {
+        self.realized_lamports + self.unrealized_lamports
+    }
+
+    /// P&L from the first to the last of `snapshots`, which must be in time order
+    pub fn from_snapshots<'a>(snapshots: impl IntoIterator<Item = &'a PortfolioSnapshot>) -> Self {
+        let snapshots: Vec<_> = snapshots.into_iter().collect();
+        let (Some(first), Some(last)) = (snapshots.first(), snapshots.last()) else {
+            return Self::default();
+        };
+
+        let realized_lamports = last.sol_lamports as i64 - first.sol_lamports as i64;
+        let unrealized_lamports = last.token_value_lamports as i64 - first.token_value_lamports as i64;
+        let starting_value = first.total_value_lamports();
+        let roi_percent = if starting_value > 0 {
+            (realized_lamports + unrealized_lamports) as f64 / starting_value as f64 * 100.0
+        } else {
+            0.0
+        };
+
+        Self {
+            realized_lamports,
+            unrealized_lamports,
+            roi_percent,
+            sharpe_ratio: Self::sharpe_ratio(&snapshots),
+        }
+    }
+
+    fn sharpe_ratio(snapshots: &[&PortfolioSnapshot]) -> f64 {
+        let returns: Vec<f64> = snapshots.windows(2)
+            .filter(|pair| pair[0].total_value_lamports() > 0)
+            .map(|pair| {
+                let before = pair[0].total_value_lamports() as f64;
+                (pair[1].total_value_lamports() as f64 - before) / before
+            })
+            .collect();
+        if returns.len() < 2 {
+            return 0.0;
+        }
+
+        let n = returns.len() as f64;
+        let mean = returns.iter().sum::<f64>() / n;
+        let variance = returns.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / (n - 1.0);
+        let std_dev = variance.sqrt();
+        if std_dev == 0.0 {
+            return 0.0;
+        }
+
+        // Scale the per-interval ratio by the number of intervals in a day
+        let span = snapshots[snapshots.len() - 1].timestamp - snapshots[0].timestamp;
+        let interval_secs = span.num_seconds() as f64 / n;
+        if interval_secs <= 0.0 {
+            return 0.0;
+        }
+        mean / std_dev * (86_400.0 / interval_secs).sqrt()
+    }
+}
+
+/// Tracks the trading wallet's value over time
+pub struct Portfolio {
+    rpc_client: Arc<RpcClient>,
+    owner: Pubkey,
+    price_feed: JupiterPriceFeed,
+    prices: Mutex<(HashMap<Pubkey, f64>, Option<Instant>)>,
+    decimals: Mutex<HashMap<Pubkey, u8>>,
+    token_accounts: Mutex<(Vec<Pubkey>, Option<Instant>)>,
+    snapshots: RwLock<VecDeque<PortfolioSnapshot>>,
+    started_at: DateTime<Utc>,
+}
+
+impl Portfolio {
+    pub fn new(rpc_client: Arc<RpcClient>, keypair_pubkey: Pubkey) -> Self {
+        Self {
+            rpc_client,
+            owner: keypair_pubkey,
+            price_feed: JupiterPriceFeed::new(),
+            prices: Mutex::new((HashMap::new(), None)),
+            decimals: Mutex::new(HashMap::new()),
+            token_accounts: Mutex::new((Vec::new(), None)),
+            snapshots: RwLock::new(VecDeque::with_capacity(MAX_SNAPSHOTS)),
+            started_at: Utc::now(),
+        }
+    }
+
+    /// When tracking started; P&L since then is the session P&L
+    pub fn started_at(&self) -> DateTime<Utc> {
+        self.started_at
+    }
+
+    /// Read the wallet's SOL and token balances, value them and keep the snapshot
+    pub async fn snapshot_balance(&self) -> Result<PortfolioSnapshot> {
+        let token_accounts = self.token_accounts().await?;
+        let mut addresses = vec![self.owner];
+        addresses.extend(token_accounts);
+        let accounts = self.get_accounts(&addresses).await?;
+
+        let mut sol_lamports = accounts[0].as_ref().map_or(0, |account| account.lamports);
+        let mut token_balances: HashMap<Pubkey, u64> = HashMap::new();
+        for account in accounts.iter().skip(1).flatten() {
+            let Ok(token_account) = TokenAccount::unpack(&account.data) else {
+                continue;
+            };
+            if token_account.amount == 0 {
+                continue;
+            }
+            if token_account.mint == spl_token::native_mint::id() {
+                sol_lamports += token_account.amount;
+            } else {
+                *token_balances.entry(token_account.mint).or_default() += token_account.amount;
+            }
+        }
+
+        let mints: Vec<Pubkey> = token_balances.keys().copied().collect();
+        let decimals = self.mint_decimals(&mints).await?;
+        let prices = self.token_prices(&mints).await;
+        let sol_price = prices.get(&spl_token::native_mint::id()).copied();
+
+        let token_value_usd: f64 = token_balances.iter()
+            .filter_map(|(mint, amount)| {
+                let price = prices.get(mint)?;
+                let decimals = *decimals.get(mint)?;
+                Some(*amount as f64 / 10f64.powi(decimals as i32) * price)
+            })
+            .sum();
+        let sol_value_usd = sol_price.map_or(0.0, |price| sol_lamports as f64 / LAMPORTS_PER_SOL * price);
+        let token_value_lamports = sol_price
+            .map_or(0, |price| (token_value_usd / price * LAMPORTS_PER_SOL) as u64);
+
+        let snapshot = PortfolioSnapshot {
+            timestamp: Utc::now(),
+            sol_lamports,
+            token_balances,
+            token_value_lamports,
+            total_value_usd: sol_value_usd + token_value_usd,
+        };
+        debug!(
+            "Portfolio snapshot: {} lamports SOL, {} tokens, ${:.2}",
+            snapshot.sol_lamports, snapshot.token_balances.len(), snapshot.total_value_usd
+        );
+
+        let mut snapshots = self.snapshots.write().unwrap();
+        if snapshots.len() == MAX_SNAPSHOTS {
+            snapshots.pop_front();
+        }
+        snapshots.push_back(snapshot.clone());
+        Ok(snapshot)
+    }
+
+    /// P&L between the first snapshot at or after `since` and the latest one
+    pub fn compute_pnl(&self, since: DateTime<Utc>) -> PnlReport {
+        let snapshots = self.snapshots.read().unwrap();
+        PnlReport::from_snapshots(snapshots.iter().filter(|snapshot| snapshot.timestamp >= since))
+    }
+
+    async fn token_accounts(&self) -> Result<Vec<Pubkey>> {
+        let mut cached = self.token_accounts.lock().await;
+        if cached.1.is_some_and(|fetched_at| fetched_at.elapsed() < TOKEN_ACCOUNTS_TTL) {
+            return Ok(cached.0.clone());
+        }
+
+        cached.0 = self.rpc_client.get_token_accounts_by_owner(&self.owner).await?;
+        cached.1 = Some(Instant::now());
+        Ok(cached.0.clone())
+    }
+
+    async fn get_accounts(&self, addresses: &[Pubkey]) -> Result<Vec<Option<Account>>> {
+        let mut accounts = Vec::with_capacity(addresses.len());
+        for chunk in addresses.chunks(MAX_ACCOUNTS_PER_BATCH) {
+            accounts.extend(self.rpc_client.get_multiple_accounts(chunk).await?);
+        }
+        Ok(accounts)
+    }
+
+    async fn mint_decimals(&self, mints: &[Pubkey]) -> Result<HashMap<Pubkey, u8>> {
+        let mut decimals = self.decimals.lock().await;
+        let missing: Vec<Pubkey> = mints.iter().filter(|mint| !decimals.contains_key(mint)).copied().collect();
+
+        if !missing.is_empty() {
+            let accounts = self.get_accounts(&missing).await?;
+            for (mint, account) in missing.iter().zip(accounts) {
+                if let Some(account) = account {
+                    let state = Mint::unpack(&account.data)
+                        .with_context(|| format!("Invalid mint account {}", mint))?;
+                    decimals.insert(*mint, state.decimals);
+                }
+            }
+        }
+
+        Ok(decimals.clone())
+    }
+
+    /// USD prices for `mints` and SOL, refreshed at most once a minute
+    async fn token_prices(&self, mints: &[Pubkey]) -> HashMap<Pubkey, f64> {
+        let mut cached = self.prices.lock().await;
+        let fresh = cached.1.is_some_and(|fetched_at| fetched_at.elapsed() < PRICE_TTL);
+        if fresh && mints.iter().all(|mint| cached.0.contains_key(mint)) {
+            return cached.0.clone();
+        }
+
+        let mut ids = mints.to_vec();
+        ids.push(spl_token::native_mint::id());
+        match self.price_feed.get_prices(&ids).await {
+            Ok(prices) => {
+                cached.0.extend(prices);
+                cached.1 = Some(Instant::now());
+            }
+            Err(e) => warn!("Failed to refresh portfolio prices, using cached values: {}", e),
+        }
+        cached.0.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot(minute: i64, sol_lamports: u64, token_value_lamports: u64) -> PortfolioSnapshot {
+        PortfolioSnapshot {
+            timestamp: DateTime::from_timestamp(minute * 60, 0).unwrap(),
+            sol_lamports,
+            token_balances: HashMap::new(),
+            token_value_lamports,
+            total_value_usd: 0.0,
+        }
+    }
+
+    #[test]
+    fn test_pnl_from_snapshots() {
+        assert_eq!(PnlReport::from_snapshots(&[]), PnlReport::default());
+
+        let snapshots = [
+            snapshot(0, 10_000_000_000, 0),
+            snapshot(1, 10_100_000_000, 0),
+            snapshot(2, 10_050_000_000, 200_000_000),
+            snapshot(3, 10_200_000_000, 150_000_000),
+        ];
+        let report = PnlReport::from_snapshots(&snapshots);
+
+        assert_eq!(report.realized_lamports, 200_000_000);
+        assert_eq!(report.unrealized_lamports, 150_000_000);
+        assert_eq!(report.total_lamports(), 350_000_000);
+        assert!((report.roi_percent - 3.5).abs() < 1e-9);
+        // Positive mean return, scaled by sqrt(1440) one-minute intervals per day
+        assert!(report.sharpe_ratio > 1.0);
+
+        // A flat portfolio has no volatility to measure against
+        let flat = [snapshot(0, 1, 0), snapshot(1, 1, 0), snapshot(2, 1, 0)];
+        assert_eq!(PnlReport::from_snapshots(&flat).sharpe_ratio, 0.0);
+    }
+}
@@ -0,0 +1,4 @@
+pub mod trade_history;
+pub mod report;
+
+pub use trade_history::{ProfitDivergence, TradeHistory, TradeOutcomes, TradeRecord};
@@ -0,0 +1,249 @@
+use crate::storage::trade_history::TradeRecord;
+use anyhow::Result;
+use chrono::NaiveDate;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+const LAMPORTS_PER_SOL: f64 = 1_000_000_000.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ReportFormat {
+    Table,
+    Csv,
+    Json,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ReportStats {
+    pub trades: usize,
+    pub wins: usize,
+    pub losses: usize,
+    pub profit_sol: f64,
+    /// Converted with the last known SOL price; `None` if no trade recorded one
+    pub profit_usd: Option<f64>,
+    pub max_drawdown_sol: f64,
+    pub win_rate: f64,
+}
+
+impl ReportStats {
+    fn from_trades<'a>(trades: impl IntoIterator<Item = &'a TradeRecord>, sol_price_usd: Option<f64>) -> Self {
+        let mut stats = ReportStats {
+            trades: 0,
+            wins: 0,
+            losses: 0,
+            profit_sol: 0.0,
+            profit_usd: None,
+            max_drawdown_sol: 0.0,
+            win_rate: 0.0,
+        };
+
+        // Drawdown is measured on the cumulative profit curve
+        let mut peak = 0.0_f64;
+        for trade in trades {
+            stats.trades += 1;
+            if trade.is_win() {
+                stats.wins += 1;
+            } else {
+                stats.losses += 1;
+            }
+            stats.profit_sol += trade.actual_profit_lamports as f64 / LAMPORTS_PER_SOL;
+            peak = peak.max(stats.profit_sol);
+            stats.max_drawdown_sol = stats.max_drawdown_sol.max(peak - stats.profit_sol);
+        }
+
+        if stats.trades > 0 {
+            stats.win_rate = stats.wins as f64 / stats.trades as f64;
+        }
+        stats.profit_usd = sol_price_usd.map(|price| stats.profit_sol * price);
+        stats
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DailyReport {
+    pub date: NaiveDate,
+    #[serde(flatten)]
+    pub stats: ReportStats,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TradeReport {
+    pub summary: ReportStats,
+    pub sol_price_usd: Option<f64>,
+    pub daily: Vec<DailyReport>,
+    pub trades: Vec<TradeRecord>,
+}
+
+impl TradeReport {
+    /// Build a report from trades ordered oldest first
+    pub fn build(trades: Vec<TradeRecord>) -> Self {
+        let sol_price_usd = trades.iter().rev().find_map(|t| t.sol_price_usd);
+
+        let mut by_day: BTreeMap<NaiveDate, Vec<&TradeRecord>> = BTreeMap::new();
+        for trade in &trades {
+            by_day.entry(trade.executed_at.date_naive()).or_default().push(trade);
+        }
+
+        let daily = by_day
+            .into_iter()
+            .map(|(date, day_trades)| DailyReport {
+                date,
+                stats: ReportStats::from_trades(day_trades, sol_price_usd),
+            })
+            .collect();
+
+        Self {
+            summary: ReportStats::from_trades(&trades, sol_price_usd),
+            sol_price_usd,
+            daily,
+            trades,
+        }
+    }
+
+    pub fn render(&self, format: ReportFormat) -> Result<String> {
+        match format {
+            ReportFormat::Table => Ok(self.render_table()),
+            ReportFormat::Csv => Ok(self.render_csv()),
+            ReportFormat::Json => Ok(serde_json::to_string_pretty(self)?),
+        }
+    }
+
+    fn render_table(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(
+            out,
+            "{:<12} {:>7} {:>6} {:>7} {:>14} {:>14} {:>12} {:>9}",
+            "Date", "Trades", "Wins", "Losses", "Profit (SOL)", "Profit (USD)", "Max DD", "Win Rate"
+        );
+        let _ = writeln!(out, "{}", "-".repeat(88));
+
+        for day in &self.daily {
+            Self::write_table_row(&mut out, &day.date.to_string(), &day.stats);
+        }
+
+        let _ = writeln!(out, "{}", "-".repeat(88));
+        Self::write_table_row(&mut out, "TOTAL", &self.summary);
+        out
+    }
+
+    fn write_table_row(out: &mut String, label: &str, stats: &ReportStats) {
+        let profit_usd = stats
+            .profit_usd
+            .map(|usd| format!("{:.2}", usd))
+            .unwrap_or_else(|| "n/a".to_string());
+
+        let _ = writeln!(
+            out,
+            "{:<12} {:>7} {:>6} {:>7} {:>14.6} {:>14} {:>12.6} {:>8.1}%",
+            label,
+            stats.trades,
+            stats.wins,
+            stats.losses,
+            stats.profit_sol,
+            profit_usd,
+            stats.max_drawdown_sol,
+            stats.win_rate * 100.0
+        );
+    }
+
+    fn render_csv(&self) -> String {
+        let mut out = String::from(
+            "id,executed_at,opportunity_id,route_type,from_token,to_token,intermediate_token,dexes,\
+             input_amount,expected_output,expected_profit,expected_profit_percent,confidence_score,\
+             risk_score,signature,actual_profit_lamports,gas_cost_lamports,sol_price_usd\n",
+        );
+
+        for t in &self.trades {
+            let fields = [
+                t.id.to_string(),
+                t.executed_at.to_rfc3339(),
+                csv_escape(&t.opportunity_id),
+                csv_escape(&t.route_type),
+                csv_escape(&t.from_token),
+                csv_escape(&t.to_token),
+                csv_escape(t.intermediate_token.as_deref().unwrap_or("")),
+                csv_escape(&t.dexes),
+                t.input_amount.to_string(),
+                t.expected_output.to_string(),
+                t.expected_profit.to_string(),
+                t.expected_profit_percent.to_string(),
+                t.confidence_score.to_string(),
+                t.risk_score.to_string(),
+                csv_escape(&t.signature),
+                t.actual_profit_lamports.to_string(),
+                t.gas_cost_lamports.to_string(),
+                t.sol_price_usd.map(|p| p.to_string()).unwrap_or_default(),
+            ];
+            out.push_str(&fields.join(","));
+            out.push('\n');
+        }
+        out
+    }
+}
+
+fn csv_escape(value: &str) -> String {
+    if value.contains([',', '"', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+
+    fn trade(id: i64, day: u32, profit_lamports: i64, sol_price_usd: Option<f64>) -> TradeRecord {
+        TradeRecord {
+            id,
+            opportunity_id: format!("opp-{}", id),
+            route_type: "Direct".to_string(),
+            from_token: "SOL".to_string(),
+            to_token: "USDC".to_string(),
+            intermediate_token: None,
+            dexes: "orca,raydium".to_string(),
+            input_amount: 1_000_000_000,
+            expected_output: 1_010_000_000,
+            expected_profit: 10_000_000,
+            expected_profit_percent: 1.0,
+            confidence_score: 0.8,
+            risk_score: 0.2,
+            signature: format!("sig{}", id),
+            actual_profit_lamports: profit_lamports,
+            gas_cost_lamports: 5000,
+            sol_price_usd,
+            executed_at: Utc.with_ymd_and_hms(2024, 1, day, 12, 0, 0).unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_report_summary_and_drawdown() {
+        let report = TradeReport::build(vec![
+            trade(1, 1, 2_000_000_000, Some(100.0)),
+            trade(2, 1, -500_000_000, None),
+            trade(3, 2, -1_000_000_000, Some(120.0)),
+            trade(4, 2, 1_000_000_000, None),
+        ]);
+
+        assert_eq!(report.daily.len(), 2);
+        assert_eq!(report.summary.trades, 4);
+        assert_eq!(report.summary.wins, 2);
+        assert_eq!(report.summary.losses, 2);
+        assert!((report.summary.profit_sol - 1.5).abs() < 1e-9);
+        assert!((report.summary.max_drawdown_sol - 1.5).abs() < 1e-9);
+        assert!((report.summary.win_rate - 0.5).abs() < 1e-9);
+        // Last known price wins
+        assert_eq!(report.sol_price_usd, Some(120.0));
+        assert!((report.summary.profit_usd.unwrap() - 180.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_report_csv_has_row_per_trade() {
+        let report = TradeReport::build(vec![trade(1, 1, 1, None), trade(2, 2, -1, None)]);
+        let csv = report.render(ReportFormat::Csv).unwrap();
+        assert_eq!(csv.lines().count(), 3);
+        assert!(csv.contains("\"orca,raydium\""));
+    }
+}
@@ -0,0 +1,355 @@
+use crate::models::ArbitrageOpportunity;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection, OpenFlags, Row};
+use serde::Serialize;
+use std::path::Path;
+use tracing::debug;
+
+pub const DEFAULT_DB_PATH: &str = "trade_history.db";
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS trades (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    opportunity_id TEXT NOT NULL,
+    route_type TEXT NOT NULL,
+    from_token TEXT NOT NULL,
+    to_token TEXT NOT NULL,
+    intermediate_token TEXT,
+    dexes TEXT NOT NULL,
+    input_amount INTEGER NOT NULL,
+    expected_output INTEGER NOT NULL,
+    expected_profit INTEGER NOT NULL,
+    expected_profit_percent REAL NOT NULL,
+    confidence_score REAL NOT NULL,
+    risk_score REAL NOT NULL,
+    signature TEXT NOT NULL,
+    actual_profit_lamports INTEGER NOT NULL,
+    gas_cost_lamports INTEGER NOT NULL,
+    sol_price_usd REAL,
+    executed_at TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS idx_trades_executed_at ON trades(executed_at);
+";
+
+/// A single executed arbitrage as persisted in the trade history database
+#[derive(Debug, Clone, Serialize)]
+pub struct TradeRecord {
+    pub id: i64,
+    pub opportunity_id: String,
+    pub route_type: String,
+    pub from_token: String,
+    pub to_token: String,
+    pub intermediate_token: Option<String>,
+    pub dexes: String,
+    pub input_amount: u64,
+    pub expected_output: u64,
+    pub expected_profit: u64,
+    pub expected_profit_percent: f64,
+    pub confidence_score: f64,
+    pub risk_score: f64,
+    pub signature: String,
+    pub actual_profit_lamports: i64,
+    pub gas_cost_lamports: u64,
+    /// SOL price at execution time, if one was known
+    pub sol_price_usd: Option<f64>,
+    pub executed_at: DateTime<Utc>,
+}
+
+impl TradeRecord {
+    pub fn is_win(&self) -> bool {
+        self.actual_profit_lamports > 0
+    }
+
+    fn from_row(row: &Row) -> rusqlite::Result<Self> {
+        let executed_at: String = row.get("executed_at")?;
+        let executed_at = DateTime::parse_from_rfc3339(&executed_at)
+            .map(|dt| dt.with_timezone(&Utc))
+            .map_err(|e| rusqlite::Error::FromSqlConversionFailure(17, rusqlite::types::Type::Text, Box::new(e)))?;
+
+        Ok(Self {
+            id: row.get("id")?,
+            opportunity_id: row.get("opportunity_id")?,
+            route_type: row.get("route_type")?,
+            from_token: row.get("from_token")?,
+            to_token: row.get("to_token")?,
+            intermediate_token: row.get("intermediate_token")?,
+            dexes: row.get("dexes")?,
+            input_amount: row.get::<_, i64>("input_amount")? as u64,
+            expected_output: row.get::<_, i64>("expected_output")? as u64,
+            expected_profit: row.get::<_, i64>("expected_profit")? as u64,
+            expected_profit_percent: row.get("expected_profit_percent")?,
+            confidence_score: row.get("confidence_score")?,
+            risk_score: row.get("risk_score")?,
+            signature: row.get("signature")?,
+            actual_profit_lamports: row.get("actual_profit_lamports")?,
+            gas_cost_lamports: row.get::<_, i64>("gas_cost_lamports")? as u64,
+            sol_price_usd: row.get("sol_price_usd")?,
+            executed_at,
+        })
+    }
+}
+
+/// Realized against expected profit over a window of recent trades
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ProfitDivergence {
+    pub trades: usize,
+    pub expected_profit_lamports: i64,
+    pub realized_profit_lamports: i64,
+}
+
+impl ProfitDivergence {
+    /// How far realized profit landed from expected, as a percentage of expected
+    pub fn divergence_percent(&self) -> Option<f64> {
+        (self.expected_profit_lamports != 0).then(|| {
+            (self.realized_profit_lamports - self.expected_profit_lamports) as f64
+                / self.expected_profit_lamports.abs() as f64
+                * 100.0
+        })
+    }
+}
+
+/// Win rate and average win/loss size, relative to the traded amount, over recent trades
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TradeOutcomes {
+    pub trades: usize,
+    pub win_rate: f64,
+    /// Mean realized profit of winning trades as a fraction of their input
+    pub avg_profit_ratio: f64,
+    /// Mean realized loss of losing trades as a fraction of their input
+    pub avg_loss_ratio: f64,
+}
+
+/// SQLite-backed store of executed trades
+pub struct TradeHistory {
+    conn: Connection,
+}
+
+impl TradeHistory {
+    /// Open (creating if needed) the trade history database at `path`
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let conn = Connection::open(path)
+            .with_context(|| format!("Failed to open trade history database {}", path.display()))?;
+        Self::with_connection(conn)
+    }
+
+    /// Open an existing database without creating or migrating it
+    pub fn open_read_only(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let conn = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+            .with_context(|| format!("Failed to open trade history database {}", path.display()))?;
+        Ok(Self { conn })
+    }
+
+    pub fn in_memory() -> Result<Self> {
+        Self::with_connection(Connection::open_in_memory()?)
+    }
+
+    fn with_connection(conn: Connection) -> Result<Self> {
+        conn.execute_batch(SCHEMA)
+            .context("Failed to initialize trade history schema")?;
+        Ok(Self { conn })
+    }
+
+    /// Persist a confirmed trade with its realized outcome
+    pub fn record_trade(
+        &self,
+        opportunity: &ArbitrageOpportunity,
+        signature: &str,
+        actual_profit_lamports: i64,
+        gas_cost_lamports: u64,
+    ) -> Result<()> {
+        let route = &opportunity.route;
+        let dexes = route.steps.iter()
+            .map(|step| step.pool.dex.as_str())
+            .collect::<Vec<_>>()
+            .join(",");
+
+        self.conn.execute(
+            "INSERT INTO trades (
+                opportunity_id, route_type, from_token, to_token, intermediate_token, dexes,
+                input_amount, expected_output, expected_profit, expected_profit_percent,
+                confidence_score, risk_score, signature, actual_profit_lamports,
+                gas_cost_lamports, sol_price_usd, executed_at
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17)",
+            params![
+                opportunity.id,
+                format!("{:?}", route.route_type),
+                route.from_token,
+                route.to_token,
+                route.intermediate_token,
+                dexes,
+                opportunity.input_amount as i64,
+                opportunity.expected_output as i64,
+                opportunity.expected_profit as i64,
+                opportunity.expected_profit_percent,
+                opportunity.confidence_score,
+                opportunity.risk_score,
+                signature,
+                actual_profit_lamports,
+                gas_cost_lamports as i64,
+                None::<f64>,
+                Utc::now().to_rfc3339(),
+            ],
+        ).context("Failed to record trade")?;
+
+        debug!("Recorded trade {} ({} lamports realized)", signature, actual_profit_lamports);
+        Ok(())
+    }
+
+    /// Expected and realized profit summed over the `limit` most recent trades
+    pub fn profit_divergence(&self, limit: usize) -> Result<Option<ProfitDivergence>> {
+        let (trades, expected, realized): (i64, Option<i64>, Option<i64>) = self.conn.query_row(
+            "SELECT COUNT(*), SUM(expected_profit), SUM(actual_profit_lamports)
+             FROM (SELECT expected_profit, actual_profit_lamports FROM trades ORDER BY id DESC LIMIT ?1)",
+            params![limit as i64],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        ).context("Failed to read recent trades")?;
+
+        Ok((trades > 0).then(|| ProfitDivergence {
+            trades: trades as usize,
+            expected_profit_lamports: expected.unwrap_or_default(),
+            realized_profit_lamports: realized.unwrap_or_default(),
+        }))
+    }
+
+    /// Win rate and average profit/loss ratios of the trades executed at or after `since`
+    pub fn trade_outcomes(&self, since: DateTime<Utc>) -> Result<Option<TradeOutcomes>> {
+        let (trades, wins, avg_profit, avg_loss): (i64, Option<i64>, Option<f64>, Option<f64>) = self.conn.query_row(
+            "SELECT COUNT(*),
+                    SUM(actual_profit_lamports > 0),
+                    AVG(CASE WHEN actual_profit_lamports > 0
+                        THEN CAST(actual_profit_lamports AS REAL) / input_amount END),
+                    AVG(CASE WHEN actual_profit_lamports < 0
+                        THEN -CAST(actual_profit_lamports AS REAL) / input_amount END)
+             FROM trades WHERE executed_at >= ?1 AND input_amount > 0",
+            params![since.to_rfc3339()],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+        ).context("Failed to read trade outcomes")?;
+
+        Ok((trades > 0).then(|| TradeOutcomes {
+            trades: trades as usize,
+            win_rate: wins.unwrap_or_default() as f64 / trades as f64,
+            avg_profit_ratio: avg_profit.unwrap_or_default(),
+            avg_loss_ratio: avg_loss.unwrap_or_default(),
+        }))
+    }
+
+    /// Trades executed at or after `since`, oldest first
+    pub fn get_trades(&self, since: DateTime<Utc>, limit: usize) -> Result<Vec<TradeRecord>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT * FROM trades WHERE executed_at >= ?1 ORDER BY executed_at ASC LIMIT ?2",
+        )?;
+        let trades = stmt
+            .query_map(params![since.to_rfc3339(), limit as i64], TradeRecord::from_row)?
+            .collect::<rusqlite::Result<Vec<_>>>()
+            .context("Failed to read trade history")?;
+
+        debug!("Loaded {} trades since {}", trades.len(), since);
+        Ok(trades)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        models::{ArbitrageRoute, Pool, TokenInfo, TradeStep},
+        types::{ArbitrageType, TradeDirection},
+    };
+    use rust_decimal::Decimal;
+    use solana_sdk::pubkey::Pubkey;
+
+    fn opportunity(expected_profit: u64) -> ArbitrageOpportunity {
+        let token = TokenInfo {
+            mint: Pubkey::new_unique(),
+            symbol: "SOL".to_string(),
+            decimals: 9,
+            price_usd: None,
+        };
+        let pool = |dex: &str| Pool {
+            address: Pubkey::new_unique(),
+            dex: dex.to_string(),
+            token_a: token.clone(),
+            token_b: token.clone(),
+            reserve_a: 1_000_000,
+            reserve_b: 1_000_000,
+            fee_percent: Decimal::ZERO,
+            liquidity_usd: Decimal::ZERO,
+            last_updated: Utc::now(),
+            metadata: None,
+        };
+        let step = |dex: &str, direction| TradeStep {
+            pool: pool(dex),
+            direction,
+            input_amount: 1_000,
+            expected_output: 1_000,
+            price_impact: 0,
+            slippage: Decimal::ZERO,
+        };
+
+        ArbitrageOpportunity {
+            id: "opp".to_string(),
+            route: ArbitrageRoute {
+                route_type: ArbitrageType::CrossDex,
+                from_token: "SOL".to_string(),
+                to_token: "USDC".to_string(),
+                intermediate_token: None,
+                steps: vec![step("orca", TradeDirection::Buy), step("raydium", TradeDirection::Sell)],
+                total_fee_percent: Decimal::ZERO,
+            },
+            input_amount: 1_000_000,
+            expected_output: 1_000_000 + expected_profit,
+            expected_profit,
+            expected_profit_percent: expected_profit as f64 / 10_000.0,
+            confidence_score: 0.9,
+            risk_score: 0.1,
+            timestamp: Utc::now(),
+            expiry: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_record_trade_and_divergence() {
+        let history = TradeHistory::in_memory().unwrap();
+        assert_eq!(history.profit_divergence(10).unwrap(), None);
+
+        history.record_trade(&opportunity(5_000), "sig1", 1_000, 5_000).unwrap();
+        history.record_trade(&opportunity(10_000), "sig2", 9_000, 5_000).unwrap();
+        history.record_trade(&opportunity(10_000), "sig3", 5_000, 5_000).unwrap();
+
+        let trades = history.get_trades(Utc::now() - chrono::Duration::minutes(1), 10).unwrap();
+        assert_eq!(trades.len(), 3);
+        assert_eq!(trades[0].signature, "sig1");
+        assert_eq!(trades[0].route_type, "CrossDex");
+        assert_eq!(trades[0].dexes, "orca,raydium");
+        assert_eq!(trades[2].actual_profit_lamports, 5_000);
+
+        // Only the two most recent trades fall in the window
+        let divergence = history.profit_divergence(2).unwrap().unwrap();
+        assert_eq!(divergence.trades, 2);
+        assert_eq!(divergence.expected_profit_lamports, 20_000);
+        assert_eq!(divergence.realized_profit_lamports, 14_000);
+        assert!((divergence.divergence_percent().unwrap() + 30.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_trade_outcomes() {
+        let history = TradeHistory::in_memory().unwrap();
+        let since = Utc::now() - chrono::Duration::minutes(1);
+        assert_eq!(history.trade_outcomes(since).unwrap(), None);
+
+        // Input is 1_000_000 lamports: wins of 2% and 1%, one loss of 3%
+        history.record_trade(&opportunity(20_000), "sig1", 20_000, 5_000).unwrap();
+        history.record_trade(&opportunity(20_000), "sig2", 10_000, 5_000).unwrap();
+        history.record_trade(&opportunity(20_000), "sig3", -30_000, 5_000).unwrap();
+
+        let outcomes = history.trade_outcomes(since).unwrap().unwrap();
+        assert_eq!(outcomes.trades, 3);
+        assert!((outcomes.win_rate - 2.0 / 3.0).abs() < 1e-9);
+        assert!((outcomes.avg_profit_ratio - 0.015).abs() < 1e-9);
+        assert!((outcomes.avg_loss_ratio - 0.03).abs() < 1e-9);
+        assert_eq!(history.trade_outcomes(Utc::now() + chrono::Duration::minutes(1)).unwrap(), None);
+    }
+}
@@ -1,5 +1,7 @@
 use anyhow::Result;
-use std::sync::Arc;
+use futures_util::future::join_all;
+use serde::Serialize;
+use std::{sync::Arc, time::Instant};
 use tokio::time::{timeout, Duration};
 use tracing::{info, error, warn};
 
@@ -9,14 +11,19 @@ use crate::{
         orca::OrcaClient,
         raydium::RaydiumClient,
         phoenix::PhoenixClient,
+        jupiter::JupiterClient,
         DexClient,
+        HealthStatus,
     },
     dex_config::DexConfigs,
-    utils::rpc::RpcClient,
+    utils::{rpc::RpcClient, token_registry::TokenRegistry},
     models::Pool,
 };
 
-#[derive(Debug, Clone)]
+/// Healthy DEXes slower than this to answer are reported as degraded
+const DEGRADED_RESPONSE_MS: u64 = 5_000;
+
+#[derive(Debug, Clone, Serialize)]
 pub struct ConnectionTestResult {
     pub dex_name: String,
     pub success: bool,
@@ -25,23 +32,122 @@ pub struct ConnectionTestResult {
     pub response_time_ms: u64,
 }
 
+impl ConnectionTestResult {
+    fn from_health(dex_name: &str, health: HealthStatus) -> Self {
+        Self {
+            dex_name: dex_name.to_string(),
+            success: health.is_healthy,
+            pools_count: health.is_healthy.then_some(health.pool_count),
+            error_message: health.error,
+            response_time_ms: health.latency_ms,
+        }
+    }
+
+    /// Answered, but slowly or without any pools
+    fn is_degraded(&self) -> bool {
+        self.success && (self.response_time_ms > DEGRADED_RESPONSE_MS || self.pools_count == Some(0))
+    }
+}
+
+type CachedTestResult = (ConnectionTestResult, Vec<Pool>);
+
+/// Summary of one `parallel_health_check` across the DEXes
+#[derive(Debug, Clone, Serialize)]
+pub struct HealthReport {
+    pub results: Vec<ConnectionTestResult>,
+    pub healthy_count: usize,
+    pub degraded_count: usize,
+    pub failed_count: usize,
+    /// Pools reported by the healthy DEXes
+    pub total_pools: usize,
+    pub recommended_action: String,
+}
+
+impl HealthReport {
+    /// Summarize `results`; fewer than `min_required_dexes` answering is critical
+    pub fn new(results: Vec<ConnectionTestResult>, min_required_dexes: usize) -> Self {
+        let degraded_count = results.iter().filter(|r| r.is_degraded()).count();
+        let healthy_count = results.iter().filter(|r| r.success).count() - degraded_count;
+        let failed_count = results.len() - healthy_count - degraded_count;
+        let total_pools = results.iter()
+            .filter(|r| r.success && !r.is_degraded())
+            .filter_map(|r| r.pools_count)
+            .sum();
+
+        let recommended_action = if healthy_count + degraded_count < min_required_dexes.max(1) {
+            "Critical: restart recommended"
+        } else if degraded_count + failed_count > 0 {
+            "Reduced DEX coverage"
+        } else {
+            "All systems operational"
+        };
+
+        Self {
+            results,
+            healthy_count,
+            degraded_count,
+            failed_count,
+            total_pools,
+            recommended_action: recommended_action.to_string(),
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct DexConnectionTester {
     rpc_client: Arc<RpcClient>,
     console_manager: Arc<ConsoleManager>,
+    min_required_dexes: usize,
+    /// Clients `parallel_health_check` probes; empty means the configured DEXes
+    dex_clients: Vec<Arc<dyn DexClient>>,
 }
 
 impl DexConnectionTester {
+    /// `min_required_dexes` is how many DEXes must connect for the tests to pass
     pub fn new(
         rpc_client: Arc<RpcClient>,
         console_manager: Arc<ConsoleManager>,
+        min_required_dexes: usize,
     ) -> Self {
         Self {
             rpc_client,
             console_manager,
+            min_required_dexes,
+            dex_clients: Vec::new(),
         }
     }
 
+    /// Health check these clients rather than fresh ones for the configured DEXes
+    pub fn with_dex_clients(mut self, dex_clients: Vec<Arc<dyn DexClient>>) -> Self {
+        self.dex_clients = dex_clients;
+        self
+    }
+
+    fn panicked_result(dex_name: &str, error: tokio::task::JoinError) -> ConnectionTestResult {
+        error!("{} connection test task panicked: {:?}", dex_name, error);
+        ConnectionTestResult {
+            dex_name: dex_name.to_string(),
+            success: false,
+            pools_count: None,
+            error_message: Some(format!("Task panicked: {:?}", error)),
+            response_time_ms: 0,
+        }
+    }
+
+    /// Refuse to run degraded when too few DEXes are reachable
+    fn ensure_min_connected(&self, results: &[ConnectionTestResult]) -> Result<()> {
+        let successful = results.iter().filter(|r| r.success).count();
+        if successful < self.min_required_dexes {
+            anyhow::bail!(
+                "Only {}/{} DEX connections succeeded, at least {} required",
+                successful,
+                results.len(),
+                self.min_required_dexes
+            );
+        }
+        Ok(())
+    }
+
     /// Test all enabled DEX connections concurrently
     pub async fn test_all_connections(&self) -> Result<Vec<ConnectionTestResult>> {
         info!("Starting comprehensive DEX connection tests...");
@@ -49,7 +155,7 @@ impl DexConnectionTester {
         let dex_configs = DexConfigs::new();
         let enabled_dexs = dex_configs.get_enabled();
         
-        let mut test_tasks: Vec<tokio::task::JoinHandle<ConnectionTestResult>> = Vec::new();
+        let mut test_tasks: Vec<(String, tokio::task::JoinHandle<ConnectionTestResult>)> = Vec::new();
         
         for dex_config in enabled_dexs {
             let self_clone = self.clone();
@@ -57,23 +163,62 @@ impl DexConnectionTester {
             let task = tokio::spawn(async move {
                 self_clone.test_single_dex_connection(&dex_name).await
             });
-            test_tasks.push(task);
+            test_tasks.push((dex_config.name.clone(), task));
         }
         
         let mut results = Vec::new();
-        for task in test_tasks {
-            match task.await {
-                Ok(result) => results.push(result),
-                Err(e) => {
-                    error!("Connection test task failed: {}", e);
-                }
-            }
+        for (dex_name, task) in test_tasks {
+            let result = task.await.unwrap_or_else(|e| Self::panicked_result(&dex_name, e));
+            results.push(result);
         }
         
+        self.ensure_min_connected(&results)?;
         Ok(results)
     }
 
-    /// Test connection to a specific DEX
+    /// Health check every DEX at once, giving up on those still silent after `deadline`
+    pub async fn parallel_health_check(&self, deadline: Duration) -> Result<HealthReport> {
+        let mut clients: Vec<(String, Result<Arc<dyn DexClient>>)> = Vec::new();
+        if self.dex_clients.is_empty() {
+            for dex_config in DexConfigs::new().get_enabled() {
+                clients.push((dex_config.name.clone(), self.create_dex_client(&dex_config.name).await));
+            }
+        } else {
+            clients.extend(self.dex_clients.iter().map(|client| (client.get_dex_name().to_string(), Ok(client.clone()))));
+        }
+
+        // The probes run side by side, so each timing out at `deadline` bounds the whole check
+        let results = join_all(clients.into_iter().map(|(dex_name, client)| async move {
+            let start_time = Instant::now();
+            let health = match client {
+                Ok(client) => match timeout(deadline, client.health_check()).await {
+                    Ok(Ok(health)) => health,
+                    Ok(Err(e)) => HealthStatus::from_probe(start_time, Err(e)),
+                    Err(_) => {
+                        return ConnectionTestResult {
+                            dex_name,
+                            success: false,
+                            pools_count: None,
+                            error_message: Some("Timeout".to_string()),
+                            response_time_ms: deadline.as_millis() as u64,
+                        };
+                    }
+                },
+                Err(e) => HealthStatus::from_probe(start_time, Err(anyhow::anyhow!("Client creation failed: {}", e))),
+            };
+            ConnectionTestResult::from_health(&dex_name, health)
+        }))
+        .await;
+
+        let report = HealthReport::new(results, self.min_required_dexes);
+        info!(
+            "DEX health: {} healthy, {} degraded, {} failed, {} pools - {}",
+            report.healthy_count, report.degraded_count, report.failed_count, report.total_pools, report.recommended_action
+        );
+        Ok(report)
+    }
+
+    /// Test connection to a specific DEX through its health check
     pub async fn test_single_dex_connection(&self, dex_name: &str) -> ConnectionTestResult {
         let start_time = std::time::Instant::now();
         
@@ -85,95 +230,44 @@ impl DexConnectionTester {
             None,
         );
 
-        let client_result = self.create_dex_client(dex_name).await;
-        
-        let result = match client_result {
-            Ok(client) => {
-                // Test with 30-second timeout
-                match timeout(Duration::from_secs(30), client.fetch_pools()).await {
-                    Ok(Ok(pools)) => {
-                        let pools_count = pools.len();
-                        info!(
-                            "{} connection successful - fetched {} pools in {}ms",
-                            dex_name,
-                            pools_count,
-                            start_time.elapsed().as_millis()
-                        );
-                        
-                        self.console_manager.update_service_status(
-                            dex_name,
-                            "Connected",
-                            "Healthy",
-                            Some(format!("{} pools", pools_count)),
-                        );
-                        
-                        ConnectionTestResult {
-                            dex_name: dex_name.to_string(),
-                            success: true,
-                            pools_count: Some(pools_count),
-                            error_message: None,
-                            response_time_ms: start_time.elapsed().as_millis() as u64,
-                        }
-                    }
-                    Ok(Err(e)) => {
-                        error!("{} connection failed: {}", dex_name, e);
-                        
-                        self.console_manager.update_service_status(
-                            dex_name,
-                            "Failed",
-                            "Connection error",
-                            Some(e.to_string()),
-                        );
-                        
-                        ConnectionTestResult {
-                            dex_name: dex_name.to_string(),
-                            success: false,
-                            pools_count: None,
-                            error_message: Some(e.to_string()),
-                            response_time_ms: start_time.elapsed().as_millis() as u64,
-                        }
-                    }
-                    Err(_) => {
-                        error!("{} connection timed out after 30 seconds", dex_name);
-                        
-                        self.console_manager.update_service_status(
-                            dex_name,
-                            "Failed",
-                            "Timeout",
-                            Some("Connection timed out".to_string()),
-                        );
-                        
-                        ConnectionTestResult {
-                            dex_name: dex_name.to_string(),
-                            success: false,
-                            pools_count: None,
-                            error_message: Some("Connection timed out after 30 seconds".to_string()),
-                            response_time_ms: start_time.elapsed().as_millis() as u64,
-                        }
-                    }
-                }
-            }
-            Err(e) => {
-                error!("{} client creation failed: {}", dex_name, e);
-                
-                self.console_manager.update_service_status(
-                    dex_name,
-                    "Failed",
-                    "Initialization error",
-                    Some(e.to_string()),
-                );
-                
-                ConnectionTestResult {
-                    dex_name: dex_name.to_string(),
-                    success: false,
-                    pools_count: None,
-                    error_message: Some(format!("Client creation failed: {}", e)),
-                    response_time_ms: start_time.elapsed().as_millis() as u64,
-                }
-            }
+        let health = match self.create_dex_client(dex_name).await {
+            // Test with 30-second timeout
+            Ok(client) => match timeout(Duration::from_secs(30), client.health_check()).await {
+                Ok(Ok(health)) => health,
+                Ok(Err(e)) => HealthStatus::from_probe(start_time, Err(e)),
+                Err(_) => HealthStatus::from_probe(
+                    start_time,
+                    Err(anyhow::anyhow!("Connection timed out after 30 seconds")),
+                ),
+            },
+            Err(e) => HealthStatus::from_probe(
+                start_time,
+                Err(anyhow::anyhow!("Client creation failed: {}", e)),
+            ),
         };
-        
-        result
+
+        if health.is_healthy {
+            info!(
+                "{} connection successful - {} pools in {}ms",
+                dex_name, health.pool_count, health.latency_ms
+            );
+            self.console_manager.update_service_status(
+                dex_name,
+                "Connected",
+                "Healthy",
+                Some(format!("{} pools", health.pool_count)),
+            );
+        } else {
+            error!("{} connection failed: {}", dex_name, health.error.as_deref().unwrap_or("Unknown error"));
+            self.console_manager.update_service_status(
+                dex_name,
+                "Failed",
+                "Connection error",
+                health.error.clone(),
+            );
+        }
+
+        ConnectionTestResult::from_health(dex_name, health)
     }
 
     /// Create a DEX client instance for testing
@@ -182,15 +276,21 @@ impl DexConnectionTester {
             "Orca" => Arc::new(OrcaClient::new(
                 self.rpc_client.clone(),
                 self.console_manager.clone(),
+                Arc::new(TokenRegistry::new(self.rpc_client.clone())),
             )?),
             "Raydium" => Arc::new(RaydiumClient::new(
                 self.rpc_client.clone(),
                 self.console_manager.clone(),
+                Arc::new(TokenRegistry::new(self.rpc_client.clone())),
             )?),
             "Phoenix" => Arc::new(PhoenixClient::new(
                 self.rpc_client.clone(),
                 self.console_manager.clone(),
             )?),
+            "Jupiter" => Arc::new(JupiterClient::new(
+                self.console_manager.clone(),
+                Arc::new(TokenRegistry::new(self.rpc_client.clone())),
+            )?),
             _ => {
                 return Err(anyhow::anyhow!("Unknown DEX: {}", dex_name));
             }
@@ -206,7 +306,7 @@ impl DexConnectionTester {
     ) -> Result<(Vec<ConnectionTestResult>, Vec<Pool>)> {
         info!("Testing {} DEX clients and caching pools...", dex_clients.len());
         
-        let mut test_tasks: Vec<tokio::task::JoinHandle<Result<(ConnectionTestResult, Vec<Pool>), anyhow::Error>>> = Vec::new();
+        let mut test_tasks: Vec<tokio::task::JoinHandle<Result<CachedTestResult>>> = Vec::new();
         
         for (index, client) in dex_clients.iter().enumerate() {
             let client_clone = client.clone();
@@ -296,7 +396,7 @@ impl DexConnectionTester {
         let mut results = Vec::new();
         let mut all_pools = Vec::new();
         
-        for task in test_tasks {
+        for (index, task) in test_tasks.into_iter().enumerate() {
             match task.await {
                 Ok(Ok((result, pools))) => {
                     results.push(result);
@@ -306,7 +406,8 @@ impl DexConnectionTester {
                     error!("Connection test failed: {}", e);
                 }
                 Err(e) => {
-                    error!("Connection test task failed: {}", e);
+                    // Keep a result per client so callers can still match results to DEXes
+                    results.push(Self::panicked_result(&format!("DEX_{}", index), e));
                 }
             }
         }
@@ -319,6 +420,7 @@ impl DexConnectionTester {
             successful, total, all_pools.len()
         );
         
+        self.ensure_min_connected(&results)?;
         Ok((results, all_pools))
     }
 
@@ -400,7 +502,54 @@ mod tests {
         let rpc_client = Arc::new(RpcClient::new(&config).unwrap());
         let console_manager = Arc::new(ConsoleManager::new());
         
-        let _tester = DexConnectionTester::new(rpc_client, console_manager);
+        let _tester = DexConnectionTester::new(rpc_client, console_manager, 1);
         // Test that the tester can be created successfully
     }
+
+    #[tokio::test]
+    async fn test_min_required_dexes() {
+        let config = Config::default();
+        let rpc_client = Arc::new(RpcClient::new(&config).unwrap());
+        let console_manager = Arc::new(ConsoleManager::new());
+        let tester = DexConnectionTester::new(rpc_client, console_manager, 2);
+
+        let result = |success| ConnectionTestResult {
+            dex_name: "orca".to_string(),
+            success,
+            pools_count: None,
+            error_message: None,
+            response_time_ms: 0,
+        };
+        assert!(tester.ensure_min_connected(&[result(true), result(false)]).is_err());
+        assert!(tester.ensure_min_connected(&[result(true), result(true), result(false)]).is_ok());
+
+        let panicked = tokio::spawn(async { panic!("boom") }).await.unwrap_err();
+        let panicked = DexConnectionTester::panicked_result("raydium", panicked);
+        assert!(!panicked.success);
+        assert!(panicked.error_message.unwrap().starts_with("Task panicked"));
+    }
+
+    #[test]
+    fn test_health_report() {
+        let result = |success, pools, response_time_ms| ConnectionTestResult {
+            dex_name: "orca".to_string(),
+            success,
+            pools_count: success.then_some(pools),
+            error_message: None,
+            response_time_ms,
+        };
+
+        let report = HealthReport::new(vec![result(true, 10, 100), result(true, 20, 200)], 2);
+        assert_eq!((report.healthy_count, report.degraded_count, report.failed_count), (2, 0, 0));
+        assert_eq!(report.total_pools, 30);
+        assert_eq!(report.recommended_action, "All systems operational");
+
+        let report = HealthReport::new(vec![result(true, 10, 100), result(true, 20, 9_000), result(false, 0, 30_000)], 2);
+        assert_eq!((report.healthy_count, report.degraded_count, report.failed_count), (1, 1, 1));
+        assert_eq!(report.total_pools, 10);
+        assert_eq!(report.recommended_action, "Reduced DEX coverage");
+
+        let report = HealthReport::new(vec![result(true, 10, 100), result(false, 0, 30_000)], 2);
+        assert_eq!(report.recommended_action, "Critical: restart recommended");
+    }
 }
\ No newline at end of file
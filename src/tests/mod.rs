@@ -1,3 +1,3 @@
 pub mod connection_tests;
 
-pub use connection_tests::{DexConnectionTester, ConnectionTestResult};
\ No newline at end of file
+pub use connection_tests::{DexConnectionTester, ConnectionTestResult, HealthReport};
\ No newline at end of file
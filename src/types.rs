@@ -10,9 +10,11 @@ pub enum ArbitrageType {
     Direct,      // A -> B -> A
     Triangular,  // A -> B -> C -> A
     CrossDex,    // A -> B (DEX1), B -> A (DEX2)
+    Stable,      // A -> B -> A between stablecoin pools
+    MultiHop(u8), // A -> B -> C -> D -> ... -> A, carrying the hop count
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum TradeDirection {
     Buy,
     Sell,
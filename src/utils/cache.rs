@@ -1,7 +1,19 @@
-use crate::models::Pool;
+use crate::{metrics::MetricsCollector, models::Pool};
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use lru::LruCache;
+use ordered_float::NotNan;
+use rust_decimal::prelude::ToPrimitive;
+use serde::{Deserialize, Serialize};
+use solana_sdk::pubkey::Pubkey;
 use std::{
-    collections::HashMap,
-    sync::Arc,
+    collections::{BTreeSet, HashMap, HashSet, VecDeque},
+    num::NonZeroUsize,
+    path::Path,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
     time::{Duration, Instant},
 };
 use tokio::sync::RwLock;
@@ -26,119 +38,445 @@ impl<T> CacheEntry<T> {
     }
 }
 
+/// On-disk form of a cached pool list. `Instant`s don't survive a restart, so the
+/// expiry is stored as wall-clock time.
+#[derive(Serialize, Deserialize)]
+struct PersistedPools {
+    dex_name: String,
+    expires_at: DateTime<Utc>,
+    pools: Vec<Pool>,
+}
+
+type CacheMap<T> = Arc<RwLock<LruCache<String, CacheEntry<T>>>>;
+type ReserveHistory = VecDeque<(Instant, (u64, u64))>;
+
+/// One pool list per DEX
+pub const DEFAULT_POOL_CAPACITY: usize = 32;
+pub const DEFAULT_RESERVE_CAPACITY: usize = 10_000;
+const DEFAULT_POOL_TTL: Duration = Duration::from_secs(300);
+const DEFAULT_RESERVES_TTL: Duration = Duration::from_secs(30);
+/// Reserve snapshots kept per pool, covering at most `RESERVE_HISTORY_WINDOW`
+const RESERVE_HISTORY_LEN: usize = 10;
+const RESERVE_HISTORY_WINDOW: Duration = Duration::from_secs(30);
+/// Reserve updates are counted over this window, whatever `RESERVE_HISTORY_LEN` keeps
+const RESERVE_UPDATE_WINDOW: Duration = Duration::from_secs(60);
+/// Whirlpool configs are set by Orca governance and change very rarely
+const WHIRLPOOLS_CONFIG_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+/// Orca runs only a handful of `WhirlpoolsConfig` accounts
+const WHIRLPOOLS_CONFIG_CAPACITY: usize = 16;
+
+fn lru_map<T>(capacity: usize) -> CacheMap<T> {
+    let capacity = NonZeroUsize::new(capacity).unwrap_or(NonZeroUsize::MIN);
+    Arc::new(RwLock::new(LruCache::new(capacity)))
+}
+
+/// Insert `entry`, returning true if that pushed out the least recently used one
+fn put_entry<T>(cache: &mut LruCache<String, CacheEntry<T>>, key: &str, entry: CacheEntry<T>) -> bool {
+    let evicts = cache.len() == cache.cap().get() && !cache.contains(key);
+    cache.put(key.to_string(), entry);
+    evicts
+}
+
+/// Drop expired entries, returning their keys
+fn remove_expired<T>(cache: &mut LruCache<String, CacheEntry<T>>, kind: &str) -> Vec<String> {
+    let expired: Vec<String> = cache.iter()
+        .filter(|(_, entry)| entry.is_expired())
+        .map(|(key, _)| key.clone())
+        .collect();
+    for key in &expired {
+        debug!("Removing expired {} cache for: {}", kind, key);
+        cache.pop(key);
+    }
+    expired
+}
+
+/// Secondary index over the cached pool lists
+#[derive(Default)]
+struct PoolIndex {
+    /// Mint to the addresses of the pools trading it
+    by_token: HashMap<Pubkey, Vec<String>>,
+    /// Pool address to its DEX list and position in it
+    locations: HashMap<String, (String, usize)>,
+    /// Pool addresses ordered by USD liquidity; the address breaks ties
+    by_liquidity: BTreeSet<(NotNan<f64>, String)>,
+    /// Key each pool is filed under in `by_liquidity`
+    liquidity: HashMap<String, NotNan<f64>>,
+}
+
+impl PoolIndex {
+    fn insert_dex(&mut self, dex_name: &str, pools: &[Pool]) {
+        self.remove_dex(dex_name);
+        for (position, pool) in pools.iter().enumerate() {
+            let address = pool.address.to_string();
+            for mint in [pool.token_a.mint, pool.token_b.mint] {
+                self.by_token.entry(mint).or_default().push(address.clone());
+            }
+            self.set_liquidity(&address, pool);
+            self.locations.insert(address, (dex_name.to_string(), position));
+        }
+    }
+
+    /// File `address` under `pool`'s current USD liquidity
+    fn set_liquidity(&mut self, address: &str, pool: &Pool) {
+        let liquidity = NotNan::new(pool.liquidity_usd.to_f64().unwrap_or(0.0)).unwrap_or_default();
+        if let Some(previous) = self.liquidity.insert(address.to_string(), liquidity) {
+            self.by_liquidity.remove(&(previous, address.to_string()));
+        }
+        self.by_liquidity.insert((liquidity, address.to_string()));
+    }
+
+    fn remove_dex(&mut self, dex_name: &str) {
+        let removed: HashSet<String> = self.locations.iter()
+            .filter(|(_, (dex, _))| dex == dex_name)
+            .map(|(address, _)| address.clone())
+            .collect();
+        if removed.is_empty() {
+            return;
+        }
+        self.locations.retain(|address, _| !removed.contains(address));
+        self.liquidity.retain(|address, _| !removed.contains(address));
+        self.by_liquidity.retain(|(_, address)| !removed.contains(address));
+        self.by_token.retain(|_, addresses| {
+            addresses.retain(|address| !removed.contains(address));
+            !addresses.is_empty()
+        });
+    }
+
+    /// Where the indexed pools trading `mint` live
+    fn locations_for(&self, mint: &Pubkey) -> Vec<(String, usize)> {
+        self.by_token.get(mint)
+            .map(|addresses| addresses.iter().filter_map(|address| self.locations.get(address).cloned()).collect())
+            .unwrap_or_default()
+    }
+}
+
 pub struct PoolCache {
-    pools: Arc<RwLock<HashMap<String, CacheEntry<Vec<Pool>>>>>,
-    pool_reserves: Arc<RwLock<HashMap<String, CacheEntry<(u64, u64)>>>>,
+    pools: CacheMap<Vec<Pool>>,
+    pool_reserves: CacheMap<(u64, u64)>,
+    /// Default protocol fee rate per `WhirlpoolsConfig` address
+    whirlpools_configs: CacheMap<u16>,
+    /// Recent reserves per pool address, oldest first
+    reserve_history: Arc<RwLock<HashMap<String, ReserveHistory>>>,
+    /// When each pool's reserves were recorded over the last minute
+    reserve_updates: Arc<RwLock<HashMap<String, VecDeque<Instant>>>>,
+    token_index: Arc<RwLock<PoolIndex>>,
     default_ttl: Duration,
     reserves_ttl: Duration,
+    pool_hit_count: Arc<AtomicU64>,
+    pool_miss_count: Arc<AtomicU64>,
+    reserve_hit_count: Arc<AtomicU64>,
+    reserve_miss_count: Arc<AtomicU64>,
+    evictions: Arc<AtomicU64>,
 }
 
 impl PoolCache {
     pub fn new() -> Self {
-        Self {
-            pools: Arc::new(RwLock::new(HashMap::new())),
-            pool_reserves: Arc::new(RwLock::new(HashMap::new())),
-            default_ttl: Duration::from_secs(300), // 5 minutes for pool list
-            reserves_ttl: Duration::from_secs(30), // 30 seconds for reserves
-        }
+        Self::with_ttl(
+            DEFAULT_POOL_TTL,     // 5 minutes for pool list
+            DEFAULT_RESERVES_TTL, // 30 seconds for reserves
+        )
     }
 
     pub fn with_ttl(pool_ttl: Duration, reserves_ttl: Duration) -> Self {
+        Self::with_limits(pool_ttl, reserves_ttl, DEFAULT_POOL_CAPACITY, DEFAULT_RESERVE_CAPACITY)
+    }
+
+    /// Bound the cache to `pool_capacity` pool lists and `reserve_capacity` reserve
+    /// entries, evicting the least recently used once full
+    pub fn with_capacity(pool_capacity: usize, reserve_capacity: usize) -> Self {
+        Self::with_limits(DEFAULT_POOL_TTL, DEFAULT_RESERVES_TTL, pool_capacity, reserve_capacity)
+    }
+
+    fn with_limits(pool_ttl: Duration, reserves_ttl: Duration, pool_capacity: usize, reserve_capacity: usize) -> Self {
         Self {
-            pools: Arc::new(RwLock::new(HashMap::new())),
-            pool_reserves: Arc::new(RwLock::new(HashMap::new())),
+            pools: lru_map(pool_capacity),
+            pool_reserves: lru_map(reserve_capacity),
+            whirlpools_configs: lru_map(WHIRLPOOLS_CONFIG_CAPACITY),
+            reserve_history: Arc::new(RwLock::new(HashMap::new())),
+            reserve_updates: Arc::new(RwLock::new(HashMap::new())),
+            token_index: Arc::new(RwLock::new(PoolIndex::default())),
             default_ttl: pool_ttl,
             reserves_ttl,
+            pool_hit_count: Arc::new(AtomicU64::new(0)),
+            pool_miss_count: Arc::new(AtomicU64::new(0)),
+            reserve_hit_count: Arc::new(AtomicU64::new(0)),
+            reserve_miss_count: Arc::new(AtomicU64::new(0)),
+            evictions: Arc::new(AtomicU64::new(0)),
         }
     }
 
     pub async fn get_pools(&self, dex_name: &str) -> Option<Vec<Pool>> {
-        let pools = self.pools.read().await;
+        // A lookup marks the entry as recently used, so it needs the write lock
+        let mut pools = self.pools.write().await;
         if let Some(entry) = pools.get(dex_name) {
             if !entry.is_expired() {
                 debug!("Cache hit for {} pools", dex_name);
+                self.pool_hit_count.fetch_add(1, Ordering::Relaxed);
+                MetricsCollector::global().record_pool_cache_hit();
                 return Some(entry.data.clone());
             } else {
                 debug!("Cache expired for {} pools", dex_name);
             }
         }
+        self.pool_miss_count.fetch_add(1, Ordering::Relaxed);
+        MetricsCollector::global().record_pool_cache_miss();
         None
     }
 
     pub async fn set_pools(&self, dex_name: &str, pools: Vec<Pool>) {
+        let count = pools.len();
         let mut cache = self.pools.write().await;
-        cache.insert(
-            dex_name.to_string(),
-            CacheEntry::new(pools, self.default_ttl),
-        );
-        debug!("Cached {} pools for {}", cache.get(dex_name).unwrap().data.len(), dex_name);
+        self.token_index.write().await.insert_dex(dex_name, &pools);
+        if put_entry(&mut cache, dex_name, CacheEntry::new(pools, self.default_ttl)) {
+            self.evictions.fetch_add(1, Ordering::Relaxed);
+        }
+        debug!("Cached {} pools for {}", count, dex_name);
+    }
+
+    /// Unexpired cached pools with `token_mint` on either side
+    pub async fn get_pools_by_token(&self, token_mint: &Pubkey) -> Vec<Pool> {
+        let locations = self.token_index.read().await.locations_for(token_mint);
+        self.pools_at(&locations).await
+    }
+
+    /// Unexpired cached pools trading `mint_a` against `mint_b`, in either order
+    pub async fn get_pool_pairs_for_token_pair(&self, mint_a: &Pubkey, mint_b: &Pubkey) -> Vec<Pool> {
+        let locations = {
+            let index = self.token_index.read().await;
+            let with_b: HashSet<&String> = index.by_token.get(mint_b).into_iter().flatten().collect();
+            index.by_token.get(mint_a).into_iter().flatten()
+                .filter(|address| with_b.contains(address))
+                .filter_map(|address| index.locations.get(address).cloned())
+                .collect::<Vec<_>>()
+        };
+        self.pools_at(&locations).await
+    }
+
+    /// The `n` unexpired cached pools with the most USD liquidity, across all DEXes,
+    /// most liquid first
+    pub async fn get_most_liquid_pools(&self, n: usize) -> Vec<Pool> {
+        let index = self.token_index.read().await;
+        let pools = self.pools.read().await;
+        index.by_liquidity.iter()
+            .rev()
+            .filter_map(|(_, address)| {
+                let (dex_name, position) = index.locations.get(address)?;
+                let entry = pools.peek(dex_name).filter(|entry| !entry.is_expired())?;
+                entry.data.get(*position).cloned()
+            })
+            .take(n)
+            .collect()
+    }
+
+    /// Re-rank `pools` by their current `liquidity_usd`, e.g. once USD prices are known.
+    /// Pools that aren't cached are ignored.
+    pub async fn update_liquidity(&self, pools: &[Pool]) {
+        let mut index = self.token_index.write().await;
+        for pool in pools {
+            let address = pool.address.to_string();
+            if index.locations.contains_key(&address) {
+                index.set_liquidity(&address, pool);
+            }
+        }
+    }
+
+    /// Pools at the given list positions, skipping lists that expired or were evicted
+    async fn pools_at(&self, locations: &[(String, usize)]) -> Vec<Pool> {
+        let pools = self.pools.read().await;
+        locations.iter()
+            .filter_map(|(dex_name, position)| {
+                let entry = pools.peek(dex_name).filter(|entry| !entry.is_expired())?;
+                entry.data.get(*position).cloned()
+            })
+            .collect()
+    }
+
+    /// Write the unexpired pool lists to `path` with bincode. Reserves are left out,
+    /// since they expire long before the next start.
+    pub async fn save_to_disk(&self, path: &Path) -> Result<()> {
+        let entries: Vec<PersistedPools> = {
+            let pools = self.pools.read().await;
+            let now = Instant::now();
+            pools.iter()
+                .filter(|(_, entry)| !entry.is_expired())
+                .map(|(dex_name, entry)| PersistedPools {
+                    dex_name: dex_name.clone(),
+                    expires_at: Utc::now() + chrono::Duration::from_std(entry.expires_at - now).unwrap_or_default(),
+                    pools: entry.data.clone(),
+                })
+                .collect()
+        };
+
+        let bytes = bincode::serialize(&entries).context("Failed to serialize pool cache")?;
+        // Write then rename, so a crash mid-write never leaves a truncated cache behind
+        let temp_path = path.with_extension("tmp");
+        tokio::fs::write(&temp_path, bytes).await
+            .with_context(|| format!("Failed to write {}", temp_path.display()))?;
+        tokio::fs::rename(&temp_path, path).await
+            .with_context(|| format!("Failed to replace {}", path.display()))?;
+
+        debug!("Saved {} pool lists to {}", entries.len(), path.display());
+        Ok(())
+    }
+
+    /// Cache holding the pool lists saved by `save_to_disk`, each keeping whatever
+    /// is left of its TTL. Lists that expired while the bot was down are skipped.
+    pub fn load_from_disk(path: &Path) -> Result<Self> {
+        let bytes = std::fs::read(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let entries: Vec<PersistedPools> = bincode::deserialize(&bytes)
+            .context("Failed to deserialize pool cache")?;
+
+        let cache = Self::new();
+        {
+            let mut pools = cache.pools.try_write().expect("new cache is not shared yet");
+            let mut index = cache.token_index.try_write().expect("new cache is not shared yet");
+            for entry in entries {
+                let Ok(remaining_ttl) = (entry.expires_at - Utc::now()).to_std() else {
+                    continue;
+                };
+                index.insert_dex(&entry.dex_name, &entry.pools);
+                pools.put(entry.dex_name, CacheEntry::new(entry.pools, remaining_ttl));
+            }
+        }
+        Ok(cache)
+    }
+
+    /// Copy `other`'s unexpired pool lists into this cache, keeping their expiry.
+    /// Returns how many lists were copied.
+    pub async fn restore_pools_from(&self, other: &PoolCache) -> usize {
+        let restored: Vec<(String, CacheEntry<Vec<Pool>>)> = other.pools.read().await.iter()
+            .filter(|(_, entry)| !entry.is_expired())
+            .map(|(dex_name, entry)| (dex_name.clone(), entry.clone()))
+            .collect();
+
+        let mut pools = self.pools.write().await;
+        let mut index = self.token_index.write().await;
+        for (dex_name, entry) in &restored {
+            index.insert_dex(dex_name, &entry.data);
+            if put_entry(&mut pools, dex_name, entry.clone()) {
+                self.evictions.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+        restored.len()
     }
 
     pub async fn get_pool_reserves(&self, pool_address: &str) -> Option<(u64, u64)> {
-        let reserves = self.pool_reserves.read().await;
+        let mut reserves = self.pool_reserves.write().await;
         if let Some(entry) = reserves.get(pool_address) {
             if !entry.is_expired() {
                 debug!("Cache hit for pool reserves: {}", pool_address);
+                self.reserve_hit_count.fetch_add(1, Ordering::Relaxed);
                 return Some(entry.data);
             } else {
                 debug!("Cache expired for pool reserves: {}", pool_address);
             }
         }
+        self.reserve_miss_count.fetch_add(1, Ordering::Relaxed);
         None
     }
 
     pub async fn set_pool_reserves(&self, pool_address: &str, reserves: (u64, u64)) {
-        let mut cache = self.pool_reserves.write().await;
-        cache.insert(
-            pool_address.to_string(),
-            CacheEntry::new(reserves, self.reserves_ttl),
-        );
+        {
+            let mut cache = self.pool_reserves.write().await;
+            if put_entry(&mut cache, pool_address, CacheEntry::new(reserves, self.reserves_ttl)) {
+                self.evictions.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+        self.record_reserve_snapshot(pool_address, reserves, Instant::now()).await;
         debug!("Cached reserves for pool: {}", pool_address);
     }
 
+    /// Add a reserve reading to the pool's history, dropping readings older than 30 seconds
+    pub async fn record_reserve_snapshot(&self, pool_address: &str, reserves: (u64, u64), timestamp: Instant) {
+        {
+            let mut history = self.reserve_history.write().await;
+            let snapshots = history.entry(pool_address.to_string()).or_default();
+            snapshots.push_back((timestamp, reserves));
+            while snapshots.len() > RESERVE_HISTORY_LEN
+                || snapshots.front().is_some_and(|(taken, _)| timestamp.duration_since(*taken) > RESERVE_HISTORY_WINDOW)
+            {
+                snapshots.pop_front();
+            }
+        }
+
+        let mut updates = self.reserve_updates.write().await;
+        let times = updates.entry(pool_address.to_string()).or_default();
+        times.push_back(timestamp);
+        while times.front().is_some_and(|taken| timestamp.duration_since(*taken) > RESERVE_UPDATE_WINDOW) {
+            times.pop_front();
+        }
+    }
+
+    /// How many times the pool's reserves were recorded in the last minute
+    pub async fn reserve_update_count(&self, pool_address: &str) -> usize {
+        let updates = self.reserve_updates.read().await;
+        let now = Instant::now();
+        updates.get(pool_address).map_or(0, |times| {
+            times.iter().filter(|taken| now.duration_since(**taken) <= RESERVE_UPDATE_WINDOW).count()
+        })
+    }
+
+    /// How far, in percent and either direction, the pool's `reserve_a / reserve_b` ratio
+    /// moved over the last 30 seconds. `None` without two readings in that window.
+    pub async fn get_reserve_change_rate(&self, pool_address: &str) -> Option<f64> {
+        let history = self.reserve_history.read().await;
+        let now = Instant::now();
+        let mut recent = history.get(pool_address)?.iter()
+            .filter(|(taken, _)| now.duration_since(*taken) <= RESERVE_HISTORY_WINDOW)
+            .map(|&(_, (reserve_a, reserve_b))| (reserve_b > 0).then(|| reserve_a as f64 / reserve_b as f64));
+        let first = recent.next()??;
+        let last = recent.next_back()??;
+        if first == 0.0 {
+            return None;
+        }
+        Some((last - first).abs() / first * 100.0)
+    }
+
+    /// Cached default protocol fee rate of the `WhirlpoolsConfig` at `config_address`
+    pub async fn get_whirlpools_config(&self, config_address: &str) -> Option<u16> {
+        let mut configs = self.whirlpools_configs.write().await;
+        configs.get(config_address)
+            .filter(|entry| !entry.is_expired())
+            .map(|entry| entry.data)
+    }
+
+    pub async fn set_whirlpools_config(&self, config_address: &str, default_protocol_fee_rate: u16) {
+        let mut configs = self.whirlpools_configs.write().await;
+        put_entry(&mut configs, config_address, CacheEntry::new(default_protocol_fee_rate, WHIRLPOOLS_CONFIG_TTL));
+        debug!("Cached Whirlpools config: {}", config_address);
+    }
+
     pub async fn invalidate_pool(&self, pool_address: &str) {
         let mut reserves = self.pool_reserves.write().await;
-        reserves.remove(pool_address);
+        reserves.pop(pool_address);
         debug!("Invalidated cache for pool: {}", pool_address);
     }
 
     pub async fn invalidate_dex(&self, dex_name: &str) {
         let mut pools = self.pools.write().await;
-        pools.remove(dex_name);
+        pools.pop(dex_name);
+        self.token_index.write().await.remove_dex(dex_name);
         debug!("Invalidated cache for DEX: {}", dex_name);
     }
 
     pub async fn cleanup_expired(&self) {
-        let mut pools_removed = 0;
-        let mut reserves_removed = 0;
-
-        // Clean up expired pool lists
+        let expired_dexes = remove_expired(&mut *self.pools.write().await, "pool");
         {
-            let mut pools = self.pools.write().await;
-            pools.retain(|dex_name, entry| {
-                if entry.is_expired() {
-                    debug!("Removing expired pool cache for: {}", dex_name);
-                    pools_removed += 1;
-                    false
-                } else {
-                    true
-                }
-            });
-        }
-
-        // Clean up expired pool reserves
-        {
-            let mut reserves = self.pool_reserves.write().await;
-            reserves.retain(|pool_address, entry| {
-                if entry.is_expired() {
-                    debug!("Removing expired reserves cache for: {}", pool_address);
-                    reserves_removed += 1;
-                    false
-                } else {
-                    true
-                }
-            });
+            let mut index = self.token_index.write().await;
+            for dex_name in &expired_dexes {
+                index.remove_dex(dex_name);
+            }
         }
+        let pools_removed = expired_dexes.len();
+        let reserves_removed = remove_expired(&mut *self.pool_reserves.write().await, "reserves").len();
+        remove_expired(&mut *self.whirlpools_configs.write().await, "Whirlpools config");
+        self.reserve_history.write().await.retain(|_, snapshots| {
+            snapshots.back().is_some_and(|(taken, _)| taken.elapsed() <= RESERVE_HISTORY_WINDOW)
+        });
+        self.reserve_updates.write().await.retain(|_, times| {
+            times.back().is_some_and(|taken| taken.elapsed() <= RESERVE_UPDATE_WINDOW)
+        });
 
         if pools_removed > 0 || reserves_removed > 0 {
             debug!("Cache cleanup: removed {} pool lists, {} reserve entries", 
@@ -155,14 +493,14 @@ impl PoolCache {
         let mut reserve_entries = 0;
         let mut expired_reserve_entries = 0;
 
-        for entry in pools.values() {
+        for (_, entry) in pools.iter() {
             pool_entries += 1;
             if entry.is_expired() {
                 expired_pool_entries += 1;
             }
         }
 
-        for entry in reserves.values() {
+        for (_, entry) in reserves.iter() {
             reserve_entries += 1;
             if entry.is_expired() {
                 expired_reserve_entries += 1;
@@ -174,6 +512,11 @@ impl PoolCache {
             expired_pool_entries,
             reserve_entries,
             expired_reserve_entries,
+            pool_hit_count: self.pool_hit_count.load(Ordering::Relaxed),
+            pool_miss_count: self.pool_miss_count.load(Ordering::Relaxed),
+            reserve_hit_count: self.reserve_hit_count.load(Ordering::Relaxed),
+            reserve_miss_count: self.reserve_miss_count.load(Ordering::Relaxed),
+            evictions: self.evictions.load(Ordering::Relaxed),
         }
     }
 
@@ -190,13 +533,28 @@ impl PoolCache {
     }
 }
 
+impl Default for PoolCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Clone for PoolCache {
     fn clone(&self) -> Self {
         Self {
             pools: Arc::clone(&self.pools),
             pool_reserves: Arc::clone(&self.pool_reserves),
+            whirlpools_configs: Arc::clone(&self.whirlpools_configs),
+            reserve_history: Arc::clone(&self.reserve_history),
+            reserve_updates: Arc::clone(&self.reserve_updates),
+            token_index: Arc::clone(&self.token_index),
             default_ttl: self.default_ttl,
             reserves_ttl: self.reserves_ttl,
+            pool_hit_count: Arc::clone(&self.pool_hit_count),
+            pool_miss_count: Arc::clone(&self.pool_miss_count),
+            reserve_hit_count: Arc::clone(&self.reserve_hit_count),
+            reserve_miss_count: Arc::clone(&self.reserve_miss_count),
+            evictions: Arc::clone(&self.evictions),
         }
     }
 }
@@ -207,17 +565,24 @@ pub struct CacheStats {
     pub expired_pool_entries: usize,
     pub reserve_entries: usize,
     pub expired_reserve_entries: usize,
+    pub pool_hit_count: u64,
+    pub pool_miss_count: u64,
+    pub reserve_hit_count: u64,
+    pub reserve_miss_count: u64,
+    /// Entries pushed out because the cache was full
+    pub evictions: u64,
 }
 
 impl CacheStats {
+    /// Fraction of pool and reserve lookups served from the cache
     pub fn hit_rate(&self) -> f64 {
-        let total_entries = self.pool_entries + self.reserve_entries;
-        let valid_entries = total_entries - self.expired_pool_entries - self.expired_reserve_entries;
+        let hits = self.pool_hit_count + self.reserve_hit_count;
+        let lookups = hits + self.pool_miss_count + self.reserve_miss_count;
         
-        if total_entries == 0 {
+        if lookups == 0 {
             0.0
         } else {
-            valid_entries as f64 / total_entries as f64
+            hits as f64 / lookups as f64
         }
     }
 }
@@ -250,6 +615,7 @@ mod tests {
             liquidity_usd: Decimal::from(10000),
             fee_percent: Decimal::from_f64_retain(0.003).unwrap(),
             last_updated: chrono::Utc::now(),
+            metadata: None,
         }
     }
 
@@ -284,6 +650,50 @@ mod tests {
         assert_eq!(cached_reserves.unwrap(), reserves);
     }
 
+    #[tokio::test]
+    async fn test_reserve_change_rate() {
+        let cache = PoolCache::new();
+        let now = Instant::now();
+        assert!(cache.get_reserve_change_rate("pool").await.is_none());
+
+        // Too old to count
+        cache.record_reserve_snapshot("pool", (1_000, 1_000), now - Duration::from_secs(60)).await;
+        cache.record_reserve_snapshot("pool", (1_000, 1_000), now - Duration::from_secs(20)).await;
+        assert!(cache.get_reserve_change_rate("pool").await.is_none());
+
+        cache.record_reserve_snapshot("pool", (1_100, 1_000), now).await;
+        let rate = cache.get_reserve_change_rate("pool").await.unwrap();
+        assert!((rate - 10.0).abs() < 1e-9, "{}", rate);
+
+        // Only the latest readings are kept
+        for _ in 0..RESERVE_HISTORY_LEN {
+            cache.record_reserve_snapshot("pool", (1_100, 1_000), now).await;
+        }
+        assert_eq!(cache.get_reserve_change_rate("pool").await, Some(0.0));
+    }
+
+    #[tokio::test]
+    async fn test_reserve_update_count_covers_last_minute() {
+        let cache = PoolCache::new();
+        let now = Instant::now();
+        cache.record_reserve_snapshot("pool", (1_000, 1_000), now - Duration::from_secs(90)).await;
+        for _ in 0..(RESERVE_HISTORY_LEN * 2) {
+            cache.record_reserve_snapshot("pool", (1_000, 1_000), now).await;
+        }
+
+        // Counted beyond the history length, but not past the one minute window
+        assert_eq!(cache.reserve_update_count("pool").await, RESERVE_HISTORY_LEN * 2);
+        assert_eq!(cache.reserve_update_count("other").await, 0);
+    }
+
+    #[tokio::test]
+    async fn test_whirlpools_config_cache() {
+        let cache = PoolCache::new();
+        assert!(cache.get_whirlpools_config("config").await.is_none());
+        cache.set_whirlpools_config("config", 300).await;
+        assert_eq!(cache.get_whirlpools_config("config").await, Some(300));
+    }
+
     #[tokio::test]
     async fn test_cache_expiration() {
         let cache = PoolCache::with_ttl(
@@ -318,4 +728,157 @@ mod tests {
         assert_eq!(stats.expired_pool_entries, 0);
         assert_eq!(stats.expired_reserve_entries, 0);
     }
-}
\ No newline at end of file
+
+    #[tokio::test]
+    async fn test_least_recently_used_entries_evicted() {
+        let cache = PoolCache::with_capacity(2, 2);
+        cache.set_pools("orca", vec![create_test_pool()]).await;
+        cache.set_pools("raydium", vec![create_test_pool()]).await;
+        // Reading orca makes raydium the least recently used list
+        assert!(cache.get_pools("orca").await.is_some());
+        cache.set_pools("phoenix", vec![create_test_pool()]).await;
+
+        assert!(cache.get_pools("raydium").await.is_none());
+        assert!(cache.get_pools("orca").await.is_some());
+        assert!(cache.get_pools("phoenix").await.is_some());
+
+        // Overwriting an existing key doesn't evict anything
+        cache.set_pool_reserves("pool_a", (1, 2)).await;
+        cache.set_pool_reserves("pool_a", (3, 4)).await;
+        cache.set_pool_reserves("pool_b", (5, 6)).await;
+        cache.set_pool_reserves("pool_c", (7, 8)).await;
+        assert!(cache.get_pool_reserves("pool_a").await.is_none());
+
+        let stats = cache.get_cache_stats().await;
+        assert_eq!(stats.pool_entries, 2);
+        assert_eq!(stats.reserve_entries, 2);
+        assert_eq!(stats.evictions, 2);
+    }
+
+    #[tokio::test]
+    async fn test_cache_survives_restart() {
+        let path = std::env::temp_dir().join(format!("pool_cache_{}.bin", uuid::Uuid::new_v4()));
+        let cache = PoolCache::with_ttl(Duration::from_secs(300), Duration::from_secs(30));
+        let mut pool = create_test_pool();
+        pool.metadata = Some(Arc::new(7u64));
+        cache.set_pools("orca", vec![pool.clone()]).await;
+        cache.set_pool_reserves("test_pool", (1, 2)).await;
+        cache.save_to_disk(&path).await.unwrap();
+
+        let loaded = PoolCache::load_from_disk(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let restored = PoolCache::with_capacity(4, 4);
+        assert_eq!(restored.restore_pools_from(&loaded).await, 1);
+
+        let pools = restored.get_pools("orca").await.unwrap();
+        assert_eq!(pools[0].address, pool.address);
+        assert_eq!(pools[0].token_b.symbol, "TESTB");
+        assert_eq!(pools[0].fee_percent, pool.fee_percent);
+        assert!(pools[0].metadata.is_none());
+        assert!(restored.get_pool_reserves("test_pool").await.is_none());
+
+        // The remaining TTL carries over rather than restarting
+        let remaining = restored.pools.read().await.peek("orca").unwrap().expires_at - Instant::now();
+        assert!(remaining <= Duration::from_secs(300) && remaining > Duration::from_secs(290));
+
+        assert!(PoolCache::load_from_disk(&path).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_cache_hit_rate_counts_lookups() {
+        let cache = PoolCache::new();
+        assert_eq!(cache.get_cache_stats().await.hit_rate(), 0.0);
+
+        assert!(cache.get_pools("test_dex").await.is_none());
+        cache.set_pools("test_dex", vec![create_test_pool()]).await;
+        assert!(cache.get_pools("test_dex").await.is_some());
+        assert!(cache.get_pools("test_dex").await.is_some());
+        assert!(cache.get_pool_reserves("test_pool").await.is_none());
+
+        let stats = cache.get_cache_stats().await;
+        assert_eq!(stats.pool_hit_count, 2);
+        assert_eq!(stats.pool_miss_count, 1);
+        assert_eq!(stats.reserve_hit_count, 0);
+        assert_eq!(stats.reserve_miss_count, 1);
+        assert!((stats.hit_rate() - 0.5).abs() < f64::EPSILON);
+    }
+
+    #[tokio::test]
+    async fn test_token_index_follows_set_pools() {
+        let cache = PoolCache::new();
+        let orca_pool = create_test_pool();
+        let mut raydium_pool = create_test_pool();
+        raydium_pool.token_b.mint = orca_pool.token_a.mint;
+        let sol = orca_pool.token_a.mint;
+
+        cache.set_pools("orca", vec![orca_pool.clone()]).await;
+        cache.set_pools("raydium", vec![raydium_pool.clone()]).await;
+
+        let addresses = |pools: Vec<Pool>| pools.iter().map(|pool| pool.address).collect::<HashSet<_>>();
+        assert_eq!(addresses(cache.get_pools_by_token(&sol).await), HashSet::from([orca_pool.address, raydium_pool.address]));
+        assert_eq!(addresses(cache.get_pools_by_token(&orca_pool.token_b.mint).await), HashSet::from([orca_pool.address]));
+        assert!(cache.get_pools_by_token(&Pubkey::new_unique()).await.is_empty());
+
+        // Either mint order finds the pair
+        let pair = cache.get_pool_pairs_for_token_pair(&orca_pool.token_b.mint, &sol).await;
+        assert_eq!(addresses(pair), HashSet::from([orca_pool.address]));
+        assert!(cache.get_pool_pairs_for_token_pair(&orca_pool.token_b.mint, &raydium_pool.token_a.mint).await.is_empty());
+
+        // Replacing a DEX's list drops its old pools from the index
+        let replacement = create_test_pool();
+        cache.set_pools("orca", vec![replacement.clone()]).await;
+        assert_eq!(addresses(cache.get_pools_by_token(&sol).await), HashSet::from([raydium_pool.address]));
+        assert_eq!(addresses(cache.get_pools_by_token(&replacement.token_a.mint).await), HashSet::from([replacement.address]));
+    }
+
+    #[tokio::test]
+    async fn test_invalidation_clears_token_index() {
+        let cache = PoolCache::with_ttl(Duration::from_millis(50), Duration::from_secs(30));
+        let orca_pool = create_test_pool();
+        let raydium_pool = create_test_pool();
+        cache.set_pools("orca", vec![orca_pool.clone()]).await;
+        cache.set_pools("raydium", vec![raydium_pool.clone()]).await;
+
+        cache.invalidate_dex("orca").await;
+        assert!(cache.get_pools_by_token(&orca_pool.token_a.mint).await.is_empty());
+        assert!(!cache.token_index.read().await.by_token.contains_key(&orca_pool.token_a.mint));
+        assert_eq!(cache.get_pools_by_token(&raydium_pool.token_a.mint).await.len(), 1);
+
+        // Expired lists stop matching at once and leave the index on cleanup
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert!(cache.get_pools_by_token(&raydium_pool.token_a.mint).await.is_empty());
+        cache.cleanup_expired().await;
+        let index = cache.token_index.read().await;
+        assert!(index.by_token.is_empty());
+        assert!(index.locations.is_empty());
+        assert!(index.by_liquidity.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_most_liquid_pools() {
+        let cache = PoolCache::new();
+        let pool_with_liquidity = |liquidity_usd: i64| {
+            let mut pool = create_test_pool();
+            pool.liquidity_usd = Decimal::from(liquidity_usd);
+            pool
+        };
+        let (small, large, medium) = (pool_with_liquidity(1_000), pool_with_liquidity(50_000), pool_with_liquidity(10_000));
+        // Equal liquidity must not hide either pool
+        let tied = pool_with_liquidity(1_000);
+        cache.set_pools("orca", vec![small.clone(), large.clone()]).await;
+        cache.set_pools("raydium", vec![medium.clone(), tied.clone()]).await;
+
+        let addresses = |pools: Vec<Pool>| pools.iter().map(|pool| pool.address).collect::<Vec<_>>();
+        assert_eq!(addresses(cache.get_most_liquid_pools(2).await), vec![large.address, medium.address]);
+        assert_eq!(cache.get_most_liquid_pools(10).await.len(), 4);
+
+        // Re-ranking with fresh prices moves a pool up
+        let mut repriced = small.clone();
+        repriced.liquidity_usd = Decimal::from(100_000);
+        cache.update_liquidity(&[repriced]).await;
+        assert_eq!(addresses(cache.get_most_liquid_pools(1).await), vec![small.address]);
+
+        cache.invalidate_dex("orca").await;
+        assert_eq!(addresses(cache.get_most_liquid_pools(10).await).len(), 2);
+    }
+}
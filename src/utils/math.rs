@@ -1,7 +1,14 @@
+use crate::models::Pool;
 use anyhow::Result;
 use rust_decimal::Decimal;
 use rust_decimal::prelude::ToPrimitive;
 
+/// Smallest input `calculate_triangular_optimal_amount` considers
+const TRIANGULAR_MIN_INPUT: u64 = 10_000;
+const TRIANGULAR_SEARCH_ITERATIONS: usize = 50;
+/// The search stops once the interval is narrower than this many lamports
+const TRIANGULAR_SEARCH_TOLERANCE: u64 = 1_000;
+
 /// Calculate output amount for a constant product AMM swap
 /// Uses the formula: output = (input * output_reserve) / (input_reserve + input)
 /// Accounts for fees by reducing input amount
@@ -35,6 +42,355 @@ pub fn calculate_output_amount(
     Ok(output.to_u64().unwrap_or(0))
 }
 
+/// Denominator of CLMM fee rates, which are expressed in hundredths of a basis point
+pub const CLMM_FEE_RATE_DENOMINATOR: u32 = 1_000_000;
+
+/// Calculate output amount for a concentrated liquidity swap of token 0 into token 1
+/// that stays within the current tick range, so `liquidity` is constant throughout.
+/// `sqrt_price` is Q64.64 and `fee_rate` is in hundredths of a basis point.
+/// Quote the token 1 -> token 0 direction with `invert_sqrt_price_x64(sqrt_price)`.
+pub fn calculate_clmm_output_amount(
+    sqrt_price: u128,
+    liquidity: u128,
+    input: u64,
+    fee_rate: u32,
+) -> Result<u64> {
+    if fee_rate >= CLMM_FEE_RATE_DENOMINATOR {
+        anyhow::bail!("CLMM fee rate {} is 100% or more", fee_rate);
+    }
+    if sqrt_price == 0 || liquidity == 0 {
+        return Ok(0);
+    }
+
+    let input_after_fee = input as u128 * (CLMM_FEE_RATE_DENOMINATOR - fee_rate) as u128
+        / CLMM_FEE_RATE_DENOMINATOR as u128;
+    let sqrt_price = sqrt_price as f64 / 2f64.powi(64);
+    let liquidity = liquidity as f64;
+
+    // Adding dx of token 0 pushes the price down: 1/sqrtP' = 1/sqrtP + dx/L
+    let next_sqrt_price = liquidity * sqrt_price / (liquidity + input_after_fee as f64 * sqrt_price);
+    // and releases dy = L * (sqrtP - sqrtP') of token 1
+    let output = liquidity * (sqrt_price - next_sqrt_price);
+
+    Ok(output.max(0.0).floor() as u64)
+}
+
+/// Q64.64 sqrt price of the inverted pair (token 1 priced in token 0)
+pub fn invert_sqrt_price_x64(sqrt_price: u128) -> u128 {
+    if sqrt_price == 0 {
+        return 0;
+    }
+    u128::MAX / sqrt_price
+}
+
+/// Ticks covered by one Whirlpool tick-array account
+pub const TICK_ARRAY_SIZE: i32 = 88;
+
+/// An initialized tick of a concentrated liquidity pool
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Tick {
+    pub index: i32,
+    /// Liquidity added when the price crosses this tick upwards, removed going down
+    pub liquidity_net: i128,
+}
+
+/// Initialized ticks of one tick-array account, which covers
+/// `start_tick_index..end_tick_index()`
+#[derive(Debug, Clone, PartialEq)]
+pub struct TickArray {
+    pub start_tick_index: i32,
+    pub tick_spacing: u16,
+    pub ticks: Vec<Tick>,
+}
+
+impl TickArray {
+    pub fn end_tick_index(&self) -> i32 {
+        self.start_tick_index + TICK_ARRAY_SIZE * self.tick_spacing as i32
+    }
+
+    /// The same ticks seen from the inverted pair (token B priced in token A): tick `i`
+    /// becomes `-i`, and crossing it upwards removes the liquidity it used to add
+    pub fn inverted(&self) -> Self {
+        Self {
+            start_tick_index: -self.end_tick_index(),
+            tick_spacing: self.tick_spacing,
+            ticks: self.ticks.iter().rev()
+                .map(|tick| Tick { index: -tick.index, liquidity_net: -tick.liquidity_net })
+                .collect(),
+        }
+    }
+}
+
+/// Square root of the price at `tick`, where price = 1.0001^tick
+pub fn sqrt_price_at_tick(tick: i32) -> f64 {
+    1.0001f64.powf(tick as f64 / 2.0)
+}
+
+/// Largest tick a Whirlpool price can reach; the lowest is its negation
+pub const MAX_TICK_INDEX: i32 = 443_636;
+
+/// `2^64 / 1.0001^(2^i / 2)` as a Q64.64 fraction, for each bit `i` of an absolute tick
+const SQRT_PRICE_TICK_FACTORS: [u128; 19] = [
+    18445821805675392311,
+    18444899583751176498,
+    18443055278223354162,
+    18439367220385604838,
+    18431993317065449817,
+    18417254355718160513,
+    18387811781193591352,
+    18329067761203520168,
+    18212142134806087854,
+    17980523815641551639,
+    17526086738831147013,
+    16651378430235024244,
+    15030750278693429944,
+    12247334978882834399,
+    8131365268884726200,
+    3584323654723342297,
+    696457651847595233,
+    26294789957452057,
+    37481735321082,
+];
+
+/// Q64.64 sqrt price at `tick`, i.e. `1.0001^(tick / 2) * 2^64`, built by multiplying the
+/// factor for each set bit of the tick as Uniswap V3's `TickMath` does. Ticks outside
+/// `±MAX_TICK_INDEX` are clamped.
+pub fn tick_to_sqrt_price_x64(tick: i32) -> u128 {
+    let abs_tick = tick.clamp(-MAX_TICK_INDEX, MAX_TICK_INDEX).unsigned_abs();
+
+    // Each factor is below 2^64 and the ratio never exceeds it, so products fit in u128
+    let mut ratio: u128 = 1 << 64;
+    for (bit, factor) in SQRT_PRICE_TICK_FACTORS.iter().enumerate() {
+        if abs_tick & (1 << bit) != 0 {
+            ratio = (ratio * factor) >> 64;
+        }
+    }
+
+    // The table walks the price down; positive ticks take the reciprocal
+    if tick > 0 {
+        u128::MAX / ratio
+    } else {
+        ratio
+    }
+}
+
+/// Price of token A in token B, in whole tokens, from a Q64.64 sqrt price over raw amounts:
+/// `(sqrt_price_x64 / 2^64)^2 * 10^(decimals_a - decimals_b)`
+pub fn sqrt_price_x64_to_price(sqrt_price_x64: u128, decimals_a: u8, decimals_b: u8) -> Decimal {
+    let exponent = decimals_a as i32 - decimals_b as i32;
+    let scale = if exponent >= 0 {
+        10u64.checked_pow(exponent as u32).map(Decimal::from)
+    } else {
+        Decimal::try_new(1, exponent.unsigned_abs()).ok()
+    };
+    let exact = i128::try_from(sqrt_price_x64)
+        .ok()
+        .and_then(|sqrt_price| Decimal::try_from_i128_with_scale(sqrt_price, 0).ok())
+        .and_then(|sqrt_price| sqrt_price.checked_div(Decimal::from(1u128 << 64)))
+        .and_then(|sqrt_price| sqrt_price.checked_mul(sqrt_price))
+        .and_then(|price| price.checked_mul(scale?));
+
+    // Sqrt prices near the tick limits overflow Decimal; fall back to f64 there
+    exact.unwrap_or_else(|| {
+        let price = (sqrt_price_x64 as f64 / 2f64.powi(64)).powi(2) * 10f64.powi(exponent);
+        Decimal::try_from(price).unwrap_or(Decimal::MAX)
+    })
+}
+
+/// Calculate output amount for an exact-input Whirlpool swap of token A into token B,
+/// which moves the price down and crosses initialized ticks as each range is exhausted.
+/// `fee_rate` is in hundredths of a basis point. For token B into token A pass
+/// `invert_sqrt_price_x64(sqrt_price_x64)` and `TickArray::inverted` arrays.
+/// Without tick arrays liquidity is assumed constant; with them, a trade that runs past
+/// the lowest array fails, as it would on chain.
+pub fn calculate_whirlpool_output(
+    input_amount: u64,
+    sqrt_price_x64: u128,
+    liquidity: u128,
+    fee_rate: u16,
+    tick_arrays: &[TickArray],
+) -> Result<u64> {
+    if sqrt_price_x64 == 0 {
+        return Ok(0);
+    }
+
+    let mut remaining = input_amount as f64 * (1.0 - fee_rate as f64 / CLMM_FEE_RATE_DENOMINATOR as f64);
+    let mut sqrt_price = sqrt_price_x64 as f64 / 2f64.powi(64);
+    let mut liquidity = liquidity as f64;
+    let mut output = 0.0;
+
+    // Initialized ticks below the current price, nearest first
+    let mut ticks: Vec<Tick> = tick_arrays.iter()
+        .flat_map(|array| array.ticks.iter().copied())
+        .filter(|tick| sqrt_price_at_tick(tick.index) < sqrt_price)
+        .collect();
+    ticks.sort_by_key(|tick| std::cmp::Reverse(tick.index));
+    ticks.dedup_by_key(|tick| tick.index);
+
+    // Lowest price the supplied arrays let the swap reach
+    let floor = tick_arrays.iter().map(|array| array.start_tick_index).min().map(sqrt_price_at_tick);
+    let stops = ticks.iter()
+        .map(|tick| (sqrt_price_at_tick(tick.index), tick.liquidity_net))
+        .chain(floor.map(|floor| (floor, 0)));
+
+    for (target, liquidity_net) in stops {
+        if target >= sqrt_price {
+            continue;
+        }
+        if liquidity > 0.0 {
+            // Token A needed to move the price down to the tick: L * (1/target - 1/sqrtP)
+            let to_target = liquidity * (1.0 / target - 1.0 / sqrt_price);
+            if remaining <= to_target {
+                let next_sqrt_price = liquidity * sqrt_price / (liquidity + remaining * sqrt_price);
+                output += liquidity * (sqrt_price - next_sqrt_price);
+                return Ok(output.floor() as u64);
+            }
+            output += liquidity * (sqrt_price - target);
+            remaining -= to_target;
+        }
+        sqrt_price = target;
+        liquidity = (liquidity - liquidity_net as f64).max(0.0);
+    }
+
+    if floor.is_some() {
+        anyhow::bail!("Swap runs past the supplied tick arrays");
+    }
+    if liquidity <= 0.0 {
+        anyhow::bail!("Not enough liquidity to fill the swap");
+    }
+
+    let next_sqrt_price = liquidity * sqrt_price / (liquidity + remaining * sqrt_price);
+    output += liquidity * (sqrt_price - next_sqrt_price);
+    Ok(output.floor() as u64)
+}
+
+/// Liquidity held at one price bin of a Meteora DLMM pair
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Bin {
+    pub id: i32,
+    pub amount_x: u64,
+    pub amount_y: u64,
+}
+
+/// Price of token X in token Y at `bin_id`, where price = (1 + bin_step / 10_000)^bin_id
+pub fn dlmm_price_at_bin(bin_step: u16, bin_id: i32) -> f64 {
+    (1.0 + bin_step as f64 / 10_000.0).powi(bin_id)
+}
+
+/// Calculate output amount for an exact-input DLMM swap, with fees already taken from
+/// `input_amount`. Every bin trades at its own fixed price: X into Y (`swap_for_y`)
+/// drains token Y from the active bin and then the bins below it, Y into X drains
+/// token X from the active bin upwards. Input the supplied bins can't absorb is left
+/// unfilled, so the output is a lower bound for swaps that outrun `bins`.
+pub fn calculate_dlmm_output(
+    bin_step: u16,
+    active_bin_id: i32,
+    bins: &[Bin],
+    input_amount: u64,
+    swap_for_y: bool,
+) -> u64 {
+    let mut bins: Vec<&Bin> = bins.iter()
+        .filter(|bin| if swap_for_y { bin.id <= active_bin_id } else { bin.id >= active_bin_id })
+        .collect();
+    if swap_for_y {
+        bins.sort_by_key(|bin| std::cmp::Reverse(bin.id));
+    } else {
+        bins.sort_by_key(|bin| bin.id);
+    }
+
+    let mut remaining = input_amount as f64;
+    let mut output = 0.0;
+    for bin in bins {
+        let price = dlmm_price_at_bin(bin_step, bin.id);
+        // Output one unit of input buys at this bin, and what the bin holds
+        let (rate, available) = if swap_for_y {
+            (price, bin.amount_y as f64)
+        } else {
+            (1.0 / price, bin.amount_x as f64)
+        };
+        if available <= 0.0 {
+            continue;
+        }
+
+        let to_drain = available / rate;
+        if remaining <= to_drain {
+            output += remaining * rate;
+            break;
+        }
+        output += available;
+        remaining -= to_drain;
+    }
+
+    output.floor() as u64
+}
+
+/// Newton's method iterations before giving up on the StableSwap invariant
+const STABLE_SWAP_MAX_ITERATIONS: usize = 255;
+
+/// Output of a two-token StableSwap (Curve/Saber) pool.
+/// Solves the invariant `A * n^n * sum(x) + D = A * D * n^n + D^(n+1) / (n^n * prod(x))`
+/// for `D` with Newton's method, then for the output reserve after the input is added.
+/// The fee is taken from the output.
+pub fn calculate_stable_swap_output(
+    input_amount: u64,
+    input_reserve: u64,
+    output_reserve: u64,
+    amplification_factor: u64,
+    fee_numerator: u64,
+    fee_denominator: u64,
+) -> Result<u64> {
+    if input_amount == 0 || input_reserve == 0 || output_reserve == 0 {
+        return Ok(0);
+    }
+    anyhow::ensure!(amplification_factor > 0, "Amplification factor must be positive");
+    anyhow::ensure!(fee_denominator > 0 && fee_numerator <= fee_denominator, "Invalid fee {}/{}", fee_numerator, fee_denominator);
+
+    // n = 2 tokens, so n^n = 4
+    let ann = amplification_factor as u128 * 4;
+    let d = stable_swap_invariant(input_reserve as u128, output_reserve as u128, ann)?;
+    let new_output_reserve = stable_swap_reserve(input_reserve as u128 + input_amount as u128, d, ann)?;
+
+    // Round against the trader
+    let output = (output_reserve as u128).saturating_sub(new_output_reserve).saturating_sub(1);
+    let fee = output * fee_numerator as u128 / fee_denominator as u128;
+    Ok((output - fee) as u64)
+}
+
+fn stable_swap_invariant(x: u128, y: u128, ann: u128) -> Result<u128> {
+    let overflow = || anyhow::anyhow!("StableSwap invariant overflow");
+    let sum = x + y;
+    let mut d = sum;
+    for _ in 0..STABLE_SWAP_MAX_ITERATIONS {
+        // D^(n+1) / (n^n * prod(x)), built up one reserve at a time
+        let d_p = d.checked_mul(d).ok_or_else(overflow)? / (x * 2);
+        let d_p = d_p.checked_mul(d).ok_or_else(overflow)? / (y * 2);
+        let previous = d;
+        let numerator = (ann * sum + d_p * 2).checked_mul(d).ok_or_else(overflow)?;
+        d = numerator / ((ann - 1) * d + 3 * d_p);
+        if d.abs_diff(previous) <= 1 {
+            return Ok(d);
+        }
+    }
+    anyhow::bail!("StableSwap invariant did not converge")
+}
+
+/// Reserve of the other token once one side holds `x`, keeping the invariant at `d`
+fn stable_swap_reserve(x: u128, d: u128, ann: u128) -> Result<u128> {
+    let overflow = || anyhow::anyhow!("StableSwap reserve overflow");
+    let c = d.checked_mul(d).ok_or_else(overflow)? / (x * 2);
+    let c = c.checked_mul(d).ok_or_else(overflow)? / (ann * 2);
+    let b = x + d / ann;
+    let mut y = d;
+    for _ in 0..STABLE_SWAP_MAX_ITERATIONS {
+        let previous = y;
+        y = (y.checked_mul(y).ok_or_else(overflow)? + c) / (2 * y + b - d);
+        if y.abs_diff(previous) <= 1 {
+            return Ok(y);
+        }
+    }
+    anyhow::bail!("StableSwap reserve did not converge")
+}
+
 /// Calculate price impact for a swap
 /// Price impact = (old_price - new_price) / old_price
 pub fn calculate_price_impact(
@@ -71,6 +427,17 @@ pub fn calculate_price_impact(
     Ok(price_impact.abs())
 }
 
+/// Price impact of a swap in basis points (1 bps = 0.01%), using the
+/// input's share of the post-trade input reserve
+pub fn calculate_price_impact_bps(input_amount: u64, reserve_in: u64, reserve_out: u64) -> u32 {
+    if reserve_in == 0 || reserve_out == 0 {
+        return 0;
+    }
+
+    let impact = input_amount as u128 * 10_000 / (reserve_in as u128 + input_amount as u128);
+    impact as u32
+}
+
 /// Calculate slippage for a trade
 /// Slippage is the difference between expected and actual execution price
 pub fn calculate_slippage(
@@ -91,6 +458,12 @@ pub fn calculate_slippage(
     Ok(calculated_slippage.min(max_slippage))
 }
 
+/// Minimum acceptable output after allowing for `max_slippage_percent` of slippage
+pub fn minimum_amount_out(expected_output: u64, max_slippage_percent: f64) -> u64 {
+    let tolerance = (1.0 - max_slippage_percent / 100.0).clamp(0.0, 1.0);
+    (expected_output as f64 * tolerance).floor() as u64
+}
+
 /// Calculate the optimal trade size based on price impact tolerance
 pub fn calculate_optimal_trade_size(
     input_reserve: u64,
@@ -121,6 +494,60 @@ pub fn calculate_optimal_trade_size(
     Ok(optimal_size)
 }
 
+/// Input in `[10_000, max_input]` that maximizes `output - input` over three
+/// constant-product hops, each trading A to B when its direction is `true`.
+/// Ternary search, since the cycle's profit is concave in its input.
+pub fn calculate_triangular_optimal_amount(
+    pool1: &Pool,
+    dir1: bool,
+    pool2: &Pool,
+    dir2: bool,
+    pool3: &Pool,
+    dir3: bool,
+    max_input: u64,
+) -> u64 {
+    if max_input <= TRIANGULAR_MIN_INPUT {
+        return max_input;
+    }
+
+    let hop = |pool: &Pool, a_to_b: bool, amount: u64| {
+        let (reserve_in, reserve_out) = if a_to_b {
+            (pool.reserve_a, pool.reserve_b)
+        } else {
+            (pool.reserve_b, pool.reserve_a)
+        };
+        calculate_output_amount(amount, reserve_in, reserve_out, pool.fee_percent).ok()
+    };
+    let profit = |input: u64| -> i128 {
+        let output = hop(pool1, dir1, input)
+            .and_then(|amount| hop(pool2, dir2, amount))
+            .and_then(|amount| hop(pool3, dir3, amount));
+        match output {
+            Some(output) => output as i128 - input as i128,
+            None => i128::MIN,
+        }
+    };
+
+    let (mut low, mut high) = (TRIANGULAR_MIN_INPUT, max_input);
+    for _ in 0..TRIANGULAR_SEARCH_ITERATIONS {
+        if high - low < TRIANGULAR_SEARCH_TOLERANCE {
+            break;
+        }
+        let third = (high - low) / 3;
+        let (left, right) = (low + third, high - third);
+        if profit(left) < profit(right) {
+            low = left;
+        } else {
+            high = right;
+        }
+    }
+
+    // Rounding can flatten the curve near the optimum, so never do worse than a bound
+    [TRIANGULAR_MIN_INPUT, low, low + (high - low) / 2, high, max_input].into_iter()
+        .max_by_key(|&input| profit(input))
+        .unwrap_or(max_input)
+}
+
 /// Calculate profit after fees and slippage
 pub fn calculate_net_profit(
     input_amount: u64,
@@ -190,6 +617,54 @@ pub fn calculate_sharpe_ratio(
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_tick_to_sqrt_price_x64() {
+        assert_eq!(tick_to_sqrt_price_x64(0), 1 << 64);
+        for tick in [-443_636, -50_000, -1, 1, 7_500, 100_000, 443_636] {
+            let expected = sqrt_price_at_tick(tick) * 2f64.powi(64);
+            let actual = tick_to_sqrt_price_x64(tick) as f64;
+            assert!((actual / expected - 1.0).abs() < 1e-9, "tick {}: {} vs {}", tick, actual, expected);
+        }
+        assert_eq!(tick_to_sqrt_price_x64(500_000), tick_to_sqrt_price_x64(MAX_TICK_INDEX));
+    }
+
+    #[test]
+    fn test_sqrt_price_x64_to_price() {
+        // SOL (9 decimals) at 150 USDC (6 decimals): raw price is 150 * 10^-3
+        let sqrt_price_x64 = ((0.15f64).sqrt() * 2f64.powi(64)) as u128;
+        let price = sqrt_price_x64_to_price(sqrt_price_x64, 9, 6).to_f64().unwrap();
+        assert!((price - 150.0).abs() < 1e-9);
+
+        assert_eq!(sqrt_price_x64_to_price(1 << 64, 6, 6), Decimal::ONE);
+        assert!(sqrt_price_x64_to_price(tick_to_sqrt_price_x64(MAX_TICK_INDEX), 0, 0) > Decimal::ZERO);
+    }
+
+    #[test]
+    fn test_calculate_stable_swap_output() {
+        let reserve = 1_000_000_000_000; // 1M tokens with 6 decimals
+
+        // A balanced pool swaps almost 1:1, far better than constant product
+        let stable = calculate_stable_swap_output(1_000_000_000, reserve, reserve, 100, 0, 1).unwrap();
+        let constant_product = calculate_output_amount(1_000_000_000, reserve, reserve, Decimal::ZERO).unwrap();
+        assert!(stable > 999_900_000 && stable < 1_000_000_000);
+        assert!(stable > constant_product);
+
+        // Lower amplification behaves more like constant product
+        let low_amp = calculate_stable_swap_output(1_000_000_000, reserve, reserve, 1, 0, 1).unwrap();
+        assert!(low_amp < stable && low_amp > constant_product);
+
+        // Selling into the heavier side returns less than one for one
+        let skewed = calculate_stable_swap_output(1_000_000_000, 2 * reserve, reserve / 2, 100, 0, 1).unwrap();
+        assert!(skewed < stable);
+
+        // 0.04% fee comes out of the output
+        let with_fee = calculate_stable_swap_output(1_000_000_000, reserve, reserve, 100, 4, 10_000).unwrap();
+        assert_eq!(with_fee, stable - stable * 4 / 10_000);
+
+        assert_eq!(calculate_stable_swap_output(1_000, 0, reserve, 100, 0, 1).unwrap(), 0);
+        assert!(calculate_stable_swap_output(1_000, reserve, reserve, 0, 0, 1).is_err());
+    }
+
     #[test]
     fn test_calculate_output_amount() {
         let input_amount = 1000;
@@ -213,6 +688,16 @@ mod tests {
         assert!(price_impact < Decimal::ONE); // Should be less than 100%
     }
 
+    #[test]
+    fn test_calculate_price_impact_bps() {
+        // 1,000 into 100,000: 1000 * 10_000 / 101_000
+        assert_eq!(calculate_price_impact_bps(1_000, 100_000, 200_000), 99);
+        assert_eq!(calculate_price_impact_bps(100_000, 100_000, 200_000), 5_000);
+        assert_eq!(calculate_price_impact_bps(1_000, 0, 200_000), 0);
+        // Large reserves don't overflow
+        assert_eq!(calculate_price_impact_bps(u64::MAX, u64::MAX, u64::MAX), 5_000);
+    }
+
     #[test]
     fn test_calculate_net_profit() {
         let input_amount = 1000;
@@ -223,4 +708,142 @@ mod tests {
         let net_profit = calculate_net_profit(input_amount, output_amount, transaction_fee, gas_fee);
         assert_eq!(net_profit, 75); // 1100 - 1000 - 20 - 5 = 75
     }
+
+    #[test]
+    fn test_calculate_clmm_output_amount() {
+        // Price of 4 token 1 per token 0: sqrt price 2.0 in Q64.64
+        let sqrt_price = 2u128 << 64;
+        let liquidity = 1_000_000_000_000u128;
+
+        // In range the curve matches a constant-product pool on the virtual reserves
+        let (reserve_0, reserve_1) = (liquidity as u64 / 2, liquidity as u64 * 2);
+        let clmm = calculate_clmm_output_amount(sqrt_price, liquidity, 1_000_000, 0).unwrap();
+        let amm = calculate_output_amount(1_000_000, reserve_0, reserve_1, Decimal::ZERO).unwrap();
+        assert!(clmm.abs_diff(amm) <= 1, "clmm {} vs amm {}", clmm, amm);
+        assert!(clmm < 4_000_000);
+
+        // 0.25% fee tier
+        let with_fee = calculate_clmm_output_amount(sqrt_price, liquidity, 1_000_000, 2_500).unwrap();
+        assert!(with_fee < clmm);
+
+        // Reverse direction: 4 token 1 buys just under 1 token 0
+        let reverse = calculate_clmm_output_amount(invert_sqrt_price_x64(sqrt_price), liquidity, 4_000_000, 0).unwrap();
+        assert!((999_000..1_000_000).contains(&reverse), "got {}", reverse);
+
+        assert_eq!(calculate_clmm_output_amount(sqrt_price, 0, 1_000_000, 0).unwrap(), 0);
+        assert!(calculate_clmm_output_amount(sqrt_price, liquidity, 1_000_000, CLMM_FEE_RATE_DENOMINATOR).is_err());
+    }
+
+    #[test]
+    fn test_calculate_whirlpool_output() {
+        let sqrt_price = 1u128 << 64; // tick 0
+        let liquidity = 1_000_000_000u128;
+
+        // No tick data: same curve as a single CLMM range
+        let unbounded = calculate_whirlpool_output(1_000_000, sqrt_price, liquidity, 0, &[]).unwrap();
+        assert_eq!(unbounded, calculate_clmm_output_amount(sqrt_price, liquidity, 1_000_000, 0).unwrap());
+
+        // Half the liquidity ends at tick -64, so a trade crossing it fills worse
+        let arrays = [TickArray {
+            start_tick_index: -5_632,
+            tick_spacing: 64,
+            ticks: vec![Tick { index: -64, liquidity_net: 500_000_000 }],
+        }];
+        let to_tick = (liquidity as f64 * (1.0 / sqrt_price_at_tick(-64) - 1.0)) as u64;
+        let small = calculate_whirlpool_output(to_tick / 2, sqrt_price, liquidity, 0, &arrays).unwrap();
+        assert_eq!(small, calculate_clmm_output_amount(sqrt_price, liquidity, to_tick / 2, 0).unwrap());
+        let crossing = calculate_whirlpool_output(to_tick * 2, sqrt_price, liquidity, 0, &arrays).unwrap();
+        let constant = calculate_clmm_output_amount(sqrt_price, liquidity, to_tick * 2, 0).unwrap();
+        assert!(crossing < constant, "{} should be below {}", crossing, constant);
+
+        // Fees come off the input
+        assert!(calculate_whirlpool_output(to_tick / 2, sqrt_price, liquidity, 3_000, &arrays).unwrap() < small);
+
+        // Running past the array fails
+        assert!(calculate_whirlpool_output(u64::MAX / 2, sqrt_price, liquidity, 0, &arrays).is_err());
+
+        // The inverted view covers the mirrored range
+        let inverted = arrays[0].inverted();
+        assert_eq!(inverted.start_tick_index, 0);
+        assert_eq!(inverted.end_tick_index(), 5_632);
+        assert_eq!(inverted.ticks, vec![Tick { index: 64, liquidity_net: -500_000_000 }]);
+    }
+
+    #[test]
+    fn test_calculate_dlmm_output() {
+        let bins = [
+            Bin { id: -1, amount_x: 0, amount_y: 1_000 },
+            Bin { id: 0, amount_x: 1_000, amount_y: 1_000 },
+            Bin { id: 1, amount_x: 1_000, amount_y: 0 },
+        ];
+
+        // Within the active bin the price is flat
+        assert_eq!(calculate_dlmm_output(100, 0, &bins, 500, true), 500);
+        assert_eq!(calculate_dlmm_output(100, 0, &bins, 500, false), 500);
+
+        // Past it, the next bin fills 1% worse in either direction
+        assert_eq!(calculate_dlmm_output(100, 0, &bins, 1_500, true), 1_495);
+        assert_eq!(calculate_dlmm_output(100, 0, &bins, 1_500, false), 1_495);
+
+        // Output stops at the liquidity the bins hold
+        assert_eq!(calculate_dlmm_output(100, 0, &bins, 1_000_000, true), 2_000);
+        assert_eq!(calculate_dlmm_output(100, 0, &[], 1_000, true), 0);
+    }
+
+    #[test]
+    fn test_minimum_amount_out() {
+        assert_eq!(minimum_amount_out(1_000_000, 1.0), 990_000);
+        assert_eq!(minimum_amount_out(1_000_000, 0.0), 1_000_000);
+        assert_eq!(minimum_amount_out(1_000_000, 150.0), 0);
+    }
+
+    fn cycle_pool(reserve_a: u64, reserve_b: u64, fee_bps: i64) -> Pool {
+        let token = crate::models::TokenInfo {
+            mint: solana_sdk::pubkey::Pubkey::new_unique(),
+            symbol: "TEST".to_string(),
+            decimals: 9,
+            price_usd: None,
+        };
+        Pool {
+            address: solana_sdk::pubkey::Pubkey::new_unique(),
+            dex: "raydium".to_string(),
+            token_a: token.clone(),
+            token_b: token,
+            reserve_a,
+            reserve_b,
+            fee_percent: Decimal::new(fee_bps, 4),
+            liquidity_usd: Decimal::ZERO,
+            last_updated: chrono::Utc::now(),
+            metadata: None,
+        }
+    }
+
+    fn cycle_profit(pools: &[(Pool, bool); 3], input: u64) -> i128 {
+        let output = pools.iter().fold(input, |amount, (pool, a_to_b)| {
+            let (reserve_in, reserve_out) = if *a_to_b { (pool.reserve_a, pool.reserve_b) } else { (pool.reserve_b, pool.reserve_a) };
+            calculate_output_amount(amount, reserve_in, reserve_out, pool.fee_percent).unwrap()
+        });
+        output as i128 - input as i128
+    }
+
+    proptest::proptest! {
+        #[test]
+        fn test_triangular_optimal_amount_beats_fixed_amount(
+            reserves in proptest::array::uniform6(1_000_000_000u64..10_000_000_000_000),
+            fees in proptest::array::uniform3(0i64..100),
+            directions in proptest::array::uniform3(proptest::bool::ANY),
+            max_input in 10_001u64..10_000_000_000,
+        ) {
+            let pools = [
+                (cycle_pool(reserves[0], reserves[1], fees[0]), directions[0]),
+                (cycle_pool(reserves[2], reserves[3], fees[1]), directions[1]),
+                (cycle_pool(reserves[4], reserves[5], fees[2]), directions[2]),
+            ];
+            let optimal = calculate_triangular_optimal_amount(
+                &pools[0].0, pools[0].1, &pools[1].0, pools[1].1, &pools[2].0, pools[2].1, max_input,
+            );
+            proptest::prop_assert!((TRIANGULAR_MIN_INPUT..=max_input).contains(&optimal));
+            proptest::prop_assert!(cycle_profit(&pools, optimal) >= cycle_profit(&pools, max_input));
+        }
+    }
 }
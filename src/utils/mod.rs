@@ -1,7 +1,11 @@
 pub mod cache;
 pub mod math;
+pub mod price_feed;
 pub mod rpc;
+pub mod token_registry;
 
 pub use cache::*;
 pub use math::*;
+pub use price_feed::*;
 pub use rpc::*;
+pub use token_registry::*;
@@ -0,0 +1,277 @@
+use crate::utils::rpc::RpcClient;
+use anyhow::{Context, Result};
+use dashmap::DashMap;
+use rust_decimal::Decimal;
+use serde_json::Value;
+use solana_sdk::pubkey::Pubkey;
+use std::{
+    collections::HashMap,
+    str::FromStr,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tracing::debug;
+
+pub const JUPITER_PRICE_API: &str = "https://api.jup.ag/price/v2";
+
+/// Legacy Pyth v2 price accounts on mainnet, the on-chain `PriceAccount`s publishers
+/// update directly: (token mint, USD price account)
+pub const PYTH_PRICE_ACCOUNTS: [(&str, &str); 6] = [
+    ("So11111111111111111111111111111111111111112", "H6ARHf6YXhGYeQfUzQNGk6rDNnLBQKrenN712K4AQJEG"),
+    ("EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v", "Gnt27xtC473ZT2Mw5u8wZ68Z3gULkSTb5DuxJy7eJotD"),
+    ("Es9vMFrzaCERmJfrF4H2FYD4KCoNkY11McCe8BenwNYB", "3vxLXJqLqF3JG5TCbYycbKWRBbCJQLxQmBGCkyqEEefL"),
+    ("4k3Dyjzvzp8eMZWUXbBCjEvwSkkk59S5iCNLY3QrkX6R", "AnLf8tVYCM816gmBjiy8n53eXKKEDydT5piYjjQDPgTB"),
+    ("orcaEKTdK7LKz57vaAYr9QeNsVEPfiu6QeMU1kektZE", "4ivThkX8uRxBpHsdWSqyXYihzKF3zpRGAUCqyuagnLoV"),
+    ("DezXAZ8z7PnrnRJjz3wXBoRgixCa6xjnB7YaB1pPB263", "8ihFLu5FimgTQ1Unh4dVyEHUGodJ5gJQCrQf4KUVB9bN"),
+];
+
+const PYTH_MAGIC: u32 = 0xa1b2_c3d4;
+/// `PriceStatus::Trading`; other statuses mean the aggregate can't be trusted
+const PYTH_STATUS_TRADING: u32 = 1;
+const PYTH_PRICE_TTL: Duration = Duration::from_secs(5);
+/// Offsets into the v2 `PriceAccount` layout
+const PYTH_EXPO_OFFSET: usize = 20;
+const PYTH_AGG_PRICE_OFFSET: usize = 208;
+const PYTH_AGG_CONF_OFFSET: usize = 216;
+const PYTH_AGG_STATUS_OFFSET: usize = 224;
+const PYTH_AGG_PUB_SLOT_OFFSET: usize = 232;
+
+/// Aggregate price read from a Pyth `PriceAccount`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PythPrice {
+    pub price: i64,
+    pub conf: u64,
+    pub expo: i32,
+    pub status: u32,
+    /// Slot the aggregate price was published in
+    pub pub_slot: u64,
+}
+
+impl PythPrice {
+    pub fn parse(data: &[u8]) -> Result<Self> {
+        let read = |offset: usize, len: usize| {
+            data.get(offset..offset + len).context("Pyth price account data too short")
+        };
+        let magic = u32::from_le_bytes(read(0, 4)?.try_into()?);
+        if magic != PYTH_MAGIC {
+            anyhow::bail!("Not a Pyth account (magic {:#x})", magic);
+        }
+
+        Ok(Self {
+            price: i64::from_le_bytes(read(PYTH_AGG_PRICE_OFFSET, 8)?.try_into()?),
+            conf: u64::from_le_bytes(read(PYTH_AGG_CONF_OFFSET, 8)?.try_into()?),
+            expo: i32::from_le_bytes(read(PYTH_EXPO_OFFSET, 4)?.try_into()?),
+            status: u32::from_le_bytes(read(PYTH_AGG_STATUS_OFFSET, 4)?.try_into()?),
+            pub_slot: u64::from_le_bytes(read(PYTH_AGG_PUB_SLOT_OFFSET, 8)?.try_into()?),
+        })
+    }
+
+    /// Fail if the aggregate was published more than `max_age_slots` before `current_slot`.
+    /// A feed whose publishers stopped keeps its last status, so this is the only sign.
+    pub fn check_fresh(&self, current_slot: u64, max_age_slots: u64) -> Result<()> {
+        let age = current_slot.saturating_sub(self.pub_slot);
+        if age > max_age_slots {
+            anyhow::bail!("Pyth price is {} slots old (published in slot {}), limit is {}", age, self.pub_slot, max_age_slots);
+        }
+        Ok(())
+    }
+
+    /// `price * 10^expo` in USD
+    pub fn to_decimal(&self) -> Result<Decimal> {
+        if self.status != PYTH_STATUS_TRADING {
+            anyhow::bail!("Pyth price is not trading (status {})", self.status);
+        }
+        if self.price <= 0 {
+            anyhow::bail!("Pyth price is not positive: {}", self.price);
+        }
+
+        if self.expo <= 0 {
+            Decimal::try_from_i128_with_scale(self.price as i128, self.expo.unsigned_abs())
+                .context("Pyth exponent out of range")
+        } else {
+            Decimal::from(self.price)
+                .checked_mul(Decimal::from(10u64.checked_pow(self.expo as u32).context("Pyth exponent out of range")?))
+                .context("Pyth price overflow")
+        }
+    }
+}
+
+/// Real-time USD prices read directly from Pyth price accounts
+pub struct PythPriceFeed {
+    rpc_client: Arc<RpcClient>,
+    price_accounts: HashMap<Pubkey, Pubkey>,
+    cache: DashMap<Pubkey, (Decimal, Instant)>,
+}
+
+impl PythPriceFeed {
+    pub fn new(rpc_client: Arc<RpcClient>) -> Self {
+        let price_accounts = PYTH_PRICE_ACCOUNTS.iter()
+            .map(|(mint, account)| (Pubkey::from_str(mint).unwrap(), Pubkey::from_str(account).unwrap()))
+            .collect();
+
+        Self {
+            rpc_client,
+            price_accounts,
+            cache: DashMap::new(),
+        }
+    }
+
+    /// Whether `token_mint` has a known Pyth price account
+    pub fn supports(&self, token_mint: &Pubkey) -> bool {
+        self.price_accounts.contains_key(token_mint)
+    }
+
+    /// USD price per whole token, cached for five seconds. Prices published more than
+    /// `max_age_slots` before the slot the account was read at are rejected.
+    pub async fn get_price(&self, token_mint: &Pubkey, max_age_slots: u64) -> Result<Decimal> {
+        if let Some(entry) = self.cache.get(token_mint) {
+            let (price, fetched_at) = *entry;
+            if fetched_at.elapsed() < PYTH_PRICE_TTL {
+                return Ok(price);
+            }
+        }
+
+        let price_account = self.price_accounts.get(token_mint)
+            .with_context(|| format!("No Pyth price account for {}", token_mint))?;
+        let (account, slot) = self.rpc_client.get_account_with_slot(price_account).await?;
+        let account = account
+            .with_context(|| format!("Pyth price account {} not found", price_account))?;
+        let pyth_price = PythPrice::parse(&account.data)?;
+        pyth_price.check_fresh(slot, max_age_slots)
+            .with_context(|| format!("Stale Pyth price for {}", token_mint))?;
+        let price = pyth_price.to_decimal()
+            .with_context(|| format!("Invalid Pyth price for {}", token_mint))?;
+
+        debug!("Pyth price for {}: ${}", token_mint, price);
+        self.cache.insert(*token_mint, (price, Instant::now()));
+        Ok(price)
+    }
+}
+
+/// The price API rejects requests with more ids than this
+const MAX_IDS_PER_REQUEST: usize = 100;
+
+/// USD token prices from the Jupiter price API
+pub struct JupiterPriceFeed {
+    http_client: reqwest::Client,
+}
+
+impl JupiterPriceFeed {
+    pub fn new() -> Self {
+        Self {
+            http_client: reqwest::Client::new(),
+        }
+    }
+
+    /// USD price per whole token for each mint; mints without a price are left out
+    pub async fn get_prices(&self, mints: &[Pubkey]) -> Result<HashMap<Pubkey, f64>> {
+        let mut prices = HashMap::new();
+
+        for chunk in mints.chunks(MAX_IDS_PER_REQUEST) {
+            let ids = chunk.iter().map(|mint| mint.to_string()).collect::<Vec<_>>().join(",");
+            let response: Value = self.http_client
+                .get(JUPITER_PRICE_API)
+                .query(&[("ids", ids)])
+                .header("User-Agent", "solana-arbitrage-bot/1.0")
+                .timeout(std::time::Duration::from_secs(10))
+                .send()
+                .await
+                .context("Failed to fetch Jupiter prices")?
+                .error_for_status()
+                .context("Jupiter price request failed")?
+                .json()
+                .await
+                .context("Failed to parse Jupiter price response")?;
+
+            prices.extend(Self::parse_prices(&response));
+        }
+
+        debug!("Fetched USD prices for {}/{} mints", prices.len(), mints.len());
+        Ok(prices)
+    }
+
+    fn parse_prices(response: &Value) -> HashMap<Pubkey, f64> {
+        let Some(data) = response.get("data").and_then(|d| d.as_object()) else {
+            return HashMap::new();
+        };
+
+        data.iter()
+            .filter_map(|(mint, entry)| {
+                let mint = Pubkey::from_str(mint).ok()?;
+                // Prices come back as strings, but accept plain numbers too
+                let price = match entry.get("price")? {
+                    Value::String(price) => price.parse::<f64>().ok()?,
+                    price => price.as_f64()?,
+                };
+                (price.is_finite() && price > 0.0).then_some((mint, price))
+            })
+            .collect()
+    }
+}
+
+impl Default for JupiterPriceFeed {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn pyth_account(price: i64, expo: i32, status: u32) -> Vec<u8> {
+        let mut data = vec![0u8; 240];
+        data[PYTH_AGG_PUB_SLOT_OFFSET..PYTH_AGG_PUB_SLOT_OFFSET + 8].copy_from_slice(&1_000u64.to_le_bytes());
+        data[0..4].copy_from_slice(&PYTH_MAGIC.to_le_bytes());
+        data[PYTH_EXPO_OFFSET..PYTH_EXPO_OFFSET + 4].copy_from_slice(&expo.to_le_bytes());
+        data[PYTH_AGG_PRICE_OFFSET..PYTH_AGG_PRICE_OFFSET + 8].copy_from_slice(&price.to_le_bytes());
+        data[PYTH_AGG_CONF_OFFSET..PYTH_AGG_CONF_OFFSET + 8].copy_from_slice(&5_000u64.to_le_bytes());
+        data[PYTH_AGG_STATUS_OFFSET..PYTH_AGG_STATUS_OFFSET + 4].copy_from_slice(&status.to_le_bytes());
+        data
+    }
+
+    #[test]
+    fn test_parse_pyth_price() {
+        let price = PythPrice::parse(&pyth_account(14_250_000_000, -8, PYTH_STATUS_TRADING)).unwrap();
+        assert_eq!(price.conf, 5_000);
+        assert_eq!(price.to_decimal().unwrap(), Decimal::new(1425, 1));
+
+        // Halted feeds and non-Pyth accounts are rejected
+        assert!(PythPrice::parse(&pyth_account(14_250_000_000, -8, 0)).unwrap().to_decimal().is_err());
+        assert!(PythPrice::parse(&[0u8; 240]).is_err());
+        assert!(PythPrice::parse(&pyth_account(1, -8, 1)[..100]).is_err());
+    }
+
+    #[test]
+    fn test_pyth_price_staleness() {
+        let price = PythPrice::parse(&pyth_account(14_250_000_000, -8, PYTH_STATUS_TRADING)).unwrap();
+        assert_eq!(price.pub_slot, 1_000);
+
+        price.check_fresh(1_025, 25).unwrap();
+        assert!(price.check_fresh(1_026, 25).is_err());
+        // A node a little behind the publisher is not a stale price
+        price.check_fresh(990, 25).unwrap();
+    }
+
+    #[test]
+    fn test_parse_prices() {
+        let sol = Pubkey::new_unique();
+        let usdc = Pubkey::new_unique();
+        let unpriced = Pubkey::new_unique();
+        let response = json!({
+            "data": {
+                sol.to_string(): { "id": sol.to_string(), "price": "142.5" },
+                usdc.to_string(): { "id": usdc.to_string(), "price": 1.0 },
+                unpriced.to_string(): null,
+                "not-a-mint": { "price": "3.0" }
+            }
+        });
+
+        let prices = JupiterPriceFeed::parse_prices(&response);
+        assert_eq!(prices.len(), 2);
+        assert_eq!(prices[&sol], 142.5);
+        assert_eq!(prices[&usdc], 1.0);
+        assert!(JupiterPriceFeed::parse_prices(&json!({})).is_empty());
+    }
+}
@@ -1,10 +1,23 @@
 use anyhow::{Context, Result};
-use governor::{Quota, RateLimiter};
+use base64::Engine;
+use futures_util::{SinkExt, StreamExt};
+use governor::{
+    clock::DefaultClock,
+    middleware::StateInformationMiddleware,
+    state::{direct::NotKeyed, InMemoryState},
+    Quota, RateLimiter,
+};
 use reqwest::Client;
 use serde_json::{json, Value};
+use solana_account_decoder::{UiAccountEncoding, UiDataSliceConfig};
 use solana_client::{
-    rpc_client::RpcClient as SolanaRpcClient,
-    rpc_response::RpcSimulateTransactionResult,
+    client_error::{ClientError, ClientErrorKind, Result as ClientResult},
+    rpc_client::{GetConfirmedSignaturesForAddress2Config, RpcClient as SolanaRpcClient, SerializableTransaction},
+    rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig, RpcSendTransactionConfig},
+    rpc_request::TokenAccountsFilter,
+    rpc_filter::RpcFilterType,
+    rpc_request::RpcError,
+    rpc_response::{RpcConfirmedTransactionStatusWithSignature, RpcSimulateTransactionResult},
 };
 use solana_sdk::{
     commitment_config::CommitmentConfig,
@@ -15,43 +28,174 @@ use solana_sdk::{
     epoch_info::EpochInfo,
     account::Account,
 };
-use std::{num::NonZeroU32, sync::Arc, time::Duration};
+use std::{
+    collections::{HashMap, VecDeque},
+    num::NonZeroU32,
+    str::FromStr,
+    sync::{
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+        Arc, Mutex, RwLock,
+    },
+    time::{Duration, Instant},
+};
+use tokio::sync::{mpsc, oneshot};
+use tokio_tungstenite::{connect_async, tungstenite::Message};
 use tracing::{debug, error, warn};
 
-use crate::config::Config;
+use crate::{config::Config, console::ConsoleManager, metrics::MetricsCollector};
+
+type DirectRateLimiter = RateLimiter<NotKeyed, InMemoryState, DefaultClock, StateInformationMiddleware>;
+
+/// Recent slots considered when estimating priority fees
+const PRIORITY_FEE_SLOTS: usize = 150;
+/// JSON-RPC error returned by a node that has fallen behind the cluster
+const NODE_UNHEALTHY_ERROR_CODE: i64 = -32005;
+/// Most recent request latencies kept for percentiles
+const LATENCY_SAMPLES: usize = 1_000;
+/// How long `subscribe_accounts` waits for the node to answer every subscription request
+const SUBSCRIPTION_CONFIRM_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Snapshot of RPC quota usage
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct RateLimitStats {
+    pub requests_last_second: u32,
+    pub max_requests_per_second: u32,
+    pub burst_remaining: u32,
+    /// Time the most recent request spent waiting for quota
+    pub wait_time_ms: u64,
+}
+
+impl RateLimitStats {
+    /// Share of the per-second quota in use, 0.0 to 1.0
+    pub fn usage_ratio(&self) -> f64 {
+        if self.max_requests_per_second == 0 {
+            return 0.0;
+        }
+        (self.requests_last_second as f64 / self.max_requests_per_second as f64).min(1.0)
+    }
+}
+
+/// SPL Token mint layout: the fixed 82-byte prefix shared by Token and Token-2022 mints
+const MINT_ACCOUNT_LEN: usize = 82;
+/// SPL Token account layout: 165 bytes, with the `amount` little-endian u64 at bytes 64..72
+const TOKEN_ACCOUNT_LEN: usize = 165;
+const TOKEN_ACCOUNT_AMOUNT_OFFSET: usize = 64;
+
+/// State of an SPL Token mint
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MintInfo {
+    pub decimals: u8,
+    pub supply: u64,
+    pub freeze_authority: Option<Pubkey>,
+    pub mint_authority: Option<Pubkey>,
+}
+
+impl MintInfo {
+    /// Parse the 82-byte mint layout. Authorities are `COption`s: a 4-byte tag
+    /// followed by the key, so `freeze_authority` starts at byte 50, not 47.
+    /// Token-2022 extensions after the prefix are ignored.
+    pub fn parse(data: &[u8]) -> Result<Self> {
+        if data.len() < MINT_ACCOUNT_LEN {
+            anyhow::bail!("Mint account data too short: {} bytes", data.len());
+        }
+        if data[45] == 0 {
+            anyhow::bail!("Mint is not initialized");
+        }
+
+        let authority = |tag: &[u8], key: &[u8]| -> Result<Option<Pubkey>> {
+            match u32::from_le_bytes(tag.try_into().unwrap()) {
+                0 => Ok(None),
+                1 => Ok(Some(Pubkey::try_from(key).unwrap())),
+                tag => anyhow::bail!("Invalid authority option tag {}", tag),
+            }
+        };
+
+        Ok(Self {
+            mint_authority: authority(&data[0..4], &data[4..36])?,
+            supply: u64::from_le_bytes(data[36..44].try_into().unwrap()),
+            decimals: data[44],
+            freeze_authority: authority(&data[46..50], &data[50..82])?,
+        })
+    }
+}
+
+/// New contents of a subscribed account
+#[derive(Debug, Clone, PartialEq)]
+pub struct AccountUpdate {
+    pub pubkey: Pubkey,
+    pub slot: u64,
+    pub data: Vec<u8>,
+}
+
+#[derive(Default)]
+struct RateLimitTracker {
+    recent_requests: VecDeque<Instant>,
+    total_requests: u64,
+    burst_remaining: u32,
+    last_wait: Duration,
+}
 
 pub struct RpcClient {
-    solana_client: SolanaRpcClient,
+    solana_client: RwLock<Arc<SolanaRpcClient>>,
     http_client: Client,
-    rate_limiter: Arc<RateLimiter<governor::state::direct::NotKeyed, governor::state::InMemoryState, governor::clock::DefaultClock>>,
-    rpc_url: String,
+    rate_limiter: Arc<DirectRateLimiter>,
+    rate_tracker: Arc<Mutex<RateLimitTracker>>,
+    max_requests_per_second: u32,
+    console: Option<Arc<ConsoleManager>>,
+    /// RPC URLs in priority order; requests move down the list when one fails
+    endpoints: Vec<String>,
+    current_index: AtomicUsize,
+    ws_url: String,
+    /// Listener tasks of `subscribe_account_changes`, by the id it returned
+    account_subscriptions: Arc<Mutex<HashMap<u64, tokio::task::JoinHandle<()>>>>,
+    next_subscription_id: Arc<AtomicU64>,
+    /// Counts requests and errors per JSON-RPC method
+    metrics: &'static MetricsCollector,
+    /// Round-trip time of recent requests in milliseconds, oldest first
+    latency_tracker: Arc<Mutex<VecDeque<u64>>>,
 }
 
 impl Clone for RpcClient {
     fn clone(&self) -> Self {
         Self {
-            solana_client: SolanaRpcClient::new_with_commitment(
-                self.rpc_url.clone(),
-                CommitmentConfig::confirmed(),
-            ),
+            solana_client: RwLock::new(Self::connect(self.get_current_endpoint())),
             http_client: self.http_client.clone(),
             rate_limiter: Arc::clone(&self.rate_limiter),
-            rpc_url: self.rpc_url.clone(),
+            rate_tracker: Arc::clone(&self.rate_tracker),
+            max_requests_per_second: self.max_requests_per_second,
+            console: self.console.clone(),
+            endpoints: self.endpoints.clone(),
+            current_index: AtomicUsize::new(self.current_index.load(Ordering::Relaxed)),
+            ws_url: self.ws_url.clone(),
+            account_subscriptions: Arc::clone(&self.account_subscriptions),
+            next_subscription_id: Arc::clone(&self.next_subscription_id),
+            metrics: self.metrics,
+            latency_tracker: Arc::clone(&self.latency_tracker),
         }
     }
 }
 
+// Failover closures return solana_client's `ClientError`, which is large but not ours to box
+#[allow(clippy::result_large_err)]
 impl RpcClient {
     pub fn new(config: &Config) -> Result<Self> {
         let rpc_url = config.rpc.quicknode_rpc_url
             .as_ref()
             .unwrap_or(&config.rpc.solana_rpc_url)
             .clone();
+        let ws_url = config.rpc.quicknode_ws_url
+            .as_ref()
+            .unwrap_or(&config.rpc.solana_ws_url)
+            .clone();
 
-        let solana_client = SolanaRpcClient::new_with_commitment(
-            rpc_url.clone(),
-            CommitmentConfig::confirmed(),
-        );
+        // Primary first, then the configured fallbacks, then the public endpoint
+        let mut endpoints = vec![rpc_url];
+        for url in config.rpc.fallback_rpc_urls.iter().chain([&config.rpc.solana_rpc_url]) {
+            if !endpoints.contains(url) {
+                endpoints.push(url.clone());
+            }
+        }
+        let solana_client = Self::connect(&endpoints[0]);
 
         let http_client = Client::builder()
             .timeout(Duration::from_secs(30))
@@ -61,29 +205,471 @@ impl RpcClient {
         // Create rate limiter
         let quota = Quota::per_second(NonZeroU32::new(config.rpc.max_requests_per_second).unwrap())
             .allow_burst(NonZeroU32::new(config.rpc.burst_size).unwrap());
-        let rate_limiter = Arc::new(RateLimiter::direct(quota));
+        let rate_limiter = Arc::new(
+            RateLimiter::direct(quota).with_middleware::<StateInformationMiddleware>(),
+        );
 
         Ok(Self {
-            solana_client,
+            solana_client: RwLock::new(solana_client),
             http_client,
             rate_limiter,
-            rpc_url,
+            rate_tracker: Arc::new(Mutex::new(RateLimitTracker {
+                burst_remaining: config.rpc.burst_size,
+                ..Default::default()
+            })),
+            max_requests_per_second: config.rpc.max_requests_per_second,
+            console: None,
+            endpoints,
+            current_index: AtomicUsize::new(0),
+            ws_url,
+            account_subscriptions: Arc::new(Mutex::new(HashMap::new())),
+            next_subscription_id: Arc::new(AtomicU64::new(1)),
+            metrics: MetricsCollector::global(),
+            latency_tracker: Arc::new(Mutex::new(VecDeque::with_capacity(LATENCY_SAMPLES))),
         })
     }
 
+    /// Report quota usage to the console whenever requests are throttled
+    pub fn with_console(mut self, console: Arc<ConsoleManager>) -> Self {
+        console.update_rpc_endpoint(self.get_current_endpoint());
+        self.console = Some(console);
+        self
+    }
+
+    /// Count requests in this collector instead of the global one
+    pub fn with_metrics(mut self, metrics: &'static MetricsCollector) -> Self {
+        self.metrics = metrics;
+        self
+    }
+
+    fn connect(url: &str) -> Arc<SolanaRpcClient> {
+        Arc::new(SolanaRpcClient::new_with_commitment(url.to_string(), CommitmentConfig::confirmed()))
+    }
+
+    /// URL of the endpoint requests are currently sent to
+    pub fn get_current_endpoint(&self) -> &str {
+        &self.endpoints[self.current_index.load(Ordering::Relaxed) % self.endpoints.len()]
+    }
+
+    /// Switch to the next endpoint in the priority list, wrapping around to the first
+    fn rotate_endpoint(&self) {
+        let previous = self.current_index.load(Ordering::Relaxed) % self.endpoints.len();
+        let next = (previous + 1) % self.endpoints.len();
+        self.current_index.store(next, Ordering::Relaxed);
+        *self.solana_client.write().unwrap() = Self::connect(&self.endpoints[next]);
+
+        warn!("RPC endpoint {} failed, switching to {}", self.endpoints[previous], self.endpoints[next]);
+        if let Some(console) = &self.console {
+            console.update_rpc_endpoint(&self.endpoints[next]);
+        }
+    }
+
+    /// Whether `e` means the endpoint itself is unavailable, as opposed to an error
+    /// every endpoint would return for the same request
+    fn is_endpoint_error(e: &ClientError) -> bool {
+        match e.kind() {
+            ClientErrorKind::Io(_) | ClientErrorKind::Reqwest(_) => true,
+            ClientErrorKind::RpcError(RpcError::RpcRequestError(_)) => true,
+            ClientErrorKind::RpcError(RpcError::RpcResponseError { code, .. }) => *code == NODE_UNHEALTHY_ERROR_CODE,
+            _ => false,
+        }
+    }
+
+    /// After a failed attempt, rotate and return true if another endpoint is left to try
+    fn retry_on_next_endpoint(&self, attempts: &mut usize, e: &dyn std::fmt::Display) -> bool {
+        *attempts += 1;
+        if *attempts >= self.endpoints.len() {
+            error!("All {} RPC endpoints failed: {}", self.endpoints.len(), e);
+            return false;
+        }
+        self.rotate_endpoint();
+        true
+    }
+
+    /// Run `call` against the active endpoint, failing over down the priority list
+    async fn with_failover<T>(&self, method: &str, call: impl Fn(&SolanaRpcClient) -> ClientResult<T>) -> ClientResult<T> {
+        let mut attempts = 0;
+        loop {
+            let client = self.solana_client.read().unwrap().clone();
+            self.metrics.record_rpc_call(method);
+            let started = Instant::now();
+            let result = call(&client);
+            self.record_latency(started);
+            match result {
+                Err(e) if Self::is_endpoint_error(&e) && self.retry_on_next_endpoint(&mut attempts, &e) => {
+                    self.metrics.record_rpc_error(method);
+                    self.wait_for_rate_limit().await;
+                }
+                Err(e) => {
+                    self.metrics.record_rpc_error(method);
+                    return Err(e);
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// POST a JSON-RPC request, failing over when the endpoint is unreachable or answers
+    /// with an HTTP error. JSON-RPC errors in the body are left to the caller.
+    async fn post_rpc_request(&self, request_body: &Value) -> Result<Value> {
+        let method = request_body["method"].as_str().unwrap_or("unknown");
+        let mut attempts = 0;
+        loop {
+            self.metrics.record_rpc_call(method);
+            let started = Instant::now();
+            let result = self.post_to_current_endpoint(request_body).await;
+            self.record_latency(started);
+            match result {
+                Err(e) if self.retry_on_next_endpoint(&mut attempts, &e) => {
+                    self.metrics.record_rpc_error(method);
+                    self.wait_for_rate_limit().await;
+                }
+                Err(e) => {
+                    self.metrics.record_rpc_error(method);
+                    return Err(e);
+                }
+                result => return result,
+            }
+        }
+    }
+
+    async fn post_to_current_endpoint(&self, request_body: &Value) -> Result<Value> {
+        let response = self.http_client
+            .post(self.get_current_endpoint())
+            .header("Content-Type", "application/json")
+            .json(request_body)
+            .send()
+            .await
+            .context("Failed to send RPC request")?;
+
+        if !response.status().is_success() {
+            anyhow::bail!("RPC request failed with status: {}", response.status());
+        }
+
+        response.json().await.context("Failed to parse RPC response")
+    }
+
     async fn wait_for_rate_limit(&self) {
-        self.rate_limiter.until_ready().await;
+        let started = Instant::now();
+        let snapshot = self.rate_limiter.until_ready().await;
+        let waited = started.elapsed();
+
+        {
+            let mut tracker = self.rate_tracker.lock().unwrap();
+            let now = Instant::now();
+            tracker.recent_requests.push_back(now);
+            tracker.total_requests += 1;
+            while tracker
+                .recent_requests
+                .front()
+                .is_some_and(|t| now.duration_since(*t) > Duration::from_secs(1))
+            {
+                tracker.recent_requests.pop_front();
+            }
+            tracker.burst_remaining = snapshot.remaining_burst_capacity();
+            tracker.last_wait = waited;
+        }
+
+        // until_ready returns immediately when quota is available; anything longer was a throttle
+        if waited >= Duration::from_millis(1) {
+            debug!("RPC request throttled for {:?}", waited);
+            if let Some(console) = &self.console {
+                console.update_rpc_stats(self.get_rate_limit_stats());
+            }
+        }
+    }
+
+    fn record_latency(&self, started: Instant) {
+        let mut samples = self.latency_tracker.lock().unwrap();
+        if samples.len() == LATENCY_SAMPLES {
+            samples.pop_front();
+        }
+        samples.push_back(started.elapsed().as_millis() as u64);
+    }
+
+    /// Latency in milliseconds that `p` percent of recent requests came in under,
+    /// or `None` before the first request
+    pub fn get_latency_percentile(&self, p: f64) -> Option<u64> {
+        let mut samples: Vec<u64> = self.latency_tracker.lock().unwrap().iter().copied().collect();
+        if samples.is_empty() {
+            return None;
+        }
+        samples.sort_unstable();
+        let rank = (p.clamp(0.0, 100.0) / 100.0 * (samples.len() - 1) as f64).round() as usize;
+        Some(samples[rank])
+    }
+
+    /// Requests made through this client and its clones since startup
+    pub fn total_requests(&self) -> u64 {
+        self.rate_tracker.lock().unwrap().total_requests
+    }
+
+    pub fn get_rate_limit_stats(&self) -> RateLimitStats {
+        let tracker = self.rate_tracker.lock().unwrap();
+        let now = Instant::now();
+        let requests_last_second = tracker
+            .recent_requests
+            .iter()
+            .filter(|t| now.duration_since(**t) <= Duration::from_secs(1))
+            .count() as u32;
+
+        RateLimitStats {
+            requests_last_second,
+            max_requests_per_second: self.max_requests_per_second,
+            burst_remaining: tracker.burst_remaining,
+            wait_time_ms: tracker.last_wait.as_millis() as u64,
+        }
     }
 
     pub fn get_url(&self) -> &str {
-        &self.rpc_url
+        self.get_current_endpoint()
+    }
+
+    pub fn get_ws_url(&self) -> &str {
+        &self.ws_url
+    }
+
+    /// Open a WebSocket connection and `accountSubscribe` to every account.
+    /// Returns once the node has confirmed the subscriptions, or fails if it confirmed
+    /// none of them. Updates arrive on the returned channel, which closes when the
+    /// connection drops.
+    pub async fn subscribe_accounts(&self, accounts: &[Pubkey]) -> Result<mpsc::Receiver<AccountUpdate>> {
+        let (ws_stream, _) = connect_async(&self.ws_url).await
+            .context("Failed to connect to Solana WebSocket")?;
+        let (mut ws_sender, mut ws_receiver) = ws_stream.split();
+
+        // Request ids index into `accounts` so confirmations can be matched back to a pubkey
+        for (id, account) in accounts.iter().enumerate() {
+            let request = json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "method": "accountSubscribe",
+                "params": [
+                    account.to_string(),
+                    { "commitment": "confirmed", "encoding": "base64" }
+                ]
+            });
+            ws_sender.send(Message::Text(request.to_string())).await
+                .context("Failed to send account subscription")?;
+        }
+
+        let accounts = accounts.to_vec();
+        let requested = accounts.len();
+        let confirmed = Arc::new(AtomicUsize::new(0));
+        let (answered_tx, answered_rx) = oneshot::channel();
+        let (tx, rx) = mpsc::channel(1024);
+        let confirmed_in_task = confirmed.clone();
+        let task = tokio::spawn(async move {
+            let mut subscriptions: HashMap<u64, Pubkey> = HashMap::new();
+            let mut answered = 0;
+            let mut answered_tx = Some(answered_tx);
+
+            while let Some(message) = ws_receiver.next().await {
+                let text = match message {
+                    Ok(Message::Text(text)) => text,
+                    Ok(Message::Ping(payload)) => {
+                        if ws_sender.send(Message::Pong(payload)).await.is_err() {
+                            break;
+                        }
+                        continue;
+                    }
+                    Ok(Message::Close(_)) => {
+                        warn!("Account subscription WebSocket closed");
+                        break;
+                    }
+                    Err(e) => {
+                        error!("Account subscription WebSocket error: {}", e);
+                        break;
+                    }
+                    _ => continue,
+                };
+                let Ok(message) = serde_json::from_str::<Value>(&text) else {
+                    continue;
+                };
+
+                // Subscription confirmation: {"id": <request id>, "result": <subscription id>}
+                if let (Some(id), Some(subscription)) = (
+                    message.get("id").and_then(|v| v.as_u64()),
+                    message.get("result").and_then(|v| v.as_u64()),
+                ) {
+                    if let Some(account) = accounts.get(id as usize) {
+                        subscriptions.insert(subscription, *account);
+                        confirmed_in_task.fetch_add(1, Ordering::Relaxed);
+                    }
+                    answered += 1;
+                } else if let (Some(id), Some(error)) = (
+                    message.get("id").and_then(|v| v.as_u64()),
+                    message.get("error"),
+                ) {
+                    if let Some(account) = accounts.get(id as usize) {
+                        warn!("Subscription to account {} rejected: {}", account, error);
+                    }
+                    answered += 1;
+                }
+                if message.get("id").is_some() {
+                    if let Some(answered_tx) = answered_tx.take_if(|_| answered >= requested) {
+                        let _ = answered_tx.send(());
+                    }
+                    continue;
+                }
+
+                if let Some(update) = Self::parse_account_notification(&message, &subscriptions) {
+                    // Receiver dropped, nobody is listening any more
+                    if tx.send(update).await.is_err() {
+                        break;
+                    }
+                }
+            }
+        });
+
+        // A closed connection drops the sender; a timeout keeps whatever was confirmed so far
+        if let Ok(Err(_)) = tokio::time::timeout(SUBSCRIPTION_CONFIRM_TIMEOUT, answered_rx).await {
+            anyhow::bail!("Account subscription WebSocket closed before confirming subscriptions");
+        }
+        let confirmed = confirmed.load(Ordering::Relaxed);
+        if confirmed == 0 {
+            task.abort();
+            anyhow::bail!("Node confirmed none of {} account subscriptions", requested);
+        }
+        if confirmed < requested {
+            warn!("Node confirmed {} of {} account subscriptions", confirmed, requested);
+        }
+
+        Ok(rx)
+    }
+
+    /// Send every change to `pubkey` on `tx`, over a WebSocket connection of its own.
+    /// Returns an id for `unsubscribe_account`; the subscription also ends when the
+    /// connection drops or `tx`'s receiver is dropped.
+    pub async fn subscribe_account_changes(&self, pubkey: &Pubkey, tx: mpsc::Sender<Account>) -> Result<u64> {
+        let (ws_stream, _) = connect_async(&self.ws_url).await
+            .context("Failed to connect to Solana WebSocket")?;
+        let (mut ws_sender, mut ws_receiver) = ws_stream.split();
+
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "accountSubscribe",
+            "params": [
+                pubkey.to_string(),
+                { "commitment": "confirmed", "encoding": "base64" }
+            ]
+        });
+        ws_sender.send(Message::Text(request.to_string())).await
+            .context("Failed to send account subscription")?;
+
+        // Wait for the confirmation so a rejected subscription fails here
+        let server_subscription = loop {
+            let message = ws_receiver.next().await
+                .context("WebSocket closed before the subscription was confirmed")?
+                .context("Account subscription WebSocket error")?;
+            let Message::Text(text) = message else {
+                continue;
+            };
+            let response: Value = serde_json::from_str(&text)
+                .context("Invalid account subscription response")?;
+            if let Some(error) = response.get("error") {
+                anyhow::bail!("Account subscription for {} rejected: {}", pubkey, error);
+            }
+            if let Some(subscription) = response.get("result").and_then(|v| v.as_u64()) {
+                break subscription;
+            }
+        };
+
+        let id = self.next_subscription_id.fetch_add(1, Ordering::Relaxed);
+        let pubkey = *pubkey;
+        let subscriptions = Arc::clone(&self.account_subscriptions);
+        // Held until the handle is stored, so a task that ends at once still removes it
+        let mut active = self.account_subscriptions.lock().unwrap();
+        let handle = tokio::spawn(async move {
+            while let Some(message) = ws_receiver.next().await {
+                let text = match message {
+                    Ok(Message::Text(text)) => text,
+                    Ok(Message::Ping(payload)) => {
+                        if ws_sender.send(Message::Pong(payload)).await.is_err() {
+                            break;
+                        }
+                        continue;
+                    }
+                    Ok(Message::Close(_)) => {
+                        warn!("Account subscription for {} closed", pubkey);
+                        break;
+                    }
+                    Err(e) => {
+                        error!("Account subscription WebSocket error for {}: {}", pubkey, e);
+                        break;
+                    }
+                    _ => continue,
+                };
+                let Some(account) = serde_json::from_str::<Value>(&text).ok()
+                    .and_then(|message| Self::parse_account_change(&message, server_subscription))
+                else {
+                    continue;
+                };
+                // Receiver dropped, nobody is listening any more
+                if tx.send(account).await.is_err() {
+                    break;
+                }
+            }
+            subscriptions.lock().unwrap().remove(&id);
+        });
+        active.insert(id, handle);
+
+        debug!("Subscribed to changes of {} as subscription {}", pubkey, id);
+        Ok(id)
+    }
+
+    /// Stop a subscription made with `subscribe_account_changes`, closing its connection
+    pub fn unsubscribe_account(&self, subscription_id: u64) -> Result<()> {
+        let handle = self.account_subscriptions.lock().unwrap().remove(&subscription_id)
+            .with_context(|| format!("No active account subscription {}", subscription_id))?;
+        handle.abort();
+        debug!("Unsubscribed account subscription {}", subscription_id);
+        Ok(())
+    }
+
+    /// Full account from an `accountNotification` for `subscription`
+    fn parse_account_change(message: &Value, subscription: u64) -> Option<Account> {
+        if message.get("method")?.as_str()? != "accountNotification" {
+            return None;
+        }
+        let params = message.get("params")?;
+        if params.get("subscription")?.as_u64()? != subscription {
+            return None;
+        }
+        let value = params.get("result")?.get("value")?;
+        let data = value.get("data")?.get(0)?.as_str()?;
+
+        Some(Account {
+            lamports: value.get("lamports")?.as_u64()?,
+            data: base64::engine::general_purpose::STANDARD.decode(data).ok()?,
+            owner: Pubkey::from_str(value.get("owner")?.as_str()?).ok()?,
+            executable: value.get("executable").and_then(|v| v.as_bool()).unwrap_or(false),
+            rent_epoch: value.get("rentEpoch").and_then(|v| v.as_u64()).unwrap_or(0),
+        })
+    }
+
+    fn parse_account_notification(message: &Value, subscriptions: &HashMap<u64, Pubkey>) -> Option<AccountUpdate> {
+        if message.get("method")?.as_str()? != "accountNotification" {
+            return None;
+        }
+        let params = message.get("params")?;
+        let pubkey = *subscriptions.get(&params.get("subscription")?.as_u64()?)?;
+        let result = params.get("result")?;
+        let slot = result.get("context")?.get("slot")?.as_u64()?;
+        let data = result.get("value")?.get("data")?.get(0)?.as_str()?;
+
+        Some(AccountUpdate {
+            pubkey,
+            slot,
+            data: base64::engine::general_purpose::STANDARD.decode(data).ok()?,
+        })
     }
 
     pub async fn get_latest_blockhash(&self) -> Result<Hash> {
         self.wait_for_rate_limit().await;
         
-        let blockhash = self.solana_client
-            .get_latest_blockhash()
+        let blockhash = self.with_failover("getLatestBlockhash", |client| client.get_latest_blockhash()).await
             .context("Failed to get latest blockhash")?;
         
         debug!("Retrieved latest blockhash: {}", blockhash);
@@ -93,7 +679,7 @@ impl RpcClient {
     pub async fn get_account(&self, address: &Pubkey) -> Result<Account> {
         self.wait_for_rate_limit().await;
         
-        match self.solana_client.get_account(address) {
+        match self.with_failover("getAccountInfo", |client| client.get_account(address)).await {
             Ok(account) => {
                 debug!("Retrieved account for {}: {} bytes", address, account.data.len());
                 Ok(account)
@@ -114,7 +700,7 @@ impl RpcClient {
     pub async fn get_account_data(&self, address: &Pubkey) -> Result<Vec<u8>> {
         self.wait_for_rate_limit().await;
         
-        match self.solana_client.get_account_data(address) {
+        match self.with_failover("getAccountInfo", |client| client.get_account_data(address)).await {
             Ok(data) => {
                 debug!("Retrieved account data for {}: {} bytes", address, data.len());
                 Ok(data)
@@ -126,10 +712,17 @@ impl RpcClient {
         }
     }
 
-    pub async fn simulate_transaction(&self, transaction: &Transaction) -> Result<RpcSimulateTransactionResult> {
+    /// Decimals, supply and authorities of `mint`, for SPL Token and Token-2022 mints
+    pub async fn get_token_mint_info(&self, mint: &Pubkey) -> Result<MintInfo> {
+        let data = self.get_account_data(mint).await?;
+        MintInfo::parse(&data).with_context(|| format!("Failed to parse mint {}", mint))
+    }
+
+    /// Simulate a legacy or versioned transaction
+    pub async fn simulate_transaction(&self, transaction: &(impl SerializableTransaction + Sync)) -> Result<RpcSimulateTransactionResult> {
         self.wait_for_rate_limit().await;
         
-        match self.solana_client.simulate_transaction(transaction) {
+        match self.with_failover("simulateTransaction", |client| client.simulate_transaction(transaction)).await {
             Ok(result) => {
                 debug!("Transaction simulation completed");
                 Ok(result.value)
@@ -141,10 +734,11 @@ impl RpcClient {
         }
     }
 
-    pub async fn send_transaction(&self, transaction: &Transaction) -> Result<Signature> {
+    /// Send a legacy or versioned transaction
+    pub async fn send_transaction(&self, transaction: &(impl SerializableTransaction + Sync)) -> Result<Signature> {
         self.wait_for_rate_limit().await;
         
-        match self.solana_client.send_transaction(transaction) {
+        match self.with_failover("sendTransaction", |client| client.send_transaction(transaction)).await {
             Ok(signature) => {
                 debug!("Transaction sent successfully: {}", signature);
                 Ok(signature)
@@ -156,10 +750,28 @@ impl RpcClient {
         }
     }
 
+    /// Send without the RPC node re-simulating the transaction first; only for
+    /// transactions the caller has already simulated
+    pub async fn send_transaction_skip_preflight(&self, transaction: &(impl SerializableTransaction + Sync)) -> Result<Signature> {
+        self.wait_for_rate_limit().await;
+
+        let config = RpcSendTransactionConfig { skip_preflight: true, ..Default::default() };
+        match self.with_failover("sendTransaction", |client| client.send_transaction_with_config(transaction, config)).await {
+            Ok(signature) => {
+                debug!("Transaction sent without preflight: {}", signature);
+                Ok(signature)
+            }
+            Err(e) => {
+                error!("Failed to send transaction: {}", e);
+                anyhow::bail!("Transaction send failed: {}", e);
+            }
+        }
+    }
+
     pub async fn get_signature_status(&self, signature: &Signature) -> Result<bool> {
         self.wait_for_rate_limit().await;
         
-        match self.solana_client.get_signature_status(signature) {
+        match self.with_failover("getSignatureStatuses", |client| client.get_signature_status(signature)).await {
             Ok(Some(Ok(()))) => Ok(true),
             Ok(Some(Err(_))) => Ok(false),
             Ok(None) => Ok(false),
@@ -170,6 +782,45 @@ impl RpcClient {
         }
     }
 
+    /// Whether transactions signed with `blockhash` can still land; once this is
+    /// false they never will
+    pub async fn is_blockhash_valid(&self, blockhash: &Hash) -> Result<bool> {
+        self.wait_for_rate_limit().await;
+
+        self.with_failover("isBlockhashValid", |client| client.is_blockhash_valid(blockhash, CommitmentConfig::processed())).await
+            .context("Failed to check blockhash validity")
+    }
+
+    /// Signatures touching `address`, newest first, stopping at `until` if it is found
+    pub async fn get_signatures_for_address(
+        &self,
+        address: &Pubkey,
+        until: Option<&str>,
+        limit: usize,
+    ) -> Result<Vec<RpcConfirmedTransactionStatusWithSignature>> {
+        self.wait_for_rate_limit().await;
+
+        let until = until.map(Signature::from_str).transpose()
+            .context("Invalid until signature")?;
+        let config = || GetConfirmedSignaturesForAddress2Config {
+            before: None,
+            until,
+            limit: Some(limit),
+            commitment: Some(CommitmentConfig::confirmed()),
+        };
+
+        match self.with_failover("getSignaturesForAddress", |client| client.get_signatures_for_address_with_config(address, config())).await {
+            Ok(signatures) => {
+                debug!("Retrieved {} signatures for {}", signatures.len(), address);
+                Ok(signatures)
+            }
+            Err(e) => {
+                error!("Failed to get signatures for {}: {}", address, e);
+                anyhow::bail!("Signature lookup failed: {}", e);
+            }
+        }
+    }
+
     pub async fn get_transaction_info(&self, signature: &str) -> Result<Value> {
         self.wait_for_rate_limit().await;
         
@@ -187,20 +838,8 @@ impl RpcClient {
             ]
         });
 
-        let response = self.http_client
-            .post(&self.rpc_url)
-            .header("Content-Type", "application/json")
-            .json(&request_body)
-            .send()
-            .await
-            .context("Failed to send transaction info request")?;
-
-        if !response.status().is_success() {
-            anyhow::bail!("RPC request failed with status: {}", response.status());
-        }
-
-        let response_json: Value = response.json().await
-            .context("Failed to parse transaction info response")?;
+        let response_json = self.post_rpc_request(&request_body).await
+            .context("Transaction info request failed")?;
 
         if let Some(error) = response_json.get("error") {
             anyhow::bail!("RPC error: {}", error);
@@ -211,10 +850,54 @@ impl RpcClient {
             .context("No result in transaction info response")
     }
 
+    /// Compute unit price, in microlamports, paid at `percentile` (1-100) of the last 150 slots
+    pub async fn get_recent_priority_fees(&self, percentile: u8) -> Result<u64> {
+        self.wait_for_rate_limit().await;
+
+        let request_body = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "getRecentPrioritizationFees",
+            "params": []
+        });
+
+        let response_json = self.post_rpc_request(&request_body).await
+            .context("Prioritization fees request failed")?;
+
+        Self::parse_priority_fees(&response_json, percentile)
+    }
+
+    fn parse_priority_fees(response: &Value, percentile: u8) -> Result<u64> {
+        if let Some(error) = response.get("error") {
+            anyhow::bail!("RPC error: {}", error);
+        }
+
+        let entries = response.get("result")
+            .and_then(|result| result.as_array())
+            .context("No result in prioritization fees response")?;
+
+        let mut samples: Vec<(u64, u64)> = entries.iter()
+            .filter_map(|entry| Some((entry.get("slot")?.as_u64()?, entry.get("prioritizationFee")?.as_u64()?)))
+            .collect();
+        if samples.is_empty() {
+            return Ok(0);
+        }
+
+        // Keep the newest slots only, then take the fee at the requested percentile
+        samples.sort_unstable_by_key(|(slot, _)| std::cmp::Reverse(*slot));
+        samples.truncate(PRIORITY_FEE_SLOTS);
+        let mut fees: Vec<u64> = samples.into_iter().map(|(_, fee)| fee).collect();
+        fees.sort_unstable();
+
+        let percentile = percentile.clamp(1, 100) as usize;
+        let index = (fees.len() * percentile).div_ceil(100) - 1;
+        Ok(fees[index])
+    }
+
     pub async fn get_multiple_accounts(&self, addresses: &[Pubkey]) -> Result<Vec<Option<Account>>> {
         self.wait_for_rate_limit().await;
         
-        match self.solana_client.get_multiple_accounts(addresses) {
+        match self.with_failover("getMultipleAccounts", |client| client.get_multiple_accounts(addresses)).await {
             Ok(accounts) => {
                 debug!("Retrieved {} accounts", accounts.len());
                 Ok(accounts)
@@ -226,10 +909,30 @@ impl RpcClient {
         }
     }
 
+    /// Balances of many token accounts in one `getMultipleAccounts` call, in input order;
+    /// `None` for accounts that don't exist or aren't SPL Token accounts
+    pub async fn get_multiple_token_account_balances(&self, accounts: &[Pubkey]) -> Result<Vec<Option<u64>>> {
+        if accounts.is_empty() {
+            return Ok(Vec::new());
+        }
+        let accounts = self.get_multiple_accounts(accounts).await?;
+        Ok(accounts.iter()
+            .map(|account| account.as_ref().and_then(Self::parse_token_account_amount))
+            .collect())
+    }
+
+    fn parse_token_account_amount(account: &Account) -> Option<u64> {
+        if account.data.len() != TOKEN_ACCOUNT_LEN || account.owner != spl_token::id() {
+            return None;
+        }
+        let amount = account.data[TOKEN_ACCOUNT_AMOUNT_OFFSET..TOKEN_ACCOUNT_AMOUNT_OFFSET + 8].try_into().ok()?;
+        Some(u64::from_le_bytes(amount))
+    }
+
     pub async fn get_token_account_balance(&self, token_account: &Pubkey) -> Result<u64> {
         self.wait_for_rate_limit().await;
         
-        match self.solana_client.get_token_account_balance(token_account) {
+        match self.with_failover("getTokenAccountBalance", |client| client.get_token_account_balance(token_account)).await {
             Ok(balance) => {
                 let amount = balance.amount.parse::<u64>()
                     .context("Failed to parse token balance")?;
@@ -254,7 +957,7 @@ impl RpcClient {
     pub async fn get_sol_balance(&self, address: &Pubkey) -> Result<u64> {
         self.wait_for_rate_limit().await;
         
-        match self.solana_client.get_balance(address) {
+        match self.with_failover("getBalance", |client| client.get_balance(address)).await {
             Ok(balance) => {
                 debug!("SOL balance for {}: {} lamports", address, balance);
                 Ok(balance)
@@ -269,7 +972,7 @@ impl RpcClient {
     pub async fn get_recent_blockhash(&self) -> Result<(Hash, u64)> {
         self.wait_for_rate_limit().await;
         
-        match self.solana_client.get_latest_blockhash() {
+        match self.with_failover("getLatestBlockhash", |client| client.get_latest_blockhash()).await {
             Ok(hash) => {
                 debug!("Recent blockhash: {}", hash);
                 Ok((hash, 0)) // Assuming fee_calculator is no longer needed or can be set to a default/dummy value
@@ -284,7 +987,7 @@ impl RpcClient {
     pub async fn send_and_confirm_transaction(&self, transaction: &Transaction) -> Result<Signature> {
         self.wait_for_rate_limit().await;
         
-        match self.solana_client.send_and_confirm_transaction(transaction) {
+        match self.with_failover("sendTransaction", |client| client.send_and_confirm_transaction(transaction)).await {
             Ok(signature) => {
                 debug!("Transaction sent and confirmed: {}", signature);
                 Ok(signature)
@@ -299,7 +1002,7 @@ impl RpcClient {
     pub async fn get_minimum_balance_for_rent_exemption(&self, data_len: usize) -> Result<u64> {
         self.wait_for_rate_limit().await;
         
-        match self.solana_client.get_minimum_balance_for_rent_exemption(data_len) {
+        match self.with_failover("getMinimumBalanceForRentExemption", |client| client.get_minimum_balance_for_rent_exemption(data_len)).await {
             Ok(balance) => {
                 debug!("Minimum balance for {} bytes: {} lamports", data_len, balance);
                 Ok(balance)
@@ -321,7 +1024,7 @@ impl RpcClient {
     pub async fn get_epoch_info(&self) -> Result<EpochInfo> {
         self.wait_for_rate_limit().await;
         
-        match self.solana_client.get_epoch_info() {
+        match self.with_failover("getEpochInfo", |client| client.get_epoch_info()).await {
             Ok(epoch_info) => {
                 debug!("Epoch info: {:?}", epoch_info);
                 Ok(epoch_info)
@@ -336,7 +1039,7 @@ impl RpcClient {
     pub async fn get_program_accounts(&self, program_id: &Pubkey) -> Result<Vec<(Pubkey, Account)>> {
         self.wait_for_rate_limit().await;
         
-        match self.solana_client.get_program_accounts(program_id) {
+        match self.with_failover("getProgramAccounts", |client| client.get_program_accounts(program_id)).await {
             Ok(accounts) => {
                 debug!("Retrieved {} program accounts for {}", accounts.len(), program_id);
                 Ok(accounts)
@@ -348,10 +1051,78 @@ impl RpcClient {
         }
     }
 
+    /// Program accounts matching `filters`, evaluated server-side so only the
+    /// wanted account type is transferred
+    pub async fn get_program_accounts_with_filters(
+        &self,
+        program_id: &Pubkey,
+        filters: &[RpcFilterType],
+    ) -> Result<Vec<(Pubkey, Account)>> {
+        self.fetch_program_accounts(program_id, filters, None).await
+    }
+
+    /// Like `get_program_accounts_with_filters`, but only `data_slice` of each
+    /// account's data is returned
+    pub async fn get_program_accounts_with_data_slice(
+        &self,
+        program_id: &Pubkey,
+        filters: &[RpcFilterType],
+        data_slice: UiDataSliceConfig,
+    ) -> Result<Vec<(Pubkey, Account)>> {
+        self.fetch_program_accounts(program_id, filters, Some(data_slice)).await
+    }
+
+    async fn fetch_program_accounts(
+        &self,
+        program_id: &Pubkey,
+        filters: &[RpcFilterType],
+        data_slice: Option<UiDataSliceConfig>,
+    ) -> Result<Vec<(Pubkey, Account)>> {
+        self.wait_for_rate_limit().await;
+
+        let config = RpcProgramAccountsConfig {
+            filters: Some(filters.to_vec()),
+            account_config: RpcAccountInfoConfig {
+                encoding: Some(UiAccountEncoding::Base64),
+                data_slice,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        match self.with_failover("getProgramAccounts", |client| client.get_program_accounts_with_config(program_id, config.clone())).await {
+            Ok(accounts) => {
+                debug!("Retrieved {} filtered program accounts for {}", accounts.len(), program_id);
+                Ok(accounts)
+            }
+            Err(e) => {
+                error!("Failed to get filtered program accounts for {}: {}", program_id, e);
+                anyhow::bail!("Program accounts fetch failed: {}", e);
+            }
+        }
+    }
+
+    /// Addresses of every SPL token account owned by `owner`
+    pub async fn get_token_accounts_by_owner(&self, owner: &Pubkey) -> Result<Vec<Pubkey>> {
+        self.wait_for_rate_limit().await;
+
+        match self.with_failover("getTokenAccountsByOwner", |client| client.get_token_accounts_by_owner(owner, TokenAccountsFilter::ProgramId(spl_token::id()))).await {
+            Ok(accounts) => {
+                debug!("Retrieved {} token accounts for {}", accounts.len(), owner);
+                accounts.iter()
+                    .map(|account| Pubkey::from_str(&account.pubkey).context("Invalid token account address"))
+                    .collect()
+            }
+            Err(e) => {
+                error!("Failed to get token accounts for {}: {}", owner, e);
+                anyhow::bail!("Token accounts fetch failed: {}", e);
+            }
+        }
+    }
+
     pub async fn get_health(&self) -> Result<()> {
         self.wait_for_rate_limit().await;
         
-        match self.solana_client.get_health() {
+        match self.with_failover("getHealth", |client| client.get_health()).await {
             Ok(_) => {
                 debug!("RPC health check passed");
                 Ok(())
@@ -369,7 +1140,7 @@ impl RpcClient {
     pub async fn try_get_account(&self, address: &Pubkey) -> Result<Option<Account>> {
         self.wait_for_rate_limit().await;
         
-        match self.solana_client.get_account(address) {
+        match self.with_failover("getAccountInfo", |client| client.get_account(address)).await {
             Ok(account) => {
                 debug!("Retrieved account for {}: {} bytes", address, account.data.len());
                 Ok(Some(account))
@@ -388,10 +1159,21 @@ impl RpcClient {
     }
 
     /// Get token account balance if valid, returns None if account doesn't exist or isn't a token account
+    /// Account at `address`, if it exists, and the slot the node read it at
+    pub async fn get_account_with_slot(&self, address: &Pubkey) -> Result<(Option<Account>, u64)> {
+        self.wait_for_rate_limit().await;
+
+        let response = self.with_failover("getAccountInfo", |client| {
+            client.get_account_with_commitment(address, client.commitment())
+        }).await
+            .with_context(|| format!("Account fetch failed for {}", address))?;
+        Ok((response.value, response.context.slot))
+    }
+
     pub async fn try_get_token_account_balance(&self, token_account: &Pubkey) -> Result<Option<u64>> {
         self.wait_for_rate_limit().await;
         
-        match self.solana_client.get_token_account_balance(token_account) {
+        match self.with_failover("getTokenAccountBalance", |client| client.get_token_account_balance(token_account)).await {
             Ok(balance) => {
                 let amount = balance.amount.parse::<u64>()
                     .context("Failed to parse token balance")?;
@@ -415,7 +1197,7 @@ impl RpcClient {
     pub async fn try_get_sol_balance(&self, address: &Pubkey) -> Result<Option<u64>> {
         self.wait_for_rate_limit().await;
         
-        match self.solana_client.get_balance(address) {
+        match self.with_failover("getBalance", |client| client.get_balance(address)).await {
             Ok(balance) => {
                 debug!("SOL balance for {}: {} lamports", address, balance);
                 Ok(Some(balance))
@@ -433,3 +1215,195 @@ impl RpcClient {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_token_account_amount() {
+        let mut data = vec![0u8; TOKEN_ACCOUNT_LEN];
+        data[64..72].copy_from_slice(&1_234_567u64.to_le_bytes());
+        let account = Account {
+            lamports: 2_039_280,
+            data,
+            owner: spl_token::id(),
+            executable: false,
+            rent_epoch: 0,
+        };
+        assert_eq!(RpcClient::parse_token_account_amount(&account), Some(1_234_567));
+
+        let foreign = Account { owner: Pubkey::new_unique(), ..account.clone() };
+        assert_eq!(RpcClient::parse_token_account_amount(&foreign), None);
+
+        let mint = Account { data: vec![0u8; MINT_ACCOUNT_LEN], ..account };
+        assert_eq!(RpcClient::parse_token_account_amount(&mint), None);
+    }
+
+    #[test]
+    fn test_parse_mint_info() {
+        use solana_sdk::program_pack::Pack;
+
+        let mint_authority = Pubkey::new_unique();
+        let freeze_authority = Pubkey::new_unique();
+        let mut data = vec![0u8; spl_token::state::Mint::LEN];
+        spl_token::state::Mint {
+            mint_authority: Some(mint_authority).into(),
+            supply: 1_000_000_000,
+            decimals: 6,
+            is_initialized: true,
+            freeze_authority: Some(freeze_authority).into(),
+        }.pack_into_slice(&mut data);
+
+        let info = MintInfo::parse(&data).unwrap();
+        assert_eq!(info, MintInfo {
+            decimals: 6,
+            supply: 1_000_000_000,
+            freeze_authority: Some(freeze_authority),
+            mint_authority: Some(mint_authority),
+        });
+
+        // Token-2022 extensions follow the same prefix
+        data.extend([0u8; 83]);
+        data[0..4].copy_from_slice(&0u32.to_le_bytes());
+        assert_eq!(MintInfo::parse(&data).unwrap().mint_authority, None);
+
+        assert!(MintInfo::parse(&data[..81]).is_err());
+        data[45] = 0;
+        assert!(MintInfo::parse(&data).is_err());
+    }
+
+    #[test]
+    fn test_endpoint_priority_and_rotation() {
+        let mut config = Config::default();
+        config.rpc.quicknode_rpc_url = Some("https://primary.example.com".to_string());
+        config.rpc.fallback_rpc_urls = vec![
+            "https://fallback.example.com".to_string(),
+            "https://primary.example.com".to_string(),
+        ];
+        let client = RpcClient::new(&config).unwrap();
+
+        assert_eq!(client.endpoints, [
+            "https://primary.example.com",
+            "https://fallback.example.com",
+            "https://api.mainnet-beta.solana.com",
+        ]);
+        assert_eq!(client.get_current_endpoint(), "https://primary.example.com");
+
+        client.rotate_endpoint();
+        assert_eq!(client.get_current_endpoint(), "https://fallback.example.com");
+        assert_eq!(client.solana_client.read().unwrap().url(), "https://fallback.example.com");
+        client.rotate_endpoint();
+        client.rotate_endpoint();
+        assert_eq!(client.get_url(), "https://primary.example.com");
+
+        // Errors every node would return don't trigger a failover
+        let unavailable = ClientError::from(ClientErrorKind::Io(std::io::ErrorKind::ConnectionRefused.into()));
+        assert!(RpcClient::is_endpoint_error(&unavailable));
+        let behind = ClientError::from(ClientErrorKind::RpcError(RpcError::RpcResponseError {
+            code: NODE_UNHEALTHY_ERROR_CODE,
+            message: "Node is behind".to_string(),
+            data: solana_client::rpc_request::RpcResponseErrorData::Empty,
+        }));
+        assert!(RpcClient::is_endpoint_error(&behind));
+        let missing = ClientError::from(ClientErrorKind::RpcError(RpcError::ForUser("AccountNotFound".to_string())));
+        assert!(!RpcClient::is_endpoint_error(&missing));
+    }
+
+    #[test]
+    fn test_parse_account_notification() {
+        let pool = Pubkey::new_unique();
+        let subscriptions = HashMap::from([(23784, pool)]);
+        let notification = json!({
+            "jsonrpc": "2.0",
+            "method": "accountNotification",
+            "params": {
+                "subscription": 23784,
+                "result": {
+                    "context": { "slot": 5199307 },
+                    "value": { "data": ["AQID", "base64"], "lamports": 33594, "owner": "11111111111111111111111111111111" }
+                }
+            }
+        });
+
+        assert_eq!(
+            RpcClient::parse_account_notification(&notification, &subscriptions),
+            Some(AccountUpdate { pubkey: pool, slot: 5199307, data: vec![1, 2, 3] })
+        );
+        assert_eq!(RpcClient::parse_account_notification(&notification, &HashMap::new()), None);
+        assert_eq!(RpcClient::parse_account_notification(&json!({ "id": 0, "result": 23784 }), &subscriptions), None);
+    }
+
+    #[test]
+    fn test_parse_account_change() {
+        let notification = json!({
+            "jsonrpc": "2.0",
+            "method": "accountNotification",
+            "params": {
+                "subscription": 23784,
+                "result": {
+                    "context": { "slot": 5199307 },
+                    "value": {
+                        "data": ["AQID", "base64"],
+                        "executable": false,
+                        "lamports": 33594,
+                        "owner": "11111111111111111111111111111111",
+                        "rentEpoch": 635
+                    }
+                }
+            }
+        });
+
+        let account = RpcClient::parse_account_change(&notification, 23784).unwrap();
+        assert_eq!(account.lamports, 33594);
+        assert_eq!(account.data, vec![1, 2, 3]);
+        assert_eq!(account.owner, solana_sdk::system_program::id());
+        assert_eq!(account.rent_epoch, 635);
+        assert!(RpcClient::parse_account_change(&notification, 1).is_none());
+    }
+
+    #[test]
+    fn test_unsubscribe_unknown_account_subscription() {
+        let rpc_client = RpcClient::new(&Config::default()).unwrap();
+        assert!(rpc_client.unsubscribe_account(42).is_err());
+    }
+
+    #[test]
+    fn test_parse_priority_fees() {
+        // 200 slots: the 50 oldest pay a huge fee and must fall outside the window
+        let result: Vec<Value> = (0..200u64)
+            .map(|slot| {
+                let fee = if slot < 50 { 1_000_000 } else { (slot - 50) * 10 };
+                json!({ "slot": 1000 + slot, "prioritizationFee": fee })
+            })
+            .collect();
+        let response = json!({ "jsonrpc": "2.0", "id": 1, "result": result });
+
+        // Fees 0..=1490 in steps of 10, 75th percentile of 150 samples is the 113th
+        assert_eq!(RpcClient::parse_priority_fees(&response, 75).unwrap(), 1120);
+        // The median is the 75th sample, the 100th percentile the largest in the window
+        assert_eq!(RpcClient::parse_priority_fees(&response, 50).unwrap(), 740);
+        assert_eq!(RpcClient::parse_priority_fees(&response, 100).unwrap(), 1490);
+        assert_eq!(RpcClient::parse_priority_fees(&json!({ "result": [] }), 75).unwrap(), 0);
+        assert!(RpcClient::parse_priority_fees(&json!({ "error": { "code": -32601 } }), 75).is_err());
+    }
+
+    #[test]
+    fn test_latency_percentiles() {
+        let client = RpcClient::new(&Config::default()).unwrap();
+        assert_eq!(client.get_latency_percentile(50.0), None);
+
+        client.latency_tracker.lock().unwrap().extend((1..=100).rev());
+        assert_eq!(client.get_latency_percentile(0.0), Some(1));
+        assert_eq!(client.get_latency_percentile(50.0), Some(51));
+        assert_eq!(client.get_latency_percentile(95.0), Some(95));
+        assert_eq!(client.get_latency_percentile(100.0), Some(100));
+
+        // Only the newest samples are kept
+        for _ in 0..LATENCY_SAMPLES {
+            client.record_latency(Instant::now());
+        }
+        assert_eq!(client.latency_tracker.lock().unwrap().len(), LATENCY_SAMPLES);
+        assert_eq!(client.get_latency_percentile(99.0), Some(0));
+    }
+}
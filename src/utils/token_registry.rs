@@ -0,0 +1,201 @@
+use crate::{models::TokenInfo, utils::rpc::{MintInfo, RpcClient}};
+use anyhow::{Context, Result};
+use solana_sdk::pubkey::Pubkey;
+use std::{collections::HashMap, str::FromStr, sync::Arc};
+use tokio::sync::RwLock;
+use tracing::debug;
+
+pub const TOKEN_METADATA_PROGRAM_ID: &str = "metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s";
+
+/// Well-known mints that never need a lookup: (mint, symbol, decimals)
+pub const COMMON_TOKENS: [(&str, &str, u8); 6] = [
+    ("So11111111111111111111111111111111111111112", "SOL", 9),
+    ("EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v", "USDC", 6),
+    ("Es9vMFrzaCERmJfrF4H2FYD4KCoNkY11McCe8BenwNYB", "USDT", 6),
+    ("4k3Dyjzvzp8eMZWUXbBCjEvwSkkk59S5iCNLY3QrkX6R", "RAY", 6),
+    ("orcaEKTdK7LKz57vaAYr9QeNsVEPfiu6QeMU1kektZE", "ORCA", 6),
+    ("DezXAZ8z7PnrnRJjz3wXBoRgixCa6xjnB7YaB1pPB263", "BONK", 5),
+];
+
+/// Symbol used for mints without Metaplex metadata
+pub const UNKNOWN_SYMBOL: &str = "UNK";
+
+/// `getMultipleAccounts` accepts at most this many addresses per call
+const MAX_ACCOUNTS_PER_BATCH: usize = 100;
+/// Metadata account: key (1), update authority (32) and mint (32) precede the name
+const METADATA_NAME_OFFSET: usize = 65;
+
+/// Token symbols and decimals read from SPL mint and Metaplex metadata accounts
+pub struct TokenRegistry {
+    rpc_client: Arc<RpcClient>,
+    tokens: RwLock<HashMap<Pubkey, TokenInfo>>,
+    /// Mint state as first read; kept for good since decimals never change
+    mint_info: RwLock<HashMap<Pubkey, MintInfo>>,
+}
+
+impl TokenRegistry {
+    pub fn new(rpc_client: Arc<RpcClient>) -> Self {
+        let tokens = COMMON_TOKENS.iter()
+            .map(|(mint, symbol, decimals)| {
+                let mint = Pubkey::from_str(mint).unwrap();
+                (mint, TokenInfo {
+                    mint,
+                    symbol: symbol.to_string(),
+                    decimals: *decimals,
+                    price_usd: None,
+                })
+            })
+            .collect();
+
+        Self {
+            rpc_client,
+            tokens: RwLock::new(tokens),
+            mint_info: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Mint state for `mint`, read once over RPC. Supply and authorities may have
+    /// moved on since; only the decimals are guaranteed current.
+    pub async fn mint_info(&self, mint: &Pubkey) -> Result<MintInfo> {
+        if let Some(info) = self.mint_info.read().await.get(mint) {
+            return Ok(*info);
+        }
+
+        let info = self.rpc_client.get_token_mint_info(mint).await?;
+        self.mint_info.write().await.insert(*mint, info);
+        Ok(info)
+    }
+
+    /// Decimals of `mint` from the registry, or from the mint account itself when
+    /// its metadata couldn't be resolved
+    pub async fn decimals(&self, mint: &Pubkey) -> Result<u8> {
+        if let Some(token) = self.tokens.read().await.get(mint) {
+            return Ok(token.decimals);
+        }
+        Ok(self.mint_info(mint).await?.decimals)
+    }
+
+    pub async fn resolve(&self, mint: &Pubkey) -> Result<TokenInfo> {
+        if let Some(token) = self.tokens.read().await.get(mint) {
+            return Ok(token.clone());
+        }
+
+        self.resolve_many(std::slice::from_ref(mint)).await?;
+        self.tokens.read().await.get(mint).cloned()
+            .with_context(|| format!("Mint account {} not found", mint))
+    }
+
+    /// Look up every uncached mint in `mints` with batched account reads, so
+    /// per-pool `resolve` calls afterwards are served from the cache
+    pub async fn resolve_many(&self, mints: &[Pubkey]) -> Result<()> {
+        let missing: Vec<Pubkey> = {
+            let tokens = self.tokens.read().await;
+            let mut missing: Vec<Pubkey> = mints.iter().filter(|mint| !tokens.contains_key(mint)).copied().collect();
+            missing.sort_unstable();
+            missing.dedup();
+            missing
+        };
+        if missing.is_empty() {
+            return Ok(());
+        }
+
+        let metadata_program_id = Pubkey::from_str(TOKEN_METADATA_PROGRAM_ID)?;
+        // Each batch reads half mints, half their metadata PDAs
+        for chunk in missing.chunks(MAX_ACCOUNTS_PER_BATCH / 2) {
+            let mut addresses = chunk.to_vec();
+            addresses.extend(chunk.iter().map(|mint| Self::metadata_address(mint, &metadata_program_id)));
+            let accounts = self.rpc_client.get_multiple_accounts(&addresses).await?;
+            let (mint_accounts, metadata_accounts) = accounts.split_at(chunk.len());
+
+            let mut tokens = self.tokens.write().await;
+            let mut mint_info = self.mint_info.write().await;
+            for ((mint, mint_account), metadata_account) in chunk.iter().zip(mint_accounts).zip(metadata_accounts) {
+                let Some(info) = mint_account.as_ref()
+                    .and_then(|account| MintInfo::parse(&account.data).ok())
+                else {
+                    debug!("Skipping {}: not an SPL mint", mint);
+                    continue;
+                };
+                let decimals = info.decimals;
+                mint_info.insert(*mint, info);
+
+                let (name, symbol) = metadata_account.as_ref()
+                    .and_then(|account| Self::parse_metadata(&account.data))
+                    .unwrap_or_default();
+                debug!("Resolved {} as {} ({}), {} decimals", mint, symbol, name, decimals);
+
+                tokens.insert(*mint, TokenInfo {
+                    mint: *mint,
+                    symbol: if symbol.is_empty() { UNKNOWN_SYMBOL.to_string() } else { symbol },
+                    decimals,
+                    price_usd: None,
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn metadata_address(mint: &Pubkey, metadata_program_id: &Pubkey) -> Pubkey {
+        Pubkey::find_program_address(
+            &[b"metadata", metadata_program_id.as_ref(), mint.as_ref()],
+            metadata_program_id,
+        ).0
+    }
+
+    /// Name and symbol from a Metaplex `Metadata` account; both are borsh strings
+    /// padded with NULs to a fixed width
+    fn parse_metadata(data: &[u8]) -> Option<(String, String)> {
+        let (name, offset) = Self::read_string(data, METADATA_NAME_OFFSET)?;
+        let (symbol, _) = Self::read_string(data, offset)?;
+        Some((name, symbol))
+    }
+
+    fn read_string(data: &[u8], offset: usize) -> Option<(String, usize)> {
+        let len = u32::from_le_bytes(data.get(offset..offset + 4)?.try_into().ok()?) as usize;
+        let bytes = data.get(offset + 4..offset + 4 + len)?;
+        let value = String::from_utf8_lossy(bytes).trim_end_matches('\0').trim().to_string();
+        Some((value, offset + 4 + len))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn borsh_string(value: &str, width: usize) -> Vec<u8> {
+        let mut padded = value.as_bytes().to_vec();
+        padded.resize(width, 0);
+        let mut bytes = (padded.len() as u32).to_le_bytes().to_vec();
+        bytes.extend(padded);
+        bytes
+    }
+
+    #[test]
+    fn test_parse_metadata() {
+        let mut data = vec![4u8];
+        data.extend(Pubkey::new_unique().to_bytes());
+        data.extend(Pubkey::new_unique().to_bytes());
+        data.extend(borsh_string("Jupiter", 32));
+        data.extend(borsh_string("JUP", 10));
+        data.extend(borsh_string("https://example.com", 200));
+
+        assert_eq!(
+            TokenRegistry::parse_metadata(&data),
+            Some(("Jupiter".to_string(), "JUP".to_string()))
+        );
+        assert_eq!(TokenRegistry::parse_metadata(&data[..70]), None);
+    }
+
+    #[tokio::test]
+    async fn test_common_tokens_preloaded() {
+        let config = crate::config::Config::default();
+        let rpc_client = Arc::new(RpcClient::new(&config).unwrap());
+        let registry = TokenRegistry::new(rpc_client);
+
+        let usdc = Pubkey::from_str("EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v").unwrap();
+        let token = registry.resolve(&usdc).await.unwrap();
+        assert_eq!(token.symbol, "USDC");
+        assert_eq!(token.decimals, 6);
+    }
+}
@@ -1,18 +1,17 @@
 use solana_arbitrage_bot::{
-    config::{Config, BotConfig, DexConfig, RpcConfig, MonitoringConfig, RiskManagementConfig},
+    config::{Config, BotConfig, DexConfig, RpcConfig, MonitoringConfig, RiskManagementConfig, BotScoringConfig},
     engine::{screener::Screener, executor::Executor},
-    dex::{orca::OrcaClient, raydium::RaydiumClient, phoenix::PhoenixClient, DexClient},
+    dex::{orca::OrcaClient, raydium::RaydiumClient, phoenix::PhoenixClient, openbook::OpenBookClient, meteora::MeteoraDex, DexClient},
     models::{Pool, TokenInfo},
-    utils::{rpc::RpcClient, cache::PoolCache},
+    utils::{rpc::RpcClient, cache::PoolCache, token_registry::TokenRegistry},
     console::ConsoleManager,
 };
 use rust_decimal::Decimal;
 use solana_sdk::pubkey::Pubkey;
 use std::sync::Arc;
 use tokio::time::{sleep, Duration};
-use chrono;
 
-#[tokio::test]
+#[tokio::test(flavor = "multi_thread")]
 async fn test_full_arbitrage_workflow() {
     // Initialize logging for tests
     let _ = tracing_subscriber::fmt::try_init();
@@ -29,18 +28,19 @@ async fn test_full_arbitrage_workflow() {
     // Initialize RPC client
     let rpc_client = Arc::new(RpcClient::new(&config)
         .expect("Failed to create RPC client"));
+    let token_registry = Arc::new(TokenRegistry::new(rpc_client.clone()));
     
     // Initialize DEX clients
     let mut dex_clients = Vec::new();
     
     if config.dexs.enabled.contains(&"orca".to_string()) {
-        let orca_client = OrcaClient::new(rpc_client.clone(), console.clone())
+        let orca_client = OrcaClient::new(rpc_client.clone(), console.clone(), token_registry.clone())
             .expect("Failed to create Orca client");
         dex_clients.push(Arc::new(orca_client) as Arc<dyn DexClient>);
     }
     
     if config.dexs.enabled.contains(&"raydium".to_string()) {
-        let raydium_client = RaydiumClient::new(rpc_client.clone(), console.clone())
+        let raydium_client = RaydiumClient::new(rpc_client.clone(), console.clone(), token_registry.clone())
             .expect("Failed to create Raydium client");
         dex_clients.push(Arc::new(raydium_client) as Arc<dyn DexClient>);
     }
@@ -106,7 +106,7 @@ async fn test_cache_performance_under_load() {
     
     // Generate test pools
     let pools: Vec<Pool> = (0..num_pools)
-        .map(|i| create_test_pool(i))
+        .map(create_test_pool)
         .collect();
     
     // Test cache performance under load
@@ -175,19 +175,20 @@ async fn test_error_recovery() {
     assert!(opportunities.unwrap().is_empty(), "Should return empty opportunities list");
 }
 
-#[tokio::test]
+#[tokio::test(flavor = "multi_thread")]
 async fn test_concurrent_arbitrage_detection() {
     let config = load_test_config();
     let console = Arc::new(ConsoleManager::new());
     let rpc_client = Arc::new(RpcClient::new(&config)
         .expect("Failed to create RPC client"));
+    let token_registry = Arc::new(TokenRegistry::new(rpc_client.clone()));
     
     // Create multiple screener instances
     let mut screeners = Vec::new();
     for _i in 0..3 {
         let mut dex_clients = Vec::new();
         
-        let orca_client = OrcaClient::new(rpc_client.clone(), console.clone())
+        let orca_client = OrcaClient::new(rpc_client.clone(), console.clone(), token_registry.clone())
             .expect("Failed to create Orca client");
         dex_clients.push(Arc::new(orca_client) as Arc<dyn DexClient>);
         
@@ -235,7 +236,7 @@ async fn test_memory_usage() {
     // Create large number of pools and opportunities
     let cache = PoolCache::new();
     let pools: Vec<Pool> = (0..10000)
-        .map(|i| create_test_pool(i))
+        .map(create_test_pool)
         .collect();
     
     // Cache all pools
@@ -269,6 +270,71 @@ async fn test_configuration_validation() {
     assert!(valid_config.bot.simulation_mode, "Test config should have simulation_mode enabled");
 }
 
+/// Compares per-pool reserve reads with batched `getMultipleAccounts` reads.
+/// Needs a local validator: `solana-test-validator`, or set LOCAL_VALIDATOR_URL.
+#[tokio::test(flavor = "multi_thread")]
+#[ignore]
+async fn bench_batched_reserve_refresh() {
+    let mut config = load_test_config();
+    config.rpc.solana_rpc_url = std::env::var("LOCAL_VALIDATOR_URL")
+        .unwrap_or_else(|_| "http://127.0.0.1:8899".to_string());
+    config.rpc.quicknode_rpc_url = None;
+    // Measure round trips, not the rate limiter
+    config.rpc.max_requests_per_second = 10_000;
+    config.rpc.burst_size = 10_000;
+    let rpc_client = RpcClient::new(&config).expect("Failed to create RPC client");
+
+    let addresses: Vec<Pubkey> = (0..200).map(|_| Pubkey::new_unique()).collect();
+
+    let start = std::time::Instant::now();
+    for address in &addresses {
+        rpc_client.try_get_account(address).await.expect("Single account read failed");
+    }
+    let single = start.elapsed();
+
+    let start = std::time::Instant::now();
+    for chunk in addresses.chunks(100) {
+        rpc_client.get_multiple_accounts(chunk).await.expect("Batched account read failed");
+    }
+    let batched = start.elapsed();
+
+    println!("Reserve refresh for {} pools: single {:?}, batched {:?}", addresses.len(), single, batched);
+    assert!(batched < single, "Batched refresh should beat per-pool reads");
+}
+
+/// Fetches live OpenBook V2 markets from mainnet; needs network access.
+#[tokio::test(flavor = "multi_thread")]
+#[ignore]
+async fn test_openbook_fetch_pools_mainnet() {
+    let config = load_test_config();
+    let console = Arc::new(ConsoleManager::new());
+    let rpc_client = Arc::new(RpcClient::new(&config).expect("Failed to create RPC client"));
+    let token_registry = Arc::new(TokenRegistry::new(rpc_client.clone()));
+    let client = OpenBookClient::new(rpc_client, console, token_registry)
+        .expect("Failed to create OpenBook client");
+
+    let pools = client.fetch_pools().await.expect("Failed to fetch OpenBook V2 markets");
+    assert!(!pools.is_empty(), "Expected at least one funded OpenBook V2 market");
+    assert!(pools.iter().all(|pool| pool.dex == "openbook" && pool.fee_percent >= Decimal::ZERO));
+}
+
+/// Reads live Meteora DAMM pools and their dynamic vaults from mainnet; needs network access.
+#[tokio::test(flavor = "multi_thread")]
+#[ignore]
+async fn test_meteora_damm_fetch_pools_mainnet() {
+    let config = load_test_config();
+    let console = Arc::new(ConsoleManager::new());
+    let rpc_client = Arc::new(RpcClient::new(&config).expect("Failed to create RPC client"));
+    let token_registry = Arc::new(TokenRegistry::new(rpc_client.clone()));
+    let client = MeteoraDex::new(rpc_client, console, token_registry)
+        .expect("Failed to create Meteora client");
+
+    let pools = client.fetch_damm_pools().await.expect("Failed to fetch Meteora DAMM pools");
+    assert!(!pools.is_empty(), "Expected at least one funded Meteora DAMM pool");
+    assert!(pools.iter().all(|pool| pool.reserve_a > 0 && pool.reserve_b > 0));
+    assert!(pools.iter().all(|pool| pool.fee_percent >= Decimal::ZERO && pool.fee_percent < Decimal::new(1, 1)));
+}
+
 fn load_test_config() -> Config {
     // Create a test configuration
     Config::load().unwrap_or_else(|_| {
@@ -283,6 +349,46 @@ fn load_test_config() -> Config {
                 execute_trades: false,
                 simulation_mode: true,
                 private_key: None,
+                private_keys: vec![],
+                keypair_file_path: None,
+                dex_reinit_threshold: 3,
+                direct_arb_max_slippage_percent: 2.0,
+                triangular_arb_max_slippage_percent: 1.0,
+                cross_dex_max_slippage_percent: 1.5,
+                auto_recover_partial_execution: true,
+                use_jito: false,
+                jito_tip_lamports: 10_000,
+                enable_flash_loans: false,
+                marginfi_group: "4qp6Fx6tnZkY5Wropq9wUYgtFxXKwE6viZxFHg3rdAG8".to_string(),
+                max_priority_fee_microlamports: 1_000_000,
+                priority_fee_percentile: 75,
+                cache_pool_capacity: 32,
+                cache_reserve_capacity: 10_000,
+                cache_file_path: None,
+                stable_mints: vec![
+                    "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v".to_string(),
+                    "Es9vMFrzaCERmJfrF4H2FYD4KCoNkY11McCe8BenwNYB".to_string(),
+                ],
+                stable_profit_threshold_percent: 0.02,
+                use_versioned_transactions: false,
+                address_lookup_tables: Vec::new(),
+                enable_multi_hop: false,
+                max_multi_hop: 4,
+                auto_create_token_accounts: false,
+                max_pool_age_secs: 60,
+                allow_skip_preflight: false,
+                route_cache_ttl_ms: 500,
+                max_pools_per_scan: 500,
+                max_plausible_profit_percent: 10.0,
+                min_liquidity_for_high_profit_usd: 50000.0,
+                pumpfun_max_graduation_progress: 0.9,
+                max_price_impact_bps: 300,
+                min_wallet_sol: 0.1,
+                opportunity_dedup_cooldown_secs: 30,
+                min_product_threshold: 1e12,
+                max_oracle_age_slots: 25,
+                token_whitelist: Vec::new(),
+                token_blacklist: Vec::new(),
             },
             rpc: RpcConfig {
                 solana_rpc_url: "https://api.mainnet-beta.solana.com".to_string(),
@@ -291,21 +397,31 @@ fn load_test_config() -> Config {
                 quicknode_ws_url: None,
                 max_requests_per_second: 10,
                 burst_size: 20,
+                fallback_rpc_urls: Vec::new(),
             },
             dexs: DexConfig {
                 enabled: vec!["orca".to_string(), "raydium".to_string()],
+                pumpfun_max_pools: 20,
+                pumpfun_min_market_cap: 10000.0,
+                min_required_dexes: 1,
             },
             monitoring: MonitoringConfig {
                 min_whale_transaction_sol: 10.0,
                 mempool_enabled: false,
                 whale_tracking_enabled: false,
                 whale_wallet_addresses: vec![],
+                max_replay_signatures: 100,
+                discord_webhook_url: None,
+                watched_wallets: vec![],
             },
             risk_management: RiskManagementConfig {
                 max_consecutive_losses: 3,
                 daily_loss_limit_sol: 10.0,
                 position_sizing_enabled: true,
             },
+            scoring: BotScoringConfig::default(),
+            storage_path: None,
+            api_port: 8080,
         }
     })
 }
@@ -335,6 +451,7 @@ fn create_test_pool(index: usize) -> Pool {
         liquidity_usd: Decimal::from_f64_retain(10000.0 + (index as f64 * 100.0)).unwrap(),
         fee_percent: Decimal::from_f64_retain(0.003).unwrap(),
         last_updated: chrono::Utc::now(),
+        metadata: None,
     }
 }
 